@@ -40,6 +40,11 @@ pub mod paths {
     pub fn get_kernel_work_dir() -> PathBuf {
         get_config_dir()
     }
+
+    /// 获取多版本内核存放目录（每个版本一个子目录）
+    pub fn get_kernel_store_dir() -> PathBuf {
+        get_config_dir().join("kernels")
+    }
 }
 
 /// 配置常量
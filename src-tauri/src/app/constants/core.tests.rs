@@ -23,3 +23,11 @@ fn get_kernel_path_should_use_platform_specific_file_name() {
 fn get_kernel_work_dir_should_match_config_dir() {
     assert_eq!(paths::get_kernel_work_dir(), paths::get_config_dir());
 }
+
+#[test]
+fn get_kernel_store_dir_should_live_under_config_dir() {
+    let store_dir = paths::get_kernel_store_dir();
+
+    assert!(store_dir.ends_with("kernels"));
+    assert_eq!(store_dir.parent(), Some(paths::get_config_dir().as_path()));
+}
@@ -16,6 +16,9 @@ pub mod registry {
 
     /// 代理例外地址键名
     pub const PROXY_OVERRIDE: &str = "ProxyOverride";
+
+    /// 自动配置脚本（PAC）地址键名
+    pub const AUTO_CONFIG_URL: &str = "AutoConfigURL";
 }
 
 /// 数据库默认配置
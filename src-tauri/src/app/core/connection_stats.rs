@@ -0,0 +1,347 @@
+//! 按进程 / 域名 / 出站链聚合的连接统计
+//!
+//! `/connections` 每帧都携带全部活动连接，直接转发给前端开销较大。
+//! 这里在后端聚合 `connection_tracker` 算出的逐连接字节差值，前端只需要订阅
+//! `connection-stats` 事件（或调用 `get_connection_stats`）拿到排行后的汇总结果。
+
+use crate::app::core::connection_tracker::ConnectionDelta;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+use tokio::task::JoinHandle;
+
+/// 周期事件名
+pub const CONNECTION_STATS_EVENT: &str = "connection-stats";
+const EMIT_INTERVAL_SECS: u64 = 2;
+const EVENT_TOP_LIMIT: usize = 20;
+/// 每个维度最多保留的条目数，超出后淘汰已无活动连接且流量最少的条目
+const MAX_ENTRIES_PER_DIMENSION: usize = 500;
+/// 单个条目最多记录的命中规则数
+const MAX_RULES_PER_ENTRY: usize = 8;
+const UNKNOWN_PROCESS: &str = "unknown";
+
+lazy_static::lazy_static! {
+    static ref CONNECTION_STATS: Mutex<ConnectionStatsAggregator> =
+        Mutex::new(ConnectionStatsAggregator::default());
+}
+
+/// 单个进程/域名/出站链的汇总
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ConnectionStatEntry {
+    /// 聚合键：进程完整路径 / 域名 / 出站链
+    pub key: String,
+    /// 展示名称（进程取文件名，其余与 key 相同）
+    pub label: String,
+    /// 本次会话累计上传字节
+    pub upload: u64,
+    /// 本次会话累计下载字节
+    pub download: u64,
+    /// 最近一帧的上传速率（字节/秒）
+    pub upload_speed: u64,
+    /// 最近一帧的下载速率（字节/秒）
+    pub download_speed: u64,
+    /// 当前活动连接数
+    pub active_connections: u32,
+    /// 本次会话出现过的连接总数
+    pub total_connections: u32,
+    /// 命中的规则（去重）
+    pub rules: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ConnectionStatsSnapshot {
+    pub timestamp_ms: u64,
+    pub active_connections: u32,
+    pub by_process: Vec<ConnectionStatEntry>,
+    pub by_domain: Vec<ConnectionStatEntry>,
+    pub by_chain: Vec<ConnectionStatEntry>,
+}
+
+#[derive(Debug, Default)]
+struct EntryState {
+    label: String,
+    upload: u64,
+    download: u64,
+    frame_upload: u64,
+    frame_download: u64,
+    active_connections: u32,
+    total_connections: u32,
+    rules: BTreeSet<String>,
+}
+
+impl EntryState {
+    fn to_entry(&self, key: &str, elapsed_secs: f64) -> ConnectionStatEntry {
+        let per_second = |bytes: u64| {
+            if elapsed_secs > 0.0 {
+                (bytes as f64 / elapsed_secs) as u64
+            } else {
+                0
+            }
+        };
+        ConnectionStatEntry {
+            key: key.to_string(),
+            label: self.label.clone(),
+            upload: self.upload,
+            download: self.download,
+            upload_speed: per_second(self.frame_upload),
+            download_speed: per_second(self.frame_download),
+            active_connections: self.active_connections,
+            total_connections: self.total_connections,
+            rules: self.rules.iter().cloned().collect(),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Dimension {
+    entries: HashMap<String, EntryState>,
+}
+
+impl Dimension {
+    fn begin_frame(&mut self) {
+        for state in self.entries.values_mut() {
+            state.frame_upload = 0;
+            state.frame_download = 0;
+            state.active_connections = 0;
+        }
+    }
+
+    fn record(&mut self, key: &str, label: &str, conn: &ConnectionSample, is_new: bool) {
+        let state = self.entries.entry(key.to_string()).or_default();
+        if state.label.is_empty() {
+            state.label = label.to_string();
+        }
+        state.upload = state.upload.saturating_add(conn.delta_upload);
+        state.download = state.download.saturating_add(conn.delta_download);
+        state.frame_upload = state.frame_upload.saturating_add(conn.delta_upload);
+        state.frame_download = state.frame_download.saturating_add(conn.delta_download);
+        state.active_connections += 1;
+        if is_new {
+            state.total_connections += 1;
+        }
+        if let Some(rule) = conn.rule.as_ref() {
+            if state.rules.len() < MAX_RULES_PER_ENTRY {
+                state.rules.insert(rule.clone());
+            }
+        }
+    }
+
+    fn trim(&mut self) {
+        if self.entries.len() <= MAX_ENTRIES_PER_DIMENSION {
+            return;
+        }
+        let mut idle: Vec<(String, u64)> = self
+            .entries
+            .iter()
+            .filter(|(_, s)| s.active_connections == 0)
+            .map(|(k, s)| (k.clone(), s.upload.saturating_add(s.download)))
+            .collect();
+        idle.sort_by_key(|(_, bytes)| *bytes);
+        let overflow = self.entries.len() - MAX_ENTRIES_PER_DIMENSION;
+        for (key, _) in idle.into_iter().take(overflow) {
+            self.entries.remove(&key);
+        }
+    }
+
+    fn top(&self, limit: usize, elapsed_secs: f64) -> Vec<ConnectionStatEntry> {
+        let mut entries: Vec<ConnectionStatEntry> = self
+            .entries
+            .iter()
+            .map(|(key, state)| state.to_entry(key, elapsed_secs))
+            .collect();
+        entries.sort_by(|a, b| {
+            let speed = |e: &ConnectionStatEntry| e.upload_speed + e.download_speed;
+            let total = |e: &ConnectionStatEntry| e.upload + e.download;
+            speed(b)
+                .cmp(&speed(a))
+                .then_with(|| total(b).cmp(&total(a)))
+                .then_with(|| a.key.cmp(&b.key))
+        });
+        entries.truncate(limit);
+        entries
+    }
+}
+
+struct ConnectionSample {
+    delta_upload: u64,
+    delta_download: u64,
+    rule: Option<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct ConnectionStatsAggregator {
+    by_process: Dimension,
+    by_domain: Dimension,
+    by_chain: Dimension,
+    active_connections: u32,
+    last_frame_at: Option<Instant>,
+    frame_elapsed_secs: f64,
+}
+
+impl ConnectionStatsAggregator {
+    /// 处理一帧 `/connections` 的逐连接字节差值
+    pub fn observe(&mut self, deltas: &[ConnectionDelta]) {
+        let now = Instant::now();
+        self.frame_elapsed_secs = self
+            .last_frame_at
+            .map(|at| now.duration_since(at).as_secs_f64())
+            .unwrap_or(0.0);
+        self.last_frame_at = Some(now);
+
+        self.by_process.begin_frame();
+        self.by_domain.begin_frame();
+        self.by_chain.begin_frame();
+
+        for delta in deltas {
+            let conn = delta.conn;
+            let sample = ConnectionSample {
+                delta_upload: delta.delta_upload,
+                delta_download: delta.delta_download,
+                rule: connection_rule(conn),
+            };
+
+            let (process_key, process_label) = connection_process(conn);
+            self.by_process
+                .record(&process_key, &process_label, &sample, delta.is_new);
+
+            let domain = connection_domain(conn);
+            self.by_domain
+                .record(&domain, &domain, &sample, delta.is_new);
+
+            let chain = connection_chain(conn);
+            self.by_chain.record(&chain, &chain, &sample, delta.is_new);
+        }
+
+        self.active_connections = deltas.len() as u32;
+        self.by_process.trim();
+        self.by_domain.trim();
+        self.by_chain.trim();
+    }
+
+    pub fn snapshot(&self, limit: usize) -> ConnectionStatsSnapshot {
+        ConnectionStatsSnapshot {
+            timestamp_ms: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or(0),
+            active_connections: self.active_connections,
+            by_process: self.by_process.top(limit, self.frame_elapsed_secs),
+            by_domain: self.by_domain.top(limit, self.frame_elapsed_secs),
+            by_chain: self.by_chain.top(limit, self.frame_elapsed_secs),
+        }
+    }
+}
+
+/// 进程聚合键：`metadata.processPath`，未开启进程识别时归入 `unknown`
+fn connection_process(conn: &Value) -> (String, String) {
+    let path = conn
+        .pointer("/metadata/processPath")
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|s| !s.is_empty());
+
+    match path {
+        Some(path) => {
+            // processPath 可能来自 Windows，按两种分隔符取文件名
+            let label = path.rsplit(['/', '\\']).next().unwrap_or(path).to_string();
+            (path.to_string(), label)
+        }
+        None => (UNKNOWN_PROCESS.to_string(), UNKNOWN_PROCESS.to_string()),
+    }
+}
+
+/// 域名聚合键：优先 `metadata.host`，否则使用目标 IP
+fn connection_domain(conn: &Value) -> String {
+    ["/metadata/host", "/metadata/destinationIP"]
+        .iter()
+        .filter_map(|p| conn.pointer(p).and_then(Value::as_str))
+        .map(str::trim)
+        .find(|s| !s.is_empty())
+        .unwrap_or(UNKNOWN_PROCESS)
+        .to_string()
+}
+
+/// 出站链聚合键：由外到内展示，如 `手动切换 -> 香港 01`
+fn connection_chain(conn: &Value) -> String {
+    let chains: Vec<&str> = conn
+        .get("chains")
+        .and_then(Value::as_array)
+        .map(|items| items.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+    if chains.is_empty() {
+        return UNKNOWN_PROCESS.to_string();
+    }
+    chains.into_iter().rev().collect::<Vec<_>>().join(" -> ")
+}
+
+fn connection_rule(conn: &Value) -> Option<String> {
+    let rule = conn.get("rule").and_then(Value::as_str)?.trim();
+    if rule.is_empty() {
+        return None;
+    }
+    let payload = conn
+        .get("rulePayload")
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .unwrap_or("");
+    if payload.is_empty() {
+        Some(rule.to_string())
+    } else {
+        Some(format!("{}({})", rule, payload))
+    }
+}
+
+/// `connection_tracker` 算出一帧 `/connections` 的差值后调用
+pub fn record_connection_deltas(deltas: &[ConnectionDelta]) {
+    if let Ok(mut stats) = CONNECTION_STATS.lock() {
+        stats.observe(deltas);
+    }
+}
+
+/// 当前活跃连接数（用于判断是否空闲）
+pub fn active_connection_count() -> u32 {
+    CONNECTION_STATS
+        .lock()
+        .map(|stats| stats.active_connections)
+        .unwrap_or(0)
+}
+
+/// 内核（事件中继）重启时清空会话统计
+pub fn reset_connection_stats() {
+    if let Ok(mut stats) = CONNECTION_STATS.lock() {
+        *stats = ConnectionStatsAggregator::default();
+    }
+}
+
+/// 周期性推送汇总结果，生命周期与事件中继一致
+pub fn spawn_connection_stats_task(app_handle: AppHandle) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(Duration::from_secs(EMIT_INTERVAL_SECS)).await;
+            let snapshot = match CONNECTION_STATS.lock() {
+                Ok(stats) => stats.snapshot(EVENT_TOP_LIMIT),
+                Err(_) => continue,
+            };
+            let _ = app_handle.emit(CONNECTION_STATS_EVENT, snapshot);
+        }
+    })
+}
+
+#[tauri::command]
+pub async fn get_connection_stats(limit: Option<usize>) -> Result<ConnectionStatsSnapshot, String> {
+    let stats = CONNECTION_STATS
+        .lock()
+        .map_err(|_| "连接统计不可用".to_string())?;
+    Ok(stats.snapshot(
+        limit
+            .unwrap_or(EVENT_TOP_LIMIT)
+            .clamp(1, MAX_ENTRIES_PER_DIMENSION),
+    ))
+}
+
+#[cfg(test)]
+#[path = "connection_stats.tests.rs"]
+mod tests;
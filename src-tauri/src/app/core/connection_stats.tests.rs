@@ -0,0 +1,102 @@
+use super::*;
+use crate::app::core::connection_tracker::ConnectionDeltaTracker;
+use serde_json::json;
+
+/// 与事件中继一致：先由共享的差值跟踪器计算，再交给聚合器
+fn observe_frame(
+    tracker: &mut ConnectionDeltaTracker,
+    stats: &mut ConnectionStatsAggregator,
+    frame: Value,
+) {
+    stats.observe(&tracker.observe(&frame).unwrap());
+}
+
+fn conn(id: &str, process: &str, host: &str, upload: u64, download: u64) -> Value {
+    json!({
+        "id": id,
+        "upload": upload,
+        "download": download,
+        "chains": ["香港 01", "手动切换"],
+        "rule": "final",
+        "rulePayload": "",
+        "metadata": {
+            "processPath": process,
+            "host": host,
+            "destinationIP": "1.2.3.4"
+        }
+    })
+}
+
+#[test]
+fn observe_should_group_connections_by_process_domain_and_chain() {
+    let mut tracker = ConnectionDeltaTracker::default();
+    let mut stats = ConnectionStatsAggregator::default();
+    observe_frame(
+        &mut tracker,
+        &mut stats,
+        json!({
+            "connections": [
+                conn("a", "C:\\Program Files\\Google\\chrome.exe", "www.youtube.com", 10, 100),
+                conn("b", "C:\\Program Files\\Google\\chrome.exe", "rr1.googlevideo.com", 20, 900),
+                conn("c", "/usr/bin/curl", "example.com", 5, 5),
+            ]
+        }),
+    );
+
+    let snapshot = stats.snapshot(10);
+    assert_eq!(snapshot.active_connections, 3);
+
+    let chrome = snapshot
+        .by_process
+        .iter()
+        .find(|e| e.label == "chrome.exe")
+        .unwrap();
+    assert_eq!((chrome.upload, chrome.download), (30, 1000));
+    assert_eq!(chrome.active_connections, 2);
+    assert_eq!(chrome.rules, vec!["final".to_string()]);
+
+    assert_eq!(snapshot.by_domain.len(), 3);
+    assert_eq!(snapshot.by_chain.len(), 1);
+    assert_eq!(snapshot.by_chain[0].key, "手动切换 -> 香港 01");
+}
+
+#[test]
+fn observe_should_keep_session_totals_after_connections_close() {
+    let mut tracker = ConnectionDeltaTracker::default();
+    let mut stats = ConnectionStatsAggregator::default();
+    let curl = |up, down| conn("a", "/usr/bin/curl", "example.com", up, down);
+    observe_frame(
+        &mut tracker,
+        &mut stats,
+        json!({ "connections": [curl(10, 10)] }),
+    );
+    observe_frame(
+        &mut tracker,
+        &mut stats,
+        json!({ "connections": [curl(15, 30)] }),
+    );
+    observe_frame(&mut tracker, &mut stats, json!({ "connections": [] }));
+
+    let snapshot = stats.snapshot(10);
+    let curl = &snapshot.by_process[0];
+    assert_eq!((curl.upload, curl.download), (15, 30));
+    assert_eq!(curl.active_connections, 0);
+    assert_eq!(curl.total_connections, 1);
+}
+
+#[test]
+fn connection_process_should_fall_back_to_unknown() {
+    let value = json!({ "metadata": { "processPath": "" } });
+    assert_eq!(
+        connection_process(&value),
+        (UNKNOWN_PROCESS.to_string(), UNKNOWN_PROCESS.to_string())
+    );
+    let value = json!({ "metadata": { "processPath": "/usr/lib/firefox/firefox" } });
+    assert_eq!(connection_process(&value).1, "firefox");
+}
+
+#[test]
+fn connection_domain_should_prefer_host_over_ip() {
+    let value = json!({ "metadata": { "host": "", "destinationIP": "8.8.8.8" } });
+    assert_eq!(connection_domain(&value), "8.8.8.8");
+}
@@ -0,0 +1,89 @@
+//! `/connections` 帧的逐连接字节差值
+//!
+//! 每帧都携带各连接的累计字节数，流量记账与连接统计需要的都是与上一帧的差值。
+//! 这里只维护一份“连接 id -> 上一帧累计值”，每帧计算一次差值后分发给两者。
+
+use crate::app::core::{connection_stats, traffic_accounting};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+
+lazy_static::lazy_static! {
+    static ref CONNECTION_TRACKER: Mutex<ConnectionDeltaTracker> =
+        Mutex::new(ConnectionDeltaTracker::default());
+}
+
+/// 单条连接在本帧的字节增量
+#[derive(Debug)]
+pub struct ConnectionDelta<'a> {
+    /// 原始连接对象（chains、metadata 等）
+    pub conn: &'a Value,
+    pub delta_upload: u64,
+    pub delta_download: u64,
+    /// 上一帧中不存在该连接
+    pub is_new: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct ConnectionDeltaTracker {
+    /// 连接 id -> 上一帧的 (upload, download) 累计值
+    last_seen: HashMap<String, (u64, u64)>,
+}
+
+impl ConnectionDeltaTracker {
+    /// 计算一帧中每条连接的字节差值，并忘记已关闭的连接。
+    ///
+    /// 帧中没有 `connections` 数组时返回 `None`。
+    pub fn observe<'a>(&mut self, data: &'a Value) -> Option<Vec<ConnectionDelta<'a>>> {
+        let connections = data.get("connections").and_then(Value::as_array)?;
+
+        let mut deltas = Vec::with_capacity(connections.len());
+        let mut alive = HashSet::with_capacity(connections.len());
+        for conn in connections {
+            let Some(id) = conn.get("id").and_then(Value::as_str) else {
+                continue;
+            };
+            let upload = conn.get("upload").and_then(Value::as_u64).unwrap_or(0);
+            let download = conn.get("download").and_then(Value::as_u64).unwrap_or(0);
+
+            let previous = self.last_seen.insert(id.to_string(), (upload, download));
+            let (prev_up, prev_down) = previous.unwrap_or((0, 0));
+            // 计数回退（连接 id 复用等异常）时按新连接处理
+            deltas.push(ConnectionDelta {
+                conn,
+                delta_upload: upload.checked_sub(prev_up).unwrap_or(upload),
+                delta_download: download.checked_sub(prev_down).unwrap_or(download),
+                is_new: previous.is_none(),
+            });
+            alive.insert(id);
+        }
+
+        self.last_seen.retain(|id, _| alive.contains(id.as_str()));
+        Some(deltas)
+    }
+}
+
+/// 事件中继收到 `/connections` 帧时调用：计算一次差值，交给流量记账与连接统计
+pub fn record_connections_frame(data: &Value) {
+    let Ok(mut tracker) = CONNECTION_TRACKER.lock() else {
+        return;
+    };
+    let Some(deltas) = tracker.observe(data) else {
+        return;
+    };
+    drop(tracker);
+
+    traffic_accounting::record_connection_deltas(&deltas);
+    connection_stats::record_connection_deltas(&deltas);
+}
+
+/// 内核（事件中继）重启时清空，新内核的连接 id 与旧内核无关
+pub fn reset_connection_tracker() {
+    if let Ok(mut tracker) = CONNECTION_TRACKER.lock() {
+        *tracker = ConnectionDeltaTracker::default();
+    }
+}
+
+#[cfg(test)]
+#[path = "connection_tracker.tests.rs"]
+mod tests;
@@ -0,0 +1,70 @@
+use super::*;
+use serde_json::json;
+
+fn frame(connections: &[(&str, u64, u64)]) -> Value {
+    let connections: Vec<Value> = connections
+        .iter()
+        .map(|(id, up, down)| json!({ "id": id, "upload": up, "download": down }))
+        .collect();
+    json!({ "connections": connections })
+}
+
+fn summarize(deltas: &[ConnectionDelta]) -> Vec<(String, u64, u64, bool)> {
+    deltas
+        .iter()
+        .map(|delta| {
+            (
+                delta.conn["id"].as_str().unwrap().to_string(),
+                delta.delta_upload,
+                delta.delta_download,
+                delta.is_new,
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn observe_should_report_byte_differences_per_connection() {
+    let mut tracker = ConnectionDeltaTracker::default();
+    let first = frame(&[("a", 10, 20)]);
+    assert_eq!(
+        summarize(&tracker.observe(&first).unwrap()),
+        vec![("a".to_string(), 10, 20, true)]
+    );
+
+    let second = frame(&[("a", 15, 50), ("b", 1, 2)]);
+    assert_eq!(
+        summarize(&tracker.observe(&second).unwrap()),
+        vec![
+            ("a".to_string(), 5, 30, false),
+            ("b".to_string(), 1, 2, true)
+        ]
+    );
+}
+
+#[test]
+fn observe_should_treat_counter_reset_and_reused_id_as_new_bytes() {
+    let mut tracker = ConnectionDeltaTracker::default();
+    tracker.observe(&frame(&[("a", 100, 100)]));
+
+    // 计数回退：按完整字节计入
+    let reset = frame(&[("a", 40, 60)]);
+    assert_eq!(
+        summarize(&tracker.observe(&reset).unwrap()),
+        vec![("a".to_string(), 40, 60, false)]
+    );
+
+    // 连接关闭后同一 id 再次出现视为新连接
+    tracker.observe(&frame(&[]));
+    let reused = frame(&[("a", 5, 7)]);
+    assert_eq!(
+        summarize(&tracker.observe(&reused).unwrap()),
+        vec![("a".to_string(), 5, 7, true)]
+    );
+}
+
+#[test]
+fn observe_should_ignore_frames_without_connections() {
+    let mut tracker = ConnectionDeltaTracker::default();
+    assert!(tracker.observe(&json!({ "downloadTotal": 1 })).is_none());
+}
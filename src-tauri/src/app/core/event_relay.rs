@@ -1,3 +1,5 @@
+use crate::app::core::connection_tracker::record_connections_frame;
+use crate::app::core::traffic_accounting::record_traffic_frame;
 use futures::StreamExt;
 use serde::Serialize;
 use serde_json::Value;
@@ -111,7 +113,10 @@ pub fn create_traffic_event_relay(
         app_handle,
         "/traffic",
         "traffic-data",
-        |data| data,
+        |data| {
+            record_traffic_frame(&data);
+            data
+        },
         api_port,
         token,
     )
@@ -159,7 +164,10 @@ pub fn create_connection_event_relay(
         app_handle,
         "/connections",
         "connections-data",
-        |data| data,
+        |data| {
+            record_connections_frame(&data);
+            data
+        },
         api_port,
         token,
     )
@@ -0,0 +1,371 @@
+//! 额外入站（mixed-in / tun-in 之外）
+//!
+//! 部分客户端只认 SOCKS 或 HTTP，路由器式部署需要 redirect / tproxy 透明代理，
+//! 局域网手机需要带认证的 shadowsocks / VMess 入站把桌面端当作网关。
+//! 这些入站统一以 [`EXTRA_INBOUND_TAG_PREFIX`] 开头的 tag 写入配置，
+//! 同步时先按前缀整体移除再重建，避免删除条目后在原始订阅配置中残留。
+//! 密码与 UUID 在数据库中加密保存，读取时解密。
+
+use crate::app::storage::secret_cipher::{
+    decrypt_secret_in, encrypt_secret_in, SEALED_SECRET_PREFIX,
+};
+use base64::engine::general_purpose::STANDARD as BASE64_ENGINE;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::net::IpAddr;
+use std::path::Path;
+use tracing::warn;
+use ts_rs::TS;
+
+/// 本程序生成的额外入站 tag 前缀
+pub const EXTRA_INBOUND_TAG_PREFIX: &str = "extra-";
+/// 未设置认证的 socks / http 入站使用的监听地址
+const LOOPBACK_LISTEN: &str = "127.0.0.1";
+const SECRET_PURPOSE: &str = "extra-inbound";
+
+const SHADOWSOCKS_METHODS: &[&str] = &[
+    "2022-blake3-aes-128-gcm",
+    "2022-blake3-aes-256-gcm",
+    "2022-blake3-chacha20-poly1305",
+    "aes-128-gcm",
+    "aes-192-gcm",
+    "aes-256-gcm",
+    "chacha20-ietf-poly1305",
+    "xchacha20-ietf-poly1305",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "../src/types/generated/ExtraInboundKind.ts")]
+pub enum ExtraInboundKind {
+    Socks,
+    Http,
+    /// 仅 Linux：iptables REDIRECT 透明代理（TCP）
+    Redirect,
+    /// 仅 Linux：TPROXY 透明代理（TCP + UDP）
+    Tproxy,
+    Shadowsocks,
+    Vmess,
+}
+
+impl ExtraInboundKind {
+    pub fn singbox_type(&self) -> &'static str {
+        match self {
+            Self::Socks => "socks",
+            Self::Http => "http",
+            Self::Redirect => "redirect",
+            Self::Tproxy => "tproxy",
+            Self::Shadowsocks => "shadowsocks",
+            Self::Vmess => "vmess",
+        }
+    }
+
+    /// 透明代理入站依赖 Linux netfilter
+    pub fn is_supported(&self) -> bool {
+        match self {
+            Self::Redirect | Self::Tproxy => cfg!(target_os = "linux"),
+            _ => true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../src/types/generated/ExtraInbound.ts")]
+pub struct ExtraInbound {
+    pub kind: ExtraInboundKind,
+    /// 留空时按类型与端口生成；保存时统一补上 `extra-` 前缀
+    #[serde(default)]
+    pub tag: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// 监听地址；留空时跟随“允许局域网访问”开关，未设置认证的 socks / http 只监听本机
+    #[serde(default)]
+    pub listen: Option<String>,
+    pub listen_port: u16,
+    /// socks / http 认证用户名（与 password 同时设置才生效）
+    #[serde(default)]
+    pub username: Option<String>,
+    /// socks / http 认证密码，或 shadowsocks 密码
+    #[serde(default)]
+    pub password: Option<String>,
+    /// shadowsocks 加密方式
+    #[serde(default)]
+    pub method: Option<String>,
+    /// VMess 用户 UUID
+    #[serde(default)]
+    pub uuid: Option<String>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl ExtraInbound {
+    /// 生成 sing-box inbound JSON，`default_listen` 为跟随 LAN 开关得到的监听地址
+    pub fn to_json(&self, default_listen: &str) -> Value {
+        let default_listen = if self.requires_auth_for_lan() && !self.has_auth() {
+            LOOPBACK_LISTEN
+        } else {
+            default_listen
+        };
+        let mut inbound = json!({
+            "type": self.kind.singbox_type(),
+            "tag": self.tag,
+            "listen": self.listen.as_deref().unwrap_or(default_listen),
+            "listen_port": self.listen_port,
+        });
+        match self.kind {
+            ExtraInboundKind::Socks | ExtraInboundKind::Http => {
+                if let (Some(username), Some(password)) = (&self.username, &self.password) {
+                    inbound["users"] = json!([{ "username": username, "password": password }]);
+                }
+            }
+            ExtraInboundKind::Shadowsocks => {
+                inbound["method"] = json!(self.method);
+                inbound["password"] = json!(self.password);
+            }
+            ExtraInboundKind::Vmess => {
+                inbound["users"] = json!([{ "name": self.tag, "uuid": self.uuid, "alterId": 0 }]);
+            }
+            ExtraInboundKind::Redirect | ExtraInboundKind::Tproxy => {}
+        }
+        inbound
+    }
+
+    /// socks / http 的认证是可选的，对局域网开放时必须设置
+    fn requires_auth_for_lan(&self) -> bool {
+        matches!(self.kind, ExtraInboundKind::Socks | ExtraInboundKind::Http)
+    }
+
+    fn has_auth(&self) -> bool {
+        self.username.is_some() && self.password.is_some()
+    }
+
+    fn secrets_mut(&mut self) -> [&mut Option<String>; 2] {
+        [&mut self.password, &mut self.uuid]
+    }
+}
+
+pub fn is_extra_inbound_tag(tag: &str) -> bool {
+    tag.starts_with(EXTRA_INBOUND_TAG_PREFIX)
+}
+
+/// 当前平台可用且已启用的额外入站
+pub fn build_extra_inbounds(inbounds: &[ExtraInbound], default_listen: &str) -> Vec<Value> {
+    inbounds
+        .iter()
+        .filter(|inbound| inbound.enabled && inbound.kind.is_supported())
+        .map(|inbound| inbound.to_json(default_listen))
+        .collect()
+}
+
+/// 用 `extra` 替换 `inbounds` 中上次生成的额外入站
+pub fn sync_extra_inbounds(inbounds: &mut Vec<Value>, extra: Vec<Value>) {
+    inbounds.retain(|inbound| {
+        !inbound
+            .get("tag")
+            .and_then(|v| v.as_str())
+            .is_some_and(is_extra_inbound_tag)
+    });
+    inbounds.extend(extra);
+}
+
+/// 保存前校验并规范化额外入站；`reserved_ports` 为代理端口与 API 端口
+pub fn normalize_extra_inbounds(
+    inbounds: Vec<ExtraInbound>,
+    reserved_ports: &[u16],
+) -> Result<Vec<ExtraInbound>, String> {
+    let mut tags = HashSet::new();
+    let mut ports = HashSet::new();
+    let mut normalized = Vec::with_capacity(inbounds.len());
+
+    for mut inbound in inbounds {
+        if inbound.listen_port == 0 {
+            return Err("额外入站端口不能为 0".to_string());
+        }
+        let tag = inbound.tag.trim();
+        let tag = tag.strip_prefix(EXTRA_INBOUND_TAG_PREFIX).unwrap_or(tag);
+        inbound.tag = if tag.is_empty() {
+            format!(
+                "{}{}-{}",
+                EXTRA_INBOUND_TAG_PREFIX,
+                inbound.kind.singbox_type(),
+                inbound.listen_port
+            )
+        } else {
+            format!("{}{}", EXTRA_INBOUND_TAG_PREFIX, tag)
+        };
+        if !tags.insert(inbound.tag.clone()) {
+            return Err(format!("额外入站 tag 重复: {}", inbound.tag));
+        }
+
+        inbound.listen = normalize_optional(inbound.listen);
+        if let Some(listen) = inbound.listen.as_deref() {
+            listen
+                .parse::<IpAddr>()
+                .map_err(|_| format!("额外入站 {} 的监听地址无效: {}", inbound.tag, listen))?;
+        }
+        inbound.username = normalize_optional(inbound.username);
+        // 密码原样保存，首尾空白也是密码的一部分
+        inbound.password = inbound
+            .password
+            .filter(|password| !password.trim().is_empty());
+        inbound.method = normalize_optional(inbound.method);
+        inbound.uuid = normalize_optional(inbound.uuid);
+
+        if !inbound.enabled {
+            normalized.push(inbound);
+            continue;
+        }
+        if reserved_ports.contains(&inbound.listen_port) || !ports.insert(inbound.listen_port) {
+            return Err(format!(
+                "额外入站 {} 的端口 {} 已被占用",
+                inbound.tag, inbound.listen_port
+            ));
+        }
+        validate_kind_options(&inbound)?;
+        normalized.push(inbound);
+    }
+
+    Ok(normalized)
+}
+
+fn validate_kind_options(inbound: &ExtraInbound) -> Result<(), String> {
+    match inbound.kind {
+        ExtraInboundKind::Redirect | ExtraInboundKind::Tproxy if !inbound.kind.is_supported() => {
+            Err(format!("{} 入站仅支持 Linux", inbound.kind.singbox_type()))
+        }
+        ExtraInboundKind::Socks | ExtraInboundKind::Http
+            if inbound.username.is_some() != inbound.password.is_some() =>
+        {
+            Err(format!(
+                "额外入站 {} 的用户名和密码需要同时设置",
+                inbound.tag
+            ))
+        }
+        ExtraInboundKind::Socks | ExtraInboundKind::Http
+            if !inbound.has_auth()
+                && inbound
+                    .listen
+                    .as_deref()
+                    .and_then(|listen| listen.parse::<IpAddr>().ok())
+                    .is_some_and(|ip| !ip.is_loopback()) =>
+        {
+            Err(format!(
+                "额外入站 {} 监听非本机地址时必须设置用户名和密码",
+                inbound.tag
+            ))
+        }
+        ExtraInboundKind::Shadowsocks => {
+            let method = inbound.method.as_deref().unwrap_or_default();
+            if !SHADOWSOCKS_METHODS.contains(&method) {
+                return Err(format!("不支持的 shadowsocks 加密方式: {}", method));
+            }
+            let Some(password) = inbound.password.as_deref() else {
+                return Err(format!("shadowsocks 入站 {} 缺少密码", inbound.tag));
+            };
+            if let Some(key_len) = shadowsocks_2022_key_len(method) {
+                let valid = BASE64_ENGINE
+                    .decode(password)
+                    .is_ok_and(|key| key.len() == key_len);
+                if !valid {
+                    return Err(format!(
+                        "shadowsocks 入站 {} 的 {} 密码必须是 {} 字节密钥的 base64 编码",
+                        inbound.tag, method, key_len
+                    ));
+                }
+            }
+            Ok(())
+        }
+        ExtraInboundKind::Vmess => {
+            let uuid = inbound.uuid.as_deref().unwrap_or_default();
+            if !is_valid_uuid(uuid) {
+                return Err(format!("VMess 入站 {} 的 UUID 无效: {}", inbound.tag, uuid));
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// 2022 系列加密方式要求密码为固定长度密钥的 base64
+fn shadowsocks_2022_key_len(method: &str) -> Option<usize> {
+    match method {
+        "2022-blake3-aes-128-gcm" => Some(16),
+        "2022-blake3-aes-256-gcm" | "2022-blake3-chacha20-poly1305" => Some(32),
+        _ => None,
+    }
+}
+
+/// 写入数据库前加密密码与 UUID
+pub fn seal_extra_inbound_secrets(
+    inbounds: &mut [ExtraInbound],
+    data_dir: &Path,
+) -> Result<(), String> {
+    for secret in inbounds
+        .iter_mut()
+        .flat_map(|inbound| inbound.secrets_mut())
+        .flatten()
+    {
+        *secret = format!(
+            "{}{}",
+            SEALED_SECRET_PREFIX,
+            encrypt_secret_in(data_dir, SECRET_PURPOSE, secret)?
+        );
+    }
+    Ok(())
+}
+
+/// 从数据库读取后解密；旧版本保存的明文原样保留，下次保存时加密。
+///
+/// 解密失败（例如数据目录变化）时清空该字段并停用入站，避免把密文当作密码写入配置。
+/// 返回解密失败的字段数。
+pub fn open_extra_inbound_secrets(inbounds: &mut [ExtraInbound], data_dir: &Path) -> usize {
+    let mut failures = 0;
+    for inbound in inbounds.iter_mut() {
+        let tag = inbound.tag.clone();
+        let mut failed = false;
+        for secret in inbound.secrets_mut() {
+            let Some(sealed) = secret
+                .as_deref()
+                .and_then(|value| value.strip_prefix(SEALED_SECRET_PREFIX))
+            else {
+                continue;
+            };
+            match decrypt_secret_in(data_dir, SECRET_PURPOSE, sealed) {
+                Ok(plain) => *secret = Some(plain),
+                Err(err) => {
+                    warn!("额外入站 {} 的密钥解密失败，已停用该入站: {}", tag, err);
+                    *secret = None;
+                    failed = true;
+                    failures += 1;
+                }
+            }
+        }
+        if failed {
+            inbound.enabled = false;
+        }
+    }
+    failures
+}
+
+fn normalize_optional(value: Option<String>) -> Option<String> {
+    value
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+fn is_valid_uuid(value: &str) -> bool {
+    let groups: Vec<&str> = value.split('-').collect();
+    groups.len() == 5
+        && groups
+            .iter()
+            .zip([8, 4, 4, 4, 12])
+            .all(|(group, len)| group.len() == len && group.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+#[cfg(test)]
+#[path = "extra_inbounds.tests.rs"]
+mod tests;
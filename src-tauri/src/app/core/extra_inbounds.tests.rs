@@ -0,0 +1,256 @@
+use super::*;
+
+fn inbound(kind: ExtraInboundKind, port: u16) -> ExtraInbound {
+    ExtraInbound {
+        kind,
+        tag: String::new(),
+        enabled: true,
+        listen: None,
+        listen_port: port,
+        username: None,
+        password: None,
+        method: None,
+        uuid: None,
+    }
+}
+
+#[test]
+fn test_normalize_generates_prefixed_tags() {
+    let mut named = inbound(ExtraInboundKind::Http, 8081);
+    named.tag = " phone ".to_string();
+    let mut prefixed = inbound(ExtraInboundKind::Socks, 1081);
+    prefixed.tag = "extra-socks5".to_string();
+
+    let normalized = normalize_extra_inbounds(
+        vec![inbound(ExtraInboundKind::Socks, 1080), named, prefixed],
+        &[12080, 12081],
+    )
+    .expect("valid inbounds");
+
+    let tags: Vec<&str> = normalized.iter().map(|i| i.tag.as_str()).collect();
+    assert_eq!(
+        tags,
+        vec!["extra-socks-1080", "extra-phone", "extra-socks5"]
+    );
+}
+
+#[test]
+fn test_normalize_rejects_port_and_tag_conflicts() {
+    let error = normalize_extra_inbounds(vec![inbound(ExtraInboundKind::Socks, 12080)], &[12080])
+        .expect_err("proxy port reserved");
+    assert!(error.contains("12080"));
+
+    let error = normalize_extra_inbounds(
+        vec![
+            inbound(ExtraInboundKind::Socks, 1080),
+            inbound(ExtraInboundKind::Http, 1080),
+        ],
+        &[],
+    )
+    .expect_err("duplicate port");
+    assert!(error.contains("1080"));
+
+    let mut first = inbound(ExtraInboundKind::Socks, 1080);
+    first.tag = "lan".to_string();
+    let mut second = inbound(ExtraInboundKind::Http, 1081);
+    second.tag = "extra-lan".to_string();
+    assert!(normalize_extra_inbounds(vec![first, second], &[]).is_err());
+
+    // 已停用的条目不占用端口
+    let mut disabled = inbound(ExtraInboundKind::Http, 12080);
+    disabled.enabled = false;
+    assert!(normalize_extra_inbounds(vec![disabled], &[12080]).is_ok());
+}
+
+#[test]
+fn test_normalize_validates_protocol_options() {
+    let mut half_auth = inbound(ExtraInboundKind::Socks, 1080);
+    half_auth.username = Some("user".to_string());
+    assert!(normalize_extra_inbounds(vec![half_auth], &[]).is_err());
+
+    let mut ss = inbound(ExtraInboundKind::Shadowsocks, 8388);
+    ss.method = Some("rc4-md5".to_string());
+    ss.password = Some("secret".to_string());
+    assert!(normalize_extra_inbounds(vec![ss.clone()], &[]).is_err());
+    ss.method = Some("aes-256-gcm".to_string());
+    ss.password = Some("  ".to_string());
+    assert!(normalize_extra_inbounds(vec![ss.clone()], &[]).is_err());
+    ss.password = Some("secret".to_string());
+    assert!(normalize_extra_inbounds(vec![ss], &[]).is_ok());
+
+    let mut vmess = inbound(ExtraInboundKind::Vmess, 10086);
+    vmess.uuid = Some("not-a-uuid".to_string());
+    assert!(normalize_extra_inbounds(vec![vmess.clone()], &[]).is_err());
+    vmess.uuid = Some("b831381d-6324-4d53-ad4f-8cda48b30811".to_string());
+    assert!(normalize_extra_inbounds(vec![vmess], &[]).is_ok());
+
+    let mut listen = inbound(ExtraInboundKind::Http, 8080);
+    listen.listen = Some("localhost".to_string());
+    assert!(normalize_extra_inbounds(vec![listen], &[]).is_err());
+}
+
+#[test]
+fn test_normalize_validates_shadowsocks_2022_keys() {
+    let mut ss = inbound(ExtraInboundKind::Shadowsocks, 8388);
+    ss.method = Some("2022-blake3-aes-128-gcm".to_string());
+    ss.password = Some("not a key".to_string());
+    assert!(normalize_extra_inbounds(vec![ss.clone()], &[]).is_err());
+
+    // 32 字节密钥不能用于 aes-128
+    let key_32 = BASE64_ENGINE.encode([7u8; 32]);
+    ss.password = Some(key_32.clone());
+    assert!(normalize_extra_inbounds(vec![ss.clone()], &[]).is_err());
+
+    ss.password = Some(BASE64_ENGINE.encode([7u8; 16]));
+    assert!(normalize_extra_inbounds(vec![ss.clone()], &[]).is_ok());
+
+    for method in ["2022-blake3-aes-256-gcm", "2022-blake3-chacha20-poly1305"] {
+        ss.method = Some(method.to_string());
+        assert!(normalize_extra_inbounds(vec![ss.clone()], &[]).is_err());
+        ss.password = Some(key_32.clone());
+        assert!(normalize_extra_inbounds(vec![ss.clone()], &[]).is_ok());
+        ss.password = Some(BASE64_ENGINE.encode([7u8; 16]));
+    }
+}
+
+#[test]
+fn test_normalize_keeps_password_whitespace() {
+    let mut socks = inbound(ExtraInboundKind::Socks, 1080);
+    socks.username = Some(" user ".to_string());
+    socks.password = Some(" pass ".to_string());
+
+    let normalized = normalize_extra_inbounds(vec![socks], &[]).expect("valid inbound");
+    assert_eq!(normalized[0].username.as_deref(), Some("user"));
+    assert_eq!(normalized[0].password.as_deref(), Some(" pass "));
+}
+
+#[test]
+fn test_unauthenticated_socks_and_http_stay_on_loopback() {
+    let mut socks = inbound(ExtraInboundKind::Socks, 1080);
+    socks.listen = Some("0.0.0.0".to_string());
+    assert!(normalize_extra_inbounds(vec![socks.clone()], &[]).is_err());
+    socks.listen = Some("::1".to_string());
+    assert!(normalize_extra_inbounds(vec![socks.clone()], &[]).is_ok());
+
+    // 跟随局域网开关的条目在没有认证时不对外监听
+    let http = inbound(ExtraInboundKind::Http, 8081);
+    assert_eq!(http.to_json("0.0.0.0")["listen"], json!("127.0.0.1"));
+
+    let mut authed = http.clone();
+    authed.username = Some("user".to_string());
+    authed.password = Some("pass".to_string());
+    assert_eq!(authed.to_json("0.0.0.0")["listen"], json!("0.0.0.0"));
+}
+
+#[test]
+fn test_secrets_are_sealed_for_storage() {
+    let data_dir = std::env::temp_dir().join("extra-inbound-secret-test");
+    let mut ss = inbound(ExtraInboundKind::Shadowsocks, 8388);
+    ss.password = Some(" secret ".to_string());
+    let mut vmess = inbound(ExtraInboundKind::Vmess, 10086);
+    vmess.uuid = Some("b831381d-6324-4d53-ad4f-8cda48b30811".to_string());
+    let original = vec![ss, vmess];
+
+    let mut stored = original.clone();
+    seal_extra_inbound_secrets(&mut stored, &data_dir).expect("seal");
+    assert!(stored[0]
+        .password
+        .as_deref()
+        .is_some_and(|value| value.starts_with(SEALED_SECRET_PREFIX) && !value.contains("secret")));
+    assert!(stored[1]
+        .uuid
+        .as_deref()
+        .is_some_and(|value| value.starts_with(SEALED_SECRET_PREFIX)));
+
+    assert_eq!(open_extra_inbound_secrets(&mut stored, &data_dir), 0);
+    assert_eq!(stored, original);
+
+    // 旧版本保存的明文原样读取
+    let mut legacy = original.clone();
+    open_extra_inbound_secrets(&mut legacy, &data_dir);
+    assert_eq!(legacy, original);
+
+    // 换了数据目录无法解密时停用
+    let mut moved = original.clone();
+    seal_extra_inbound_secrets(&mut moved, &data_dir).expect("seal");
+    assert_eq!(
+        open_extra_inbound_secrets(&mut moved, &data_dir.join("other")),
+        2
+    );
+    assert!(!moved[0].enabled && moved[0].password.is_none());
+}
+
+#[test]
+fn test_transparent_inbounds_are_linux_only() {
+    let result = normalize_extra_inbounds(vec![inbound(ExtraInboundKind::Tproxy, 7893)], &[]);
+    assert_eq!(result.is_ok(), cfg!(target_os = "linux"));
+
+    let built = build_extra_inbounds(
+        &[ExtraInbound {
+            tag: "extra-redir".to_string(),
+            ..inbound(ExtraInboundKind::Redirect, 7892)
+        }],
+        "127.0.0.1",
+    );
+    assert_eq!(built.len(), usize::from(cfg!(target_os = "linux")));
+}
+
+#[test]
+fn test_to_json_per_kind() {
+    let mut socks = inbound(ExtraInboundKind::Socks, 1080);
+    socks.tag = "extra-socks".to_string();
+    socks.listen = Some("0.0.0.0".to_string());
+    socks.username = Some("user".to_string());
+    socks.password = Some("pass".to_string());
+    assert_eq!(
+        socks.to_json("127.0.0.1"),
+        json!({
+            "type": "socks",
+            "tag": "extra-socks",
+            "listen": "0.0.0.0",
+            "listen_port": 1080,
+            "users": [{ "username": "user", "password": "pass" }]
+        })
+    );
+
+    let mut ss = inbound(ExtraInboundKind::Shadowsocks, 8388);
+    ss.tag = "extra-ss".to_string();
+    ss.method = Some("2022-blake3-aes-128-gcm".to_string());
+    ss.password = Some("key".to_string());
+    let value = ss.to_json("127.0.0.1");
+    assert_eq!(value["listen"], json!("127.0.0.1"));
+    assert_eq!(value["method"], json!("2022-blake3-aes-128-gcm"));
+    assert_eq!(value["password"], json!("key"));
+
+    let mut vmess = inbound(ExtraInboundKind::Vmess, 10086);
+    vmess.tag = "extra-vmess".to_string();
+    vmess.uuid = Some("b831381d-6324-4d53-ad4f-8cda48b30811".to_string());
+    assert_eq!(
+        vmess.to_json("127.0.0.1")["users"],
+        json!([{
+            "name": "extra-vmess",
+            "uuid": "b831381d-6324-4d53-ad4f-8cda48b30811",
+            "alterId": 0
+        }])
+    );
+}
+
+#[test]
+fn test_sync_replaces_only_generated_inbounds() {
+    let mut inbounds = vec![
+        json!({ "type": "mixed", "tag": "mixed-in" }),
+        json!({ "type": "socks", "tag": "extra-old" }),
+        json!({ "type": "http", "tag": "custom" }),
+    ];
+
+    sync_extra_inbounds(
+        &mut inbounds,
+        vec![json!({ "type": "socks", "tag": "extra-new" })],
+    );
+
+    let tags: Vec<&str> = inbounds
+        .iter()
+        .filter_map(|inbound| inbound["tag"].as_str())
+        .collect();
+    assert_eq!(tags, vec!["mixed-in", "custom", "extra-new"]);
+}
@@ -6,11 +6,15 @@ lazy_static::lazy_static! {
         Arc::new(ProcessManager::new());
 }
 
+pub mod auto_update;
+pub mod capabilities;
+pub mod config_snapshot;
 pub mod download;
 pub mod embedded;
 pub mod event;
 pub mod guard;
 pub mod import;
+pub mod kernel_store;
 pub mod log_rotation;
 pub mod orchestrator;
 pub mod runtime;
@@ -0,0 +1,366 @@
+//! 内核自动更新
+//!
+//! 按策略（通道、检查间隔、空闲时自动安装、最低兼容版本）在后台检查 sing-box 新版本：
+//! - 通道选择复用应用更新的 `UpdateChannel` 规则；
+//! - 低于最低兼容版本的 release 不会被选中（生成的配置依赖该版本起的特性）；
+//! - 安装后若新内核未通过启动稳定性校验（或配置检查），自动切回之前的版本。
+
+use crate::app::core::connection_stats::active_connection_count;
+use crate::app::core::kernel_service::capabilities::MIN_COMPATIBLE_KERNEL_VERSION;
+use crate::app::core::kernel_service::download::download_kernel;
+use crate::app::core::kernel_service::kernel_store::{
+    activate_kernel_version, list_installed_kernels,
+};
+use crate::app::core::kernel_service::runtime::verify_kernel_startup_stability;
+use crate::app::core::kernel_service::status::is_kernel_running;
+use crate::app::core::kernel_service::versioning::{
+    check_config_validity, get_kernel_release_list,
+};
+use crate::app::storage::enhanced_storage_service::{db_get_app_config, get_enhanced_storage};
+use crate::app::system::update_service::{select_release_by_channel, UpdateChannel};
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter};
+use tracing::{error, info, warn};
+
+pub const POLICY_STORAGE_KEY: &str = "kernel_update_policy";
+pub const STATUS_STORAGE_KEY: &str = "kernel_update_status";
+pub const KERNEL_UPDATE_EVENT: &str = "kernel-update-status";
+/// 后台循环的检查粒度：到期才请求 GitHub，其余时间只尝试安装待装版本
+const LOOP_TICK: Duration = Duration::from_secs(10 * 60);
+const LOOP_START_DELAY: Duration = Duration::from_secs(60);
+
+/// 内核更新策略
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KernelUpdatePolicy {
+    pub enabled: bool,
+    /// `stable` / `prerelease`
+    pub channel: String,
+    pub check_interval_hours: u64,
+    /// 发现新版本后在空闲时（内核未运行或没有活跃连接）自动安装，否则仅提示
+    pub auto_install_when_idle: bool,
+    /// 用户指定的最低版本，实际取值不会低于 `MIN_COMPATIBLE_KERNEL_VERSION`
+    pub min_version: String,
+}
+
+impl Default for KernelUpdatePolicy {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            channel: "stable".to_string(),
+            check_interval_hours: 24,
+            auto_install_when_idle: true,
+            min_version: MIN_COMPATIBLE_KERNEL_VERSION.to_string(),
+        }
+    }
+}
+
+/// 最近一次检查/安装的结果
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KernelUpdateStatus {
+    pub last_checked_at: Option<i64>,
+    pub latest_version: Option<String>,
+    /// 已发现、尚未安装的版本
+    pub pending_version: Option<String>,
+    pub last_installed_version: Option<String>,
+    /// 安装失败或未通过校验而被放弃的版本，不再自动重试
+    pub rolled_back_from: Option<String>,
+    pub last_error: Option<String>,
+}
+
+/// 解析 sing-box 版本号，兼容 `v` 前缀与缺省补丁号（如 `1.12`）
+pub fn parse_kernel_version(raw: &str) -> Option<Version> {
+    let trimmed = raw.trim().trim_start_matches('v');
+    Version::parse(trimmed).ok().or_else(|| {
+        let (core, suffix) = match trimmed.split_once('-') {
+            Some((core, suffix)) => (core, format!("-{}", suffix)),
+            None => (trimmed, String::new()),
+        };
+        if core.split('.').count() != 2 {
+            return None;
+        }
+        Version::parse(&format!("{}.0{}", core, suffix)).ok()
+    })
+}
+
+/// 实际生效的最低版本：用户设置与内置兼容下限取较高者
+pub fn effective_min_version(policy: &KernelUpdatePolicy) -> Version {
+    let builtin = parse_kernel_version(MIN_COMPATIBLE_KERNEL_VERSION)
+        .expect("MIN_COMPATIBLE_KERNEL_VERSION 必须是合法版本号");
+    parse_kernel_version(&policy.min_version)
+        .filter(|user| *user > builtin)
+        .unwrap_or(builtin)
+}
+
+fn release_version(release: &Value) -> Option<Version> {
+    release["tag_name"].as_str().and_then(parse_kernel_version)
+}
+
+/// 按策略从 release 列表中挑选可升级的版本；当前版本已是最新时返回 None
+pub fn pick_kernel_update(
+    releases: &[Value],
+    policy: &KernelUpdatePolicy,
+    current_version: Option<&str>,
+) -> Option<String> {
+    let min_version = effective_min_version(policy);
+    let eligible: Vec<Value> = releases
+        .iter()
+        .filter(|release| !release["draft"].as_bool().unwrap_or(false))
+        .filter(|release| release_version(release).is_some_and(|v| v >= min_version))
+        .cloned()
+        .collect();
+
+    let channel = UpdateChannel::from_inputs(Some(policy.channel.as_str()), false);
+    let candidate = release_version(&select_release_by_channel(&eligible, channel)?)?;
+    match current_version.and_then(parse_kernel_version) {
+        Some(current) if current >= candidate => None,
+        _ => Some(candidate.to_string()),
+    }
+}
+
+fn validate_policy(policy: &KernelUpdatePolicy) -> Result<(), String> {
+    if !matches!(policy.channel.as_str(), "stable" | "prerelease") {
+        return Err(format!("不支持的内核更新通道: {}", policy.channel));
+    }
+    if policy.check_interval_hours == 0 {
+        return Err("检查间隔至少为 1 小时".to_string());
+    }
+    if parse_kernel_version(&policy.min_version).is_none() {
+        return Err(format!("最低版本格式无效: {}", policy.min_version));
+    }
+    Ok(())
+}
+
+fn now_secs() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+async fn load_policy(app_handle: &AppHandle) -> Result<KernelUpdatePolicy, String> {
+    let storage = get_enhanced_storage(app_handle).await?;
+    storage
+        .load_generic_config::<KernelUpdatePolicy>(POLICY_STORAGE_KEY)
+        .await
+        .map(|policy| policy.unwrap_or_default())
+        .map_err(|e| format!("读取内核更新策略失败: {}", e))
+}
+
+async fn load_status(app_handle: &AppHandle) -> Result<KernelUpdateStatus, String> {
+    let storage = get_enhanced_storage(app_handle).await?;
+    storage
+        .load_generic_config::<KernelUpdateStatus>(STATUS_STORAGE_KEY)
+        .await
+        .map(|status| status.unwrap_or_default())
+        .map_err(|e| format!("读取内核更新状态失败: {}", e))
+}
+
+async fn save_status(app_handle: &AppHandle, status: &KernelUpdateStatus) {
+    match get_enhanced_storage(app_handle).await {
+        Ok(storage) => {
+            if let Err(e) = storage
+                .save_generic_config(STATUS_STORAGE_KEY, status)
+                .await
+            {
+                warn!("保存内核更新状态失败: {}", e);
+            }
+        }
+        Err(e) => warn!("保存内核更新状态失败: {}", e),
+    }
+    let _ = app_handle.emit(KERNEL_UPDATE_EVENT, status);
+}
+
+async fn current_kernel_version(app_handle: &AppHandle) -> Option<String> {
+    db_get_app_config(app_handle.clone())
+        .await
+        .ok()
+        .and_then(|config| config.installed_kernel_version)
+        .filter(|v| !v.is_empty())
+}
+
+/// 空闲：内核未运行，或运行中但没有活跃连接
+async fn is_idle() -> bool {
+    !is_kernel_running().await.unwrap_or(false) || active_connection_count() == 0
+}
+
+/// 检查新版本并更新状态中的 `pending_version`
+async fn check_for_update(
+    app_handle: &AppHandle,
+    policy: &KernelUpdatePolicy,
+    status: &mut KernelUpdateStatus,
+) -> Result<(), String> {
+    status.last_checked_at = Some(now_secs());
+    let releases = get_kernel_release_list().await?;
+    let current = current_kernel_version(app_handle).await;
+    let update = pick_kernel_update(&releases, policy, current.as_deref());
+
+    status.latest_version = update.clone().or(current);
+    status.pending_version = update.filter(|v| status.rolled_back_from.as_ref() != Some(v));
+    if let Some(version) = &status.pending_version {
+        info!("发现可用的内核更新: {}", version);
+    }
+    Ok(())
+}
+
+/// 安装指定版本，未通过校验时回滚到之前使用的版本
+async fn install_with_rollback(app_handle: &AppHandle, version: &str) -> Result<(), String> {
+    let previous_version = list_installed_kernels()
+        .await
+        .ok()
+        .and_then(|kernels| kernels.into_iter().find(|k| k.active))
+        .map(|k| k.meta.version);
+    let was_running = is_kernel_running().await.unwrap_or(false);
+
+    info!(
+        "开始自动更新内核: {:?} -> {} (内核运行中: {})",
+        previous_version, version, was_running
+    );
+    // 自动更新不做无校验安装：拿不到摘要时直接失败，等待用户手动处理
+    download_kernel(app_handle.clone(), Some(version.to_string()), None).await?;
+
+    let verification = if was_running {
+        // download_kernel 会按原配置重启内核，这里再确认新内核稳定运行
+        let config = db_get_app_config(app_handle.clone()).await?;
+        verify_kernel_startup_stability(config.api_port, config.proxy_port).await
+    } else {
+        check_config_validity(app_handle.clone(), String::new()).await
+    };
+
+    let Err(reason) = verification else {
+        return Ok(());
+    };
+    error!("新内核 {} 校验失败: {}", version, reason);
+
+    let Some(previous) = previous_version else {
+        return Err(format!(
+            "新内核 {} 校验失败且没有可回滚的版本: {}",
+            version, reason
+        ));
+    };
+    match activate_kernel_version(app_handle.clone(), previous.clone()).await {
+        Ok(_) => Err(format!(
+            "新内核 {} 校验失败，已回滚到 {}: {}",
+            version, previous, reason
+        )),
+        Err(e) => Err(format!(
+            "新内核 {} 校验失败，回滚到 {} 也失败了: {}; {}",
+            version, previous, reason, e
+        )),
+    }
+}
+
+async fn install_pending(app_handle: &AppHandle, status: &mut KernelUpdateStatus) {
+    let Some(version) = status.pending_version.clone() else {
+        return;
+    };
+    match install_with_rollback(app_handle, &version).await {
+        Ok(_) => {
+            info!("内核已自动更新到 {}", version);
+            status.pending_version = None;
+            status.last_installed_version = Some(version);
+            status.rolled_back_from = None;
+            status.last_error = None;
+        }
+        Err(e) => {
+            warn!("内核自动更新失败: {}", e);
+            // 回滚过的版本不再自动重试，等待下一个新版本
+            status.pending_version = None;
+            status.rolled_back_from = Some(version);
+            status.last_error = Some(e);
+        }
+    }
+}
+
+async fn run_update_tick(app_handle: &AppHandle) -> Result<(), String> {
+    let policy = load_policy(app_handle).await?;
+    if !policy.enabled {
+        return Ok(());
+    }
+
+    let mut status = load_status(app_handle).await?;
+    let interval_secs = (policy.check_interval_hours * 3600) as i64;
+    let due = status
+        .last_checked_at
+        .map(|at| now_secs() - at >= interval_secs)
+        .unwrap_or(true);
+
+    if due {
+        if let Err(e) = check_for_update(app_handle, &policy, &mut status).await {
+            status.last_error = Some(e);
+        }
+        save_status(app_handle, &status).await;
+    }
+
+    if policy.auto_install_when_idle && status.pending_version.is_some() {
+        if is_idle().await {
+            install_pending(app_handle, &mut status).await;
+            save_status(app_handle, &status).await;
+        } else {
+            info!("内核更新待安装，当前有活跃连接，稍后重试");
+        }
+    }
+    Ok(())
+}
+
+/// 后台内核更新循环
+pub async fn start_kernel_update_loop(app_handle: AppHandle) {
+    tokio::time::sleep(LOOP_START_DELAY).await;
+    loop {
+        if let Err(e) = run_update_tick(&app_handle).await {
+            warn!("内核自动更新检查失败: {}", e);
+        }
+        tokio::time::sleep(LOOP_TICK).await;
+    }
+}
+
+#[tauri::command]
+pub async fn get_kernel_update_policy(app_handle: AppHandle) -> Result<KernelUpdatePolicy, String> {
+    load_policy(&app_handle).await
+}
+
+#[tauri::command]
+pub async fn save_kernel_update_policy(
+    app_handle: AppHandle,
+    policy: KernelUpdatePolicy,
+) -> Result<(), String> {
+    validate_policy(&policy)?;
+    let storage = get_enhanced_storage(&app_handle).await?;
+    storage
+        .save_generic_config(POLICY_STORAGE_KEY, &policy)
+        .await
+        .map_err(|e| format!("保存内核更新策略失败: {}", e))
+}
+
+#[tauri::command]
+pub async fn get_kernel_update_status(app_handle: AppHandle) -> Result<KernelUpdateStatus, String> {
+    load_status(&app_handle).await
+}
+
+/// 立即检查一次；`install` 为 true 时发现新版本即安装（不要求空闲）
+#[tauri::command]
+pub async fn check_kernel_update_now(
+    app_handle: AppHandle,
+    install: Option<bool>,
+) -> Result<KernelUpdateStatus, String> {
+    let policy = load_policy(&app_handle).await?;
+    let mut status = load_status(&app_handle).await?;
+    if install.unwrap_or(false) {
+        // 手动安装时允许重试之前被放弃的版本
+        status.rolled_back_from = None;
+    }
+    check_for_update(&app_handle, &policy, &mut status).await?;
+    if install.unwrap_or(false) {
+        install_pending(&app_handle, &mut status).await;
+    }
+    save_status(&app_handle, &status).await;
+    Ok(status)
+}
+
+#[cfg(test)]
+#[path = "auto_update.tests.rs"]
+mod tests;
@@ -0,0 +1,91 @@
+use super::*;
+use serde_json::json;
+
+fn releases() -> Vec<Value> {
+    vec![
+        json!({ "tag_name": "v1.13.0-beta.3", "prerelease": true }),
+        json!({ "tag_name": "v1.12.10", "prerelease": false }),
+        json!({ "tag_name": "v1.12.9", "prerelease": false }),
+        json!({ "tag_name": "v1.11.15", "prerelease": false }),
+    ]
+}
+
+fn policy(channel: &str) -> KernelUpdatePolicy {
+    KernelUpdatePolicy {
+        channel: channel.to_string(),
+        ..KernelUpdatePolicy::default()
+    }
+}
+
+#[test]
+fn parse_kernel_version_should_accept_prefix_and_short_versions() {
+    assert_eq!(
+        parse_kernel_version("v1.12.10"),
+        Version::parse("1.12.10").ok()
+    );
+    assert_eq!(parse_kernel_version("1.12"), Version::parse("1.12.0").ok());
+    assert_eq!(
+        parse_kernel_version("1.13-beta.1"),
+        Version::parse("1.13.0-beta.1").ok()
+    );
+    assert_eq!(parse_kernel_version("latest"), None);
+}
+
+#[test]
+fn pick_kernel_update_should_follow_channel() {
+    assert_eq!(
+        pick_kernel_update(&releases(), &policy("stable"), Some("1.12.9")),
+        Some("1.12.10".to_string())
+    );
+    assert_eq!(
+        pick_kernel_update(&releases(), &policy("prerelease"), Some("1.12.10")),
+        Some("1.13.0-beta.3".to_string())
+    );
+    assert_eq!(
+        pick_kernel_update(&releases(), &policy("stable"), Some("1.12.10")),
+        None
+    );
+    // 当前已是更新的测试版时，稳定通道不会降级
+    assert_eq!(
+        pick_kernel_update(&releases(), &policy("stable"), Some("1.13.0-beta.3")),
+        None
+    );
+}
+
+#[test]
+fn pick_kernel_update_should_respect_min_version() {
+    let old_only = vec![json!({ "tag_name": "v1.11.15", "prerelease": false })];
+    assert_eq!(pick_kernel_update(&old_only, &policy("stable"), None), None);
+
+    let strict = KernelUpdatePolicy {
+        min_version: "1.13.0".to_string(),
+        ..policy("stable")
+    };
+    assert_eq!(
+        pick_kernel_update(&releases(), &strict, Some("1.12.9")),
+        None
+    );
+}
+
+#[test]
+fn effective_min_version_should_never_drop_below_builtin() {
+    let lenient = KernelUpdatePolicy {
+        min_version: "1.8.0".to_string(),
+        ..KernelUpdatePolicy::default()
+    };
+    assert_eq!(
+        effective_min_version(&lenient),
+        parse_kernel_version(MIN_COMPATIBLE_KERNEL_VERSION).unwrap()
+    );
+}
+
+#[test]
+fn validate_policy_should_reject_invalid_values() {
+    assert!(validate_policy(&KernelUpdatePolicy::default()).is_ok());
+    assert!(validate_policy(&policy("autobuild")).is_err());
+    assert!(validate_policy(&KernelUpdatePolicy {
+        check_interval_hours: 0,
+        ..KernelUpdatePolicy::default()
+    })
+    .is_err());
+}
@@ -0,0 +1,282 @@
+//! 内核能力探测
+//!
+//! 生成的配置会用到 `domain_resolver`、`action: sniff` / `hijack-dns` 路由动作、`store_rdrc`
+//! 等较新的字段，以及依赖构建标签的功能（QUIC 协议、WireGuard、gVisor 协议栈）。
+//! 旧版本或精简构建的内核会直接拒绝这些配置，因此在这里解析 `sing-box version` 的输出，
+//! 供配置生成时降级；无法降级的不兼容项直接作为错误返回给用户。
+
+use crate::app::constants::paths;
+use crate::app::core::kernel_service::embedded::{
+    extract_version_from_output, read_kernel_version_output,
+};
+use crate::app::storage::enhanced_storage_service::db_get_app_config;
+use crate::app::storage::state_model::AppConfig;
+use semver::Version;
+use serde::Serialize;
+use serde_json::Value;
+use std::sync::RwLock;
+use tauri::AppHandle;
+use tracing::{info, warn};
+
+/// `rule action`（`action: sniff` / `hijack-dns`）最低版本
+const RULE_ACTIONS_MIN_VERSION: &str = "1.11.0";
+/// `domain_resolver` 与新版 DNS 服务器格式最低版本，也是当前生成的配置所需的最低内核版本；
+/// 自动更新据此拒绝安装更旧的内核
+pub const MIN_COMPATIBLE_KERNEL_VERSION: &str = "1.12.0";
+/// `cache_file.store_rdrc` 最低版本
+const STORE_RDRC_MIN_VERSION: &str = "1.9.0";
+
+pub const TAG_QUIC: &str = "with_quic";
+pub const TAG_WIREGUARD: &str = "with_wireguard";
+pub const TAG_GVISOR: &str = "with_gvisor";
+
+lazy_static::lazy_static! {
+    static ref CAPABILITIES_CACHE: RwLock<KernelCapabilities> =
+        RwLock::new(KernelCapabilities::default());
+}
+
+/// 已安装内核的版本与构建标签
+///
+/// 未探测到内核（`probed == false`）时视为支持全部功能，保持与探测前一致的生成结果。
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct KernelCapabilities {
+    pub probed: bool,
+    pub version: Option<String>,
+    pub tags: Vec<String>,
+}
+
+impl KernelCapabilities {
+    /// 解析 `sing-box version` 的输出
+    pub fn from_version_output(output: &str) -> Self {
+        let version = output
+            .lines()
+            .find(|line| line.trim_start().starts_with("sing-box version"))
+            .or_else(|| output.lines().next())
+            .and_then(extract_version_from_output);
+        let tags = output
+            .lines()
+            .find_map(|line| line.trim().strip_prefix("Tags:"))
+            .map(|raw| {
+                raw.split(',')
+                    .map(str::trim)
+                    .filter(|tag| !tag.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Self {
+            probed: true,
+            version,
+            tags,
+        }
+    }
+
+    /// 版本不低于 `min`；版本未知或无法解析时按支持处理
+    pub fn version_at_least(&self, min: &str) -> bool {
+        let Some(version) = self.version.as_deref() else {
+            return true;
+        };
+        match (Version::parse(version), Version::parse(min)) {
+            (Ok(current), Ok(min)) => {
+                // 预发布版（如 1.12.0-beta.1）已包含对应功能，只比较主版本号部分
+                Version::new(current.major, current.minor, current.patch) >= min
+            }
+            _ => true,
+        }
+    }
+
+    /// 是否带有指定构建标签；未探测或输出中没有标签行时按支持处理
+    pub fn has_tag(&self, tag: &str) -> bool {
+        !self.probed || self.tags.is_empty() || self.tags.iter().any(|t| t == tag)
+    }
+
+    pub fn supports_rule_actions(&self) -> bool {
+        self.version_at_least(RULE_ACTIONS_MIN_VERSION)
+    }
+
+    pub fn supports_domain_resolver(&self) -> bool {
+        self.version_at_least(MIN_COMPATIBLE_KERNEL_VERSION)
+    }
+
+    pub fn supports_store_rdrc(&self) -> bool {
+        self.version_at_least(STORE_RDRC_MIN_VERSION)
+    }
+
+    /// 指定的 TUN 协议栈是否可用（`gvisor` / `mixed` 依赖 `with_gvisor`）
+    pub fn supports_tun_stack(&self, stack: &str) -> bool {
+        match stack {
+            "gvisor" | "mixed" => self.has_tag(TAG_GVISOR),
+            _ => true,
+        }
+    }
+
+    /// 实际写入配置的 TUN 协议栈：内核不支持时回退到 `system`
+    pub fn effective_tun_stack<'a>(&self, stack: &'a str) -> &'a str {
+        if self.supports_tun_stack(stack) {
+            stack
+        } else {
+            "system"
+        }
+    }
+
+    /// 出站类型所需的构建标签
+    fn required_tag_for_outbound(outbound_type: &str) -> Option<&'static str> {
+        match outbound_type {
+            "hysteria" | "hysteria2" | "tuic" => Some(TAG_QUIC),
+            "wireguard" => Some(TAG_WIREGUARD),
+            _ => None,
+        }
+    }
+
+    fn version_label(&self) -> &str {
+        self.version.as_deref().unwrap_or("未知版本")
+    }
+
+    /// 生成的配置必然无法加载的不兼容项（没有可降级的旧写法），配置生成时据此直接报错
+    pub fn blocking_issues(&self) -> Vec<String> {
+        let mut issues = Vec::new();
+        if !self.supports_domain_resolver() {
+            issues.push(format!(
+                "当前内核 {} 不支持 domain_resolver 与新版 DNS 配置（需要 {} 及以上），请升级内核",
+                self.version_label(),
+                MIN_COMPATIBLE_KERNEL_VERSION
+            ));
+        }
+        if !self.supports_rule_actions() {
+            issues.push(format!(
+                "当前内核 {} 不支持 sniff / hijack-dns 路由动作（需要 {} 及以上），请升级内核",
+                self.version_label(),
+                RULE_ACTIONS_MIN_VERSION
+            ));
+        }
+        issues
+    }
+
+    /// 检查应用设置中当前内核不支持的功能（含 [`Self::blocking_issues`]）
+    pub fn warnings_for_settings(&self, app_config: &AppConfig) -> Vec<String> {
+        let mut warnings = self.blocking_issues();
+        if app_config.singbox_fake_dns_enabled && !self.supports_store_rdrc() {
+            warnings.push(format!(
+                "当前内核 {} 不支持 store_rdrc，Fake DNS 映射将不会持久化",
+                self.version_label()
+            ));
+        }
+        if app_config.tun_enabled && !self.supports_tun_stack(&app_config.tun_stack) {
+            warnings.push(format!(
+                "当前内核未包含 {} 构建标签，TUN 协议栈 {} 已回退为 system",
+                TAG_GVISOR, app_config.tun_stack
+            ));
+        }
+        warnings
+    }
+
+    /// 检查配置中的出站是否依赖内核缺少的构建标签
+    pub fn warnings_for_outbounds(&self, config: &Value) -> Vec<String> {
+        let mut missing: Vec<(&'static str, Vec<String>)> = Vec::new();
+        let outbounds = config
+            .get("outbounds")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .chain(
+                config
+                    .get("endpoints")
+                    .and_then(Value::as_array)
+                    .into_iter()
+                    .flatten(),
+            );
+        for outbound in outbounds {
+            let Some(outbound_type) = outbound.get("type").and_then(Value::as_str) else {
+                continue;
+            };
+            let Some(tag) = Self::required_tag_for_outbound(outbound_type) else {
+                continue;
+            };
+            if self.has_tag(tag) {
+                continue;
+            }
+            let name = outbound
+                .get("tag")
+                .and_then(Value::as_str)
+                .unwrap_or(outbound_type)
+                .to_string();
+            match missing.iter_mut().find(|(t, _)| *t == tag) {
+                Some((_, names)) => names.push(name),
+                None => missing.push((tag, vec![name])),
+            }
+        }
+
+        missing
+            .into_iter()
+            .map(|(tag, names)| {
+                format!(
+                    "当前内核未包含 {} 构建标签，以下节点将无法使用: {}",
+                    tag,
+                    names.join(", ")
+                )
+            })
+            .collect()
+    }
+}
+
+/// 获取缓存的内核能力（未探测时返回“全部支持”）
+pub fn current_capabilities() -> KernelCapabilities {
+    CAPABILITIES_CACHE
+        .read()
+        .map(|caps| caps.clone())
+        .unwrap_or_default()
+}
+
+/// 重新探测已安装内核的能力并更新缓存；内核安装、导入、切换版本后调用
+pub async fn refresh_kernel_capabilities() -> KernelCapabilities {
+    let kernel_path = paths::get_kernel_path();
+    let capabilities = if kernel_path.is_file() {
+        match read_kernel_version_output(&kernel_path).await {
+            Some(output) => KernelCapabilities::from_version_output(&output),
+            None => {
+                warn!("读取内核版本信息失败，按全部功能可用处理");
+                KernelCapabilities::default()
+            }
+        }
+    } else {
+        KernelCapabilities::default()
+    };
+
+    if capabilities.probed {
+        info!(
+            "内核能力: 版本 {}，构建标签 [{}]",
+            capabilities.version_label(),
+            capabilities.tags.join(", ")
+        );
+    }
+    if let Ok(mut cache) = CAPABILITIES_CACHE.write() {
+        *cache = capabilities.clone();
+    }
+    capabilities
+}
+
+/// 内核能力与当前设置的兼容性报告
+#[derive(Debug, Clone, Serialize)]
+pub struct KernelCapabilityReport {
+    #[serde(flatten)]
+    pub capabilities: KernelCapabilities,
+    pub warnings: Vec<String>,
+}
+
+/// 重新探测内核能力，并检查当前设置中内核不支持的功能
+#[tauri::command]
+pub async fn get_kernel_capabilities(
+    app_handle: AppHandle,
+) -> Result<KernelCapabilityReport, String> {
+    let capabilities = refresh_kernel_capabilities().await;
+    let app_config = db_get_app_config(app_handle).await?;
+    Ok(KernelCapabilityReport {
+        warnings: capabilities.warnings_for_settings(&app_config),
+        capabilities,
+    })
+}
+
+#[cfg(test)]
+#[path = "capabilities.tests.rs"]
+mod tests;
@@ -0,0 +1,99 @@
+use super::*;
+use serde_json::json;
+
+const FULL_OUTPUT: &str = "sing-box version 1.12.10
+
+Environment: go1.24.6 linux/amd64
+Tags: with_gvisor,with_quic,with_dhcp,with_wireguard,with_utls,with_acme,with_clash_api
+Revision: 0e6b7b1a0c5b5b0f
+CGO: disabled
+";
+
+const MINIMAL_OUTPUT: &str = "sing-box version 1.10.7
+
+Environment: go1.23.4 linux/amd64
+Tags: with_clash_api
+CGO: disabled
+";
+
+#[test]
+fn from_version_output_should_parse_version_and_tags() {
+    let caps = KernelCapabilities::from_version_output(FULL_OUTPUT);
+    assert!(caps.probed);
+    assert_eq!(caps.version.as_deref(), Some("1.12.10"));
+    assert!(caps.has_tag(TAG_QUIC));
+    assert!(caps.has_tag(TAG_WIREGUARD));
+    assert!(caps.supports_tun_stack("gvisor"));
+    assert!(caps.supports_domain_resolver());
+    assert!(caps.supports_rule_actions());
+}
+
+#[test]
+fn minimal_kernel_should_report_missing_features() {
+    let caps = KernelCapabilities::from_version_output(MINIMAL_OUTPUT);
+    assert!(!caps.has_tag(TAG_GVISOR));
+    assert!(!caps.supports_domain_resolver());
+    assert!(!caps.supports_rule_actions());
+    assert!(caps.supports_store_rdrc());
+    assert_eq!(caps.effective_tun_stack("mixed"), "system");
+    assert_eq!(caps.effective_tun_stack("system"), "system");
+
+    let app_config = AppConfig {
+        tun_enabled: true,
+        tun_stack: "gvisor".to_string(),
+        ..AppConfig::default()
+    };
+    let warnings = caps.warnings_for_settings(&app_config);
+    assert_eq!(warnings.len(), 3);
+    assert!(warnings[2].contains("with_gvisor"));
+
+    let blocking = caps.blocking_issues();
+    assert_eq!(blocking.len(), 2);
+    assert!(blocking[0].contains(MIN_COMPATIBLE_KERNEL_VERSION));
+    assert_eq!(&warnings[..2], &blocking[..]);
+}
+
+#[test]
+fn unprobed_kernel_should_be_treated_as_fully_capable() {
+    let caps = KernelCapabilities::default();
+    assert!(caps.has_tag(TAG_QUIC));
+    assert!(caps.supports_domain_resolver());
+    assert_eq!(caps.effective_tun_stack("gvisor"), "gvisor");
+    assert!(caps.blocking_issues().is_empty());
+    assert!(caps
+        .warnings_for_settings(&AppConfig {
+            tun_enabled: true,
+            ..AppConfig::default()
+        })
+        .is_empty());
+}
+
+#[test]
+fn prerelease_should_satisfy_feature_version() {
+    let caps = KernelCapabilities::from_version_output("sing-box version 1.12.0-beta.3\n");
+    assert!(caps.supports_domain_resolver());
+    // 输出中没有 Tags 行时无法判断，按支持处理
+    assert!(caps.has_tag(TAG_GVISOR));
+}
+
+#[test]
+fn warnings_for_outbounds_should_group_nodes_by_missing_tag() {
+    let caps = KernelCapabilities::from_version_output(MINIMAL_OUTPUT);
+    let config = json!({
+        "outbounds": [
+            { "type": "hysteria2", "tag": "hy2" },
+            { "type": "tuic", "tag": "tuic" },
+            { "type": "vless", "tag": "vless" }
+        ],
+        "endpoints": [
+            { "type": "wireguard", "tag": "wg" }
+        ]
+    });
+    let warnings = caps.warnings_for_outbounds(&config);
+    assert_eq!(warnings.len(), 2);
+    assert!(warnings[0].contains(TAG_QUIC) && warnings[0].contains("hy2, tuic"));
+    assert!(warnings[1].contains(TAG_WIREGUARD) && warnings[1].contains("wg"));
+
+    let full = KernelCapabilities::from_version_output(FULL_OUTPUT);
+    assert!(full.warnings_for_outbounds(&config).is_empty());
+}
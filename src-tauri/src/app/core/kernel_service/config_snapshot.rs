@@ -0,0 +1,347 @@
+//! 可用配置快照（last-known-good）与启动失败回滚
+//!
+//! - 每次启动通过 `verify_kernel_startup_stability` 后，记录当前配置的 SHA-256 与副本；
+//! - 后续启动在 Preflight/Spawn/Readiness 阶段失败、且配置与最近可用快照不一致时，
+//!   自动（或交由前端确认）回滚到最近一次可用配置；
+//! - 快照索引持久化在 `generic_config` 表（key = `STORAGE_KEY`），副本存放在
+//!   `sing-box/config_history/` 下，仅保留最近 `MAX_GOOD_CONFIG_SNAPSHOTS` 份。
+
+use crate::app::constants::paths;
+use crate::app::core::kernel_service::state::{
+    StartupDiagnosis, StartupDiagnosisKind, StartupStage,
+};
+use crate::app::core::kernel_service::utils::resolve_config_path;
+use crate::app::storage::enhanced_storage_service::get_enhanced_storage;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter};
+use tracing::{info, warn};
+
+pub const STORAGE_KEY: &str = "kernel_good_config_history";
+/// 最多保留的可用配置快照数量
+pub const MAX_GOOD_CONFIG_SNAPSHOTS: usize = 5;
+const SNAPSHOT_DIR_NAME: &str = "config_history";
+const FAILED_CONFIG_FILE_NAME: &str = "last-failed.json";
+
+/// 一份通过稳定性校验的配置快照
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GoodConfigSnapshot {
+    pub id: String,
+    /// 配置内容 SHA-256（hex）
+    pub hash: String,
+    /// 快照对应的原始配置路径
+    pub source_path: String,
+    /// 快照副本路径
+    pub snapshot_path: String,
+    pub recorded_at: u64,
+    /// 最近一次以该配置成功启动的时间
+    pub last_verified_at: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GoodConfigHistory {
+    /// 启动失败时是否自动回滚；关闭时仅在返回结果中提示可回滚
+    pub auto_rollback: bool,
+    /// 按最近验证时间倒序排列
+    pub entries: Vec<GoodConfigSnapshot>,
+}
+
+impl Default for GoodConfigHistory {
+    fn default() -> Self {
+        Self {
+            auto_rollback: true,
+            entries: Vec::new(),
+        }
+    }
+}
+
+impl GoodConfigHistory {
+    /// 写入一条快照：同一路径同一哈希只刷新验证时间并移到最前，
+    /// 超出上限的旧快照被淘汰并返回，由调用方清理副本文件。
+    pub fn upsert(&mut self, entry: GoodConfigSnapshot, max: usize) -> Vec<GoodConfigSnapshot> {
+        if let Some(pos) = self
+            .entries
+            .iter()
+            .position(|e| e.hash == entry.hash && e.source_path == entry.source_path)
+        {
+            let mut existing = self.entries.remove(pos);
+            existing.last_verified_at = entry.last_verified_at;
+            self.entries.insert(0, existing);
+        } else {
+            self.entries.insert(0, entry);
+        }
+
+        let max = max.max(1);
+        if self.entries.len() > max {
+            self.entries.split_off(max)
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// 查找指定配置路径最近一次可用的快照
+    pub fn latest_for(&self, source_path: &str) -> Option<&GoodConfigSnapshot> {
+        self.entries.iter().find(|e| e.source_path == source_path)
+    }
+
+    pub fn find(&self, id: &str) -> Option<&GoodConfigSnapshot> {
+        self.entries.iter().find(|e| e.id == id)
+    }
+
+    fn is_snapshot_referenced(&self, snapshot_path: &str) -> bool {
+        self.entries
+            .iter()
+            .any(|e| e.snapshot_path == snapshot_path)
+    }
+}
+
+/// 一次回滚尝试的结果
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigRollbackOutcome {
+    /// 是否已把快照写回配置文件（关闭自动回滚时为 false，仅提示）
+    pub applied: bool,
+    pub snapshot: GoodConfigSnapshot,
+    pub config_path: String,
+    /// 失败配置的备份位置，便于排查
+    pub failed_config_backup: Option<String>,
+}
+
+pub fn hash_config_bytes(content: &[u8]) -> String {
+    let digest = Sha256::digest(content);
+    digest.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// 只有与配置相关的启动失败才值得回滚：
+/// 二进制缺失、权限/提权、端口占用、旧进程清理失败等环境问题换配置也无济于事。
+pub fn should_rollback_for(diagnosis: &StartupDiagnosis) -> bool {
+    let stage_matches = matches!(
+        diagnosis.stage,
+        StartupStage::Preflight | StartupStage::Spawn | StartupStage::Readiness
+    );
+    let kind_matches = !matches!(
+        diagnosis.kind,
+        StartupDiagnosisKind::BinaryMissing
+            | StartupDiagnosisKind::PermissionDenied
+            | StartupDiagnosisKind::SudoRequired
+            | StartupDiagnosisKind::SudoInvalid
+            | StartupDiagnosisKind::PortConflict
+            | StartupDiagnosisKind::ConflictCleanupFailed
+    );
+    stage_matches && kind_matches
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn snapshot_dir() -> PathBuf {
+    paths::get_config_dir().join(SNAPSHOT_DIR_NAME)
+}
+
+async fn load_history(app_handle: &AppHandle) -> Result<GoodConfigHistory, String> {
+    let storage = get_enhanced_storage(app_handle)
+        .await
+        .map_err(|e| format!("初始化存储失败: {}", e))?;
+    Ok(storage
+        .load_generic_config::<GoodConfigHistory>(STORAGE_KEY)
+        .await
+        .map_err(|e| format!("读取可用配置快照失败: {}", e))?
+        .unwrap_or_default())
+}
+
+async fn save_history(app_handle: &AppHandle, history: &GoodConfigHistory) -> Result<(), String> {
+    let storage = get_enhanced_storage(app_handle)
+        .await
+        .map_err(|e| format!("初始化存储失败: {}", e))?;
+    storage
+        .save_generic_config(STORAGE_KEY, history)
+        .await
+        .map_err(|e| format!("保存可用配置快照失败: {}", e))
+}
+
+/// 内核通过稳定性校验后调用：记录当前配置为可用快照。
+pub async fn record_good_config(app_handle: &AppHandle, config_path: &Path) -> Result<(), String> {
+    let content = tokio::fs::read(config_path)
+        .await
+        .map_err(|e| format!("读取配置文件失败: {}", e))?;
+    let hash = hash_config_bytes(&content);
+    let source_path = config_path.to_string_lossy().to_string();
+
+    let mut history = load_history(app_handle).await?;
+    let now = now_millis();
+
+    if let Some(front) = history.entries.first() {
+        if front.hash == hash && front.source_path == source_path {
+            history.entries[0].last_verified_at = now;
+            return save_history(app_handle, &history).await;
+        }
+    }
+
+    let dir = snapshot_dir();
+    tokio::fs::create_dir_all(&dir)
+        .await
+        .map_err(|e| format!("创建配置快照目录失败: {}", e))?;
+
+    // 以内容哈希命名，同一份配置在多处引用时共享副本
+    let snapshot_path = dir.join(format!("{}.json", &hash[..16]));
+    if !snapshot_path.exists() {
+        tokio::fs::write(&snapshot_path, &content)
+            .await
+            .map_err(|e| format!("写入配置快照失败: {}", e))?;
+    }
+
+    let evicted = history.upsert(
+        GoodConfigSnapshot {
+            id: format!("{}-{}", &hash[..12], now),
+            hash,
+            source_path,
+            snapshot_path: snapshot_path.to_string_lossy().to_string(),
+            recorded_at: now,
+            last_verified_at: now,
+        },
+        MAX_GOOD_CONFIG_SNAPSHOTS,
+    );
+
+    for entry in evicted {
+        if !history.is_snapshot_referenced(&entry.snapshot_path) {
+            if let Err(e) = tokio::fs::remove_file(&entry.snapshot_path).await {
+                warn!("清理过期配置快照失败 {}: {}", entry.snapshot_path, e);
+            }
+        }
+    }
+
+    save_history(app_handle, &history).await?;
+    info!("已记录可用配置快照: {}", config_path.display());
+    Ok(())
+}
+
+/// 启动失败后尝试回滚到当前配置路径最近一次可用的快照。
+///
+/// 返回 `Ok(None)` 表示无需回滚：没有可用快照，或当前配置与快照一致（失败与配置无关）。
+pub async fn rollback_to_last_good_config(
+    app_handle: &AppHandle,
+) -> Result<Option<ConfigRollbackOutcome>, String> {
+    let config_path = resolve_config_path(app_handle).await?;
+    let source_path = config_path.to_string_lossy().to_string();
+    let history = load_history(app_handle).await?;
+
+    let Some(snapshot) = history.latest_for(&source_path).cloned() else {
+        return Ok(None);
+    };
+
+    // 配置文件缺失也视为变更，同样可以回滚
+    let current = tokio::fs::read(&config_path).await.ok();
+    if current
+        .as_deref()
+        .map(|content| hash_config_bytes(content) == snapshot.hash)
+        .unwrap_or(false)
+    {
+        return Ok(None);
+    }
+
+    if !Path::new(&snapshot.snapshot_path).exists() {
+        warn!("可用配置快照副本丢失: {}", snapshot.snapshot_path);
+        return Ok(None);
+    }
+
+    if !history.auto_rollback {
+        return Ok(Some(ConfigRollbackOutcome {
+            applied: false,
+            snapshot,
+            config_path: source_path,
+            failed_config_backup: None,
+        }));
+    }
+
+    let failed_config_backup = match current {
+        Some(content) => {
+            let backup = snapshot_dir().join(FAILED_CONFIG_FILE_NAME);
+            match tokio::fs::write(&backup, content).await {
+                Ok(_) => Some(backup.to_string_lossy().to_string()),
+                Err(e) => {
+                    warn!("备份失败配置失败: {}", e);
+                    None
+                }
+            }
+        }
+        None => None,
+    };
+
+    restore_snapshot_file(&snapshot, &config_path).await?;
+
+    let outcome = ConfigRollbackOutcome {
+        applied: true,
+        snapshot,
+        config_path: source_path,
+        failed_config_backup,
+    };
+    warn!(
+        "启动失败，已回滚到最近可用配置: {} (快照 {})",
+        outcome.config_path, outcome.snapshot.id
+    );
+    let _ = app_handle.emit("kernel-config-rolled-back", &outcome);
+
+    Ok(Some(outcome))
+}
+
+async fn restore_snapshot_file(snapshot: &GoodConfigSnapshot, target: &Path) -> Result<(), String> {
+    if let Some(parent) = target.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|e| format!("创建配置目录失败: {}", e))?;
+    }
+    tokio::fs::copy(&snapshot.snapshot_path, target)
+        .await
+        .map(|_| ())
+        .map_err(|e| format!("恢复配置快照失败: {}", e))
+}
+
+/// 列出可用配置快照（供前端历史列表展示）
+#[tauri::command]
+pub async fn list_good_configs(app_handle: AppHandle) -> Result<GoodConfigHistory, String> {
+    load_history(&app_handle).await
+}
+
+/// 手动恢复指定快照到其原始配置路径，重启内核后生效
+#[tauri::command]
+pub async fn restore_good_config(
+    app_handle: AppHandle,
+    id: String,
+) -> Result<serde_json::Value, String> {
+    let history = load_history(&app_handle).await?;
+    let snapshot = history
+        .find(&id)
+        .cloned()
+        .ok_or_else(|| format!("未找到配置快照: {}", id))?;
+
+    restore_snapshot_file(&snapshot, Path::new(&snapshot.source_path)).await?;
+    info!(
+        "已手动恢复配置快照 {} -> {}",
+        snapshot.id, snapshot.source_path
+    );
+
+    Ok(json!({
+        "success": true,
+        "message": "配置已恢复，重启内核后生效",
+        "config_path": snapshot.source_path
+    }))
+}
+
+/// 设置启动失败时是否自动回滚
+#[tauri::command]
+pub async fn set_config_auto_rollback(app_handle: AppHandle, enabled: bool) -> Result<(), String> {
+    let mut history = load_history(&app_handle).await?;
+    history.auto_rollback = enabled;
+    save_history(&app_handle, &history).await
+}
+
+#[cfg(test)]
+#[path = "config_snapshot.tests.rs"]
+mod tests;
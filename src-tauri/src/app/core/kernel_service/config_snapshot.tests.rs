@@ -0,0 +1,115 @@
+use super::*;
+
+fn snapshot(hash: &str, source: &str, at: u64) -> GoodConfigSnapshot {
+    GoodConfigSnapshot {
+        id: format!("{}-{}", hash, at),
+        hash: hash.to_string(),
+        source_path: source.to_string(),
+        snapshot_path: format!("/tmp/{}.json", hash),
+        recorded_at: at,
+        last_verified_at: at,
+    }
+}
+
+fn diagnosis(stage: StartupStage, kind: StartupDiagnosisKind) -> StartupDiagnosis {
+    StartupDiagnosis {
+        attempt_id: "kernel-start-1".to_string(),
+        stage,
+        code: "KERNEL_START_FAILED".to_string(),
+        kind,
+        message: String::new(),
+        detail: String::new(),
+        source: "kernel.runtime.start".to_string(),
+        recoverable: true,
+        config_path: None,
+        http_status: None,
+        suggested_actions: None,
+        timestamp_ms: 0,
+    }
+}
+
+#[test]
+fn hash_config_bytes_should_be_stable_sha256_hex() {
+    assert_eq!(
+        hash_config_bytes(b"abc"),
+        "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+    );
+}
+
+#[test]
+fn upsert_should_refresh_existing_entry_and_move_it_to_front() {
+    let mut history = GoodConfigHistory::default();
+    history.upsert(snapshot("a", "/cfg.json", 1), 5);
+    history.upsert(snapshot("b", "/cfg.json", 2), 5);
+
+    let evicted = history.upsert(snapshot("a", "/cfg.json", 3), 5);
+
+    assert!(evicted.is_empty());
+    assert_eq!(history.entries.len(), 2);
+    assert_eq!(history.entries[0].hash, "a");
+    assert_eq!(history.entries[0].recorded_at, 1);
+    assert_eq!(history.entries[0].last_verified_at, 3);
+}
+
+#[test]
+fn upsert_should_evict_oldest_entries_beyond_limit() {
+    let mut history = GoodConfigHistory::default();
+    history.upsert(snapshot("a", "/cfg.json", 1), 2);
+    history.upsert(snapshot("b", "/cfg.json", 2), 2);
+
+    let evicted = history.upsert(snapshot("c", "/cfg.json", 3), 2);
+
+    assert_eq!(evicted.len(), 1);
+    assert_eq!(evicted[0].hash, "a");
+    assert_eq!(
+        history
+            .entries
+            .iter()
+            .map(|e| e.hash.as_str())
+            .collect::<Vec<_>>(),
+        vec!["c", "b"]
+    );
+}
+
+#[test]
+fn latest_for_should_match_source_path() {
+    let mut history = GoodConfigHistory::default();
+    history.upsert(snapshot("a", "/sub-a.json", 1), 5);
+    history.upsert(snapshot("b", "/sub-b.json", 2), 5);
+
+    assert_eq!(
+        history.latest_for("/sub-a.json").map(|e| e.hash.as_str()),
+        Some("a")
+    );
+    assert!(history.latest_for("/missing.json").is_none());
+}
+
+#[test]
+fn should_rollback_for_config_related_failures_only() {
+    assert!(should_rollback_for(&diagnosis(
+        StartupStage::Preflight,
+        StartupDiagnosisKind::ConfigInvalid
+    )));
+    assert!(should_rollback_for(&diagnosis(
+        StartupStage::Readiness,
+        StartupDiagnosisKind::ProcessExitedEarly
+    )));
+    assert!(!should_rollback_for(&diagnosis(
+        StartupStage::Spawn,
+        StartupDiagnosisKind::BinaryMissing
+    )));
+    assert!(!should_rollback_for(&diagnosis(
+        StartupStage::Spawn,
+        StartupDiagnosisKind::ConflictCleanupFailed
+    )));
+    assert!(!should_rollback_for(&diagnosis(
+        StartupStage::Guard,
+        StartupDiagnosisKind::ConfigInvalid
+    )));
+}
+
+#[test]
+fn history_should_default_to_auto_rollback_when_field_missing() {
+    let history: GoodConfigHistory = serde_json::from_str(r#"{"entries":[]}"#).unwrap();
+    assert!(history.auto_rollback);
+}
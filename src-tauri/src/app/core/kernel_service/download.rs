@@ -1,24 +1,37 @@
 use crate::app::core::kernel_auto_manage::auto_manage_with_saved_config;
+use crate::app::core::kernel_service::capabilities::refresh_kernel_capabilities;
+use crate::app::core::kernel_service::kernel_store::{store_kernel_binary, KernelSource};
 use crate::app::core::kernel_service::runtime::stop_kernel;
 use crate::app::core::kernel_service::status::is_kernel_running;
 use crate::app::core::kernel_service::versioning::{get_latest_kernel_version, get_system_arch};
 use crate::app::core::kernel_service::PROCESS_MANAGER;
+use crate::app::network::mirror_registry::{report_mirror_result, resolve_download_urls};
+use crate::app::network::release_digest::{
+    fetch_release_asset_digest, record_download_provenance, resolve_expected_digest,
+    verify_file_digest, DownloadProvenance,
+};
+use crate::utils::resumable_download::{download_resumable, meta_path, part_path, DownloadOptions};
 use serde_json::json;
 use std::path::Path;
 use std::time::Duration;
-use tauri::Manager;
-use tauri::{AppHandle, Emitter, WebviewWindow};
+use tauri::{AppHandle, Emitter};
 use tracing::{info, warn};
 
+const KERNEL_RELEASE_REPO: &str = "SagerNet/sing-box";
+/// 内核压缩包下载的并行连接数
+const KERNEL_DOWNLOAD_CONNECTIONS: usize = 4;
+
+/// `allow_unverified` 仅在用户确认“无法获取摘要仍继续”后由前端传入；
+/// 摘要不一致时无论如何都会失败。
 #[tauri::command]
-pub async fn download_kernel(app_handle: AppHandle, version: Option<String>) -> Result<(), String> {
+pub async fn download_kernel(
+    app_handle: AppHandle,
+    version: Option<String>,
+    allow_unverified: Option<bool>,
+) -> Result<(), String> {
     info!("开始下载内核 (指定版本: {:?})...", version);
 
-    let window = app_handle
-        .get_webview_window("main")
-        .ok_or("无法获取主窗口")?;
-
-    let _ = window.emit(
+    let _ = app_handle.emit(
         "kernel-download-progress",
         json!({
             "status": "downloading",
@@ -63,47 +76,19 @@ pub async fn download_kernel(app_handle: AppHandle, version: Option<String>) ->
         format!("sing-box-{}-linux-{}.tar.gz", version, arch)
     };
 
-    let download_urls = [
-        format!(
-            "https://v6.gh-proxy.com/https://github.com/SagerNet/sing-box/releases/download/v{}/{}",
-            version, filename
-        ),
-        format!(
-            "https://gh-proxy.com/https://github.com/SagerNet/sing-box/releases/download/v{}/{}",
-            version, filename
-        ),
-        format!(
-            "https://ghfast.top/https://github.com/SagerNet/sing-box/releases/download/v{}/{}",
-            version, filename
-        ),
-        format!(
-            "https://hub.fastgit.xyz/SagerNet/sing-box/releases/download/v{}/{}",
-            version, filename
-        ),
-        format!(
-            "https://hub.fgit.cf/SagerNet/sing-box/releases/download/v{}/{}",
-            version, filename
-        ),
-        format!(
-            "https://cdn.jsdelivr.net/gh/SagerNet/sing-box@releases/download/v{}/{}",
-            version, filename
-        ),
-        format!(
-            "https://github.com/SagerNet/sing-box/releases/download/v{}/{}",
-            version, filename
-        ),
-    ];
+    let release_url = format!(
+        "https://github.com/{}/releases/download/v{}/{}",
+        KERNEL_RELEASE_REPO, version, filename
+    );
+    // 下载源由镜像注册表按用户配置与健康度排序
+    let download_urls = resolve_download_urls(&release_url);
 
     info!("内核版本: {}", version);
     info!("平台: {}, 架构: {}", platform, arch);
     info!("文件名: {}", filename);
-    info!("主要下载 URL (v6.gh-proxy 加速): {}", download_urls[0]);
-    info!("备用下载源 1 (gh-proxy): {}", download_urls[1]);
-    info!("备用下载源 2 (ghfast.top): {}", download_urls[2]);
-    info!("备用下载源 3 (hub.fastgit.xyz): {}", download_urls[3]);
-    info!("备用下载源 4 (hub.fgit.cf): {}", download_urls[4]);
-    info!("备用下载源 5 (jsdelivr CDN): {}", download_urls[5]);
-    info!("备用下载源 6 (GitHub 原始): {}", download_urls[6]);
+    for (index, (mirror, url)) in download_urls.iter().enumerate() {
+        info!("下载源 {} ({}): {}", index + 1, mirror.name, url);
+    }
     info!("总共 {} 个下载源", download_urls.len());
 
     let work_dir = crate::utils::app_util::get_work_dir_sync();
@@ -115,9 +100,14 @@ pub async fn download_kernel(app_handle: AppHandle, version: Option<String>) ->
         return Err(format!("创建临时更新目录失败: {}", e));
     }
 
-    // 清理旧的临时目录内容（如果有）
+    // 清理旧的临时目录内容（如果有），保留同一文件未完成的下载以便续传
+    let download_path = temp_update_dir.join(&filename);
+    let resumable_files = [part_path(&download_path), meta_path(&download_path)];
     if let Ok(entries) = std::fs::read_dir(&temp_update_dir) {
         for entry in entries.flatten() {
+            if resumable_files.contains(&entry.path()) {
+                continue;
+            }
             if let Err(e) = if entry.path().is_dir() {
                 std::fs::remove_dir_all(entry.path())
             } else {
@@ -128,9 +118,19 @@ pub async fn download_kernel(app_handle: AppHandle, version: Option<String>) ->
         }
     }
 
-    let download_path = temp_update_dir.join(&filename);
+    // 一次性获取 GitHub 公布的资源摘要，之后每个镜像下载都必须与之匹配；
+    // 拿不到摘要时需用户确认才继续，由前端捕获 DIGEST_UNAVAILABLE 后重新调用
+    let digest_lookup =
+        fetch_release_asset_digest(KERNEL_RELEASE_REPO, &format!("v{}", version), &filename).await;
+    let expected_digest =
+        resolve_expected_digest(digest_lookup, allow_unverified.unwrap_or(false))?;
+    match &expected_digest {
+        Some(digest) => info!("内核资源 SHA-256: {}", digest),
+        None => warn!("未获取到内核资源摘要，按用户确认跳过校验"),
+    }
+    let mut served_by: Option<(String, String)> = None;
 
-    let _ = window.emit(
+    let _ = app_handle.emit(
         "kernel-download-progress",
         json!({
             "status": "downloading",
@@ -139,43 +139,43 @@ pub async fn download_kernel(app_handle: AppHandle, version: Option<String>) ->
         }),
     );
 
-    for (index, download_url) in download_urls.iter().enumerate() {
+    for (index, (mirror, download_url)) in download_urls.iter().enumerate() {
         info!("尝试第 {} 个下载源: {}", index + 1, download_url);
 
-        let _ = window.emit(
+        let _ = app_handle.emit(
             "kernel-download-progress",
             json!({
                 "status": "downloading",
-                "progress": 15 + (index * 5),
-                "message": format!("尝试第 {} 个下载源...", index + 1)
+                "progress": (15 + index * 5).min(60),
+                "message": format!("尝试第 {} 个下载源 ({})...", index + 1, mirror.name)
             }),
         );
 
-        match download_file(download_url, &download_path, &window).await {
-            Ok(_) => {
+        let result =
+            match download_file(download_url, &download_path, &release_url, &app_handle).await {
+                Ok(_) => verify_file_digest(&download_path, expected_digest.as_deref()),
+                Err(e) => Err(e),
+            };
+
+        match result {
+            Ok(sha256) => {
                 info!("下载成功，使用下载源: {}", download_url);
+                report_mirror_result(&app_handle, &mirror.id, true).await;
+                served_by = Some((download_url.clone(), sha256));
                 break;
             }
             Err(e) => {
-                let source_name = match index {
-                    0 => "v6.gh-proxy 镜像",
-                    1 => "gh-proxy 镜像",
-                    2 => "ghfast.top 加速",
-                    3 => "hub.fastgit.xyz",
-                    4 => "hub.fgit.cf",
-                    5 => "jsdelivr CDN",
-                    6 => "GitHub 原始",
-                    _ => "未知源",
-                };
+                report_mirror_result(&app_handle, &mirror.id, false).await;
+                let source_name = mirror.name.as_str();
 
                 let error_details = format!("{} 失败: {}", source_name, e);
                 warn!("下载源 {} 失败: {}", source_name, e);
 
-                let _ = window.emit(
+                let _ = app_handle.emit(
                     "kernel-download-progress",
                     json!({
                         "status": "downloading",
-                        "progress": 15 + (index * 5),
+                        "progress": (15 + index * 5).min(60),
                         "message": format!("?? {} - 尝试下一个下载源...", error_details)
                     }),
                 );
@@ -191,7 +191,7 @@ pub async fn download_kernel(app_handle: AppHandle, version: Option<String>) ->
                     source_name
                 );
 
-                let _ = window.emit(
+                let _ = app_handle.emit(
                     "kernel-download-progress",
                     json!({
                         "status": "error",
@@ -200,8 +200,7 @@ pub async fn download_kernel(app_handle: AppHandle, version: Option<String>) ->
                     }),
                 );
 
-                // 失败时清理
-                let _ = std::fs::remove_dir_all(&temp_update_dir);
+                // 失败时保留未完成的 .part 文件，下次下载同一版本时可续传
                 return Err(final_error);
             }
         }
@@ -212,6 +211,22 @@ pub async fn download_kernel(app_handle: AppHandle, version: Option<String>) ->
         return Err("下载的文件不存在".to_string());
     }
 
+    if let Some((mirror_url, sha256)) = served_by {
+        record_download_provenance(
+            &app_handle,
+            DownloadProvenance {
+                kind: "kernel".to_string(),
+                asset_name: filename.clone(),
+                version: version.clone(),
+                mirror_url,
+                sha256,
+                verified: expected_digest.is_some(),
+                recorded_at: chrono::Utc::now().timestamp(),
+            },
+        )
+        .await;
+    }
+
     let was_running_before_update = is_kernel_running().await.unwrap_or(false);
     if was_running_before_update {
         info!("内核更新前检测到正在运行，先尝试停止以便替换");
@@ -241,7 +256,7 @@ pub async fn download_kernel(app_handle: AppHandle, version: Option<String>) ->
         }
     }
 
-    let _ = window.emit(
+    let _ = app_handle.emit(
         "kernel-download-progress",
         json!({
             "status": "extracting",
@@ -253,7 +268,7 @@ pub async fn download_kernel(app_handle: AppHandle, version: Option<String>) ->
     // 解压到临时目录
     if let Err(e) = extract_archive(&download_path, &temp_update_dir).await {
         let error_msg = format!("解压文件失败: {}", e);
-        let _ = window.emit(
+        let _ = app_handle.emit(
             "kernel-download-progress",
             json!({
                 "status": "error",
@@ -286,6 +301,13 @@ pub async fn download_kernel(app_handle: AppHandle, version: Option<String>) ->
         }
     };
 
+    // 同时登记到多版本目录，便于之后在不同版本间切换
+    if let Err(e) =
+        store_kernel_binary(&found_executable_path, &version, KernelSource::Download).await
+    {
+        warn!("保存内核到版本目录失败: {}", e);
+    }
+
     let target_executable_path = kernel_dir.join(executable_name);
 
     info!(
@@ -333,6 +355,7 @@ pub async fn download_kernel(app_handle: AppHandle, version: Option<String>) ->
     }
 
     info!("成功部署新内核文件");
+    refresh_kernel_capabilities().await;
 
     // 清理临时目录
     if let Err(e) = std::fs::remove_dir_all(&temp_update_dir) {
@@ -369,7 +392,7 @@ pub async fn download_kernel(app_handle: AppHandle, version: Option<String>) ->
     info!("内核文件已准备就绪: {:?}", target_executable_path);
     info!("内核下载并解压完成: {:?}", target_executable_path);
 
-    let _ = window.emit(
+    let _ = app_handle.emit(
         "kernel-download-progress",
         json!({
             "status": "completed",
@@ -402,53 +425,36 @@ pub async fn download_kernel(app_handle: AppHandle, version: Option<String>) ->
 async fn download_file(
     url: &str,
     path: &Path,
-    window: &WebviewWindow,
-) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    use futures::StreamExt;
-    use tokio::fs::File;
-    use tokio::io::AsyncWriteExt;
-
+    resume_key: &str,
+    app_handle: &AppHandle,
+) -> Result<(), String> {
     let client = reqwest::Client::builder()
         .timeout(std::time::Duration::from_secs(300))
         .user_agent("sing-box-windows/1.8.2")
-        .build()?;
+        .build()
+        .map_err(|e| format!("创建 HTTP 客户端失败: {}", e))?;
 
     info!("开始下载: {}", url);
-    let response = client.get(url).send().await?;
-
-    if !response.status().is_success() {
-        return Err(format!("HTTP 错误: {}", response.status()).into());
-    }
-
-    let total_size = response.content_length().unwrap_or(0);
-    let mut downloaded = 0u64;
-    let mut file = File::create(path).await?;
-
-    let mut stream = response.bytes_stream();
-
-    while let Some(chunk) = stream.next().await {
-        let chunk = chunk?;
-        file.write_all(&chunk).await?;
-
-        downloaded += chunk.len() as u64;
-
-        if let Some(progress) = total_size
-            .checked_div(100)
-            .and_then(|scaled_total| downloaded.checked_div(scaled_total.max(1)))
-        {
-            let _ = window.emit(
-                "kernel-download-progress",
-                json!({
-                    "status": "downloading",
-                    "progress": progress.min(70),
-                    "message": format!("下载中... {}/{} bytes", downloaded, total_size)
-                }),
-            );
-        }
-    }
+    let options = DownloadOptions {
+        connections: KERNEL_DOWNLOAD_CONNECTIONS,
+        resume_key: Some(resume_key.to_string()),
+    };
 
-    file.flush().await?;
-    Ok(())
+    download_resumable(&client, url, path, &options, |progress| {
+        let _ = app_handle.emit(
+            "kernel-download-progress",
+            json!({
+                "status": "downloading",
+                "progress": progress.percent().unwrap_or(0).min(70),
+                "message": format!("下载中... {}", progress.describe()),
+                "downloaded": progress.downloaded,
+                "total": progress.total,
+                "speed": progress.speed_bps,
+                "eta": progress.eta_secs
+            }),
+        );
+    })
+    .await
 }
 
 async fn extract_archive(
@@ -1,4 +1,5 @@
 use crate::app::constants::paths;
+use crate::app::network::mirror_registry::resolve_download_urls;
 use crate::app::storage::enhanced_storage_service::{
     db_get_app_config, db_save_app_config_internal,
 };
@@ -201,6 +202,12 @@ async fn save_installed_version(app_handle: &AppHandle, version: String) -> Resu
 }
 
 async fn read_kernel_version_from_binary(kernel_path: &Path) -> Option<String> {
+    let output = read_kernel_version_output(kernel_path).await?;
+    extract_version_from_output(&output)
+}
+
+/// 执行 `sing-box version` 并返回完整输出（包含版本号、构建标签等）
+pub(super) async fn read_kernel_version_output(kernel_path: &Path) -> Option<String> {
     let mut cmd = tokio::process::Command::new(kernel_path);
     cmd.arg("version");
 
@@ -212,10 +219,10 @@ async fn read_kernel_version_from_binary(kernel_path: &Path) -> Option<String> {
         return None;
     }
 
-    extract_version_from_output(&String::from_utf8_lossy(&output.stdout))
+    Some(String::from_utf8_lossy(&output.stdout).to_string())
 }
 
-fn extract_version_from_output(output: &str) -> Option<String> {
+pub(super) fn extract_version_from_output(output: &str) -> Option<String> {
     for token in output.split_whitespace() {
         let cleaned =
             token.trim_matches(|c: char| c == ':' || c == ',' || c == ';' || c == ')' || c == '(');
@@ -252,7 +259,7 @@ fn is_embedded_newer(current: &str, embedded: &str) -> Option<bool> {
 /// 确保 metacubexd 外部 UI 已就绪。
 /// 首次启动时 sing-box 会从 GitHub 下载 metacubexd，此下载在 API 启动前执行，
 /// 可能导致稳定性校验超时。此函数在内核启动前预下载，消除该阻塞。
-const METACUBEXD_URL: &str =
+pub(crate) const METACUBEXD_URL: &str =
     "https://github.com/MetaCubeX/metacubexd/archive/refs/heads/gh-pages.zip";
 const METACUBEXD_DIR: &str = "metacubexd";
 const METACUBEXD_DOWNLOAD_TIMEOUT_SECS: u64 = 120;
@@ -268,25 +275,23 @@ pub async fn ensure_external_ui() -> Result<(), String> {
 
     info!("metacubexd UI 不存在，开始预下载: {}", METACUBEXD_URL);
 
-    let client = http_client::get_client();
-    let response = client
-        .get(METACUBEXD_URL)
-        .timeout(std::time::Duration::from_secs(METACUBEXD_DOWNLOAD_TIMEOUT_SECS))
-        .send()
-        .await
-        .map_err(|e| format!("下载 metacubexd 失败: {}", e))?;
-
-    if !response.status().is_success() {
-        return Err(format!(
-            "下载 metacubexd 失败，HTTP 状态码: {}",
-            response.status()
-        ));
+    // 按镜像注册表依次尝试，任一来源成功即可
+    let mut last_error = String::new();
+    let mut downloaded = None;
+    for (mirror, url) in resolve_download_urls(METACUBEXD_URL) {
+        match download_external_ui_archive(&url).await {
+            Ok(bytes) => {
+                info!("metacubexd 下载成功，来源: {} ({})", mirror.name, url);
+                downloaded = Some(bytes);
+                break;
+            }
+            Err(e) => {
+                warn!("通过 {} 下载 metacubexd 失败: {}", mirror.name, e);
+                last_error = e;
+            }
+        }
     }
-
-    let bytes = response
-        .bytes()
-        .await
-        .map_err(|e| format!("读取 metacubexd 响应体失败: {}", e))?;
+    let bytes = downloaded.ok_or(last_error)?;
 
     info!(
         "metacubexd 下载完成 ({} 字节)，开始解压",
@@ -322,6 +327,29 @@ pub async fn ensure_external_ui() -> Result<(), String> {
     Ok(())
 }
 
+async fn download_external_ui_archive(url: &str) -> Result<Vec<u8>, String> {
+    let client = http_client::get_client();
+    let response = client
+        .get(url)
+        .timeout(std::time::Duration::from_secs(METACUBEXD_DOWNLOAD_TIMEOUT_SECS))
+        .send()
+        .await
+        .map_err(|e| format!("下载 metacubexd 失败: {}", e))?;
+
+    if !response.status().is_success() {
+        return Err(format!(
+            "下载 metacubexd 失败，HTTP 状态码: {}",
+            response.status()
+        ));
+    }
+
+    response
+        .bytes()
+        .await
+        .map(|bytes| bytes.to_vec())
+        .map_err(|e| format!("读取 metacubexd 响应体失败: {}", e))
+}
+
 /// 将 zip 数据解压到指定目录
 fn extract_zip_to_dir(bytes: &[u8], target_dir: &Path) -> Result<(), String> {
     use std::fs;
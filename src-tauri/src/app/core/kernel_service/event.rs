@@ -1,7 +1,10 @@
+use crate::app::core::connection_stats::{reset_connection_stats, spawn_connection_stats_task};
+use crate::app::core::connection_tracker::reset_connection_tracker;
 use crate::app::core::event_relay::{
     create_connection_event_relay, create_log_event_relay, create_memory_event_relay,
     create_traffic_event_relay, start_event_relay_with_retry,
 };
+use crate::app::core::traffic_accounting::spawn_traffic_flush_task;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use tauri::{AppHandle, Emitter};
@@ -27,6 +30,8 @@ pub(super) async fn start_websocket_relay(
     // 清理完成后，必须把停止标志复位为 false，否则紧接着 spawn 的新中继任务
     // 会在 start_event_relay_with_retry 里读到 true 而立即退出，导致流量/连接/日志全部丢失。
     SHOULD_STOP_EVENTS.store(false, Ordering::Relaxed);
+    reset_connection_stats();
+    reset_connection_tracker();
 
     info!("?? 开始启动事件中继服务，端口: {}", port);
 
@@ -64,12 +69,18 @@ pub(super) async fn start_websocket_relay(
         }
     });
 
+    // 流量记账落库任务与中继同生命周期
+    let traffic_flush_task = spawn_traffic_flush_task(app_handle.clone());
+    let connection_stats_task = spawn_connection_stats_task(app_handle.clone());
+
     {
         let mut tasks = EVENT_RELAY_TASKS.lock().await;
         tasks.push(traffic_task);
         tasks.push(memory_task);
         tasks.push(log_task);
         tasks.push(connection_task);
+        tasks.push(traffic_flush_task);
+        tasks.push(connection_stats_task);
     }
 
     let _ = app_handle.emit("kernel-ready", ());
@@ -5,11 +5,12 @@ use crate::app::core::kernel_service::runtime::{
 use crate::app::core::kernel_service::state::KERNEL_STATE;
 use crate::app::core::kernel_service::status::is_kernel_running;
 use crate::app::core::kernel_service::utils::{emit_kernel_error_with_context, emit_kernel_stopped};
+use crate::app::network::connectivity_probe::{probe_and_record, ProbeVerdict};
 use crate::app::storage::enhanced_storage_service::db_get_app_config;
 use std::sync::atomic::{AtomicBool, AtomicU16, Ordering};
 use std::time::Duration;
 use std::time::Instant;
-use tauri::AppHandle;
+use tauri::{AppHandle, Emitter};
 use tokio::sync::Mutex;
 use tokio::task::JoinHandle;
 use tracing::{info, warn};
@@ -31,15 +32,8 @@ const LOG_ROTATION_INTERVAL_SECS: u64 = 6 * 60 * 60;
 struct SelfHealPolicy {
     enabled: bool,
     cooldown_secs: u64,
-}
-
-impl SelfHealPolicy {
-    fn default_policy() -> Self {
-        Self {
-            enabled: true,
-            cooldown_secs: 90,
-        }
-    }
+    /// 代理探测经由的 mixed 入站端口
+    proxy_port: u16,
 }
 
 lazy_static::lazy_static! {
@@ -51,15 +45,17 @@ lazy_static::lazy_static! {
 ///
 /// 复用原 TUN 自愈配置项（`tun_self_heal_enabled` / `tun_self_heal_cooldown_secs`），
 /// 现在适用于所有代理模式（system/manual/tun），避免引入新的配置项。
-async fn load_self_heal_policy(app_handle: &AppHandle) -> SelfHealPolicy {
+/// 读取失败时返回 `None`：不知道实际的 mixed 端口就无法可靠探测，本轮跳过。
+async fn load_self_heal_policy(app_handle: &AppHandle) -> Option<SelfHealPolicy> {
     match db_get_app_config(app_handle.clone()).await {
-        Ok(config) => SelfHealPolicy {
+        Ok(config) => Some(SelfHealPolicy {
             enabled: config.tun_self_heal_enabled,
             cooldown_secs: u64::from(config.tun_self_heal_cooldown_secs).clamp(15, 600),
-        },
+            proxy_port: config.proxy_port,
+        }),
         Err(err) => {
-            warn!("读取自愈策略失败，回退默认值: {}", err);
-            SelfHealPolicy::default_policy()
+            warn!("读取自愈策略失败，跳过本轮连通性探测: {}", err);
+            None
         }
     }
 }
@@ -200,7 +196,9 @@ fn spawn_guard_loop(app_handle: AppHandle) -> JoinHandle<()> {
             match is_kernel_running().await {
                 Ok(true) => {
                     // 所有代理模式都做连通性自愈：进程活着但假死时也能恢复。
-                    let policy = load_self_heal_policy(&app_handle).await;
+                    let Some(policy) = load_self_heal_policy(&app_handle).await else {
+                        continue;
+                    };
                     if !policy.enabled {
                         connectivity_failures = 0;
                         next_self_heal_at =
@@ -209,23 +207,32 @@ fn spawn_guard_loop(app_handle: AppHandle) -> JoinHandle<()> {
                     }
 
                     let mut should_attempt_self_heal = false;
-                    match crate::app::system::system_service::check_network_connectivity(Some(false))
-                        .await
-                    {
-                        Ok(true) => {
-                            if connectivity_failures > 0 {
-                                info!("连通性已恢复，清空失败计数");
+                    let tun_enabled = GUARDED_TUN_ENABLED.load(Ordering::Relaxed);
+                    // 多目标探测：仅当代理探测全部失败而直连成功时才判定内核假死，
+                    // 本机断网（直连也失败）不计入自愈，避免无意义的重启。
+                    match probe_and_record(&app_handle, policy.proxy_port, tun_enabled).await {
+                        Ok(round) => {
+                            let _ = app_handle.emit("connectivity-probe-result", &round);
+                            match round.verdict {
+                                ProbeVerdict::Healthy | ProbeVerdict::Degraded => {
+                                    if connectivity_failures > 0 {
+                                        info!("连通性已恢复，清空失败计数");
+                                    }
+                                    connectivity_failures = 0;
+                                }
+                                ProbeVerdict::NetworkDown => {
+                                    info!("直连与代理探测均失败，判定为本机网络异常，暂不自愈");
+                                }
+                                ProbeVerdict::KernelWedged => {
+                                    connectivity_failures = connectivity_failures.saturating_add(1);
+                                    warn!(
+                                        "代理探测失败而直连正常，计数: {}/{}",
+                                        connectivity_failures, CONNECTIVITY_FAIL_THRESHOLD
+                                    );
+                                    should_attempt_self_heal =
+                                        connectivity_failures >= CONNECTIVITY_FAIL_THRESHOLD;
+                                }
                             }
-                            connectivity_failures = 0;
-                        }
-                        Ok(false) => {
-                            connectivity_failures = connectivity_failures.saturating_add(1);
-                            warn!(
-                                "连通性检测失败，计数: {}/{}",
-                                connectivity_failures, CONNECTIVITY_FAIL_THRESHOLD
-                            );
-                            should_attempt_self_heal =
-                                connectivity_failures >= CONNECTIVITY_FAIL_THRESHOLD;
                         }
                         Err(err) => {
                             connectivity_failures = connectivity_failures.saturating_add(1);
@@ -1,5 +1,7 @@
 use crate::app::constants::paths;
 use crate::app::core::kernel_auto_manage::auto_manage_with_saved_config;
+use crate::app::core::kernel_service::capabilities::refresh_kernel_capabilities;
+use crate::app::core::kernel_service::kernel_store::{store_kernel_binary, KernelSource};
 use crate::app::core::kernel_service::runtime::stop_kernel;
 use crate::app::core::kernel_service::status::is_kernel_running;
 use crate::app::core::kernel_service::versioning::extract_clean_version;
@@ -82,32 +84,14 @@ async fn import_kernel_executable_inner(
     let staged_binary_path = stage_kernel_binary(&source_binary_path, temp_dir).await?;
     let imported_version = validate_kernel_binary(&staged_binary_path).await?;
 
-    let kernel_path = paths::get_kernel_path();
-    let was_running_before_import = is_kernel_running().await.unwrap_or(false);
-    if was_running_before_import {
-        stop_running_kernel_for_replace(app_handle).await?;
+    if let Err(e) =
+        store_kernel_binary(&staged_binary_path, &imported_version, KernelSource::Import).await
+    {
+        warn!("保存导入内核到版本目录失败: {}", e);
     }
 
-    let backup_path = replace_installed_kernel(&staged_binary_path, &kernel_path).await?;
-
-    let restarted = if was_running_before_import {
-        auto_manage_with_saved_config(app_handle, true, "kernel-manual-import").await;
-        let restarted = wait_kernel_running(Duration::from_secs(10)).await;
-
-        if !restarted {
-            if let Some(path) = backup_path.as_deref() {
-                warn!("新内核重启失败，尝试回滚到旧内核: {}", path);
-                restore_kernel_from_backup(&kernel_path, Path::new(path)).await?;
-                auto_manage_with_saved_config(app_handle, true, "kernel-manual-import-rollback")
-                    .await;
-            }
-            return Err("新内核导入成功但重启失败，已自动回滚到旧内核".to_string());
-        }
-
-        true
-    } else {
-        false
-    };
+    let (restarted, backup_path) =
+        install_staged_kernel(app_handle, &staged_binary_path, "kernel-manual-import").await?;
 
     if let Ok(mut app_config) = db_get_app_config(app_handle.clone()).await {
         app_config.installed_kernel_version = Some(imported_version.clone());
@@ -132,14 +116,49 @@ async fn import_kernel_executable_inner(
     })
 }
 
-fn now_timestamp_secs() -> u64 {
+/// 用已暂存的内核替换当前内核：运行中会先停止，替换后重启并等待就绪，失败时回滚到旧内核。
+///
+/// 返回 (是否已重启, 旧内核备份路径)。`reason` 用于区分重启来源。
+pub(super) async fn install_staged_kernel(
+    app_handle: &AppHandle,
+    staged_binary_path: &Path,
+    reason: &str,
+) -> Result<(bool, Option<String>), String> {
+    let kernel_path = paths::get_kernel_path();
+    let was_running_before_import = is_kernel_running().await.unwrap_or(false);
+    if was_running_before_import {
+        stop_running_kernel_for_replace(app_handle).await?;
+    }
+
+    let backup_path = replace_installed_kernel(staged_binary_path, &kernel_path).await?;
+    refresh_kernel_capabilities().await;
+
+    if !was_running_before_import {
+        return Ok((false, backup_path));
+    }
+
+    auto_manage_with_saved_config(app_handle, true, reason).await;
+    if !wait_kernel_running(Duration::from_secs(10)).await {
+        if let Some(path) = backup_path.as_deref() {
+            warn!("新内核重启失败，尝试回滚到旧内核: {}", path);
+            restore_kernel_from_backup(&kernel_path, Path::new(path)).await?;
+            refresh_kernel_capabilities().await;
+            auto_manage_with_saved_config(app_handle, true, &format!("{}-rollback", reason)).await;
+        }
+        return Err("新内核替换成功但重启失败，已自动回滚到旧内核".to_string());
+    }
+
+    Ok((true, backup_path))
+}
+
+pub(super) fn now_timestamp_secs() -> u64 {
     SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap_or_else(|_| Duration::from_secs(0))
         .as_secs()
 }
 
-fn kernel_executable_name() -> &'static str {
+pub(super) fn kernel_executable_name() -> &'static str {
     if cfg!(target_os = "windows") {
         "sing-box.exe"
     } else {
@@ -169,7 +188,7 @@ async fn resolve_kernel_binary_source(
     find_executable_file(&extract_dir, kernel_executable_name())
 }
 
-async fn stage_kernel_binary(
+pub(super) async fn stage_kernel_binary(
     source_binary_path: &Path,
     temp_dir: &Path,
 ) -> Result<PathBuf, String> {
@@ -181,7 +200,7 @@ async fn stage_kernel_binary(
     Ok(staged_path)
 }
 
-async fn validate_kernel_binary(binary_path: &Path) -> Result<String, String> {
+pub(super) async fn validate_kernel_binary(binary_path: &Path) -> Result<String, String> {
     let mut cmd = Command::new(binary_path);
     cmd.arg("version");
 
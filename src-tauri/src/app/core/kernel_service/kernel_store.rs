@@ -0,0 +1,315 @@
+//! 多版本内核并存与切换
+//!
+//! 每个版本存放在 `kernels/<版本>/` 下，附带 `kernel.json` 元数据（版本、来源、校验和、安装时间）。
+//! 运行时仍使用 `paths::get_kernel_path()` 处的内核，切换版本即把对应版本的二进制替换过去，
+//! 复用手动导入时的停止 / 替换 / 等待运行 / 失败回滚逻辑。
+
+use crate::app::constants::paths;
+use crate::app::core::kernel_service::import::{
+    install_staged_kernel, kernel_executable_name, now_timestamp_secs, stage_kernel_binary,
+    validate_kernel_binary, KernelImportResult,
+};
+use crate::app::network::release_digest::sha256_file;
+use crate::app::storage::enhanced_storage_service::{
+    db_get_app_config, db_save_app_config_internal,
+};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+use tracing::{info, warn};
+
+const METADATA_FILE: &str = "kernel.json";
+/// 垃圾回收默认保留的版本数（不含当前使用的版本）
+const DEFAULT_GC_KEEP: usize = 2;
+
+/// 内核来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KernelSource {
+    /// 应用内下载
+    Download,
+    /// 手动导入
+    Import,
+    /// 多版本目录启用前已安装的内核
+    Legacy,
+}
+
+/// `kernel.json` 中记录的版本信息
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KernelVersionMeta {
+    pub version: String,
+    pub source: KernelSource,
+    pub sha256: String,
+    pub installed_at: i64,
+}
+
+/// 已安装版本（供前端展示）
+#[derive(Debug, Clone, Serialize)]
+pub struct InstalledKernel {
+    #[serde(flatten)]
+    pub meta: KernelVersionMeta,
+    pub path: String,
+    pub size_bytes: u64,
+    /// 是否为当前使用的内核
+    pub active: bool,
+}
+
+/// 版本号转换为目录名：去掉前缀 `v`，非法字符替换为 `_`
+pub fn version_dir_name(version: &str) -> String {
+    let trimmed = version.trim();
+    let trimmed = trimmed.strip_prefix('v').unwrap_or(trimmed);
+    let name: String = trimmed
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '+') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if name.is_empty() || name.chars().all(|c| c == '.') {
+        "unknown".to_string()
+    } else {
+        name
+    }
+}
+
+/// 选出需要清理的版本：保留当前使用的版本以及最近安装的 `keep` 个版本
+pub fn select_gc_victims(
+    entries: &[KernelVersionMeta],
+    active_version: Option<&str>,
+    keep: usize,
+) -> Vec<String> {
+    let mut sorted: Vec<&KernelVersionMeta> = entries.iter().collect();
+    sorted.sort_by(|a, b| b.installed_at.cmp(&a.installed_at));
+
+    sorted
+        .into_iter()
+        .filter(|meta| Some(meta.version.as_str()) != active_version)
+        .skip(keep)
+        .map(|meta| meta.version.clone())
+        .collect()
+}
+
+fn version_dir(version: &str) -> PathBuf {
+    paths::get_kernel_store_dir().join(version_dir_name(version))
+}
+
+fn binary_in(dir: &Path) -> PathBuf {
+    dir.join(kernel_executable_name())
+}
+
+fn read_meta(dir: &Path) -> Option<KernelVersionMeta> {
+    let content = std::fs::read_to_string(dir.join(METADATA_FILE)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+/// 读取全部已安装版本（缺少二进制或元数据的目录会被忽略）
+fn load_installed() -> Vec<(KernelVersionMeta, PathBuf)> {
+    let Ok(entries) = std::fs::read_dir(paths::get_kernel_store_dir()) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|dir| dir.is_dir() && binary_in(dir).is_file())
+        .filter_map(|dir| read_meta(&dir).map(|meta| (meta, dir)))
+        .collect()
+}
+
+fn active_kernel_sha256() -> Option<String> {
+    let kernel_path = paths::get_kernel_path();
+    if !kernel_path.is_file() {
+        return None;
+    }
+    sha256_file(&kernel_path).ok()
+}
+
+/// 把内核二进制保存到版本目录（同版本会被覆盖），返回写入的元数据
+pub async fn store_kernel_binary(
+    binary_path: &Path,
+    version: &str,
+    source: KernelSource,
+) -> Result<KernelVersionMeta, String> {
+    let dir = version_dir(version);
+    tokio::fs::create_dir_all(&dir)
+        .await
+        .map_err(|e| format!("创建内核版本目录失败: {}", e))?;
+
+    let staged = stage_kernel_binary(binary_path, &dir).await?;
+    let meta = KernelVersionMeta {
+        version: version.trim().trim_start_matches('v').to_string(),
+        source,
+        sha256: sha256_file(&staged)?,
+        installed_at: now_timestamp_secs() as i64,
+    };
+    let content =
+        serde_json::to_string_pretty(&meta).map_err(|e| format!("序列化内核元数据失败: {}", e))?;
+    tokio::fs::write(dir.join(METADATA_FILE), content)
+        .await
+        .map_err(|e| format!("写入内核元数据失败: {}", e))?;
+
+    info!("内核 {} 已保存到版本目录: {:?}", meta.version, dir);
+    Ok(meta)
+}
+
+/// 当前内核尚未登记到版本目录时（例如升级前安装的内核），补登记一份
+async fn adopt_active_kernel(installed: &[(KernelVersionMeta, PathBuf)]) {
+    let Some(active_sha) = active_kernel_sha256() else {
+        return;
+    };
+    if installed.iter().any(|(meta, _)| meta.sha256 == active_sha) {
+        return;
+    }
+
+    let kernel_path = paths::get_kernel_path();
+    let version = match validate_kernel_binary(&kernel_path).await {
+        Ok(version) => version,
+        Err(e) => {
+            warn!("无法识别当前内核版本，跳过登记: {}", e);
+            return;
+        }
+    };
+    // 同版本目录已存在但内容不同，保留已有记录，避免覆盖
+    if version_dir(&version).join(METADATA_FILE).exists() {
+        return;
+    }
+    if let Err(e) = store_kernel_binary(&kernel_path, &version, KernelSource::Legacy).await {
+        warn!("登记当前内核到版本目录失败: {}", e);
+    }
+}
+
+async fn collect_installed() -> (Vec<(KernelVersionMeta, PathBuf)>, Option<String>) {
+    adopt_active_kernel(&load_installed()).await;
+    let installed = load_installed();
+    let active_sha = active_kernel_sha256();
+    let active_version = active_sha.and_then(|sha| {
+        installed
+            .iter()
+            .find(|(meta, _)| meta.sha256 == sha)
+            .map(|(meta, _)| meta.version.clone())
+    });
+    (installed, active_version)
+}
+
+/// 列出所有已安装的内核版本（最近安装的在前）
+#[tauri::command]
+pub async fn list_installed_kernels() -> Result<Vec<InstalledKernel>, String> {
+    let (installed, active_version) = collect_installed().await;
+    let mut kernels: Vec<InstalledKernel> = installed
+        .into_iter()
+        .map(|(meta, dir)| {
+            let binary = binary_in(&dir);
+            InstalledKernel {
+                active: Some(&meta.version) == active_version.as_ref(),
+                size_bytes: std::fs::metadata(&binary).map(|m| m.len()).unwrap_or(0),
+                path: binary.to_string_lossy().to_string(),
+                meta,
+            }
+        })
+        .collect();
+    kernels.sort_by(|a, b| b.meta.installed_at.cmp(&a.meta.installed_at));
+    Ok(kernels)
+}
+
+/// 切换到指定的已安装版本
+#[tauri::command]
+pub async fn activate_kernel_version(
+    app_handle: AppHandle,
+    version: String,
+) -> Result<KernelImportResult, String> {
+    let (installed, active_version) = collect_installed().await;
+    let target = version_dir_name(&version);
+    let (meta, dir) = installed
+        .into_iter()
+        .find(|(meta, _)| version_dir_name(&meta.version) == target)
+        .ok_or_else(|| format!("未找到已安装的内核版本: {}", version))?;
+
+    if active_version.as_deref() == Some(meta.version.as_str()) {
+        return Ok(KernelImportResult {
+            imported_version: meta.version.clone(),
+            restarted: false,
+            backup_path: None,
+            message: format!("内核 {} 已是当前版本", meta.version),
+        });
+    }
+
+    let binary = binary_in(&dir);
+    let actual_sha = sha256_file(&binary)?;
+    if actual_sha != meta.sha256 {
+        return Err(format!(
+            "内核 {} 的文件校验失败，可能已损坏，请重新下载或导入",
+            meta.version
+        ));
+    }
+
+    // 替换过程会移动暂存文件，因此先复制一份，保证版本目录中的文件不受影响
+    let temp_dir =
+        paths::get_config_dir().join(format!("kernel-activate-{}", now_timestamp_secs()));
+    tokio::fs::create_dir_all(&temp_dir)
+        .await
+        .map_err(|e| format!("创建临时目录失败: {}", e))?;
+    let result = match stage_kernel_binary(&binary, &temp_dir).await {
+        Ok(staged) => install_staged_kernel(&app_handle, &staged, "kernel-version-switch").await,
+        Err(e) => Err(e),
+    };
+    if let Err(e) = tokio::fs::remove_dir_all(&temp_dir).await {
+        warn!("清理临时切换目录失败 {:?}: {}", temp_dir, e);
+    }
+    let (restarted, backup_path) = result?;
+
+    if let Ok(mut app_config) = db_get_app_config(app_handle.clone()).await {
+        app_config.installed_kernel_version = Some(meta.version.clone());
+        if let Err(e) = db_save_app_config_internal(app_config, &app_handle).await {
+            warn!("保存切换后的内核版本失败: {}", e);
+        }
+    }
+
+    info!("已切换到内核 {}", meta.version);
+    Ok(KernelImportResult {
+        imported_version: meta.version.clone(),
+        restarted,
+        backup_path,
+        message: format!(
+            "已切换到内核 {}{}",
+            meta.version,
+            if restarted {
+                "，已自动重启内核"
+            } else {
+                ""
+            }
+        ),
+    })
+}
+
+/// 清理不再需要的内核版本，返回被删除的版本号。当前使用的版本永远不会被删除。
+#[tauri::command]
+pub async fn gc_kernel_versions(keep: Option<usize>) -> Result<Vec<String>, String> {
+    let (installed, active_version) = collect_installed().await;
+    let metas: Vec<KernelVersionMeta> = installed.iter().map(|(meta, _)| meta.clone()).collect();
+    let victims = select_gc_victims(
+        &metas,
+        active_version.as_deref(),
+        keep.unwrap_or(DEFAULT_GC_KEEP),
+    );
+
+    let mut removed = Vec::new();
+    for (meta, dir) in installed {
+        if !victims.contains(&meta.version) {
+            continue;
+        }
+        match tokio::fs::remove_dir_all(&dir).await {
+            Ok(_) => {
+                info!("已清理内核版本 {}", meta.version);
+                removed.push(meta.version);
+            }
+            Err(e) => warn!("清理内核版本 {} 失败: {}", meta.version, e),
+        }
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+#[path = "kernel_store.tests.rs"]
+mod tests;
@@ -0,0 +1,44 @@
+use super::*;
+
+fn meta(version: &str, installed_at: i64) -> KernelVersionMeta {
+    KernelVersionMeta {
+        version: version.to_string(),
+        source: KernelSource::Download,
+        sha256: format!("sha-{}", version),
+        installed_at,
+    }
+}
+
+#[test]
+fn version_dir_name_should_sanitize_version() {
+    assert_eq!(version_dir_name("v1.12.10"), "1.12.10");
+    assert_eq!(version_dir_name("1.13.0-beta.3"), "1.13.0-beta.3");
+    assert_eq!(version_dir_name("1.13.0/../x"), "1.13.0_.._x");
+    assert_eq!(version_dir_name(".."), "unknown");
+    assert_eq!(version_dir_name("  "), "unknown");
+}
+
+#[test]
+fn select_gc_victims_should_keep_active_and_newest_versions() {
+    let entries = vec![
+        meta("1.10.0", 100),
+        meta("1.11.0", 200),
+        meta("1.12.10", 300),
+        meta("1.13.0-beta.3", 400),
+    ];
+
+    let victims = select_gc_victims(&entries, Some("1.10.0"), 2);
+    assert_eq!(victims, vec!["1.11.0".to_string()]);
+
+    let victims = select_gc_victims(&entries, None, 0);
+    assert_eq!(victims.len(), 4);
+    assert!(select_gc_victims(&entries, Some("1.12.10"), 10).is_empty());
+}
+
+#[test]
+fn kernel_source_should_serialize_as_snake_case() {
+    assert_eq!(
+        serde_json::to_string(&KernelSource::Legacy).unwrap(),
+        "\"legacy\""
+    );
+}
@@ -1,4 +1,5 @@
 ﻿use crate::app::constants::common::messages;
+use crate::app::core::kernel_service::config_snapshot;
 use crate::app::core::kernel_service::event::{
     cleanup_event_relay_tasks, start_websocket_relay, SHOULD_STOP_EVENTS,
 };
@@ -15,7 +16,8 @@ use crate::app::core::proxy_service::{
     apply_os_proxy, apply_proxy_runtime_state, update_dns_strategy, write_inbounds_to_config,
     ProxyRuntimeState,
 };
-use crate::app::core::tun_profile::TunProxyOptions;
+use crate::app::core::tun_preflight::run_tun_preflight;
+use crate::app::core::tun_profile::{configured_fake_ip_ranges, TunProxyOptions};
 use crate::app::storage::enhanced_storage_service::db_get_app_config;
 use crate::utils::http_client;
 use futures::FutureExt;
@@ -81,7 +83,10 @@ fn classify_runtime_start_failure(detail: &str) -> &'static str {
     }
 }
 
-async fn verify_kernel_startup_stability(api_port: u16, proxy_port: u16) -> Result<(), String> {
+pub(super) async fn verify_kernel_startup_stability(
+    api_port: u16,
+    proxy_port: u16,
+) -> Result<(), String> {
     // stability window: detect false-positive "started then crashed" scenarios.
     // first run may need to download metacubexd UI / rule sets,
     // use exponential backoff for cold starts; success returns quickly.
@@ -207,8 +212,9 @@ pub async fn resolve_proxy_runtime_state(
     }
 
     if let Some(proxy_mode) = overrides.proxy_mode {
+        app_config.proxy_mode = proxy_mode.clone();
         match proxy_mode.as_str() {
-            "system" => {
+            "system" | "pac" => {
                 app_config.system_proxy_enabled = true;
                 app_config.tun_enabled = false;
             }
@@ -239,13 +245,18 @@ pub async fn resolve_proxy_runtime_state(
         stack: app_config.tun_stack.clone(),
         enable_ipv6: app_config.tun_enable_ipv6,
         route_exclude_address: app_config.tun_route_exclude_address.clone(),
+        route_address: app_config.tun_route_address.clone(),
+        route_presets: app_config.tun_route_presets.clone(),
         interface_name: None,
+        split_rules: app_config.tun_split_rules.clone(),
+        fake_ip_ranges: configured_fake_ip_ranges(&app_config),
     });
 
     let proxy_state = ProxyRuntimeState {
         proxy_port: app_config.proxy_port,
         allow_lan_access: app_config.allow_lan_access,
         system_proxy_enabled: app_config.system_proxy_enabled,
+        system_proxy_pac: app_config.proxy_mode == "pac",
         tun_enabled: app_config.tun_enabled,
         system_proxy_bypass: overrides
             .system_proxy_bypass
@@ -272,10 +283,66 @@ pub async fn start_kernel_with_state(
 /// `reactivate_guard` 控制是否在启动成功后调用 `enable_kernel_guard`：
 /// - 普通启动（true）：建立守护任务；
 /// - 守护/自愈调用（false）：守护循环已在运行，避免重建自身，也避免非 Send future 链。
+///
+/// 启动失败且失败与配置相关时，尝试回滚到最近一次可用配置并重试一次。
 pub(super) async fn start_kernel_impl(
     app_handle: AppHandle,
     resolved: &ResolvedProxyState,
     reactivate_guard: bool,
+) -> Result<serde_json::Value, String> {
+    let result = start_kernel_attempt(app_handle.clone(), resolved, reactivate_guard).await?;
+    if is_start_success(&result) {
+        return Ok(result);
+    }
+
+    let Some(diagnosis) = KERNEL_STATE.get_startup_diagnosis() else {
+        return Ok(result);
+    };
+    if !config_snapshot::should_rollback_for(&diagnosis) {
+        return Ok(result);
+    }
+
+    match config_snapshot::rollback_to_last_good_config(&app_handle).await {
+        Ok(Some(outcome)) if outcome.applied => {
+            info!("已回滚到最近可用配置，重新尝试启动内核");
+            let mut retry = start_kernel_attempt(app_handle, resolved, reactivate_guard).await?;
+            let retry_ok = is_start_success(&retry);
+            retry["rolled_back"] = json!(outcome);
+            retry["message"] = json!(if retry_ok {
+                "新配置启动失败，已自动回滚到最近一次可用配置并启动成功".to_string()
+            } else {
+                format!(
+                    "新配置启动失败，回滚到最近一次可用配置后仍启动失败: {}",
+                    retry["message"].as_str().unwrap_or_default()
+                )
+            });
+            Ok(retry)
+        }
+        Ok(Some(outcome)) => {
+            // 未开启自动回滚：把可回滚的快照返回给前端，由用户决定是否恢复
+            let mut result = result;
+            result["rollback_available"] = json!(outcome);
+            Ok(result)
+        }
+        Ok(None) => Ok(result),
+        Err(e) => {
+            warn!("回滚可用配置失败: {}", e);
+            Ok(result)
+        }
+    }
+}
+
+fn is_start_success(result: &serde_json::Value) -> bool {
+    result
+        .get("success")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false)
+}
+
+async fn start_kernel_attempt(
+    app_handle: AppHandle,
+    resolved: &ResolvedProxyState,
+    reactivate_guard: bool,
 ) -> Result<serde_json::Value, String> {
     let _attempt_id = KERNEL_STATE.begin_attempt("kernel-start");
     KERNEL_STATE.set_state(KernelState::Starting);
@@ -324,9 +391,40 @@ pub(super) async fn start_kernel_impl(
         warn!("更新端口配置失败: {}", e);
     }
 
+    // TUN 地址与本机网段（Docker/WSL/VPN）冲突时本次启动换用空闲网段，不回写用户设置。
+    let mut proxy_state = resolved.proxy.clone();
+    if proxy_state.tun_enabled {
+        match run_tun_preflight(&app_handle, &mut proxy_state.tun_options).await {
+            Ok(report) => {
+                for (original, replacement) in &report.replaced {
+                    warn!(
+                        "TUN 地址 {} 与本机网络冲突，本次启动改用 {}",
+                        original, replacement
+                    );
+                }
+            }
+            Err(conflict) => {
+                KERNEL_STATE.mark_failed();
+                let detail = conflict.to_string();
+                emit_kernel_error_with_context(
+                    &app_handle,
+                    "KERNEL_TUN_ADDRESS_CONFLICT",
+                    "TUN 地址与本机网络冲突",
+                    Some(&detail),
+                    Some("kernel.runtime.tun_preflight"),
+                    true,
+                );
+                return Ok(json!({
+                    "success": false,
+                    "message": detail
+                }));
+            }
+        }
+    }
+
     // 启动前仅写入 inbound 配置，不开启 OS 系统代理。
     // OS 代理在内核端口真正监听后再开启（见下方），避免代理指向尚未就绪的端口。
-    if let Err(e) = write_inbounds_to_config(&app_handle, &resolved.proxy).await {
+    if let Err(e) = write_inbounds_to_config(&app_handle, &proxy_state).await {
         KERNEL_STATE.mark_failed();
         let detail = format!("应用代理配置失败: {}", e);
         emit_kernel_error_with_context(
@@ -361,7 +459,7 @@ pub(super) async fn start_kernel_impl(
             readiness.relay_ready = true;
         });
         // 内核已在运行（端口已监听），安全地应用 OS 代理设置。
-        apply_os_proxy(&resolved.proxy);
+        apply_os_proxy(&app_handle, &resolved.proxy).await;
         if reactivate_guard {
             enable_kernel_guard(
                 app_handle.clone(),
@@ -489,9 +587,13 @@ pub(super) async fn start_kernel_impl(
                 readiness.relay_ready = false;
             });
 
+            if let Err(e) = config_snapshot::record_good_config(&app_handle, &config_path).await {
+                warn!("记录可用配置快照失败: {}", e);
+            }
+
             // 稳定性校验通过（含 proxy_port 连通校验），此时端口已就绪，
             // 安全地开启 OS 系统代理，避免代理指向尚未监听的端口。
-            apply_os_proxy(&resolved.proxy);
+            apply_os_proxy(&app_handle, &resolved.proxy).await;
 
             info!("?? 启动事件中继服务，端口: {}", resolved.api_port);
             match start_websocket_relay(app_handle.clone(), Some(resolved.api_port)).await {
@@ -760,8 +862,10 @@ pub async fn apply_proxy_settings(
     app_handle: AppHandle,
     system_proxy_enabled: Option<bool>,
     tun_enabled: Option<bool>,
+    proxy_mode: Option<String>,
 ) -> Result<serde_json::Value, String> {
     let overrides = ProxyOverrides {
+        proxy_mode,
         system_proxy_enabled,
         tun_enabled,
         ..Default::default()
@@ -830,6 +934,12 @@ pub async fn stop_kernel(app_handle: Option<&AppHandle>) -> Result<String, Strin
     disable_kernel_guard().await;
     SHOULD_STOP_EVENTS.store(true, std::sync::atomic::Ordering::Relaxed);
     cleanup_event_relay_tasks().await;
+    // 落库任务已随中继中止，补写最后一分钟内累计的流量
+    if let Some(app_handle) = app_handle {
+        if let Err(e) = crate::app::core::traffic_accounting::flush_pending(app_handle).await {
+            warn!("停止内核时写入流量统计失败: {}", e);
+        }
+    }
 
     if let Err(e) = PROCESS_MANAGER.stop(app_handle).await {
         KERNEL_STATE.mark_failed();
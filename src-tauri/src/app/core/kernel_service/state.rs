@@ -2,7 +2,7 @@
 //!
 //! 提供统一的配置类型，替代分散的 ProxyOverrides 和 AutoManageOptions。
 
-use crate::app::core::tun_profile::TunProxyOptions;
+use crate::app::core::tun_profile::{configured_fake_ip_ranges, TunProxyOptions};
 use crate::app::storage::state_model::AppConfig;
 use serde::{Deserialize, Serialize};
 use std::sync::atomic::{AtomicU16, AtomicU32, AtomicU8, Ordering};
@@ -51,6 +51,7 @@ pub enum StartupDiagnosisKind {
     SudoRequired,
     SudoInvalid,
     PortConflict,
+    TunAddressConflict,
     ProcessExitedEarly,
     ApiHttpError,
     ApiTimeout,
@@ -70,6 +71,7 @@ impl StartupDiagnosisKind {
             | StartupDiagnosisKind::PermissionDenied => 70,
             StartupDiagnosisKind::PortConflict
             | StartupDiagnosisKind::ConflictCleanupFailed => 60,
+            StartupDiagnosisKind::TunAddressConflict => 60,
             StartupDiagnosisKind::ProcessExitedEarly => 50,
             StartupDiagnosisKind::ApiHttpError | StartupDiagnosisKind::ApiTimeout => 40,
             StartupDiagnosisKind::GuardRestartFailed => 30,
@@ -443,7 +445,11 @@ impl KernelRuntimeConfig {
                 stack: config.tun_stack.clone(),
                 enable_ipv6: config.tun_enable_ipv6,
                 route_exclude_address: config.tun_route_exclude_address.clone(),
+                route_address: config.tun_route_address.clone(),
+                route_presets: config.tun_route_presets.clone(),
                 interface_name: None,
+                split_rules: config.tun_split_rules.clone(),
+                fake_ip_ranges: configured_fake_ip_ranges(config),
             }),
             system_proxy_enabled: Some(config.system_proxy_enabled),
             tun_enabled: Some(config.tun_enabled),
@@ -262,6 +262,7 @@ fn infer_kind_from_code(code: &str, details: &str) -> StartupDiagnosisKind {
         "KERNEL_PERMISSION_DENIED" => StartupDiagnosisKind::PermissionDenied,
         "KERNEL_PORT_CONFLICT" | "KERNEL_CONFLICT_DETECTED" => StartupDiagnosisKind::PortConflict,
         "KERNEL_CONFLICT_FORCE_STOP_FAILED" => StartupDiagnosisKind::ConflictCleanupFailed,
+        "KERNEL_TUN_ADDRESS_CONFLICT" => StartupDiagnosisKind::TunAddressConflict,
         "KERNEL_PROCESS_EXITED_EARLY" => StartupDiagnosisKind::ProcessExitedEarly,
         "KERNEL_API_HTTP_ERROR" => StartupDiagnosisKind::ApiHttpError,
         "KERNEL_API_TIMEOUT" => StartupDiagnosisKind::ApiTimeout,
@@ -324,6 +325,10 @@ fn build_suggested_actions(kind: StartupDiagnosisKind) -> Option<Vec<String>> {
         StartupDiagnosisKind::PortConflict | StartupDiagnosisKind::ConflictCleanupFailed => {
             vec!["修改端口或结束占用当前端口的进程".to_string()]
         }
+        StartupDiagnosisKind::TunAddressConflict => vec![
+            "在设置中修改 TUN 地址或 Fake IP 地址池".to_string(),
+            "断开占用该网段的 VPN / 虚拟网卡后重试".to_string(),
+        ],
         StartupDiagnosisKind::SudoRequired => vec!["输入系统密码后重试".to_string()],
         StartupDiagnosisKind::SudoInvalid => vec!["重新保存正确的系统密码".to_string()],
         StartupDiagnosisKind::PermissionDenied => vec!["检查权限后重试".to_string()],
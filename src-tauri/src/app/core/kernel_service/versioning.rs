@@ -1,10 +1,15 @@
 use crate::app::constants::{common::messages, paths};
+use crate::app::network::mirror_registry::fetch_github_api_json;
 use crate::app::storage::enhanced_storage_service::db_get_app_config;
 use serde::Deserialize;
 use serde_json;
 use std::process::Command;
 use tauri::AppHandle;
-use tracing::{info, warn};
+use tracing::info;
+
+const KERNEL_LATEST_RELEASE_API: &str =
+    "https://api.github.com/repos/SagerNet/sing-box/releases/latest";
+const KERNEL_RELEASES_API: &str = "https://api.github.com/repos/SagerNet/sing-box/releases";
 
 pub(super) async fn get_latest_kernel_version(
 ) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
@@ -13,118 +18,57 @@ pub(super) async fn get_latest_kernel_version(
         tag_name: String,
     }
 
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(20))
-        .user_agent("sing-box-windows/1.8.2")
-        .build()?;
-
-    let api_urls = [
-        "https://api.github.com/repos/SagerNet/sing-box/releases/latest",
-        "https://v6.gh-proxy.com/https://api.github.com/repos/SagerNet/sing-box/releases/latest",
-        "https://gh-proxy.com/https://api.github.com/repos/SagerNet/sing-box/releases/latest",
-        "https://ghfast.top/https://api.github.com/repos/SagerNet/sing-box/releases/latest",
-    ];
-
-    for (index, api_url) in api_urls.iter().enumerate() {
-        info!("尝试第 {} 个 API 源获取版本: {}", index + 1, api_url);
-
-        match client.get(*api_url).send().await {
-            Ok(response) => {
-                if response.status().is_success() {
-                    let release: GitHubRelease = response.json().await?;
-                    let tag_name = release.tag_name;
-
-                    let version = if let Some(stripped) = tag_name.strip_prefix('v') {
-                        stripped.to_string()
-                    } else {
-                        tag_name
-                    };
-
-                    info!("成功获取版本号: {} (来源: {})", version, api_url);
-                    return Ok(version);
-                } else {
-                    warn!(
-                        "API 返回错误状态: {} (来源: {})",
-                        response.status(),
-                        api_url
-                    );
-                }
-            }
-            Err(e) => {
-                warn!("API 请求失败: {} (来源: {})", e, api_url);
-            }
-        }
-    }
+    let release: GitHubRelease =
+        serde_json::from_value(fetch_github_api_json(KERNEL_LATEST_RELEASE_API).await?)?;
+    let tag_name = release.tag_name;
 
-    Err("所有 API 源都获取版本失败".into())
+    let version = if let Some(stripped) = tag_name.strip_prefix('v') {
+        stripped.to_string()
+    } else {
+        tag_name
+    };
+
+    info!("成功获取版本号: {}", version);
+    Ok(version)
 }
 
-pub(super) async fn get_kernel_releases(
-) -> Result<Vec<String>, Box<dyn std::error::Error + Send + Sync>> {
-    #[derive(Deserialize)]
-    struct GitHubRelease {
-        tag_name: String,
-        prerelease: bool,
-    }
+/// 获取 sing-box release 原始 JSON 列表，版本列表与自动更新共用
+async fn fetch_releases_json() -> Result<Vec<serde_json::Value>, String> {
+    let value = fetch_github_api_json(KERNEL_RELEASES_API).await?;
+    serde_json::from_value(value).map_err(|e| format!("解析版本列表失败: {}", e))
+}
 
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(20))
-        .user_agent("sing-box-windows/1.8.2")
-        .build()?;
-
-    let api_urls = [
-        "https://api.github.com/repos/SagerNet/sing-box/releases",
-        "https://v6.gh-proxy.com/https://api.github.com/repos/SagerNet/sing-box/releases",
-        "https://gh-proxy.com/https://api.github.com/repos/SagerNet/sing-box/releases",
-        "https://ghfast.top/https://api.github.com/repos/SagerNet/sing-box/releases",
-    ];
-
-    for (index, api_url) in api_urls.iter().enumerate() {
-        info!("尝试第 {} 个 API 源获取版本列表: {}", index + 1, api_url);
-
-        match client.get(*api_url).send().await {
-            Ok(response) => {
-                if response.status().is_success() {
-                    let releases: Vec<GitHubRelease> = response.json().await?;
-                    let versions: Vec<String> = releases
-                        .into_iter()
-                        .filter(|r| !r.prerelease) // Filter out GitHub pre-releases
-                        .map(|r| {
-                            if let Some(stripped) = r.tag_name.strip_prefix('v') {
-                                stripped.to_string()
-                            } else {
-                                r.tag_name
-                            }
-                        })
-                        .filter(|v| {
-                            let lower = v.to_lowercase();
-                            !lower.contains("rc")
-                                && !lower.contains("beta")
-                                && !lower.contains("alpha")
-                        })
-                        .collect();
-
-                    info!(
-                        "成功获取版本列表（已过滤正式版），共 {} 个版本 (来源: {})",
-                        versions.len(),
-                        api_url
-                    );
-                    return Ok(versions);
-                } else {
-                    warn!(
-                        "API 返回错误状态: {} (来源: {})",
-                        response.status(),
-                        api_url
-                    );
-                }
-            }
-            Err(e) => {
-                warn!("API 请求失败: {} (来源: {})", e, api_url);
-            }
-        }
-    }
+/// 从 release 列表中筛出正式版版本号（去掉 `v` 前缀）
+fn stable_release_versions(releases: &[serde_json::Value]) -> Vec<String> {
+    releases
+        .iter()
+        // Filter out GitHub pre-releases
+        .filter(|r| {
+            !r.get("prerelease")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false)
+        })
+        .filter_map(|r| r.get("tag_name").and_then(|v| v.as_str()))
+        .map(|tag| tag.strip_prefix('v').unwrap_or(tag).to_string())
+        .filter(|v| {
+            let lower = v.to_lowercase();
+            !lower.contains("rc") && !lower.contains("beta") && !lower.contains("alpha")
+        })
+        .collect()
+}
 
-    Err("所有 API 源都获取版本列表失败".into())
+pub(super) async fn get_kernel_releases() -> Result<Vec<String>, String> {
+    let versions = stable_release_versions(&fetch_releases_json().await?);
+    info!(
+        "成功获取版本列表（已过滤正式版），共 {} 个版本",
+        versions.len()
+    );
+    Ok(versions)
+}
+
+/// 获取 sing-box release 原始列表（含预发布版本），供自动更新按通道筛选
+pub(super) async fn get_kernel_release_list() -> Result<Vec<serde_json::Value>, String> {
+    fetch_releases_json().await
 }
 
 fn normalize_version_str(raw: &str) -> String {
@@ -399,5 +343,9 @@ pub async fn get_latest_kernel_version_cmd() -> Result<String, String> {
 
 #[tauri::command]
 pub async fn get_kernel_releases_cmd() -> Result<Vec<String>, String> {
-    get_kernel_releases().await.map_err(|e| e.to_string())
+    get_kernel_releases().await
 }
+
+#[cfg(test)]
+#[path = "versioning.tests.rs"]
+mod tests;
@@ -0,0 +1,17 @@
+use super::*;
+use serde_json::json;
+
+#[test]
+fn stable_release_versions_should_skip_prereleases() {
+    let releases = vec![
+        json!({ "tag_name": "v1.13.0-beta.1", "prerelease": true }),
+        json!({ "tag_name": "v1.12.1-rc.2", "prerelease": false }),
+        json!({ "tag_name": "v1.12.0", "prerelease": false }),
+        json!({ "tag_name": "1.11.15" }),
+        json!({ "prerelease": false }),
+    ];
+    assert_eq!(
+        stable_release_versions(&releases),
+        vec!["1.12.0".to_string(), "1.11.15".to_string()]
+    );
+}
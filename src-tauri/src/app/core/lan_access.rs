@@ -0,0 +1,428 @@
+//! 局域网访问 mixed 入站的认证与来源限制
+//!
+//! 开启“允许局域网访问”后 mixed-in 监听 `0.0.0.0`，同一网络下的任何设备都能使用代理。
+//! 这里负责：
+//! - 用户名 / 密码：首次开启时自动生成，与 sudo 密码相同方式加密保存，运行时缓存明文供配置生成使用；
+//! - 认证入站：sing-box 的入站认证对所有来源生效，开启认证时 mixed-in 收回到回环地址且不要求认证，
+//!   本机的系统代理、PAC、终端代理与探测照常使用；局域网设备改走同端口、绑定各局域网地址（IPv4 与 IPv6）
+//!   的带认证入站。地址在每次写入入站配置时重新枚举，内核运行期间地址变化（DHCP 续租、切换 Wi-Fi 等）
+//!   由 [`start_lan_address_watch_loop`] 发现后重启内核重新绑定；
+//! - 来源网段白名单：通过只作用于 mixed-in 与额外入站的 `source_ip_cidr` 反选 reject 规则实现。
+
+use crate::app::constants::config::DEFAULT_INBOUND_TAG;
+use crate::app::core::kernel_service::status::is_kernel_running;
+use crate::app::core::lan_share::lan_share_addresses;
+use crate::app::core::tun_preflight::{enumerate_interface_addresses, HostNetwork};
+use crate::app::core::tun_profile::normalize_cidr_list;
+use crate::app::storage::enhanced_storage_service::{
+    apply_runtime_config_update, db_get_app_config_internal, get_enhanced_storage,
+};
+use crate::app::storage::secret_cipher::{decrypt_secret, encrypt_secret};
+use crate::app::storage::state_model::AppConfig;
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::net::IpAddr;
+use std::sync::RwLock;
+use std::time::Duration;
+use tauri::{AppHandle, Runtime};
+use tracing::{info, warn};
+use ts_rs::TS;
+
+const LAN_AUTH_KEY: &str = "lan_proxy_auth_cipher_v1";
+const LAN_AUTH_PURPOSE: &str = "lan-auth";
+const GENERATED_USERNAME_LEN: usize = 8;
+const GENERATED_PASSWORD_LEN: usize = 20;
+/// 本机回环始终放行，否则本机系统代理也会被白名单拦截
+const LOOPBACK_CIDRS: [&str; 2] = ["127.0.0.0/8", "::1/128"];
+/// 开启认证后局域网设备接入的 mixed 入站 tag 前缀，后接监听地址
+pub const LAN_AUTH_INBOUND_TAG_PREFIX: &str = "mixed-lan-";
+const LOOPBACK_LISTEN: &str = "127.0.0.1";
+const ANY_LISTEN: &str = "0.0.0.0";
+/// 局域网地址巡检间隔
+const LAN_ADDRESS_WATCH_SECS: u64 = 30;
+
+lazy_static::lazy_static! {
+    static ref LAN_CREDENTIALS_CACHE: RwLock<Option<LanCredentials>> = RwLock::new(None);
+    /// 认证入站绑定的 (网卡名, 地址)，即最近一次写入配置的结果
+    static ref LAN_LISTEN_ADDRESSES_CACHE: RwLock<Vec<(String, IpAddr)>> = RwLock::new(Vec::new());
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../src/types/generated/LanCredentials.ts")]
+pub struct LanCredentials {
+    pub username: String,
+    pub password: String,
+}
+
+impl LanCredentials {
+    fn generate() -> Self {
+        Self {
+            username: format!(
+                "lan-{}",
+                random_token(GENERATED_USERNAME_LEN).to_lowercase()
+            ),
+            password: random_token(GENERATED_PASSWORD_LEN),
+        }
+    }
+}
+
+fn random_token(len: usize) -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(len)
+        .map(char::from)
+        .collect()
+}
+
+/// 当前配置下局域网认证入站使用的用户；未开启局域网访问或关闭认证时为 `None`
+pub fn effective_lan_credentials(app_config: &AppConfig) -> Option<LanCredentials> {
+    if !(app_config.allow_lan_access && app_config.lan_auth_enabled) {
+        return None;
+    }
+    cached_lan_credentials()
+}
+
+/// 缓存只在需要认证时有值
+fn cached_lan_credentials() -> Option<LanCredentials> {
+    LAN_CREDENTIALS_CACHE
+        .read()
+        .ok()
+        .and_then(|cached| cached.clone())
+}
+
+/// 认证入站绑定的局域网地址快照，局域网分享据此列出真正可连接的地址
+pub fn cached_lan_listen_addresses() -> Vec<(String, IpAddr)> {
+    LAN_LISTEN_ADDRESSES_CACHE
+        .read()
+        .map(|cached| cached.clone())
+        .unwrap_or_default()
+}
+
+fn cached_lan_listen_ips() -> Vec<IpAddr> {
+    cached_lan_listen_addresses()
+        .into_iter()
+        .map(|(_, ip)| ip)
+        .collect()
+}
+
+/// mixed-in 的监听地址：开启认证时只监听回环，局域网设备走 [`build_lan_auth_inbounds`]
+pub fn mixed_listen_address(
+    allow_lan_access: bool,
+    credentials: Option<&LanCredentials>,
+) -> &'static str {
+    if allow_lan_access && credentials.is_none() {
+        ANY_LISTEN
+    } else {
+        LOOPBACK_LISTEN
+    }
+}
+
+/// 开启认证时为每个局域网地址生成与 mixed-in 同端口的带认证 mixed 入站
+pub fn build_lan_auth_inbounds(
+    allow_lan_access: bool,
+    port: u16,
+    credentials: Option<&LanCredentials>,
+    addresses: &[IpAddr],
+) -> Vec<Value> {
+    let Some(credentials) = credentials.filter(|_| allow_lan_access) else {
+        return Vec::new();
+    };
+    addresses
+        .iter()
+        .map(|address| {
+            json!({
+                "type": "mixed",
+                "tag": format!("{}{}", LAN_AUTH_INBOUND_TAG_PREFIX, address),
+                "listen": address.to_string(),
+                "listen_port": port,
+                "users": [credentials],
+            })
+        })
+        .collect()
+}
+
+/// 按应用设置与缓存的局域网地址生成认证入站
+pub fn lan_auth_inbounds(app_config: &AppConfig) -> Vec<Value> {
+    build_lan_auth_inbounds(
+        app_config.allow_lan_access,
+        app_config.proxy_port,
+        effective_lan_credentials(app_config).as_ref(),
+        &cached_lan_listen_ips(),
+    )
+}
+
+pub fn is_lan_auth_inbound_tag(tag: &str) -> bool {
+    tag.starts_with(LAN_AUTH_INBOUND_TAG_PREFIX)
+}
+
+/// 用 `lan_inbounds` 替换上次生成的认证入站，放在 mixed-in 之后
+pub fn sync_lan_auth_inbounds(inbounds: &mut Vec<Value>, lan_inbounds: Vec<Value>) {
+    inbounds.retain(|inbound| {
+        !inbound
+            .get("tag")
+            .and_then(|v| v.as_str())
+            .is_some_and(is_lan_auth_inbound_tag)
+    });
+    let position = inbounds
+        .iter()
+        .position(|inbound| {
+            inbound.get("tag").and_then(|v| v.as_str()) == Some(DEFAULT_INBOUND_TAG)
+        })
+        .map_or(0, |index| index + 1);
+    inbounds.splice(position..position, lan_inbounds);
+}
+
+/// 来源不在白名单内时拒绝连接；白名单为空，或既未开启局域网访问也没有启用的额外入站时不生成
+pub fn lan_source_route_rule(app_config: &AppConfig) -> Option<Value> {
+    if app_config.lan_allowed_cidrs.is_empty() {
+        return None;
+    }
+    let mut inbound_tags: Vec<String> = Vec::new();
+    if app_config.allow_lan_access {
+        inbound_tags.push(DEFAULT_INBOUND_TAG.to_string());
+        inbound_tags.extend(lan_auth_inbounds(app_config).iter().filter_map(|inbound| {
+            inbound
+                .get("tag")
+                .and_then(|v| v.as_str())
+                .map(str::to_string)
+        }));
+    }
+    // 额外入站可以单独指定监听地址，不受局域网开关约束，始终纳入白名单
+    inbound_tags.extend(
+        app_config
+            .extra_inbounds
+            .iter()
+            .filter(|inbound| inbound.enabled && inbound.kind.is_supported())
+            .map(|inbound| inbound.tag.clone()),
+    );
+    if inbound_tags.is_empty() {
+        return None;
+    }
+
+    let mut cidrs: Vec<String> = LOOPBACK_CIDRS.iter().map(|cidr| cidr.to_string()).collect();
+    cidrs.extend(app_config.lan_allowed_cidrs.iter().cloned());
+    // invert 作用于整条规则，所以需要 logical and 把“入站匹配”单独拆出来
+    Some(json!({
+        "type": "logical",
+        "mode": "and",
+        "rules": [
+            { "inbound": inbound_tags },
+            { "source_ip_cidr": cidrs, "invert": true }
+        ],
+        "action": "reject"
+    }))
+}
+
+pub fn is_lan_source_route_rule(rule: &Value) -> bool {
+    let Some(sub_rules) = rule.get("rules").and_then(|v| v.as_array()) else {
+        return false;
+    };
+    rule.get("type").and_then(|v| v.as_str()) == Some("logical")
+        && rule.get("action").and_then(|v| v.as_str()) == Some("reject")
+        && sub_rules.len() == 2
+        && sub_rules[0].as_object().is_some_and(|first| {
+            first.len() == 1 && first.get("inbound").is_some_and(Value::is_array)
+        })
+        && sub_rules[1].get("source_ip_cidr").is_some()
+        && sub_rules[1].get("invert") == Some(&Value::Bool(true))
+}
+
+/// 移除上次生成的来源限制规则，并把新规则放到最前面，先于 sniff 生效
+pub fn sync_lan_source_route_rule(rules: &mut Vec<Value>, rule: Option<Value>) {
+    rules.retain(|existing| !is_lan_source_route_rule(existing));
+    if let Some(rule) = rule {
+        rules.insert(0, rule);
+    }
+}
+
+pub fn normalize_lan_allowed_cidrs(cidrs: Vec<String>) -> Result<Vec<String>, String> {
+    Ok(normalize_cidr_list(Some(cidrs))?.unwrap_or_default())
+}
+
+async fn load_saved_credentials<R: Runtime>(
+    app: &AppHandle<R>,
+) -> Result<Option<LanCredentials>, String> {
+    let storage = get_enhanced_storage(app).await?;
+    let cipher: Option<String> = storage
+        .get_config(LAN_AUTH_KEY)
+        .await
+        .map_err(|e| e.to_string())?;
+    let Some(cipher) = cipher else {
+        return Ok(None);
+    };
+
+    match decrypt_secret(app, LAN_AUTH_PURPOSE, &cipher)
+        .and_then(|plain| serde_json::from_str::<LanCredentials>(&plain).map_err(|e| e.to_string()))
+    {
+        Ok(credentials) => Ok(Some(credentials)),
+        Err(err) => {
+            warn!("保存的局域网代理凭据解密失败，将重新生成: {}", err);
+            let _ = storage.remove_config(LAN_AUTH_KEY).await;
+            Ok(None)
+        }
+    }
+}
+
+async fn save_credentials<R: Runtime>(
+    app: &AppHandle<R>,
+    credentials: &LanCredentials,
+) -> Result<(), String> {
+    let plain = serde_json::to_string(credentials).map_err(|e| e.to_string())?;
+    let cipher = encrypt_secret(app, LAN_AUTH_PURPOSE, &plain)?;
+    let storage = get_enhanced_storage(app).await?;
+    storage
+        .save_config(LAN_AUTH_KEY, &cipher)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+fn set_cached_credentials(credentials: Option<LanCredentials>) {
+    if let Ok(mut cached) = LAN_CREDENTIALS_CACHE.write() {
+        *cached = credentials;
+    }
+}
+
+/// 认证入站可以绑定的地址：局域网分享地址中去掉虚拟网卡。
+///
+/// VPN（utun、wg、tailscale 等）、容器与本程序的 TUN 网卡随时可能消失或重建，
+/// 绑定到不存在的地址会让内核启动失败。
+pub fn lan_listen_addresses(networks: &[HostNetwork], tun_cidrs: &[&str]) -> Vec<(String, IpAddr)> {
+    lan_share_addresses(networks, tun_cidrs)
+        .into_iter()
+        .filter(|(_, _, is_virtual)| !is_virtual)
+        .map(|(interface, ip, _)| (interface, ip))
+        .collect()
+}
+
+async fn resolve_lan_listen_addresses(app_config: &AppConfig) -> Vec<(String, IpAddr)> {
+    let networks = tokio::task::spawn_blocking(enumerate_interface_addresses)
+        .await
+        .unwrap_or_else(|e| {
+            warn!("枚举本机网络任务异常: {}", e);
+            Vec::new()
+        });
+    lan_listen_addresses(
+        &networks,
+        &[app_config.tun_ipv4.as_str(), app_config.tun_ipv6.as_str()],
+    )
+}
+
+fn set_cached_lan_listen_addresses(addresses: Vec<(String, IpAddr)>) {
+    if addresses.is_empty() {
+        warn!("未找到可用的局域网地址，局域网设备暂时无法接入代理");
+    }
+    if let Ok(mut cached) = LAN_LISTEN_ADDRESSES_CACHE.write() {
+        *cached = addresses;
+    }
+}
+
+/// 重新枚举认证入站要绑定的局域网地址；每次写入入站配置（内核启动、切换代理模式）前调用
+pub async fn refresh_lan_listen_addresses(app_config: &AppConfig) {
+    set_cached_lan_listen_addresses(resolve_lan_listen_addresses(app_config).await);
+}
+
+/// 内核运行且局域网认证生效时巡检本机地址，与已绑定的地址不一致时重启内核重新绑定
+pub async fn start_lan_address_watch_loop(app_handle: AppHandle) {
+    loop {
+        tokio::time::sleep(Duration::from_secs(LAN_ADDRESS_WATCH_SECS)).await;
+
+        let Ok(app_config) = db_get_app_config_internal(&app_handle).await else {
+            continue;
+        };
+        if effective_lan_credentials(&app_config).is_none()
+            || !is_kernel_running().await.unwrap_or(false)
+        {
+            continue;
+        }
+
+        let addresses = resolve_lan_listen_addresses(&app_config).await;
+        let previous = cached_lan_listen_addresses();
+        if addresses == previous {
+            continue;
+        }
+        info!(
+            "局域网地址变化（{:?} -> {:?}），重启内核重新绑定认证入站",
+            previous, addresses
+        );
+        set_cached_lan_listen_addresses(addresses);
+        apply_runtime_config_update(
+            &app_handle,
+            &app_config,
+            None,
+            true,
+            "lan-addresses-changed",
+        )
+        .await;
+    }
+}
+
+/// 按当前设置刷新凭据缓存：需要认证但尚未保存时自动生成。
+///
+/// 读写数据库失败时仍使用一份仅在内存中的随机凭据，保证局域网入站不会退化为无认证。
+pub async fn refresh_lan_credentials<R: Runtime>(app: &AppHandle<R>, app_config: &AppConfig) {
+    if !(app_config.allow_lan_access && app_config.lan_auth_enabled) {
+        set_cached_credentials(None);
+        return;
+    }
+
+    let credentials = match load_saved_credentials(app).await {
+        Ok(Some(credentials)) => credentials,
+        Ok(None) => {
+            let generated = LanCredentials::generate();
+            if let Err(err) = save_credentials(app, &generated).await {
+                warn!("保存局域网代理凭据失败，本次运行使用临时凭据: {}", err);
+            }
+            generated
+        }
+        Err(err) => {
+            warn!("读取局域网代理凭据失败，本次运行使用临时凭据: {}", err);
+            LanCredentials::generate()
+        }
+    };
+    set_cached_credentials(Some(credentials));
+}
+
+async fn apply_credentials_change(app: &AppHandle) -> Result<(), String> {
+    let app_config = db_get_app_config_internal(app).await?;
+    refresh_lan_credentials(app, &app_config).await;
+    apply_runtime_config_update(app, &app_config, None, false, "lan-auth-updated").await;
+    Ok(())
+}
+
+/// 查看局域网代理凭据，便于在手机等设备上填写
+#[tauri::command]
+pub async fn lan_auth_get_credentials(app: AppHandle) -> Result<Option<LanCredentials>, String> {
+    load_saved_credentials(&app).await
+}
+
+#[tauri::command]
+pub async fn lan_auth_set_credentials(
+    username: String,
+    password: String,
+    app: AppHandle,
+) -> Result<(), String> {
+    let username = username.trim().to_string();
+    if username.is_empty() || password.is_empty() {
+        return Err("用户名和密码不能为空".to_string());
+    }
+    if username.contains(':') {
+        return Err("用户名不能包含冒号".to_string());
+    }
+    save_credentials(&app, &LanCredentials { username, password }).await?;
+    apply_credentials_change(&app).await
+}
+
+/// 重新生成凭据（例如怀疑泄露时）
+#[tauri::command]
+pub async fn lan_auth_regenerate_credentials(app: AppHandle) -> Result<LanCredentials, String> {
+    let credentials = LanCredentials::generate();
+    save_credentials(&app, &credentials).await?;
+    apply_credentials_change(&app).await?;
+    Ok(credentials)
+}
+
+#[cfg(test)]
+#[path = "lan_access.tests.rs"]
+mod tests;
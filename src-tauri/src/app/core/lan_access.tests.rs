@@ -0,0 +1,196 @@
+use super::*;
+use crate::app::core::extra_inbounds::{ExtraInbound, ExtraInboundKind};
+
+fn lan_config(allowed: &[&str]) -> AppConfig {
+    AppConfig {
+        allow_lan_access: true,
+        lan_allowed_cidrs: allowed.iter().map(|cidr| cidr.to_string()).collect(),
+        ..AppConfig::default()
+    }
+}
+
+#[test]
+fn test_source_rule_only_when_lan_enabled_with_whitelist() {
+    assert!(lan_source_route_rule(&lan_config(&[])).is_none());
+
+    let mut config = lan_config(&["192.168.1.0/24"]);
+    config.allow_lan_access = false;
+    assert!(lan_source_route_rule(&config).is_none());
+
+    let rule = lan_source_route_rule(&lan_config(&["192.168.1.0/24"])).expect("rule");
+    assert!(is_lan_source_route_rule(&rule));
+    assert_eq!(rule["rules"][0], json!({ "inbound": ["mixed-in"] }));
+    assert_eq!(
+        rule["rules"][1]["source_ip_cidr"],
+        json!(["127.0.0.0/8", "::1/128", "192.168.1.0/24"])
+    );
+    assert_eq!(rule["rules"][1]["invert"], json!(true));
+}
+
+#[test]
+fn test_source_rule_covers_enabled_extra_inbounds() {
+    let extra = |tag: &str, enabled: bool| ExtraInbound {
+        kind: ExtraInboundKind::Shadowsocks,
+        tag: tag.to_string(),
+        enabled,
+        listen: Some("0.0.0.0".to_string()),
+        listen_port: 8388,
+        username: None,
+        password: None,
+        method: None,
+        uuid: None,
+    };
+    let mut config = lan_config(&["192.168.1.0/24"]);
+    config.extra_inbounds = vec![extra("extra-ss", true), extra("extra-off", false)];
+
+    let rule = lan_source_route_rule(&config).expect("rule");
+    assert!(is_lan_source_route_rule(&rule));
+    assert_eq!(
+        rule["rules"][0],
+        json!({ "inbound": ["mixed-in", "extra-ss"] })
+    );
+
+    // 未开启局域网访问时只约束额外入站
+    config.allow_lan_access = false;
+    let rule = lan_source_route_rule(&config).expect("rule");
+    assert_eq!(rule["rules"][0], json!({ "inbound": ["extra-ss"] }));
+}
+
+#[test]
+fn test_sync_source_rule_replaces_previous_and_keeps_user_rules() {
+    let user_reject =
+        json!({ "inbound": ["mixed-in"], "ip_cidr": ["10.0.0.0/8"], "action": "reject" });
+    let mut rules = vec![
+        lan_source_route_rule(&lan_config(&["10.0.0.0/8"])).unwrap(),
+        json!({ "action": "sniff" }),
+        user_reject.clone(),
+    ];
+
+    let new_rule = lan_source_route_rule(&lan_config(&["192.168.1.0/24"])).unwrap();
+    sync_lan_source_route_rule(&mut rules, Some(new_rule.clone()));
+    assert_eq!(
+        rules,
+        vec![new_rule, json!({ "action": "sniff" }), user_reject.clone()]
+    );
+
+    sync_lan_source_route_rule(&mut rules, None);
+    assert_eq!(rules, vec![json!({ "action": "sniff" }), user_reject]);
+}
+
+#[test]
+fn test_normalize_lan_allowed_cidrs() {
+    assert_eq!(
+        normalize_lan_allowed_cidrs(vec![
+            " 192.168.1.0/24 ".to_string(),
+            String::new(),
+            "192.168.1.0/24".to_string(),
+            "fd00::/8".to_string(),
+        ])
+        .unwrap(),
+        vec!["192.168.1.0/24".to_string(), "fd00::/8".to_string()]
+    );
+    assert!(normalize_lan_allowed_cidrs(vec!["192.168.1.1".to_string()]).is_err());
+}
+
+#[test]
+fn test_credentials_require_lan_access_and_auth() {
+    let mut config = lan_config(&[]);
+    config.lan_auth_enabled = false;
+    assert!(effective_lan_credentials(&config).is_none());
+    assert!(lan_auth_inbounds(&AppConfig::default()).is_empty());
+}
+
+fn credentials() -> LanCredentials {
+    LanCredentials {
+        username: "lan-user".to_string(),
+        password: "secret".to_string(),
+    }
+}
+
+#[test]
+fn test_lan_auth_keeps_loopback_mixed_in_auth_free() {
+    let credentials = credentials();
+    assert_eq!(mixed_listen_address(false, None), "127.0.0.1");
+    assert_eq!(mixed_listen_address(true, None), "0.0.0.0");
+    assert_eq!(mixed_listen_address(true, Some(&credentials)), "127.0.0.1");
+
+    let addresses = [
+        IpAddr::from([192, 168, 1, 5]),
+        "fd00::5".parse::<IpAddr>().unwrap(),
+    ];
+    let inbounds = build_lan_auth_inbounds(true, 12080, Some(&credentials), &addresses);
+    assert_eq!(
+        inbounds[0],
+        json!({
+            "type": "mixed",
+            "tag": "mixed-lan-192.168.1.5",
+            "listen": "192.168.1.5",
+            "listen_port": 12080,
+            "users": [{ "username": "lan-user", "password": "secret" }]
+        })
+    );
+    assert_eq!(inbounds[1]["tag"], json!("mixed-lan-fd00::5"));
+    assert_eq!(inbounds[1]["listen"], json!("fd00::5"));
+
+    assert!(build_lan_auth_inbounds(true, 12080, None, &addresses).is_empty());
+    assert!(build_lan_auth_inbounds(false, 12080, Some(&credentials), &addresses).is_empty());
+}
+
+#[test]
+fn test_sync_lan_auth_inbounds_replaces_previous_after_mixed_in() {
+    let mut inbounds = vec![
+        json!({ "type": "mixed", "tag": "mixed-in" }),
+        json!({ "type": "mixed", "tag": "mixed-lan-192.168.1.5" }),
+        json!({ "type": "tun", "tag": "tun-in" }),
+    ];
+    let fresh = build_lan_auth_inbounds(
+        true,
+        12080,
+        Some(&credentials()),
+        &[IpAddr::from([192, 168, 1, 9])],
+    );
+
+    sync_lan_auth_inbounds(&mut inbounds, fresh);
+    let tags: Vec<&str> = inbounds
+        .iter()
+        .filter_map(|inbound| inbound["tag"].as_str())
+        .collect();
+    assert_eq!(tags, vec!["mixed-in", "mixed-lan-192.168.1.9", "tun-in"]);
+
+    sync_lan_auth_inbounds(&mut inbounds, Vec::new());
+    assert_eq!(inbounds.len(), 2);
+}
+
+#[test]
+fn test_lan_listen_addresses_skip_virtual_interfaces_and_keep_ipv6() {
+    let host = |interface: &str, cidr: &str| HostNetwork {
+        interface: interface.to_string(),
+        cidr: cidr.to_string(),
+    };
+    let networks = [
+        host("en0", "192.168.1.23/24"),
+        host("en0", "fe80::1/64"),
+        host("en0", "2001:db8::23/64"),
+        host("utun4", "10.8.0.2/24"),
+        host("wg0", "10.9.0.2/24"),
+        host("singbox_tun", "172.19.0.1/30"),
+        host("docker0", "172.17.0.1/16"),
+    ];
+
+    assert_eq!(
+        lan_listen_addresses(&networks, &["172.19.0.1/30", "fdfe:dcba:9876::1/126"]),
+        vec![
+            ("en0".to_string(), IpAddr::from([192, 168, 1, 23])),
+            ("en0".to_string(), "2001:db8::23".parse::<IpAddr>().unwrap()),
+        ]
+    );
+}
+
+#[test]
+fn test_generated_credentials_are_random() {
+    let first = LanCredentials::generate();
+    let second = LanCredentials::generate();
+    assert!(first.username.starts_with("lan-"));
+    assert_eq!(first.password.len(), GENERATED_PASSWORD_LEN);
+    assert_ne!(first.password, second.password);
+}
@@ -0,0 +1,255 @@
+//! 局域网分享：列出本机可被局域网访问的地址，并为 mixed-in 生成客户端配置片段
+//!
+//! 开启“允许局域网访问”后，手机等设备仍需要知道本机 IP、端口与认证信息。
+//! 这里按网卡地址逐个生成 HTTP / SOCKS URI、Clash 代理条目与 sing-box 出站 JSON，
+//! 均为单行或短文本，前端可直接渲染为二维码。
+
+use crate::app::core::kernel_service::status::is_kernel_running;
+use crate::app::core::lan_access::{
+    cached_lan_listen_addresses, effective_lan_credentials, lan_listen_addresses,
+    refresh_lan_credentials, LanCredentials,
+};
+use crate::app::core::tun_preflight::{enumerate_interface_addresses, HostNetwork};
+use crate::app::core::tun_profile::{cidrs_overlap, parse_cidr};
+use crate::app::storage::enhanced_storage_service::db_get_app_config_internal;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::net::IpAddr;
+use tauri::AppHandle;
+use tracing::warn;
+use ts_rs::TS;
+
+/// 容器、虚拟机与 VPN 常见的虚拟网卡名前缀，排在物理网卡之后
+const VIRTUAL_INTERFACE_PREFIXES: &[&str] = &[
+    "docker",
+    "br-",
+    "veth",
+    "virbr",
+    "vmnet",
+    "vboxnet",
+    "vethernet",
+    "utun",
+    "tun",
+    "tap",
+    "wg",
+    "tailscale",
+    "zt",
+    "singbox",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../src/types/generated/LanShareEndpoint.ts")]
+pub struct LanShareEndpoint {
+    pub interface: String,
+    pub address: String,
+    /// 疑似虚拟网卡（容器 / 虚拟机 / VPN），手机通常无法直接访问
+    pub is_virtual: bool,
+    pub http_uri: String,
+    pub socks_uri: String,
+    /// Clash / Mihomo `proxies` 列表中的一项
+    pub clash_proxy: String,
+    /// sing-box socks 出站
+    pub singbox_outbound: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../src/types/generated/LanShareInfo.ts")]
+pub struct LanShareInfo {
+    pub port: u16,
+    pub auth_enabled: bool,
+    /// 内核未运行时片段仍可生成，但暂时无法连通
+    pub kernel_running: bool,
+    pub endpoints: Vec<LanShareEndpoint>,
+}
+
+/// 筛选可供局域网设备连接的 IPv4 / IPv6 地址，链路本地地址需要带 zone，不予列出。
+///
+/// `tun_cidrs` 为 TUN 网卡地址，其所在网段仅本机可达，需要排除。
+pub fn lan_share_addresses(
+    networks: &[HostNetwork],
+    tun_cidrs: &[&str],
+) -> Vec<(String, IpAddr, bool)> {
+    let tun_networks: Vec<(IpAddr, u8)> = tun_cidrs
+        .iter()
+        .filter_map(|cidr| parse_cidr(cidr.trim()).ok())
+        .collect();
+    let mut addresses: Vec<(String, IpAddr, bool)> = Vec::new();
+    for network in networks {
+        let address = network.cidr.split('/').next().unwrap_or_default();
+        let Ok(ip) = address.parse::<IpAddr>() else {
+            continue;
+        };
+        if !is_reachable_from_lan(ip) {
+            continue;
+        }
+        let host_prefix = if ip.is_ipv4() { 32 } else { 128 };
+        if tun_networks
+            .iter()
+            .any(|tun| cidrs_overlap(*tun, (ip, host_prefix)))
+        {
+            continue;
+        }
+        if addresses.iter().any(|(_, existing, _)| *existing == ip) {
+            continue;
+        }
+        addresses.push((
+            network.interface.clone(),
+            ip,
+            is_virtual_interface(&network.interface),
+        ));
+    }
+    // 物理网卡、IPv4、私网地址优先
+    addresses.sort_by_key(|(_, ip, is_virtual)| (*is_virtual, ip.is_ipv6(), !is_private(*ip)));
+    addresses
+}
+
+fn is_reachable_from_lan(ip: IpAddr) -> bool {
+    if ip.is_loopback() || ip.is_unspecified() || ip.is_multicast() {
+        return false;
+    }
+    match ip {
+        IpAddr::V4(ip) => !ip.is_link_local(),
+        IpAddr::V6(ip) => (ip.segments()[0] & 0xffc0) != 0xfe80,
+    }
+}
+
+/// IPv4 私网地址与 IPv6 唯一本地地址（fc00::/7）
+fn is_private(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => ip.is_private(),
+        IpAddr::V6(ip) => (ip.segments()[0] & 0xfe00) == 0xfc00,
+    }
+}
+
+fn is_virtual_interface(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    VIRTUAL_INTERFACE_PREFIXES
+        .iter()
+        .any(|prefix| name.starts_with(prefix))
+}
+
+fn uri_userinfo(credentials: Option<&LanCredentials>) -> String {
+    credentials
+        .map(|credentials| {
+            format!(
+                "{}:{}@",
+                urlencoding::encode(&credentials.username),
+                urlencoding::encode(&credentials.password)
+            )
+        })
+        .unwrap_or_default()
+}
+
+/// 为单个地址生成各格式的客户端配置
+pub fn build_lan_share_endpoint(
+    interface: &str,
+    ip: IpAddr,
+    is_virtual: bool,
+    port: u16,
+    credentials: Option<&LanCredentials>,
+) -> LanShareEndpoint {
+    let userinfo = uri_userinfo(credentials);
+    let name = format!("sing-box-lan-{}", ip);
+    let uri_host = match ip {
+        IpAddr::V4(ip) => ip.to_string(),
+        IpAddr::V6(ip) => format!("[{}]", ip),
+    };
+
+    let mut clash_proxy = json!({
+        "name": name,
+        "type": "socks5",
+        "server": ip.to_string(),
+        "port": port,
+        "udp": true,
+    });
+    let mut singbox_outbound = json!({
+        "type": "socks",
+        "tag": name,
+        "server": ip.to_string(),
+        "server_port": port,
+        "version": "5",
+    });
+    if let Some(credentials) = credentials {
+        for value in [&mut clash_proxy, &mut singbox_outbound] {
+            value["username"] = json!(credentials.username);
+            value["password"] = json!(credentials.password);
+        }
+    }
+
+    LanShareEndpoint {
+        interface: interface.to_string(),
+        address: ip.to_string(),
+        is_virtual,
+        http_uri: format!("http://{}{}:{}", userinfo, uri_host, port),
+        socks_uri: format!("socks5://{}{}:{}", userinfo, uri_host, port),
+        clash_proxy: serde_yaml::to_string(&vec![clash_proxy]).unwrap_or_default(),
+        singbox_outbound: singbox_outbound.to_string(),
+    }
+}
+
+/// 获取局域网分享信息；未开启“允许局域网访问”时返回错误
+#[tauri::command]
+pub async fn lan_share_get_info(app: AppHandle) -> Result<LanShareInfo, String> {
+    let app_config = db_get_app_config_internal(&app).await?;
+    if !app_config.allow_lan_access {
+        return Err("请先开启“允许局域网访问”".to_string());
+    }
+
+    let mut credentials = effective_lan_credentials(&app_config);
+    if app_config.lan_auth_enabled && credentials.is_none() {
+        refresh_lan_credentials(&app, &app_config).await;
+        credentials = effective_lan_credentials(&app_config);
+    }
+
+    let kernel_running = is_kernel_running().await.unwrap_or(false);
+    // 开启认证时局域网设备只能连到认证入站绑定的地址：内核运行中按已绑定的地址列出，
+    // 否则按下次启动时会绑定的地址列出；不认证时 mixed-in 监听 `0.0.0.0`，只接受 IPv4
+    let addresses: Vec<(String, IpAddr, bool)> = if credentials.is_some() && kernel_running {
+        cached_lan_listen_addresses()
+            .into_iter()
+            .map(|(interface, ip)| (interface, ip, false))
+            .collect()
+    } else {
+        let networks = tokio::task::spawn_blocking(enumerate_interface_addresses)
+            .await
+            .unwrap_or_else(|e| {
+                warn!("枚举本机网络任务异常: {}", e);
+                Vec::new()
+            });
+        let tun_cidrs = [app_config.tun_ipv4.as_str(), app_config.tun_ipv6.as_str()];
+        if credentials.is_some() {
+            lan_listen_addresses(&networks, &tun_cidrs)
+                .into_iter()
+                .map(|(interface, ip)| (interface, ip, false))
+                .collect()
+        } else {
+            lan_share_addresses(&networks, &tun_cidrs)
+                .into_iter()
+                .filter(|(_, ip, _)| ip.is_ipv4())
+                .collect()
+        }
+    };
+    let endpoints = addresses
+        .into_iter()
+        .map(|(interface, ip, is_virtual)| {
+            build_lan_share_endpoint(
+                &interface,
+                ip,
+                is_virtual,
+                app_config.proxy_port,
+                credentials.as_ref(),
+            )
+        })
+        .collect();
+
+    Ok(LanShareInfo {
+        port: app_config.proxy_port,
+        auth_enabled: credentials.is_some(),
+        kernel_running,
+        endpoints,
+    })
+}
+
+#[cfg(test)]
+#[path = "lan_share.tests.rs"]
+mod tests;
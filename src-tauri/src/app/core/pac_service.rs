@@ -0,0 +1,496 @@
+//! PAC（代理自动配置）模式
+//!
+//! 由绕过列表、用户自定义的直连规则和国内域名列表生成 PAC 脚本，
+//! 通过仅监听本机的 HTTP 端点提供给系统，再把系统的自动配置地址指向该端点。
+//! 与全局系统代理相比，不支持全局代理的应用仍可按 PAC 判定直连。
+//!
+//! 端点端口首次随机分配后持久化，之后每次启动复用同一端口，
+//! 系统中残留的 PAC 地址在应用重启后依然有效。
+//!
+//! 国内域名取自 geosite-cn 的纯文本版本，经下载镜像拉取后缓存在工作目录并定期刷新。
+//! 未命中直连规则的请求只交给本机代理端口，内核未运行时不会悄悄改为直连。
+
+use crate::app::constants::network_config;
+use crate::app::storage::custom_rule::{
+    CustomRule, CustomRuleAction, CustomRuleMatchType, STORAGE_KEY,
+};
+use crate::app::storage::enhanced_storage_service::get_enhanced_storage;
+use crate::utils::app_util::get_work_dir_sync;
+use crate::utils::file_util::download_with_fallback;
+use crate::utils::proxy_util::parse_bypass_entries;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tauri::AppHandle;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::OnceCell;
+use tracing::{info, warn};
+
+const PAC_PATH: &str = "/proxy.pac";
+const MAX_REQUEST_BYTES: usize = 8 * 1024;
+/// generic_config 中保存 PAC 服务端口所用的 key
+const PAC_PORT_STORAGE_KEY: &str = "pac_server_port";
+
+/// geosite-cn 的纯文本版本（Loyalsoldier/v2ray-rules-dat 的 direct-list 与其 geosite:cn 一致）。
+/// sing-geosite 的 `.srs` 为二进制格式，无法直接写进 PAC。
+const CN_DOMAIN_LIST_URL: &str =
+    "https://github.com/Loyalsoldier/v2ray-rules-dat/releases/latest/download/direct-list.txt";
+const CN_DOMAIN_LIST_FILE: &str = "pac-cn-direct-list.txt";
+/// 本地列表超过该时长后在后台刷新
+const CN_DOMAIN_LIST_MAX_AGE: Duration = Duration::from_secs(7 * 24 * 3600);
+/// 列表尚未下载成功时只直连 `.cn`；其余国内流量进入内核后仍由 geosite-cn 路由规则直连
+const FALLBACK_CN_SUFFIXES: &[&str] = &["cn"];
+
+lazy_static::lazy_static! {
+    static ref PAC_SCRIPT: RwLock<String> = RwLock::new(String::new());
+    static ref CN_DOMAINS: RwLock<Option<Arc<CnDomainList>>> = RwLock::new(None);
+}
+
+static CN_DOMAIN_LIST_REFRESHING: AtomicBool = AtomicBool::new(false);
+
+static PAC_SERVER_PORT: OnceCell<u16> = OnceCell::const_new();
+
+/// 国内直连域名：后缀匹配与完整匹配
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CnDomainList {
+    pub suffixes: Vec<String>,
+    pub domains: Vec<String>,
+}
+
+impl CnDomainList {
+    pub fn fallback() -> Self {
+        Self {
+            suffixes: FALLBACK_CN_SUFFIXES.iter().map(|s| s.to_string()).collect(),
+            domains: Vec::new(),
+        }
+    }
+
+    /// 解析 geosite 纯文本列表：普通行与 `domain:` 为后缀，`full:` 为完整域名，
+    /// `keyword:` / `regexp:` 无法高效写进 PAC，跳过
+    pub fn parse(content: &str) -> Self {
+        let mut list = Self::default();
+        for line in content.lines() {
+            // 去掉行尾属性（`example.cn @ads` / `example.cn:@ads`）
+            let entry = line.split_whitespace().next().unwrap_or("");
+            let entry = entry.split(":@").next().unwrap_or(entry);
+            if entry.is_empty() || entry.starts_with('#') {
+                continue;
+            }
+            let (target, value) = match entry.split_once(':') {
+                Some(("full", value)) => (&mut list.domains, value),
+                Some(("domain", value)) => (&mut list.suffixes, value),
+                Some(_) => continue,
+                None => (&mut list.suffixes, entry),
+            };
+            let value = value.trim_start_matches('.').to_ascii_lowercase();
+            let valid = !value.is_empty()
+                && value
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'));
+            if valid {
+                target.push(value);
+            }
+        }
+        list
+    }
+}
+
+/// PAC 直连规则
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PacDirectRules {
+    /// `shExpMatch` 通配模式（如 `localhost`、`*.example.com`、`192.168.*`）
+    pub patterns: Vec<String>,
+    pub domains: Vec<String>,
+    pub suffixes: Vec<String>,
+    pub keywords: Vec<String>,
+    /// IPv4 网段 (网络地址, 掩码)
+    pub networks: Vec<(String, String)>,
+}
+
+impl PacDirectRules {
+    /// 汇总绕过列表、自定义直连规则与国内域名
+    pub fn collect(bypass: &str, custom_rules: &[CustomRule], cn_domains: &CnDomainList) -> Self {
+        let mut rules = Self::default();
+        let bypass = if bypass.trim().is_empty() {
+            None
+        } else {
+            Some(bypass)
+        };
+        for entry in parse_bypass_entries(bypass) {
+            // `<local>` 是 Windows 的写法，PAC 中由 isPlainHostName 处理
+            if entry == "<local>" {
+                continue;
+            }
+            match ipv4_cidr_to_mask(&entry) {
+                Some(network) => rules.networks.push(network),
+                None => rules.patterns.push(entry.to_ascii_lowercase()),
+            }
+        }
+
+        for rule in custom_rules
+            .iter()
+            .filter(|rule| rule.enabled && rule.action == CustomRuleAction::Direct)
+        {
+            let values = rule
+                .payload
+                .split([',', '\n'])
+                .map(|value| value.trim().to_ascii_lowercase())
+                .filter(|value| !value.is_empty());
+            match rule.match_type {
+                CustomRuleMatchType::Domain => rules.domains.extend(values),
+                CustomRuleMatchType::DomainSuffix => rules
+                    .suffixes
+                    .extend(values.map(|value| value.trim_start_matches('.').to_string())),
+                CustomRuleMatchType::DomainKeyword => rules.keywords.extend(values),
+                CustomRuleMatchType::IpCidr => {
+                    rules
+                        .networks
+                        .extend(values.filter_map(|value| ipv4_cidr_to_mask(&value)));
+                }
+            }
+        }
+
+        rules.suffixes.extend(cn_domains.suffixes.iter().cloned());
+        rules.domains.extend(cn_domains.domains.iter().cloned());
+        rules
+    }
+}
+
+/// `10.0.0.0/8` 转换为 (`10.0.0.0`, `255.0.0.0`)；非 IPv4 CIDR 返回 `None`
+pub fn ipv4_cidr_to_mask(cidr: &str) -> Option<(String, String)> {
+    let (address, prefix) = cidr.split_once('/')?;
+    let address: std::net::Ipv4Addr = address.trim().parse().ok()?;
+    let prefix: u32 = prefix.trim().parse().ok()?;
+    if prefix > 32 {
+        return None;
+    }
+    let mask = if prefix == 0 {
+        0
+    } else {
+        u32::MAX << (32 - prefix)
+    };
+    let network = std::net::Ipv4Addr::from(u32::from(address) & mask);
+    Some((
+        network.to_string(),
+        std::net::Ipv4Addr::from(mask).to_string(),
+    ))
+}
+
+fn js_string_array(values: &[String]) -> String {
+    serde_json::to_string(values).unwrap_or_else(|_| "[]".to_string())
+}
+
+/// 域名集合写成对象字面量，国内列表有数万条，逐条遍历数组过慢
+fn js_string_set(values: &[String]) -> String {
+    let set: serde_json::Map<String, serde_json::Value> = values
+        .iter()
+        .map(|value| (value.clone(), serde_json::Value::from(1)))
+        .collect();
+    serde_json::to_string(&set).unwrap_or_else(|_| "{}".to_string())
+}
+
+/// 生成 PAC 脚本；未命中直连规则的请求只走 `proxy_host:proxy_port`，
+/// 不追加 `DIRECT` 兜底，避免内核停止时流量在用户不知情的情况下直连
+pub fn build_pac_script(proxy_host: &str, proxy_port: u16, rules: &PacDirectRules) -> String {
+    let networks: Vec<String> = rules
+        .networks
+        .iter()
+        .map(|(network, mask)| format!("[\"{}\", \"{}\"]", network, mask))
+        .collect();
+    format!(
+        r#"// 由应用自动生成，请勿手动修改
+var proxy = "PROXY {host}:{port}; SOCKS5 {host}:{port}";
+var patterns = {patterns};
+var domains = {domains};
+var suffixes = {suffixes};
+var keywords = {keywords};
+var networks = [{networks}];
+
+function hasKey(set, key) {{
+  return Object.prototype.hasOwnProperty.call(set, key);
+}}
+
+function matchesSuffix(host) {{
+  var name = host;
+  while (true) {{
+    if (hasKey(suffixes, name)) return true;
+    var dot = name.indexOf(".");
+    if (dot < 0) return false;
+    name = name.substring(dot + 1);
+  }}
+}}
+
+function FindProxyForURL(url, host) {{
+  host = host.toLowerCase();
+  if (isPlainHostName(host)) return "DIRECT";
+  var i;
+  for (i = 0; i < patterns.length; i++) {{
+    if (shExpMatch(host, patterns[i])) return "DIRECT";
+  }}
+  if (hasKey(domains, host) || matchesSuffix(host)) return "DIRECT";
+  for (i = 0; i < keywords.length; i++) {{
+    if (host.indexOf(keywords[i]) >= 0) return "DIRECT";
+  }}
+  // 仅对 IP 字面量匹配网段，避免为每个域名触发 DNS 解析
+  if (/^\d+\.\d+\.\d+\.\d+$/.test(host)) {{
+    for (i = 0; i < networks.length; i++) {{
+      if (isInNet(host, networks[i][0], networks[i][1])) return "DIRECT";
+    }}
+  }}
+  return proxy;
+}}
+"#,
+        host = proxy_host,
+        port = proxy_port,
+        patterns = js_string_array(&rules.patterns),
+        domains = js_string_set(&rules.domains),
+        suffixes = js_string_set(&rules.suffixes),
+        keywords = js_string_array(&rules.keywords),
+        networks = networks.join(", "),
+    )
+}
+
+fn current_script() -> String {
+    PAC_SCRIPT
+        .read()
+        .map(|script| script.clone())
+        .unwrap_or_default()
+}
+
+fn build_response(request: &str) -> Vec<u8> {
+    let path = request
+        .lines()
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .unwrap_or("/");
+    let path = path.split('?').next().unwrap_or(path);
+    let (status, content_type, body) = if path == PAC_PATH {
+        (
+            "200 OK",
+            "application/x-ns-proxy-autoconfig",
+            current_script(),
+        )
+    } else {
+        ("404 Not Found", "text/plain", "Not Found".to_string())
+    };
+    format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-cache\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )
+    .into_bytes()
+}
+
+async fn handle_connection(mut stream: TcpStream) {
+    let mut buffer = Vec::with_capacity(1024);
+    let mut chunk = [0u8; 1024];
+    loop {
+        match stream.read(&mut chunk).await {
+            Ok(0) => break,
+            Ok(n) => {
+                buffer.extend_from_slice(&chunk[..n]);
+                if buffer.windows(4).any(|w| w == b"\r\n\r\n") || buffer.len() > MAX_REQUEST_BYTES {
+                    break;
+                }
+            }
+            Err(_) => return,
+        }
+    }
+    let response = build_response(&String::from_utf8_lossy(&buffer));
+    let _ = stream.write_all(&response).await;
+    let _ = stream.shutdown().await;
+}
+
+async fn load_saved_pac_port(app_handle: &AppHandle) -> Option<u16> {
+    let storage = get_enhanced_storage(app_handle).await.ok()?;
+    storage
+        .load_generic_config::<u16>(PAC_PORT_STORAGE_KEY)
+        .await
+        .ok()
+        .flatten()
+        .filter(|port| *port != 0)
+}
+
+/// 优先绑定上次保存的端口；被占用时改用新端口并保存，系统 PAC 地址随后由调用方更新
+async fn bind_pac_listener(app_handle: &AppHandle) -> Result<TcpListener, String> {
+    let host = network_config::DEFAULT_CLASH_API_ADDRESS;
+    let saved_port = load_saved_pac_port(app_handle).await;
+    if let Some(port) = saved_port {
+        match TcpListener::bind((host, port)).await {
+            Ok(listener) => return Ok(listener),
+            Err(e) => warn!("PAC 服务端口 {} 不可用，改用新端口: {}", port, e),
+        }
+    }
+
+    let listener = TcpListener::bind((host, 0))
+        .await
+        .map_err(|e| format!("启动 PAC 服务失败: {}", e))?;
+    if let Ok(addr) = listener.local_addr() {
+        if saved_port != Some(addr.port()) {
+            match get_enhanced_storage(app_handle).await {
+                Ok(storage) => {
+                    if let Err(e) = storage
+                        .save_generic_config(PAC_PORT_STORAGE_KEY, &addr.port())
+                        .await
+                    {
+                        warn!("保存 PAC 服务端口失败: {}", e);
+                    }
+                }
+                Err(e) => warn!("保存 PAC 服务端口失败: {}", e),
+            }
+        }
+    }
+    Ok(listener)
+}
+
+/// 启动本机 PAC 服务（整个应用生命周期只启动一次），返回监听端口
+async fn ensure_pac_server(app_handle: &AppHandle) -> Result<u16, String> {
+    PAC_SERVER_PORT
+        .get_or_try_init(|| async {
+            let listener = bind_pac_listener(app_handle).await?;
+            let port = listener
+                .local_addr()
+                .map_err(|e| format!("读取 PAC 服务端口失败: {}", e))?
+                .port();
+            tauri::async_runtime::spawn(async move {
+                loop {
+                    match listener.accept().await {
+                        Ok((stream, _)) => {
+                            tauri::async_runtime::spawn(handle_connection(stream));
+                        }
+                        Err(e) => warn!("PAC 服务接受连接失败: {}", e),
+                    }
+                }
+            });
+            info!("PAC 服务已启动，端口 {}", port);
+            Ok(port)
+        })
+        .await
+        .copied()
+}
+
+async fn load_custom_rules(app_handle: &AppHandle) -> Vec<CustomRule> {
+    let Ok(storage) = get_enhanced_storage(app_handle).await else {
+        return Vec::new();
+    };
+    storage
+        .load_generic_config::<Vec<CustomRule>>(STORAGE_KEY)
+        .await
+        .ok()
+        .flatten()
+        .unwrap_or_default()
+}
+
+fn cn_domain_list_path() -> PathBuf {
+    PathBuf::from(get_work_dir_sync()).join(CN_DOMAIN_LIST_FILE)
+}
+
+/// 当前的国内域名列表：优先内存缓存，其次工作目录中已下载的列表，最后是兜底列表
+fn current_cn_domains() -> Arc<CnDomainList> {
+    if let Some(list) = CN_DOMAINS.read().ok().and_then(|cache| cache.clone()) {
+        return list;
+    }
+    let list = std::fs::read_to_string(cn_domain_list_path())
+        .ok()
+        .map(|content| CnDomainList::parse(&content))
+        .filter(|list| !list.suffixes.is_empty())
+        .unwrap_or_else(CnDomainList::fallback);
+    let list = Arc::new(list);
+    if let Ok(mut cache) = CN_DOMAINS.write() {
+        *cache = Some(list.clone());
+    }
+    list
+}
+
+fn cn_domain_list_is_stale() -> bool {
+    std::fs::metadata(cn_domain_list_path())
+        .and_then(|meta| meta.modified())
+        .ok()
+        .and_then(|modified| modified.elapsed().ok())
+        .map_or(true, |age| age > CN_DOMAIN_LIST_MAX_AGE)
+}
+
+/// 经下载镜像拉取国内域名列表，校验非空后替换本地文件与缓存
+async fn download_cn_domain_list() -> Result<(), String> {
+    let path = cn_domain_list_path();
+    let temp_path = path.with_extension("txt.download");
+    let temp = temp_path.to_string_lossy().to_string();
+    download_with_fallback(CN_DOMAIN_LIST_URL, &temp, |_| {}).await?;
+
+    let content =
+        std::fs::read_to_string(&temp_path).map_err(|e| format!("读取国内域名列表失败: {}", e))?;
+    let list = CnDomainList::parse(&content);
+    if list.suffixes.is_empty() {
+        let _ = std::fs::remove_file(&temp_path);
+        return Err("下载的国内域名列表为空".to_string());
+    }
+    std::fs::rename(&temp_path, &path).map_err(|e| format!("保存国内域名列表失败: {}", e))?;
+
+    info!(
+        "国内域名列表已更新：{} 条后缀，{} 条完整域名",
+        list.suffixes.len(),
+        list.domains.len()
+    );
+    if let Ok(mut cache) = CN_DOMAINS.write() {
+        *cache = Some(Arc::new(list));
+    }
+    Ok(())
+}
+
+async fn rebuild_script(app_handle: &AppHandle, proxy_port: u16, bypass: &str) {
+    let rules = PacDirectRules::collect(
+        bypass,
+        &load_custom_rules(app_handle).await,
+        &current_cn_domains(),
+    );
+    let script = build_pac_script(
+        network_config::DEFAULT_CLASH_API_ADDRESS,
+        proxy_port,
+        &rules,
+    );
+    if let Ok(mut current) = PAC_SCRIPT.write() {
+        *current = script;
+    }
+}
+
+/// 本地列表缺失或过期时在后台刷新，成功后重新生成 PAC 脚本
+fn spawn_cn_domain_list_refresh(app_handle: &AppHandle, proxy_port: u16, bypass: &str) {
+    if !cn_domain_list_is_stale() || CN_DOMAIN_LIST_REFRESHING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let app_handle = app_handle.clone();
+    let bypass = bypass.to_string();
+    tauri::async_runtime::spawn(async move {
+        match download_cn_domain_list().await {
+            Ok(()) => rebuild_script(&app_handle, proxy_port, &bypass).await,
+            Err(e) => warn!("更新国内域名列表失败，继续使用现有列表: {}", e),
+        }
+        CN_DOMAIN_LIST_REFRESHING.store(false, Ordering::SeqCst);
+    });
+}
+
+/// 按当前规则重新生成 PAC 脚本并确保服务已启动，返回供系统使用的 PAC 地址
+pub async fn publish_pac(
+    app_handle: &AppHandle,
+    proxy_port: u16,
+    bypass: &str,
+) -> Result<String, String> {
+    rebuild_script(app_handle, proxy_port, bypass).await;
+    spawn_cn_domain_list_refresh(app_handle, proxy_port, bypass);
+
+    let port = ensure_pac_server(app_handle).await?;
+    Ok(format!(
+        "http://{}:{}{}",
+        network_config::DEFAULT_CLASH_API_ADDRESS,
+        port,
+        PAC_PATH
+    ))
+}
+
+#[cfg(test)]
+#[path = "pac_service.tests.rs"]
+mod tests;
@@ -0,0 +1,145 @@
+use super::*;
+use chrono::Utc;
+
+fn rule(match_type: CustomRuleMatchType, action: CustomRuleAction, payload: &str) -> CustomRule {
+    CustomRule {
+        id: "test".to_string(),
+        enabled: true,
+        match_type,
+        payload: payload.to_string(),
+        action,
+        outbound: None,
+        note: None,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    }
+}
+
+#[test]
+fn ipv4_cidr_to_mask_should_normalize_network_and_mask() {
+    assert_eq!(
+        ipv4_cidr_to_mask("192.168.1.7/16"),
+        Some(("192.168.0.0".to_string(), "255.255.0.0".to_string()))
+    );
+    assert_eq!(
+        ipv4_cidr_to_mask("0.0.0.0/0"),
+        Some(("0.0.0.0".to_string(), "0.0.0.0".to_string()))
+    );
+    assert_eq!(ipv4_cidr_to_mask("fd00::/8"), None);
+    assert_eq!(ipv4_cidr_to_mask("10.0.0.0/33"), None);
+    assert_eq!(ipv4_cidr_to_mask("localhost"), None);
+}
+
+#[test]
+fn collect_should_merge_bypass_custom_direct_rules_and_cn_domains() {
+    let mut disabled = rule(
+        CustomRuleMatchType::Domain,
+        CustomRuleAction::Direct,
+        "disabled.example.com",
+    );
+    disabled.enabled = false;
+    let custom_rules = vec![
+        rule(
+            CustomRuleMatchType::DomainSuffix,
+            CustomRuleAction::Direct,
+            ".corp.example, Intranet.local",
+        ),
+        rule(
+            CustomRuleMatchType::DomainKeyword,
+            CustomRuleAction::Direct,
+            "mirror",
+        ),
+        rule(
+            CustomRuleMatchType::IpCidr,
+            CustomRuleAction::Direct,
+            "100.64.0.0/10\nfd00::/8",
+        ),
+        rule(
+            CustomRuleMatchType::Domain,
+            CustomRuleAction::Proxy,
+            "proxied.example.com",
+        ),
+        disabled,
+    ];
+
+    let cn_domains = CnDomainList {
+        suffixes: vec!["cn".to_string(), "baidu.com".to_string()],
+        domains: vec!["www.example.cn".to_string()],
+    };
+    let rules = PacDirectRules::collect(
+        "localhost;<local>;10.0.0.0/8;*.lan",
+        &custom_rules,
+        &cn_domains,
+    );
+    assert_eq!(rules.patterns, vec!["localhost", "*.lan"]);
+    assert_eq!(rules.domains, vec!["www.example.cn"]);
+    assert_eq!(
+        rules.suffixes,
+        vec!["corp.example", "intranet.local", "cn", "baidu.com"]
+    );
+    assert_eq!(rules.keywords, vec!["mirror"]);
+    assert_eq!(
+        rules.networks,
+        vec![
+            ("10.0.0.0".to_string(), "255.0.0.0".to_string()),
+            ("100.64.0.0".to_string(), "255.192.0.0".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn collect_should_fall_back_to_default_bypass_list() {
+    let rules = PacDirectRules::collect("  ", &[], &CnDomainList::fallback());
+    assert!(rules.patterns.iter().any(|p| p == "192.168.*"));
+    assert_eq!(rules.suffixes, vec!["cn"]);
+}
+
+#[test]
+fn build_pac_script_should_embed_proxy_and_rules() {
+    let rules = PacDirectRules {
+        patterns: vec!["localhost".to_string()],
+        domains: vec!["direct.example.com".to_string()],
+        suffixes: vec!["cn".to_string()],
+        keywords: vec![],
+        networks: vec![("10.0.0.0".to_string(), "255.0.0.0".to_string())],
+    };
+    let script = build_pac_script("127.0.0.1", 12080, &rules);
+    assert!(script.contains("function FindProxyForURL(url, host)"));
+    assert!(script.contains(r#"var proxy = "PROXY 127.0.0.1:12080; SOCKS5 127.0.0.1:12080";"#));
+    assert!(script.contains(r#"var patterns = ["localhost"];"#));
+    assert!(script.contains(r#"var domains = {"direct.example.com":1};"#));
+    assert!(script.contains(r#"var suffixes = {"cn":1};"#));
+    assert!(script.contains(r#"var keywords = [];"#));
+    assert!(script.contains(r#"var networks = [["10.0.0.0", "255.0.0.0"]];"#));
+}
+
+#[test]
+fn build_response_should_serve_pac_only_on_pac_path() {
+    let ok = String::from_utf8(build_response(
+        "GET /proxy.pac?t=1 HTTP/1.1\r\nHost: 127.0.0.1\r\n\r\n",
+    ))
+    .unwrap();
+    assert!(ok.starts_with("HTTP/1.1 200 OK"));
+    assert!(ok.contains("application/x-ns-proxy-autoconfig"));
+
+    let missing = String::from_utf8(build_response("GET /other HTTP/1.1\r\n\r\n")).unwrap();
+    assert!(missing.starts_with("HTTP/1.1 404 Not Found"));
+}
+
+#[test]
+fn cn_domain_list_should_parse_geosite_text_entries() {
+    let list = CnDomainList::parse(
+        "# comment\n\
+         baidu.com\n\
+         domain:QQ.com\n\
+         full:www.example.cn\n\
+         keyword:taobao\n\
+         regexp:^.+\\.cn$\n\
+         bilibili.com:@cn\n\
+         \n\
+         *.wildcard.cn\n\
+         bad/domain.com\n",
+    );
+    assert_eq!(list.suffixes, vec!["baidu.com", "qq.com", "bilibili.com"]);
+    assert_eq!(list.domains, vec!["www.example.cn"]);
+}
@@ -1,14 +1,31 @@
 use crate::app::constants::{config, messages, network_config, paths};
+use crate::app::core::extra_inbounds::{build_extra_inbounds, sync_extra_inbounds};
+use crate::app::core::lan_access::{
+    build_lan_auth_inbounds, cached_lan_listen_addresses, effective_lan_credentials,
+    lan_source_route_rule, mixed_listen_address, refresh_lan_credentials,
+    refresh_lan_listen_addresses, sync_lan_auth_inbounds, sync_lan_source_route_rule,
+    LanCredentials,
+};
+use crate::app::core::pac_service::publish_pac;
+use crate::app::core::proxy_watchdog::{
+    applied_proxy_state, clear_applied_proxy, record_applied_proxy, ExpectedSystemProxy,
+};
 use crate::app::core::tun_profile::{TunProfile, TunProxyOptions};
+use crate::app::singbox::settings_patch::sync_tun_split_route_rules;
 use crate::app::system::config_service;
 use crate::entity::config_model;
 use crate::utils::config_util::ConfigUtil;
 use crate::utils::http_client;
-use crate::utils::proxy_util::{disable_system_proxy, enable_system_proxy, DEFAULT_BYPASS_LIST};
+use crate::utils::proxy_util::{
+    disable_system_proxy, enable_system_proxy, enable_system_proxy_pac, parse_bypass_entries,
+    DEFAULT_BYPASS_LIST,
+};
+use crate::utils::shell_proxy::{ProxyEnvVars, ShellKind, ShellProxyEnv, ShellProxyTargets};
 use futures::stream::{self, StreamExt};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use std::fs;
+use std::net::IpAddr;
 use std::time::Duration;
 use tracing::{error, info, warn};
 use url::Url;
@@ -18,6 +35,8 @@ pub struct ProxyRuntimeState {
     pub proxy_port: u16,
     pub allow_lan_access: bool,
     pub system_proxy_enabled: bool,
+    /// 系统代理以 PAC 方式提供（仅在 `system_proxy_enabled` 时生效）
+    pub system_proxy_pac: bool,
     pub tun_enabled: bool,
     pub system_proxy_bypass: String,
     pub tun_options: TunProxyOptions,
@@ -27,6 +46,8 @@ impl ProxyRuntimeState {
     pub fn derived_mode(&self) -> String {
         if self.tun_enabled {
             "tun".to_string()
+        } else if self.system_proxy_enabled && self.system_proxy_pac {
+            "pac".to_string()
         } else if self.system_proxy_enabled {
             "system".to_string()
         } else {
@@ -68,12 +89,59 @@ fn build_inbounds_for_state(state: &ProxyRuntimeState) -> Vec<config_model::Inbo
         mtu: None,
         route_address: None,
         route_exclude_address: None,
+        route_exclude_address_set: None,
+        include_interface: None,
+        exclude_interface: None,
+        include_uid: None,
+        exclude_uid: None,
+        users: None,
         // 系统代理由 app 侧统一管理（修复"双重写入"竞态），inbound 不再写 set_system_proxy。
         set_system_proxy: None,
     }]
 }
 
+/// 写入配置的 mixed-in / tun-in，以及开启局域网认证时的局域网入站。
+///
+/// 认证只放在局域网入站上：mixed-in 此时只监听回环且不要求认证，
+/// 系统代理、PAC、终端代理与探测连接 `127.0.0.1:proxy_port` 无需凭据。
+fn build_runtime_inbounds(
+    state: &ProxyRuntimeState,
+    rule_set_tags: &[String],
+    credentials: Option<&LanCredentials>,
+    lan_addresses: &[IpAddr],
+) -> Result<Vec<Value>, String> {
+    let mut inbounds = build_inbounds_for_state(state);
+    for inbound in inbounds.iter_mut() {
+        if inbound.tag == config::DEFAULT_INBOUND_TAG {
+            inbound.listen =
+                Some(mixed_listen_address(state.allow_lan_access, credentials).to_string());
+        }
+        if let Some(rule_sets) = inbound.route_exclude_address_set.as_mut() {
+            rule_sets.retain(|tag| rule_set_tags.contains(tag));
+            if rule_sets.is_empty() {
+                inbound.route_exclude_address_set = None;
+            }
+        }
+    }
+    let mut inbounds = inbounds
+        .iter()
+        .map(serde_json::to_value)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("序列化配置失败: {}", e))?;
+    sync_lan_auth_inbounds(
+        &mut inbounds,
+        build_lan_auth_inbounds(
+            state.allow_lan_access,
+            state.proxy_port,
+            credentials,
+            lan_addresses,
+        ),
+    );
+    Ok(inbounds)
+}
+
 use crate::app::storage::enhanced_storage_service::db_get_app_config;
+use crate::app::storage::state_model::AppConfig;
 use tauri::AppHandle;
 
 async fn load_allow_lan_access(app_handle: &AppHandle) -> bool {
@@ -88,7 +156,7 @@ pub async fn apply_proxy_runtime_state(
     state: &ProxyRuntimeState,
 ) -> Result<(), String> {
     write_inbounds_to_config(app_handle, state).await?;
-    apply_os_proxy(state);
+    apply_os_proxy(app_handle, state).await;
     Ok(())
 }
 
@@ -108,8 +176,10 @@ pub async fn write_inbounds_to_config(
     let app_config = db_get_app_config(app_handle.clone())
         .await
         .map_err(|e| format!("获取应用配置失败: {}", e))?;
+    // 首次开启局域网访问后直接启动内核时，凭据可能还没生成
+    refresh_lan_credentials(app_handle, &app_config).await;
 
-    let config_path = if let Some(path_str) = app_config.active_config_path {
+    let config_path = if let Some(path_str) = &app_config.active_config_path {
         std::path::PathBuf::from(path_str)
     } else {
         paths::get_config_dir().join("config.json")
@@ -122,11 +192,58 @@ pub async fn write_inbounds_to_config(
     let mut json_util = ConfigUtil::new(config_path_str)
         .map_err(|e| format!("{}: {}", messages::ERR_CONFIG_READ_FAILED, e))?;
 
-    let inbounds = build_inbounds_for_state(state);
-    json_util.update_key(
-        vec!["inbounds"],
-        serde_json::to_value(inbounds).map_err(|e| format!("序列化配置失败: {}", e))?,
+    let mut state = state.clone();
+    state.tun_options.hydrate_from_app_config(&app_config);
+
+    // 预设引用的规则集不在当前配置中时去掉，避免内核拒绝加载
+    let rule_set_tags: Vec<String> = json_util
+        .get_property_as_entity::<Vec<Value>>(&["route", "rule_set"])
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|rule_set| rule_set.get("tag").and_then(|v| v.as_str()))
+        .map(str::to_string)
+        .collect();
+    let credentials = state
+        .allow_lan_access
+        .then(|| effective_lan_credentials(&app_config))
+        .flatten();
+    // 认证入站绑定具体地址，每次写入前重新枚举，避免沿用网络变化前的地址
+    if credentials.is_some() {
+        refresh_lan_listen_addresses(&app_config).await;
+    }
+    let lan_addresses: Vec<IpAddr> = cached_lan_listen_addresses()
+        .into_iter()
+        .map(|(_, ip)| ip)
+        .collect();
+    let mut inbounds =
+        build_runtime_inbounds(&state, &rule_set_tags, credentials.as_ref(), &lan_addresses)?;
+    sync_extra_inbounds(
+        &mut inbounds,
+        build_extra_inbounds(
+            &app_config.extra_inbounds,
+            resolve_proxy_listen_address(&state),
+        ),
     );
+    json_util.update_key(vec!["inbounds"], Value::Array(inbounds));
+
+    // 进程分流依赖只作用于 tun-in 的 route 规则，随 TUN 开关一起增删；局域网来源限制同理
+    if let Ok(mut rules) = json_util.get_property_as_entity::<Vec<Value>>(&["route", "rules"]) {
+        let outbounds = json_util
+            .get_property_as_entity::<Vec<Value>>(&["outbounds"])
+            .unwrap_or_default();
+        let split_rules = state
+            .tun_enabled
+            .then_some(&state.tun_options.split_rules)
+            .filter(|rules| !rules.is_empty());
+        sync_tun_split_route_rules(&mut rules, &outbounds, split_rules);
+        // 运行态的局域网开关决定 mixed-in 是否对外监听，以它为准
+        let lan_rule = lan_source_route_rule(&AppConfig {
+            allow_lan_access: state.allow_lan_access,
+            ..app_config.clone()
+        });
+        sync_lan_source_route_rule(&mut rules, lan_rule);
+        json_util.update_key(vec!["route", "rules"], Value::Array(rules));
+    }
     json_util
         .save_to_file()
         .map_err(|e| format!("{}: {}", messages::ERR_CONFIG_READ_FAILED, e))?;
@@ -138,8 +255,29 @@ pub async fn write_inbounds_to_config(
 ///
 /// 在内核 proxy_port 真正监听后调用，避免代理指向尚未就绪的端口。
 /// 失败仅记录警告：OS 代理写入失败不应阻断内核启动流程。
-pub fn apply_os_proxy(state: &ProxyRuntimeState) {
-    if state.system_proxy_enabled {
+pub async fn apply_os_proxy(app_handle: &AppHandle, state: &ProxyRuntimeState) {
+    if state.system_proxy_enabled && state.system_proxy_pac {
+        let pac_url =
+            match publish_pac(app_handle, state.proxy_port, &state.system_proxy_bypass).await {
+                Ok(url) => url,
+                Err(e) => {
+                    warn!("生成 PAC 失败: {}", e);
+                    clear_applied_proxy();
+                    return;
+                }
+            };
+        match enable_system_proxy_pac(&pac_url) {
+            Ok(()) => {
+                info!("PAC 系统代理已启用: {}", pac_url);
+                record_applied_proxy(state, ExpectedSystemProxy::Pac(pac_url));
+                write_shell_proxy(app_handle, state).await;
+            }
+            Err(e) => {
+                warn!("设置 PAC 系统代理失败: {}", e);
+                clear_applied_proxy();
+            }
+        }
+    } else if state.system_proxy_enabled {
         let bypass = state.system_proxy_bypass.trim();
         let normalized_bypass = if bypass.is_empty() {
             DEFAULT_BYPASS_LIST.to_string()
@@ -151,17 +289,106 @@ pub fn apply_os_proxy(state: &ProxyRuntimeState) {
             state.proxy_port,
             Some(normalized_bypass.as_str()),
         ) {
-            Ok(()) => info!(
-                "系统代理已启用，端口 {}，绕过列表: {}",
-                state.proxy_port, normalized_bypass
-            ),
-            Err(e) => warn!("设置系统代理失败: {}", e),
+            Ok(()) => {
+                info!(
+                    "系统代理已启用，端口 {}，绕过列表: {}",
+                    state.proxy_port, normalized_bypass
+                );
+                record_applied_proxy(
+                    state,
+                    ExpectedSystemProxy::System(format!(
+                        "{}:{}",
+                        network_config::DEFAULT_CLASH_API_ADDRESS,
+                        state.proxy_port
+                    )),
+                );
+                write_shell_proxy(app_handle, state).await;
+            }
+            Err(e) => {
+                warn!("设置系统代理失败: {}", e);
+                clear_applied_proxy();
+            }
+        }
+    } else {
+        clear_applied_proxy();
+        if let Err(err) = disable_system_proxy() {
+            warn!("关闭系统代理失败: {}", err);
         }
-    } else if let Err(err) = disable_system_proxy() {
-        warn!("关闭系统代理失败: {}", err);
     }
 }
 
+/// generic_config 中存储终端代理写入目标所用的 key。
+pub const SHELL_PROXY_TARGETS_KEY: &str = "shell_proxy_targets";
+
+async fn load_shell_proxy_targets(app_handle: &AppHandle) -> ShellProxyTargets {
+    let Ok(storage) = get_enhanced_storage(app_handle).await else {
+        return ShellProxyTargets::default();
+    };
+    storage
+        .load_generic_config::<ShellProxyTargets>(SHELL_PROXY_TARGETS_KEY)
+        .await
+        .ok()
+        .flatten()
+        .unwrap_or_default()
+}
+
+fn shell_proxy_vars(proxy_port: u16, bypass: &str) -> ProxyEnvVars {
+    ProxyEnvVars::new(
+        network_config::DEFAULT_CLASH_API_ADDRESS,
+        proxy_port,
+        &parse_bypass_entries(Some(bypass)),
+    )
+}
+
+/// 系统代理开启后，把代理同步写入用户勾选的终端与命令行工具配置
+async fn write_shell_proxy(app_handle: &AppHandle, state: &ProxyRuntimeState) {
+    let targets = load_shell_proxy_targets(app_handle).await;
+    let Some(env) = ShellProxyEnv::system() else {
+        return;
+    };
+    let vars = shell_proxy_vars(state.proxy_port, &state.system_proxy_bypass);
+    if let Err(e) = env.apply(&targets, &vars) {
+        warn!("写入终端代理环境配置失败: {}", e);
+    }
+}
+
+#[tauri::command]
+pub async fn get_shell_proxy_targets(app_handle: AppHandle) -> Result<ShellProxyTargets, String> {
+    Ok(load_shell_proxy_targets(&app_handle).await)
+}
+
+/// 保存终端代理写入目标；系统代理开启中时立即按新目标重写
+#[tauri::command]
+pub async fn save_shell_proxy_targets(
+    app_handle: AppHandle,
+    targets: ShellProxyTargets,
+) -> Result<(), String> {
+    let storage = get_enhanced_storage(&app_handle).await?;
+    storage
+        .save_generic_config(SHELL_PROXY_TARGETS_KEY, &targets)
+        .await
+        .map_err(|e| format!("保存终端代理设置失败: {}", e))?;
+
+    if let Some(state) = applied_proxy_state() {
+        write_shell_proxy(&app_handle, &state).await;
+    }
+    Ok(())
+}
+
+/// 输出当前代理端口对应的环境变量设置语句，`shell` 缺省时按平台选择
+#[tauri::command]
+pub async fn get_shell_proxy_exports(
+    app_handle: AppHandle,
+    shell: Option<String>,
+) -> Result<String, String> {
+    let kind = match shell.as_deref() {
+        Some(name) => ShellKind::parse(name).ok_or_else(|| format!("不支持的 shell: {}", name))?,
+        None => ShellKind::platform_default(),
+    };
+    let app_config = db_get_app_config(app_handle).await?;
+    Ok(shell_proxy_vars(app_config.proxy_port, &app_config.system_proxy_bypass).render(kind))
+}
+
 // 修改代理模式为系统代理
 #[tauri::command]
 pub async fn set_system_proxy(
@@ -174,6 +401,27 @@ pub async fn set_system_proxy(
         proxy_port: port,
         allow_lan_access,
         system_proxy_enabled: true,
+        system_proxy_pac: false,
+        tun_enabled: false,
+        system_proxy_bypass: system_proxy_bypass.unwrap_or_else(|| DEFAULT_BYPASS_LIST.to_string()),
+        tun_options: TunProxyOptions::default(),
+    };
+    apply_proxy_runtime_state(&app_handle, &runtime_state).await
+}
+
+// 修改代理模式为 PAC 系统代理
+#[tauri::command]
+pub async fn set_pac_proxy(
+    app_handle: AppHandle,
+    port: u16,
+    system_proxy_bypass: Option<String>,
+) -> Result<(), String> {
+    let allow_lan_access = load_allow_lan_access(&app_handle).await;
+    let runtime_state = ProxyRuntimeState {
+        proxy_port: port,
+        allow_lan_access,
+        system_proxy_enabled: true,
+        system_proxy_pac: true,
         tun_enabled: false,
         system_proxy_bypass: system_proxy_bypass.unwrap_or_else(|| DEFAULT_BYPASS_LIST.to_string()),
         tun_options: TunProxyOptions::default(),
@@ -189,6 +437,7 @@ pub async fn set_manual_proxy(app_handle: AppHandle, port: u16) -> Result<(), St
         proxy_port: port,
         allow_lan_access,
         system_proxy_enabled: false,
+        system_proxy_pac: false,
         tun_enabled: false,
         system_proxy_bypass: DEFAULT_BYPASS_LIST.to_string(),
         tun_options: TunProxyOptions::default(),
@@ -208,6 +457,7 @@ pub async fn set_tun_proxy(
         proxy_port: port,
         allow_lan_access,
         system_proxy_enabled: false,
+        system_proxy_pac: false,
         tun_enabled: true,
         system_proxy_bypass: DEFAULT_BYPASS_LIST.to_string(),
         tun_options: tun_options.unwrap_or_default(),
@@ -451,9 +701,249 @@ pub async fn close_connection(id: String, port: u16) -> Result<(), String> {
     delete_controller(port, &path).await
 }
 
+// -----------------------------
+// 按条件批量关闭连接
+// -----------------------------
+
+/// generic_config 中存储连接关闭策略所用的 key。
+pub const CONNECTION_CLOSE_POLICY_KEY: &str = "connection_close_policy";
+const CLOSE_CONNECTIONS_CONCURRENCY: usize = 16;
+
+/// 连接过滤条件，所有已指定的条件需同时满足。
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConnectionFilter {
+    /// 出站链中任一节点/代理组名称（精确匹配）
+    pub chain: Option<String>,
+    /// 出站链中包含该名称的连接不匹配（精确匹配），单独指定时不算有效条件
+    pub exclude_chain: Option<String>,
+    /// 进程文件名或完整路径（不区分大小写）
+    pub process: Option<String>,
+    /// 目标主机通配符，如 `*.googlevideo.com`，支持 `*` 与 `?`
+    pub host: Option<String>,
+    /// 命中规则（子串匹配，不区分大小写）
+    pub rule: Option<String>,
+    /// 仅匹配已存活超过该秒数的连接
+    pub min_age_secs: Option<u64>,
+}
+
+impl ConnectionFilter {
+    fn is_empty(&self) -> bool {
+        criterion(&self.chain).is_none()
+            && criterion(&self.process).is_none()
+            && criterion(&self.host).is_none()
+            && criterion(&self.rule).is_none()
+            && self.min_age_secs.is_none()
+    }
+
+    fn matches(&self, conn: &Value, now: chrono::DateTime<chrono::Utc>) -> bool {
+        if let Some(chain) = criterion(&self.chain) {
+            if !chain_contains(conn, chain) {
+                return false;
+            }
+        }
+
+        if let Some(excluded) = criterion(&self.exclude_chain) {
+            if chain_contains(conn, excluded) {
+                return false;
+            }
+        }
+
+        if let Some(process) = criterion(&self.process) {
+            let path = conn
+                .pointer("/metadata/processPath")
+                .and_then(Value::as_str)
+                .unwrap_or("");
+            let file_name = path.rsplit(['/', '\\']).next().unwrap_or(path);
+            if !path.eq_ignore_ascii_case(process) && !file_name.eq_ignore_ascii_case(process) {
+                return false;
+            }
+        }
+
+        if let Some(pattern) = criterion(&self.host) {
+            let host = ["/metadata/host", "/metadata/destinationIP"]
+                .iter()
+                .filter_map(|p| conn.pointer(p).and_then(Value::as_str))
+                .find(|s| !s.is_empty())
+                .unwrap_or("");
+            if !host_pattern_matches(pattern, host) {
+                return false;
+            }
+        }
+
+        if let Some(rule) = criterion(&self.rule) {
+            let matched = ["rule", "rulePayload"]
+                .iter()
+                .filter_map(|k| conn.get(*k).and_then(Value::as_str))
+                .any(|v| v.to_ascii_lowercase().contains(&rule.to_ascii_lowercase()));
+            if !matched {
+                return false;
+            }
+        }
+
+        if let Some(min_age) = self.min_age_secs {
+            let started = conn
+                .get("start")
+                .and_then(Value::as_str)
+                .and_then(|s| chrono::DateTime::parse_from_rfc3339(s).ok());
+            match started {
+                Some(start) => {
+                    let age = now.signed_duration_since(start).num_seconds();
+                    if age < 0 || (age as u64) < min_age {
+                        return false;
+                    }
+                }
+                None => return false,
+            }
+        }
+
+        true
+    }
+}
+
+fn chain_contains(conn: &Value, name: &str) -> bool {
+    conn.get("chains")
+        .and_then(Value::as_array)
+        .map(|items| items.iter().any(|v| v.as_str() == Some(name)))
+        .unwrap_or(false)
+}
+
+/// 切换节点后要关闭的连接：经过该代理组、但尚未走到新节点上的连接。
+/// 已经经由新节点的连接（例如外层组先切到了同一节点）保持不动。
+fn proxy_change_filter(group: &str, proxy: &str) -> ConnectionFilter {
+    ConnectionFilter {
+        chain: Some(group.to_string()),
+        exclude_chain: Some(proxy.to_string()),
+        ..Default::default()
+    }
+}
+
+fn criterion(value: &Option<String>) -> Option<&str> {
+    value.as_deref().map(str::trim).filter(|s| !s.is_empty())
+}
+
+/// 主机通配符匹配（不区分大小写）：`*` 匹配任意长度字符，`?` 匹配单个字符。
+fn host_pattern_matches(pattern: &str, host: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_ascii_lowercase().chars().collect();
+    let host: Vec<char> = host.to_ascii_lowercase().chars().collect();
+
+    let (mut p, mut h) = (0usize, 0usize);
+    let mut star: Option<(usize, usize)> = None;
+    while h < host.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == host[h]) {
+            p += 1;
+            h += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, h));
+            p += 1;
+        } else if let Some((star_p, star_h)) = star {
+            p = star_p + 1;
+            h = star_h + 1;
+            star = Some((star_p, star_h + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// 切换节点时的连接处理策略
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConnectionClosePolicy {
+    /// 切换节点后自动关闭仍经过该代理组的连接
+    pub close_on_proxy_change: bool,
+}
+
+async fn close_matching_connections(port: u16, filter: &ConnectionFilter) -> Result<usize, String> {
+    let data = fetch_controller_json(port, "connections").await?;
+    let now = chrono::Utc::now();
+    let ids: Vec<String> = data
+        .get("connections")
+        .and_then(Value::as_array)
+        .map(|items| {
+            items
+                .iter()
+                .filter(|conn| filter.matches(conn, now))
+                .filter_map(|conn| conn.get("id").and_then(Value::as_str))
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default();
+
+    if ids.is_empty() {
+        return Ok(0);
+    }
+
+    let closed = stream::iter(ids)
+        .map(|id| async move { close_connection(id, port).await })
+        .buffer_unordered(CLOSE_CONNECTIONS_CONCURRENCY)
+        .filter(|result| {
+            if let Err(e) = result {
+                warn!("关闭连接失败: {}", e);
+            }
+            futures::future::ready(result.is_ok())
+        })
+        .count()
+        .await;
+
+    Ok(closed)
+}
+
+/// 按条件批量关闭连接，返回成功关闭的数量。
+#[tauri::command]
+pub async fn close_connections_by_filter(
+    filter: ConnectionFilter,
+    port: u16,
+) -> Result<usize, String> {
+    if filter.is_empty() {
+        return Err("至少需要指定一个过滤条件".to_string());
+    }
+
+    let closed = close_matching_connections(port, &filter).await?;
+    info!("按条件关闭连接: {} 条", closed);
+    Ok(closed)
+}
+
+async fn load_connection_close_policy(app_handle: &AppHandle) -> ConnectionClosePolicy {
+    let Ok(storage) = get_enhanced_storage(app_handle).await else {
+        return ConnectionClosePolicy::default();
+    };
+    storage
+        .load_generic_config::<ConnectionClosePolicy>(CONNECTION_CLOSE_POLICY_KEY)
+        .await
+        .ok()
+        .flatten()
+        .unwrap_or_default()
+}
+
+#[tauri::command]
+pub async fn get_connection_close_policy(
+    app_handle: AppHandle,
+) -> Result<ConnectionClosePolicy, String> {
+    Ok(load_connection_close_policy(&app_handle).await)
+}
+
+#[tauri::command]
+pub async fn save_connection_close_policy(
+    app_handle: AppHandle,
+    policy: ConnectionClosePolicy,
+) -> Result<(), String> {
+    let storage = get_enhanced_storage(&app_handle).await?;
+    storage
+        .save_generic_config(CONNECTION_CLOSE_POLICY_KEY, &policy)
+        .await
+        .map_err(|e| format!("保存连接关闭策略失败: {}", e))
+}
+
 // 切换代理节点
 #[tauri::command]
-pub async fn change_proxy(group: String, proxy: String, port: u16) -> Result<(), String> {
+pub async fn change_proxy(
+    app_handle: AppHandle,
+    group: String,
+    proxy: String,
+    port: u16,
+) -> Result<(), String> {
     let url = format!("http://127.0.0.1:{}/proxies/{}", port, group);
     let data = json!({
         "name": proxy
@@ -470,6 +960,16 @@ pub async fn change_proxy(group: String, proxy: String, port: u16) -> Result<(),
         Ok(response) => {
             if response.status().is_success() {
                 info!("代理节点已切换: {} -> {}", group, proxy);
+                if load_connection_close_policy(&app_handle)
+                    .await
+                    .close_on_proxy_change
+                {
+                    let filter = proxy_change_filter(&group, &proxy);
+                    match close_matching_connections(port, &filter).await {
+                        Ok(closed) => info!("切换节点后关闭代理组 {} 的连接: {} 条", group, closed),
+                        Err(e) => warn!("切换节点后关闭旧连接失败: {}", e),
+                    }
+                }
                 Ok(())
             } else {
                 let error_msg = format!("切换代理节点失败，HTTP状态码: {}", response.status());
@@ -783,10 +1283,14 @@ pub async fn test_node_delay(
 // 注入策略：读取 AppConfig.active_config_path 指向的文件，调用 inject_custom_rules，
 // 写回磁盘。若该文件是“用户原始订阅配置”（use_original_config），则跳过注入避免破坏。
 
-use crate::app::singbox::config_generator::inject_custom_rules;
+use crate::app::network::subscription_service::fetch_options::subscription_detour_rules;
 use crate::app::singbox::common::normalize_default_outbound;
-use crate::app::storage::custom_rule::{CustomRule, CustomRuleAction, CustomRuleMatchType, STORAGE_KEY};
+use crate::app::singbox::config_generator::insert_before_default_rules;
+use crate::app::storage::custom_rule::{
+    CustomRule, CustomRuleAction, CustomRuleMatchType, STORAGE_KEY,
+};
 use crate::app::storage::enhanced_storage_service::get_enhanced_storage;
+use crate::app::storage::state_model::Subscription;
 use chrono::Utc;
 
 /// 读取所有自定义规则（按创建时间升序）。
@@ -937,6 +1441,8 @@ pub async fn toggle_custom_rule(app_handle: AppHandle, id: String) -> Result<(),
 
 /// 把当前所有启用规则注入活动配置文件（失败仅记录，不阻断 CRUD）。
 ///
+/// 订阅刷新写入新的活动配置、订阅拉取选项变更后也会调用，一并写入订阅的节点中转规则。
+///
 /// 实现要点：
 /// - 仅对“本程序生成的订阅配置”注入；用户原始订阅（use_original_config）跳过，避免破坏其结构。
 /// - 每次注入前重新读盘、覆盖式重写 route.rules 段是不安全的（默认规则由内核/生成器维护）；
@@ -947,7 +1453,7 @@ pub async fn toggle_custom_rule(app_handle: AppHandle, id: String) -> Result<(),
 ///
 /// 折中方案：读取活动配置 → inject_custom_rules（该函数基于 rule_set/ip_cidr 定位插入，幂等性
 /// 由调用频率保证：每次 CRUD 后调用，但 inject 会累积）。为避免累积，这里先移除上次注入的规则。
-async fn inject_into_active_config(app_handle: &AppHandle) {
+pub(crate) async fn inject_into_active_config(app_handle: &AppHandle) {
     if let Err(e) = inject_into_active_config_inner(app_handle).await {
         warn!("自定义规则注入活动配置失败（不影响持久化）: {}", e);
     }
@@ -957,11 +1463,10 @@ async fn inject_into_active_config_inner(app_handle: &AppHandle) -> Result<(), S
     let storage = get_enhanced_storage(app_handle)
         .await
         .map_err(|e| format!("初始化存储失败: {}", e))?;
-    let app_config = crate::app::storage::enhanced_storage_service::db_get_app_config(
-        app_handle.clone(),
-    )
-    .await
-    .map_err(|e| format!("读取应用配置失败: {}", e))?;
+    let app_config =
+        crate::app::storage::enhanced_storage_service::db_get_app_config(app_handle.clone())
+            .await
+            .map_err(|e| format!("读取应用配置失败: {}", e))?;
 
     // 用户原始订阅配置：不注入，避免破坏其结构。
     if is_active_config_use_original(&storage, &app_config).await {
@@ -983,8 +1488,14 @@ async fn inject_into_active_config_inner(app_handle: &AppHandle) -> Result<(), S
         .map_err(|e| format!("读取自定义规则失败: {}", e))?
         .unwrap_or_default();
 
+    // 订阅的“经指定节点拉取”选项同样以路由规则的形式注入
+    let subscriptions = storage
+        .get_subscriptions()
+        .await
+        .map_err(|e| format!("读取订阅列表失败: {}", e))?;
+
     let default_outbound = normalize_default_outbound(&app_config);
-    inject_custom_rules_into_file(&config_path, &rules, default_outbound)?;
+    inject_custom_rules_into_file(&config_path, &rules, default_outbound, &subscriptions)?;
     let enabled_count = rules.iter().filter(|r| r.enabled).count();
     info!(
         "已把 {} 条自定义规则注入活动配置: {:?}",
@@ -1012,9 +1523,10 @@ fn inject_custom_rules_into_file(
     config_path: &std::path::Path,
     rules: &[CustomRule],
     default_outbound: &str,
+    subscriptions: &[Subscription],
 ) -> Result<(), String> {
-    let active_content = std::fs::read_to_string(config_path)
-        .map_err(|e| format!("读取配置文件失败: {}", e))?;
+    let active_content =
+        std::fs::read_to_string(config_path).map_err(|e| format!("读取配置文件失败: {}", e))?;
     let snapshot_path = base_snapshot_path(config_path);
     let last_path = last_injected_path(config_path);
 
@@ -1046,15 +1558,22 @@ fn inject_custom_rules_into_file(
             let mut init: Value = serde_json::from_str(&active_content)
                 .map_err(|e| format!("解析配置失败: {}", e))?;
             strip_legacy_markers(&mut init);
-            let init_str =
-                serde_json::to_string_pretty(&init).map_err(|e| format!("序列化快照失败: {}", e))?;
+            let init_str = serde_json::to_string_pretty(&init)
+                .map_err(|e| format!("序列化快照失败: {}", e))?;
             std::fs::write(&snapshot_path, &init_str)
                 .map_err(|e| format!("写入快照失败: {}", e))?;
             init
         }
     };
 
-    inject_custom_rules(&mut base, rules, default_outbound);
+    // 订阅中转规则排在自定义规则之前，避免订阅主机的请求被用户规则转去其他出口
+    let mut route_rules = subscription_detour_rules(&base, subscriptions);
+    route_rules.extend(
+        rules
+            .iter()
+            .filter_map(|r| r.to_route_rule(default_outbound)),
+    );
+    insert_before_default_rules(&mut base, route_rules);
 
     let updated =
         serde_json::to_string_pretty(&base).map_err(|e| format!("序列化配置失败: {}", e))?;
@@ -1141,9 +1660,7 @@ fn uuid_v4() -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::app::storage::custom_rule::{
-        CustomRule, CustomRuleAction, CustomRuleMatchType,
-    };
+    use crate::app::storage::custom_rule::{CustomRule, CustomRuleAction, CustomRuleMatchType};
     use chrono::Utc;
     use serde_json::{json, Value};
     use std::path::PathBuf;
@@ -1201,7 +1718,12 @@ mod tests {
         "rules",
     ];
 
-    fn rule(id: &str, mt: CustomRuleMatchType, action: CustomRuleAction, payload: &str) -> CustomRule {
+    fn rule(
+        id: &str,
+        mt: CustomRuleMatchType,
+        action: CustomRuleAction,
+        payload: &str,
+    ) -> CustomRule {
         CustomRule {
             id: id.to_string(),
             enabled: true,
@@ -1252,7 +1774,12 @@ mod tests {
         fn new_with(tag: &str, initial: Value) -> Self {
             let dir = std::env::temp_dir().join("singbox_custom_rule_tests");
             std::fs::create_dir_all(&dir).unwrap();
-            let active = dir.join(format!("{}-{}-{}.json", tag, std::process::id(), unique_id()));
+            let active = dir.join(format!(
+                "{}-{}-{}.json",
+                tag,
+                std::process::id(),
+                unique_id()
+            ));
             // 清理可能残留的同名产物（活动 / .base / .last）。
             let _ = std::fs::remove_file(&active);
             let _ = std::fs::remove_file(base_snapshot_path(&active));
@@ -1271,12 +1798,16 @@ mod tests {
 
         /// 跑一次注入（原地修改活动配置 + .base 快照 + .last 记录）。
         fn inject(&self, rules: &[CustomRule]) {
-            inject_custom_rules_into_file(&self.active, rules, "自动选择").unwrap();
+            inject_custom_rules_into_file(&self.active, rules, "自动选择", &[]).unwrap();
         }
 
         /// 用任意内容覆盖活动配置（模拟订阅刷新重写活动配置）。
         fn overwrite_active(&self, content: Value) {
-            std::fs::write(&self.active, serde_json::to_string_pretty(&content).unwrap()).unwrap();
+            std::fs::write(
+                &self.active,
+                serde_json::to_string_pretty(&content).unwrap(),
+            )
+            .unwrap();
         }
 
         fn read_active(&self) -> Value {
@@ -1458,7 +1989,8 @@ mod tests {
         assert!(
             snap_rules
                 .iter()
-                .any(|r| r.get("rule_set").and_then(|v| v.as_str()) == Some("geosite-category-ads-all")),
+                .any(|r| r.get("rule_set").and_then(|v| v.as_str())
+                    == Some("geosite-category-ads-all")),
             ".base 快照应在订阅刷新后跟随更新"
         );
         assert!(
@@ -1469,9 +2001,135 @@ mod tests {
         assert_rules_only_known_fields(&result);
     }
 
+    #[test]
+    fn host_pattern_matches_supports_wildcards() {
+        let pattern = "*.googlevideo.com";
+        assert!(host_pattern_matches(pattern, "rr3.googlevideo.com"));
+        assert!(host_pattern_matches("*.Example.com", "a.example.com"));
+        assert!(!host_pattern_matches(pattern, "googlevideo.com"));
+        assert!(host_pattern_matches("api?.example.com", "api1.example.com"));
+        assert!(!host_pattern_matches("example.com", "www.example.com"));
+    }
+
+    #[test]
+    fn connection_filter_requires_all_criteria() {
+        let conn = json!({
+            "id": "c1",
+            "chains": ["香港 01", "手动切换"],
+            "rule": "rule_set=geosite-youtube",
+            "start": "2024-01-01T00:00:00Z",
+            "metadata": {
+                "host": "rr1.googlevideo.com",
+                "processPath": "C:\\Program Files\\Google\\chrome.exe"
+            }
+        });
+        let now = chrono::DateTime::parse_from_rfc3339("2024-01-01T00:10:00Z")
+            .unwrap()
+            .with_timezone(&chrono::Utc);
+
+        let filter = ConnectionFilter {
+            chain: Some("手动切换".to_string()),
+            exclude_chain: None,
+            process: Some("CHROME.EXE".to_string()),
+            host: Some("*.googlevideo.com".to_string()),
+            rule: Some("youtube".to_string()),
+            min_age_secs: Some(300),
+        };
+        assert!(filter.matches(&conn, now));
+
+        let too_young = ConnectionFilter {
+            min_age_secs: Some(3600),
+            ..filter.clone()
+        };
+        assert!(!too_young.matches(&conn, now));
+
+        let other_process = ConnectionFilter {
+            process: Some("firefox".to_string()),
+            ..Default::default()
+        };
+        assert!(!other_process.matches(&conn, now));
+        assert!(ConnectionFilter::default().is_empty());
+    }
+
+    #[test]
+    fn proxy_change_filter_skips_connections_already_on_new_proxy() {
+        let now = chrono::Utc::now();
+        let filter = proxy_change_filter("手动切换", "日本 01");
+
+        let old_node = json!({"id": "c1", "chains": ["香港 01", "手动切换"]});
+        let new_node = json!({"id": "c2", "chains": ["日本 01", "手动切换"]});
+        let other_group = json!({"id": "c3", "chains": ["香港 01", "自动选择"]});
+        assert!(filter.matches(&old_node, now));
+        assert!(!filter.matches(&new_node, now));
+        assert!(!filter.matches(&other_group, now));
+        assert!(ConnectionFilter {
+            exclude_chain: Some("日本 01".to_string()),
+            ..Default::default()
+        }
+        .is_empty());
+    }
+
     #[test]
     fn base_snapshot_path_appends_base_suffix() {
         let p: PathBuf = base_snapshot_path(std::path::Path::new("home-1784548482083.json"));
         assert_eq!(p.to_str().unwrap(), "home-1784548482083.json.base");
     }
+
+    fn lan_runtime_state(tun_enabled: bool) -> ProxyRuntimeState {
+        ProxyRuntimeState {
+            proxy_port: 12080,
+            allow_lan_access: true,
+            system_proxy_enabled: true,
+            system_proxy_pac: false,
+            tun_enabled,
+            system_proxy_bypass: String::new(),
+            tun_options: TunProxyOptions::default(),
+        }
+    }
+
+    #[test]
+    fn system_proxy_target_should_not_require_lan_auth() {
+        let credentials = LanCredentials {
+            username: "lan-user".to_string(),
+            password: "secret".to_string(),
+        };
+        let lan_address = IpAddr::from([192, 168, 1, 5]);
+
+        for tun_enabled in [false, true] {
+            let state = lan_runtime_state(tun_enabled);
+            let inbounds =
+                build_runtime_inbounds(&state, &[], Some(&credentials), &[lan_address]).unwrap();
+
+            // 系统代理、PAC 与终端代理都指向 127.0.0.1:proxy_port
+            let loopback = inbounds
+                .iter()
+                .find(|inbound| {
+                    inbound["listen"] == json!(network_config::DEFAULT_CLASH_API_ADDRESS)
+                        && inbound["listen_port"] == json!(state.proxy_port)
+                })
+                .expect("本机代理入口应存在");
+            assert_eq!(loopback["tag"], json!(config::DEFAULT_INBOUND_TAG));
+            assert!(loopback.get("users").is_none());
+
+            let lan = inbounds
+                .iter()
+                .find(|inbound| inbound["listen"] == json!("192.168.1.5"))
+                .expect("局域网入口应存在");
+            assert_eq!(lan["listen_port"], json!(state.proxy_port));
+            assert_eq!(lan["users"][0]["username"], json!("lan-user"));
+            assert!(inbounds
+                .iter()
+                .all(|inbound| inbound["listen"] != json!("0.0.0.0")));
+        }
+    }
+
+    #[test]
+    fn lan_access_without_auth_should_keep_single_mixed_inbound() {
+        let state = lan_runtime_state(false);
+        let inbounds =
+            build_runtime_inbounds(&state, &[], None, &[IpAddr::from([192, 168, 1, 5])]).unwrap();
+
+        assert_eq!(inbounds.len(), 1);
+        assert_eq!(inbounds[0]["listen"], json!("0.0.0.0"));
+    }
 }
@@ -0,0 +1,277 @@
+//! 系统代理漂移守护
+//!
+//! 企业 VPN 客户端、浏览器代理扩展等软件经常在本程序写入系统代理之后把它改掉，
+//! 而运行态仍认为系统代理在生效。这里周期性读回系统实际的代理设置，
+//! 与最近一次 [`apply_os_proxy`] 写入的期望值比对：不一致时通知前端，
+//! 并按策略重新写入；重新写入有次数限制，避免与其他工具无休止地互相覆盖。
+
+use crate::app::core::kernel_service::status::is_kernel_running;
+use crate::app::core::proxy_service::{apply_os_proxy, ProxyRuntimeState};
+use crate::app::storage::enhanced_storage_service::get_enhanced_storage;
+use crate::utils::proxy_util::{read_system_proxy, ObservedSystemProxy};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+use tracing::{info, warn};
+
+/// generic_config 中存储守护策略所用的 key。
+pub const PROXY_WATCHDOG_POLICY_KEY: &str = "proxy_watchdog_policy";
+/// 检测到系统代理被改动时发送的事件
+pub const PROXY_DRIFT_EVENT: &str = "system-proxy-drift";
+/// 巡检周期
+const WATCHDOG_TICK_SECS: u64 = 15;
+
+/// 系统代理漂移守护策略
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProxyWatchdogPolicy {
+    pub enabled: bool,
+    /// 检测到漂移后自动重新写入系统代理
+    pub auto_reapply: bool,
+    /// 统计窗口内最多重新写入的次数，超过后只上报不再写入
+    pub max_reapplies: u32,
+    pub window_secs: u64,
+}
+
+impl Default for ProxyWatchdogPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            auto_reapply: true,
+            max_reapplies: 3,
+            window_secs: 600,
+        }
+    }
+}
+
+/// 本程序期望系统上生效的代理
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "mode", content = "value", rename_all = "lowercase")]
+pub enum ExpectedSystemProxy {
+    /// 固定代理地址（`host:port`）
+    System(String),
+    /// PAC 地址
+    Pac(String),
+}
+
+impl ExpectedSystemProxy {
+    /// 读回的系统代理是否与期望一致
+    pub fn is_satisfied_by(&self, observed: &ObservedSystemProxy) -> bool {
+        match self {
+            // Windows / macOS 上 PAC 优先于固定代理，残留的 PAC 地址同样视为漂移
+            Self::System(server) => {
+                observed.enabled
+                    && observed.pac_url.is_none()
+                    && observed
+                        .server
+                        .as_deref()
+                        .is_some_and(|value| value.trim().eq_ignore_ascii_case(server))
+            }
+            Self::Pac(url) => observed.pac_url.as_deref().map(str::trim) == Some(url.as_str()),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct WatchedProxy {
+    state: ProxyRuntimeState,
+    expected: ExpectedSystemProxy,
+    /// 每次记录递增，巡检据此判断期望值在比对期间是否被替换
+    generation: u64,
+}
+
+lazy_static::lazy_static! {
+    static ref WATCHED_PROXY: RwLock<Option<WatchedProxy>> = RwLock::new(None);
+}
+
+static WATCH_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// 记录刚写入系统的代理设置，作为后续巡检的期望值
+pub fn record_applied_proxy(state: &ProxyRuntimeState, expected: ExpectedSystemProxy) {
+    if let Ok(mut slot) = WATCHED_PROXY.write() {
+        *slot = Some(WatchedProxy {
+            state: state.clone(),
+            expected,
+            generation: WATCH_GENERATION.fetch_add(1, Ordering::Relaxed) + 1,
+        });
+    }
+}
+
+/// 系统代理已关闭或写入失败时清除期望值，守护不再干预
+pub fn clear_applied_proxy() {
+    if let Ok(mut slot) = WATCHED_PROXY.write() {
+        *slot = None;
+    }
+}
+
+fn watched_proxy() -> Option<WatchedProxy> {
+    WATCHED_PROXY.read().ok().and_then(|slot| slot.clone())
+}
+
+/// 期望值是否仍是 `generation` 那一次记录（期间没有切换模式、关闭系统代理或重新写入）
+fn is_current_watch(generation: u64) -> bool {
+    watched_proxy().is_some_and(|watched| watched.generation == generation)
+}
+
+/// 最近一次成功写入系统代理时的运行态；系统代理未开启时为 `None`
+pub fn applied_proxy_state() -> Option<ProxyRuntimeState> {
+    watched_proxy().map(|watched| watched.state)
+}
+
+/// 重新写入的限速器：`window` 内最多放行 `max` 次
+#[derive(Debug, Default)]
+pub struct ReapplyLimiter {
+    history: VecDeque<Instant>,
+}
+
+impl ReapplyLimiter {
+    pub fn try_acquire(&mut self, now: Instant, max: u32, window: Duration) -> bool {
+        while self
+            .history
+            .front()
+            .is_some_and(|at| now.duration_since(*at) >= window)
+        {
+            self.history.pop_front();
+        }
+        if self.history.len() >= max as usize {
+            return false;
+        }
+        self.history.push_back(now);
+        true
+    }
+
+    pub fn reset(&mut self) {
+        self.history.clear();
+    }
+}
+
+/// 漂移事件内容
+#[derive(Debug, Clone, Serialize)]
+pub struct ProxyDriftPayload {
+    pub expected: ExpectedSystemProxy,
+    pub observed: ObservedSystemProxy,
+    pub reapplied: bool,
+    /// 达到重新写入次数上限，本次只上报
+    pub rate_limited: bool,
+}
+
+async fn load_watchdog_policy(app_handle: &AppHandle) -> ProxyWatchdogPolicy {
+    let Ok(storage) = get_enhanced_storage(app_handle).await else {
+        return ProxyWatchdogPolicy::default();
+    };
+    storage
+        .load_generic_config::<ProxyWatchdogPolicy>(PROXY_WATCHDOG_POLICY_KEY)
+        .await
+        .ok()
+        .flatten()
+        .unwrap_or_default()
+}
+
+#[tauri::command]
+pub async fn get_proxy_watchdog_policy(
+    app_handle: AppHandle,
+) -> Result<ProxyWatchdogPolicy, String> {
+    Ok(load_watchdog_policy(&app_handle).await)
+}
+
+#[tauri::command]
+pub async fn save_proxy_watchdog_policy(
+    app_handle: AppHandle,
+    policy: ProxyWatchdogPolicy,
+) -> Result<(), String> {
+    let storage = get_enhanced_storage(&app_handle).await?;
+    storage
+        .save_generic_config(PROXY_WATCHDOG_POLICY_KEY, &policy)
+        .await
+        .map_err(|e| format!("保存系统代理守护策略失败: {}", e))
+}
+
+async fn observe_system_proxy() -> Option<ObservedSystemProxy> {
+    match tokio::task::spawn_blocking(read_system_proxy).await {
+        Ok(Ok(observed)) => observed,
+        Ok(Err(e)) => {
+            warn!("读取系统代理设置失败: {}", e);
+            None
+        }
+        Err(e) => {
+            warn!("读取系统代理设置任务异常: {}", e);
+            None
+        }
+    }
+}
+
+/// 启动系统代理漂移巡检（随应用常驻，仅在内核运行且本程序开启了系统代理时比对）
+pub async fn start_proxy_watchdog_loop(app_handle: AppHandle) {
+    let mut limiter = ReapplyLimiter::default();
+    // 同一种漂移只上报一次，恢复一致后清空
+    let mut last_reported: Option<ObservedSystemProxy> = None;
+
+    loop {
+        tokio::time::sleep(Duration::from_secs(WATCHDOG_TICK_SECS)).await;
+
+        let policy = load_watchdog_policy(&app_handle).await;
+        let watched = match watched_proxy() {
+            Some(watched) if policy.enabled => watched,
+            _ => {
+                limiter.reset();
+                last_reported = None;
+                continue;
+            }
+        };
+        if !is_kernel_running().await.unwrap_or(false) {
+            continue;
+        }
+
+        let Some(observed) = observe_system_proxy().await else {
+            continue;
+        };
+        if watched.expected.is_satisfied_by(&observed) {
+            last_reported = None;
+            continue;
+        }
+        // 读取系统代理期间用户可能切换了模式或关闭了系统代理，此时旧的期望值已失效，
+        // 不能再用它覆盖刚写入的设置
+        if !is_current_watch(watched.generation) {
+            continue;
+        }
+
+        let can_reapply = policy.auto_reapply
+            && limiter.try_acquire(
+                Instant::now(),
+                policy.max_reapplies,
+                Duration::from_secs(policy.window_secs.max(WATCHDOG_TICK_SECS)),
+            );
+        if !can_reapply && last_reported.as_ref() == Some(&observed) {
+            continue;
+        }
+
+        if can_reapply {
+            warn!(
+                "系统代理被外部修改（期望 {:?}，实际 {:?}），重新写入",
+                watched.expected, observed
+            );
+            apply_os_proxy(&app_handle, &watched.state).await;
+        } else {
+            info!(
+                "系统代理被外部修改（期望 {:?}，实际 {:?}），本次不重新写入",
+                watched.expected, observed
+            );
+        }
+
+        let payload = ProxyDriftPayload {
+            expected: watched.expected,
+            observed: observed.clone(),
+            reapplied: can_reapply,
+            rate_limited: policy.auto_reapply && !can_reapply,
+        };
+        let _ = app_handle.emit(PROXY_DRIFT_EVENT, &payload);
+        last_reported = Some(observed);
+    }
+}
+
+#[cfg(test)]
+#[path = "proxy_watchdog.tests.rs"]
+mod tests;
@@ -0,0 +1,90 @@
+use super::*;
+
+fn manual(server: &str) -> ObservedSystemProxy {
+    ObservedSystemProxy {
+        enabled: true,
+        server: Some(server.to_string()),
+        pac_url: None,
+    }
+}
+
+#[test]
+fn system_expectation_should_require_enabled_matching_server() {
+    let expected = ExpectedSystemProxy::System("127.0.0.1:7890".to_string());
+    assert!(expected.is_satisfied_by(&manual("127.0.0.1:7890")));
+    assert!(!expected.is_satisfied_by(&manual("10.0.0.1:8080")));
+    assert!(!expected.is_satisfied_by(&ObservedSystemProxy::default()));
+
+    let mut with_pac = manual("127.0.0.1:7890");
+    with_pac.pac_url = Some("http://corp/proxy.pac".to_string());
+    assert!(!expected.is_satisfied_by(&with_pac));
+}
+
+#[test]
+fn pac_expectation_should_compare_pac_url_only() {
+    let expected = ExpectedSystemProxy::Pac("http://127.0.0.1:34567/proxy.pac".to_string());
+    assert!(expected.is_satisfied_by(&ObservedSystemProxy {
+        enabled: false,
+        server: None,
+        pac_url: Some("http://127.0.0.1:34567/proxy.pac".to_string()),
+    }));
+    assert!(!expected.is_satisfied_by(&manual("127.0.0.1:7890")));
+}
+
+#[test]
+fn reapply_limiter_should_cap_attempts_within_window() {
+    let mut limiter = ReapplyLimiter::default();
+    let window = Duration::from_secs(600);
+    let start = Instant::now();
+
+    assert!(limiter.try_acquire(start, 2, window));
+    assert!(limiter.try_acquire(start + Duration::from_secs(10), 2, window));
+    assert!(!limiter.try_acquire(start + Duration::from_secs(20), 2, window));
+
+    // 第一次写入滑出窗口后重新放行
+    assert!(limiter.try_acquire(start + Duration::from_secs(600), 2, window));
+    assert!(!limiter.try_acquire(start + Duration::from_secs(601), 2, window));
+
+    limiter.reset();
+    assert!(limiter.try_acquire(start + Duration::from_secs(602), 2, window));
+}
+
+#[test]
+fn policy_should_default_missing_fields() {
+    let policy: ProxyWatchdogPolicy =
+        serde_json::from_str(r#"{"auto_reapply":false}"#).expect("policy should parse");
+    assert!(policy.enabled);
+    assert!(!policy.auto_reapply);
+    assert_eq!(policy.max_reapplies, 3);
+}
+
+#[test]
+fn watch_generation_should_change_when_expectation_is_replaced_or_cleared() {
+    let state = ProxyRuntimeState {
+        proxy_port: 12080,
+        allow_lan_access: false,
+        system_proxy_enabled: true,
+        system_proxy_pac: false,
+        tun_enabled: false,
+        system_proxy_bypass: String::new(),
+        tun_options: crate::app::core::tun_profile::TunProxyOptions::default(),
+    };
+
+    record_applied_proxy(
+        &state,
+        ExpectedSystemProxy::System("127.0.0.1:12080".to_string()),
+    );
+    let first = watched_proxy().unwrap().generation;
+    assert!(is_current_watch(first));
+
+    record_applied_proxy(
+        &state,
+        ExpectedSystemProxy::Pac("http://127.0.0.1:1/proxy.pac".to_string()),
+    );
+    assert!(!is_current_watch(first));
+    let second = watched_proxy().unwrap().generation;
+    assert!(is_current_watch(second));
+
+    clear_applied_proxy();
+    assert!(!is_current_watch(second));
+}
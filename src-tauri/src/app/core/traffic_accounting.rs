@@ -0,0 +1,434 @@
+//! 流量记账
+//!
+//! 事件中继在转发 `/traffic`、`/connections` 帧的同时把数据交给这里累加：
+//! - `/traffic` 的每秒速率累加为总流量；
+//! - `/connections` 的逐连接字节差值（由 `connection_tracker` 统一计算）归属到
+//!   节点（chains 第一跳）与出站组（chains 最外层）；
+//! - 落库时把第一跳属于当前激活订阅节点的流量汇总到订阅维度，直连等流量不计入。
+//!
+//! 增量在记录时即按当时所在的小时桶/日桶归档，后台任务每分钟把累计的增量写入
+//! `DatabaseService` 的小时桶/日桶表，并按保留策略定期清理过期数据；停止内核与
+//! 退出应用时也会补写一次，避免跨整点或退出前的流量记错桶或丢失。
+
+use crate::app::core::connection_tracker::ConnectionDelta;
+use crate::app::network::subscription_service::exporter::node_outbounds;
+use crate::app::storage::enhanced_storage_service::{
+    db_get_app_config_internal, get_enhanced_storage,
+};
+use crate::app::storage::traffic_record::{
+    TrafficBucket, TrafficDelta, TrafficDimension, TrafficGranularity, TrafficRetentionPolicy,
+    TrafficTotal, RETENTION_STORAGE_KEY,
+};
+use chrono::{Local, TimeZone, Utc};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::AppHandle;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+/// 增量落库间隔
+const FLUSH_INTERVAL_SECS: u64 = 60;
+/// 每隔多少次落库执行一次过期清理（约 6 小时）
+const PRUNE_EVERY_FLUSHES: u32 = 360;
+const DEFAULT_TOP_LIMIT: u32 = 10;
+
+lazy_static::lazy_static! {
+    static ref TRAFFIC_ACCUMULATOR: Mutex<TrafficAccumulator> =
+        Mutex::new(TrafficAccumulator::default());
+}
+
+/// 增量所属的 (小时桶, 日桶) 起始时间
+pub type BucketStarts = (i64, i64);
+
+/// 同一对时间桶下待写入的增量
+#[derive(Debug)]
+pub struct BucketedDeltas {
+    pub buckets: BucketStarts,
+    pub deltas: Vec<TrafficDelta>,
+}
+
+/// 内存中的流量累加器，落库前的增量缓冲
+#[derive(Debug, Default)]
+pub struct TrafficAccumulator {
+    pending: HashMap<(BucketStarts, TrafficDimension, String), (u64, u64)>,
+}
+
+impl TrafficAccumulator {
+    fn add(
+        &mut self,
+        buckets: BucketStarts,
+        dimension: TrafficDimension,
+        name: &str,
+        upload: u64,
+        download: u64,
+    ) {
+        if upload == 0 && download == 0 {
+            return;
+        }
+        let entry = self
+            .pending
+            .entry((buckets, dimension, name.to_string()))
+            .or_insert((0, 0));
+        entry.0 = entry.0.saturating_add(upload);
+        entry.1 = entry.1.saturating_add(download);
+    }
+
+    /// 处理一帧 `/traffic` 数据（`{"up": 字节/秒, "down": 字节/秒}`）
+    pub fn observe_traffic(&mut self, data: &Value) {
+        self.observe_traffic_at(current_buckets(), data);
+    }
+
+    fn observe_traffic_at(&mut self, buckets: BucketStarts, data: &Value) {
+        let up = data.get("up").and_then(Value::as_u64).unwrap_or(0);
+        let down = data.get("down").and_then(Value::as_u64).unwrap_or(0);
+        self.add(buckets, TrafficDimension::Total, "total", up, down);
+    }
+
+    /// 处理一帧 `/connections` 的逐连接字节差值
+    pub fn observe_connections(&mut self, deltas: &[ConnectionDelta]) {
+        self.observe_connections_at(current_buckets(), deltas);
+    }
+
+    fn observe_connections_at(&mut self, buckets: BucketStarts, deltas: &[ConnectionDelta]) {
+        for delta in deltas {
+            if let Some((node, group)) = connection_chain_labels(delta.conn) {
+                let (up, down) = (delta.delta_upload, delta.delta_download);
+                self.add(buckets, TrafficDimension::Node, &node, up, down);
+                if let Some(group) = group {
+                    self.add(buckets, TrafficDimension::Group, &group, up, down);
+                }
+            }
+        }
+    }
+
+    /// 取出并清空待写入的增量，按记录时所在的时间桶分组（按时间先后排列）
+    pub fn take_deltas(&mut self) -> Vec<BucketedDeltas> {
+        let mut grouped: HashMap<BucketStarts, Vec<TrafficDelta>> = HashMap::new();
+        for ((buckets, dimension, name), (upload, download)) in self.pending.drain() {
+            grouped.entry(buckets).or_default().push(TrafficDelta {
+                dimension,
+                name,
+                upload,
+                download,
+            });
+        }
+        let mut batches: Vec<BucketedDeltas> = grouped
+            .into_iter()
+            .map(|(buckets, deltas)| BucketedDeltas { buckets, deltas })
+            .collect();
+        batches.sort_by_key(|batch| batch.buckets);
+        batches
+    }
+
+    /// 落库失败时把尚未写入的增量放回，等待下次重试
+    fn restore(&mut self, batches: Vec<BucketedDeltas>) {
+        for batch in batches {
+            for delta in batch.deltas {
+                self.add(
+                    batch.buckets,
+                    delta.dimension,
+                    &delta.name,
+                    delta.upload,
+                    delta.download,
+                );
+            }
+        }
+    }
+}
+
+/// 解析连接的出站链：sing-box 的 chains 由内到外排列，
+/// 第一项是实际节点，最后一项是最外层出站组（只有一项时没有分组）。
+pub(crate) fn connection_chain_labels(conn: &Value) -> Option<(String, Option<String>)> {
+    let chains: Vec<&str> = conn
+        .get("chains")
+        .and_then(Value::as_array)?
+        .iter()
+        .filter_map(Value::as_str)
+        .filter(|s| !s.is_empty())
+        .collect();
+
+    let node = chains.first()?.to_string();
+    let group = if chains.len() > 1 {
+        chains.last().map(|s| s.to_string())
+    } else {
+        None
+    };
+    Some((node, group))
+}
+
+/// 事件中继转发 `/traffic` 帧时调用
+pub fn record_traffic_frame(data: &Value) {
+    if let Ok(mut accumulator) = TRAFFIC_ACCUMULATOR.lock() {
+        accumulator.observe_traffic(data);
+    }
+}
+
+/// `connection_tracker` 算出一帧 `/connections` 的差值后调用
+pub fn record_connection_deltas(deltas: &[ConnectionDelta]) {
+    if let Ok(mut accumulator) = TRAFFIC_ACCUMULATOR.lock() {
+        accumulator.observe_connections(deltas);
+    }
+}
+
+/// 当前时间对应的 (小时桶, 日桶) 起始时间。日桶按本地时区零点对齐，便于按自然日统计。
+fn current_buckets() -> BucketStarts {
+    let now = Utc::now().timestamp();
+    let hour_bucket = now - now.rem_euclid(3600);
+    let day_bucket = Local::now()
+        .date_naive()
+        .and_hms_opt(0, 0, 0)
+        .and_then(|midnight| Local.from_local_datetime(&midnight).earliest())
+        .map(|dt| dt.timestamp())
+        .unwrap_or(now - now.rem_euclid(86_400));
+    (hour_bucket, day_bucket)
+}
+
+/// 当前激活的订阅：名称与其配置中的节点 tag
+struct ActiveSubscription {
+    name: String,
+    node_tags: HashSet<String>,
+}
+
+/// 根据 `active_config_path` 找到当前激活的订阅，并读取该配置中的节点
+async fn resolve_active_subscription(app_handle: &AppHandle) -> Option<ActiveSubscription> {
+    let app_config = db_get_app_config_internal(app_handle).await.ok()?;
+    let active_path = app_config.active_config_path?;
+    let storage = get_enhanced_storage(app_handle).await.ok()?;
+    let subscriptions = storage.get_subscriptions().await.ok()?;
+
+    let name = subscriptions
+        .into_iter()
+        .find(|sub| sub.config_path.as_deref() == Some(active_path.as_str()))
+        .map(|sub| sub.name)
+        .or_else(|| {
+            std::path::Path::new(&active_path)
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+        })?;
+
+    let content = std::fs::read_to_string(&active_path).ok()?;
+    let config: Value = serde_json::from_str(&content).ok()?;
+    let node_tags = node_outbounds(&config)
+        .into_iter()
+        .filter_map(|outbound| outbound.get("tag").and_then(Value::as_str))
+        .map(str::to_string)
+        .collect();
+    Some(ActiveSubscription { name, node_tags })
+}
+
+/// 汇总第一跳属于订阅节点的节点增量，作为订阅维度的增量
+fn subscription_delta(
+    deltas: &[TrafficDelta],
+    name: &str,
+    node_tags: &HashSet<String>,
+) -> Option<TrafficDelta> {
+    let (upload, download) = deltas
+        .iter()
+        .filter(|d| d.dimension == TrafficDimension::Node && node_tags.contains(&d.name))
+        .fold((0u64, 0u64), |(up, down), d| {
+            (up.saturating_add(d.upload), down.saturating_add(d.download))
+        });
+    (upload > 0 || download > 0).then(|| TrafficDelta {
+        dimension: TrafficDimension::Subscription,
+        name: name.to_string(),
+        upload,
+        download,
+    })
+}
+
+fn restore_pending(batches: Vec<BucketedDeltas>) {
+    if batches.is_empty() {
+        return;
+    }
+    if let Ok(mut accumulator) = TRAFFIC_ACCUMULATOR.lock() {
+        accumulator.restore(batches);
+    }
+}
+
+/// 把内存中的增量按各自记录时的时间桶写入数据库；写入失败的批次放回累加器。
+pub(crate) async fn flush_pending(app_handle: &AppHandle) -> Result<usize, String> {
+    let mut batches = match TRAFFIC_ACCUMULATOR.lock() {
+        Ok(mut accumulator) => accumulator.take_deltas(),
+        Err(_) => return Err("流量累加器不可用".to_string()),
+    };
+    if batches.is_empty() {
+        return Ok(0);
+    }
+
+    let storage = match get_enhanced_storage(app_handle).await {
+        Ok(storage) => storage,
+        Err(e) => {
+            restore_pending(batches);
+            return Err(e);
+        }
+    };
+
+    if let Some(active) = resolve_active_subscription(app_handle).await {
+        for batch in &mut batches {
+            if let Some(delta) = subscription_delta(&batch.deltas, &active.name, &active.node_tags)
+            {
+                batch.deltas.push(delta);
+            }
+        }
+    }
+
+    let mut written = 0;
+    let mut remaining = batches.into_iter();
+    while let Some(batch) = remaining.next() {
+        let (hour_bucket, day_bucket) = batch.buckets;
+        if let Err(e) = storage
+            .add_traffic_deltas(hour_bucket, day_bucket, &batch.deltas)
+            .await
+        {
+            // 订阅维度由节点增量汇总而来，重试时会重新计算，放回前先剔除
+            let mut unwritten = vec![batch];
+            unwritten.extend(remaining);
+            for batch in &mut unwritten {
+                batch
+                    .deltas
+                    .retain(|d| d.dimension != TrafficDimension::Subscription);
+            }
+            restore_pending(unwritten);
+            return Err(format!("写入流量统计失败: {}", e));
+        }
+        written += batch.deltas.len();
+    }
+
+    Ok(written)
+}
+
+async fn load_retention_policy(app_handle: &AppHandle) -> TrafficRetentionPolicy {
+    let Ok(storage) = get_enhanced_storage(app_handle).await else {
+        return TrafficRetentionPolicy::default();
+    };
+    storage
+        .load_generic_config::<TrafficRetentionPolicy>(RETENTION_STORAGE_KEY)
+        .await
+        .ok()
+        .flatten()
+        .unwrap_or_default()
+}
+
+async fn prune_expired(app_handle: &AppHandle) -> Result<u64, String> {
+    let policy = load_retention_policy(app_handle).await;
+    let now = Utc::now().timestamp();
+    let hourly_before = now - i64::from(policy.hourly_days.max(1)) * 86_400;
+    let daily_before = now - i64::from(policy.daily_days.max(1)) * 86_400;
+
+    let storage = get_enhanced_storage(app_handle).await?;
+    storage
+        .prune_traffic_stats(hourly_before, daily_before)
+        .await
+        .map_err(|e| format!("清理过期流量统计失败: {}", e))
+}
+
+/// 启动后台落库任务。由事件中继统一管理生命周期，停止内核时随中继任务一起中止，
+/// 随后由 `stop_kernel` 补写一次；退出应用时在 `RunEvent::Exit` 中再补写一次。
+pub fn spawn_traffic_flush_task(app_handle: AppHandle) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut flush_count: u32 = 0;
+        if let Err(e) = prune_expired(&app_handle).await {
+            warn!("{}", e);
+        }
+
+        loop {
+            tokio::time::sleep(Duration::from_secs(FLUSH_INTERVAL_SECS)).await;
+
+            match flush_pending(&app_handle).await {
+                Ok(count) if count > 0 => debug!("已写入 {} 条流量统计增量", count),
+                Ok(_) => {}
+                Err(e) => warn!("{}", e),
+            }
+
+            flush_count = flush_count.wrapping_add(1);
+            if flush_count % PRUNE_EVERY_FLUSHES == 0 {
+                match prune_expired(&app_handle).await {
+                    Ok(removed) if removed > 0 => info!("已清理 {} 条过期流量统计", removed),
+                    Ok(_) => {}
+                    Err(e) => warn!("{}", e),
+                }
+            }
+        }
+    })
+}
+
+/// 按时间桶查询流量（`from`/`to` 为 Unix 秒，左闭右开）
+#[tauri::command]
+pub async fn get_traffic_stats(
+    app_handle: AppHandle,
+    granularity: TrafficGranularity,
+    dimension: TrafficDimension,
+    from: i64,
+    to: i64,
+    name: Option<String>,
+) -> Result<Vec<TrafficBucket>, String> {
+    // 先把内存中尚未落库的增量写入，保证查询结果包含最近一分钟
+    if let Err(e) = flush_pending(&app_handle).await {
+        warn!("查询前写入流量增量失败: {}", e);
+    }
+
+    let storage = get_enhanced_storage(&app_handle).await?;
+    storage
+        .query_traffic_buckets(granularity, dimension, name.as_deref(), from, to)
+        .await
+        .map_err(|e| format!("查询流量统计失败: {}", e))
+}
+
+/// 查询区间内流量最多的前 N 个节点/分组/订阅
+#[tauri::command]
+pub async fn get_traffic_top(
+    app_handle: AppHandle,
+    granularity: TrafficGranularity,
+    dimension: TrafficDimension,
+    from: i64,
+    to: i64,
+    limit: Option<u32>,
+) -> Result<Vec<TrafficTotal>, String> {
+    if let Err(e) = flush_pending(&app_handle).await {
+        warn!("查询前写入流量增量失败: {}", e);
+    }
+
+    let storage = get_enhanced_storage(&app_handle).await?;
+    storage
+        .query_traffic_top(
+            granularity,
+            dimension,
+            from,
+            to,
+            limit.unwrap_or(DEFAULT_TOP_LIMIT).clamp(1, 200),
+        )
+        .await
+        .map_err(|e| format!("查询流量排行失败: {}", e))
+}
+
+#[tauri::command]
+pub async fn get_traffic_retention(
+    app_handle: AppHandle,
+) -> Result<TrafficRetentionPolicy, String> {
+    Ok(load_retention_policy(&app_handle).await)
+}
+
+/// 保存保留策略并立即按新策略清理
+#[tauri::command]
+pub async fn set_traffic_retention(
+    app_handle: AppHandle,
+    policy: TrafficRetentionPolicy,
+) -> Result<u64, String> {
+    if policy.hourly_days == 0 || policy.daily_days == 0 {
+        return Err("保留天数必须大于 0".to_string());
+    }
+
+    let storage = get_enhanced_storage(&app_handle).await?;
+    storage
+        .save_generic_config(RETENTION_STORAGE_KEY, &policy)
+        .await
+        .map_err(|e| format!("保存流量统计保留策略失败: {}", e))?;
+
+    prune_expired(&app_handle).await
+}
+
+#[cfg(test)]
+#[path = "traffic_accounting.tests.rs"]
+mod tests;
@@ -0,0 +1,170 @@
+use super::*;
+use crate::app::core::connection_tracker::ConnectionDeltaTracker;
+use serde_json::json;
+
+/// 与事件中继一致：先由共享的差值跟踪器计算，再交给累加器
+fn observe_frame(
+    tracker: &mut ConnectionDeltaTracker,
+    accumulator: &mut TrafficAccumulator,
+    frame: &Value,
+) {
+    let deltas = tracker.observe(frame).unwrap();
+    accumulator.observe_connections(&deltas);
+}
+
+/// 测试只关心同一时间桶内的累加结果，把分组展开
+fn take_all(accumulator: &mut TrafficAccumulator) -> Vec<TrafficDelta> {
+    accumulator
+        .take_deltas()
+        .into_iter()
+        .flat_map(|batch| batch.deltas)
+        .collect()
+}
+
+fn find_delta<'a>(
+    deltas: &'a [TrafficDelta],
+    dimension: TrafficDimension,
+    name: &str,
+) -> Option<&'a TrafficDelta> {
+    deltas
+        .iter()
+        .find(|d| d.dimension == dimension && d.name == name)
+}
+
+#[test]
+fn observe_traffic_should_accumulate_total_rates() {
+    let mut accumulator = TrafficAccumulator::default();
+    accumulator.observe_traffic(&json!({"up": 100, "down": 1000}));
+    accumulator.observe_traffic(&json!({"up": 50, "down": 500}));
+
+    let deltas = take_all(&mut accumulator);
+    let total = find_delta(&deltas, TrafficDimension::Total, "total").unwrap();
+    assert_eq!((total.upload, total.download), (150, 1500));
+    assert!(take_all(&mut accumulator).is_empty());
+}
+
+#[test]
+fn observe_connections_should_count_only_byte_differences() {
+    let mut tracker = ConnectionDeltaTracker::default();
+    let mut accumulator = TrafficAccumulator::default();
+    let frame = |up: u64, down: u64| {
+        json!({
+            "connections": [{
+                "id": "c1",
+                "upload": up,
+                "download": down,
+                "chains": ["香港 01", "手动切换"]
+            }]
+        })
+    };
+
+    observe_frame(&mut tracker, &mut accumulator, &frame(100, 200));
+    observe_frame(&mut tracker, &mut accumulator, &frame(150, 260));
+
+    let deltas = take_all(&mut accumulator);
+    let node = find_delta(&deltas, TrafficDimension::Node, "香港 01").unwrap();
+    let group = find_delta(&deltas, TrafficDimension::Group, "手动切换").unwrap();
+    assert_eq!((node.upload, node.download), (150, 260));
+    assert_eq!((group.upload, group.download), (150, 260));
+}
+
+#[test]
+fn observe_connections_should_forget_closed_connections() {
+    let mut tracker = ConnectionDeltaTracker::default();
+    let mut accumulator = TrafficAccumulator::default();
+    observe_frame(
+        &mut tracker,
+        &mut accumulator,
+        &json!({
+            "connections": [{"id": "c1", "upload": 10, "download": 10, "chains": ["direct"]}]
+        }),
+    );
+    observe_frame(&mut tracker, &mut accumulator, &json!({"connections": []}));
+    take_all(&mut accumulator);
+
+    // 同一 id 再次出现时按新连接完整计入
+    observe_frame(
+        &mut tracker,
+        &mut accumulator,
+        &json!({
+            "connections": [{"id": "c1", "upload": 5, "download": 7, "chains": ["direct"]}]
+        }),
+    );
+    let deltas = take_all(&mut accumulator);
+    let node = find_delta(&deltas, TrafficDimension::Node, "direct").unwrap();
+    assert_eq!((node.upload, node.download), (5, 7));
+    assert!(find_delta(&deltas, TrafficDimension::Group, "direct").is_none());
+}
+
+#[test]
+fn take_deltas_should_keep_samples_in_the_bucket_they_were_recorded_in() {
+    let mut accumulator = TrafficAccumulator::default();
+    let before_hour = (3600, 0);
+    let after_hour = (7200, 0);
+    accumulator.observe_traffic_at(before_hour, &json!({"up": 10, "down": 100}));
+    accumulator.observe_traffic_at(after_hour, &json!({"up": 1, "down": 2}));
+    accumulator.observe_traffic_at(before_hour, &json!({"up": 5, "down": 50}));
+
+    let batches = accumulator.take_deltas();
+    assert_eq!(batches.len(), 2);
+    assert_eq!(batches[0].buckets, before_hour);
+    assert_eq!(batches[1].buckets, after_hour);
+    let early = find_delta(&batches[0].deltas, TrafficDimension::Total, "total").unwrap();
+    let late = find_delta(&batches[1].deltas, TrafficDimension::Total, "total").unwrap();
+    assert_eq!((early.upload, early.download), (15, 150));
+    assert_eq!((late.upload, late.download), (1, 2));
+}
+
+#[test]
+fn restore_should_merge_unwritten_deltas_back_into_their_buckets() {
+    let mut accumulator = TrafficAccumulator::default();
+    accumulator.observe_traffic_at((3600, 0), &json!({"up": 10, "down": 100}));
+    let batches = accumulator.take_deltas();
+
+    accumulator.observe_traffic_at((3600, 0), &json!({"up": 1, "down": 1}));
+    accumulator.restore(batches);
+
+    let batches = accumulator.take_deltas();
+    assert_eq!(batches.len(), 1);
+    let total = find_delta(&batches[0].deltas, TrafficDimension::Total, "total").unwrap();
+    assert_eq!((total.upload, total.download), (11, 101));
+}
+
+#[test]
+fn connection_chain_labels_should_split_node_and_outer_group() {
+    let conn = json!({"chains": ["节点A", "自动选择", "手动切换"]});
+    assert_eq!(
+        connection_chain_labels(&conn),
+        Some(("节点A".to_string(), Some("手动切换".to_string())))
+    );
+    assert_eq!(connection_chain_labels(&json!({"chains": []})), None);
+}
+
+#[test]
+fn subscription_delta_should_only_count_subscription_nodes() {
+    let delta = |dimension, name: &str, upload, download| TrafficDelta {
+        dimension,
+        name: name.to_string(),
+        upload,
+        download,
+    };
+    let deltas = vec![
+        delta(TrafficDimension::Total, "total", 1000, 10_000),
+        delta(TrafficDimension::Node, "香港 01", 100, 1000),
+        delta(TrafficDimension::Node, "日本 01", 10, 100),
+        delta(TrafficDimension::Node, "direct", 500, 5000),
+        delta(TrafficDimension::Group, "香港 01", 999, 999),
+    ];
+    let node_tags: HashSet<String> = ["香港 01", "日本 01"]
+        .iter()
+        .map(|tag| tag.to_string())
+        .collect();
+
+    let sub = subscription_delta(&deltas, "机场", &node_tags).unwrap();
+    assert_eq!(sub.dimension, TrafficDimension::Subscription);
+    assert_eq!(sub.name, "机场");
+    assert_eq!((sub.upload, sub.download), (110, 1100));
+
+    let direct_only = vec![delta(TrafficDimension::Node, "direct", 1, 1)];
+    assert!(subscription_delta(&direct_only, "机场", &node_tags).is_none());
+}
@@ -0,0 +1,507 @@
+//! TUN 启动前的地址冲突检查
+//!
+//! Docker、WSL、Hyper-V 与公司 VPN 经常占用 172.16/12 一带的网段，而 TUN 默认使用
+//! `172.19.0.1/30`。启动 TUN 前枚举本机网卡地址与路由，检查 TUN 地址、Fake IP 地址池与
+//! 排除路由之间以及与本机网段的重叠：TUN 地址冲突时本次启动自动换用空闲网段，
+//! 无法规避时拒绝启动，由调用方上报 `TunAddressConflict` 诊断。
+
+use crate::app::core::tun_profile::{
+    carve_out_cidrs, cidrs_overlap, is_default_interface_name, parse_cidr,
+    resolve_tun_route_exclude_address, TunProxyOptions, DEFAULT_TUN_IPV6,
+};
+use crate::app::storage::enhanced_storage_service::db_get_app_config;
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use tauri::AppHandle;
+use tracing::warn;
+
+/// TUN 地址冲突时依次尝试的 IPv4 地址池，按 /30 切分
+const IPV4_CANDIDATE_POOLS: &[(Ipv4Addr, u8)] = &[
+    (Ipv4Addr::new(172, 19, 0, 0), 16),
+    (Ipv4Addr::new(10, 255, 0, 0), 16),
+    (Ipv4Addr::new(192, 168, 255, 0), 24),
+];
+/// IPv6 候选地址在默认 /48 下按子网号递增尝试的次数
+const IPV6_CANDIDATE_SUBNETS: u16 = 256;
+/// 比它更短的前缀视为默认路由的拆分（如 VPN 的 0.0.0.0/1、128.0.0.0/1、::/1），不算本机网段
+const MIN_HOST_NETWORK_PREFIX: u8 = 8;
+
+/// 本机网卡地址或路由所占用的网段
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HostNetwork {
+    pub interface: String,
+    pub cidr: String,
+}
+
+impl HostNetwork {
+    fn new(interface: &str, cidr: String) -> Self {
+        Self {
+            interface: interface.to_string(),
+            cidr,
+        }
+    }
+}
+
+/// 无法自动规避、需要用户处理的冲突
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TunConflict {
+    /// Fake IP 地址池与本机网段重叠，这些真实地址将无法访问
+    FakeIpOverlapsHost { range: String, network: HostNetwork },
+    /// Fake IP 地址池被排除路由覆盖，fakeip 流量不会进入 TUN
+    FakeIpExcluded { range: String, exclude: String },
+    /// TUN 地址与本机网段冲突，且候选地址池中没有空闲网段
+    NoFreeTunAddress {
+        address: String,
+        network: HostNetwork,
+    },
+    /// 排除路由与 TUN 地址所在网段相同或更小，TUN 网段自身的路由被排除
+    TunAddressExcluded { address: String, exclude: String },
+}
+
+impl fmt::Display for TunConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::FakeIpOverlapsHost { range, network } => write!(
+                f,
+                "Fake IP 地址池 {} 与网卡 {} 的网段 {} 重叠",
+                range, network.interface, network.cidr
+            ),
+            Self::FakeIpExcluded { range, exclude } => write!(
+                f,
+                "Fake IP 地址池 {} 被排除路由 {} 覆盖，fakeip 流量不会进入 TUN",
+                range, exclude
+            ),
+            Self::NoFreeTunAddress { address, network } => write!(
+                f,
+                "TUN 地址 {} 与网卡 {} 的网段 {} 重叠，且没有可用的替代网段",
+                address, network.interface, network.cidr
+            ),
+            Self::TunAddressExcluded { address, exclude } => {
+                write!(f, "TUN 地址 {} 被排除路由 {} 覆盖", address, exclude)
+            }
+        }
+    }
+}
+
+/// 检查结果：记录本次启动自动替换的 TUN 地址
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TunPreflightReport {
+    /// (原地址, 替换后的地址)
+    pub replaced: Vec<(String, String)>,
+}
+
+/// 检查 TUN 配置与本机网络的冲突，必要时直接改写 `options` 中的 TUN 地址
+pub fn check_tun_conflicts(
+    options: &mut TunProxyOptions,
+    fake_ip_ranges: &[String],
+    host_networks: &[HostNetwork],
+) -> Result<TunPreflightReport, TunConflict> {
+    let own_interface = options
+        .interface_name
+        .as_deref()
+        .map(str::trim)
+        .filter(|name| !name.is_empty());
+    // 上次异常退出残留的 TUN 网卡不算冲突；未指定网卡名时按平台默认命名识别（macOS 为随机的 utunN）
+    let is_own_interface = |interface: &str| match own_interface {
+        Some(name) => interface == name,
+        None => is_default_interface_name(interface),
+    };
+    let networks: Vec<(HostNetwork, (IpAddr, u8))> = host_networks
+        .iter()
+        .filter(|network| !is_own_interface(&network.interface))
+        .filter_map(|network| {
+            let parsed = parse_cidr(&network.cidr).ok()?;
+            is_relevant_network(parsed).then(|| (network.clone(), parsed))
+        })
+        .collect();
+    // 与生成配置时一致：含默认值与预设的最终排除网段，并已挖掉 Fake IP 地址池
+    let route_address = options.route_address.clone().unwrap_or_default();
+    let excludes: Vec<(String, (IpAddr, u8))> = carve_out_cidrs(
+        resolve_tun_route_exclude_address(
+            options.route_exclude_address.clone(),
+            &route_address,
+            &options.route_presets,
+        ),
+        fake_ip_ranges,
+    )
+    .into_iter()
+    .filter_map(|exclude| {
+        let parsed = parse_cidr(exclude.trim()).ok()?;
+        Some((exclude, parsed))
+    })
+    .collect();
+    let fake_ip_ranges: Vec<(String, (IpAddr, u8))> = fake_ip_ranges
+        .iter()
+        .filter_map(|range| Some((range.clone(), parse_cidr(range.trim()).ok()?)))
+        .collect();
+
+    for (range, parsed) in &fake_ip_ranges {
+        if let Some((network, _)) = networks
+            .iter()
+            .find(|(_, host)| cidrs_overlap(*parsed, *host))
+        {
+            return Err(TunConflict::FakeIpOverlapsHost {
+                range: range.clone(),
+                network: network.clone(),
+            });
+        }
+        if let Some((exclude, _)) = excludes
+            .iter()
+            .find(|(_, exclude)| cidrs_overlap(*exclude, *parsed))
+        {
+            return Err(TunConflict::FakeIpExcluded {
+                range: range.clone(),
+                exclude: exclude.clone(),
+            });
+        }
+    }
+
+    let occupied: Vec<(IpAddr, u8)> = networks
+        .iter()
+        .map(|(_, parsed)| *parsed)
+        .chain(fake_ip_ranges.iter().map(|(_, parsed)| *parsed))
+        .collect();
+    let find_conflict = |address: &str| {
+        let parsed = parse_cidr(address).ok()?;
+        networks
+            .iter()
+            .find(|(_, host)| cidrs_overlap(parsed, *host))
+            .map(|(network, _)| network.clone())
+            .or_else(|| {
+                fake_ip_ranges
+                    .iter()
+                    .find(|(_, range)| cidrs_overlap(parsed, *range))
+                    .map(|(range, _)| HostNetwork::new("fakeip", range.clone()))
+            })
+    };
+
+    let mut report = TunPreflightReport::default();
+    if let Some(network) = find_conflict(&options.ipv4_address) {
+        let replacement =
+            pick_free_ipv4(&occupied).ok_or_else(|| TunConflict::NoFreeTunAddress {
+                address: options.ipv4_address.clone(),
+                network,
+            })?;
+        report
+            .replaced
+            .push((options.ipv4_address.clone(), replacement.clone()));
+        options.ipv4_address = replacement;
+    }
+    if options.enable_ipv6 {
+        if let Some(network) = find_conflict(&options.ipv6_address) {
+            let replacement =
+                pick_free_ipv6(&occupied).ok_or_else(|| TunConflict::NoFreeTunAddress {
+                    address: options.ipv6_address.clone(),
+                    network,
+                })?;
+            report
+                .replaced
+                .push((options.ipv6_address.clone(), replacement.clone()));
+            options.ipv6_address = replacement;
+        }
+    }
+
+    let mut tun_addresses = vec![&options.ipv4_address];
+    if options.enable_ipv6 {
+        tun_addresses.push(&options.ipv6_address);
+    }
+    for address in tun_addresses {
+        let Ok(parsed) = parse_cidr(address.trim()) else {
+            continue;
+        };
+        // 更宽的排除网段（如默认的 172.16.0.0/12）不影响 TUN 网段本身更具体的链路路由
+        if let Some((exclude, _)) = excludes
+            .iter()
+            .find(|(_, exclude)| exclude.1 >= parsed.1 && cidrs_overlap(*exclude, parsed))
+        {
+            return Err(TunConflict::TunAddressExcluded {
+                address: address.clone(),
+                exclude: exclude.clone(),
+            });
+        }
+    }
+
+    Ok(report)
+}
+
+/// 默认路由及其拆分、回环与链路本地地址和 TUN 不构成冲突
+fn is_relevant_network((ip, prefix): (IpAddr, u8)) -> bool {
+    if prefix < MIN_HOST_NETWORK_PREFIX {
+        return false;
+    }
+    match ip {
+        IpAddr::V4(ip) => !ip.is_loopback() && !ip.is_link_local(),
+        IpAddr::V6(ip) => !ip.is_loopback() && (ip.segments()[0] & 0xffc0) != 0xfe80,
+    }
+}
+
+fn pick_free_ipv4(occupied: &[(IpAddr, u8)]) -> Option<String> {
+    IPV4_CANDIDATE_POOLS.iter().find_map(|(base, prefix)| {
+        let base = u32::from(*base);
+        let blocks = 1u32 << (30 - *prefix as u32);
+        (0..blocks).find_map(|block| {
+            let network = Ipv4Addr::from(base + block * 4);
+            let candidate = (IpAddr::V4(network), 30);
+            (!occupied.iter().any(|used| cidrs_overlap(candidate, *used)))
+                .then(|| format!("{}/30", Ipv4Addr::from(u32::from(network) + 1)))
+        })
+    })
+}
+
+fn pick_free_ipv6(occupied: &[(IpAddr, u8)]) -> Option<String> {
+    let (default_ip, _) = parse_cidr(DEFAULT_TUN_IPV6).ok()?;
+    let IpAddr::V6(default_ip) = default_ip else {
+        return None;
+    };
+    let segments = default_ip.segments();
+    (1..IPV6_CANDIDATE_SUBNETS).find_map(|subnet| {
+        let network = Ipv6Addr::new(segments[0], segments[1], segments[2], subnet, 0, 0, 0, 0);
+        let candidate = (IpAddr::V6(network), 126);
+        (!occupied.iter().any(|used| cidrs_overlap(candidate, *used))).then(|| {
+            let mut address = network.segments();
+            address[7] = 1;
+            format!("{}/126", Ipv6Addr::from(address))
+        })
+    })
+}
+
+/// `ip -o addr show` 输出：`2: eth0    inet 192.168.1.10/24 brd ... scope global eth0`
+pub fn parse_ip_addr_output(output: &str) -> Vec<HostNetwork> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let interface = fields.nth(1)?.trim_end_matches(':');
+            let family = fields.next()?;
+            if family != "inet" && family != "inet6" {
+                return None;
+            }
+            let cidr = fields.next()?;
+            Some(HostNetwork::new(interface, cidr.to_string()))
+        })
+        .collect()
+}
+
+/// `/proc/net/route`：目标与掩码是按本机字节序打印的网络序 u32
+pub fn parse_proc_net_route(content: &str) -> Vec<HostNetwork> {
+    content
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let interface = fields.first()?;
+            let destination = u32::from_str_radix(fields.get(1)?, 16).ok()?;
+            let mask = u32::from_str_radix(fields.get(7)?, 16).ok()?;
+            let destination = Ipv4Addr::from(destination.to_ne_bytes());
+            let prefix = mask.count_ones();
+            Some(HostNetwork::new(
+                interface,
+                format!("{}/{}", destination, prefix),
+            ))
+        })
+        .collect()
+}
+
+/// `/proc/net/ipv6_route`：目标地址为 32 位十六进制，前缀长度为十六进制
+pub fn parse_proc_net_ipv6_route(content: &str) -> Vec<HostNetwork> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let destination = u128::from_str_radix(fields.first()?, 16).ok()?;
+            let prefix = u8::from_str_radix(fields.get(1)?, 16).ok()?;
+            let interface = fields.last()?;
+            Some(HostNetwork::new(
+                interface,
+                format!("{}/{}", Ipv6Addr::from(destination), prefix),
+            ))
+        })
+        .collect()
+}
+
+/// PowerShell 输出的 `接口名|网段` 行，IPv6 地址可能带 `%zone` 后缀
+pub fn parse_powershell_networks(output: &str) -> Vec<HostNetwork> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (interface, cidr) = line.trim().rsplit_once('|')?;
+            let (address, prefix) = cidr.split_once('/')?;
+            let address = address.split('%').next()?;
+            Some(HostNetwork::new(
+                interface,
+                format!("{}/{}", address, prefix),
+            ))
+        })
+        .collect()
+}
+
+/// `ifconfig` 输出：网卡名顶格，`inet 1.2.3.4 netmask 0xffffff00` 与 `inet6 ... prefixlen 64`
+pub fn parse_ifconfig_output(output: &str) -> Vec<HostNetwork> {
+    let mut networks = Vec::new();
+    let mut interface = "";
+    for line in output.lines() {
+        if !line.starts_with(char::is_whitespace) {
+            if let Some((name, _)) = line.split_once(':') {
+                interface = name;
+            }
+            continue;
+        }
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let value_after = |key: &str| {
+            fields
+                .iter()
+                .position(|field| *field == key)
+                .and_then(|index| fields.get(index + 1))
+                .copied()
+        };
+        match fields.first() {
+            Some(&"inet") => {
+                let (Some(address), Some(mask)) = (value_after("inet"), value_after("netmask"))
+                else {
+                    continue;
+                };
+                let Ok(mask) = u32::from_str_radix(mask.trim_start_matches("0x"), 16) else {
+                    continue;
+                };
+                networks.push(HostNetwork::new(
+                    interface,
+                    format!("{}/{}", address, mask.count_ones()),
+                ));
+            }
+            Some(&"inet6") => {
+                let (Some(address), Some(prefix)) =
+                    (value_after("inet6"), value_after("prefixlen"))
+                else {
+                    continue;
+                };
+                let address = address.split('%').next().unwrap_or(address);
+                networks.push(HostNetwork::new(
+                    interface,
+                    format!("{}/{}", address, prefix),
+                ));
+            }
+            _ => {}
+        }
+    }
+    networks
+}
+
+#[cfg(target_os = "windows")]
+const WINDOWS_ADDRESS_SCRIPT: &str =
+    "Get-NetIPAddress | ForEach-Object { \"$($_.InterfaceAlias)|$($_.IPAddress)/$($_.PrefixLength)\" }";
+#[cfg(target_os = "windows")]
+const WINDOWS_ROUTE_SCRIPT: &str =
+    "Get-NetRoute | ForEach-Object { \"$($_.InterfaceAlias)|$($_.DestinationPrefix)\" }";
+
+/// 枚举本机网卡地址（不含路由），用于局域网分享等需要“本机 IP”的场景
+pub fn enumerate_interface_addresses() -> Vec<HostNetwork> {
+    #[cfg(target_os = "linux")]
+    {
+        run_command("ip", &["-o", "addr", "show"])
+            .map(|output| parse_ip_addr_output(&output))
+            .unwrap_or_default()
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        run_command(
+            "powershell",
+            &["-NoProfile", "-Command", WINDOWS_ADDRESS_SCRIPT],
+        )
+        .map(|output| parse_powershell_networks(&output))
+        .unwrap_or_default()
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        run_command("ifconfig", &[])
+            .map(|output| parse_ifconfig_output(&output))
+            .unwrap_or_default()
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "windows", target_os = "macos")))]
+    {
+        Vec::new()
+    }
+}
+
+/// 枚举本机网卡地址与路由；命令不可用时尽量返回已拿到的部分
+pub fn enumerate_host_networks() -> Vec<HostNetwork> {
+    let mut networks = Vec::new();
+
+    #[cfg(target_os = "linux")]
+    {
+        networks.extend(enumerate_interface_addresses());
+        if let Ok(content) = std::fs::read_to_string("/proc/net/route") {
+            networks.extend(parse_proc_net_route(&content));
+        }
+        if let Ok(content) = std::fs::read_to_string("/proc/net/ipv6_route") {
+            networks.extend(parse_proc_net_ipv6_route(&content));
+        }
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        // 合并为一次 PowerShell 调用，避免重复启动的开销
+        let script = format!("{}; {}", WINDOWS_ADDRESS_SCRIPT, WINDOWS_ROUTE_SCRIPT);
+        if let Some(output) = run_command("powershell", &["-NoProfile", "-Command", &script]) {
+            networks.extend(parse_powershell_networks(&output));
+        }
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        networks.extend(enumerate_interface_addresses());
+    }
+
+    networks
+}
+
+#[cfg(any(target_os = "linux", target_os = "windows", target_os = "macos"))]
+fn run_command(program: &str, args: &[&str]) -> Option<String> {
+    match crate::utils::process_util::create_hidden_command(program)
+        .args(args)
+        .output()
+    {
+        Ok(output) if output.status.success() => {
+            Some(String::from_utf8_lossy(&output.stdout).into_owned())
+        }
+        Ok(output) => {
+            warn!(
+                "枚举本机网络失败（{} 退出码 {:?}）",
+                program,
+                output.status.code()
+            );
+            None
+        }
+        Err(e) => {
+            warn!("枚举本机网络失败（无法执行 {}）: {}", program, e);
+            None
+        }
+    }
+}
+
+/// TUN 启动路径上的检查入口
+pub async fn run_tun_preflight(
+    app_handle: &AppHandle,
+    options: &mut TunProxyOptions,
+) -> Result<TunPreflightReport, TunConflict> {
+    // 与写入配置时一样补齐路由预设、排除网段与 Fake IP 地址池
+    match db_get_app_config(app_handle.clone()).await {
+        Ok(app_config) => options.hydrate_from_app_config(&app_config),
+        Err(e) => warn!("读取应用配置失败，跳过 Fake IP 冲突检查: {}", e),
+    }
+    let fake_ip_ranges = options.fake_ip_ranges.clone();
+    let host_networks = tokio::task::spawn_blocking(enumerate_host_networks)
+        .await
+        .unwrap_or_else(|e| {
+            warn!("枚举本机网络任务异常: {}", e);
+            Vec::new()
+        });
+
+    check_tun_conflicts(options, &fake_ip_ranges, &host_networks)
+}
+
+#[cfg(test)]
+#[path = "tun_preflight.tests.rs"]
+mod tests;
@@ -0,0 +1,323 @@
+use super::*;
+use crate::app::core::tun_profile::{
+    default_interface_name, default_tun_route_exclude_addresses, TunRoutePreset,
+};
+
+fn host(interface: &str, cidr: &str) -> HostNetwork {
+    HostNetwork::new(interface, cidr.to_string())
+}
+
+#[test]
+fn test_no_conflict_keeps_tun_address() {
+    let mut options = TunProxyOptions::default();
+    let report = check_tun_conflicts(
+        &mut options,
+        &["198.18.0.0/15".to_string()],
+        &[host("eth0", "192.168.1.10/24"), host("eth0", "0.0.0.0/0")],
+    )
+    .expect("no conflict");
+
+    assert!(report.replaced.is_empty());
+    assert_eq!(options.ipv4_address, "172.19.0.1/30");
+}
+
+#[test]
+fn test_docker_bridge_overlap_picks_free_address() {
+    let mut options = TunProxyOptions {
+        enable_ipv6: false,
+        ..TunProxyOptions::default()
+    };
+    let report = check_tun_conflicts(&mut options, &[], &[host("br-1a2b", "172.19.0.1/16")])
+        .expect("replacement available");
+
+    assert_eq!(options.ipv4_address, "10.255.0.1/30");
+    assert_eq!(
+        report.replaced,
+        vec![("172.19.0.1/30".to_string(), "10.255.0.1/30".to_string())]
+    );
+}
+
+#[test]
+fn test_partial_overlap_uses_next_free_block() {
+    let mut options = TunProxyOptions {
+        enable_ipv6: false,
+        ..TunProxyOptions::default()
+    };
+    check_tun_conflicts(&mut options, &[], &[host("wsl", "172.19.0.0/29")])
+        .expect("replacement available");
+
+    assert_eq!(options.ipv4_address, "172.19.0.9/30");
+}
+
+#[test]
+fn test_own_interface_and_loopback_are_ignored() {
+    let mut options = TunProxyOptions::default();
+    let report = check_tun_conflicts(
+        &mut options,
+        &[],
+        &[
+            host(&default_interface_name(), "172.19.0.1/30"),
+            host("lo", "127.0.0.1/8"),
+            host("eth0", "fe80::1/64"),
+        ],
+    )
+    .expect("no conflict");
+
+    assert!(report.replaced.is_empty());
+}
+
+#[test]
+fn test_explicit_interface_name_replaces_default_match() {
+    let mut options = TunProxyOptions {
+        enable_ipv6: false,
+        interface_name: Some("tun-custom".to_string()),
+        ..TunProxyOptions::default()
+    };
+    let report = check_tun_conflicts(
+        &mut options,
+        &[],
+        &[
+            host("tun-custom", "172.19.0.1/30"),
+            host(&default_interface_name(), "172.19.0.0/29"),
+        ],
+    )
+    .expect("replacement available");
+
+    assert_eq!(
+        report.replaced,
+        vec![("172.19.0.1/30".to_string(), "172.19.0.9/30".to_string())]
+    );
+}
+
+#[test]
+fn test_split_default_routes_are_ignored() {
+    let mut options = TunProxyOptions::default();
+    let report = check_tun_conflicts(
+        &mut options,
+        &["198.18.0.0/15".to_string(), "fc00::/18".to_string()],
+        &[
+            host("utun3", "0.0.0.0/1"),
+            host("utun3", "128.0.0.0/1"),
+            host("utun3", "::/1"),
+            host("utun3", "8000::/1"),
+        ],
+    )
+    .expect("no conflict");
+
+    assert!(report.replaced.is_empty());
+    assert_eq!(options.ipv4_address, "172.19.0.1/30");
+}
+
+#[test]
+fn test_ipv6_conflict_is_only_checked_when_enabled() {
+    let networks = [host("vpn0", "fdfe:dcba:9876::/64")];
+
+    let mut disabled = TunProxyOptions {
+        enable_ipv6: false,
+        ..TunProxyOptions::default()
+    };
+    check_tun_conflicts(&mut disabled, &[], &networks).expect("ipv6 disabled");
+    assert_eq!(disabled.ipv6_address, DEFAULT_TUN_IPV6);
+
+    let mut enabled = TunProxyOptions::default();
+    check_tun_conflicts(&mut enabled, &[], &networks).expect("replacement available");
+    assert_eq!(enabled.ipv6_address, "fdfe:dcba:9876:1::1/126");
+}
+
+#[test]
+fn test_fake_ip_conflicts_are_refused() {
+    let mut options = TunProxyOptions::default();
+    let err = check_tun_conflicts(
+        &mut options,
+        &["198.18.0.0/15".to_string()],
+        &[host("corp-vpn", "198.19.10.0/24")],
+    )
+    .expect_err("fakeip overlaps host");
+    assert!(matches!(err, TunConflict::FakeIpOverlapsHost { .. }));
+    assert!(err.to_string().contains("corp-vpn"));
+
+    let mut options = TunProxyOptions {
+        route_exclude_address: Some(vec!["198.18.0.0/16".to_string()]),
+        ..TunProxyOptions::default()
+    };
+    let err = check_tun_conflicts(&mut options, &["198.18.0.0/15".to_string()], &[])
+        .expect_err("fakeip excluded");
+    assert_eq!(
+        err,
+        TunConflict::FakeIpExcluded {
+            range: "198.18.0.0/15".to_string(),
+            exclude: "198.18.0.0/16".to_string(),
+        }
+    );
+}
+
+#[test]
+fn test_default_excludes_do_not_swallow_fake_ip_ranges() {
+    let fake_ip_ranges = ["198.18.0.0/15".to_string(), "fc00::/18".to_string()];
+
+    // 默认排除的 fc00::/7 覆盖 fc00::/18，按生成配置时的方式挖掉后不算冲突
+    let mut options = TunProxyOptions::default();
+    check_tun_conflicts(&mut options, &fake_ip_ranges, &[]).expect("default excludes");
+
+    // 显式保存同一份默认列表并叠加预设，结果一致
+    let mut options = TunProxyOptions {
+        route_exclude_address: Some(default_tun_route_exclude_addresses()),
+        route_presets: vec![TunRoutePreset::BypassLanTailscale],
+        ..TunProxyOptions::default()
+    };
+    check_tun_conflicts(&mut options, &fake_ip_ranges, &[]).expect("saved default excludes");
+}
+
+#[test]
+fn test_presets_are_checked_against_fake_ip_ranges() {
+    let mut options = TunProxyOptions {
+        route_address: Some(vec!["10.20.0.0/16".to_string()]),
+        route_presets: vec![TunRoutePreset::BypassLanTailscale],
+        ..TunProxyOptions::default()
+    };
+    let err = check_tun_conflicts(&mut options, &["100.64.0.0/10".to_string()], &[])
+        .expect_err("preset excludes fakeip");
+
+    assert!(matches!(err, TunConflict::FakeIpExcluded { .. }));
+}
+
+#[test]
+fn test_tun_address_inside_exclude_is_refused() {
+    let mut options = TunProxyOptions {
+        enable_ipv6: false,
+        route_exclude_address: Some(vec!["172.19.0.0/30".to_string()]),
+        ..TunProxyOptions::default()
+    };
+    let err = check_tun_conflicts(&mut options, &[], &[]).expect_err("tun address excluded");
+
+    assert_eq!(
+        err,
+        TunConflict::TunAddressExcluded {
+            address: "172.19.0.1/30".to_string(),
+            exclude: "172.19.0.0/30".to_string(),
+        }
+    );
+
+    // 默认的 172.16.0.0/12 比 TUN 网段宽，不影响 TUN 网段本身的路由
+    let mut options = TunProxyOptions {
+        enable_ipv6: false,
+        ..TunProxyOptions::default()
+    };
+    check_tun_conflicts(&mut options, &[], &[]).expect("wider exclude");
+}
+
+#[test]
+fn test_tun_address_avoids_fake_ip_range() {
+    let mut options = TunProxyOptions {
+        ipv4_address: "198.18.0.1/30".to_string(),
+        enable_ipv6: false,
+        ..TunProxyOptions::default()
+    };
+    check_tun_conflicts(&mut options, &["198.18.0.0/15".to_string()], &[])
+        .expect("replacement available");
+
+    assert_eq!(options.ipv4_address, "172.19.0.1/30");
+}
+
+#[test]
+fn test_no_free_address_is_refused() {
+    let mut options = TunProxyOptions {
+        enable_ipv6: false,
+        ..TunProxyOptions::default()
+    };
+    let err = check_tun_conflicts(
+        &mut options,
+        &[],
+        &[
+            host("a", "172.16.0.0/12"),
+            host("b", "10.0.0.0/8"),
+            host("c", "192.168.0.0/16"),
+        ],
+    )
+    .expect_err("all pools occupied");
+
+    assert!(matches!(err, TunConflict::NoFreeTunAddress { .. }));
+    assert_eq!(options.ipv4_address, "172.19.0.1/30");
+}
+
+#[test]
+fn test_parse_ip_addr_output() {
+    let output = [
+        "1: lo    inet 127.0.0.1/8 scope host lo\\       valid_lft forever",
+        "3: docker0    inet 172.17.0.1/16 brd 172.17.255.255 scope global docker0",
+        "3: docker0    inet6 fe80::42:acff:fe11:1/64 scope link ",
+        "4: wlan0    link/ether 00:11:22:33:44:55 brd ff:ff:ff:ff:ff:ff",
+    ]
+    .join("\n");
+
+    assert_eq!(
+        parse_ip_addr_output(&output),
+        vec![
+            host("lo", "127.0.0.1/8"),
+            host("docker0", "172.17.0.1/16"),
+            host("docker0", "fe80::42:acff:fe11:1/64"),
+        ]
+    );
+}
+
+#[test]
+#[cfg(target_endian = "little")]
+fn test_parse_proc_net_route() {
+    let content = [
+        "Iface\tDestination\tGateway \tFlags\tRefCnt\tUse\tMetric\tMask\t\tMTU\tWindow\tIRTT",
+        "eth0\t00000000\t0101A8C0\t0003\t0\t0\t100\t00000000\t0\t0\t0",
+        "tailscale0\t0000400A\t00000000\t0001\t0\t0\t0\t0000C0FF\t0\t0\t0",
+    ]
+    .join("\n");
+
+    assert_eq!(
+        parse_proc_net_route(&content),
+        vec![
+            host("eth0", "0.0.0.0/0"),
+            host("tailscale0", "10.64.0.0/10"),
+        ]
+    );
+}
+
+#[test]
+fn test_parse_proc_net_ipv6_route() {
+    let content = "fd7a115ca1e0ab120000000000000000 30 00000000000000000000000000000000 00 00000000000000000000000000000000 00000000 00000001 00000000 00000001 tailscale0\n";
+
+    assert_eq!(
+        parse_proc_net_ipv6_route(content),
+        vec![host("tailscale0", "fd7a:115c:a1e0:ab12::/48")]
+    );
+}
+
+#[test]
+fn test_parse_powershell_networks() {
+    let output = "vEthernet (WSL)|172.19.144.1/20\r\n\
+                  以太网|fe80::1234%12/64\r\n\
+                  garbage line\r\n";
+
+    assert_eq!(
+        parse_powershell_networks(output),
+        vec![
+            host("vEthernet (WSL)", "172.19.144.1/20"),
+            host("以太网", "fe80::1234/64"),
+        ]
+    );
+}
+
+#[test]
+fn test_parse_ifconfig_output() {
+    let output = "en0: flags=8863<UP,BROADCAST,SMART,RUNNING,SIMPLEX,MULTICAST> mtu 1500\n\
+                  \tinet6 fe80::1c%en0 prefixlen 64 secured scopeid 0x6\n\
+                  \tinet 192.168.1.5 netmask 0xffffff00 broadcast 192.168.1.255\n\
+                  utun4: flags=8051<UP,POINTOPOINT,RUNNING,MULTICAST> mtu 1380\n\
+                  \tinet 100.64.0.2 --> 100.64.0.2 netmask 0xffc00000\n";
+
+    assert_eq!(
+        parse_ifconfig_output(output),
+        vec![
+            host("en0", "fe80::1c/64"),
+            host("en0", "192.168.1.5/24"),
+            host("utun4", "100.64.0.2/10"),
+        ]
+    );
+}
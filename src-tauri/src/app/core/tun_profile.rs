@@ -1,12 +1,23 @@
-use crate::app::singbox::common::PRIVATE_IP_CIDRS;
+use crate::app::singbox::common::{PRIVATE_IP_CIDRS, RS_GEOIP_CN};
+use crate::app::storage::state_model::AppConfig;
 use crate::entity::config_model;
 use serde::{Deserialize, Serialize};
-use std::net::IpAddr;
+use serde_json::{json, Value};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use ts_rs::TS;
 
 /// 默认的 TUN IPv4 地址段
 pub const DEFAULT_TUN_IPV4: &str = "172.19.0.1/30";
 /// 默认的 TUN IPv6 地址段
 pub const DEFAULT_TUN_IPV6: &str = "fdfe:dcba:9876::1/126";
+/// TUN 入站的 tag
+pub const TUN_INBOUND_TAG: &str = "tun-in";
+/// Linux 网卡名最大长度（IFNAMSIZ - 1）
+const MAX_INTERFACE_NAME_LEN: usize = 15;
+/// Tailscale 使用的 CGNAT 与 ULA 地址段
+const TAILSCALE_CIDRS: &[&str] = &["100.64.0.0/10", "fd7a:115c:a1e0::/48"];
+/// Docker / Podman / WSL2 常见的桥接网段
+const CONTAINER_BRIDGE_CIDRS: &[&str] = &["172.16.0.0/12", "10.88.0.0/16", "192.168.65.0/24"];
 /// 默认排除的 TUN 路由网段。
 ///
 /// 与 `PRIVATE_IP_CIDRS` 共享同一份 canonical 列表，避免 TUN 默认值和直连私网规则漂移。
@@ -17,15 +28,214 @@ pub fn default_tun_route_exclude_addresses() -> Vec<String> {
         .collect()
 }
 
+/// TUN 路由预设：一键追加常见的不走 TUN 的目标
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../src/types/generated/TunRoutePreset.ts")]
+#[serde(rename_all = "snake_case")]
+pub enum TunRoutePreset {
+    /// 中国大陆 IP 不走 TUN（使用 geoip-cn 规则集）
+    BypassCn,
+    /// 局域网与 Tailscale CGNAT 不走 TUN
+    BypassLanTailscale,
+    /// Docker / WSL 桥接网段不走 TUN
+    BypassContainerBridges,
+}
+
+impl TunRoutePreset {
+    pub fn exclude_addresses(self) -> Vec<&'static str> {
+        match self {
+            Self::BypassCn => Vec::new(),
+            Self::BypassLanTailscale => PRIVATE_IP_CIDRS
+                .iter()
+                .chain(TAILSCALE_CIDRS)
+                .copied()
+                .collect(),
+            Self::BypassContainerBridges => CONTAINER_BRIDGE_CIDRS.to_vec(),
+        }
+    }
+
+    /// 写入 `route_exclude_address_set` 的规则集 tag
+    pub fn exclude_rule_sets(self) -> &'static [&'static str] {
+        match self {
+            Self::BypassCn => &[RS_GEOIP_CN],
+            Self::BypassLanTailscale | Self::BypassContainerBridges => &[],
+        }
+    }
+}
+
+/// 计算最终的 TUN 排除地址。
+///
+/// 未显式配置时：只路由指定网段（`route_address` 非空）的情况下不再追加默认私网排除，
+/// 否则沿用 [`default_tun_route_exclude_addresses`]；随后合并预设中的网段。
+pub fn resolve_tun_route_exclude_address(
+    explicit: Option<Vec<String>>,
+    route_address: &[String],
+    presets: &[TunRoutePreset],
+) -> Vec<String> {
+    let mut resolved = explicit.unwrap_or_else(|| {
+        if route_address.is_empty() {
+            default_tun_route_exclude_addresses()
+        } else {
+            Vec::new()
+        }
+    });
+    for cidr in presets.iter().flat_map(|preset| preset.exclude_addresses()) {
+        if !resolved.iter().any(|existing| existing == cidr) {
+            resolved.push(cidr.to_string());
+        }
+    }
+    resolved
+}
+
+/// 启用 Fake DNS 时的地址池
+pub fn configured_fake_ip_ranges(app_config: &AppConfig) -> Vec<String> {
+    if !app_config.singbox_fake_dns_enabled {
+        return Vec::new();
+    }
+    [
+        &app_config.singbox_fake_dns_ipv4_range,
+        &app_config.singbox_fake_dns_ipv6_range,
+    ]
+    .into_iter()
+    .map(|range| range.trim().to_string())
+    .filter(|range| !range.is_empty())
+    .collect()
+}
+
+/// 从排除网段中挖掉 `keep` 覆盖的地址。
+///
+/// 默认排除的 `fc00::/7` 覆盖了 Fake IP 的 `fc00::/18`，整段排除会让 fakeip 流量绕过 TUN。
+/// 严格覆盖 `keep` 的排除网段被拆成不含 `keep` 的若干子网段；与 `keep` 相同或更小的
+/// 排除网段原样保留，由调用方按冲突处理。
+pub fn carve_out_cidrs(excludes: Vec<String>, keep: &[String]) -> Vec<String> {
+    let keep: Vec<(IpAddr, u8)> = keep
+        .iter()
+        .filter_map(|cidr| parse_cidr(cidr.trim()).ok())
+        .collect();
+    if keep.is_empty() {
+        return excludes;
+    }
+
+    let mut carved: Vec<String> = Vec::with_capacity(excludes.len());
+    for exclude in excludes {
+        let Ok(parsed) = parse_cidr(exclude.trim()) else {
+            carved.push(exclude);
+            continue;
+        };
+        let mut pieces = vec![parsed];
+        for kept in &keep {
+            pieces = pieces
+                .into_iter()
+                .flat_map(|piece| {
+                    if piece.1 < kept.1 && cidr_contains(piece, *kept) {
+                        split_cidr_around(piece, *kept)
+                    } else {
+                        vec![piece]
+                    }
+                })
+                .collect();
+        }
+        if pieces == [parsed] {
+            carved.push(exclude);
+        } else {
+            carved.extend(
+                pieces
+                    .into_iter()
+                    .map(|(ip, prefix)| format!("{}/{}", ip, prefix)),
+            );
+        }
+    }
+    carved
+}
+
+/// 把 `outer` 拆成不含 `inner` 的兄弟网段，前缀从 `outer` 的下一位到 `inner` 的前缀
+fn split_cidr_around(outer: (IpAddr, u8), inner: (IpAddr, u8)) -> Vec<(IpAddr, u8)> {
+    let (width, inner_bits) = match inner.0 {
+        IpAddr::V4(ip) => (32u32, u32::from(ip) as u128),
+        IpAddr::V6(ip) => (128u32, u128::from(ip)),
+    };
+    (outer.1 + 1..=inner.1)
+        .map(|prefix| {
+            let host_bits = width - prefix as u32;
+            let mask = (u128::MAX >> (128 - width)) & !((1u128 << host_bits) - 1);
+            let sibling = (inner_bits & mask) ^ (1u128 << host_bits);
+            let ip = if width == 32 {
+                IpAddr::V4(Ipv4Addr::from(sibling as u32))
+            } else {
+                IpAddr::V6(Ipv6Addr::from(sibling))
+            };
+            (ip, prefix)
+        })
+        .collect()
+}
+
+pub fn normalize_tun_route_presets(presets: Vec<TunRoutePreset>) -> Vec<TunRoutePreset> {
+    dedup_preserving_order(presets)
+}
+
+pub fn tun_route_exclude_rule_sets(presets: &[TunRoutePreset]) -> Vec<String> {
+    let mut rule_sets: Vec<String> = Vec::new();
+    for tag in presets.iter().flat_map(|preset| preset.exclude_rule_sets()) {
+        if !rule_sets.iter().any(|existing| existing == tag) {
+            rule_sets.push(tag.to_string());
+        }
+    }
+    rule_sets
+}
+
+/// 校验包含网段没有被排除网段整体覆盖，否则这些目标永远不会进入 TUN
+pub fn validate_tun_route_address_conflicts(
+    route_address: &[String],
+    route_exclude_address: &[String],
+) -> Result<(), String> {
+    for include in route_address {
+        let include_net = parse_cidr(include)?;
+        for exclude in route_exclude_address {
+            if cidr_contains(parse_cidr(exclude)?, include_net) {
+                return Err(format!(
+                    "路由地址 {} 被排除地址 {} 覆盖，不会进入 TUN",
+                    include, exclude
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+pub fn normalize_tun_route_address(
+    route_address: Option<Vec<String>>,
+) -> Result<Option<Vec<String>>, String> {
+    normalize_cidr_list(route_address)
+}
+
+pub fn normalize_persisted_tun_route_address(
+    route_address: Option<Vec<String>>,
+) -> Option<Vec<String>> {
+    match normalize_tun_route_address(route_address) {
+        Ok(normalized) => normalized,
+        Err(error) => {
+            tracing::warn!(
+                "检测到无效的已持久化 tun_route_address，已回退为 None: {}",
+                error
+            );
+            None
+        }
+    }
+}
+
 pub fn normalize_tun_route_exclude_address(
     route_exclude_address: Option<Vec<String>>,
 ) -> Result<Option<Vec<String>>, String> {
-    let Some(route_exclude_address) = route_exclude_address else {
+    normalize_cidr_list(route_exclude_address)
+}
+
+pub fn normalize_cidr_list(cidrs: Option<Vec<String>>) -> Result<Option<Vec<String>>, String> {
+    let Some(cidrs) = cidrs else {
         return Ok(None);
     };
 
-    let mut normalized = Vec::with_capacity(route_exclude_address.len());
-    for cidr in route_exclude_address {
+    let mut normalized = Vec::with_capacity(cidrs.len());
+    for cidr in cidrs {
         let trimmed = cidr.trim();
         if trimmed.is_empty() {
             continue;
@@ -60,6 +270,149 @@ pub fn normalize_persisted_tun_route_exclude_address(
     }
 }
 
+/// TUN 分流名单：按进程 / 用户 / 网卡决定哪些流量进入 TUN。
+///
+/// - 进程条目含路径分隔符时按 `process_path` 匹配，否则按 `process_name` 匹配，
+///   通过只作用于 tun-in 的 route 规则实现；
+/// - UID 与网卡写入 tun inbound 的 `include_*` / `exclude_*` 字段，仅 Linux 生效。
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../src/types/generated/TunSplitRules.ts")]
+#[serde(default)]
+pub struct TunSplitRules {
+    /// 非空时只有这些进程走 TUN，其余进程直连
+    pub include_process: Vec<String>,
+    /// 这些进程始终直连，例如公司 VPN 客户端、备份程序
+    pub exclude_process: Vec<String>,
+    pub include_uid: Vec<u32>,
+    pub exclude_uid: Vec<u32>,
+    pub include_interface: Vec<String>,
+    pub exclude_interface: Vec<String>,
+}
+
+impl TunSplitRules {
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// 生成进程分流的 route 规则，命中的流量走 `direct_outbound`
+    pub fn route_rules(&self, direct_outbound: &str) -> Vec<Value> {
+        let mut rules = Vec::new();
+
+        let (names, paths) = split_process_entries(&self.exclude_process);
+        if !names.is_empty() {
+            rules.push(json!({
+                "inbound": [TUN_INBOUND_TAG],
+                "process_name": names,
+                "outbound": direct_outbound
+            }));
+        }
+        if !paths.is_empty() {
+            rules.push(json!({
+                "inbound": [TUN_INBOUND_TAG],
+                "process_path": paths,
+                "outbound": direct_outbound
+            }));
+        }
+
+        // 包含名单：tun-in 上不属于名单内进程的流量全部直连
+        let (names, paths) = split_process_entries(&self.include_process);
+        if !names.is_empty() || !paths.is_empty() {
+            let mut conditions = vec![json!({ "inbound": [TUN_INBOUND_TAG] })];
+            if !names.is_empty() {
+                conditions.push(json!({ "process_name": names, "invert": true }));
+            }
+            if !paths.is_empty() {
+                conditions.push(json!({ "process_path": paths, "invert": true }));
+            }
+            rules.push(json!({
+                "type": "logical",
+                "mode": "and",
+                "rules": conditions,
+                "outbound": direct_outbound
+            }));
+        }
+
+        rules
+    }
+}
+
+/// 判断 route 规则是否由 [`TunSplitRules::route_rules`] 生成，便于重写前整体移除
+pub fn is_tun_split_route_rule(rule: &Value) -> bool {
+    let is_tun_only = |value: &Value| {
+        value
+            .get("inbound")
+            .and_then(|v| v.as_array())
+            .is_some_and(|inbound| {
+                inbound.len() == 1 && inbound[0].as_str() == Some(TUN_INBOUND_TAG)
+            })
+    };
+    let has_process =
+        |value: &Value| value.get("process_name").is_some() || value.get("process_path").is_some();
+
+    if rule.get("type").and_then(|v| v.as_str()) == Some("logical") {
+        let Some(conditions) = rule.get("rules").and_then(|v| v.as_array()) else {
+            return false;
+        };
+        return conditions.len() >= 2
+            && is_tun_only(&conditions[0])
+            && conditions[1..].iter().all(has_process);
+    }
+
+    is_tun_only(rule) && has_process(rule)
+}
+
+fn split_process_entries(entries: &[String]) -> (Vec<String>, Vec<String>) {
+    entries
+        .iter()
+        .cloned()
+        .partition(|entry| !entry.contains('/') && !entry.contains('\\'))
+}
+
+pub fn normalize_tun_split_rules(rules: TunSplitRules) -> Result<TunSplitRules, String> {
+    let normalized = TunSplitRules {
+        include_process: normalize_string_list(rules.include_process, validate_process_entry)?,
+        exclude_process: normalize_string_list(rules.exclude_process, validate_process_entry)?,
+        include_uid: dedup_preserving_order(rules.include_uid),
+        exclude_uid: dedup_preserving_order(rules.exclude_uid),
+        include_interface: normalize_string_list(rules.include_interface, validate_interface_name)?,
+        exclude_interface: normalize_string_list(rules.exclude_interface, validate_interface_name)?,
+    };
+
+    if let Some(process) = normalized
+        .include_process
+        .iter()
+        .find(|entry| normalized.exclude_process.contains(entry))
+    {
+        return Err(format!("进程不能同时出现在包含和排除列表中: {}", process));
+    }
+    if let Some(uid) = normalized
+        .include_uid
+        .iter()
+        .find(|uid| normalized.exclude_uid.contains(uid))
+    {
+        return Err(format!("UID 不能同时出现在包含和排除列表中: {}", uid));
+    }
+    // sing-box 不允许同时设置 include_interface 与 exclude_interface
+    if !normalized.include_interface.is_empty() && !normalized.exclude_interface.is_empty() {
+        return Err("包含网卡与排除网卡不能同时设置".to_string());
+    }
+
+    Ok(normalized)
+}
+
+pub fn normalize_persisted_tun_split_rules(rules: TunSplitRules) -> TunSplitRules {
+    match normalize_tun_split_rules(rules) {
+        Ok(normalized) => normalized,
+        Err(error) => {
+            tracing::warn!(
+                "检测到无效的已持久化 tun_split_rules，已回退为空规则: {}",
+                error
+            );
+            TunSplitRules::default()
+        }
+    }
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(default)]
 pub struct TunProxyOptions {
@@ -71,7 +424,30 @@ pub struct TunProxyOptions {
     pub stack: String,
     pub enable_ipv6: bool,
     pub route_exclude_address: Option<Vec<String>>,
+    /// 只路由这些网段；为空时接管全部流量
+    pub route_address: Option<Vec<String>>,
+    pub route_presets: Vec<TunRoutePreset>,
     pub interface_name: Option<String>,
+    pub split_rules: TunSplitRules,
+    /// Fake IP 地址池，生成配置时从排除网段中挖掉
+    pub fake_ip_ranges: Vec<String>,
+}
+
+impl TunProxyOptions {
+    /// 补齐只保存在应用设置里的字段，前端下发的 tun_options 通常不携带
+    pub fn hydrate_from_app_config(&mut self, app_config: &AppConfig) {
+        self.split_rules = app_config.tun_split_rules.clone();
+        if self.route_exclude_address.is_none() {
+            self.route_exclude_address = app_config.tun_route_exclude_address.clone();
+        }
+        if self.route_address.is_none() {
+            self.route_address = app_config.tun_route_address.clone();
+        }
+        if self.route_presets.is_empty() {
+            self.route_presets = app_config.tun_route_presets.clone();
+        }
+        self.fake_ip_ranges = configured_fake_ip_ranges(app_config);
+    }
 }
 
 impl Default for TunProxyOptions {
@@ -85,7 +461,11 @@ impl Default for TunProxyOptions {
             stack: "mixed".to_string(),
             enable_ipv6: true,
             route_exclude_address: None,
+            route_address: None,
+            route_presets: Vec::new(),
             interface_name: None,
+            split_rules: TunSplitRules::default(),
+            fake_ip_ranges: Vec::new(),
         }
     }
 }
@@ -100,7 +480,10 @@ pub struct TunProfile {
     pub stack: String,
     pub mtu: u16,
     pub interface_name: String,
+    pub route_address: Vec<String>,
     pub route_exclude_address: Vec<String>,
+    pub route_exclude_address_set: Vec<String>,
+    pub split_rules: TunSplitRules,
 }
 
 impl TunProfile {
@@ -119,6 +502,17 @@ impl TunProfile {
         } else {
             None
         };
+        let route_address = options.route_address.clone().unwrap_or_default();
+        let route_exclude_address = carve_out_cidrs(
+            resolve_tun_route_exclude_address(
+                route_exclude_address_override
+                    .map(|cidrs| cidrs.to_vec())
+                    .or_else(|| options.route_exclude_address.clone()),
+                &route_address,
+                &options.route_presets,
+            ),
+            &options.fake_ip_ranges,
+        );
 
         Self {
             ipv4_address: if options.ipv4_address.trim().is_empty() {
@@ -132,10 +526,10 @@ impl TunProfile {
             stack: normalize_stack(&options.stack),
             mtu: options.mtu,
             interface_name,
-            route_exclude_address: route_exclude_address_override
-                .map(|cidrs| cidrs.to_vec())
-                .or_else(|| options.route_exclude_address.clone())
-                .unwrap_or_else(default_tun_route_exclude_addresses),
+            route_exclude_address_set: tun_route_exclude_rule_sets(&options.route_presets),
+            route_address,
+            route_exclude_address,
+            split_rules: options.split_rules.clone(),
         }
     }
 
@@ -162,11 +556,17 @@ impl TunProfile {
                 mtu: None,
                 route_address: None,
                 route_exclude_address: None,
+                route_exclude_address_set: None,
+                include_interface: None,
+                exclude_interface: None,
+                include_uid: None,
+                exclude_uid: None,
+                users: None,
                 set_system_proxy: None,
             },
             config_model::Inbound {
                 r#type: "tun".to_string(),
-                tag: "tun-in".to_string(),
+                tag: TUN_INBOUND_TAG.to_string(),
                 listen: None,
                 interface_name: Some(self.interface_name.clone()),
                 listen_port: None,
@@ -175,8 +575,14 @@ impl TunProfile {
                 strict_route: Some(self.strict_route),
                 stack: Some(self.stack.clone()),
                 mtu: Some(self.mtu),
-                route_address: None,
+                route_address: non_empty_list(&self.route_address),
                 route_exclude_address: Some(self.route_exclude_address.clone()),
+                route_exclude_address_set: non_empty_list(&self.route_exclude_address_set),
+                include_interface: linux_only_list(&self.split_rules.include_interface),
+                exclude_interface: linux_only_list(&self.split_rules.exclude_interface),
+                include_uid: linux_only_list(&self.split_rules.include_uid),
+                exclude_uid: linux_only_list(&self.split_rules.exclude_uid),
+                users: None,
                 set_system_proxy: None,
             },
         ]
@@ -184,6 +590,10 @@ impl TunProfile {
 }
 
 fn validate_cidr(value: &str) -> Result<(), String> {
+    parse_cidr(value).map(|_| ())
+}
+
+pub fn parse_cidr(value: &str) -> Result<(IpAddr, u8), String> {
     let (address, prefix) = value
         .split_once('/')
         .ok_or_else(|| format!("无效的 CIDR: {}", value))?;
@@ -203,9 +613,101 @@ fn validate_cidr(value: &str) -> Result<(), String> {
         return Err(format!("无效的 CIDR: {}", value));
     }
 
+    Ok((ip, prefix))
+}
+
+/// 两个网段是否有交集（同一地址族中，较短前缀覆盖另一方即视为重叠）
+pub fn cidrs_overlap(a: (IpAddr, u8), b: (IpAddr, u8)) -> bool {
+    if a.1 <= b.1 {
+        cidr_contains(a, b)
+    } else {
+        cidr_contains(b, a)
+    }
+}
+
+/// `outer` 是否完整覆盖 `inner`
+fn cidr_contains(outer: (IpAddr, u8), inner: (IpAddr, u8)) -> bool {
+    let (outer_ip, outer_prefix) = outer;
+    let (inner_ip, inner_prefix) = inner;
+    if outer_prefix > inner_prefix {
+        return false;
+    }
+    match (outer_ip, inner_ip) {
+        (IpAddr::V4(outer_ip), IpAddr::V4(inner_ip)) => {
+            let mask = u32::MAX.checked_shl(32 - outer_prefix as u32).unwrap_or(0);
+            u32::from(outer_ip) & mask == u32::from(inner_ip) & mask
+        }
+        (IpAddr::V6(outer_ip), IpAddr::V6(inner_ip)) => {
+            let mask = u128::MAX
+                .checked_shl(128 - outer_prefix as u32)
+                .unwrap_or(0);
+            u128::from(outer_ip) & mask == u128::from(inner_ip) & mask
+        }
+        _ => false,
+    }
+}
+
+fn validate_process_entry(value: &str) -> Result<(), String> {
+    if value.chars().any(char::is_control) {
+        return Err(format!("无效的进程名或路径: {}", value));
+    }
+    Ok(())
+}
+
+fn validate_interface_name(value: &str) -> Result<(), String> {
+    if value.len() > MAX_INTERFACE_NAME_LEN
+        || value
+            .chars()
+            .any(|c| c.is_whitespace() || c.is_control() || c == '/')
+    {
+        return Err(format!("无效的网卡名: {}", value));
+    }
     Ok(())
 }
 
+fn normalize_string_list(
+    values: Vec<String>,
+    validate: fn(&str) -> Result<(), String>,
+) -> Result<Vec<String>, String> {
+    let mut normalized = Vec::with_capacity(values.len());
+    for value in values {
+        let trimmed = value.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+
+        validate(trimmed)?;
+        let trimmed = trimmed.to_string();
+        if !normalized.contains(&trimmed) {
+            normalized.push(trimmed);
+        }
+    }
+    Ok(normalized)
+}
+
+fn dedup_preserving_order<T: PartialEq>(values: Vec<T>) -> Vec<T> {
+    let mut deduped = Vec::with_capacity(values.len());
+    for value in values {
+        if !deduped.contains(&value) {
+            deduped.push(value);
+        }
+    }
+    deduped
+}
+
+fn non_empty_list(values: &[String]) -> Option<Vec<String>> {
+    (!values.is_empty()).then(|| values.to_vec())
+}
+
+/// UID / 网卡过滤只在 Linux 上由 sing-box 支持，其他平台不写入
+pub fn linux_only_list<T: Clone>(values: &[T]) -> Option<Vec<T>> {
+    if cfg!(target_os = "linux") && !values.is_empty() {
+        Some(values.to_vec())
+    } else {
+        None
+    }
+}
+
 fn normalize_stack(stack: &str) -> String {
     match stack {
         "system" | "gvisor" | "mixed" => stack.to_string(),
@@ -213,15 +715,19 @@ fn normalize_stack(stack: &str) -> String {
     }
 }
 
-fn default_interface_name() -> String {
+/// macOS 上随机挑选的 `utunN` 编号范围，避开系统自身占用的低位编号
+const MACOS_UTUN_INDEX_START: u32 = 5;
+const MACOS_UTUN_INDEX_COUNT: u32 = 90;
+
+pub(crate) fn default_interface_name() -> String {
     #[cfg(target_os = "macos")]
     {
         use std::time::{SystemTime, UNIX_EPOCH};
         if let Ok(duration) = SystemTime::now().duration_since(UNIX_EPOCH) {
-            let idx = (duration.subsec_millis() % 90) + 5;
+            let idx = (duration.subsec_millis() % MACOS_UTUN_INDEX_COUNT) + MACOS_UTUN_INDEX_START;
             return format!("utun{}", idx);
         }
-        "utun5".to_string()
+        format!("utun{}", MACOS_UTUN_INDEX_START)
     }
     #[cfg(not(target_os = "macos"))]
     {
@@ -229,11 +735,32 @@ fn default_interface_name() -> String {
     }
 }
 
+/// 未指定网卡名时，判断 `name` 是否可能是本应用按 [`default_interface_name`] 创建的 TUN 网卡
+pub(crate) fn is_default_interface_name(name: &str) -> bool {
+    if cfg!(target_os = "macos") {
+        is_generated_utun_name(name)
+    } else {
+        name == "singbox_tun"
+    }
+}
+
+fn is_generated_utun_name(name: &str) -> bool {
+    name.strip_prefix("utun")
+        .and_then(|index| index.parse::<u32>().ok())
+        .is_some_and(|index| {
+            (MACOS_UTUN_INDEX_START..MACOS_UTUN_INDEX_START + MACOS_UTUN_INDEX_COUNT)
+                .contains(&index)
+        })
+}
+
 #[cfg(test)]
 mod tests {
     use super::{
-        default_tun_route_exclude_addresses, normalize_persisted_tun_route_exclude_address,
-        normalize_tun_route_exclude_address, TunProfile, TunProxyOptions,
+        default_tun_route_exclude_addresses, is_generated_utun_name, is_tun_split_route_rule,
+        normalize_persisted_tun_route_exclude_address, normalize_tun_route_exclude_address,
+        normalize_tun_split_rules, resolve_tun_route_exclude_address,
+        validate_tun_route_address_conflicts, TunProfile, TunProxyOptions, TunRoutePreset,
+        TunSplitRules,
     };
 
     #[test]
@@ -322,4 +849,222 @@ mod tests {
             default_tun_route_exclude_addresses()
         );
     }
+
+    #[test]
+    fn carve_out_cidrs_should_split_excludes_around_kept_ranges() {
+        let carved = carve_out_cidrs(
+            vec![
+                "198.0.0.0/8".to_string(),
+                "10.0.0.0/8".to_string(),
+                "198.18.0.0/16".to_string(),
+            ],
+            &["198.18.0.0/15".to_string()],
+        );
+
+        assert_eq!(
+            carved,
+            vec![
+                "198.128.0.0/9",
+                "198.64.0.0/10",
+                "198.32.0.0/11",
+                "198.0.0.0/12",
+                "198.24.0.0/13",
+                "198.20.0.0/14",
+                "198.16.0.0/15",
+                "10.0.0.0/8",
+                "198.18.0.0/16",
+            ]
+        );
+    }
+
+    #[test]
+    fn tun_profile_should_keep_fake_ip_ranges_out_of_default_excludes() {
+        let options = TunProxyOptions {
+            fake_ip_ranges: vec!["198.18.0.0/15".to_string(), "fc00::/18".to_string()],
+            ..TunProxyOptions::default()
+        };
+        let profile = TunProfile::from_options(&options, None);
+        let fake_v6 = parse_cidr("fc00::/18").unwrap();
+
+        assert!(!profile
+            .route_exclude_address
+            .contains(&"fc00::/7".to_string()));
+        assert!(profile
+            .route_exclude_address
+            .contains(&"fd00::/8".to_string()));
+        assert!(profile
+            .route_exclude_address
+            .contains(&"fc00:4000::/18".to_string()));
+        assert!(profile
+            .route_exclude_address
+            .iter()
+            .all(|cidr| !cidrs_overlap(parse_cidr(cidr).unwrap(), fake_v6)));
+    }
+
+    #[test]
+    fn normalize_tun_split_rules_should_trim_and_deduplicate() {
+        let normalized = normalize_tun_split_rules(TunSplitRules {
+            exclude_process: vec![
+                " backup-agent ".to_string(),
+                "backup-agent".to_string(),
+                "".to_string(),
+            ],
+            include_uid: vec![1000, 1000, 1001],
+            exclude_interface: vec![" docker0 ".to_string()],
+            ..TunSplitRules::default()
+        })
+        .expect("split rules should normalize");
+
+        assert_eq!(normalized.exclude_process, vec!["backup-agent".to_string()]);
+        assert_eq!(normalized.include_uid, vec![1000, 1001]);
+        assert_eq!(normalized.exclude_interface, vec!["docker0".to_string()]);
+    }
+
+    #[test]
+    fn normalize_tun_split_rules_should_reject_conflicts_and_invalid_interfaces() {
+        let conflict = normalize_tun_split_rules(TunSplitRules {
+            include_uid: vec![1000],
+            exclude_uid: vec![1000],
+            ..TunSplitRules::default()
+        });
+        assert!(conflict.is_err());
+
+        let both_interfaces = normalize_tun_split_rules(TunSplitRules {
+            include_interface: vec!["eth0".to_string()],
+            exclude_interface: vec!["wg0".to_string()],
+            ..TunSplitRules::default()
+        });
+        assert!(both_interfaces.is_err());
+
+        let error = normalize_tun_split_rules(TunSplitRules {
+            exclude_interface: vec!["not an interface".to_string()],
+            ..TunSplitRules::default()
+        })
+        .expect_err("invalid interface should be rejected");
+        assert!(error.contains("not an interface"));
+    }
+
+    #[test]
+    fn split_route_rules_should_separate_names_and_paths() {
+        let rules = TunSplitRules {
+            include_process: vec!["firefox".to_string()],
+            exclude_process: vec![
+                "openvpn".to_string(),
+                "C:\\Program Files\\Backup\\agent.exe".to_string(),
+            ],
+            ..TunSplitRules::default()
+        }
+        .route_rules("direct");
+
+        assert_eq!(rules.len(), 3);
+        assert_eq!(rules[0]["process_name"][0], "openvpn");
+        assert_eq!(
+            rules[1]["process_path"][0],
+            "C:\\Program Files\\Backup\\agent.exe"
+        );
+        assert_eq!(rules[2]["type"], "logical");
+        assert_eq!(rules[2]["rules"][1]["invert"], true);
+        assert!(rules.iter().all(is_tun_split_route_rule));
+        assert!(!is_tun_split_route_rule(
+            &serde_json::json!({ "process_name": ["openvpn"], "outbound": "direct" })
+        ));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn tun_profile_should_emit_uid_and_interface_filters_on_tun_inbound() {
+        let options = TunProxyOptions {
+            split_rules: TunSplitRules {
+                exclude_uid: vec![0],
+                include_interface: vec!["eth0".to_string()],
+                ..TunSplitRules::default()
+            },
+            ..TunProxyOptions::default()
+        };
+        let inbounds = TunProfile::from_options(&options, None).to_inbounds(7890);
+
+        assert_eq!(inbounds[1].exclude_uid, Some(vec![0]));
+        assert_eq!(
+            inbounds[1].include_interface,
+            Some(vec!["eth0".to_string()])
+        );
+        assert_eq!(inbounds[1].include_uid, None);
+        assert_eq!(inbounds[0].exclude_uid, None);
+    }
+
+    #[test]
+    fn route_address_should_drop_default_excludes_and_merge_presets() {
+        let options = TunProxyOptions {
+            route_address: Some(vec!["10.20.0.0/16".to_string()]),
+            route_presets: vec![
+                TunRoutePreset::BypassCn,
+                TunRoutePreset::BypassContainerBridges,
+            ],
+            ..TunProxyOptions::default()
+        };
+        let profile = TunProfile::from_options(&options, None);
+
+        assert_eq!(profile.route_address, vec!["10.20.0.0/16".to_string()]);
+        assert_eq!(
+            profile.route_exclude_address_set,
+            vec!["geoip-cn".to_string()]
+        );
+        assert!(!profile
+            .route_exclude_address
+            .contains(&"192.168.0.0/16".to_string()));
+        assert!(profile
+            .route_exclude_address
+            .contains(&"172.16.0.0/12".to_string()));
+
+        let inbounds = profile.to_inbounds(7890);
+        assert_eq!(
+            inbounds[1].route_address,
+            Some(vec!["10.20.0.0/16".to_string()])
+        );
+        assert_eq!(
+            inbounds[1].route_exclude_address_set,
+            Some(vec!["geoip-cn".to_string()])
+        );
+    }
+
+    #[test]
+    fn presets_should_not_duplicate_default_excludes() {
+        let resolved =
+            resolve_tun_route_exclude_address(None, &[], &[TunRoutePreset::BypassLanTailscale]);
+
+        let unique: std::collections::HashSet<_> = resolved.iter().collect();
+        assert_eq!(unique.len(), resolved.len());
+        assert!(resolved.contains(&"fd7a:115c:a1e0::/48".to_string()));
+    }
+
+    #[test]
+    fn validate_tun_route_address_conflicts_should_reject_covered_ranges() {
+        let include = vec!["10.20.0.0/16".to_string(), "2001:db8:1::/48".to_string()];
+
+        let error = validate_tun_route_address_conflicts(&include, &["10.0.0.0/8".to_string()])
+            .expect_err("covered include range should conflict");
+        assert!(error.contains("10.20.0.0/16") && error.contains("10.0.0.0/8"));
+
+        assert!(validate_tun_route_address_conflicts(
+            &include,
+            &["10.20.1.0/24".to_string(), "2001:db8:2::/48".to_string()]
+        )
+        .is_ok());
+        assert!(
+            validate_tun_route_address_conflicts(&include, &["2001:db8::/32".to_string()]).is_err()
+        );
+        assert!(
+            validate_tun_route_address_conflicts(&include, &["0.0.0.0/0".to_string()]).is_err()
+        );
+    }
+
+    #[test]
+    fn is_generated_utun_name_should_only_match_the_random_index_range() {
+        assert!(is_generated_utun_name("utun5"));
+        assert!(is_generated_utun_name("utun94"));
+        assert!(!is_generated_utun_name("utun0"));
+        assert!(!is_generated_utun_name("utun95"));
+        assert!(!is_generated_utun_name("utun"));
+        assert!(!is_generated_utun_name("singbox_tun"));
+    }
 }
@@ -7,15 +7,28 @@ pub mod singbox;
 
 // Core services
 pub mod core {
+    pub mod connection_stats;
+    pub mod connection_tracker;
     pub mod event_relay;
+    pub mod extra_inbounds;
     pub mod kernel_auto_manage;
     pub mod kernel_service;
+    pub mod lan_access;
+    pub mod lan_share;
+    pub mod pac_service;
     pub mod proxy_service;
+    pub mod proxy_watchdog;
+    pub mod traffic_accounting;
+    pub mod tun_preflight;
     pub mod tun_profile;
 }
 
 // Network services
 pub mod network {
+    pub mod connectivity_probe;
+    pub mod mirror_registry;
+    pub mod release_digest;
+    pub mod subscription_server;
     pub mod subscription_service;
 }
 
@@ -0,0 +1,432 @@
+//! 多目标连通性探测
+//!
+//! - 探测目标可配置，混合国内/国外站点，分别走直连与本地 mixed 入站（代理）；
+//! - 每轮返回逐目标的延迟与状态，并给出整体判定（`ProbeVerdict`）；
+//! - 守护自愈只在“代理探测全部失败、直连探测仍成功”时判定内核假死，
+//!   避免本机断网时反复重启内核；
+//! - 最近若干轮结果保存在内存环形缓冲中，供前端绘制历史曲线。
+//!
+//! 探测配置持久化在 `generic_config` 表（key = `STORAGE_KEY`），读取后缓存在内存中；
+//! 探测客户端按“超时 + 代理端口”缓存，参数不变时各轮复用连接池。
+
+use crate::app::storage::enhanced_storage_service::get_enhanced_storage;
+use futures::future::join_all;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::RwLock;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::AppHandle;
+use tracing::debug;
+
+pub const STORAGE_KEY: &str = "connectivity_probe_config";
+const DEFAULT_TIMEOUT_MS: u64 = 5000;
+const DEFAULT_HISTORY_LIMIT: usize = 120;
+const PROBE_USER_AGENT: &str = "sing-box-windows/connectivity-check";
+
+lazy_static::lazy_static! {
+    static ref PROBE_HISTORY: RwLock<VecDeque<ProbeRound>> = RwLock::new(VecDeque::new());
+    static ref PROBE_CONFIG_CACHE: RwLock<Option<ConnectivityProbeConfig>> = RwLock::new(None);
+    static ref PROBE_CLIENTS: RwLock<Option<ProbeClients>> = RwLock::new(None);
+}
+
+/// 决定探测客户端是否需要重建的参数
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ProbeClientKey {
+    timeout_ms: u64,
+    proxy_port: u16,
+}
+
+#[derive(Clone)]
+struct ProbeClients {
+    key: ProbeClientKey,
+    direct: Client,
+    proxy: Client,
+}
+
+impl ProbeClients {
+    fn build(key: ProbeClientKey) -> Result<Self, String> {
+        let timeout = Duration::from_millis(key.timeout_ms);
+        let direct = Client::builder()
+            .timeout(timeout)
+            .no_proxy()
+            .user_agent(PROBE_USER_AGENT)
+            .build()
+            .map_err(|e| format!("创建直连探测客户端失败: {}", e))?;
+        let proxy = reqwest::Proxy::all(format!("http://127.0.0.1:{}", key.proxy_port))
+            .map_err(|e| format!("构建代理地址失败: {}", e))?;
+        let proxy = Client::builder()
+            .timeout(timeout)
+            .proxy(proxy)
+            .user_agent(PROBE_USER_AGENT)
+            .build()
+            .map_err(|e| format!("创建代理探测客户端失败: {}", e))?;
+        Ok(Self { key, direct, proxy })
+    }
+}
+
+/// 取出与参数匹配的缓存客户端，参数变化（改了超时或端口）时才重建
+fn probe_clients(key: ProbeClientKey) -> Result<ProbeClients, String> {
+    if let Some(cached) = PROBE_CLIENTS
+        .read()
+        .ok()
+        .and_then(|cached| cached.clone())
+        .filter(|cached| cached.key == key)
+    {
+        return Ok(cached);
+    }
+    let clients = ProbeClients::build(key)?;
+    if let Ok(mut cached) = PROBE_CLIENTS.write() {
+        *cached = Some(clients.clone());
+    }
+    Ok(clients)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProbeRoute {
+    /// 不经过内核，直接访问
+    Direct,
+    /// 通过本地 mixed 入站访问
+    Proxy,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProbeRegion {
+    Domestic,
+    Foreign,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProbeTarget {
+    pub name: String,
+    pub url: String,
+    pub route: ProbeRoute,
+    pub region: ProbeRegion,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConnectivityProbeConfig {
+    pub targets: Vec<ProbeTarget>,
+    pub timeout_ms: u64,
+    pub history_limit: usize,
+}
+
+impl Default for ConnectivityProbeConfig {
+    fn default() -> Self {
+        Self {
+            targets: default_probe_targets(),
+            timeout_ms: DEFAULT_TIMEOUT_MS,
+            history_limit: DEFAULT_HISTORY_LIMIT,
+        }
+    }
+}
+
+pub fn default_probe_targets() -> Vec<ProbeTarget> {
+    let target = |name: &str, url: &str, route, region| ProbeTarget {
+        name: name.to_string(),
+        url: url.to_string(),
+        route,
+        region,
+        enabled: true,
+    };
+
+    vec![
+        target(
+            "百度",
+            "https://www.baidu.com",
+            ProbeRoute::Direct,
+            ProbeRegion::Domestic,
+        ),
+        target(
+            "阿里云",
+            "https://www.aliyun.com",
+            ProbeRoute::Direct,
+            ProbeRegion::Domestic,
+        ),
+        target(
+            "Cloudflare (直连)",
+            "https://www.cloudflare.com/cdn-cgi/trace",
+            ProbeRoute::Direct,
+            ProbeRegion::Foreign,
+        ),
+        target(
+            "Google 204",
+            "https://connectivitycheck.gstatic.com/generate_204",
+            ProbeRoute::Proxy,
+            ProbeRegion::Foreign,
+        ),
+        target(
+            "Cloudflare 204",
+            "http://cp.cloudflare.com/generate_204",
+            ProbeRoute::Proxy,
+            ProbeRegion::Foreign,
+        ),
+        target(
+            "百度 (代理)",
+            "https://www.baidu.com",
+            ProbeRoute::Proxy,
+            ProbeRegion::Domestic,
+        ),
+    ]
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProbeResult {
+    pub name: String,
+    pub url: String,
+    pub route: ProbeRoute,
+    pub region: ProbeRegion,
+    pub success: bool,
+    pub latency_ms: Option<u64>,
+    pub status: Option<u16>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProbeVerdict {
+    /// 所有代理探测均成功
+    Healthy,
+    /// 部分代理探测失败，但仍有可用
+    Degraded,
+    /// 代理探测全部失败而直连成功：内核假死
+    KernelWedged,
+    /// 直连与代理都失败：本机网络故障，不应重启内核
+    NetworkDown,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProbeRound {
+    pub timestamp_ms: u64,
+    pub verdict: ProbeVerdict,
+    pub results: Vec<ProbeResult>,
+}
+
+/// 根据逐目标结果给出整体判定。
+///
+/// TUN 模式下本程序自身的“直连”请求同样会被 TUN 接管，直连与代理无法区分，
+/// 因此全部失败时仍按内核假死处理，保持与旧版自愈一致的行为。
+pub fn classify_round(results: &[ProbeResult], tun_enabled: bool) -> ProbeVerdict {
+    let proxied: Vec<&ProbeResult> = results
+        .iter()
+        .filter(|r| r.route == ProbeRoute::Proxy)
+        .collect();
+    let direct_ok = results
+        .iter()
+        .any(|r| r.route == ProbeRoute::Direct && r.success);
+    let proxy_ok_count = proxied.iter().filter(|r| r.success).count();
+
+    if proxied.is_empty() {
+        // 未配置代理探测时只能依据直连结果判断网络
+        return if direct_ok {
+            ProbeVerdict::Healthy
+        } else {
+            ProbeVerdict::NetworkDown
+        };
+    }
+
+    if proxy_ok_count == proxied.len() {
+        ProbeVerdict::Healthy
+    } else if proxy_ok_count > 0 {
+        ProbeVerdict::Degraded
+    } else if direct_ok || tun_enabled {
+        ProbeVerdict::KernelWedged
+    } else {
+        ProbeVerdict::NetworkDown
+    }
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+async fn probe_target(client: &Client, target: &ProbeTarget) -> ProbeResult {
+    let start = Instant::now();
+    let (success, status, error) = match client.get(&target.url).send().await {
+        Ok(response) => {
+            let status = response.status();
+            // 部分站点会 3xx 跳转，只要拿到响应就说明链路可用
+            let ok = status.is_success() || status.is_redirection();
+            (
+                ok,
+                Some(status.as_u16()),
+                (!ok).then(|| format!("HTTP {}", status.as_u16())),
+            )
+        }
+        Err(err) => (false, None, Some(err.to_string())),
+    };
+    let latency_ms = success.then(|| start.elapsed().as_millis() as u64);
+
+    debug!(
+        "连通性探测 {} ({:?}): success={}, latency={:?}",
+        target.url, target.route, success, latency_ms
+    );
+
+    ProbeResult {
+        name: target.name.clone(),
+        url: target.url.clone(),
+        route: target.route,
+        region: target.region,
+        success,
+        latency_ms,
+        status,
+        error,
+    }
+}
+
+/// 并发执行一轮探测。代理探测通过 `127.0.0.1:proxy_port` 的 mixed 入站发出，
+/// 本机连接不需要局域网认证凭据。
+pub async fn run_probe_round(
+    config: &ConnectivityProbeConfig,
+    proxy_port: u16,
+    tun_enabled: bool,
+) -> Result<ProbeRound, String> {
+    let clients = probe_clients(ProbeClientKey {
+        timeout_ms: config.timeout_ms.clamp(1000, 30_000),
+        proxy_port,
+    })?;
+
+    let probes = config.targets.iter().filter(|t| t.enabled).map(|target| {
+        let client = match target.route {
+            ProbeRoute::Direct => &clients.direct,
+            ProbeRoute::Proxy => &clients.proxy,
+        };
+        probe_target(client, target)
+    });
+    let results = join_all(probes).await;
+
+    Ok(ProbeRound {
+        timestamp_ms: now_millis(),
+        verdict: classify_round(&results, tun_enabled),
+        results,
+    })
+}
+
+/// 写入历史环形缓冲，超出上限时丢弃最旧的记录
+pub fn push_history(round: ProbeRound, limit: usize) {
+    if let Ok(mut history) = PROBE_HISTORY.write() {
+        history.push_back(round);
+        let limit = limit.max(1);
+        while history.len() > limit {
+            history.pop_front();
+        }
+    }
+}
+
+fn cache_probe_config(config: &ConnectivityProbeConfig) {
+    if let Ok(mut cached) = PROBE_CONFIG_CACHE.write() {
+        *cached = Some(config.clone());
+    }
+}
+
+/// 读取探测配置：首次从数据库加载，之后使用内存缓存（保存时同步更新）
+pub async fn load_probe_config(app_handle: &AppHandle) -> ConnectivityProbeConfig {
+    if let Some(config) = PROBE_CONFIG_CACHE
+        .read()
+        .ok()
+        .and_then(|cached| cached.clone())
+    {
+        return config;
+    }
+
+    let storage = match get_enhanced_storage(app_handle).await {
+        Ok(storage) => storage,
+        Err(e) => {
+            tracing::warn!("读取连通性探测配置失败，使用默认值: {}", e);
+            return ConnectivityProbeConfig::default();
+        }
+    };
+    let config = match storage
+        .load_generic_config::<ConnectivityProbeConfig>(STORAGE_KEY)
+        .await
+    {
+        Ok(Some(config)) => config,
+        Ok(None) => ConnectivityProbeConfig::default(),
+        Err(e) => {
+            tracing::warn!("读取连通性探测配置失败，使用默认值: {}", e);
+            return ConnectivityProbeConfig::default();
+        }
+    };
+    cache_probe_config(&config);
+    config
+}
+
+/// 执行一轮探测并记入历史（守护循环与手动探测共用）
+pub async fn probe_and_record(
+    app_handle: &AppHandle,
+    proxy_port: u16,
+    tun_enabled: bool,
+) -> Result<ProbeRound, String> {
+    let config = load_probe_config(app_handle).await;
+    let round = run_probe_round(&config, proxy_port, tun_enabled).await?;
+    push_history(round.clone(), config.history_limit);
+    Ok(round)
+}
+
+#[tauri::command]
+pub async fn get_connectivity_probe_config(
+    app_handle: AppHandle,
+) -> Result<ConnectivityProbeConfig, String> {
+    Ok(load_probe_config(&app_handle).await)
+}
+
+#[tauri::command]
+pub async fn save_connectivity_probe_config(
+    app_handle: AppHandle,
+    config: ConnectivityProbeConfig,
+) -> Result<(), String> {
+    for target in &config.targets {
+        if target.url.trim().is_empty() {
+            return Err(format!("探测目标 {} 的地址不能为空", target.name));
+        }
+        url::Url::parse(target.url.trim())
+            .map_err(|e| format!("探测目标 {} 地址无效: {}", target.name, e))?;
+    }
+
+    let storage = get_enhanced_storage(&app_handle)
+        .await
+        .map_err(|e| format!("初始化存储失败: {}", e))?;
+    storage
+        .save_generic_config(STORAGE_KEY, &config)
+        .await
+        .map_err(|e| format!("保存连通性探测配置失败: {}", e))?;
+    cache_probe_config(&config);
+    Ok(())
+}
+
+/// 手动触发一轮探测，返回逐目标结果
+#[tauri::command]
+pub async fn run_connectivity_probe(app_handle: AppHandle) -> Result<ProbeRound, String> {
+    let app_config =
+        crate::app::storage::enhanced_storage_service::db_get_app_config(app_handle.clone())
+            .await?;
+    probe_and_record(&app_handle, app_config.proxy_port, app_config.tun_enabled).await
+}
+
+/// 获取最近的探测历史（按时间升序）
+#[tauri::command]
+pub fn get_connectivity_probe_history(limit: Option<usize>) -> Result<Vec<ProbeRound>, String> {
+    let history = PROBE_HISTORY
+        .read()
+        .map_err(|_| "读取探测历史失败".to_string())?;
+    let skip = limit
+        .map(|limit| history.len().saturating_sub(limit))
+        .unwrap_or(0);
+    Ok(history.iter().skip(skip).cloned().collect())
+}
+
+#[cfg(test)]
+#[path = "connectivity_probe.tests.rs"]
+mod tests;
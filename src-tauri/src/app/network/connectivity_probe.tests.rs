@@ -0,0 +1,107 @@
+use super::*;
+
+fn result(route: ProbeRoute, success: bool) -> ProbeResult {
+    ProbeResult {
+        name: "t".to_string(),
+        url: "https://example.com".to_string(),
+        route,
+        region: ProbeRegion::Foreign,
+        success,
+        latency_ms: success.then_some(42),
+        status: success.then_some(204),
+        error: (!success).then(|| "timeout".to_string()),
+    }
+}
+
+#[test]
+fn classify_round_should_report_wedged_only_when_direct_still_works() {
+    let results = vec![
+        result(ProbeRoute::Direct, true),
+        result(ProbeRoute::Proxy, false),
+        result(ProbeRoute::Proxy, false),
+    ];
+    assert_eq!(classify_round(&results, false), ProbeVerdict::KernelWedged);
+}
+
+#[test]
+fn classify_round_should_report_network_down_when_everything_fails() {
+    let results = vec![
+        result(ProbeRoute::Direct, false),
+        result(ProbeRoute::Proxy, false),
+    ];
+    assert_eq!(classify_round(&results, false), ProbeVerdict::NetworkDown);
+}
+
+#[test]
+fn classify_round_should_treat_total_failure_as_wedged_in_tun_mode() {
+    let results = vec![
+        result(ProbeRoute::Direct, false),
+        result(ProbeRoute::Proxy, false),
+    ];
+    assert_eq!(classify_round(&results, true), ProbeVerdict::KernelWedged);
+}
+
+#[test]
+fn classify_round_should_distinguish_healthy_and_degraded() {
+    let healthy = vec![
+        result(ProbeRoute::Direct, false),
+        result(ProbeRoute::Proxy, true),
+    ];
+    assert_eq!(classify_round(&healthy, false), ProbeVerdict::Healthy);
+
+    let degraded = vec![
+        result(ProbeRoute::Proxy, true),
+        result(ProbeRoute::Proxy, false),
+    ];
+    assert_eq!(classify_round(&degraded, false), ProbeVerdict::Degraded);
+}
+
+#[test]
+fn default_targets_should_mix_routes_and_regions() {
+    let targets = default_probe_targets();
+    assert!(targets.iter().any(|t| t.route == ProbeRoute::Direct));
+    assert!(targets.iter().any(|t| t.route == ProbeRoute::Proxy));
+    assert!(targets.iter().any(|t| t.region == ProbeRegion::Domestic));
+    assert!(targets.iter().any(|t| t.region == ProbeRegion::Foreign));
+}
+
+#[test]
+fn push_history_should_keep_latest_rounds_within_limit() {
+    for ts in 0..5 {
+        push_history(
+            ProbeRound {
+                timestamp_ms: ts,
+                verdict: ProbeVerdict::Healthy,
+                results: Vec::new(),
+            },
+            3,
+        );
+    }
+
+    let history = get_connectivity_probe_history(None).unwrap();
+    assert_eq!(history.len(), 3);
+    assert_eq!(history.last().map(|r| r.timestamp_ms), Some(4));
+}
+
+#[test]
+fn probe_clients_should_be_reused_until_parameters_change() {
+    let key = ProbeClientKey {
+        timeout_ms: 5000,
+        proxy_port: 12080,
+    };
+    let first = probe_clients(key.clone()).unwrap();
+    assert_eq!(first.key, key);
+    let cached = PROBE_CLIENTS.read().unwrap().clone().unwrap();
+    assert_eq!(cached.key, key);
+
+    let other_port = ProbeClientKey {
+        proxy_port: 12081,
+        ..key
+    };
+    let rebuilt = probe_clients(other_port.clone()).unwrap();
+    assert_eq!(rebuilt.key, other_port);
+    assert_eq!(
+        PROBE_CLIENTS.read().unwrap().clone().unwrap().key,
+        other_port
+    );
+}
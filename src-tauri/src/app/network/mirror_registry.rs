@@ -0,0 +1,575 @@
+//! GitHub 下载镜像注册表
+//!
+//! 内核下载、更新包下载、内核版本查询（GitHub API）与 metacubexd UI 预下载
+//! 都通过这里把 GitHub 原始链接解析为镜像链接：
+//! - 镜像列表可增删、排序、启停，持久化在 `generic_config` 表（key = `STORAGE_KEY`）；
+//! - 并行探测各镜像，保留最近若干次延迟/成功记录，开启 `auto_rank` 时按健康度排序；
+//! - 实际下载的成败也会回写到健康记录中。
+//!
+//! 写进内核配置的地址（规则集、外部 UI）由 sing-box 自行下载，不参与健康度排序，
+//! 只使用用户显式选择的 `config_mirror`，避免每次生成配置时地址随探测结果漂移。
+//!
+//! 配置生成是同步流程，因此注册表在内存中保留一份缓存，启动时与每次保存/探测后刷新。
+
+use crate::app::constants::api;
+use crate::app::storage::enhanced_storage_service::get_enhanced_storage;
+use futures::future::join_all;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::RwLock;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::AppHandle;
+use tracing::{info, warn};
+
+pub const STORAGE_KEY: &str = "download_mirror_registry";
+/// 每个镜像保留的健康记录条数
+const HEALTH_SAMPLE_LIMIT: usize = 20;
+const PROBE_TIMEOUT_SECS: u64 = 8;
+const API_TIMEOUT_SECS: u64 = 20;
+/// 探测用的小文件：优先 raw 链接，不支持 raw 的镜像改用 github.com 链接
+const PROBE_URLS: &[&str] = &[
+    "https://raw.githubusercontent.com/SagerNet/sing-geosite/rule-set/geosite-private.srs",
+    "https://github.com/SagerNet/sing-box/raw/main/README.md",
+];
+
+lazy_static::lazy_static! {
+    static ref REGISTRY_CACHE: RwLock<MirrorRegistry> = RwLock::new(MirrorRegistry::default());
+}
+
+/// 镜像改写方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MirrorStyle {
+    /// 在完整 URL 前拼接前缀：`https://gh-proxy.com/https://github.com/...`
+    Prefix,
+    /// 替换 `https://github.com/` 主机部分（仅支持 github.com 链接）
+    GithubHost,
+    /// jsDelivr：`https://cdn.jsdelivr.net/gh/<owner>/<repo>@<ref>/<path>`
+    Jsdelivr,
+    /// 直连原始地址
+    Direct,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MirrorEntry {
+    pub id: String,
+    pub name: String,
+    /// 镜像地址（`Direct` 时忽略）
+    #[serde(default)]
+    pub base_url: String,
+    pub style: MirrorStyle,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl MirrorEntry {
+    /// 把 GitHub 原始链接改写为经本镜像的链接；镜像不支持该链接时返回 None
+    pub fn apply(&self, url: &str) -> Option<String> {
+        let base = self.base_url.trim().trim_end_matches('/');
+        match self.style {
+            MirrorStyle::Direct => Some(url.to_string()),
+            MirrorStyle::Prefix => Some(format!("{}/{}", base, url)),
+            MirrorStyle::GithubHost => url
+                .strip_prefix("https://github.com/")
+                .map(|rest| format!("{}/{}", base, rest)),
+            MirrorStyle::Jsdelivr => to_jsdelivr_path(url).map(|path| format!("{}/{}", base, path)),
+        }
+    }
+}
+
+/// GitHub 链接转换为 jsDelivr 路径（不含主机）
+fn to_jsdelivr_path(url: &str) -> Option<String> {
+    if let Some(rest) = url.strip_prefix("https://raw.githubusercontent.com/") {
+        let mut parts = rest.splitn(4, '/');
+        let (owner, repo, git_ref, path) =
+            (parts.next()?, parts.next()?, parts.next()?, parts.next()?);
+        return Some(format!("gh/{}/{}@{}/{}", owner, repo, git_ref, path));
+    }
+    if let Some(rest) = url.strip_prefix("https://github.com/") {
+        let mut parts = rest.splitn(3, '/');
+        let (owner, repo, path) = (parts.next()?, parts.next()?, parts.next()?);
+        if path.starts_with("releases/download/") {
+            return Some(format!(
+                "gh/{}/{}@releases/{}",
+                owner,
+                repo,
+                &path["releases/".len()..]
+            ));
+        }
+    }
+    None
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MirrorSample {
+    pub at: i64,
+    pub success: bool,
+    pub latency_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MirrorRegistry {
+    pub mirrors: Vec<MirrorEntry>,
+    /// 按健康度自动排序；关闭时严格按用户顺序尝试
+    pub auto_rank: bool,
+    pub health: HashMap<String, VecDeque<MirrorSample>>,
+    /// 写进内核配置的规则集/外部 UI 地址使用的镜像 id；`None` 表示使用原始地址
+    pub config_mirror: Option<String>,
+}
+
+/// 默认写进内核配置的镜像，与引入注册表前硬编码的前缀一致
+const DEFAULT_CONFIG_MIRROR: &str = "gh-proxy";
+
+impl Default for MirrorRegistry {
+    fn default() -> Self {
+        Self {
+            mirrors: default_mirrors(),
+            auto_rank: true,
+            health: HashMap::new(),
+            config_mirror: Some(DEFAULT_CONFIG_MIRROR.to_string()),
+        }
+    }
+}
+
+pub fn default_mirrors() -> Vec<MirrorEntry> {
+    let mirror = |id: &str, name: &str, base_url: &str, style| MirrorEntry {
+        id: id.to_string(),
+        name: name.to_string(),
+        base_url: base_url.to_string(),
+        style,
+        enabled: true,
+    };
+    vec![
+        mirror(
+            "gh-proxy",
+            "gh-proxy",
+            "https://gh-proxy.com",
+            MirrorStyle::Prefix,
+        ),
+        mirror(
+            "v6-gh-proxy",
+            "v6.gh-proxy",
+            "https://v6.gh-proxy.com",
+            MirrorStyle::Prefix,
+        ),
+        mirror(
+            "ghfast",
+            "ghfast.top",
+            "https://ghfast.top",
+            MirrorStyle::Prefix,
+        ),
+        mirror(
+            "fastgit",
+            "hub.fastgit.xyz",
+            "https://hub.fastgit.xyz",
+            MirrorStyle::GithubHost,
+        ),
+        mirror(
+            "fgit",
+            "hub.fgit.cf",
+            "https://hub.fgit.cf",
+            MirrorStyle::GithubHost,
+        ),
+        mirror(
+            "jsdelivr",
+            "jsDelivr CDN",
+            "https://cdn.jsdelivr.net",
+            MirrorStyle::Jsdelivr,
+        ),
+        mirror("github", "GitHub 原始", "", MirrorStyle::Direct),
+    ]
+}
+
+/// 镜像及其健康度汇总（供前端展示）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MirrorStatus {
+    #[serde(flatten)]
+    pub entry: MirrorEntry,
+    pub success_rate: Option<f64>,
+    pub avg_latency_ms: Option<u64>,
+    pub last_success: Option<bool>,
+    pub last_checked_at: Option<i64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MirrorRegistryView {
+    pub auto_rank: bool,
+    pub config_mirror: Option<String>,
+    pub mirrors: Vec<MirrorStatus>,
+}
+
+impl MirrorRegistry {
+    fn status_of(&self, entry: &MirrorEntry) -> MirrorStatus {
+        let samples = self.health.get(&entry.id);
+        let (total, successes, latency_sum, latency_count) = samples
+            .map(|samples| {
+                samples.iter().fold((0u64, 0u64, 0u64, 0u64), |acc, s| {
+                    (
+                        acc.0 + 1,
+                        acc.1 + u64::from(s.success),
+                        acc.2 + s.latency_ms.filter(|_| s.success).unwrap_or(0),
+                        acc.3 + u64::from(s.success && s.latency_ms.is_some()),
+                    )
+                })
+            })
+            .unwrap_or((0, 0, 0, 0));
+        let last = samples.and_then(|s| s.back());
+
+        MirrorStatus {
+            entry: entry.clone(),
+            success_rate: (total > 0).then(|| successes as f64 / total as f64),
+            avg_latency_ms: (latency_count > 0).then(|| latency_sum / latency_count),
+            last_success: last.map(|s| s.success),
+            last_checked_at: last.map(|s| s.at),
+        }
+    }
+
+    /// 启用的镜像，按当前策略排序
+    pub fn ranked(&self) -> Vec<MirrorStatus> {
+        let mut statuses: Vec<MirrorStatus> = self
+            .mirrors
+            .iter()
+            .filter(|m| m.enabled)
+            .map(|m| self.status_of(m))
+            .collect();
+
+        if self.auto_rank {
+            // 最近一次成功 > 无记录 > 最近一次失败；同档按成功率、平均延迟排序，其余保持用户顺序
+            statuses.sort_by(|a, b| {
+                let tier = |s: &MirrorStatus| match s.last_success {
+                    Some(true) => 0,
+                    None => 1,
+                    Some(false) => 2,
+                };
+                tier(a)
+                    .cmp(&tier(b))
+                    .then_with(|| {
+                        b.success_rate
+                            .unwrap_or(0.0)
+                            .partial_cmp(&a.success_rate.unwrap_or(0.0))
+                            .unwrap_or(std::cmp::Ordering::Equal)
+                    })
+                    .then_with(|| {
+                        a.avg_latency_ms
+                            .unwrap_or(u64::MAX)
+                            .cmp(&b.avg_latency_ms.unwrap_or(u64::MAX))
+                    })
+            });
+        }
+        statuses
+    }
+
+    /// 解析出按优先级排列的 (镜像, 链接) 列表；全部不适用时回退到原始链接
+    pub fn resolve(&self, url: &str) -> Vec<(MirrorEntry, String)> {
+        let mut seen = HashSet::new();
+        let mut resolved: Vec<(MirrorEntry, String)> = self
+            .ranked()
+            .into_iter()
+            .filter_map(|status| {
+                let mirrored = status.entry.apply(url)?;
+                seen.insert(mirrored.clone())
+                    .then_some((status.entry, mirrored))
+            })
+            .collect();
+
+        if resolved.is_empty() {
+            resolved.push((
+                MirrorEntry {
+                    id: "github".to_string(),
+                    name: "GitHub 原始".to_string(),
+                    base_url: String::new(),
+                    style: MirrorStyle::Direct,
+                    enabled: true,
+                },
+                url.to_string(),
+            ));
+        }
+        resolved
+    }
+
+    pub fn record(&mut self, mirror_id: &str, success: bool, latency_ms: Option<u64>) {
+        let samples = self.health.entry(mirror_id.to_string()).or_default();
+        samples.push_back(MirrorSample {
+            at: now_secs(),
+            success,
+            latency_ms,
+        });
+        while samples.len() > HEALTH_SAMPLE_LIMIT {
+            samples.pop_front();
+        }
+    }
+
+    /// 按用户选择的配置镜像改写；镜像不存在、已停用或不支持该链接时使用原始地址
+    pub fn config_url(&self, url: &str) -> String {
+        self.config_mirror
+            .as_deref()
+            .and_then(|id| self.mirrors.iter().find(|m| m.id == id && m.enabled))
+            .and_then(|mirror| mirror.apply(url))
+            .unwrap_or_else(|| url.to_string())
+    }
+
+    fn view(&self) -> MirrorRegistryView {
+        MirrorRegistryView {
+            auto_rank: self.auto_rank,
+            config_mirror: self.config_mirror.clone(),
+            mirrors: self.mirrors.iter().map(|m| self.status_of(m)).collect(),
+        }
+    }
+}
+
+fn now_secs() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+fn validate_mirrors(mirrors: &[MirrorEntry]) -> Result<(), String> {
+    let mut ids = HashSet::new();
+    for mirror in mirrors {
+        if mirror.id.trim().is_empty() || mirror.name.trim().is_empty() {
+            return Err("镜像 ID 与名称不能为空".to_string());
+        }
+        if !ids.insert(mirror.id.trim()) {
+            return Err(format!("镜像 ID 重复: {}", mirror.id));
+        }
+        if mirror.style != MirrorStyle::Direct {
+            let parsed = url::Url::parse(mirror.base_url.trim())
+                .map_err(|_| format!("镜像 {} 的地址无效: {}", mirror.name, mirror.base_url))?;
+            if !matches!(parsed.scheme(), "http" | "https") {
+                return Err(format!("镜像 {} 仅支持 http/https 地址", mirror.name));
+            }
+        }
+    }
+    if !mirrors.iter().any(|m| m.enabled) {
+        return Err("至少需要启用一个下载源".to_string());
+    }
+    Ok(())
+}
+
+/// 按当前注册表把 GitHub 链接解析为候选下载链接（含镜像信息）
+pub fn resolve_download_urls(url: &str) -> Vec<(MirrorEntry, String)> {
+    match REGISTRY_CACHE.read() {
+        Ok(registry) => registry.resolve(url),
+        Err(_) => MirrorRegistry::default().resolve(url),
+    }
+}
+
+/// 写进内核配置的地址（规则集、外部 UI）：只按用户显式选择的镜像改写，不随健康度变化
+pub fn config_mirror_url(url: &str) -> String {
+    match REGISTRY_CACHE.read() {
+        Ok(registry) => registry.config_url(url),
+        Err(_) => MirrorRegistry::default().config_url(url),
+    }
+}
+
+/// 按注册表顺序请求 GitHub API，返回第一个成功解析的 JSON。
+///
+/// 只有前缀式镜像能代理 `api.github.com`，其余镜像会被 `resolve` 自动跳过。
+pub async fn fetch_github_api_json(api_url: &str) -> Result<serde_json::Value, String> {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(API_TIMEOUT_SECS))
+        .user_agent(api::USER_AGENT)
+        .build()
+        .map_err(|e| format!("创建 HTTP 客户端失败: {}", e))?;
+
+    let mut last_error = String::new();
+    for (index, (mirror, url)) in resolve_download_urls(api_url).iter().enumerate() {
+        info!("尝试第 {} 个 API 源 ({}): {}", index + 1, mirror.name, url);
+        match client.get(url).send().await {
+            Ok(response) if response.status().is_success() => {
+                match response.json::<serde_json::Value>().await {
+                    Ok(value) => return Ok(value),
+                    Err(e) => last_error = format!("解析响应失败: {}", e),
+                }
+            }
+            Ok(response) => last_error = format!("API 返回错误状态: {}", response.status()),
+            Err(e) => last_error = format!("API 请求失败: {}", e),
+        }
+        warn!("{} (来源: {})", last_error, url);
+    }
+
+    Err(format!("所有 API 源都请求失败: {}", last_error))
+}
+
+async fn persist(app_handle: &AppHandle, registry: &MirrorRegistry) -> Result<(), String> {
+    let storage = get_enhanced_storage(app_handle).await?;
+    storage
+        .save_generic_config(STORAGE_KEY, registry)
+        .await
+        .map_err(|e| format!("保存下载镜像配置失败: {}", e))
+}
+
+fn cached_registry() -> MirrorRegistry {
+    REGISTRY_CACHE
+        .read()
+        .map(|registry| registry.clone())
+        .unwrap_or_default()
+}
+
+fn update_cache(registry: MirrorRegistry) {
+    if let Ok(mut cache) = REGISTRY_CACHE.write() {
+        *cache = registry;
+    }
+}
+
+/// 启动时从存储加载注册表到内存缓存
+pub async fn init_mirror_registry(app_handle: &AppHandle) {
+    let Ok(storage) = get_enhanced_storage(app_handle).await else {
+        return;
+    };
+    match storage
+        .load_generic_config::<MirrorRegistry>(STORAGE_KEY)
+        .await
+    {
+        Ok(Some(registry)) => update_cache(registry),
+        Ok(None) => {}
+        Err(e) => warn!("读取下载镜像配置失败，使用默认镜像: {}", e),
+    }
+}
+
+/// 记录一次实际下载的结果并持久化
+pub async fn report_mirror_result(app_handle: &AppHandle, mirror_id: &str, success: bool) {
+    let registry = match REGISTRY_CACHE.write() {
+        Ok(mut cache) => {
+            cache.record(mirror_id, success, None);
+            cache.clone()
+        }
+        Err(_) => return,
+    };
+    if let Err(e) = persist(app_handle, &registry).await {
+        warn!("{}", e);
+    }
+}
+
+async fn probe_mirror(client: &reqwest::Client, mirror: &MirrorEntry) -> (bool, Option<u64>) {
+    let Some(url) = PROBE_URLS.iter().find_map(|u| mirror.apply(u)) else {
+        return (false, None);
+    };
+
+    let started = Instant::now();
+    let result = client
+        .get(&url)
+        .header(reqwest::header::RANGE, "bytes=0-0")
+        .send()
+        .await;
+    match result {
+        Ok(response) if response.status().is_success() => {
+            (true, Some(started.elapsed().as_millis() as u64))
+        }
+        Ok(response) => {
+            warn!(
+                "镜像 {} 探测失败，HTTP 状态码: {}",
+                mirror.name,
+                response.status()
+            );
+            (false, None)
+        }
+        Err(e) => {
+            warn!("镜像 {} 探测失败: {}", mirror.name, e);
+            (false, None)
+        }
+    }
+}
+
+/// 并行探测所有启用的镜像并更新健康记录
+pub async fn probe_all_mirrors(app_handle: &AppHandle) -> Result<MirrorRegistryView, String> {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(PROBE_TIMEOUT_SECS))
+        .no_proxy()
+        .build()
+        .map_err(|e| format!("创建 HTTP 客户端失败: {}", e))?;
+
+    let mirrors: Vec<MirrorEntry> = cached_registry()
+        .mirrors
+        .into_iter()
+        .filter(|m| m.enabled)
+        .collect();
+    let results = join_all(mirrors.iter().map(|m| probe_mirror(&client, m))).await;
+
+    let registry = {
+        let mut cache = REGISTRY_CACHE
+            .write()
+            .map_err(|_| "下载镜像缓存不可用".to_string())?;
+        for (mirror, (success, latency)) in mirrors.iter().zip(results) {
+            cache.record(&mirror.id, success, latency);
+        }
+        cache.clone()
+    };
+    persist(app_handle, &registry).await?;
+
+    info!("下载镜像探测完成，共 {} 个", mirrors.len());
+    Ok(registry.view())
+}
+
+#[tauri::command]
+pub async fn get_mirror_registry() -> Result<MirrorRegistryView, String> {
+    Ok(cached_registry().view())
+}
+
+/// 保存镜像列表（增删、排序、启停都通过提交完整列表完成）
+#[tauri::command]
+pub async fn save_mirror_registry(
+    app_handle: AppHandle,
+    mirrors: Vec<MirrorEntry>,
+    auto_rank: bool,
+) -> Result<MirrorRegistryView, String> {
+    validate_mirrors(&mirrors)?;
+
+    let mut registry = cached_registry();
+    let ids: HashSet<&str> = mirrors.iter().map(|m| m.id.as_str()).collect();
+    registry.health.retain(|id, _| ids.contains(id.as_str()));
+    registry.mirrors = mirrors;
+    registry.auto_rank = auto_rank;
+
+    persist(&app_handle, &registry).await?;
+    let view = registry.view();
+    update_cache(registry);
+    Ok(view)
+}
+
+/// 选择写进内核配置的镜像，传 `None` 使用原始地址。新地址在下次生成配置时生效。
+#[tauri::command]
+pub async fn set_config_mirror(
+    app_handle: AppHandle,
+    mirror_id: Option<String>,
+) -> Result<MirrorRegistryView, String> {
+    let mut registry = cached_registry();
+    let mirror_id = mirror_id
+        .map(|id| id.trim().to_string())
+        .filter(|id| !id.is_empty());
+    if let Some(id) = &mirror_id {
+        if !registry.mirrors.iter().any(|m| &m.id == id) {
+            return Err(format!("下载源 {} 不存在", id));
+        }
+    }
+    registry.config_mirror = mirror_id;
+
+    persist(&app_handle, &registry).await?;
+    let view = registry.view();
+    update_cache(registry);
+    Ok(view)
+}
+
+#[tauri::command]
+pub async fn reset_mirror_registry(app_handle: AppHandle) -> Result<MirrorRegistryView, String> {
+    let registry = MirrorRegistry::default();
+    persist(&app_handle, &registry).await?;
+    let view = registry.view();
+    update_cache(registry);
+    Ok(view)
+}
+
+#[tauri::command]
+pub async fn probe_download_mirrors(app_handle: AppHandle) -> Result<MirrorRegistryView, String> {
+    probe_all_mirrors(&app_handle).await
+}
+
+#[cfg(test)]
+#[path = "mirror_registry.tests.rs"]
+mod tests;
@@ -0,0 +1,139 @@
+use super::*;
+
+const RELEASE_URL: &str =
+    "https://github.com/SagerNet/sing-box/releases/download/v1.12.0/sing-box-1.12.0-linux-amd64.tar.gz";
+const RAW_URL: &str = "https://raw.githubusercontent.com/SagerNet/sing-geoip/rule-set/geoip-cn.srs";
+
+fn find<'a>(mirrors: &'a [MirrorEntry], id: &str) -> &'a MirrorEntry {
+    mirrors.iter().find(|m| m.id == id).unwrap()
+}
+
+#[test]
+fn apply_should_rewrite_urls_per_style() {
+    let mirrors = default_mirrors();
+    assert_eq!(
+        find(&mirrors, "gh-proxy").apply(RAW_URL).unwrap(),
+        format!("https://gh-proxy.com/{}", RAW_URL)
+    );
+    assert_eq!(
+        find(&mirrors, "fgit").apply(RELEASE_URL).unwrap(),
+        "https://hub.fgit.cf/SagerNet/sing-box/releases/download/v1.12.0/sing-box-1.12.0-linux-amd64.tar.gz"
+    );
+    assert_eq!(find(&mirrors, "fgit").apply(RAW_URL), None);
+    assert_eq!(
+        find(&mirrors, "jsdelivr").apply(RAW_URL).unwrap(),
+        "https://cdn.jsdelivr.net/gh/SagerNet/sing-geoip@rule-set/geoip-cn.srs"
+    );
+    assert_eq!(
+        find(&mirrors, "jsdelivr").apply(RELEASE_URL).unwrap(),
+        "https://cdn.jsdelivr.net/gh/SagerNet/sing-box@releases/download/v1.12.0/sing-box-1.12.0-linux-amd64.tar.gz"
+    );
+    assert_eq!(find(&mirrors, "github").apply(RAW_URL).unwrap(), RAW_URL);
+}
+
+#[test]
+fn resolve_should_follow_user_order_without_history() {
+    let registry = MirrorRegistry::default();
+    let resolved = registry.resolve(RAW_URL);
+    assert_eq!(resolved[0].0.id, "gh-proxy");
+    assert_eq!(resolved.last().unwrap().1, RAW_URL);
+    // GithubHost 镜像不支持 raw 链接，应被跳过
+    assert!(resolved
+        .iter()
+        .all(|(m, _)| m.style != MirrorStyle::GithubHost));
+}
+
+#[test]
+fn resolve_should_rank_by_health_when_auto_rank_enabled() {
+    let mut registry = MirrorRegistry::default();
+    registry.record("gh-proxy", false, None);
+    registry.record("ghfast", true, Some(300));
+    registry.record("v6-gh-proxy", true, Some(80));
+
+    let ids: Vec<String> = registry
+        .resolve(RELEASE_URL)
+        .into_iter()
+        .map(|(m, _)| m.id)
+        .collect();
+    assert_eq!(
+        &ids[..2],
+        &["v6-gh-proxy".to_string(), "ghfast".to_string()]
+    );
+    assert_eq!(ids.last().map(String::as_str), Some("gh-proxy"));
+
+    registry.auto_rank = false;
+    assert_eq!(registry.resolve(RELEASE_URL)[0].0.id, "gh-proxy");
+}
+
+#[test]
+fn resolve_should_only_use_prefix_mirrors_and_direct_for_api_urls() {
+    let api_url = "https://api.github.com/repos/SagerNet/sing-box/releases";
+    let resolved = MirrorRegistry::default().resolve(api_url);
+    let ids: Vec<&str> = resolved.iter().map(|(m, _)| m.id.as_str()).collect();
+    assert_eq!(ids, vec!["gh-proxy", "v6-gh-proxy", "ghfast", "github"]);
+    assert_eq!(resolved[0].1, format!("https://gh-proxy.com/{}", api_url));
+    assert_eq!(resolved.last().unwrap().1, api_url);
+}
+
+#[test]
+fn resolve_should_fall_back_to_original_url_when_nothing_applies() {
+    let mut registry = MirrorRegistry::default();
+    registry.mirrors.retain(|m| m.id == "fgit");
+    let resolved = registry.resolve(RAW_URL);
+    assert_eq!(resolved.len(), 1);
+    assert_eq!(resolved[0].1, RAW_URL);
+}
+
+#[test]
+fn record_should_cap_health_samples() {
+    let mut registry = MirrorRegistry::default();
+    for _ in 0..(HEALTH_SAMPLE_LIMIT + 5) {
+        registry.record("ghfast", true, Some(10));
+    }
+    assert_eq!(registry.health["ghfast"].len(), HEALTH_SAMPLE_LIMIT);
+}
+
+#[test]
+fn validate_mirrors_should_reject_duplicates_and_bad_urls() {
+    let mut mirrors = default_mirrors();
+    assert!(validate_mirrors(&mirrors).is_ok());
+
+    mirrors.push(mirrors[0].clone());
+    assert!(validate_mirrors(&mirrors).is_err());
+
+    let bad = vec![MirrorEntry {
+        id: "bad".to_string(),
+        name: "bad".to_string(),
+        base_url: "ftp://example.com".to_string(),
+        style: MirrorStyle::Prefix,
+        enabled: true,
+    }];
+    assert!(validate_mirrors(&bad).is_err());
+}
+
+#[test]
+fn config_url_should_only_use_the_selected_mirror() {
+    let mut registry = MirrorRegistry::default();
+    assert_eq!(
+        registry.config_url(RAW_URL),
+        format!("https://gh-proxy.com/{}", RAW_URL)
+    );
+
+    // 健康度排序不影响写进配置的地址
+    registry.record("gh-proxy", false, None);
+    registry.record("jsdelivr", true, Some(10));
+    assert_eq!(
+        registry.config_url(RAW_URL),
+        format!("https://gh-proxy.com/{}", RAW_URL)
+    );
+
+    registry.config_mirror = Some("fgit".to_string());
+    assert_eq!(
+        registry.config_url(RAW_URL),
+        RAW_URL,
+        "不支持 raw 链接时回退原始地址"
+    );
+
+    registry.config_mirror = None;
+    assert_eq!(registry.config_url(RAW_URL), RAW_URL);
+}
@@ -0,0 +1,199 @@
+//! GitHub Release 资源摘要校验
+//!
+//! 内核与应用更新都可能经第三方加速镜像下载。这里统一负责：
+//! - 经 GitHub API 一次性获取 release 资源的 `digest`（`sha256:<hex>`）。请求与其他 API 调用
+//!   一样走用户配置的代理与镜像，直连不通时也能拿到摘要；
+//! - 对每个镜像下载的文件做 SHA-256 校验，摘要不一致一律视为失败；
+//! - 拿不到摘要时返回 [`DIGEST_UNAVAILABLE`] 错误，由前端提示用户确认后才允许无校验安装；
+//! - 记录最终由哪个镜像提供了文件以及是否经过校验，便于排查。
+
+use crate::app::network::mirror_registry::fetch_github_api_json;
+use crate::app::storage::enhanced_storage_service::get_enhanced_storage;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::io::Read;
+use std::path::Path;
+use tauri::AppHandle;
+use tracing::{info, warn};
+
+/// generic_config 中存储下载来源记录所用的 key。
+pub const PROVENANCE_STORAGE_KEY: &str = "download_provenance";
+const MAX_PROVENANCE_RECORDS: usize = 20;
+
+/// 错误前缀：无法获取 release 摘要。前端据此弹窗确认，用户同意后带
+/// `allow_unverified` 重新调用下载命令。
+pub const DIGEST_UNAVAILABLE: &str = "DIGEST_UNAVAILABLE";
+
+/// 一次通过校验的下载记录
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DownloadProvenance {
+    /// 下载类别：`kernel` / `app_update`
+    pub kind: String,
+    pub asset_name: String,
+    pub version: String,
+    /// 实际提供文件的镜像 URL
+    pub mirror_url: String,
+    /// 实际文件的 SHA-256
+    pub sha256: String,
+    /// 是否与 GitHub 公布的摘要比对通过（旧版本可能记录过未校验的下载）
+    pub verified: bool,
+    pub recorded_at: i64,
+}
+
+/// 从 GitHub release asset 中读取 SHA-256 摘要（`"digest": "sha256:..."`）
+pub fn parse_asset_digest(asset: &Value) -> Option<String> {
+    let digest = asset.get("digest").and_then(Value::as_str)?.trim();
+    let hex = digest.strip_prefix("sha256:")?;
+    if hex.len() == 64 && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        Some(hex.to_ascii_lowercase())
+    } else {
+        None
+    }
+}
+
+/// 在 release JSON 中按文件名查找资源摘要
+pub fn find_asset_digest(release: &Value, asset_name: &str) -> Option<String> {
+    release
+        .get("assets")
+        .and_then(Value::as_array)?
+        .iter()
+        .find(|asset| asset.get("name").and_then(Value::as_str) == Some(asset_name))
+        .and_then(parse_asset_digest)
+}
+
+/// 从（可能带镜像前缀的）GitHub release 下载链接中解析 `(owner/repo, tag, 文件名)`
+pub fn parse_release_download_url(url: &str) -> Option<(String, String, String)> {
+    let rest = &url[url.rfind("github.com/")? + "github.com/".len()..];
+    let segments: Vec<&str> = rest.split('/').collect();
+    match segments.as_slice() {
+        [owner, repo, "releases", "download", tag, name, ..] if !name.is_empty() => Some((
+            format!("{}/{}", owner, repo),
+            tag.to_string(),
+            name.to_string(),
+        )),
+        _ => None,
+    }
+}
+
+/// 获取指定 release 资源的摘要（经用户配置的代理与镜像请求 GitHub API）。
+///
+/// 请求失败或 release 没有公布该资源的摘要（较早的 release）时返回以
+/// [`DIGEST_UNAVAILABLE`] 开头的错误。
+pub async fn fetch_release_asset_digest(
+    repo: &str,
+    tag: &str,
+    asset_name: &str,
+) -> Result<String, String> {
+    let url = format!(
+        "https://api.github.com/repos/{}/releases/tags/{}",
+        repo, tag
+    );
+    let release = fetch_github_api_json(&url).await.map_err(|reason| {
+        warn!("获取 release 摘要失败 ({}): {}", url, reason);
+        digest_unavailable_error(&format!("获取 {} {} 的摘要信息失败: {}", repo, tag, reason))
+    })?;
+
+    find_asset_digest(&release, asset_name).ok_or_else(|| {
+        digest_unavailable_error(&format!(
+            "{} {} 未公布 {} 的 SHA-256 摘要",
+            repo, tag, asset_name
+        ))
+    })
+}
+
+/// 构造“摘要不可用”错误，`reason` 供前端在确认弹窗中展示
+pub fn digest_unavailable_error(reason: &str) -> String {
+    format!("{}: {}", DIGEST_UNAVAILABLE, reason)
+}
+
+/// 摘要不可用时的处理：用户已确认则返回 `Ok(None)` 以无校验继续，否则原样返回错误
+pub fn resolve_expected_digest(
+    lookup: Result<String, String>,
+    allow_unverified: bool,
+) -> Result<Option<String>, String> {
+    match lookup {
+        Ok(digest) => Ok(Some(digest)),
+        Err(e) if allow_unverified && e.starts_with(DIGEST_UNAVAILABLE) => {
+            warn!("用户已确认在无法校验摘要的情况下继续下载: {}", e);
+            Ok(None)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// 计算文件的 SHA-256（小写十六进制）
+pub fn sha256_file(path: &Path) -> Result<String, String> {
+    let mut file =
+        std::fs::File::open(path).map_err(|e| format!("打开文件 {:?} 失败: {}", path, e))?;
+    let mut hasher = Sha256::new();
+    let mut buffer = [0u8; 64 * 1024];
+    loop {
+        let read = file
+            .read(&mut buffer)
+            .map_err(|e| format!("读取文件 {:?} 失败: {}", path, e))?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// 校验文件摘要，返回实际的 SHA-256。`expected` 为空时只计算不比对。
+pub fn verify_file_digest(path: &Path, expected: Option<&str>) -> Result<String, String> {
+    let actual = sha256_file(path)?;
+    if let Some(expected) = expected {
+        if !actual.eq_ignore_ascii_case(expected.trim()) {
+            return Err(format!(
+                "文件校验失败：期望 SHA-256 {}，实际 {}",
+                expected, actual
+            ));
+        }
+    }
+    Ok(actual)
+}
+
+/// 记录下载来源（保留最近若干条）
+pub async fn record_download_provenance(app_handle: &AppHandle, record: DownloadProvenance) {
+    info!(
+        "下载来源记录: {} {} <- {} (已校验: {})",
+        record.kind, record.asset_name, record.mirror_url, record.verified
+    );
+
+    let Ok(storage) = get_enhanced_storage(app_handle).await else {
+        return;
+    };
+    let mut records = storage
+        .load_generic_config::<Vec<DownloadProvenance>>(PROVENANCE_STORAGE_KEY)
+        .await
+        .ok()
+        .flatten()
+        .unwrap_or_default();
+    records.insert(0, record);
+    records.truncate(MAX_PROVENANCE_RECORDS);
+
+    if let Err(e) = storage
+        .save_generic_config(PROVENANCE_STORAGE_KEY, &records)
+        .await
+    {
+        warn!("保存下载来源记录失败: {}", e);
+    }
+}
+
+/// 查询最近的下载来源记录
+#[tauri::command]
+pub async fn get_download_provenance(
+    app_handle: AppHandle,
+) -> Result<Vec<DownloadProvenance>, String> {
+    let storage = get_enhanced_storage(&app_handle).await?;
+    storage
+        .load_generic_config::<Vec<DownloadProvenance>>(PROVENANCE_STORAGE_KEY)
+        .await
+        .map(|records| records.unwrap_or_default())
+        .map_err(|e| format!("读取下载来源记录失败: {}", e))
+}
+
+#[cfg(test)]
+#[path = "release_digest.tests.rs"]
+mod tests;
@@ -0,0 +1,88 @@
+use super::*;
+use serde_json::json;
+
+const HELLO_SHA256: &str = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+
+fn write_temp_file(label: &str, contents: &[u8]) -> std::path::PathBuf {
+    let unique = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_nanos();
+    let path = std::env::temp_dir().join(format!("sing-box-windows-{label}-{unique}.bin"));
+    std::fs::write(&path, contents).unwrap();
+    path
+}
+
+#[test]
+fn parse_asset_digest_should_accept_only_sha256() {
+    let asset =
+        json!({ "name": "a.zip", "digest": format!("sha256:{}", HELLO_SHA256.to_uppercase()) });
+    assert_eq!(parse_asset_digest(&asset), Some(HELLO_SHA256.to_string()));
+
+    assert_eq!(parse_asset_digest(&json!({ "digest": "md5:abcd" })), None);
+    assert_eq!(parse_asset_digest(&json!({ "digest": null })), None);
+    assert_eq!(parse_asset_digest(&json!({ "digest": "sha256:xyz" })), None);
+}
+
+#[test]
+fn find_asset_digest_should_match_by_name() {
+    let release = json!({
+        "assets": [
+            { "name": "sing-box-1.12.0-linux-amd64.tar.gz", "digest": format!("sha256:{}", HELLO_SHA256) },
+            { "name": "sing-box-1.12.0-windows-amd64.zip" }
+        ]
+    });
+    assert_eq!(
+        find_asset_digest(&release, "sing-box-1.12.0-linux-amd64.tar.gz"),
+        Some(HELLO_SHA256.to_string())
+    );
+    assert_eq!(
+        find_asset_digest(&release, "sing-box-1.12.0-windows-amd64.zip"),
+        None
+    );
+}
+
+#[test]
+fn parse_release_download_url_should_strip_mirror_prefix() {
+    let url = "https://gh-proxy.com/https://github.com/xinggaoya/sing-box-windows/releases/download/v2.0.0/app_x64-setup.exe";
+    assert_eq!(
+        parse_release_download_url(url),
+        Some((
+            "xinggaoya/sing-box-windows".to_string(),
+            "v2.0.0".to_string(),
+            "app_x64-setup.exe".to_string()
+        ))
+    );
+    assert_eq!(
+        parse_release_download_url("https://example.com/file.zip"),
+        None
+    );
+}
+
+#[test]
+fn verify_file_digest_should_reject_mismatch() {
+    let path = write_temp_file("digest", b"hello");
+    assert_eq!(
+        verify_file_digest(&path, Some(HELLO_SHA256)).unwrap(),
+        HELLO_SHA256
+    );
+    assert!(verify_file_digest(&path, Some(&"0".repeat(64))).is_err());
+    assert_eq!(verify_file_digest(&path, None).unwrap(), HELLO_SHA256);
+    let _ = std::fs::remove_file(path);
+}
+
+#[test]
+fn resolve_expected_digest_should_require_confirmation_only_when_digest_is_unavailable() {
+    let unavailable = || Err(digest_unavailable_error("未公布摘要"));
+
+    assert_eq!(
+        resolve_expected_digest(Ok(HELLO_SHA256.to_string()), false),
+        Ok(Some(HELLO_SHA256.to_string()))
+    );
+    assert!(resolve_expected_digest(unavailable(), false)
+        .unwrap_err()
+        .starts_with(DIGEST_UNAVAILABLE));
+    assert_eq!(resolve_expected_digest(unavailable(), true), Ok(None));
+    // 其他错误不会因用户确认而被放行
+    assert!(resolve_expected_digest(Err("网络错误".to_string()), true).is_err());
+}
@@ -0,0 +1,570 @@
+//! 局域网订阅服务：把当前配置中的节点以订阅形式提供给同一网络下的其他设备
+//!
+//! 服务监听全部 IPv4 地址，切换网络后无需重启；只接受本机与私网来源的连接（设置了局域网来源网段白名单时
+//! 还需命中白名单），请求必须携带 `token` 参数；`format` 参数选择输出格式：
+//! `base64`（默认，分享链接列表）、`clash`（Clash / Mihomo YAML）或 `singbox`（sing-box JSON）。
+//! 节点每次请求时从活动配置实时读取，订阅更新、筛选后其他设备刷新即可同步。
+//! 状态中展示的订阅地址在每次查询时按当前首选局域网地址生成。
+//! 活动配置对应的上游订阅记录了流量信息时，会透传 `subscription-userinfo` 响应头。
+
+use crate::app::core::lan_share::lan_share_addresses;
+use crate::app::core::tun_preflight::enumerate_interface_addresses;
+use crate::app::core::tun_profile::{cidrs_overlap, parse_cidr};
+use crate::app::network::subscription_service::exporter::{
+    export_node_list, node_outbounds, NodeExportFormat,
+};
+use crate::app::network::subscription_service::read_active_config;
+use crate::app::storage::enhanced_storage_service::{
+    db_get_app_config_internal, db_get_subscriptions, get_enhanced_storage,
+};
+use crate::app::storage::secret_cipher::{decrypt_secret, encrypt_secret};
+use crate::app::storage::state_model::Subscription;
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::{Mutex, RwLock};
+use std::time::Duration;
+use tauri::AppHandle;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tracing::{info, warn};
+use ts_rs::TS;
+use url::form_urlencoded;
+
+const SETTINGS_KEY: &str = "subscription_server";
+const TOKEN_KEY: &str = "subscription_server_token_cipher_v1";
+const TOKEN_PURPOSE: &str = "subscription-server";
+const TOKEN_LEN: usize = 32;
+const SUBSCRIPTION_PATH: &str = "/sub";
+const MAX_REQUEST_BYTES: usize = 8 * 1024;
+/// 读取完整请求头的期限，避免慢速或空闲连接一直占用任务
+const REQUEST_READ_TIMEOUT: Duration = Duration::from_secs(10);
+pub const DEFAULT_SUBSCRIPTION_SERVER_PORT: u16 = 12090;
+
+struct RunningServer {
+    port: u16,
+    task: tauri::async_runtime::JoinHandle<()>,
+}
+
+lazy_static::lazy_static! {
+    static ref RUNNING_SERVER: Mutex<Option<RunningServer>> = Mutex::new(None);
+    static ref TOKEN_CACHE: RwLock<Option<String>> = RwLock::new(None);
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(
+    export,
+    export_to = "../src/types/generated/SubscriptionServerSettings.ts"
+)]
+pub struct SubscriptionServerSettings {
+    pub enabled: bool,
+    pub port: u16,
+}
+
+impl Default for SubscriptionServerSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: DEFAULT_SUBSCRIPTION_SERVER_PORT,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(
+    export,
+    export_to = "../src/types/generated/SubscriptionServerStatus.ts"
+)]
+pub struct SubscriptionServerStatus {
+    pub enabled: bool,
+    pub running: bool,
+    pub port: u16,
+    /// 订阅地址使用的局域网地址（查询时按当前网络解析）；未运行或无可用地址时为空
+    pub address: Option<String>,
+    pub token: String,
+    /// 各格式的完整订阅地址（含 token），未运行或无可用地址时为空
+    pub base64_url: Option<String>,
+    pub clash_url: Option<String>,
+    pub singbox_url: Option<String>,
+}
+
+/// 解析 `format` 参数；缺省时输出多数客户端通用的 base64 分享链接列表
+pub fn parse_subscription_format(raw: Option<&str>) -> Result<NodeExportFormat, String> {
+    let raw = raw.map(|value| value.trim().to_ascii_lowercase());
+    match raw.as_deref() {
+        None | Some("") | Some("base64") | Some("uri") | Some("v2ray") => Ok(NodeExportFormat::Uri),
+        Some("clash") | Some("mihomo") | Some("yaml") => Ok(NodeExportFormat::Clash),
+        Some("singbox") | Some("sing-box") | Some("json") => Ok(NodeExportFormat::Json),
+        Some(other) => Err(format!("不支持的订阅格式: {}", other)),
+    }
+}
+
+fn content_type(format: NodeExportFormat) -> &'static str {
+    match format {
+        NodeExportFormat::Uri => "text/plain; charset=utf-8",
+        NodeExportFormat::Clash => "text/yaml; charset=utf-8",
+        NodeExportFormat::Json => "application/json; charset=utf-8",
+    }
+}
+
+/// 按上游订阅记录的流量信息生成 `subscription-userinfo` 响应头；全部缺失时不下发
+pub fn format_subscription_userinfo(subscription: &Subscription) -> Option<String> {
+    let parts: Vec<String> = [
+        ("upload", subscription.subscription_upload),
+        ("download", subscription.subscription_download),
+        ("total", subscription.subscription_total),
+        ("expire", subscription.subscription_expire),
+    ]
+    .iter()
+    .filter_map(|(key, value)| value.map(|value| format!("{}={}", key, value)))
+    .collect();
+    if parts.is_empty() {
+        None
+    } else {
+        Some(parts.join("; "))
+    }
+}
+
+/// 逐字节比较，耗时与 token 内容无关
+fn token_matches(expected: &str, provided: &str) -> bool {
+    expected.len() == provided.len()
+        && expected
+            .bytes()
+            .zip(provided.bytes())
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// 订阅请求的参数
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubscriptionRequest {
+    pub token: Option<String>,
+    pub format: Option<String>,
+}
+
+/// 解析 HTTP 请求行；非 GET 或路径不匹配时返回对应的错误状态
+pub fn parse_subscription_request(request: &str) -> Result<SubscriptionRequest, &'static str> {
+    let mut parts = request
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .split_whitespace();
+    let method = parts.next().unwrap_or_default();
+    let target = parts.next().unwrap_or("/");
+    if method != "GET" {
+        return Err("405 Method Not Allowed");
+    }
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    if path.trim_end_matches('/') != SUBSCRIPTION_PATH {
+        return Err("404 Not Found");
+    }
+
+    let mut parsed = SubscriptionRequest {
+        token: None,
+        format: None,
+    };
+    for (key, value) in form_urlencoded::parse(query.as_bytes()) {
+        match key.as_ref() {
+            "token" => parsed.token = Some(value.into_owned()),
+            "format" => parsed.format = Some(value.into_owned()),
+            _ => {}
+        }
+    }
+    Ok(parsed)
+}
+
+fn build_response(
+    status: &str,
+    content_type: &str,
+    headers: &[(&str, String)],
+    body: &str,
+) -> Vec<u8> {
+    let mut response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-cache\r\nConnection: close\r\n",
+        status,
+        content_type,
+        body.len()
+    );
+    for (name, value) in headers {
+        response.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    response.push_str("\r\n");
+    response.push_str(body);
+    response.into_bytes()
+}
+
+fn plain_response(status: &str) -> Vec<u8> {
+    build_response(status, "text/plain; charset=utf-8", &[], status)
+}
+
+fn random_token() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(TOKEN_LEN)
+        .map(char::from)
+        .collect()
+}
+
+fn cached_token() -> Option<String> {
+    TOKEN_CACHE.read().ok().and_then(|cached| cached.clone())
+}
+
+fn set_cached_token(token: &str) {
+    if let Ok(mut cached) = TOKEN_CACHE.write() {
+        *cached = Some(token.to_string());
+    }
+}
+
+async fn save_token(app: &AppHandle, token: &str) -> Result<(), String> {
+    let cipher = encrypt_secret(app, TOKEN_PURPOSE, token)?;
+    let storage = get_enhanced_storage(app).await?;
+    storage
+        .save_config(TOKEN_KEY, &cipher)
+        .await
+        .map_err(|e| e.to_string())?;
+    set_cached_token(token);
+    Ok(())
+}
+
+/// 读取访问 token；尚未生成或已无法解密时重新生成并保存
+async fn ensure_token(app: &AppHandle) -> Result<String, String> {
+    if let Some(token) = cached_token() {
+        return Ok(token);
+    }
+    let storage = get_enhanced_storage(app).await?;
+    let cipher: Option<String> = storage
+        .get_config(TOKEN_KEY)
+        .await
+        .map_err(|e| e.to_string())?;
+    if let Some(cipher) = cipher {
+        match decrypt_secret(app, TOKEN_PURPOSE, &cipher) {
+            Ok(token) => {
+                set_cached_token(&token);
+                return Ok(token);
+            }
+            Err(err) => warn!("保存的订阅服务 token 解密失败，将重新生成: {}", err),
+        }
+    }
+    let token = random_token();
+    save_token(app, &token).await?;
+    Ok(token)
+}
+
+async fn load_settings(app: &AppHandle) -> Result<SubscriptionServerSettings, String> {
+    let storage = get_enhanced_storage(app).await?;
+    Ok(storage
+        .load_generic_config::<SubscriptionServerSettings>(SETTINGS_KEY)
+        .await
+        .map_err(|e| format!("读取订阅服务设置失败: {}", e))?
+        .unwrap_or_default())
+}
+
+/// 生成订阅内容与附加响应头
+async fn render_subscription(
+    app: &AppHandle,
+    format: NodeExportFormat,
+) -> Result<(String, Vec<(&'static str, String)>), String> {
+    let content = read_active_config(app.clone()).await?;
+    let config: Value =
+        serde_json::from_str(&content).map_err(|e| format!("解析活动配置失败: {}", e))?;
+    let nodes = node_outbounds(&config);
+    let (body, skipped) = export_node_list(&nodes, format)?;
+    if !skipped.is_empty() {
+        warn!(
+            "订阅服务跳过了 {} 个无法转换的节点: {:?}",
+            skipped.len(),
+            skipped
+        );
+    }
+
+    let mut headers = Vec::new();
+    let app_config = db_get_app_config_internal(app).await?;
+    if let Some(active_path) = app_config.active_config_path.as_deref() {
+        let subscriptions = db_get_subscriptions(app.clone()).await.unwrap_or_default();
+        if let Some(userinfo) = subscriptions
+            .iter()
+            .find(|sub| sub.config_path.as_deref() == Some(active_path))
+            .and_then(format_subscription_userinfo)
+        {
+            headers.push(("subscription-userinfo", userinfo));
+        }
+    }
+    Ok((body, headers))
+}
+
+async fn respond(app: &AppHandle, request: &str, peer: SocketAddr) -> Vec<u8> {
+    let request = match parse_subscription_request(request) {
+        Ok(request) => request,
+        Err(status) => return plain_response(status),
+    };
+    let authorized = match cached_token() {
+        Some(expected) => request
+            .token
+            .as_deref()
+            .is_some_and(|token| token_matches(&expected, token)),
+        None => false,
+    };
+    if !authorized {
+        warn!("订阅服务拒绝了 token 无效的请求: {}", peer);
+        return plain_response("403 Forbidden");
+    }
+    let format = match parse_subscription_format(request.format.as_deref()) {
+        Ok(format) => format,
+        Err(_) => return plain_response("400 Bad Request"),
+    };
+
+    match render_subscription(app, format).await {
+        Ok((body, headers)) => build_response("200 OK", content_type(format), &headers, &body),
+        Err(e) => {
+            warn!("订阅服务生成内容失败: {}", e);
+            plain_response("500 Internal Server Error")
+        }
+    }
+}
+
+/// 读取请求头（或读满上限）；连接出错时返回 `None`
+async fn read_request(stream: &mut TcpStream) -> Option<Vec<u8>> {
+    let mut buffer = Vec::with_capacity(1024);
+    let mut chunk = [0u8; 1024];
+    loop {
+        match stream.read(&mut chunk).await {
+            Ok(0) => break,
+            Ok(n) => {
+                buffer.extend_from_slice(&chunk[..n]);
+                if buffer.windows(4).any(|w| w == b"\r\n\r\n") || buffer.len() > MAX_REQUEST_BYTES {
+                    break;
+                }
+            }
+            Err(_) => return None,
+        }
+    }
+    Some(buffer)
+}
+
+/// 来源是否可以访问订阅服务：本机始终放行；其余来源须为私网地址，且命中 `allowed_cidrs`（为空时不限制）
+pub fn is_peer_allowed(peer: IpAddr, allowed_cidrs: &[String]) -> bool {
+    let peer = match peer {
+        IpAddr::V6(ip) => ip.to_ipv4_mapped().map(IpAddr::V4).unwrap_or(peer),
+        IpAddr::V4(_) => peer,
+    };
+    if peer.is_loopback() {
+        return true;
+    }
+    let is_private = match peer {
+        IpAddr::V4(ip) => ip.is_private() || ip.is_link_local(),
+        // 唯一本地地址 fc00::/7 与链路本地地址 fe80::/10
+        IpAddr::V6(ip) => {
+            (ip.segments()[0] & 0xfe00) == 0xfc00 || (ip.segments()[0] & 0xffc0) == 0xfe80
+        }
+    };
+    let host_prefix = if peer.is_ipv4() { 32 } else { 128 };
+    is_private
+        && (allowed_cidrs.is_empty()
+            || allowed_cidrs.iter().any(|cidr| {
+                parse_cidr(cidr.trim())
+                    .is_ok_and(|network| cidrs_overlap(network, (peer, host_prefix)))
+            }))
+}
+
+async fn handle_connection(app: AppHandle, mut stream: TcpStream, peer: SocketAddr) {
+    // 读取请求前先按来源过滤，公网来源即使猜中 token 也无法获取节点
+    let allowed_cidrs = match db_get_app_config_internal(&app).await {
+        Ok(app_config) => app_config.lan_allowed_cidrs,
+        Err(e) => {
+            warn!("读取局域网来源白名单失败，订阅服务拒绝连接 {}: {}", peer, e);
+            return;
+        }
+    };
+    if !is_peer_allowed(peer.ip(), &allowed_cidrs) {
+        warn!("订阅服务拒绝了非局域网或不在白名单内的来源: {}", peer);
+        let _ = stream.shutdown().await;
+        return;
+    }
+    let response = match tokio::time::timeout(REQUEST_READ_TIMEOUT, read_request(&mut stream)).await
+    {
+        Ok(Some(buffer)) => respond(&app, &String::from_utf8_lossy(&buffer), peer).await,
+        Ok(None) => return,
+        Err(_) => {
+            warn!("订阅服务读取请求超时: {}", peer);
+            plain_response("408 Request Timeout")
+        }
+    };
+    let _ = stream.write_all(&response).await;
+    let _ = stream.shutdown().await;
+}
+
+/// 停止订阅服务，并等待监听任务真正结束：监听端口随任务一起释放，
+/// 否则紧接着重新绑定同一端口时可能报地址已被占用（Windows 上尤为明显）
+async fn stop_server() {
+    let running = RUNNING_SERVER
+        .lock()
+        .ok()
+        .and_then(|mut running| running.take());
+    if let Some(server) = running {
+        server.task.abort();
+        let _ = server.task.await;
+        info!("订阅服务已停止");
+    }
+}
+
+/// 选择订阅地址中的主机：物理网卡上的私网 IPv4 优先，与局域网分享页展示的首个地址一致
+async fn preferred_lan_address(app: &AppHandle) -> Result<Ipv4Addr, String> {
+    let app_config = db_get_app_config_internal(app).await?;
+    let networks = tokio::task::spawn_blocking(enumerate_interface_addresses)
+        .await
+        .map_err(|e| format!("枚举本机网络失败: {}", e))?;
+    lan_share_addresses(
+        &networks,
+        &[app_config.tun_ipv4.as_str(), app_config.tun_ipv6.as_str()],
+    )
+    .into_iter()
+    .find_map(|(_, ip, _)| match ip {
+        IpAddr::V4(ip) => Some(ip),
+        IpAddr::V6(_) => None,
+    })
+    .ok_or_else(|| "未找到可用的局域网地址".to_string())
+}
+
+/// 按设置（重新）启动订阅服务
+///
+/// 监听 `0.0.0.0` 而不是某个局域网地址：DHCP 续租或切换 Wi-Fi 后地址变化时服务仍然可达，
+/// 访问控制由 token 负责。
+async fn start_server(app: &AppHandle, port: u16) -> Result<(), String> {
+    stop_server().await;
+    ensure_token(app).await?;
+    let listener = TcpListener::bind((Ipv4Addr::UNSPECIFIED, port))
+        .await
+        .map_err(|e| format!("启动订阅服务失败（端口 {}）: {}", port, e))?;
+
+    let app_handle = app.clone();
+    let task = tauri::async_runtime::spawn(async move {
+        loop {
+            match listener.accept().await {
+                Ok((stream, peer)) => {
+                    tauri::async_runtime::spawn(handle_connection(
+                        app_handle.clone(),
+                        stream,
+                        peer,
+                    ));
+                }
+                Err(e) => warn!("订阅服务接受连接失败: {}", e),
+            }
+        }
+    });
+    if let Ok(mut running) = RUNNING_SERVER.lock() {
+        *running = Some(RunningServer { port, task });
+    }
+    info!("订阅服务已启动: 0.0.0.0:{}{}", port, SUBSCRIPTION_PATH);
+    Ok(())
+}
+
+fn subscription_url(address: Ipv4Addr, port: u16, token: &str, format: &str) -> String {
+    format!(
+        "http://{}:{}{}?token={}&format={}",
+        address,
+        port,
+        SUBSCRIPTION_PATH,
+        urlencoding::encode(token),
+        format
+    )
+}
+
+async fn current_status(app: &AppHandle) -> Result<SubscriptionServerStatus, String> {
+    let settings = load_settings(app).await?;
+    let token = ensure_token(app).await?;
+    let running_port = RUNNING_SERVER
+        .lock()
+        .ok()
+        .and_then(|running| running.as_ref().map(|server| server.port));
+    // 每次查询都重新解析，网络切换后展示的地址随之更新
+    let address = match running_port {
+        Some(_) => match preferred_lan_address(app).await {
+            Ok(address) => Some(address),
+            Err(e) => {
+                warn!("订阅服务无法确定局域网地址: {}", e);
+                None
+            }
+        },
+        None => None,
+    };
+    let url = |format: &str| {
+        running_port
+            .zip(address)
+            .map(|(port, address)| subscription_url(address, port, &token, format))
+    };
+
+    Ok(SubscriptionServerStatus {
+        enabled: settings.enabled,
+        running: running_port.is_some(),
+        port: running_port.unwrap_or(settings.port),
+        address: address.map(|address| address.to_string()),
+        base64_url: url("base64"),
+        clash_url: url("clash"),
+        singbox_url: url("singbox"),
+        token,
+    })
+}
+
+/// 应用启动时按保存的设置恢复订阅服务
+pub async fn restore_subscription_server(app: &AppHandle) {
+    match load_settings(app).await {
+        Ok(settings) if settings.enabled => {
+            if let Err(e) = start_server(app, settings.port).await {
+                warn!("恢复订阅服务失败: {}", e);
+            }
+        }
+        Ok(_) => {}
+        Err(e) => warn!("{}", e),
+    }
+}
+
+/// 查询订阅服务状态与各格式的订阅地址
+#[tauri::command]
+pub async fn subscription_server_get_status(
+    app: AppHandle,
+) -> Result<SubscriptionServerStatus, String> {
+    current_status(&app).await
+}
+
+/// 开启 / 关闭订阅服务并保存端口设置
+#[tauri::command]
+pub async fn subscription_server_set_settings(
+    app: AppHandle,
+    settings: SubscriptionServerSettings,
+) -> Result<SubscriptionServerStatus, String> {
+    let app_config = db_get_app_config_internal(&app).await?;
+    if settings.port == 0 {
+        return Err("订阅服务端口无效".to_string());
+    }
+    if settings.port == app_config.proxy_port || settings.port == app_config.api_port {
+        return Err(format!("端口 {} 已被代理或 API 使用", settings.port));
+    }
+
+    let storage = get_enhanced_storage(&app).await?;
+    storage
+        .save_generic_config(SETTINGS_KEY, &settings)
+        .await
+        .map_err(|e| format!("保存订阅服务设置失败: {}", e))?;
+
+    if settings.enabled {
+        start_server(&app, settings.port).await?;
+    } else {
+        stop_server().await;
+    }
+    current_status(&app).await
+}
+
+/// 重新生成访问 token，旧的订阅地址立即失效
+#[tauri::command]
+pub async fn subscription_server_regenerate_token(
+    app: AppHandle,
+) -> Result<SubscriptionServerStatus, String> {
+    save_token(&app, &random_token()).await?;
+    current_status(&app).await
+}
+
+#[cfg(test)]
+#[path = "subscription_server.tests.rs"]
+mod tests;
@@ -0,0 +1,167 @@
+use super::*;
+use base64::Engine as _;
+use serde_json::json;
+
+fn subscription_with_usage() -> Subscription {
+    Subscription {
+        name: "机场".to_string(),
+        url: "https://example.com/sub".to_string(),
+        is_loading: false,
+        last_update: None,
+        is_manual: false,
+        manual_content: None,
+        use_original_config: false,
+        config_path: Some("/tmp/config.json".to_string()),
+        backup_path: None,
+        auto_update_interval_minutes: None,
+        subscription_upload: Some(1024),
+        subscription_download: Some(2048),
+        subscription_total: Some(10_737_418_240),
+        subscription_expire: None,
+        auto_update_fail_count: None,
+        last_auto_update_attempt: None,
+        last_auto_update_error: None,
+        last_auto_update_error_type: None,
+        last_auto_update_backoff_until: None,
+        fetch_options: Default::default(),
+    }
+}
+
+fn sample_config() -> Value {
+    json!({
+        "outbounds": [
+            { "type": "selector", "tag": "手动切换", "outbounds": ["香港 01", "wg"] },
+            {
+                "type": "trojan",
+                "tag": "香港 01",
+                "server": "hk.example.com",
+                "server_port": 443,
+                "password": "secret",
+                "tls": { "enabled": true, "server_name": "hk.example.com" },
+                "domain_resolver": { "server": "dns_resolver" }
+            },
+            { "type": "wireguard", "tag": "wg" },
+            { "type": "direct", "tag": "direct" }
+        ]
+    })
+}
+
+#[test]
+fn parse_subscription_format_should_accept_aliases() {
+    assert_eq!(parse_subscription_format(None), Ok(NodeExportFormat::Uri));
+    assert_eq!(
+        parse_subscription_format(Some("Clash")),
+        Ok(NodeExportFormat::Clash)
+    );
+    assert_eq!(
+        parse_subscription_format(Some("sing-box")),
+        Ok(NodeExportFormat::Json)
+    );
+    assert!(parse_subscription_format(Some("surge")).is_err());
+}
+
+#[test]
+fn format_subscription_userinfo_should_skip_missing_fields() {
+    let mut subscription = subscription_with_usage();
+    assert_eq!(
+        format_subscription_userinfo(&subscription).as_deref(),
+        Some("upload=1024; download=2048; total=10737418240")
+    );
+
+    subscription.subscription_upload = None;
+    subscription.subscription_download = None;
+    subscription.subscription_total = None;
+    assert_eq!(format_subscription_userinfo(&subscription), None);
+}
+
+#[test]
+fn parse_subscription_request_should_read_query_and_reject_other_paths() {
+    let request = parse_subscription_request(
+        "GET /sub?token=abc%2B1&format=clash HTTP/1.1\r\nHost: 192.168.1.2\r\n\r\n",
+    )
+    .unwrap();
+    assert_eq!(request.token.as_deref(), Some("abc+1"));
+    assert_eq!(request.format.as_deref(), Some("clash"));
+
+    assert_eq!(
+        parse_subscription_request("GET /other HTTP/1.1\r\n\r\n"),
+        Err("404 Not Found")
+    );
+    assert_eq!(
+        parse_subscription_request("POST /sub HTTP/1.1\r\n\r\n"),
+        Err("405 Method Not Allowed")
+    );
+}
+
+#[test]
+fn token_matches_should_compare_whole_token() {
+    assert!(token_matches("abcdef", "abcdef"));
+    assert!(!token_matches("abcdef", "abcdeg"));
+    assert!(!token_matches("abcdef", "abc"));
+}
+
+#[test]
+fn is_peer_allowed_should_only_accept_local_and_private_sources() {
+    let ip = |address: &str| address.parse::<IpAddr>().unwrap();
+    assert!(is_peer_allowed(ip("127.0.0.1"), &[]));
+    assert!(is_peer_allowed(ip("192.168.1.20"), &[]));
+    assert!(is_peer_allowed(ip("::ffff:10.0.0.8"), &[]));
+    assert!(is_peer_allowed(ip("fd00::8"), &[]));
+    assert!(!is_peer_allowed(ip("203.0.113.7"), &[]));
+    assert!(!is_peer_allowed(ip("2001:db8::7"), &[]));
+
+    let allowed = vec!["192.168.1.0/24".to_string()];
+    assert!(is_peer_allowed(ip("192.168.1.20"), &allowed));
+    assert!(is_peer_allowed(ip("::1"), &allowed));
+    assert!(!is_peer_allowed(ip("192.168.2.20"), &allowed));
+    assert!(!is_peer_allowed(
+        ip("203.0.113.7"),
+        &["0.0.0.0/0".to_string()]
+    ));
+}
+
+#[test]
+fn build_response_should_include_extra_headers() {
+    let response = build_response(
+        "200 OK",
+        content_type(NodeExportFormat::Uri),
+        &[("subscription-userinfo", "upload=1".to_string())],
+        "body",
+    );
+    let response = String::from_utf8(response).unwrap();
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(response.contains("Content-Length: 4\r\n"));
+    assert!(response.contains("subscription-userinfo: upload=1\r\n"));
+    assert!(response.ends_with("\r\n\r\nbody"));
+}
+
+#[test]
+fn node_list_should_skip_groups_and_untranslatable_nodes() {
+    let config = sample_config();
+    let nodes = node_outbounds(&config);
+    assert_eq!(nodes.len(), 2);
+
+    let (body, skipped) = export_node_list(&nodes, NodeExportFormat::Uri).unwrap();
+    let decoded = base64::engine::general_purpose::STANDARD
+        .decode(body)
+        .unwrap();
+    let decoded = String::from_utf8(decoded).unwrap();
+    assert!(decoded.starts_with("trojan://secret@hk.example.com:443"));
+    assert_eq!(skipped, vec!["wg".to_string()]);
+
+    let (body, _) = export_node_list(&nodes, NodeExportFormat::Clash).unwrap();
+    let document: serde_yaml::Value = serde_yaml::from_str(&body).unwrap();
+    assert_eq!(document["proxies"][0]["name"].as_str(), Some("香港 01"));
+    assert_eq!(
+        document["proxy-groups"][0]["proxies"][0].as_str(),
+        Some("香港 01")
+    );
+    assert_eq!(document["rules"][0].as_str(), Some("MATCH,PROXY"));
+
+    let (body, _) = export_node_list(&nodes, NodeExportFormat::Json).unwrap();
+    let json: Value = serde_json::from_str(&body).unwrap();
+    let outbounds = json["outbounds"].as_array().unwrap();
+    // wireguard 仍是合法的 sing-box 出站，JSON 格式原样保留
+    assert_eq!(outbounds.len(), 2);
+    assert!(outbounds[0].get("domain_resolver").is_none());
+}
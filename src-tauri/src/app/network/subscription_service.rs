@@ -1,23 +1,30 @@
 pub mod auto_update;
+pub mod exporter;
+pub mod fetch_options;
 pub mod helpers;
 mod mode;
 mod parser;
 
 use crate::app::constants::{messages, paths};
 use crate::app::core::kernel_auto_manage::auto_manage_with_saved_config;
-use crate::app::core::proxy_service::apply_proxy_runtime_state;
+use crate::app::core::proxy_service::{apply_proxy_runtime_state, inject_into_active_config};
+use crate::app::singbox::clash_meta::{convert_to_clash_meta, ClashMetaExport};
+use crate::app::singbox::common::normalize_default_outbound;
 use crate::app::singbox::config_generator;
 use crate::app::singbox::settings_patch::apply_port_settings_only;
+use crate::app::storage::custom_rule::{CustomRule, STORAGE_KEY as CUSTOM_RULES_KEY};
 use crate::app::storage::enhanced_storage_service::{
     apply_runtime_config_update, db_get_app_config, db_get_subscriptions,
-    db_save_app_config_internal, db_save_subscriptions,
+    db_save_app_config_internal, db_save_subscriptions, get_enhanced_storage,
 };
-use crate::app::storage::state_model::AppConfig;
-use crate::utils::http_client;
+use crate::app::storage::state_model::{AppConfig, Subscription, SubscriptionFetchOptions};
 use base64::{engine::general_purpose, Engine as _};
+use exporter::NodeExportFormat;
+use fetch_options::{normalize_fetch_options, prepare_fetch_client, unreadable_header_names};
 use helpers::{backup_existing_config, resolve_target_config_path, runtime_state_from_config};
 use parser::extract_nodes_from_subscription;
 use reqwest::header::{HeaderMap, USER_AGENT};
+use reqwest::Client;
 use serde::Serialize;
 use serde_json::Value;
 use std::error::Error;
@@ -56,6 +63,8 @@ pub struct SubscriptionPersistResult {
     pub subscription_download: Option<u64>,
     pub subscription_total: Option<u64>,
     pub subscription_expire: Option<u64>,
+    /// 因无法解密而未随请求发送的请求头，需提示用户重新填写
+    pub skipped_headers: Vec<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -141,8 +150,12 @@ fn extract_subscription_userinfo(headers: &HeaderMap) -> Option<SubscriptionUser
     parse_subscription_userinfo(raw)
 }
 
-fn should_retry_subscription_userinfo(result: &SubscriptionFetchResult) -> bool {
-    result.userinfo.is_none() && !result.body.trim().is_empty()
+/// 自定义了 User-Agent 时不再换用兼容 UA 重试：部分机场按 UA 下发不同格式或拒绝陌生 UA
+fn should_retry_subscription_userinfo(
+    result: &SubscriptionFetchResult,
+    custom_user_agent: bool,
+) -> bool {
+    !custom_user_agent && result.userinfo.is_none() && !result.body.trim().is_empty()
 }
 
 fn merge_subscription_fetch_result(
@@ -156,10 +169,11 @@ fn merge_subscription_fetch_result(
 }
 
 async fn fetch_subscription_content_with_user_agent(
+    client: &Client,
     url: &str,
     user_agent: Option<&str>,
 ) -> Result<SubscriptionFetchResult, Box<dyn Error>> {
-    let mut request = http_client::get_client().get(url);
+    let mut request = client.get(url);
     if let Some(user_agent) = user_agent {
         request = request.header(USER_AGENT, user_agent);
     }
@@ -173,11 +187,13 @@ async fn fetch_subscription_content_with_user_agent(
 }
 
 async fn fetch_subscription_content(
+    client: &Client,
     url: &str,
+    custom_user_agent: bool,
 ) -> Result<(String, Option<SubscriptionUserInfo>), Box<dyn Error>> {
-    let primary = fetch_subscription_content_with_user_agent(url, None).await?;
+    let primary = fetch_subscription_content_with_user_agent(client, url, None).await?;
 
-    if !should_retry_subscription_userinfo(&primary) {
+    if !should_retry_subscription_userinfo(&primary, custom_user_agent) {
         return Ok((primary.body, primary.userinfo));
     }
 
@@ -188,7 +204,8 @@ async fn fetch_subscription_content(
 
     let mut fallback_userinfo = None;
     for compat_user_agent in SUBSCRIPTION_USERINFO_COMPAT_UAS {
-        match fetch_subscription_content_with_user_agent(url, Some(compat_user_agent)).await {
+        match fetch_subscription_content_with_user_agent(client, url, Some(compat_user_agent)).await
+        {
             Ok(result) => {
                 if let Some(userinfo) = result.userinfo {
                     info!(
@@ -236,14 +253,7 @@ async fn update_subscription_userinfo(
 
     let mut updated = false;
     for sub in subscriptions.iter_mut() {
-        let path_match = sub
-            .config_path
-            .as_deref()
-            .map(|path| path == target_path.as_ref())
-            .unwrap_or(false);
-        let url_match = !trimmed_url.is_empty() && sub.url.trim() == trimmed_url;
-
-        if path_match || url_match {
+        if subscription_matches(sub, Some(target_path.as_ref()), trimmed_url) {
             sub.last_update = Some(now_ms);
             if let Some(info) = &userinfo {
                 sub.subscription_upload = info.upload;
@@ -269,6 +279,58 @@ async fn update_subscription_userinfo(
     Ok(())
 }
 
+fn subscription_matches(sub: &Subscription, target_path: Option<&str>, trimmed_url: &str) -> bool {
+    let path_match = target_path.is_some_and(|path| sub.config_path.as_deref() == Some(path));
+    let url_match = !trimmed_url.is_empty() && sub.url.trim() == trimmed_url;
+    path_match || url_match
+}
+
+async fn saved_fetch_options(
+    app_handle: &AppHandle,
+    target_path: &Path,
+    url: &str,
+) -> SubscriptionFetchOptions {
+    let target_path = target_path.to_string_lossy();
+    match db_get_subscriptions(app_handle.clone()).await {
+        Ok(subscriptions) => subscriptions
+            .into_iter()
+            .find(|sub| subscription_matches(sub, Some(target_path.as_ref()), url))
+            .map(|sub| sub.fetch_options)
+            .unwrap_or_default(),
+        Err(e) => {
+            warn!("读取订阅拉取选项失败，使用直连: {}", e);
+            SubscriptionFetchOptions::default()
+        }
+    }
+}
+
+/// 保存订阅的拉取选项，并把“经指定节点拉取”的路由规则同步到活动配置（重启内核后生效）
+#[tauri::command]
+pub async fn update_subscription_fetch_options(
+    app_handle: AppHandle,
+    url: String,
+    config_path: Option<String>,
+    options: SubscriptionFetchOptions,
+) -> Result<SubscriptionFetchOptions, String> {
+    let options = normalize_fetch_options(options)?;
+    let mut subscriptions = db_get_subscriptions(app_handle.clone())
+        .await
+        .map_err(|e| format!("读取订阅配置失败: {}", e))?;
+
+    let trimmed_url = url.trim();
+    let target = subscriptions
+        .iter_mut()
+        .find(|sub| subscription_matches(sub, config_path.as_deref(), trimmed_url))
+        .ok_or_else(|| "未找到对应的订阅".to_string())?;
+    target.fetch_options = options.clone();
+
+    db_save_subscriptions(subscriptions, app_handle.clone())
+        .await
+        .map_err(|e| format!("保存订阅配置失败: {}", e))?;
+    inject_into_active_config(&app_handle).await;
+    Ok(options)
+}
+
 #[tauri::command]
 #[allow(clippy::too_many_arguments)] // Tauri 接口需与前端参数保持一致
 pub async fn download_subscription(
@@ -280,6 +342,7 @@ pub async fn download_subscription(
     window: tauri::Window,
     proxy_port: Option<u16>,
     api_port: Option<u16>,
+    fetch_options: Option<SubscriptionFetchOptions>,
 ) -> Result<SubscriptionPersistResult, String> {
     let app_handle = window.app_handle();
     let apply_runtime = apply_runtime.unwrap_or(true);
@@ -297,8 +360,29 @@ pub async fn download_subscription(
 
     let target_path = resolve_target_config_path(file_name, config_path)?;
     let trimmed_url = url.trim();
+    // 未显式传入时使用该订阅已保存的拉取选项
+    let fetch_options = match fetch_options {
+        Some(options) => options,
+        None => saved_fetch_options(app_handle, &target_path, trimmed_url).await,
+    };
+    let custom_user_agent = fetch_options
+        .user_agent
+        .as_deref()
+        .is_some_and(|user_agent| !user_agent.trim().is_empty());
+    let skipped_headers = unreadable_header_names(&fetch_options);
+    if !skipped_headers.is_empty() {
+        warn!(
+            "订阅请求头无法解密，本次拉取不发送: {}",
+            skipped_headers.join(", ")
+        );
+    }
+    let client = prepare_fetch_client(&app_config, trimmed_url, fetch_options)
+        .await
+        .map_err(|e| format!("{}: {}", messages::ERR_SUBSCRIPTION_FAILED, e))?;
     let userinfo = download_and_process_subscription(
+        &client,
         trimmed_url,
+        custom_user_agent,
         use_original_config,
         app_handle,
         &app_config,
@@ -322,6 +406,8 @@ pub async fn download_subscription(
         if let Err(e) = apply_proxy_runtime_state(app_handle, &runtime_state).await {
             warn!("应用代理配置失败: {}", e);
         }
+        // 新配置不含自定义规则与订阅节点中转规则，重启内核前重新注入
+        inject_into_active_config(app_handle).await;
         auto_manage_with_saved_config(app_handle, true, "subscription-download").await;
     }
 
@@ -337,6 +423,7 @@ pub async fn download_subscription(
         subscription_download: userinfo.as_ref().and_then(|info| info.download),
         subscription_total: userinfo.as_ref().and_then(|info| info.total),
         subscription_expire: userinfo.as_ref().and_then(|info| info.expire),
+        skipped_headers,
     })
 }
 
@@ -401,11 +488,12 @@ pub async fn add_manual_subscription(
         subscription_download: None,
         subscription_total: None,
         subscription_expire: None,
+        skipped_headers: Vec::new(),
     })
 }
 
-#[tauri::command]
-pub async fn get_current_config(app_handle: AppHandle) -> Result<String, String> {
+/// 读取当前生效的配置文件内容（未设置时回退到默认 config.json）
+pub(crate) async fn read_active_config(app_handle: AppHandle) -> Result<String, String> {
     let app_config = db_get_app_config(app_handle)
         .await
         .map_err(|e| format!("获取应用配置失败: {}", e))?;
@@ -424,6 +512,50 @@ pub async fn get_current_config(app_handle: AppHandle) -> Result<String, String>
         .map_err(|e| format!("{}: {}", messages::ERR_CONFIG_READ_FAILED, e))
 }
 
+#[tauri::command]
+pub async fn get_current_config(app_handle: AppHandle) -> Result<String, String> {
+    read_active_config(app_handle).await
+}
+
+/// 将当前配置中的单个节点导出为分享链接、Clash YAML 或 sing-box JSON
+#[tauri::command]
+pub async fn export_node(
+    app_handle: AppHandle,
+    tag: String,
+    format: NodeExportFormat,
+) -> Result<String, String> {
+    let content = read_active_config(app_handle).await?;
+    let config: Value = serde_json::from_str(&content)
+        .map_err(|e| format!("{}: {}", messages::ERR_CONFIG_READ_FAILED, e))?;
+    let outbound = exporter::find_outbound(&config, &tag)
+        .ok_or_else(|| format!("当前配置中未找到节点: {}", tag))?;
+    exporter::export_outbound(outbound, format)
+}
+
+/// 将当前配置整体转换为 Clash Meta（Mihomo）配置，附带无法转换条目的说明
+#[tauri::command]
+pub async fn export_clash_meta_config(app_handle: AppHandle) -> Result<ClashMetaExport, String> {
+    let app_config = db_get_app_config(app_handle.clone())
+        .await
+        .map_err(|e| format!("获取应用配置失败: {}", e))?;
+    let content = read_active_config(app_handle.clone()).await?;
+    let config: Value = serde_json::from_str(&content)
+        .map_err(|e| format!("{}: {}", messages::ERR_CONFIG_READ_FAILED, e))?;
+
+    let storage = get_enhanced_storage(&app_handle).await?;
+    let custom_rules: Vec<CustomRule> = storage
+        .load_generic_config(CUSTOM_RULES_KEY)
+        .await
+        .map_err(|e| format!("读取自定义规则失败: {}", e))?
+        .unwrap_or_default();
+
+    convert_to_clash_meta(
+        &config,
+        &custom_rules,
+        normalize_default_outbound(&app_config),
+    )
+}
+
 #[tauri::command]
 pub async fn set_active_config_path(
     app_handle: AppHandle,
@@ -499,7 +631,9 @@ pub async fn get_current_proxy_mode(app_handle: AppHandle) -> Result<String, Str
 }
 
 async fn download_and_process_subscription(
+    client: &Client,
     url: &str,
+    custom_user_agent: bool,
     use_original_config: bool,
     _app_handle: &AppHandle,
     app_config: &AppConfig,
@@ -519,7 +653,7 @@ async fn download_and_process_subscription(
 
     info!("开始下载订阅: {}", url);
 
-    let (response_text, userinfo) = fetch_subscription_content(url)
+    let (response_text, userinfo) = fetch_subscription_content(client, url, custom_user_agent)
         .await
         .map_err(|e| format!("{}: {}", messages::ERR_SUBSCRIPTION_FAILED, e))?;
 
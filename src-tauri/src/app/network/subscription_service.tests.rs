@@ -95,7 +95,17 @@ fn should_retry_subscription_userinfo_when_body_exists_but_header_missing() {
         userinfo: None,
     };
 
-    assert!(should_retry_subscription_userinfo(&result));
+    assert!(should_retry_subscription_userinfo(&result, false));
+}
+
+#[test]
+fn should_not_retry_subscription_userinfo_with_custom_user_agent() {
+    let result = SubscriptionFetchResult {
+        body: "vmess://demo".to_string(),
+        userinfo: None,
+    };
+
+    assert!(!should_retry_subscription_userinfo(&result, true));
 }
 
 #[test]
@@ -105,7 +115,7 @@ fn should_not_retry_subscription_userinfo_when_body_is_empty() {
         userinfo: None,
     };
 
-    assert!(!should_retry_subscription_userinfo(&result));
+    assert!(!should_retry_subscription_userinfo(&result, false));
 }
 
 #[test]
@@ -220,6 +220,7 @@ async fn run_once(app: &AppHandle) -> Result<(), String> {
             window,
             Some(app_config.proxy_port),
             Some(app_config.api_port),
+            Some(sub.fetch_options.clone()),
         )
         .await
         {
@@ -0,0 +1,835 @@
+//! 节点导出：把 sing-box 出站转换回分享链接、Clash 代理条目或独立 JSON
+//!
+//! 与 `parser` 方向相反，字段映射尽量与解析时保持一致，保证“导出再导入”得到相同节点。
+
+use base64::Engine as _;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use serde_yaml::Mapping;
+use ts_rs::TS;
+use url::form_urlencoded;
+
+/// 分组与内置出站，不属于可分享的节点
+const NON_NODE_TYPES: &[&str] = &["selector", "urltest", "direct", "block", "dns"];
+
+/// 订阅中 Clash 配置的节点选择分组名
+const CLASH_SUBSCRIPTION_GROUP: &str = "PROXY";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export, export_to = "../src/types/generated/NodeExportFormat.ts")]
+pub enum NodeExportFormat {
+    /// vless:// 等标准分享链接
+    Uri,
+    /// Clash / Mihomo `proxies` 片段
+    Clash,
+    /// 可直接粘贴进 `outbounds` 的 sing-box 出站
+    Json,
+}
+
+/// 按格式导出单个节点
+pub fn export_outbound(outbound: &Value, format: NodeExportFormat) -> Result<String, String> {
+    match format {
+        NodeExportFormat::Uri => outbound_to_share_uri(outbound),
+        NodeExportFormat::Clash => outbound_to_clash_yaml(outbound),
+        NodeExportFormat::Json => outbound_to_json(outbound),
+    }
+}
+
+/// 在配置的 `outbounds` 中按 tag 查找节点
+pub fn find_outbound<'a>(config: &'a Value, tag: &str) -> Option<&'a Value> {
+    config
+        .get("outbounds")
+        .and_then(|o| o.as_array())?
+        .iter()
+        .find(|outbound| outbound.get("tag").and_then(|t| t.as_str()) == Some(tag))
+}
+
+/// 配置中的全部节点出站（按出现顺序，跳过分组与内置出站）
+pub fn node_outbounds(config: &Value) -> Vec<&Value> {
+    config
+        .get("outbounds")
+        .and_then(|o| o.as_array())
+        .map(|outbounds| {
+            outbounds
+                .iter()
+                .filter(|outbound| {
+                    outbound
+                        .get("type")
+                        .and_then(|t| t.as_str())
+                        .is_some_and(|t| !NON_NODE_TYPES.contains(&t))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn str_field<'a>(value: &'a Value, key: &str) -> Option<&'a str> {
+    value
+        .get(key)
+        .and_then(|v| v.as_str())
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+}
+
+fn required_str<'a>(outbound: &'a Value, key: &str) -> Result<&'a str, String> {
+    str_field(outbound, key).ok_or_else(|| format!("节点缺少字段: {}", key))
+}
+
+/// 密码类字段原样导出：首尾空白也是密码的一部分
+fn secret_field<'a>(value: &'a Value, key: &str) -> Option<&'a str> {
+    value
+        .get(key)
+        .and_then(|v| v.as_str())
+        .filter(|s| !s.is_empty())
+}
+
+fn required_secret<'a>(outbound: &'a Value, key: &str) -> Result<&'a str, String> {
+    secret_field(outbound, key).ok_or_else(|| format!("节点缺少字段: {}", key))
+}
+
+fn node_type(outbound: &Value) -> Result<&str, String> {
+    required_str(outbound, "type")
+}
+
+fn server_and_port(outbound: &Value) -> Result<(&str, u64), String> {
+    let server = required_str(outbound, "server")?;
+    let port = outbound
+        .get("server_port")
+        .and_then(|p| p.as_u64())
+        .ok_or_else(|| "节点缺少字段: server_port".to_string())?;
+    Ok((server, port))
+}
+
+/// 启用的 TLS 配置
+fn enabled_tls(outbound: &Value) -> Option<&Value> {
+    outbound
+        .get("tls")
+        .filter(|tls| tls.get("enabled").and_then(|v| v.as_bool()) == Some(true))
+}
+
+fn tls_insecure(tls: &Value) -> bool {
+    tls.get("insecure")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false)
+}
+
+fn tls_fingerprint(tls: &Value) -> Option<&str> {
+    let utls = tls.get("utls")?;
+    if utls.get("enabled").and_then(|v| v.as_bool()) != Some(true) {
+        return None;
+    }
+    str_field(utls, "fingerprint")
+}
+
+fn tls_alpn(tls: &Value) -> Option<Vec<String>> {
+    let alpn: Vec<String> = match tls.get("alpn")? {
+        Value::Array(items) => items
+            .iter()
+            .filter_map(|v| v.as_str().map(str::to_string))
+            .collect(),
+        Value::String(s) => s.split(',').map(|s| s.trim().to_string()).collect(),
+        _ => Vec::new(),
+    };
+    let alpn: Vec<String> = alpn.into_iter().filter(|s| !s.is_empty()).collect();
+    (!alpn.is_empty()).then_some(alpn)
+}
+
+fn enabled_reality(tls: &Value) -> Option<&Value> {
+    tls.get("reality")
+        .filter(|reality| reality.get("enabled").and_then(|v| v.as_bool()) == Some(true))
+}
+
+/// ws 的 Host 头在 sing-box 中可能是字符串或数组
+fn transport_host(transport: &Value) -> Option<&str> {
+    let host = transport.get("headers")?.get("Host")?;
+    host.as_str()
+        .or_else(|| host.as_array()?.first()?.as_str())
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+}
+
+fn bool_flag(value: bool) -> String {
+    u8::from(value).to_string()
+}
+
+/// IPv6 地址在 URI 中需要方括号
+fn uri_host(server: &str) -> String {
+    if server.contains(':') && !server.starts_with('[') {
+        format!("[{}]", server)
+    } else {
+        server.to_string()
+    }
+}
+
+fn build_uri(
+    scheme: &str,
+    userinfo: &str,
+    outbound: &Value,
+    query: &[(&str, String)],
+) -> Result<String, String> {
+    let (server, port) = server_and_port(outbound)?;
+    let mut uri = format!("{}://{}@{}:{}", scheme, userinfo, uri_host(server), port);
+    if !query.is_empty() {
+        let encoded = form_urlencoded::Serializer::new(String::new())
+            .extend_pairs(query.iter().map(|(k, v)| (*k, v.as_str())))
+            .finish();
+        uri.push('?');
+        uri.push_str(&encoded);
+    }
+    if let Some(tag) = str_field(outbound, "tag") {
+        uri.push('#');
+        uri.push_str(&urlencoding::encode(tag));
+    }
+    Ok(uri)
+}
+
+fn push_opt(query: &mut Vec<(&'static str, String)>, key: &'static str, value: Option<&str>) {
+    if let Some(value) = value {
+        query.push((key, value.to_string()));
+    }
+}
+
+/// vless / vmess / trojan 共用的传输层参数
+fn push_transport_query(
+    query: &mut Vec<(&'static str, String)>,
+    outbound: &Value,
+) -> Result<(), String> {
+    let Some(transport) = outbound.get("transport") else {
+        query.push(("type", "tcp".to_string()));
+        return Ok(());
+    };
+    match str_field(transport, "type") {
+        Some("ws") => {
+            query.push(("type", "ws".to_string()));
+            push_opt(query, "path", str_field(transport, "path"));
+            push_opt(query, "host", transport_host(transport));
+        }
+        Some("grpc") => {
+            query.push(("type", "grpc".to_string()));
+            push_opt(query, "serviceName", str_field(transport, "service_name"));
+        }
+        Some("http") => {
+            query.push(("type", "http".to_string()));
+            push_opt(query, "path", str_field(transport, "path"));
+            let host = transport
+                .get("host")
+                .and_then(|h| h.as_array())
+                .and_then(|hosts| hosts.first())
+                .and_then(|h| h.as_str());
+            push_opt(query, "host", host);
+        }
+        Some(other) => return Err(format!("分享链接不支持 {} 传输层", other)),
+        None => query.push(("type", "tcp".to_string())),
+    }
+    Ok(())
+}
+
+/// 把节点转换为标准分享链接
+pub fn outbound_to_share_uri(outbound: &Value) -> Result<String, String> {
+    match node_type(outbound)? {
+        "vless" => vless_uri(outbound),
+        "vmess" => vmess_uri(outbound),
+        "trojan" => trojan_uri(outbound),
+        "shadowsocks" => shadowsocks_uri(outbound),
+        "hysteria2" => hysteria2_uri(outbound),
+        "tuic" => tuic_uri(outbound),
+        "anytls" => anytls_uri(outbound),
+        other => Err(format!("不支持导出 {} 类型节点的分享链接", other)),
+    }
+}
+
+fn vless_uri(outbound: &Value) -> Result<String, String> {
+    let uuid = required_str(outbound, "uuid")?;
+    let mut query = vec![("encryption", "none".to_string())];
+    push_opt(&mut query, "flow", str_field(outbound, "flow"));
+
+    match enabled_tls(outbound) {
+        Some(tls) => {
+            let reality = enabled_reality(tls);
+            let security = if reality.is_some() { "reality" } else { "tls" };
+            query.push(("security", security.to_string()));
+            push_opt(&mut query, "sni", str_field(tls, "server_name"));
+            push_opt(&mut query, "fp", tls_fingerprint(tls));
+            if tls_insecure(tls) {
+                query.push(("allowInsecure", "1".to_string()));
+            }
+            if let Some(alpn) = tls_alpn(tls) {
+                query.push(("alpn", alpn.join(",")));
+            }
+            if let Some(reality) = reality {
+                push_opt(&mut query, "pbk", str_field(reality, "public_key"));
+                push_opt(&mut query, "sid", str_field(reality, "short_id"));
+            }
+        }
+        None => query.push(("security", "none".to_string())),
+    }
+    push_transport_query(&mut query, outbound)?;
+
+    build_uri("vless", &urlencoding::encode(uuid), outbound, &query)
+}
+
+fn vmess_uri(outbound: &Value) -> Result<String, String> {
+    let (server, port) = server_and_port(outbound)?;
+    let uuid = required_str(outbound, "uuid")?;
+    let tls = enabled_tls(outbound);
+    let transport = outbound.get("transport");
+
+    let (network, host, path) = match transport.and_then(|t| str_field(t, "type")) {
+        None => ("tcp", None, None),
+        Some("ws") => (
+            "ws",
+            transport.and_then(transport_host),
+            transport.and_then(|t| str_field(t, "path")),
+        ),
+        Some("grpc") => (
+            "grpc",
+            None,
+            transport.and_then(|t| str_field(t, "service_name")),
+        ),
+        Some(other) => return Err(format!("分享链接不支持 {} 传输层", other)),
+    };
+
+    // v2rayN 格式：base64(JSON)，端口与 alterId 习惯写成字符串
+    let mut payload = json!({
+        "v": "2",
+        "ps": str_field(outbound, "tag").unwrap_or_default(),
+        "add": server,
+        "port": port.to_string(),
+        "id": uuid,
+        "aid": outbound.get("alter_id").and_then(|v| v.as_u64()).unwrap_or(0).to_string(),
+        "scy": str_field(outbound, "security").unwrap_or("auto"),
+        "net": network,
+        "type": "none",
+        "host": host.unwrap_or_default(),
+        "path": path.unwrap_or_default(),
+        "tls": if tls.is_some() { "tls" } else { "" },
+        "sni": tls.and_then(|t| str_field(t, "server_name")).unwrap_or_default(),
+        "fp": tls.and_then(tls_fingerprint).unwrap_or_default(),
+    });
+    if tls.is_some_and(tls_insecure) {
+        payload["allowInsecure"] = json!("1");
+    }
+
+    Ok(format!(
+        "vmess://{}",
+        base64::engine::general_purpose::STANDARD.encode(payload.to_string())
+    ))
+}
+
+fn trojan_uri(outbound: &Value) -> Result<String, String> {
+    let password = required_secret(outbound, "password")?;
+    let mut query = Vec::new();
+    if let Some(tls) = enabled_tls(outbound) {
+        push_opt(&mut query, "sni", str_field(tls, "server_name"));
+        if tls_insecure(tls) {
+            query.push(("allowInsecure", "1".to_string()));
+        }
+        if let Some(alpn) = tls_alpn(tls) {
+            query.push(("alpn", alpn.join(",")));
+        }
+    }
+    push_transport_query(&mut query, outbound)?;
+
+    build_uri("trojan", &urlencoding::encode(password), outbound, &query)
+}
+
+fn shadowsocks_uri(outbound: &Value) -> Result<String, String> {
+    let (server, port) = server_and_port(outbound)?;
+    let method = required_str(outbound, "method")?;
+    let password = required_secret(outbound, "password")?;
+
+    // SIP002：SS-2022 的 userinfo 为百分号编码的明文 method:password，其余为 base64url(method:password)
+    let userinfo = if method.starts_with("2022-") {
+        format!(
+            "{}:{}",
+            urlencoding::encode(method),
+            urlencoding::encode(password)
+        )
+    } else {
+        base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(format!("{}:{}", method, password))
+    };
+    let mut uri = format!("ss://{}@{}:{}", userinfo, uri_host(server), port);
+    if let Some(plugin) = str_field(outbound, "plugin") {
+        let plugin = match str_field(outbound, "plugin_opts") {
+            Some(opts) => format!("{};{}", plugin, opts),
+            None => plugin.to_string(),
+        };
+        uri.push_str("?plugin=");
+        uri.push_str(&urlencoding::encode(&plugin));
+    }
+    if let Some(tag) = str_field(outbound, "tag") {
+        uri.push('#');
+        uri.push_str(&urlencoding::encode(tag));
+    }
+    Ok(uri)
+}
+
+fn hysteria2_uri(outbound: &Value) -> Result<String, String> {
+    let password = required_secret(outbound, "password")?;
+    let mut query = Vec::new();
+    if let Some(tls) = enabled_tls(outbound) {
+        push_opt(&mut query, "sni", str_field(tls, "server_name"));
+        if tls_insecure(tls) {
+            query.push(("insecure", "1".to_string()));
+        }
+        if let Some(alpn) = tls_alpn(tls) {
+            query.push(("alpn", alpn.join(",")));
+        }
+    }
+    if let Some(obfs) = outbound.get("obfs") {
+        push_opt(&mut query, "obfs", str_field(obfs, "type"));
+        push_opt(&mut query, "obfs-password", secret_field(obfs, "password"));
+    }
+    for (field, key) in [("up_mbps", "upmbps"), ("down_mbps", "downmbps")] {
+        if let Some(mbps) = outbound.get(field).and_then(|v| v.as_u64()) {
+            query.push((key, mbps.to_string()));
+        }
+    }
+    if let Some(fast_open) = outbound.get("tcp_fast_open").and_then(|v| v.as_bool()) {
+        query.push(("fastopen", bool_flag(fast_open)));
+    }
+    if let Some(ports) = outbound.get("server_ports").and_then(|v| v.as_array()) {
+        let ports: Vec<&str> = ports.iter().filter_map(|p| p.as_str()).collect();
+        if !ports.is_empty() {
+            query.push(("mport", ports.join(",")));
+        }
+    }
+
+    build_uri(
+        "hysteria2",
+        &urlencoding::encode(password),
+        outbound,
+        &query,
+    )
+}
+
+fn tuic_uri(outbound: &Value) -> Result<String, String> {
+    let uuid = required_str(outbound, "uuid")?;
+    let userinfo = match secret_field(outbound, "password") {
+        Some(password) => format!(
+            "{}:{}",
+            urlencoding::encode(uuid),
+            urlencoding::encode(password)
+        ),
+        None => urlencoding::encode(uuid).into_owned(),
+    };
+
+    let mut query = Vec::new();
+    if let Some(tls) = enabled_tls(outbound) {
+        push_opt(&mut query, "sni", str_field(tls, "server_name"));
+        if tls_insecure(tls) {
+            query.push(("insecure", "1".to_string()));
+        }
+        if let Some(alpn) = tls_alpn(tls) {
+            query.push(("alpn", alpn.join(",")));
+        }
+    }
+    for key in [
+        "congestion_control",
+        "udp_relay_mode",
+        "heartbeat",
+        "network",
+    ] {
+        push_opt(&mut query, key, str_field(outbound, key));
+    }
+    for key in ["udp_over_stream", "zero_rtt_handshake"] {
+        if let Some(flag) = outbound.get(key).and_then(|v| v.as_bool()) {
+            query.push((key, bool_flag(flag)));
+        }
+    }
+
+    build_uri("tuic", &userinfo, outbound, &query)
+}
+
+fn anytls_uri(outbound: &Value) -> Result<String, String> {
+    let password = required_secret(outbound, "password")?;
+    let mut query = Vec::new();
+    if let Some(tls) = enabled_tls(outbound) {
+        push_opt(&mut query, "sni", str_field(tls, "server_name"));
+        if tls_insecure(tls) {
+            query.push(("insecure", "1".to_string()));
+        }
+        if let Some(alpn) = tls_alpn(tls) {
+            query.push(("alpn", alpn.join(",")));
+        }
+    }
+    for key in ["idle_session_check_interval", "idle_session_timeout"] {
+        push_opt(&mut query, key, str_field(outbound, key));
+    }
+    if let Some(min_idle_session) = outbound.get("min_idle_session").and_then(|v| v.as_u64()) {
+        query.push(("min_idle_session", min_idle_session.to_string()));
+    }
+
+    build_uri("anytls", &urlencoding::encode(password), outbound, &query)
+}
+
+fn set(map: &mut Mapping, key: &str, value: Value) {
+    if value.is_null() {
+        return;
+    }
+    if let Ok(value) = serde_yaml::to_value(value) {
+        map.insert(key.into(), value);
+    }
+}
+
+fn set_opt(map: &mut Mapping, key: &str, value: Option<&str>) {
+    if let Some(value) = value {
+        set(map, key, json!(value));
+    }
+}
+
+/// TLS 字段；vmess / vless / socks / http 用 `tls` + `servername`，其余协议默认即 TLS，只写 `sni`
+fn set_clash_tls(proxy: &mut Mapping, outbound: &Value, explicit_tls: bool) {
+    let Some(tls) = enabled_tls(outbound) else {
+        if explicit_tls {
+            set(proxy, "tls", json!(false));
+        }
+        return;
+    };
+    if explicit_tls {
+        set(proxy, "tls", json!(true));
+        set_opt(proxy, "servername", str_field(tls, "server_name"));
+    } else {
+        set_opt(proxy, "sni", str_field(tls, "server_name"));
+    }
+    if tls_insecure(tls) {
+        set(proxy, "skip-cert-verify", json!(true));
+    }
+    if let Some(alpn) = tls_alpn(tls) {
+        set(proxy, "alpn", json!(alpn));
+    }
+    set_opt(proxy, "client-fingerprint", tls_fingerprint(tls));
+    if let Some(reality) = enabled_reality(tls) {
+        let mut opts = Mapping::new();
+        set_opt(&mut opts, "public-key", str_field(reality, "public_key"));
+        set_opt(&mut opts, "short-id", str_field(reality, "short_id"));
+        proxy.insert("reality-opts".into(), serde_yaml::Value::Mapping(opts));
+    }
+}
+
+fn set_clash_transport(proxy: &mut Mapping, outbound: &Value) -> Result<(), String> {
+    let Some(transport) = outbound.get("transport") else {
+        return Ok(());
+    };
+    match str_field(transport, "type") {
+        Some("ws") => {
+            set(proxy, "network", json!("ws"));
+            let mut opts = json!({});
+            if let Some(path) = str_field(transport, "path") {
+                opts["path"] = json!(path);
+            }
+            if let Some(host) = transport_host(transport) {
+                opts["headers"] = json!({ "Host": host });
+            }
+            set(proxy, "ws-opts", opts);
+        }
+        Some("grpc") => {
+            set(proxy, "network", json!("grpc"));
+            let service_name = str_field(transport, "service_name").unwrap_or_default();
+            set(
+                proxy,
+                "grpc-opts",
+                json!({ "grpc-service-name": service_name }),
+            );
+        }
+        Some("http") => {
+            set(proxy, "network", json!("h2"));
+            let mut opts = json!({});
+            if let Some(path) = str_field(transport, "path") {
+                opts["path"] = json!(path);
+            }
+            if let Some(hosts) = transport.get("host").filter(|h| h.is_array()) {
+                opts["host"] = hosts.clone();
+            }
+            set(proxy, "h2-opts", opts);
+        }
+        Some(other) => return Err(format!("Clash 不支持 {} 传输层", other)),
+        None => {}
+    }
+    Ok(())
+}
+
+/// sing-box 的 `plugin_opts`（`k=v;flag`）转换为 Clash `plugin-opts`
+fn clash_plugin_opts(plugin: &str, opts: &str) -> Result<(&'static str, Value), String> {
+    let mut mapped = json!({});
+    for item in opts.split(';').map(str::trim).filter(|s| !s.is_empty()) {
+        let (key, value) = item.split_once('=').unwrap_or((item, ""));
+        match (plugin, key) {
+            ("obfs-local", "obfs") | ("v2ray-plugin", "mode") => mapped["mode"] = json!(value),
+            ("obfs-local", "obfs-host") | ("v2ray-plugin", "host") => mapped["host"] = json!(value),
+            ("v2ray-plugin", "path") => mapped["path"] = json!(value),
+            ("v2ray-plugin", "tls") => mapped["tls"] = json!(true),
+            _ => {}
+        }
+    }
+    match plugin {
+        "obfs-local" => Ok(("obfs", mapped)),
+        "v2ray-plugin" => Ok(("v2ray-plugin", mapped)),
+        other => Err(format!("Clash 不支持 shadowsocks 插件 {}", other)),
+    }
+}
+
+/// 把节点转换为 Clash / Mihomo 的单个 `proxies` 条目
+pub fn outbound_to_clash_proxy(outbound: &Value) -> Result<Mapping, String> {
+    let singbox_type = node_type(outbound)?;
+    let name = required_str(outbound, "tag")?;
+    let (server, port) = server_and_port(outbound)?;
+
+    let clash_type = match singbox_type {
+        "shadowsocks" => "ss",
+        "socks" => "socks5",
+        "vmess" | "vless" | "trojan" | "hysteria2" | "tuic" | "anytls" | "http" => singbox_type,
+        other => return Err(format!("Clash 不支持 {} 类型节点", other)),
+    };
+
+    let mut proxy = Mapping::new();
+    set(&mut proxy, "name", json!(name));
+    set(&mut proxy, "type", json!(clash_type));
+    set(&mut proxy, "server", json!(server));
+    set(&mut proxy, "port", json!(port));
+    if str_field(outbound, "network") != Some("tcp") && clash_type != "http" {
+        set(&mut proxy, "udp", json!(true));
+    }
+
+    match singbox_type {
+        "vmess" => {
+            set_opt(&mut proxy, "uuid", Some(required_str(outbound, "uuid")?));
+            set(
+                &mut proxy,
+                "alterId",
+                json!(outbound
+                    .get("alter_id")
+                    .and_then(|v| v.as_u64())
+                    .unwrap_or(0)),
+            );
+            set_opt(
+                &mut proxy,
+                "cipher",
+                Some(str_field(outbound, "security").unwrap_or("auto")),
+            );
+            set_clash_tls(&mut proxy, outbound, true);
+            set_clash_transport(&mut proxy, outbound)?;
+        }
+        "vless" => {
+            set_opt(&mut proxy, "uuid", Some(required_str(outbound, "uuid")?));
+            set_opt(&mut proxy, "flow", str_field(outbound, "flow"));
+            set_clash_tls(&mut proxy, outbound, true);
+            set_clash_transport(&mut proxy, outbound)?;
+        }
+        "trojan" => {
+            set_opt(
+                &mut proxy,
+                "password",
+                Some(required_secret(outbound, "password")?),
+            );
+            set_clash_tls(&mut proxy, outbound, false);
+            set_clash_transport(&mut proxy, outbound)?;
+        }
+        "shadowsocks" => {
+            set_opt(
+                &mut proxy,
+                "cipher",
+                Some(required_str(outbound, "method")?),
+            );
+            set_opt(
+                &mut proxy,
+                "password",
+                Some(required_secret(outbound, "password")?),
+            );
+            if let Some(plugin) = str_field(outbound, "plugin") {
+                let opts = str_field(outbound, "plugin_opts").unwrap_or_default();
+                let (clash_plugin, clash_opts) = clash_plugin_opts(plugin, opts)?;
+                set(&mut proxy, "plugin", json!(clash_plugin));
+                set(&mut proxy, "plugin-opts", clash_opts);
+            }
+        }
+        "hysteria2" => {
+            set_opt(
+                &mut proxy,
+                "password",
+                Some(required_secret(outbound, "password")?),
+            );
+            set_clash_tls(&mut proxy, outbound, false);
+            // sing-box 端口范围写作 `a:b`，Mihomo 为 `a-b`
+            if let Some(ports) = outbound.get("server_ports").and_then(|v| v.as_array()) {
+                let ports: Vec<String> = ports
+                    .iter()
+                    .filter_map(|p| p.as_str())
+                    .map(|p| p.replace(':', "-"))
+                    .collect();
+                if !ports.is_empty() {
+                    set(&mut proxy, "ports", json!(ports.join(",")));
+                }
+            }
+            if let Some(up) = outbound.get("up_mbps").and_then(|v| v.as_u64()) {
+                set(&mut proxy, "up", json!(up));
+            }
+            if let Some(down) = outbound.get("down_mbps").and_then(|v| v.as_u64()) {
+                set(&mut proxy, "down", json!(down));
+            }
+            if let Some(obfs) = outbound.get("obfs") {
+                set_opt(&mut proxy, "obfs", str_field(obfs, "type"));
+                set_opt(&mut proxy, "obfs-password", secret_field(obfs, "password"));
+            }
+        }
+        "tuic" => {
+            set_opt(&mut proxy, "uuid", Some(required_str(outbound, "uuid")?));
+            set_opt(&mut proxy, "password", secret_field(outbound, "password"));
+            set_clash_tls(&mut proxy, outbound, false);
+            if let Some(disable_sni) = enabled_tls(outbound)
+                .and_then(|tls| tls.get("disable_sni"))
+                .and_then(|v| v.as_bool())
+            {
+                set(&mut proxy, "disable-sni", json!(disable_sni));
+            }
+            set_opt(
+                &mut proxy,
+                "congestion-controller",
+                str_field(outbound, "congestion_control"),
+            );
+            set_opt(
+                &mut proxy,
+                "udp-relay-mode",
+                str_field(outbound, "udp_relay_mode"),
+            );
+            if let Some(zero_rtt) = outbound.get("zero_rtt_handshake").and_then(|v| v.as_bool()) {
+                set(&mut proxy, "reduce-rtt", json!(zero_rtt));
+            }
+        }
+        "anytls" => {
+            set_opt(
+                &mut proxy,
+                "password",
+                Some(required_secret(outbound, "password")?),
+            );
+            set_clash_tls(&mut proxy, outbound, false);
+            set_opt(
+                &mut proxy,
+                "idle-session-check-interval",
+                str_field(outbound, "idle_session_check_interval"),
+            );
+            set_opt(
+                &mut proxy,
+                "idle-session-timeout",
+                str_field(outbound, "idle_session_timeout"),
+            );
+            if let Some(min_idle) = outbound.get("min_idle_session").and_then(|v| v.as_u64()) {
+                set(&mut proxy, "min-idle-session", json!(min_idle));
+            }
+        }
+        "socks" | "http" => {
+            set_opt(&mut proxy, "username", str_field(outbound, "username"));
+            set_opt(&mut proxy, "password", secret_field(outbound, "password"));
+            if enabled_tls(outbound).is_some() {
+                set_clash_tls(&mut proxy, outbound, true);
+            }
+        }
+        _ => {}
+    }
+
+    Ok(proxy)
+}
+
+/// 输出可直接导入 Clash 的 `proxies:` 片段
+pub fn outbound_to_clash_yaml(outbound: &Value) -> Result<String, String> {
+    let proxy = outbound_to_clash_proxy(outbound)?;
+    let mut document = Mapping::new();
+    document.insert(
+        "proxies".into(),
+        serde_yaml::Value::Sequence(vec![serde_yaml::Value::Mapping(proxy)]),
+    );
+    serde_yaml::to_string(&document).map_err(|e| format!("生成 Clash YAML 失败: {}", e))
+}
+
+/// 去掉引用本配置其他条目的字段，得到可单独使用的出站
+fn standalone_outbound(outbound: &Value) -> Result<Value, String> {
+    node_type(outbound)?;
+    let mut standalone = outbound.clone();
+    if let Some(obj) = standalone.as_object_mut() {
+        obj.remove("detour");
+        obj.remove("domain_resolver");
+    }
+    Ok(standalone)
+}
+
+/// 输出独立的 sing-box 出站 JSON
+pub fn outbound_to_json(outbound: &Value) -> Result<String, String> {
+    let standalone = standalone_outbound(outbound)?;
+    serde_json::to_string_pretty(&standalone).map_err(|e| format!("生成节点 JSON 失败: {}", e))
+}
+
+/// 批量导出节点，作为订阅内容提供给其他设备。
+///
+/// - `Uri`：分享链接逐行拼接后整体 base64，兼容常见客户端的订阅格式；
+/// - `Clash`：`proxies` 加一个包含全部节点的选择分组，可直接作为配置导入；
+/// - `Json`：sing-box `outbounds` 列表。
+///
+/// 无法转换的节点会被跳过，返回值第二项为跳过的节点 tag。
+pub fn export_node_list(
+    nodes: &[&Value],
+    format: NodeExportFormat,
+) -> Result<(String, Vec<String>), String> {
+    let mut skipped = Vec::new();
+    let mut skip = |node: &Value| {
+        skipped.push(str_field(node, "tag").unwrap_or_default().to_string());
+    };
+
+    let content = match format {
+        NodeExportFormat::Uri => {
+            let mut lines = Vec::new();
+            for &node in nodes {
+                match outbound_to_share_uri(node) {
+                    Ok(uri) => lines.push(uri),
+                    Err(_) => skip(node),
+                }
+            }
+            base64::engine::general_purpose::STANDARD.encode(lines.join("\n"))
+        }
+        NodeExportFormat::Clash => {
+            let mut proxies = Vec::new();
+            let mut names = Vec::new();
+            for &node in nodes {
+                match outbound_to_clash_proxy(node) {
+                    Ok(proxy) => {
+                        names.push(json!(str_field(node, "tag").unwrap_or_default()));
+                        proxies.push(serde_yaml::Value::Mapping(proxy));
+                    }
+                    Err(_) => skip(node),
+                }
+            }
+            let mut group = Mapping::new();
+            set(&mut group, "name", json!(CLASH_SUBSCRIPTION_GROUP));
+            set(&mut group, "type", json!("select"));
+            if names.is_empty() {
+                names.push(json!("DIRECT"));
+            }
+            set(&mut group, "proxies", json!(names));
+
+            let mut document = Mapping::new();
+            document.insert("proxies".into(), serde_yaml::Value::Sequence(proxies));
+            document.insert(
+                "proxy-groups".into(),
+                serde_yaml::Value::Sequence(vec![serde_yaml::Value::Mapping(group)]),
+            );
+            set(
+                &mut document,
+                "rules",
+                json!([format!("MATCH,{}", CLASH_SUBSCRIPTION_GROUP)]),
+            );
+            serde_yaml::to_string(&document).map_err(|e| format!("生成 Clash YAML 失败: {}", e))?
+        }
+        NodeExportFormat::Json => {
+            let mut outbounds = Vec::new();
+            for &node in nodes {
+                match standalone_outbound(node) {
+                    Ok(outbound) => outbounds.push(outbound),
+                    Err(_) => skip(node),
+                }
+            }
+            serde_json::to_string_pretty(&json!({ "outbounds": outbounds }))
+                .map_err(|e| format!("生成节点 JSON 失败: {}", e))?
+        }
+    };
+    Ok((content, skipped))
+}
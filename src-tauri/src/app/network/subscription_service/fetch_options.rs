@@ -0,0 +1,329 @@
+//! 订阅拉取选项：出口（直连 / 当前代理 / 指定节点）、User-Agent、额外请求头、超时与 TLS 校验
+//!
+//! 经代理拉取时请求发往本机 mixed-in。指定节点没有单独的入站，而是向活动配置注入一条
+//! “来自 mixed-in 且目标为订阅主机 → 该节点”的路由规则，与自定义规则一样重启内核后生效。
+//! 额外请求头的值常含鉴权 token，在数据库与备份中加密保存，读取时解密。
+
+use crate::app::constants::config::DEFAULT_INBOUND_TAG;
+use crate::app::core::kernel_service::status::is_kernel_running;
+use crate::app::storage::secret_cipher::{
+    decrypt_secret_in, encrypt_secret_in, SEALED_SECRET_PREFIX,
+};
+use crate::app::storage::state_model::{
+    AppConfig, Subscription, SubscriptionFetchDetour, SubscriptionFetchOptions, SubscriptionHeader,
+};
+use crate::utils::http_client;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, USER_AGENT};
+use reqwest::{Client, Proxy};
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::path::Path;
+use std::time::Duration;
+use tracing::warn;
+use url::{Host, Url};
+
+const DEFAULT_TIMEOUT_SECS: u64 = 30;
+const MAX_TIMEOUT_SECS: u64 = 300;
+const CONNECT_TIMEOUT_SECS: u64 = 10;
+const HEADER_SECRET_PURPOSE: &str = "subscription-header";
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+/// 校验并整理用户填写的选项：去掉空白项，非“指定节点”时清空节点 tag
+pub fn normalize_fetch_options(
+    options: SubscriptionFetchOptions,
+) -> Result<SubscriptionFetchOptions, String> {
+    let detour_node = match options.detour {
+        SubscriptionFetchDetour::Node => Some(
+            non_empty(options.detour_node).ok_or_else(|| "请选择拉取订阅使用的节点".to_string())?,
+        ),
+        SubscriptionFetchDetour::Direct | SubscriptionFetchDetour::CurrentProxy => None,
+    };
+
+    let user_agent = non_empty(options.user_agent);
+    if let Some(user_agent) = &user_agent {
+        HeaderValue::from_str(user_agent).map_err(|_| "User-Agent 含有非法字符".to_string())?;
+    }
+
+    let mut headers = Vec::new();
+    for header in options.headers {
+        let name = header.name.trim().to_string();
+        if name.is_empty() {
+            continue;
+        }
+        let parsed_name = HeaderName::from_bytes(name.as_bytes())
+            .map_err(|_| format!("请求头名称「{}」不合法", name))?;
+        if parsed_name == USER_AGENT {
+            return Err("User-Agent 请填写在单独的 User-Agent 一栏".to_string());
+        }
+        // 无法解密的请求头原样保留密文，留待用户重新填写
+        if is_unreadable_header(&header) {
+            headers.push(SubscriptionHeader {
+                name,
+                value: header.value,
+                unreadable: true,
+            });
+            continue;
+        }
+        let value = header.value.trim().to_string();
+        HeaderValue::from_str(&value).map_err(|_| format!("请求头「{}」的值含有非法字符", name))?;
+        headers.push(SubscriptionHeader {
+            name,
+            value,
+            unreadable: false,
+        });
+    }
+
+    if let Some(secs) = options.timeout_secs {
+        if secs == 0 || secs > MAX_TIMEOUT_SECS {
+            return Err(format!("超时时间需在 1-{} 秒之间", MAX_TIMEOUT_SECS));
+        }
+    }
+
+    Ok(SubscriptionFetchOptions {
+        detour: options.detour,
+        detour_node,
+        user_agent,
+        headers,
+        timeout_secs: options.timeout_secs,
+        accept_invalid_certs: options.accept_invalid_certs,
+    })
+}
+
+fn is_unreadable_header(header: &SubscriptionHeader) -> bool {
+    header.unreadable || header.value.starts_with(SEALED_SECRET_PREFIX)
+}
+
+/// 因无法解密而不会随请求发送的请求头名称
+pub fn unreadable_header_names(options: &SubscriptionFetchOptions) -> Vec<String> {
+    options
+        .headers
+        .iter()
+        .filter(|header| is_unreadable_header(header))
+        .map(|header| header.name.clone())
+        .collect()
+}
+
+fn build_header_map(options: &SubscriptionFetchOptions) -> Result<HeaderMap, String> {
+    let mut map = HeaderMap::new();
+    if let Some(user_agent) = &options.user_agent {
+        let value =
+            HeaderValue::from_str(user_agent).map_err(|_| "User-Agent 含有非法字符".to_string())?;
+        map.insert(USER_AGENT, value);
+    }
+    // 不能把密文当作值发给订阅服务器
+    for header in options
+        .headers
+        .iter()
+        .filter(|header| !is_unreadable_header(header))
+    {
+        let name = HeaderName::from_bytes(header.name.as_bytes())
+            .map_err(|_| format!("请求头名称「{}」不合法", header.name))?;
+        let value = HeaderValue::from_str(&header.value)
+            .map_err(|_| format!("请求头「{}」的值含有非法字符", header.name))?;
+        map.append(name, value);
+    }
+    Ok(map)
+}
+
+fn is_plain_direct(options: &SubscriptionFetchOptions) -> bool {
+    options.detour == SubscriptionFetchDetour::Direct
+        && options.user_agent.is_none()
+        && options.headers.is_empty()
+        && options.timeout_secs.is_none()
+        && !options.accept_invalid_certs
+}
+
+/// 按选项构建请求客户端；未做任何定制时复用全局直连客户端的连接池
+pub fn build_fetch_client(
+    options: &SubscriptionFetchOptions,
+    proxy_port: u16,
+) -> Result<Client, String> {
+    if is_plain_direct(options) {
+        return Ok(http_client::get_client().clone());
+    }
+
+    // default_headers 需在 user_agent 之后设置，自定义 UA 才能覆盖默认值
+    let builder = Client::builder()
+        .timeout(Duration::from_secs(
+            options.timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS),
+        ))
+        .connect_timeout(Duration::from_secs(CONNECT_TIMEOUT_SECS))
+        .user_agent(http_client::DEFAULT_USER_AGENT)
+        .default_headers(build_header_map(options)?)
+        .danger_accept_invalid_certs(options.accept_invalid_certs);
+
+    let builder = match options.detour {
+        SubscriptionFetchDetour::Direct => builder.no_proxy(),
+        SubscriptionFetchDetour::CurrentProxy | SubscriptionFetchDetour::Node => {
+            let proxy = Proxy::all(format!("http://127.0.0.1:{}", proxy_port))
+                .map_err(|e| format!("构建代理地址失败: {}", e))?;
+            builder.proxy(proxy)
+        }
+    };
+
+    builder
+        .build()
+        .map_err(|e| format!("创建订阅请求客户端失败: {}", e))
+}
+
+/// 经指定节点拉取订阅的路由规则：只匹配来自 mixed-in、目标为订阅主机的连接
+pub fn detour_route_rule(url: &str, node: &str) -> Option<Value> {
+    let parsed = Url::parse(url.trim()).ok()?;
+    let rule = match parsed.host()? {
+        Host::Domain(domain) => json!({
+            "inbound": [DEFAULT_INBOUND_TAG],
+            "domain": [domain.to_lowercase()],
+            "outbound": node
+        }),
+        Host::Ipv4(ip) => json!({
+            "inbound": [DEFAULT_INBOUND_TAG],
+            "ip_cidr": [format!("{}/32", ip)],
+            "outbound": node
+        }),
+        Host::Ipv6(ip) => json!({
+            "inbound": [DEFAULT_INBOUND_TAG],
+            "ip_cidr": [format!("{}/128", ip)],
+            "outbound": node
+        }),
+    };
+    Some(rule)
+}
+
+/// 为所有“经指定节点拉取”的订阅生成路由规则。
+///
+/// 节点不在当前配置的出站中时跳过，避免内核因引用未知出站而无法启动。
+pub fn subscription_detour_rules(config: &Value, subscriptions: &[Subscription]) -> Vec<Value> {
+    let tags: HashSet<&str> = config
+        .get("outbounds")
+        .and_then(Value::as_array)
+        .map(|outbounds| {
+            outbounds
+                .iter()
+                .filter_map(|outbound| outbound.get("tag").and_then(Value::as_str))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let mut rules: Vec<Value> = Vec::new();
+    for sub in subscriptions.iter().filter(|sub| !sub.is_manual) {
+        let options = &sub.fetch_options;
+        if options.detour != SubscriptionFetchDetour::Node {
+            continue;
+        }
+        let Some(node) = options
+            .detour_node
+            .as_deref()
+            .filter(|node| tags.contains(node))
+        else {
+            continue;
+        };
+        if let Some(rule) = detour_route_rule(&sub.url, node) {
+            if !rules.contains(&rule) {
+                rules.push(rule);
+            }
+        }
+    }
+    rules
+}
+
+/// 配置中是否已有经该节点访问订阅主机的路由规则
+pub fn has_detour_route_rule(config: &Value, url: &str, node: &str) -> bool {
+    let Some(expected) = detour_route_rule(url, node) else {
+        return false;
+    };
+    config
+        .pointer("/route/rules")
+        .and_then(Value::as_array)
+        .is_some_and(|rules| rules.contains(&expected))
+}
+
+fn read_active_config_value(app_config: &AppConfig) -> Option<Value> {
+    let path = app_config.active_config_path.as_deref()?;
+    let content = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+/// 拉取前检查出口是否可用，并构建对应的请求客户端
+pub async fn prepare_fetch_client(
+    app_config: &AppConfig,
+    url: &str,
+    options: SubscriptionFetchOptions,
+) -> Result<Client, String> {
+    let options = normalize_fetch_options(options)?;
+
+    if options.detour != SubscriptionFetchDetour::Direct && !is_kernel_running().await? {
+        return Err("该订阅设置为经代理拉取，但内核未运行".to_string());
+    }
+
+    if let Some(node) = options.detour_node.as_deref() {
+        let has_rule = read_active_config_value(app_config)
+            .is_some_and(|config| has_detour_route_rule(&config, url, node));
+        if !has_rule {
+            return Err(format!(
+                "当前配置中没有经节点「{}」拉取订阅的路由规则，请确认该节点存在于当前配置并重启内核后重试",
+                node
+            ));
+        }
+    }
+
+    build_fetch_client(&options, app_config.proxy_port)
+}
+
+/// 写入数据库或备份前加密各订阅额外请求头的值；无法解密而保留的密文不再重复加密
+pub fn seal_fetch_header_secrets(
+    subscriptions: &mut [Subscription],
+    data_dir: &Path,
+) -> Result<(), String> {
+    for header in subscriptions
+        .iter_mut()
+        .flat_map(|sub| sub.fetch_options.headers.iter_mut())
+        .filter(|header| !header.value.starts_with(SEALED_SECRET_PREFIX))
+    {
+        header.value = format!(
+            "{}{}",
+            SEALED_SECRET_PREFIX,
+            encrypt_secret_in(data_dir, HEADER_SECRET_PURPOSE, &header.value)?
+        );
+    }
+    Ok(())
+}
+
+/// 读取后解密请求头；旧版本保存的明文原样保留，下次保存时加密。
+///
+/// 解密失败（例如数据目录变化）时保留密文并标记为 `unreadable`：界面据此提示重新填写，
+/// 拉取订阅时跳过该请求头，再次保存也不会丢失。返回解密失败的请求头数。
+pub fn open_fetch_header_secrets(subscriptions: &mut [Subscription], data_dir: &Path) -> usize {
+    let mut failures = 0;
+    for sub in subscriptions.iter_mut() {
+        for header in sub.fetch_options.headers.iter_mut() {
+            let Some(sealed) = header.value.strip_prefix(SEALED_SECRET_PREFIX) else {
+                header.unreadable = false;
+                continue;
+            };
+            match decrypt_secret_in(data_dir, HEADER_SECRET_PURPOSE, sealed) {
+                Ok(plain) => {
+                    header.value = plain;
+                    header.unreadable = false;
+                }
+                Err(err) => {
+                    warn!(
+                        "订阅 {} 的请求头 {} 解密失败，拉取时将不发送: {}",
+                        sub.name, header.name, err
+                    );
+                    header.unreadable = true;
+                    failures += 1;
+                }
+            }
+        }
+    }
+    failures
+}
+
+#[cfg(test)]
+#[path = "fetch_options.tests.rs"]
+mod tests;
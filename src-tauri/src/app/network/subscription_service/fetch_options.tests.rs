@@ -0,0 +1,240 @@
+use super::*;
+
+fn header(name: &str, value: &str) -> SubscriptionHeader {
+    SubscriptionHeader {
+        name: name.to_string(),
+        value: value.to_string(),
+        unreadable: false,
+    }
+}
+
+fn node_options(node: &str) -> SubscriptionFetchOptions {
+    SubscriptionFetchOptions {
+        detour: SubscriptionFetchDetour::Node,
+        detour_node: Some(node.to_string()),
+        ..SubscriptionFetchOptions::default()
+    }
+}
+
+fn subscription(url: &str, options: SubscriptionFetchOptions) -> Subscription {
+    let mut sub: Subscription = serde_json::from_value(json!({
+        "name": "test-sub",
+        "url": url,
+        "is_loading": false,
+        "last_update": null,
+        "is_manual": false,
+        "manual_content": null,
+        "use_original_config": false,
+        "config_path": null,
+        "backup_path": null,
+        "auto_update_interval_minutes": 720,
+        "subscription_upload": null,
+        "subscription_download": null,
+        "subscription_total": null,
+        "subscription_expire": null,
+        "auto_update_fail_count": null,
+        "last_auto_update_attempt": null,
+        "last_auto_update_error": null,
+        "last_auto_update_error_type": null,
+        "last_auto_update_backoff_until": null
+    }))
+    .expect("旧版订阅数据应能解析");
+    sub.fetch_options = options;
+    sub
+}
+
+fn config_with_outbounds(tags: &[&str]) -> Value {
+    let outbounds: Vec<Value> = tags
+        .iter()
+        .map(|tag| json!({ "type": "vless", "tag": tag }))
+        .collect();
+    json!({ "outbounds": outbounds, "route": { "rules": [] } })
+}
+
+#[test]
+fn legacy_subscription_should_default_to_direct_fetch() {
+    let sub = subscription(
+        "https://example.com/sub",
+        SubscriptionFetchOptions::default(),
+    );
+    let value = serde_json::to_value(&sub).unwrap();
+    let mut legacy = value.as_object().unwrap().clone();
+    legacy.remove("fetch_options");
+
+    let restored: Subscription = serde_json::from_value(Value::Object(legacy)).unwrap();
+    assert_eq!(restored.fetch_options, SubscriptionFetchOptions::default());
+    assert_eq!(value["fetch_options"]["detour"], json!("direct"));
+}
+
+#[test]
+fn normalize_should_trim_values_and_drop_empty_headers() {
+    let options = SubscriptionFetchOptions {
+        detour: SubscriptionFetchDetour::CurrentProxy,
+        detour_node: Some("香港 01".to_string()),
+        user_agent: Some("  clash.meta  ".to_string()),
+        headers: vec![header(" X-Token ", " abc "), header("  ", "ignored")],
+        timeout_secs: Some(60),
+        accept_invalid_certs: true,
+    };
+
+    let normalized = normalize_fetch_options(options).unwrap();
+    assert_eq!(normalized.detour_node, None);
+    assert_eq!(normalized.user_agent.as_deref(), Some("clash.meta"));
+    assert_eq!(normalized.headers, vec![header("X-Token", "abc")]);
+    assert_eq!(normalized.timeout_secs, Some(60));
+    assert!(normalized.accept_invalid_certs);
+}
+
+#[test]
+fn normalize_should_reject_invalid_options() {
+    let missing_node = SubscriptionFetchOptions {
+        detour: SubscriptionFetchDetour::Node,
+        detour_node: Some("  ".to_string()),
+        ..SubscriptionFetchOptions::default()
+    };
+    assert!(normalize_fetch_options(missing_node).is_err());
+
+    for timeout_secs in [0, MAX_TIMEOUT_SECS + 1] {
+        let options = SubscriptionFetchOptions {
+            timeout_secs: Some(timeout_secs),
+            ..SubscriptionFetchOptions::default()
+        };
+        assert!(normalize_fetch_options(options).is_err());
+    }
+
+    for bad in [
+        header("Bad Header", "v"),
+        header("user-agent", "v"),
+        header("X-A", "a\nb"),
+    ] {
+        let options = SubscriptionFetchOptions {
+            headers: vec![bad],
+            ..SubscriptionFetchOptions::default()
+        };
+        assert!(normalize_fetch_options(options).is_err());
+    }
+}
+
+#[test]
+fn detour_route_rule_should_match_mixed_inbound_and_host() {
+    assert_eq!(
+        detour_route_rule("https://Sub.Example.com:8443/api?token=1", "香港 01"),
+        Some(json!({
+            "inbound": ["mixed-in"],
+            "domain": ["sub.example.com"],
+            "outbound": "香港 01"
+        }))
+    );
+    assert_eq!(
+        detour_route_rule("http://10.0.0.2/sub", "node")
+            .and_then(|rule| rule.get("ip_cidr").cloned()),
+        Some(json!(["10.0.0.2/32"]))
+    );
+    assert_eq!(detour_route_rule("not a url", "node"), None);
+}
+
+#[test]
+fn subscription_detour_rules_should_skip_unknown_nodes_and_duplicates() {
+    let config = config_with_outbounds(&["香港 01", "direct"]);
+    let mut manual = subscription("https://manual.example.com/sub", node_options("香港 01"));
+    manual.is_manual = true;
+    let subscriptions = vec![
+        subscription("https://a.example.com/sub", node_options("香港 01")),
+        subscription("https://a.example.com/other", node_options("香港 01")),
+        subscription("https://b.example.com/sub", node_options("已删除节点")),
+        subscription(
+            "https://c.example.com/sub",
+            SubscriptionFetchOptions::default(),
+        ),
+        manual,
+    ];
+
+    let rules = subscription_detour_rules(&config, &subscriptions);
+    assert_eq!(rules.len(), 1);
+    assert_eq!(rules[0]["domain"], json!(["a.example.com"]));
+}
+
+#[test]
+fn has_detour_route_rule_should_check_route_rules() {
+    let mut config = config_with_outbounds(&["香港 01"]);
+    let url = "https://a.example.com/sub";
+    assert!(!has_detour_route_rule(&config, url, "香港 01"));
+
+    let rule = detour_route_rule(url, "香港 01").unwrap();
+    config["route"]["rules"] = json!([rule]);
+    assert!(has_detour_route_rule(&config, url, "香港 01"));
+    assert!(!has_detour_route_rule(&config, url, "日本 01"));
+}
+
+#[test]
+fn build_fetch_client_should_accept_proxy_and_tls_options() {
+    let options = SubscriptionFetchOptions {
+        detour: SubscriptionFetchDetour::CurrentProxy,
+        user_agent: Some("clash.meta".to_string()),
+        headers: vec![header("X-Token", "abc")],
+        timeout_secs: Some(5),
+        accept_invalid_certs: true,
+        ..SubscriptionFetchOptions::default()
+    };
+
+    assert!(build_fetch_client(&options, 12080).is_ok());
+    assert!(build_fetch_client(&SubscriptionFetchOptions::default(), 12080).is_ok());
+}
+
+#[test]
+fn header_values_should_be_sealed_for_storage() {
+    let data_dir = std::env::temp_dir().join("subscription-header-secret-test");
+    let options = SubscriptionFetchOptions {
+        headers: vec![header("Authorization", "Bearer secret-token")],
+        ..SubscriptionFetchOptions::default()
+    };
+    let original = vec![subscription("https://example.com/sub", options)];
+
+    let mut stored = original.clone();
+    seal_fetch_header_secrets(&mut stored, &data_dir).expect("seal");
+    let sealed = &stored[0].fetch_options.headers[0];
+    assert_eq!(sealed.name, "Authorization");
+    assert!(sealed.value.starts_with(SEALED_SECRET_PREFIX) && !sealed.value.contains("secret"));
+
+    assert_eq!(open_fetch_header_secrets(&mut stored, &data_dir), 0);
+    assert_eq!(stored[0].fetch_options, original[0].fetch_options);
+
+    // 旧版本保存的明文原样读取
+    let mut legacy = original.clone();
+    assert_eq!(open_fetch_header_secrets(&mut legacy, &data_dir), 0);
+    assert_eq!(legacy[0].fetch_options, original[0].fetch_options);
+
+    // 换了数据目录无法解密时保留密文并标记，拉取时不发送
+    let mut moved = original.clone();
+    seal_fetch_header_secrets(&mut moved, &data_dir).expect("seal");
+    let other_dir = data_dir.join("other");
+    assert_eq!(open_fetch_header_secrets(&mut moved, &other_dir), 1);
+    let kept = moved[0].fetch_options.headers[0].clone();
+    assert!(kept.unreadable && kept.value.starts_with(SEALED_SECRET_PREFIX));
+    assert_eq!(
+        unreadable_header_names(&moved[0].fetch_options),
+        vec!["Authorization".to_string()]
+    );
+    assert!(!build_header_map(&moved[0].fetch_options)
+        .expect("header map")
+        .contains_key("authorization"));
+
+    // 再次保存不会重复加密，回到原数据目录仍可解密
+    seal_fetch_header_secrets(&mut moved, &other_dir).expect("seal");
+    assert_eq!(moved[0].fetch_options.headers[0].value, kept.value);
+    assert_eq!(open_fetch_header_secrets(&mut moved, &data_dir), 0);
+    assert_eq!(moved[0].fetch_options, original[0].fetch_options);
+}
+
+#[test]
+fn normalize_should_keep_unreadable_headers_sealed() {
+    let mut sealed = header("Authorization", "enc:v1:opaque");
+    sealed.unreadable = true;
+    let options = SubscriptionFetchOptions {
+        headers: vec![sealed.clone(), header("X-Token", " abc ")],
+        ..SubscriptionFetchOptions::default()
+    };
+
+    let normalized = normalize_fetch_options(options).expect("normalize");
+    assert_eq!(normalized.headers, vec![sealed, header("X-Token", "abc")]);
+}
@@ -1,5 +1,5 @@
 use crate::app::core::proxy_service::ProxyRuntimeState;
-use crate::app::core::tun_profile::TunProxyOptions;
+use crate::app::core::tun_profile::{configured_fake_ip_ranges, TunProxyOptions};
 use crate::app::storage::state_model::AppConfig;
 use crate::utils::app_util::get_work_dir_sync;
 use std::path::{Path, PathBuf};
@@ -45,6 +45,7 @@ pub fn runtime_state_from_config(app_config: &AppConfig) -> ProxyRuntimeState {
         proxy_port: app_config.proxy_port,
         allow_lan_access: app_config.allow_lan_access,
         system_proxy_enabled: app_config.system_proxy_enabled,
+        system_proxy_pac: app_config.proxy_mode == "pac",
         tun_enabled: app_config.tun_enabled,
         system_proxy_bypass: app_config.system_proxy_bypass.clone(),
         tun_options: TunProxyOptions {
@@ -56,7 +57,11 @@ pub fn runtime_state_from_config(app_config: &AppConfig) -> ProxyRuntimeState {
             stack: app_config.tun_stack.clone(),
             enable_ipv6: app_config.tun_enable_ipv6,
             route_exclude_address: app_config.tun_route_exclude_address.clone(),
+            route_address: app_config.tun_route_address.clone(),
+            route_presets: app_config.tun_route_presets.clone(),
             interface_name: None,
+            split_rules: app_config.tun_split_rules.clone(),
+            fake_ip_ranges: configured_fake_ip_ranges(app_config),
         },
     }
 }
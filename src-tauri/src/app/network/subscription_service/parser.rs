@@ -2,7 +2,7 @@ use base64::Engine as _;
 use serde_json::{json, Value};
 use std::collections::HashMap;
 use tracing::info;
-use url::Url;
+use url::{Host, Url};
 
 pub fn extract_nodes_from_subscription(
     content: &str,
@@ -691,6 +691,22 @@ fn decode_tag(raw: Option<&str>) -> String {
         .unwrap_or_default()
 }
 
+/// 用户名 / 密码在 URI 中可能被百分号编码（如密码含 `@`、`:`），`Url` 不会自动解码；
+/// 密码的首尾空白同样是有效字符，不做裁剪
+fn decode_userinfo(raw: &str) -> String {
+    urlencoding::decode(raw)
+        .map(|s| s.into_owned())
+        .unwrap_or_else(|_| raw.to_string())
+}
+
+/// 节点地址：IPv6 去掉 URI 中的方括号，与 sing-box 的 `server` 字段格式一致
+fn url_host(url: &Url) -> Option<String> {
+    match url.host()? {
+        Host::Ipv6(ip) => Some(ip.to_string()),
+        host => Some(host.to_string()),
+    }
+}
+
 fn default_tag_for_url(url: &Url) -> String {
     let host = url.host_str().unwrap_or("unknown");
     let port = url.port().unwrap_or(0);
@@ -721,6 +737,16 @@ fn parse_boolish(value: &str) -> Option<bool> {
     }
 }
 
+/// 跳过证书校验：v2rayN 系链接用 `allowInsecure`，其余客户端多用 `insecure`
+fn query_insecure(query: &HashMap<String, String>) -> bool {
+    query
+        .get("allowInsecure")
+        .or_else(|| query.get("insecure"))
+        .map(|v| v.as_str())
+        .and_then(parse_boolish)
+        .unwrap_or(false)
+}
+
 fn parse_csv_string_array(value: Option<&str>) -> Option<Value> {
     let list: Vec<Value> = value?
         .split(',')
@@ -757,12 +783,12 @@ fn build_basic_tls_config(server: &str, server_name: &str, insecure: bool) -> Va
 
 fn parse_vless_uri(uri: &str) -> Option<Value> {
     let url = Url::parse(uri).ok()?;
-    let uuid = url.username().trim();
+    let uuid = decode_userinfo(url.username());
     if uuid.is_empty() {
         return None;
     }
 
-    let server = url.host_str()?.to_string();
+    let server = url_host(&url)?;
     let server_port = url.port().unwrap_or(443) as u64;
 
     let query = parse_query_map(&url);
@@ -804,6 +830,9 @@ fn parse_vless_uri(uri: &str) -> Option<Value> {
 
     if security == "tls" || security == "reality" || !sni.is_empty() {
         let mut tls = build_tls_config(&server, sni, &fingerprint);
+        if query_insecure(&query) {
+            tls["insecure"] = json!(true);
+        }
         if security == "reality" {
             let mut reality = json!({
                 "enabled": true
@@ -852,12 +881,12 @@ fn parse_vless_uri(uri: &str) -> Option<Value> {
 
 fn parse_trojan_uri(uri: &str) -> Option<Value> {
     let url = Url::parse(uri).ok()?;
-    let password = url.username().trim();
+    let password = decode_userinfo(url.username());
     if password.is_empty() {
         return None;
     }
 
-    let server = url.host_str()?.to_string();
+    let server = url_host(&url)?;
     let server_port = url.port().unwrap_or(443) as u64;
 
     let query = parse_query_map(&url);
@@ -878,12 +907,7 @@ fn parse_trojan_uri(uri: &str) -> Option<Value> {
         .map(|s| s.trim())
         .unwrap_or("");
 
-    let insecure = query
-        .get("allowInsecure")
-        .or_else(|| query.get("insecure"))
-        .map(|v| v.as_str())
-        .and_then(parse_boolish)
-        .unwrap_or(false);
+    let insecure = query_insecure(&query);
 
     let mut node = json!({
         "tag": tag,
@@ -927,12 +951,12 @@ fn parse_trojan_uri(uri: &str) -> Option<Value> {
 
 fn parse_hysteria2_uri(uri: &str) -> Option<Value> {
     let url = Url::parse(uri).ok()?;
-    let password = url.username().trim();
+    let password = decode_userinfo(url.username());
     if password.is_empty() {
         return None;
     }
 
-    let server = url.host_str()?.to_string();
+    let server = url_host(&url)?;
     let server_port = url.port().unwrap_or(443) as u64;
 
     let query = parse_query_map(&url);
@@ -1027,12 +1051,12 @@ fn parse_hysteria2_uri(uri: &str) -> Option<Value> {
 
 fn parse_tuic_uri(uri: &str) -> Option<Value> {
     let url = Url::parse(uri).ok()?;
-    let uuid = url.username().trim();
+    let uuid = decode_userinfo(url.username());
     if uuid.is_empty() {
         return None;
     }
 
-    let server = url.host_str()?.to_string();
+    let server = url_host(&url)?;
     let server_port = url.port().unwrap_or(443) as u64;
     let query = parse_query_map(&url);
     let tag = {
@@ -1044,7 +1068,10 @@ fn parse_tuic_uri(uri: &str) -> Option<Value> {
         }
     };
 
-    let password = url.password().map(str::trim).filter(|value| !value.is_empty());
+    let password = url
+        .password()
+        .map(decode_userinfo)
+        .filter(|value| !value.is_empty());
     let sni = query
         .get("sni")
         .or_else(|| query.get("servername"))
@@ -1121,12 +1148,12 @@ fn parse_tuic_uri(uri: &str) -> Option<Value> {
 
 fn parse_anytls_uri(uri: &str) -> Option<Value> {
     let url = Url::parse(uri).ok()?;
-    let password = url.username().trim();
+    let password = decode_userinfo(url.username());
     if password.is_empty() {
         return None;
     }
 
-    let server = url.host_str()?.to_string();
+    let server = url_host(&url)?;
     let server_port = url.port().unwrap_or(443) as u64;
     let query = parse_query_map(&url);
     let tag = {
@@ -1196,7 +1223,12 @@ fn parse_vmess_uri(uri: &str) -> Option<Value> {
     let text = String::from_utf8(decoded).ok()?;
     let v: Value = serde_json::from_str(&text).ok()?;
 
-    let server = v.get("add").and_then(|s| s.as_str())?.to_string();
+    let server = v
+        .get("add")
+        .and_then(|s| s.as_str())?
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .to_string();
     let port = match v.get("port") {
         Some(Value::String(s)) => s.parse::<u64>().ok(),
         Some(Value::Number(n)) => n.as_u64(),
@@ -1239,6 +1271,15 @@ fn parse_vmess_uri(uri: &str) -> Option<Value> {
 
     if tls.eq_ignore_ascii_case("tls") {
         node["tls"] = build_tls_config(node["server"].as_str().unwrap_or(""), sni, "chrome");
+        let insecure = match v.get("allowInsecure").or_else(|| v.get("insecure")) {
+            Some(Value::Bool(value)) => *value,
+            Some(Value::String(value)) => parse_boolish(value).unwrap_or(false),
+            Some(Value::Number(value)) => value.as_u64() == Some(1),
+            _ => false,
+        };
+        if insecure {
+            node["tls"]["insecure"] = json!(true);
+        }
     }
 
     let network = v.get("net").and_then(|s| s.as_str()).unwrap_or("");
@@ -1270,7 +1311,7 @@ fn parse_vmess_uri(uri: &str) -> Option<Value> {
 
 fn parse_ss_uri(uri: &str) -> Option<Value> {
     // ss:// 支持多种格式（这里尽量覆盖常见形式）：
-    // 1) ss://method:password@host:port#name
+    // 1) ss://method:password@host:port#name（SIP002 对 SS-2022 使用百分号编码的明文）
     // 2) ss://BASE64(method:password)@host:port#name
     // 3) ss://BASE64(method:password@host:port)#name
     let raw = uri.trim().strip_prefix("ss://")?.trim();
@@ -1345,10 +1386,16 @@ fn base64_decode_relaxed(input: &str) -> Option<Vec<u8>> {
         .ok()
 }
 
+/// `host:port`，IPv6 地址带方括号（`[2001:db8::1]:8388`），返回时去掉方括号
 fn parse_host_port(hostport: &str) -> Option<(String, u64)> {
-    let mut it = hostport.splitn(2, ':');
-    let server = it.next()?.trim().to_string();
-    let port = it.next()?.trim().parse::<u64>().ok()?;
+    let (server, port) = hostport.trim().rsplit_once(':')?;
+    let server = server
+        .strip_prefix('[')
+        .and_then(|s| s.strip_suffix(']'))
+        .unwrap_or(server)
+        .trim()
+        .to_string();
+    let port = port.trim().parse::<u64>().ok()?;
     if server.is_empty() {
         return None;
     }
@@ -1356,12 +1403,13 @@ fn parse_host_port(hostport: &str) -> Option<(String, u64)> {
 }
 
 fn parse_ss_method_password(input: &str) -> Option<(String, String)> {
-    // input 可能是明文 method:password，也可能是 base64(method:password)
+    // input 可能是明文 method:password（密码可能被百分号编码），也可能是 base64(method:password)；
+    // 密码的首尾空白是有效字符，只裁剪加密方式
     if let Some((method, password)) = input.split_once(':') {
-        let m = method.trim();
-        let p = password.trim();
-        if !m.is_empty() && !p.is_empty() {
-            return Some((m.to_string(), p.to_string()));
+        let m = decode_userinfo(method);
+        let p = decode_userinfo(password);
+        if !m.trim().is_empty() && !p.is_empty() {
+            return Some((m.trim().to_string(), p));
         }
     }
 
@@ -1369,11 +1417,10 @@ fn parse_ss_method_password(input: &str) -> Option<(String, String)> {
     let decoded_text = String::from_utf8(decoded).ok()?;
     let (method, password) = decoded_text.split_once(':')?;
     let m = method.trim();
-    let p = password.trim();
-    if m.is_empty() || p.is_empty() {
+    if m.is_empty() || password.is_empty() {
         return None;
     }
-    Some((m.to_string(), p.to_string()))
+    Some((m.to_string(), password.to_string()))
 }
 
 // `selector` 分组的更新逻辑已迁移至 `app::singbox::config_generator`：
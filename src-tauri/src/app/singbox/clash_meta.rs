@@ -0,0 +1,952 @@
+//! 把本程序生成的 sing-box 配置转换为等价的 Clash Meta（Mihomo）YAML
+//!
+//! 映射关系：
+//! - 节点出站 → `proxies`（复用单节点导出的 Clash 映射）；selector / urltest → `proxy-groups`；
+//! - 远程 geosite / geoip 规则集 → MetaCubeX 提供的同名 mrs 规则集（Mihomo 不读取 `.srs`）；
+//! - `route.rules` 与自定义规则 → `rules`，`route.final` → `MATCH`；
+//! - DNS 服务器、分流规则与 fakeip → `dns` 段。
+//!
+//! 无法等价转换的条目不会中断导出，统一记录到 `warnings` 交给用户确认。
+
+use crate::app::network::subscription_service::exporter::outbound_to_clash_proxy;
+use crate::app::singbox::config_generator::inject_custom_rules;
+use crate::app::storage::custom_rule::CustomRule;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use serde_yaml::{Mapping, Value as YamlValue};
+use std::collections::HashMap;
+use ts_rs::TS;
+
+const GEOSITE_MRS_URL: &str =
+    "https://raw.githubusercontent.com/MetaCubeX/meta-rules-dat/meta/geo/geosite";
+const GEOIP_MRS_URL: &str =
+    "https://raw.githubusercontent.com/MetaCubeX/meta-rules-dat/meta/geo/geoip";
+const DEFAULT_URLTEST_URL: &str = "https://www.gstatic.com/generate_204";
+
+/// 路由规则中不参与匹配的字段
+const ROUTE_RULE_NON_MATCH_KEYS: &[&str] = &["outbound", "action", "method", "invert"];
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../src/types/generated/ClashMetaExport.ts")]
+pub struct ClashMetaExport {
+    pub yaml: String,
+    /// 未能转换或语义有差异的条目，逐条描述
+    pub warnings: Vec<String>,
+}
+
+/// 一条路由规则的匹配条件：组间为“与”，组内为“或”，与 sing-box 默认规则的语义一致
+struct Condition {
+    groups: Vec<Vec<String>>,
+    invert: bool,
+}
+
+struct Converter {
+    warnings: Vec<String>,
+    /// sing-box 出站 tag → Clash 中的名称（direct / block 映射为 DIRECT / REJECT）
+    names: HashMap<String, String>,
+    /// 可转换的规则集 tag → behavior
+    providers: HashMap<String, &'static str>,
+}
+
+fn yaml_str(value: &str) -> YamlValue {
+    YamlValue::String(value.to_string())
+}
+
+fn yaml_strings(values: &[String]) -> YamlValue {
+    YamlValue::Sequence(values.iter().map(|v| yaml_str(v)).collect())
+}
+
+fn str_field<'a>(value: &'a Value, key: &str) -> Option<&'a str> {
+    value
+        .get(key)
+        .and_then(|v| v.as_str())
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+}
+
+/// sing-box 字段既可写单值也可写数组，统一展开为字符串列表
+fn field_values(value: &Value) -> Vec<String> {
+    match value {
+        Value::Array(items) => items.iter().flat_map(field_values).collect(),
+        Value::String(s) => vec![s.trim().to_string()],
+        Value::Number(n) => vec![n.to_string()],
+        _ => Vec::new(),
+    }
+}
+
+/// 解析 sing-box 时长（如 `3m`、`1d`、`1h30m`），返回秒数
+fn parse_duration_secs(raw: &str) -> Option<u64> {
+    let mut total = 0u64;
+    let mut number = String::new();
+    let mut chars = raw.trim().chars().peekable();
+    if chars.peek().is_none() {
+        return None;
+    }
+    while let Some(c) = chars.next() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        let value: u64 = number.parse().ok()?;
+        number.clear();
+        let unit = match c {
+            'd' => 86_400,
+            'h' => 3_600,
+            'm' if chars.peek() == Some(&'s') => {
+                chars.next();
+                0
+            }
+            'm' => 60,
+            's' => 1,
+            _ => return None,
+        };
+        total += value * unit;
+    }
+    if !number.is_empty() {
+        return None;
+    }
+    Some(total)
+}
+
+fn logic_expr(op: &str, items: &[String]) -> String {
+    let inner: Vec<String> = items.iter().map(|item| format!("({})", item)).collect();
+    format!("{},({})", op, inner.join(","))
+}
+
+impl Condition {
+    fn to_expr(&self) -> String {
+        let groups: Vec<String> = self
+            .groups
+            .iter()
+            .map(|group| {
+                if group.len() == 1 {
+                    group[0].clone()
+                } else {
+                    logic_expr("OR", group)
+                }
+            })
+            .collect();
+        let expr = if groups.len() == 1 {
+            groups[0].clone()
+        } else {
+            logic_expr("AND", &groups)
+        };
+        if self.invert {
+            logic_expr("NOT", &[expr])
+        } else {
+            expr
+        }
+    }
+}
+
+/// DNS 服务器的默认端口，与之相同时在地址中省略
+fn default_dns_port(server_type: &str) -> u64 {
+    match server_type {
+        "https" | "h3" => 443,
+        "tls" | "quic" => 853,
+        _ => 53,
+    }
+}
+
+fn is_ip_host(host: &str) -> bool {
+    host.trim_matches(|c| c == '[' || c == ']')
+        .parse::<std::net::IpAddr>()
+        .is_ok()
+}
+
+impl Converter {
+    fn warn(&mut self, message: String) {
+        if !self.warnings.contains(&message) {
+            self.warnings.push(message);
+        }
+    }
+
+    fn convert_outbounds(&mut self, config: &Value) -> (Vec<YamlValue>, Vec<YamlValue>) {
+        let outbounds: Vec<&Value> = config
+            .get("outbounds")
+            .and_then(|v| v.as_array())
+            .map(|items| items.iter().collect())
+            .unwrap_or_default();
+
+        let mut proxies: Vec<(Mapping, Option<String>)> = Vec::new();
+        let mut groups: Vec<&Value> = Vec::new();
+        for &outbound in &outbounds {
+            let (Some(tag), Some(kind)) = (str_field(outbound, "tag"), str_field(outbound, "type"))
+            else {
+                continue;
+            };
+            match kind {
+                "direct" => {
+                    self.names.insert(tag.to_string(), "DIRECT".to_string());
+                }
+                "block" => {
+                    self.names.insert(tag.to_string(), "REJECT".to_string());
+                }
+                "dns" => {}
+                "selector" | "urltest" => {
+                    self.names.insert(tag.to_string(), tag.to_string());
+                    groups.push(outbound);
+                }
+                _ => match outbound_to_clash_proxy(outbound) {
+                    Ok(proxy) => {
+                        self.names.insert(tag.to_string(), tag.to_string());
+                        let detour = str_field(outbound, "detour").map(str::to_string);
+                        proxies.push((proxy, detour));
+                    }
+                    Err(e) => self.warn(format!("节点「{}」未导出：{}", tag, e)),
+                },
+            }
+        }
+
+        // 链式代理：sing-box 的 detour 对应 Mihomo 的 dialer-proxy
+        let proxies = proxies
+            .into_iter()
+            .map(|(mut proxy, detour)| {
+                if let Some(detour) = detour {
+                    match self.names.get(&detour).cloned() {
+                        Some(name) if name != "DIRECT" => {
+                            proxy.insert("dialer-proxy".into(), yaml_str(&name));
+                        }
+                        Some(_) => {}
+                        None => {
+                            let name = proxy.get("name").and_then(|v| v.as_str()).unwrap_or("");
+                            let message =
+                                format!("节点「{}」的前置代理「{}」无法导出，已忽略", name, detour);
+                            self.warn(message);
+                        }
+                    }
+                }
+                YamlValue::Mapping(proxy)
+            })
+            .collect();
+
+        let groups = groups
+            .into_iter()
+            .map(|group| YamlValue::Mapping(self.convert_group(group)))
+            .collect();
+        (proxies, groups)
+    }
+
+    fn convert_group(&mut self, group: &Value) -> Mapping {
+        let tag = str_field(group, "tag").unwrap_or_default();
+        let mut members: Vec<String> = Vec::new();
+        for member in group.get("outbounds").map(field_values).unwrap_or_default() {
+            match self.names.get(&member) {
+                Some(name) if !members.contains(name) => members.push(name.clone()),
+                Some(_) => {}
+                None => self.warn(format!("分组「{}」中的「{}」无法导出，已移除", tag, member)),
+            }
+        }
+        // Mihomo 没有 default 字段，select 分组默认选中第一项
+        if let Some(default) = str_field(group, "default").and_then(|d| self.names.get(d)) {
+            if let Some(pos) = members.iter().position(|m| m == default) {
+                let name = members.remove(pos);
+                members.insert(0, name);
+            }
+        }
+        if members.is_empty() {
+            self.warn(format!("分组「{}」没有可导出的成员，已回退为 DIRECT", tag));
+            members.push("DIRECT".to_string());
+        }
+
+        let mut mapping = Mapping::new();
+        mapping.insert("name".into(), yaml_str(tag));
+        if str_field(group, "type") == Some("urltest") {
+            mapping.insert("type".into(), yaml_str("url-test"));
+            mapping.insert("proxies".into(), yaml_strings(&members));
+            let url = str_field(group, "url").unwrap_or(DEFAULT_URLTEST_URL);
+            mapping.insert("url".into(), yaml_str(url));
+            let interval = str_field(group, "interval")
+                .and_then(parse_duration_secs)
+                .unwrap_or(180);
+            mapping.insert("interval".into(), interval.into());
+            let tolerance = group
+                .get("tolerance")
+                .and_then(|v| v.as_u64())
+                .unwrap_or(50);
+            mapping.insert("tolerance".into(), tolerance.into());
+        } else {
+            mapping.insert("type".into(), yaml_str("select"));
+            mapping.insert("proxies".into(), yaml_strings(&members));
+        }
+        mapping
+    }
+
+    fn convert_rule_providers(&mut self, config: &Value) -> Mapping {
+        let mut providers = Mapping::new();
+        let rule_sets = config
+            .pointer("/route/rule_set")
+            .and_then(|v| v.as_array())
+            .cloned()
+            .unwrap_or_default();
+        for rule_set in &rule_sets {
+            let Some(tag) = str_field(rule_set, "tag") else {
+                continue;
+            };
+            let (behavior, url) = if let Some(name) = tag.strip_prefix("geosite-") {
+                ("domain", format!("{}/{}.mrs", GEOSITE_MRS_URL, name))
+            } else if let Some(name) = tag.strip_prefix("geoip-") {
+                ("ipcidr", format!("{}/{}.mrs", GEOIP_MRS_URL, name))
+            } else {
+                self.warn(format!(
+                    "规则集「{}」不是 geosite / geoip 规则集，Mihomo 无法读取 sing-box 规则集格式，已跳过",
+                    tag
+                ));
+                continue;
+            };
+            let interval = str_field(rule_set, "update_interval")
+                .and_then(parse_duration_secs)
+                .unwrap_or(86_400);
+
+            let mut provider = Mapping::new();
+            provider.insert("type".into(), yaml_str("http"));
+            provider.insert("behavior".into(), yaml_str(behavior));
+            provider.insert("format".into(), yaml_str("mrs"));
+            provider.insert("url".into(), yaml_str(&url));
+            provider.insert("path".into(), yaml_str(&format!("./ruleset/{}.mrs", tag)));
+            provider.insert("interval".into(), interval.into());
+            providers.insert(yaml_str(tag), YamlValue::Mapping(provider));
+            self.providers.insert(tag.to_string(), behavior);
+        }
+        providers
+    }
+
+    /// 单个字段 → (分组键, Mihomo 条件列表)；分组键相同的字段按 sing-box 语义取“或”
+    fn field_conditions(
+        &self,
+        key: &str,
+        value: &Value,
+    ) -> Result<(&'static str, Vec<String>), String> {
+        let values = field_values(value);
+        let prefixed = |prefix: &str| -> Vec<String> {
+            values.iter().map(|v| format!("{},{}", prefix, v)).collect()
+        };
+        let conditions = match key {
+            "domain" => ("destination", prefixed("DOMAIN")),
+            "domain_suffix" => ("destination", prefixed("DOMAIN-SUFFIX")),
+            "domain_keyword" => ("destination", prefixed("DOMAIN-KEYWORD")),
+            "domain_regex" => ("destination", prefixed("DOMAIN-REGEX")),
+            "ip_cidr" => (
+                "destination",
+                values
+                    .iter()
+                    .map(|v| {
+                        if v.contains(':') {
+                            format!("IP-CIDR6,{}", v)
+                        } else {
+                            format!("IP-CIDR,{}", v)
+                        }
+                    })
+                    .collect(),
+            ),
+            "rule_set" => {
+                if let Some(missing) = values.iter().find(|v| !self.providers.contains_key(*v)) {
+                    return Err(format!("引用的规则集「{}」未导出", missing));
+                }
+                ("destination", prefixed("RULE-SET"))
+            }
+            "port" => ("port", prefixed("DST-PORT")),
+            "port_range" => (
+                "port",
+                values
+                    .iter()
+                    .map(|v| format!("DST-PORT,{}", v.replace(':', "-")))
+                    .collect(),
+            ),
+            "source_ip_cidr" => ("source", prefixed("SRC-IP-CIDR")),
+            "source_port" => ("source_port", prefixed("SRC-PORT")),
+            "source_port_range" => (
+                "source_port",
+                values
+                    .iter()
+                    .map(|v| format!("SRC-PORT,{}", v.replace(':', "-")))
+                    .collect(),
+            ),
+            "network" => (
+                "network",
+                values
+                    .iter()
+                    .map(|v| format!("NETWORK,{}", v.to_ascii_uppercase()))
+                    .collect(),
+            ),
+            "process_name" => ("process_name", prefixed("PROCESS-NAME")),
+            "process_path" => ("process_path", prefixed("PROCESS-PATH")),
+            other => return Err(format!("不支持的匹配条件 {}", other)),
+        };
+        if conditions.1.is_empty() {
+            return Err(format!("匹配条件 {} 为空", key));
+        }
+        Ok(conditions)
+    }
+
+    fn rule_condition(&self, rule: &Value) -> Result<Condition, String> {
+        let invert = rule
+            .get("invert")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+
+        if str_field(rule, "type") == Some("logical") {
+            let op = match str_field(rule, "mode") {
+                Some("and") => "AND",
+                Some("or") => "OR",
+                other => return Err(format!("不支持的逻辑模式 {:?}", other)),
+            };
+            let sub_rules = rule
+                .get("rules")
+                .and_then(|v| v.as_array())
+                .filter(|v| !v.is_empty())
+                .ok_or_else(|| "逻辑规则缺少子规则".to_string())?;
+            let items = sub_rules
+                .iter()
+                .map(|sub| self.rule_condition(sub).map(|c| c.to_expr()))
+                .collect::<Result<Vec<_>, _>>()?;
+            return Ok(Condition {
+                groups: vec![vec![logic_expr(op, &items)]],
+                invert,
+            });
+        }
+
+        let object = rule.as_object().ok_or_else(|| "规则格式无效".to_string())?;
+        let mut keyed: Vec<(&'static str, Vec<String>)> = Vec::new();
+        for (key, value) in object {
+            if ROUTE_RULE_NON_MATCH_KEYS.contains(&key.as_str()) {
+                continue;
+            }
+            let (group, conditions) = self.field_conditions(key, value)?;
+            match keyed.iter_mut().find(|(existing, _)| *existing == group) {
+                Some((_, items)) => items.extend(conditions),
+                None => keyed.push((group, conditions)),
+            }
+        }
+        if keyed.is_empty() {
+            return Err("规则没有匹配条件".to_string());
+        }
+        Ok(Condition {
+            groups: keyed.into_iter().map(|(_, items)| items).collect(),
+            invert,
+        })
+    }
+
+    /// IP 类条件需要 no-resolve：sing-box 的 ip_cidr 不会为域名请求主动发起解析
+    fn needs_no_resolve(&self, condition: &str) -> bool {
+        condition.starts_with("IP-CIDR")
+            || condition
+                .strip_prefix("RULE-SET,")
+                .is_some_and(|tag| self.providers.get(tag) == Some(&"ipcidr"))
+    }
+
+    fn convert_rules(&mut self, config: &Value) -> (Vec<String>, bool, bool) {
+        let mut rules: Vec<String> = Vec::new();
+        let mut sniff = false;
+        let mut hijack_dns = false;
+        let route_rules = config
+            .pointer("/route/rules")
+            .and_then(|v| v.as_array())
+            .cloned()
+            .unwrap_or_default();
+
+        for rule in &route_rules {
+            // Mihomo 内置 rule / global / direct 模式，clash_mode 规则无需转换
+            if rule.get("clash_mode").is_some() {
+                continue;
+            }
+            let target = match str_field(rule, "action").unwrap_or("route") {
+                "sniff" => {
+                    sniff = true;
+                    continue;
+                }
+                "hijack-dns" => {
+                    hijack_dns = true;
+                    continue;
+                }
+                "resolve" => continue,
+                "reject" if str_field(rule, "method") == Some("drop") => "REJECT-DROP".to_string(),
+                "reject" => "REJECT".to_string(),
+                "route" => {
+                    let outbound = str_field(rule, "outbound").unwrap_or_default();
+                    match self.names.get(outbound) {
+                        Some(name) => name.clone(),
+                        None => {
+                            self.warn(format!(
+                                "路由规则 {} 指向的出站「{}」无法导出，已跳过",
+                                rule, outbound
+                            ));
+                            continue;
+                        }
+                    }
+                }
+                other => {
+                    self.warn(format!(
+                        "路由规则 {} 的动作 {} 无法转换，已跳过",
+                        rule, other
+                    ));
+                    continue;
+                }
+            };
+
+            let condition = match self.rule_condition(rule) {
+                Ok(condition) => condition,
+                Err(e) => {
+                    self.warn(format!("路由规则 {} 无法转换（{}），已跳过", rule, e));
+                    continue;
+                }
+            };
+            let lines: Vec<String> = if !condition.invert && condition.groups.len() == 1 {
+                condition.groups[0]
+                    .iter()
+                    .map(|item| {
+                        if self.needs_no_resolve(item) {
+                            format!("{},{},no-resolve", item, target)
+                        } else {
+                            format!("{},{}", item, target)
+                        }
+                    })
+                    .collect()
+            } else {
+                vec![format!("{},{}", condition.to_expr(), target)]
+            };
+            for line in lines {
+                if !rules.contains(&line) {
+                    rules.push(line);
+                }
+            }
+        }
+
+        let final_tag = config
+            .pointer("/route/final")
+            .and_then(|v| v.as_str())
+            .map(str::to_string)
+            .or_else(|| {
+                // 未设置 final 时 sing-box 使用第一个出站
+                config
+                    .pointer("/outbounds/0/tag")
+                    .and_then(|v| v.as_str())
+                    .map(str::to_string)
+            })
+            .unwrap_or_default();
+        let final_name = match self.names.get(&final_tag) {
+            Some(name) => name.clone(),
+            None => {
+                self.warn(format!(
+                    "默认出站「{}」无法导出，兜底规则改为 DIRECT",
+                    final_tag
+                ));
+                "DIRECT".to_string()
+            }
+        };
+        rules.push(format!("MATCH,{}", final_name));
+        (rules, sniff, hijack_dns)
+    }
+
+    fn dns_server_address(&mut self, server: &Value) -> Option<String> {
+        let tag = str_field(server, "tag").unwrap_or_default();
+        // 旧版 `address` 写法与 Mihomo 的地址格式基本一致
+        if let Some(address) = str_field(server, "address") {
+            return Some(address.to_string());
+        }
+        let kind = str_field(server, "type").unwrap_or("udp");
+        let host_port = || -> Option<String> {
+            let host = str_field(server, "server")?;
+            let host = if host.contains(':') && !host.starts_with('[') {
+                format!("[{}]", host)
+            } else {
+                host.to_string()
+            };
+            Some(match server.get("server_port").and_then(|v| v.as_u64()) {
+                Some(port) if port != default_dns_port(kind) => format!("{}:{}", host, port),
+                _ => host,
+            })
+        };
+        let mut address = match kind {
+            "udp" => host_port(),
+            "tcp" | "tls" | "quic" => host_port().map(|hp| format!("{}://{}", kind, hp)),
+            "https" | "h3" => host_port().map(|hp| {
+                let path = str_field(server, "path").unwrap_or("/dns-query");
+                format!("https://{}{}", hp, path)
+            }),
+            "local" => return Some("system".to_string()),
+            "dhcp" => {
+                return Some(format!(
+                    "dhcp://{}",
+                    str_field(server, "interface").unwrap_or("system")
+                ))
+            }
+            other => {
+                self.warn(format!("DNS 服务器「{}」的类型 {} 无法转换", tag, other));
+                return None;
+            }
+        }?;
+
+        // `#代理名` 让该 DNS 请求经指定代理发出，h3 通过参数开启
+        let mut params: Vec<String> = Vec::new();
+        if let Some(detour) = str_field(server, "detour") {
+            match self.names.get(detour) {
+                Some(name) if name != "DIRECT" => params.push(name.clone()),
+                Some(_) => {}
+                None => self.warn(format!("DNS 服务器「{}」的出站「{}」无法导出", tag, detour)),
+            }
+        }
+        if kind == "h3" {
+            params.push("h3=true".to_string());
+        }
+        if !params.is_empty() {
+            address = format!("{}#{}", address, params.join("&"));
+        }
+        Some(address)
+    }
+
+    /// DNS 规则 → nameserver-policy / fake-ip-filter 的键
+    fn dns_rule_keys(&self, rule: &Value) -> Result<Vec<String>, String> {
+        let mut keys = Vec::new();
+        let object = rule.as_object().ok_or_else(|| "规则格式无效".to_string())?;
+        for (key, value) in object {
+            match key.as_str() {
+                "server" | "action" | "query_type" => {}
+                "domain" => keys.extend(field_values(value)),
+                "domain_suffix" => keys.extend(
+                    field_values(value)
+                        .iter()
+                        .map(|v| format!("+.{}", v.trim_start_matches('.'))),
+                ),
+                "rule_set" => {
+                    // 只有域名类规则集能在查询阶段匹配，IP 规则集在 Mihomo 中没有对应
+                    let tags: Vec<String> = field_values(value)
+                        .into_iter()
+                        .filter(|tag| self.providers.get(tag) == Some(&"domain"))
+                        .collect();
+                    if !tags.is_empty() {
+                        keys.push(format!("rule-set:{}", tags.join(",")));
+                    }
+                }
+                other => return Err(format!("不支持的匹配条件 {}", other)),
+            }
+        }
+        Ok(keys)
+    }
+
+    fn convert_dns(&mut self, config: &Value) -> Mapping {
+        let mut dns = Mapping::new();
+        dns.insert("enable".into(), true.into());
+        let strategy = config
+            .pointer("/route/default_domain_resolver/strategy")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default();
+        dns.insert("ipv6".into(), (strategy != "ipv4_only").into());
+
+        let servers = config
+            .pointer("/dns/servers")
+            .and_then(|v| v.as_array())
+            .cloned()
+            .unwrap_or_default();
+        let mut addresses: HashMap<String, String> = HashMap::new();
+        let mut fakeip_server: Option<Value> = None;
+        for server in &servers {
+            let Some(tag) = str_field(server, "tag") else {
+                continue;
+            };
+            if str_field(server, "type") == Some("fakeip") {
+                fakeip_server = Some(server.clone());
+                continue;
+            }
+            if let Some(address) = self.dns_server_address(server) {
+                addresses.insert(tag.to_string(), address);
+            }
+        }
+
+        // 节点域名与 DoH 域名的解析服务器：sing-box 的 domain_resolver
+        let mut resolver_tags: Vec<String> = Vec::new();
+        for resolver in servers
+            .iter()
+            .filter_map(|s| s.pointer("/domain_resolver/server"))
+            .chain(config.pointer("/route/default_domain_resolver/server"))
+            .filter_map(|v| v.as_str())
+        {
+            if !resolver_tags.iter().any(|t| t == resolver) {
+                resolver_tags.push(resolver.to_string());
+            }
+        }
+        let resolvers: Vec<String> = resolver_tags
+            .iter()
+            .filter_map(|tag| addresses.get(tag).cloned())
+            .collect();
+        let default_nameservers: Vec<String> = resolver_tags
+            .iter()
+            .filter(|tag| {
+                servers
+                    .iter()
+                    .find(|s| str_field(s, "tag") == Some(tag.as_str()))
+                    .and_then(|s| str_field(s, "server"))
+                    .is_some_and(is_ip_host)
+            })
+            .filter_map(|tag| addresses.get(tag).cloned())
+            .collect();
+        if !default_nameservers.is_empty() {
+            dns.insert(
+                "default-nameserver".into(),
+                yaml_strings(&default_nameservers),
+            );
+        } else if !resolvers.is_empty() {
+            self.warn(
+                "DNS 解析服务器使用域名地址，Mihomo 的 default-nameserver 需要 IP，已省略"
+                    .to_string(),
+            );
+        }
+
+        let final_tag = config
+            .pointer("/dns/final")
+            .and_then(|v| v.as_str())
+            .map(str::to_string)
+            .or_else(|| {
+                servers
+                    .iter()
+                    .find_map(|s| str_field(s, "tag").filter(|t| addresses.contains_key(*t)))
+                    .map(str::to_string)
+            })
+            .unwrap_or_default();
+        match addresses.get(&final_tag) {
+            Some(address) => {
+                dns.insert("nameserver".into(), yaml_strings(&[address.clone()]));
+            }
+            None => self.warn(format!("默认 DNS 服务器「{}」无法导出", final_tag)),
+        }
+        if !resolvers.is_empty() {
+            dns.insert("proxy-server-nameserver".into(), yaml_strings(&resolvers));
+        }
+
+        let fakeip_tag = fakeip_server
+            .as_ref()
+            .and_then(|s| str_field(s, "tag"))
+            .map(str::to_string);
+        let mut policy = Mapping::new();
+        // fakeip 规则之前分到真实 DNS 的域名，在全局 fakeip 模式下需排除
+        let mut real_dns_keys: Vec<String> = Vec::new();
+        let mut fakeip_filter: Option<(&'static str, Vec<String>)> = None;
+        let rules = config
+            .pointer("/dns/rules")
+            .and_then(|v| v.as_array())
+            .cloned()
+            .unwrap_or_default();
+        for rule in &rules {
+            if rule.get("clash_mode").is_some() {
+                continue;
+            }
+            if str_field(rule, "action").is_some_and(|action| action != "route") {
+                self.warn(format!("DNS 规则 {} 的动作无法转换，已跳过", rule));
+                continue;
+            }
+            let server = str_field(rule, "server").unwrap_or_default().to_string();
+            let keys = match self.dns_rule_keys(rule) {
+                Ok(keys) => keys,
+                Err(e) => {
+                    self.warn(format!("DNS 规则 {} 无法转换（{}），已跳过", rule, e));
+                    continue;
+                }
+            };
+
+            if fakeip_tag.as_deref() == Some(server.as_str()) {
+                if fakeip_filter.is_none() {
+                    fakeip_filter = Some(if keys.is_empty() {
+                        ("blacklist", real_dns_keys.clone())
+                    } else {
+                        ("whitelist", keys)
+                    });
+                }
+                continue;
+            }
+            if keys.is_empty() {
+                continue;
+            }
+            real_dns_keys.extend(keys.iter().cloned());
+            if server == final_tag {
+                continue;
+            }
+            match addresses.get(&server) {
+                Some(address) => {
+                    for key in keys {
+                        let key = yaml_str(&key);
+                        if !policy.contains_key(&key) {
+                            policy.insert(key, yaml_str(address));
+                        }
+                    }
+                }
+                None => self.warn(format!("DNS 规则 {} 指向的服务器无法导出，已跳过", rule)),
+            }
+        }
+
+        if let (Some(server), Some((mode, filter))) = (fakeip_server, fakeip_filter) {
+            dns.insert("enhanced-mode".into(), yaml_str("fake-ip"));
+            if let Some(range) = str_field(&server, "inet4_range") {
+                dns.insert("fake-ip-range".into(), yaml_str(range));
+            }
+            dns.insert("fake-ip-filter-mode".into(), yaml_str(mode));
+            dns.insert("fake-ip-filter".into(), yaml_strings(&filter));
+        }
+        if !policy.is_empty() {
+            dns.insert("nameserver-policy".into(), YamlValue::Mapping(policy));
+        }
+        dns
+    }
+
+    fn convert_inbounds(&mut self, config: &Value, root: &mut Mapping, hijack_dns: bool) {
+        let inbounds = config
+            .get("inbounds")
+            .and_then(|v| v.as_array())
+            .cloned()
+            .unwrap_or_default();
+        for inbound in &inbounds {
+            let tag = str_field(inbound, "tag").unwrap_or_default();
+            match str_field(inbound, "type") {
+                Some("mixed") if !root.contains_key("mixed-port") => {
+                    let port = inbound
+                        .get("listen_port")
+                        .and_then(|v| v.as_u64())
+                        .unwrap_or(7890);
+                    root.insert("mixed-port".into(), port.into());
+                    let listen = str_field(inbound, "listen").unwrap_or("127.0.0.1");
+                    root.insert(
+                        "allow-lan".into(),
+                        matches!(listen, "0.0.0.0" | "::").into(),
+                    );
+                    let users: Vec<String> = inbound
+                        .get("users")
+                        .and_then(|v| v.as_array())
+                        .map(|users| {
+                            users
+                                .iter()
+                                .filter_map(|u| {
+                                    Some(format!(
+                                        "{}:{}",
+                                        str_field(u, "username")?,
+                                        str_field(u, "password")?
+                                    ))
+                                })
+                                .collect()
+                        })
+                        .unwrap_or_default();
+                    if !users.is_empty() {
+                        root.insert("authentication".into(), yaml_strings(&users));
+                    }
+                }
+                Some("tun") => {
+                    let mut tun = Mapping::new();
+                    tun.insert("enable".into(), true.into());
+                    tun.insert(
+                        "stack".into(),
+                        yaml_str(str_field(inbound, "stack").unwrap_or("system")),
+                    );
+                    for (key, field) in [
+                        ("auto-route", "auto_route"),
+                        ("strict-route", "strict_route"),
+                    ] {
+                        let enabled = inbound
+                            .get(field)
+                            .and_then(|v| v.as_bool())
+                            .unwrap_or(false);
+                        tun.insert(key.into(), enabled.into());
+                    }
+                    if let Some(mtu) = inbound.get("mtu").and_then(|v| v.as_u64()) {
+                        tun.insert("mtu".into(), mtu.into());
+                    }
+                    let auto_detect = config
+                        .pointer("/route/auto_detect_interface")
+                        .and_then(|v| v.as_bool())
+                        .unwrap_or(false);
+                    tun.insert("auto-detect-interface".into(), auto_detect.into());
+                    if hijack_dns {
+                        tun.insert("dns-hijack".into(), yaml_strings(&["any:53".to_string()]));
+                    }
+                    root.insert("tun".into(), YamlValue::Mapping(tun));
+                }
+                Some(kind) => self.warn(format!("入站「{}」({}) 未转换", tag, kind)),
+                None => {}
+            }
+        }
+    }
+}
+
+/// 把活动配置转换为 Mihomo YAML。
+///
+/// `custom_rules` 中尚未出现在 `route.rules` 里的启用规则会按生成时的位置补入，
+/// 这样使用原始订阅配置（未注入自定义规则）时导出结果同样包含它们。
+pub fn convert_to_clash_meta(
+    config: &Value,
+    custom_rules: &[CustomRule],
+    default_outbound: &str,
+) -> Result<ClashMetaExport, String> {
+    if !config.is_object() {
+        return Err("配置格式无效".to_string());
+    }
+    let mut config = config.clone();
+    let existing_rules = config
+        .pointer("/route/rules")
+        .and_then(|v| v.as_array())
+        .cloned()
+        .unwrap_or_default();
+    let missing: Vec<CustomRule> = custom_rules
+        .iter()
+        .filter(|rule| {
+            rule.to_route_rule(default_outbound)
+                .is_some_and(|value| !existing_rules.contains(&value))
+        })
+        .cloned()
+        .collect();
+    inject_custom_rules(&mut config, &missing, default_outbound);
+
+    let mut converter = Converter {
+        warnings: Vec::new(),
+        names: HashMap::new(),
+        providers: HashMap::new(),
+    };
+    let (proxies, groups) = converter.convert_outbounds(&config);
+    let providers = converter.convert_rule_providers(&config);
+    let (rules, sniff, hijack_dns) = converter.convert_rules(&config);
+    let dns = converter.convert_dns(&config);
+
+    let mut root = Mapping::new();
+    converter.convert_inbounds(&config, &mut root, hijack_dns);
+    root.insert("mode".into(), yaml_str("rule"));
+    let log_level = match config.pointer("/log/level").and_then(|v| v.as_str()) {
+        Some("trace" | "debug") => "debug",
+        Some("warn") => "warning",
+        Some("error" | "fatal" | "panic") => "error",
+        _ => "info",
+    };
+    root.insert("log-level".into(), yaml_str(log_level));
+    if let Some(controller) = config
+        .pointer("/experimental/clash_api/external_controller")
+        .and_then(|v| v.as_str())
+    {
+        root.insert("external-controller".into(), yaml_str(controller));
+        if let Some(secret) = config
+            .pointer("/experimental/clash_api/secret")
+            .and_then(|v| v.as_str())
+        {
+            root.insert("secret".into(), yaml_str(secret));
+        }
+    }
+    if sniff {
+        // sing-box 的 sniff 只用于路由匹配，不改写目标地址
+        let sniffer: YamlValue = serde_yaml::from_str(
+            "enable: true\noverride-destination: false\nsniff:\n  HTTP:\n    ports: [80, 8080-8880]\n  TLS:\n    ports: [443, 8443]\n  QUIC:\n    ports: [443, 8443]\n",
+        )
+        .map_err(|e| format!("生成 sniffer 配置失败: {}", e))?;
+        root.insert("sniffer".into(), sniffer);
+    }
+    root.insert("dns".into(), YamlValue::Mapping(dns));
+    root.insert("proxies".into(), YamlValue::Sequence(proxies));
+    root.insert("proxy-groups".into(), YamlValue::Sequence(groups));
+    if !providers.is_empty() {
+        root.insert("rule-providers".into(), YamlValue::Mapping(providers));
+    }
+    root.insert("rules".into(), yaml_strings(&rules));
+
+    let yaml = serde_yaml::to_string(&root).map_err(|e| format!("生成 Clash YAML 失败: {}", e))?;
+    Ok(ClashMetaExport {
+        yaml,
+        warnings: converter.warnings,
+    })
+}
+
+#[cfg(test)]
+#[path = "clash_meta.tests.rs"]
+mod tests;
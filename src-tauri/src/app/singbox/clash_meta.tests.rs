@@ -0,0 +1,223 @@
+use super::*;
+use crate::app::singbox::common::TAG_MANUAL;
+use crate::app::singbox::config_generator::generate_config_with_nodes;
+use crate::app::storage::custom_rule::{CustomRuleAction, CustomRuleMatchType};
+use crate::app::storage::state_model::AppConfig;
+use chrono::Utc;
+use serde_json::json;
+
+fn vless_node() -> Value {
+    json!({
+        "type": "vless",
+        "tag": "香港 01",
+        "server": "hk.example.com",
+        "server_port": 443,
+        "uuid": "b831381d-6324-4d53-ad4f-8cda48b30811",
+        "tls": { "enabled": true, "server_name": "hk.example.com" }
+    })
+}
+
+fn custom_rule(match_type: CustomRuleMatchType, payload: &str) -> CustomRule {
+    CustomRule {
+        id: "test".to_string(),
+        enabled: true,
+        match_type,
+        payload: payload.to_string(),
+        action: CustomRuleAction::Direct,
+        outbound: None,
+        note: None,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    }
+}
+
+fn export(config: &Value, custom_rules: &[CustomRule]) -> (YamlValue, Vec<String>) {
+    let result = convert_to_clash_meta(config, custom_rules, TAG_MANUAL).expect("转换应成功");
+    let document = serde_yaml::from_str(&result.yaml).expect("输出应为合法 YAML");
+    (document, result.warnings)
+}
+
+fn rules_of(document: &YamlValue) -> Vec<String> {
+    document["rules"]
+        .as_sequence()
+        .expect("rules 应存在")
+        .iter()
+        .map(|v| v.as_str().unwrap().to_string())
+        .collect()
+}
+
+#[test]
+fn generated_config_should_map_proxies_groups_and_rules() {
+    let config = generate_config_with_nodes(&AppConfig::default(), &[vless_node()]).unwrap();
+    let (document, warnings) = export(&config, &[]);
+    // 仅广告域名的 DNS 拒绝规则没有对应写法，连接仍由路由规则拦截
+    assert_eq!(warnings.len(), 1, "{:?}", warnings);
+    assert!(warnings[0].contains("geosite-category-ads-all"));
+
+    assert_eq!(document["proxies"][0]["name"].as_str(), Some("香港 01"));
+    let groups = document["proxy-groups"].as_sequence().unwrap();
+    let auto = groups
+        .iter()
+        .find(|g| g["name"].as_str() == Some("自动选择"))
+        .expect("自动选择分组应存在");
+    assert_eq!(auto["type"].as_str(), Some("url-test"));
+    assert_eq!(auto["interval"].as_u64(), Some(180));
+
+    let provider = &document["rule-providers"]["geosite-cn"];
+    assert_eq!(provider["format"].as_str(), Some("mrs"));
+    assert_eq!(provider["behavior"].as_str(), Some("domain"));
+
+    let rules = rules_of(&document);
+    assert!(rules.contains(&"RULE-SET,geosite-category-ads-all,REJECT".to_string()));
+    assert!(rules.contains(&"RULE-SET,geosite-cn,DIRECT".to_string()));
+    assert!(rules.contains(&"RULE-SET,geoip-cn,DIRECT,no-resolve".to_string()));
+    assert!(rules.contains(&"IP-CIDR,192.168.0.0/16,DIRECT,no-resolve".to_string()));
+    assert_eq!(rules.last().map(String::as_str), Some("MATCH,手动切换"));
+    assert!(document["sniffer"]["enable"].as_bool().unwrap());
+}
+
+#[test]
+fn generated_dns_should_map_servers_and_policy() {
+    let config = generate_config_with_nodes(&AppConfig::default(), &[vless_node()]).unwrap();
+    let (document, _) = export(&config, &[]);
+    let dns = &document["dns"];
+
+    assert_eq!(
+        dns["nameserver"][0].as_str(),
+        Some("https://1.1.1.1/dns-query#手动切换")
+    );
+    assert_eq!(
+        dns["default-nameserver"][0].as_str(),
+        Some("114.114.114.114")
+    );
+    assert_eq!(
+        dns["nameserver-policy"]["rule-set:geosite-cn"].as_str(),
+        Some("https://dns.alidns.com/dns-query#h3=true")
+    );
+    assert!(dns.get("enhanced-mode").is_none());
+}
+
+#[test]
+fn fake_dns_proxy_only_should_use_whitelist_filter() {
+    let app_config = AppConfig {
+        singbox_fake_dns_enabled: true,
+        ..AppConfig::default()
+    };
+    let config = generate_config_with_nodes(&app_config, &[vless_node()]).unwrap();
+    let (document, _) = export(&config, &[]);
+    let dns = &document["dns"];
+
+    assert_eq!(dns["enhanced-mode"].as_str(), Some("fake-ip"));
+    assert_eq!(dns["fake-ip-range"].as_str(), Some("198.18.0.0/15"));
+    assert_eq!(dns["fake-ip-filter-mode"].as_str(), Some("whitelist"));
+    assert_eq!(
+        dns["fake-ip-filter"][0].as_str(),
+        Some("rule-set:geosite-geolocation-!cn")
+    );
+}
+
+#[test]
+fn custom_rules_should_be_added_once() {
+    let mut config = generate_config_with_nodes(&AppConfig::default(), &[vless_node()]).unwrap();
+    let injected = custom_rule(CustomRuleMatchType::DomainSuffix, "example.com");
+    let missing = custom_rule(CustomRuleMatchType::DomainKeyword, "tracker");
+    inject_custom_rules(&mut config, std::slice::from_ref(&injected), TAG_MANUAL);
+
+    let (document, _) = export(&config, &[injected, missing]);
+    let rules = rules_of(&document);
+    let suffix_count = rules
+        .iter()
+        .filter(|r| *r == "DOMAIN-SUFFIX,example.com,DIRECT")
+        .count();
+    assert_eq!(suffix_count, 1);
+
+    let keyword = rules
+        .iter()
+        .position(|r| r == "DOMAIN-KEYWORD,tracker,DIRECT")
+        .expect("缺失的自定义规则应补入");
+    let cn = rules
+        .iter()
+        .position(|r| r == "RULE-SET,geosite-cn,DIRECT")
+        .unwrap();
+    assert!(keyword < cn, "自定义规则应排在内置分流之前: {:?}", rules);
+}
+
+#[test]
+fn multi_field_and_logical_rules_should_use_logic_syntax() {
+    let config = json!({
+        "outbounds": [
+            { "type": "direct", "tag": "direct" },
+            { "type": "block", "tag": "block" }
+        ],
+        "route": {
+            "rules": [
+                { "domain_suffix": ["a.com", "b.com"], "network": "udp", "outbound": "block" },
+                {
+                    "type": "logical",
+                    "mode": "or",
+                    "rules": [{ "port": 22 }, { "process_name": "ssh", "invert": true }],
+                    "outbound": "direct"
+                }
+            ],
+            "final": "direct"
+        }
+    });
+    let (document, _) = export(&config, &[]);
+    let rules = rules_of(&document);
+
+    assert_eq!(
+        rules[0],
+        "AND,((OR,((DOMAIN-SUFFIX,a.com),(DOMAIN-SUFFIX,b.com))),(NETWORK,UDP)),REJECT"
+    );
+    assert_eq!(
+        rules[1],
+        "OR,((DST-PORT,22),(NOT,((PROCESS-NAME,ssh)))),DIRECT"
+    );
+    assert_eq!(rules[2], "MATCH,DIRECT");
+}
+
+#[test]
+fn untranslatable_entries_should_be_reported() {
+    let config = json!({
+        "outbounds": [
+            { "type": "selector", "tag": "节点选择", "outbounds": ["wg", "direct"] },
+            { "type": "wireguard", "tag": "wg" },
+            { "type": "direct", "tag": "direct" }
+        ],
+        "route": {
+            "rule_set": [
+                { "tag": "my-list", "type": "remote", "format": "source", "url": "https://example.com/list.json" }
+            ],
+            "rules": [
+                { "rule_set": "my-list", "outbound": "节点选择" },
+                { "inbound": "mixed-in", "outbound": "direct" }
+            ],
+            "final": "节点选择"
+        }
+    });
+    let (document, warnings) = export(&config, &[]);
+
+    assert!(document["proxies"].as_sequence().unwrap().is_empty());
+    assert_eq!(
+        document["proxy-groups"][0]["proxies"][0].as_str(),
+        Some("DIRECT")
+    );
+    assert_eq!(rules_of(&document), vec!["MATCH,节点选择".to_string()]);
+    for expected in ["节点「wg」", "「wg」无法导出", "my-list", "inbound"] {
+        assert!(
+            warnings.iter().any(|w| w.contains(expected)),
+            "警告中应包含 {}: {:?}",
+            expected,
+            warnings
+        );
+    }
+}
+
+#[test]
+fn parse_duration_secs_should_handle_compound_units() {
+    assert_eq!(parse_duration_secs("3m"), Some(180));
+    assert_eq!(parse_duration_secs("1d"), Some(86_400));
+    assert_eq!(parse_duration_secs("1h30m"), Some(5_400));
+    assert_eq!(parse_duration_secs("abc"), None);
+    assert_eq!(parse_duration_secs(""), None);
+}
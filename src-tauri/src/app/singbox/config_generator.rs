@@ -10,9 +10,13 @@ use super::config_schema::{
     CacheFileConfig, ClashApiConfig, DnsConfig, DnsServerConfig, ExperimentalConfig, LogConfig,
     RemoteRuleSetConfig, RouteConfig, SingBoxConfig,
 };
+use crate::app::core::kernel_service::capabilities::current_capabilities;
+use crate::app::core::kernel_service::embedded::METACUBEXD_URL;
+use crate::app::network::mirror_registry::config_mirror_url;
 use crate::app::singbox::settings_patch::apply_app_settings_to_config;
 use crate::app::storage::state_model::AppConfig;
 use serde_json::{json, Value};
+use tracing::warn;
 // 兼容旧引用：这些 tag 之前是 `config_generator` 的 `pub const`，保留同名导出以降低未来重构的破坏性。
 pub use super::common::{
     TAG_AUTO, TAG_BLOCK, TAG_DIRECT, TAG_GOOGLE, TAG_MANUAL, TAG_NETFLIX, TAG_OPENAI, TAG_TELEGRAM,
@@ -105,7 +109,7 @@ pub fn generate_base_config(app_config: &AppConfig) -> Value {
     if app_config.singbox_block_ads {
         rule_sets.push(remote_rule_set_value(
             RS_GEOSITE_ADS,
-            "https://gh-proxy.com/https://raw.githubusercontent.com/SagerNet/sing-geosite/rule-set/geosite-category-ads-all.srs",
+            &geosite_rule_set_url("geosite-category-ads-all"),
             download_detour,
             "1d",
         ));
@@ -114,13 +118,13 @@ pub fn generate_base_config(app_config: &AppConfig) -> Value {
     rule_sets.extend([
         remote_rule_set_value(
             RS_GEOSITE_CN,
-            "https://gh-proxy.com/https://raw.githubusercontent.com/SagerNet/sing-geosite/rule-set/geosite-cn.srs",
+            &geosite_rule_set_url("geosite-cn"),
             download_detour,
             "1d",
         ),
         remote_rule_set_value(
             RS_GEOSITE_GEOLOCATION_NOT_CN,
-            "https://gh-proxy.com/https://raw.githubusercontent.com/SagerNet/sing-geosite/rule-set/geosite-geolocation-!cn.srs",
+            &geosite_rule_set_url("geosite-geolocation-!cn"),
             download_detour,
             "1d",
         ),
@@ -130,31 +134,31 @@ pub fn generate_base_config(app_config: &AppConfig) -> Value {
         rule_sets.extend([
             remote_rule_set_value(
                 RS_GEOSITE_TELEGRAM,
-                "https://gh-proxy.com/https://raw.githubusercontent.com/SagerNet/sing-geosite/rule-set/geosite-telegram.srs",
+                &geosite_rule_set_url("geosite-telegram"),
                 download_detour,
                 "7d",
             ),
             remote_rule_set_value(
                 RS_GEOSITE_YOUTUBE,
-                "https://gh-proxy.com/https://raw.githubusercontent.com/SagerNet/sing-geosite/rule-set/geosite-youtube.srs",
+                &geosite_rule_set_url("geosite-youtube"),
                 download_detour,
                 "7d",
             ),
             remote_rule_set_value(
                 RS_GEOSITE_NETFLIX,
-                "https://gh-proxy.com/https://raw.githubusercontent.com/SagerNet/sing-geosite/rule-set/geosite-netflix.srs",
+                &geosite_rule_set_url("geosite-netflix"),
                 download_detour,
                 "7d",
             ),
             remote_rule_set_value(
                 RS_GEOSITE_OPENAI,
-                "https://gh-proxy.com/https://raw.githubusercontent.com/SagerNet/sing-geosite/rule-set/geosite-openai.srs",
+                &geosite_rule_set_url("geosite-openai"),
                 download_detour,
                 "7d",
             ),
             remote_rule_set_value(
                 RS_GEOSITE_GOOGLE,
-                "https://gh-proxy.com/https://raw.githubusercontent.com/SagerNet/sing-geosite/rule-set/geosite-google.srs",
+                &geosite_rule_set_url("geosite-google"),
                 download_detour,
                 "7d",
             ),
@@ -164,13 +168,13 @@ pub fn generate_base_config(app_config: &AppConfig) -> Value {
     rule_sets.extend([
         remote_rule_set_value(
             RS_GEOSITE_PRIVATE,
-            "https://gh-proxy.com/https://raw.githubusercontent.com/SagerNet/sing-geosite/rule-set/geosite-private.srs",
+            &geosite_rule_set_url("geosite-private"),
             TAG_DIRECT,
             "7d",
         ),
         remote_rule_set_value(
             RS_GEOIP_CN,
-            "https://gh-proxy.com/https://raw.githubusercontent.com/SagerNet/sing-geoip/rule-set/geoip-cn.srs",
+            &geoip_rule_set_url("geoip-cn"),
             download_detour,
             "1d",
         ),
@@ -245,9 +249,7 @@ pub fn generate_base_config(app_config: &AppConfig) -> Value {
                 external_controller: format!("127.0.0.1:{}", app_config.api_port),
                 external_ui: "metacubexd".to_string(),
                 // 让 sing-box 自动下载 UI（国内网络可能被墙，下载走代理可提高成功率）
-                external_ui_download_url:
-                    "https://github.com/MetaCubeX/metacubexd/archive/refs/heads/gh-pages.zip"
-                        .to_string(),
+                external_ui_download_url: config_mirror_url(METACUBEXD_URL),
                 external_ui_download_detour: download_detour.to_string(),
                 default_mode: "rule".to_string(),
             },
@@ -399,6 +401,21 @@ fn apply_fake_dns_rules(dns_rules: &mut Vec<Value>, app_config: &AppConfig) {
     }
 }
 
+/// 规则集地址只按用户选择的配置镜像改写，保证同样的设置生成同样的配置
+fn geosite_rule_set_url(name: &str) -> String {
+    config_mirror_url(&format!(
+        "https://raw.githubusercontent.com/SagerNet/sing-geosite/rule-set/{}.srs",
+        name
+    ))
+}
+
+fn geoip_rule_set_url(name: &str) -> String {
+    config_mirror_url(&format!(
+        "https://raw.githubusercontent.com/SagerNet/sing-geoip/rule-set/{}.srs",
+        name
+    ))
+}
+
 fn remote_rule_set_value(
     tag: &str,
     url: &str,
@@ -421,8 +438,25 @@ pub fn generate_config_with_nodes(
     app_config: &AppConfig,
     nodes: &[Value],
 ) -> Result<Value, String> {
+    // 旧内核无法加载 domain_resolver / sniff / hijack-dns 等写法，直接把原因返回给用户，
+    // 避免保存一份必然启动失败的配置。
+    let capabilities = current_capabilities();
+    let blocking = capabilities.blocking_issues();
+    if !blocking.is_empty() {
+        return Err(blocking.join("；"));
+    }
+
     let mut config = generate_base_config(app_config);
     inject_nodes(&mut config, app_config, nodes)?;
+
+    // 其余不兼容项已自动降级（TUN 协议栈、store_rdrc）或只影响个别节点，记录在日志中。
+    for warning in capabilities
+        .warnings_for_settings(app_config)
+        .into_iter()
+        .chain(capabilities.warnings_for_outbounds(&config))
+    {
+        warn!("{}", warning);
+    }
     Ok(config)
 }
 
@@ -705,6 +739,15 @@ pub fn inject_custom_rules(
     rules: &[crate::app::storage::custom_rule::CustomRule],
     default_outbound: &str,
 ) -> usize {
+    let custom_values: Vec<Value> = rules
+        .iter()
+        .filter_map(|r| r.to_route_rule(default_outbound))
+        .collect();
+    insert_before_default_rules(config, custom_values)
+}
+
+/// 把路由规则插到默认私网/CN 分流段之前，返回插入的数量；配置没有 `route.rules` 时不插入。
+pub fn insert_before_default_rules(config: &mut Value, values: Vec<Value>) -> usize {
     let route_rules = match config
         .get_mut("route")
         .and_then(|r| r.get_mut("rules"))
@@ -714,15 +757,11 @@ pub fn inject_custom_rules(
         None => return 0,
     };
 
-    let custom_values: Vec<Value> = rules
-        .iter()
-        .filter_map(|r| r.to_route_rule(default_outbound))
-        .collect();
-    if custom_values.is_empty() {
+    if values.is_empty() {
         return 0;
     }
 
-    let injected = custom_values.len();
+    let injected = values.len();
 
     // 找到“第一条默认直连/分流规则”的索引（特征：含 rule_set 或 ip_cidr 或 domain），
     // 把自定义规则插到它之前，使自定义规则优先于内置 CN/GeoIP/私网分流。
@@ -736,7 +775,7 @@ pub fn inject_custom_rules(
         })
         .unwrap_or(route_rules.len());
 
-    for (offset, value) in custom_values.into_iter().enumerate() {
+    for (offset, value) in values.into_iter().enumerate() {
         route_rules.insert(insert_pos + offset, value);
     }
 
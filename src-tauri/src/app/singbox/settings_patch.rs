@@ -6,8 +6,16 @@ use super::common::{
     RS_GEOSITE_YOUTUBE, TAG_AUTO, TAG_DIRECT, TAG_GOOGLE, TAG_NETFLIX, TAG_OPENAI, TAG_TELEGRAM,
     TAG_YOUTUBE,
 };
+use crate::app::core::extra_inbounds::{build_extra_inbounds, sync_extra_inbounds};
+use crate::app::core::kernel_service::capabilities::current_capabilities;
+use crate::app::core::lan_access::{
+    effective_lan_credentials, is_lan_auth_inbound_tag, lan_auth_inbounds, lan_source_route_rule,
+    mixed_listen_address, sync_lan_auth_inbounds, sync_lan_source_route_rule,
+};
 use crate::app::core::tun_profile::{
-    default_tun_route_exclude_addresses, normalize_persisted_tun_route_exclude_address,
+    carve_out_cidrs, configured_fake_ip_ranges, is_tun_split_route_rule, linux_only_list,
+    normalize_persisted_tun_route_exclude_address, resolve_tun_route_exclude_address,
+    tun_route_exclude_rule_sets, TunSplitRules, TUN_INBOUND_TAG,
 };
 use crate::app::storage::state_model::AppConfig;
 use serde_json::{json, Map, Value};
@@ -33,6 +41,8 @@ pub fn apply_app_settings_to_config(config: &mut Value, app_config: &AppConfig)
         // 针对“本程序生成的订阅配置”，尝试同步高级选项。
         // 采用“按 tag 定位并局部更新”的方式：如果用户导入的是原始订阅配置（结构不同），则不会强行改动。
         apply_profile_settings_if_present(config_obj, app_config);
+        apply_tun_split_route_rules(config_obj, app_config);
+        apply_lan_source_route_rule(config_obj, app_config);
 
         // clash_api 主要用于前端 UI 通过 Clash API 读取代理组/切换节点。
         let experimental = config_obj
@@ -55,7 +65,10 @@ pub fn apply_app_settings_to_config(config: &mut Value, app_config: &AppConfig)
             let cache_file = exp_obj.entry("cache_file".to_string()).or_insert(json!({}));
             if let Some(cache_obj) = cache_file.as_object_mut() {
                 cache_obj.insert("enabled".to_string(), json!(true));
-                if app_config.singbox_fake_dns_enabled {
+                // 旧内核不认识 store_rdrc，直接省略该字段
+                if app_config.singbox_fake_dns_enabled
+                    && current_capabilities().supports_store_rdrc()
+                {
                     cache_obj.insert("store_rdrc".to_string(), json!(true));
                 } else {
                     cache_obj.remove("store_rdrc");
@@ -99,28 +112,55 @@ pub fn apply_port_settings_only(config: &mut Value, app_config: &AppConfig) {
             .get_mut("inbounds")
             .and_then(|v| v.as_array_mut())
         {
+            let listen = mixed_listen_address(
+                app_config.allow_lan_access,
+                effective_lan_credentials(app_config).as_ref(),
+            );
             for inbound in inbounds.iter_mut() {
                 if let Some(inbound_obj) = inbound.as_object_mut() {
+                    let tag = inbound_obj.get("tag").and_then(|v| v.as_str());
+                    if tag.is_some_and(is_lan_auth_inbound_tag) {
+                        continue;
+                    }
                     let is_mixed = matches!(
                         inbound_obj.get("type").and_then(|v| v.as_str()),
                         Some("mixed")
                     );
-                    let is_mixed_in = matches!(
-                        inbound_obj.get("tag").and_then(|v| v.as_str()),
-                        Some("mixed-in")
-                    );
+                    let is_mixed_in = tag == Some("mixed-in");
                     if is_mixed || is_mixed_in {
                         if inbound_obj.contains_key("listen_port") {
                             inbound_obj
                                 .insert("listen_port".to_string(), json!(app_config.proxy_port));
                         }
-                        inbound_obj.insert(
-                            "listen".to_string(),
-                            json!(proxy_listen_address(app_config)),
-                        );
+                        inbound_obj.insert("listen".to_string(), json!(listen));
+                        // 认证只放在局域网入站上，本机连接 mixed-in 不需要凭据
+                        inbound_obj.remove("users");
                     }
                 }
             }
+            sync_lan_auth_inbounds(inbounds, lan_auth_inbounds(app_config));
+        }
+
+        if let Some(rules) = config_obj
+            .get_mut("route")
+            .and_then(|route| route.get_mut("rules"))
+            .and_then(|rules| rules.as_array_mut())
+        {
+            sync_lan_source_route_rule(rules, lan_source_route_rule(app_config));
+        }
+
+        // 原始订阅配置也需要带上额外入站，按 tag 前缀替换上次写入的条目
+        let extra_inbounds =
+            build_extra_inbounds(&app_config.extra_inbounds, proxy_listen_address(app_config));
+        if extra_inbounds.is_empty() && !config_obj.contains_key("inbounds") {
+            return;
+        }
+        if let Some(inbounds) = config_obj
+            .entry("inbounds".to_string())
+            .or_insert_with(|| json!([]))
+            .as_array_mut()
+        {
+            sync_extra_inbounds(inbounds, extra_inbounds);
         }
     }
 }
@@ -512,41 +552,218 @@ fn apply_inbounds_settings(config_obj: &mut Map<String, Value>, app_config: &App
     let mut inbounds = Vec::new();
 
     // mixed 是桌面端最通用的入口（HTTP + SOCKS），便于系统代理/浏览器直接使用。
+    // 开启局域网认证时 mixed-in 只监听本机，局域网设备走同端口的带认证入站
     inbounds.push(json!({
         "type": "mixed",
         "tag": "mixed-in",
-        "listen": proxy_listen_address(app_config),
+        "listen": mixed_listen_address(
+            app_config.allow_lan_access,
+            effective_lan_credentials(app_config).as_ref(),
+        ),
         "listen_port": app_config.proxy_port,
         "set_system_proxy": app_config.system_proxy_enabled
     }));
+    inbounds.extend(lan_auth_inbounds(app_config));
 
     // TUN 模式依赖 sing-box 配置里显式存在 tun inbound，所以这里根据设置开关动态添加/移除。
     if app_config.tun_enabled {
-        inbounds.push(json!({
+        // gvisor / mixed 协议栈需要内核带 with_gvisor 构建标签，缺少时回退到 system。
+        let stack = current_capabilities().effective_tun_stack(&app_config.tun_stack);
+        let mut tun_in = json!({
             "type": "tun",
-            "tag": "tun-in",
+            "tag": TUN_INBOUND_TAG,
             "address": tun_addresses,
             "auto_route": app_config.tun_auto_route,
             "strict_route": app_config.tun_strict_route,
-            "stack": app_config.tun_stack,
+            "stack": stack,
             "mtu": app_config.tun_mtu,
             "route_exclude_address": tun_route_exclude_address
-        }));
+        });
+        if let Some(route_address) = app_config
+            .tun_route_address
+            .as_ref()
+            .filter(|cidrs| !cidrs.is_empty())
+        {
+            tun_in["route_address"] = json!(route_address);
+        }
+        let rule_sets = resolve_tun_route_exclude_rule_sets_for_patch(config_obj, app_config);
+        if !rule_sets.is_empty() {
+            tun_in["route_exclude_address_set"] = json!(rule_sets);
+        }
+        let split_rules = &app_config.tun_split_rules;
+        // UID / 网卡过滤仅 Linux 支持，其他平台保持 tun inbound 不变
+        if let Some(uids) = linux_only_list(&split_rules.include_uid) {
+            tun_in["include_uid"] = json!(uids);
+        }
+        if let Some(uids) = linux_only_list(&split_rules.exclude_uid) {
+            tun_in["exclude_uid"] = json!(uids);
+        }
+        if let Some(interfaces) = linux_only_list(&split_rules.include_interface) {
+            tun_in["include_interface"] = json!(interfaces);
+        }
+        if let Some(interfaces) = linux_only_list(&split_rules.exclude_interface) {
+            tun_in["exclude_interface"] = json!(interfaces);
+        }
+        inbounds.push(tun_in);
     }
 
+    inbounds.extend(build_extra_inbounds(
+        &app_config.extra_inbounds,
+        proxy_listen_address(app_config),
+    ));
+
     config_obj.insert("inbounds".to_string(), json!(inbounds));
 }
 
+fn apply_tun_split_route_rules(config_obj: &mut Map<String, Value>, app_config: &AppConfig) {
+    let split_rules = app_config
+        .tun_enabled
+        .then_some(&app_config.tun_split_rules)
+        .filter(|rules| !rules.is_empty());
+    let outbounds = config_obj
+        .get("outbounds")
+        .and_then(|v| v.as_array())
+        .cloned()
+        .unwrap_or_default();
+
+    if split_rules.is_none() && !config_obj.contains_key("route") {
+        return;
+    }
+    let route = config_obj
+        .entry("route".to_string())
+        .or_insert_with(|| json!({}));
+    let Some(route_obj) = route.as_object_mut() else {
+        return;
+    };
+    let rules = route_obj
+        .entry("rules".to_string())
+        .or_insert_with(|| json!([]));
+    if let Some(rules) = rules.as_array_mut() {
+        sync_tun_split_route_rules(rules, &outbounds, split_rules);
+    }
+}
+
+fn apply_lan_source_route_rule(config_obj: &mut Map<String, Value>, app_config: &AppConfig) {
+    let rule = lan_source_route_rule(app_config);
+    if rule.is_none() && !config_obj.contains_key("route") {
+        return;
+    }
+    let route = config_obj
+        .entry("route".to_string())
+        .or_insert_with(|| json!({}));
+    let Some(route_obj) = route.as_object_mut() else {
+        return;
+    };
+    let rules = route_obj
+        .entry("rules".to_string())
+        .or_insert_with(|| json!([]));
+    if let Some(rules) = rules.as_array_mut() {
+        sync_lan_source_route_rule(rules, rule);
+    }
+}
+
+/// 重写 TUN 进程分流规则：先移除上次生成的规则，再把新规则插到 sniff / hijack-dns 之后。
+///
+/// `split_rules` 为 `None`（TUN 关闭或名单为空）时只做清理。
+pub fn sync_tun_split_route_rules(
+    rules: &mut Vec<Value>,
+    outbounds: &[Value],
+    split_rules: Option<&TunSplitRules>,
+) {
+    rules.retain(|rule| !is_tun_split_route_rule(rule));
+
+    let Some(split_rules) = split_rules else {
+        return;
+    };
+    let Some(direct_outbound) = find_direct_outbound_tag(outbounds) else {
+        tracing::warn!("配置中没有 direct 出站，跳过 TUN 进程分流规则");
+        return;
+    };
+    let generated = split_rules.route_rules(&direct_outbound);
+    if generated.is_empty() {
+        return;
+    }
+
+    let insert_at = rules
+        .iter()
+        .position(|rule| {
+            !matches!(
+                rule.get("action").and_then(|v| v.as_str()),
+                Some("sniff") | Some("hijack-dns")
+            )
+        })
+        .unwrap_or(rules.len());
+    rules.splice(insert_at..insert_at, generated);
+}
+
+/// 优先使用本程序生成配置中的 `direct`，其次是导入配置里第一个 direct 类型出站
+fn find_direct_outbound_tag(outbounds: &[Value]) -> Option<String> {
+    let tag_of = |outbound: &Value| {
+        outbound
+            .get("tag")
+            .and_then(|v| v.as_str())
+            .map(|tag| tag.to_string())
+    };
+    outbounds
+        .iter()
+        .filter_map(tag_of)
+        .find(|tag| tag == TAG_DIRECT)
+        .or_else(|| {
+            outbounds
+                .iter()
+                .find(|outbound| outbound.get("type").and_then(|v| v.as_str()) == Some("direct"))
+                .and_then(tag_of)
+        })
+}
+
 fn resolve_tun_route_exclude_address_for_patch(
     config_obj: &Map<String, Value>,
     app_config: &AppConfig,
 ) -> Vec<String> {
-    if let Some(explicit) = app_config.tun_route_exclude_address.clone() {
-        return explicit;
-    }
+    let route_address = app_config.tun_route_address.clone().unwrap_or_default();
+    // 导入配置里已有的排除地址只在未使用包含网段 / 预设时沿用，
+    // 否则取消预设后之前合并进去的网段会一直残留。
+    let explicit = app_config.tun_route_exclude_address.clone().or_else(|| {
+        if route_address.is_empty() && app_config.tun_route_presets.is_empty() {
+            extract_existing_tun_route_exclude_address(config_obj)
+        } else {
+            None
+        }
+    });
+
+    carve_out_cidrs(
+        resolve_tun_route_exclude_address(explicit, &route_address, &app_config.tun_route_presets),
+        &configured_fake_ip_ranges(app_config),
+    )
+}
 
-    extract_existing_tun_route_exclude_address(config_obj)
-        .unwrap_or_else(default_tun_route_exclude_addresses)
+/// 预设依赖的规则集必须在 `route.rule_set` 中存在，否则内核会拒绝加载配置
+fn resolve_tun_route_exclude_rule_sets_for_patch(
+    config_obj: &Map<String, Value>,
+    app_config: &AppConfig,
+) -> Vec<String> {
+    let available = config_obj
+        .get("route")
+        .and_then(|route| route.get("rule_set"))
+        .and_then(|v| v.as_array())
+        .map(|rule_sets| {
+            rule_sets
+                .iter()
+                .filter_map(|rule_set| rule_set.get("tag").and_then(|v| v.as_str()))
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+
+    tun_route_exclude_rule_sets(&app_config.tun_route_presets)
+        .into_iter()
+        .filter(|tag| {
+            let present = available.contains(&tag.as_str());
+            if !present {
+                tracing::warn!("配置中缺少规则集 {}，跳过对应的 TUN 绕过预设", tag);
+            }
+            present
+        })
+        .collect()
 }
 
 fn extract_existing_tun_route_exclude_address(
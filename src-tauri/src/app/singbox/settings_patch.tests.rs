@@ -1,5 +1,8 @@
 use super::*;
-use crate::app::core::tun_profile::default_tun_route_exclude_addresses;
+use crate::app::core::extra_inbounds::{ExtraInbound, ExtraInboundKind};
+use crate::app::core::tun_profile::{
+    default_tun_route_exclude_addresses, TunRoutePreset, TunSplitRules,
+};
 use crate::app::storage::state_model::AppConfig;
 use serde_json::{json, Value};
 
@@ -336,3 +339,233 @@ fn apply_app_settings_should_fallback_to_canonical_tun_route_exclude_address_def
         Some(&json!(default_tun_route_exclude_addresses()))
     );
 }
+
+#[test]
+fn apply_app_settings_should_sync_tun_split_route_rules() {
+    let mut config = json!({
+        "dns": {
+            "servers": [],
+            "rules": []
+        },
+        "experimental": {
+            "clash_api": {},
+            "cache_file": {}
+        },
+        "inbounds": [],
+        "outbounds": [
+            { "type": "selector", "tag": "proxy" },
+            { "type": "direct", "tag": "bypass" }
+        ],
+        "route": {
+            "rule_set": [],
+            "rules": [
+                { "action": "sniff" },
+                { "protocol": "dns", "action": "hijack-dns" },
+                { "ip_cidr": ["10.0.0.0/8"], "outbound": "bypass" }
+            ],
+            "final": "proxy"
+        }
+    });
+    let mut app_config = AppConfig {
+        tun_enabled: true,
+        singbox_block_ads: false,
+        tun_split_rules: TunSplitRules {
+            exclude_process: vec!["openvpn".to_string()],
+            include_process: vec!["/usr/bin/firefox".to_string()],
+            exclude_uid: vec![0],
+            ..TunSplitRules::default()
+        },
+        ..AppConfig::default()
+    };
+
+    apply_app_settings_to_config(&mut config, &app_config);
+    // 重复同步不应叠加规则
+    apply_app_settings_to_config(&mut config, &app_config);
+
+    let rules = config["route"]["rules"]
+        .as_array()
+        .expect("route.rules 应存在");
+    assert_eq!(rules.len(), 5, "应插入两条进程分流规则: {:?}", rules);
+    assert_eq!(rules[2]["process_name"], json!(["openvpn"]));
+    assert_eq!(rules[2]["outbound"], "bypass");
+    assert_eq!(rules[3]["type"], "logical");
+    assert_eq!(rules[4]["ip_cidr"], json!(["10.0.0.0/8"]));
+
+    let tun_in = &config["inbounds"][1];
+    if cfg!(target_os = "linux") {
+        assert_eq!(tun_in["exclude_uid"], json!([0]));
+    } else {
+        assert!(tun_in.get("exclude_uid").is_none());
+    }
+
+    // 关闭 TUN 后清理进程分流规则
+    app_config.tun_enabled = false;
+    apply_app_settings_to_config(&mut config, &app_config);
+    let rules = config["route"]["rules"]
+        .as_array()
+        .expect("route.rules 应存在");
+    assert_eq!(rules.len(), 3);
+    assert!(!rules
+        .iter()
+        .any(|rule| rule.to_string().contains("openvpn")));
+}
+
+#[test]
+fn apply_app_settings_should_write_route_address_and_available_preset_rule_sets() {
+    let mut config = json!({
+        "dns": {
+            "servers": [],
+            "rules": []
+        },
+        "experimental": {
+            "clash_api": {},
+            "cache_file": {}
+        },
+        "inbounds": [
+            {
+                "type": "tun",
+                "tag": "tun-in",
+                "route_exclude_address": ["198.51.100.0/24"]
+            }
+        ],
+        "route": {
+            "rule_set": [
+                {
+                    "type": "remote",
+                    "tag": "geoip-cn",
+                    "format": "binary",
+                    "url": "https://example.com/geoip-cn.srs"
+                }
+            ],
+            "rules": [{ "action": "sniff" }],
+            "final": "direct"
+        }
+    });
+    let app_config = AppConfig {
+        tun_enabled: true,
+        tun_route_address: Some(vec!["10.20.0.0/16".to_string()]),
+        tun_route_presets: vec![
+            TunRoutePreset::BypassCn,
+            TunRoutePreset::BypassContainerBridges,
+        ],
+        ..AppConfig::default()
+    };
+
+    apply_app_settings_to_config(&mut config, &app_config);
+
+    let tun_in = &config["inbounds"][1];
+    assert_eq!(tun_in["route_address"], json!(["10.20.0.0/16"]));
+    assert_eq!(tun_in["route_exclude_address_set"], json!(["geoip-cn"]));
+    // 使用预设时不沿用导入配置里残留的排除地址，也不附带默认私网排除
+    let excludes = tun_in["route_exclude_address"]
+        .as_array()
+        .expect("route_exclude_address 应存在");
+    assert!(!excludes.contains(&json!("198.51.100.0/24")));
+    assert!(!excludes.contains(&json!("192.168.0.0/16")));
+    assert!(excludes.contains(&json!("172.16.0.0/12")));
+
+    // 规则集缺失时跳过，避免内核加载失败
+    config["route"]["rule_set"] = json!([]);
+    apply_app_settings_to_config(&mut config, &app_config);
+    assert!(config["inbounds"][1]
+        .get("route_exclude_address_set")
+        .is_none());
+}
+
+fn socks_inbound(tag: &str, port: u16) -> ExtraInbound {
+    ExtraInbound {
+        kind: ExtraInboundKind::Socks,
+        tag: tag.to_string(),
+        enabled: true,
+        listen: None,
+        listen_port: port,
+        username: None,
+        password: None,
+        method: None,
+        uuid: None,
+    }
+}
+
+#[test]
+fn apply_app_settings_should_append_enabled_extra_inbounds() {
+    let mut config = json!({ "inbounds": [] });
+    let mut disabled = socks_inbound("extra-off", 1081);
+    disabled.enabled = false;
+    let mut socks = socks_inbound("extra-socks", 1080);
+    socks.username = Some("user".to_string());
+    socks.password = Some("pass".to_string());
+    let app_config = AppConfig {
+        allow_lan_access: true,
+        extra_inbounds: vec![socks, disabled],
+        ..AppConfig::default()
+    };
+
+    apply_app_settings_to_config(&mut config, &app_config);
+
+    let inbounds = config["inbounds"].as_array().expect("inbounds 应存在");
+    assert_eq!(inbounds.len(), 2);
+    assert_eq!(inbounds[1]["type"], json!("socks"));
+    assert_eq!(inbounds[1]["tag"], json!("extra-socks"));
+    assert_eq!(inbounds[1]["listen"], json!("0.0.0.0"));
+    assert_eq!(inbounds[1]["listen_port"], json!(1080));
+}
+
+#[test]
+fn apply_port_settings_only_should_replace_previous_extra_inbounds() {
+    let mut config = json!({
+        "inbounds": [
+            { "type": "mixed", "tag": "mixed-in", "listen": "127.0.0.1", "listen_port": 7890 },
+            { "type": "socks", "tag": "extra-stale", "listen": "127.0.0.1", "listen_port": 1080 },
+            { "type": "socks", "tag": "user-socks", "listen": "127.0.0.1", "listen_port": 1082 }
+        ]
+    });
+    let app_config = AppConfig {
+        extra_inbounds: vec![socks_inbound("extra-socks", 1081)],
+        ..AppConfig::default()
+    };
+
+    apply_port_settings_only(&mut config, &app_config);
+
+    let tags: Vec<&str> = config["inbounds"]
+        .as_array()
+        .expect("inbounds 应存在")
+        .iter()
+        .filter_map(|inbound| inbound["tag"].as_str())
+        .collect();
+    assert_eq!(tags, vec!["mixed-in", "user-socks", "extra-socks"]);
+    assert_eq!(config["inbounds"][0]["listen_port"], json!(12080));
+
+    // 未配置额外入站且原配置没有 inbounds 时不凭空创建
+    let mut empty = json!({});
+    apply_port_settings_only(&mut empty, &AppConfig::default());
+    assert!(empty.get("inbounds").is_none());
+}
+
+#[test]
+fn apply_app_settings_should_sync_lan_source_rule_before_sniff() {
+    let mut config = json!({
+        "inbounds": [],
+        "route": { "rules": [{ "action": "sniff" }] }
+    });
+    let mut app_config = AppConfig {
+        allow_lan_access: true,
+        lan_allowed_cidrs: vec!["192.168.1.0/24".to_string()],
+        ..AppConfig::default()
+    };
+
+    apply_app_settings_to_config(&mut config, &app_config);
+    apply_app_settings_to_config(&mut config, &app_config);
+
+    let rules = config["route"]["rules"]
+        .as_array()
+        .expect("route.rules 应存在");
+    assert_eq!(rules.len(), 2, "重复同步不应叠加规则: {:?}", rules);
+    assert_eq!(rules[0]["type"], json!("logical"));
+    assert_eq!(rules[0]["action"], json!("reject"));
+    assert_eq!(rules[1]["action"], json!("sniff"));
+
+    // 关闭局域网访问后移除来源限制
+    app_config.allow_lan_access = false;
+    apply_port_settings_only(&mut config, &app_config);
+    assert_eq!(config["route"]["rules"], json!([{ "action": "sniff" }]));
+}
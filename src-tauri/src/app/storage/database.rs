@@ -1,8 +1,16 @@
 use super::error::StorageError;
-use crate::app::core::tun_profile::normalize_persisted_tun_route_exclude_address;
+use crate::app::core::extra_inbounds::ExtraInbound;
+use crate::app::core::lan_access::normalize_lan_allowed_cidrs;
+use crate::app::core::tun_profile::{
+    normalize_persisted_tun_route_address, normalize_persisted_tun_route_exclude_address,
+    normalize_persisted_tun_split_rules, TunRoutePreset, TunSplitRules,
+};
 use crate::app::storage::state_model::{
     AppConfig, LocaleConfig, ThemeConfig, UpdateConfig, WindowConfig,
 };
+use crate::app::storage::traffic_record::{
+    TrafficBucket, TrafficDelta, TrafficDimension, TrafficGranularity, TrafficTotal,
+};
 use chrono::Utc;
 use serde::{Deserialize, Serialize};
 use sqlx::{migrate::MigrateDatabase, sqlite::Sqlite, sqlite::SqlitePool, Row};
@@ -72,6 +80,12 @@ impl DatabaseService {
                 tun_stack TEXT DEFAULT 'mixed',
                 tun_enable_ipv6 BOOLEAN DEFAULT FALSE,
                 tun_route_exclude_address TEXT,
+                tun_route_address TEXT,
+                tun_route_presets TEXT,
+                tun_split_rules TEXT,
+                extra_inbounds TEXT,
+                lan_auth_enabled BOOLEAN DEFAULT TRUE,
+                lan_allowed_cidrs TEXT,
                 active_config_path TEXT,
                 installed_kernel_version TEXT,
                 singbox_dns_proxy TEXT DEFAULT 'https://1.1.1.1/dns-query',
@@ -131,6 +145,13 @@ impl DatabaseService {
             "ALTER TABLE app_config ADD COLUMN singbox_enable_app_groups BOOLEAN DEFAULT TRUE",
             "ALTER TABLE app_config ADD COLUMN tun_self_heal_enabled BOOLEAN DEFAULT TRUE",
             "ALTER TABLE app_config ADD COLUMN tun_self_heal_cooldown_secs INTEGER DEFAULT 90",
+            "ALTER TABLE app_config ADD COLUMN tun_split_rules TEXT",
+            "ALTER TABLE app_config ADD COLUMN tun_route_address TEXT",
+            "ALTER TABLE app_config ADD COLUMN tun_route_presets TEXT",
+            "ALTER TABLE app_config ADD COLUMN extra_inbounds TEXT",
+            // 已有安装开启局域网访问的设备原本无需认证，升级后不强制开启，由用户自行选择；新安装默认开启
+            "ALTER TABLE app_config ADD COLUMN lan_auth_enabled BOOLEAN DEFAULT FALSE",
+            "ALTER TABLE app_config ADD COLUMN lan_allowed_cidrs TEXT",
         ];
 
         for statement in alter_statements {
@@ -237,6 +258,30 @@ impl DatabaseService {
         .execute(pool)
         .await?;
 
+        // 流量统计表（小时桶 / 日桶），按维度累加
+        for table in ["traffic_stats_hourly", "traffic_stats_daily"] {
+            sqlx::query(&format!(
+                r#"
+                CREATE TABLE IF NOT EXISTS {table} (
+                    bucket_start INTEGER NOT NULL,
+                    dimension TEXT NOT NULL,
+                    name TEXT NOT NULL,
+                    upload INTEGER NOT NULL DEFAULT 0,
+                    download INTEGER NOT NULL DEFAULT 0,
+                    PRIMARY KEY (bucket_start, dimension, name)
+                )
+                "#
+            ))
+            .execute(pool)
+            .await?;
+
+            sqlx::query(&format!(
+                "CREATE INDEX IF NOT EXISTS idx_{table}_dimension ON {table} (dimension, bucket_start)"
+            ))
+            .execute(pool)
+            .await?;
+        }
+
         Ok(())
     }
 
@@ -296,6 +341,24 @@ impl DatabaseService {
                 tun_route_exclude_address: parse_tun_route_exclude_address_column(
                     row.try_get("tun_route_exclude_address").unwrap_or(None),
                 ),
+                tun_route_address: parse_tun_route_address_column(
+                    row.try_get("tun_route_address").unwrap_or(None),
+                ),
+                tun_route_presets: parse_tun_route_presets_column(
+                    row.try_get("tun_route_presets").unwrap_or(None),
+                ),
+                tun_split_rules: parse_tun_split_rules_column(
+                    row.try_get("tun_split_rules").unwrap_or(None),
+                ),
+                extra_inbounds: parse_extra_inbounds_column(
+                    row.try_get("extra_inbounds").unwrap_or(None),
+                ),
+                lan_auth_enabled: row
+                    .try_get("lan_auth_enabled")
+                    .unwrap_or(default_config.lan_auth_enabled),
+                lan_allowed_cidrs: parse_lan_allowed_cidrs_column(
+                    row.try_get("lan_allowed_cidrs").unwrap_or(None),
+                ),
                 active_config_path: row.try_get("active_config_path").unwrap_or(None),
                 installed_kernel_version: row.try_get("installed_kernel_version").unwrap_or(None),
                 singbox_dns_proxy: row
@@ -353,8 +416,8 @@ impl DatabaseService {
         sqlx::query(
             r#"
             INSERT OR REPLACE INTO app_config
-            (id, auto_start_kernel, auto_start_app, auto_hide_to_tray_on_autostart, tray_close_behavior, prefer_ipv6, allow_lan_access, proxy_port, api_port, proxy_mode, system_proxy_enabled, tun_enabled, tray_instance_id, system_proxy_bypass, tun_auto_route, tun_strict_route, tun_mtu, tun_ipv4, tun_ipv6, tun_stack, tun_enable_ipv6, tun_route_exclude_address, active_config_path, installed_kernel_version, singbox_dns_proxy, singbox_dns_cn, singbox_dns_resolver, singbox_urltest_url, singbox_default_proxy_outbound, singbox_block_ads, singbox_download_detour, singbox_dns_hijack, singbox_fake_dns_enabled, singbox_fake_dns_ipv4_range, singbox_fake_dns_ipv6_range, singbox_fake_dns_filter_mode, singbox_enable_app_groups, tun_self_heal_enabled, tun_self_heal_cooldown_secs, updated_at)
-            VALUES (1, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            (id, auto_start_kernel, auto_start_app, auto_hide_to_tray_on_autostart, tray_close_behavior, prefer_ipv6, allow_lan_access, proxy_port, api_port, proxy_mode, system_proxy_enabled, tun_enabled, tray_instance_id, system_proxy_bypass, tun_auto_route, tun_strict_route, tun_mtu, tun_ipv4, tun_ipv6, tun_stack, tun_enable_ipv6, tun_route_exclude_address, tun_route_address, tun_route_presets, tun_split_rules, extra_inbounds, lan_auth_enabled, lan_allowed_cidrs, active_config_path, installed_kernel_version, singbox_dns_proxy, singbox_dns_cn, singbox_dns_resolver, singbox_urltest_url, singbox_default_proxy_outbound, singbox_block_ads, singbox_download_detour, singbox_dns_hijack, singbox_fake_dns_enabled, singbox_fake_dns_ipv4_range, singbox_fake_dns_ipv6_range, singbox_fake_dns_filter_mode, singbox_enable_app_groups, tun_self_heal_enabled, tun_self_heal_cooldown_secs, updated_at)
+            VALUES (1, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
         )
         .bind(config.auto_start_kernel)
@@ -378,6 +441,12 @@ impl DatabaseService {
         .bind(&config.tun_stack)
         .bind(config.tun_enable_ipv6)
         .bind(serialize_optional_json(&config.tun_route_exclude_address)?)
+        .bind(serialize_optional_json(&config.tun_route_address)?)
+        .bind(serde_json::to_string(&config.tun_route_presets).map_err(StorageError::Serialization)?)
+        .bind(serde_json::to_string(&config.tun_split_rules).map_err(StorageError::Serialization)?)
+        .bind(serde_json::to_string(&config.extra_inbounds).map_err(StorageError::Serialization)?)
+        .bind(config.lan_auth_enabled)
+        .bind(serde_json::to_string(&config.lan_allowed_cidrs).map_err(StorageError::Serialization)?)
         .bind(&config.active_config_path)
         .bind(&config.installed_kernel_version)
         .bind(&config.singbox_dns_proxy)
@@ -613,6 +682,154 @@ impl DatabaseService {
         Ok(())
     }
 
+    // 流量统计：把增量同时累加到小时桶与日桶
+    pub async fn add_traffic_deltas(
+        &self,
+        hour_bucket: i64,
+        day_bucket: i64,
+        deltas: &[TrafficDelta],
+    ) -> Result<(), StorageError> {
+        if deltas.is_empty() {
+            return Ok(());
+        }
+
+        let mut tx = self.pool.begin().await?;
+        for (table, bucket) in [
+            ("traffic_stats_hourly", hour_bucket),
+            ("traffic_stats_daily", day_bucket),
+        ] {
+            let sql = format!(
+                r#"
+                INSERT INTO {table} (bucket_start, dimension, name, upload, download)
+                VALUES (?, ?, ?, ?, ?)
+                ON CONFLICT(bucket_start, dimension, name) DO UPDATE SET
+                    upload = upload + excluded.upload,
+                    download = download + excluded.download
+                "#
+            );
+            for delta in deltas {
+                sqlx::query(&sql)
+                    .bind(bucket)
+                    .bind(delta.dimension.as_str())
+                    .bind(&delta.name)
+                    .bind(delta.upload as i64)
+                    .bind(delta.download as i64)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+        }
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    pub async fn query_traffic_buckets(
+        &self,
+        granularity: TrafficGranularity,
+        dimension: TrafficDimension,
+        name: Option<&str>,
+        from: i64,
+        to: i64,
+    ) -> Result<Vec<TrafficBucket>, StorageError> {
+        // 月度统计按本地时区的年月聚合日桶
+        let (table, group_key) = match granularity {
+            TrafficGranularity::Hour => ("traffic_stats_hourly", "bucket_start"),
+            TrafficGranularity::Day => ("traffic_stats_daily", "bucket_start"),
+            TrafficGranularity::Month => (
+                "traffic_stats_daily",
+                "strftime('%Y-%m', bucket_start, 'unixepoch', 'localtime')",
+            ),
+        };
+        let sql = format!(
+            r#"
+            SELECT MIN(bucket_start) AS bucket_start, name,
+                   SUM(upload) AS upload, SUM(download) AS download
+            FROM {table}
+            WHERE dimension = ?1 AND bucket_start >= ?2 AND bucket_start < ?3
+              AND (?4 IS NULL OR name = ?4)
+            GROUP BY {group_key}, name
+            ORDER BY bucket_start ASC, name ASC
+            "#
+        );
+
+        let rows = sqlx::query(&sql)
+            .bind(dimension.as_str())
+            .bind(from)
+            .bind(to)
+            .bind(name)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| TrafficBucket {
+                bucket_start: row.get("bucket_start"),
+                name: row.get("name"),
+                upload: row.get("upload"),
+                download: row.get("download"),
+            })
+            .collect())
+    }
+
+    pub async fn query_traffic_top(
+        &self,
+        granularity: TrafficGranularity,
+        dimension: TrafficDimension,
+        from: i64,
+        to: i64,
+        limit: u32,
+    ) -> Result<Vec<TrafficTotal>, StorageError> {
+        // 小时表保留期较短，长区间排行走日表
+        let table = match granularity {
+            TrafficGranularity::Hour => "traffic_stats_hourly",
+            TrafficGranularity::Day | TrafficGranularity::Month => "traffic_stats_daily",
+        };
+        let sql = format!(
+            r#"
+            SELECT name, SUM(upload) AS upload, SUM(download) AS download
+            FROM {table}
+            WHERE dimension = ? AND bucket_start >= ? AND bucket_start < ?
+            GROUP BY name
+            ORDER BY SUM(upload) + SUM(download) DESC
+            LIMIT ?
+            "#
+        );
+        let rows = sqlx::query(&sql)
+            .bind(dimension.as_str())
+            .bind(from)
+            .bind(to)
+            .bind(limit as i64)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| TrafficTotal {
+                name: row.get("name"),
+                upload: row.get("upload"),
+                download: row.get("download"),
+            })
+            .collect())
+    }
+
+    /// 删除早于阈值的统计桶，返回删除的行数
+    pub async fn prune_traffic_stats(
+        &self,
+        hourly_before: i64,
+        daily_before: i64,
+    ) -> Result<u64, StorageError> {
+        let hourly = sqlx::query("DELETE FROM traffic_stats_hourly WHERE bucket_start < ?")
+            .bind(hourly_before)
+            .execute(&self.pool)
+            .await?;
+        let daily = sqlx::query("DELETE FROM traffic_stats_daily WHERE bucket_start < ?")
+            .bind(daily_before)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(hourly.rows_affected() + daily.rows_affected())
+    }
+
     pub async fn close(&self) -> Result<(), StorageError> {
         self.pool.close().await;
         Ok(())
@@ -648,3 +865,83 @@ fn parse_tun_route_exclude_address_column(raw: Option<String>) -> Option<Vec<Str
         }
     }
 }
+
+fn parse_tun_route_address_column(raw: Option<String>) -> Option<Vec<String>> {
+    let raw = raw.filter(|raw| !raw.trim().is_empty())?;
+
+    match serde_json::from_str::<Vec<String>>(&raw) {
+        Ok(values) => normalize_persisted_tun_route_address(Some(values)),
+        Err(error) => {
+            tracing::warn!(
+                "检测到无效的已持久化 tun_route_address JSON，已回退为 None: {}",
+                error
+            );
+            None
+        }
+    }
+}
+
+fn parse_tun_route_presets_column(raw: Option<String>) -> Vec<TunRoutePreset> {
+    let Some(raw) = raw.filter(|raw| !raw.trim().is_empty()) else {
+        return Vec::new();
+    };
+
+    serde_json::from_str(&raw).unwrap_or_else(|error| {
+        tracing::warn!(
+            "检测到无效的已持久化 tun_route_presets JSON，已回退为空: {}",
+            error
+        );
+        Vec::new()
+    })
+}
+
+fn parse_tun_split_rules_column(raw: Option<String>) -> TunSplitRules {
+    let Some(raw) = raw.filter(|raw| !raw.trim().is_empty()) else {
+        return TunSplitRules::default();
+    };
+
+    match serde_json::from_str::<TunSplitRules>(&raw) {
+        Ok(rules) => normalize_persisted_tun_split_rules(rules),
+        Err(error) => {
+            tracing::warn!(
+                "检测到无效的已持久化 tun_split_rules JSON，已回退为空规则: {}",
+                error
+            );
+            TunSplitRules::default()
+        }
+    }
+}
+
+fn parse_lan_allowed_cidrs_column(raw: Option<String>) -> Vec<String> {
+    let Some(raw) = raw.filter(|raw| !raw.trim().is_empty()) else {
+        return Vec::new();
+    };
+
+    match serde_json::from_str::<Vec<String>>(&raw)
+        .map_err(|e| e.to_string())
+        .and_then(normalize_lan_allowed_cidrs)
+    {
+        Ok(cidrs) => cidrs,
+        Err(error) => {
+            tracing::warn!(
+                "检测到无效的已持久化 lan_allowed_cidrs，已回退为空: {}",
+                error
+            );
+            Vec::new()
+        }
+    }
+}
+
+fn parse_extra_inbounds_column(raw: Option<String>) -> Vec<ExtraInbound> {
+    let Some(raw) = raw.filter(|raw| !raw.trim().is_empty()) else {
+        return Vec::new();
+    };
+
+    serde_json::from_str(&raw).unwrap_or_else(|error| {
+        tracing::warn!(
+            "检测到无效的已持久化 extra_inbounds JSON，已回退为空: {}",
+            error
+        );
+        Vec::new()
+    })
+}
@@ -1,11 +1,25 @@
 use super::DatabaseService;
+use crate::app::core::extra_inbounds::{
+    normalize_extra_inbounds, open_extra_inbound_secrets, seal_extra_inbound_secrets,
+};
 use crate::app::core::kernel_auto_manage::auto_manage_with_saved_config;
-use crate::app::core::tun_profile::normalize_tun_route_exclude_address;
+use crate::app::core::lan_access::{normalize_lan_allowed_cidrs, refresh_lan_credentials};
+use crate::app::core::tun_profile::{
+    normalize_tun_route_address, normalize_tun_route_exclude_address, normalize_tun_route_presets,
+    normalize_tun_split_rules, resolve_tun_route_exclude_address,
+    validate_tun_route_address_conflicts,
+};
+use crate::app::network::subscription_service::fetch_options::{
+    open_fetch_header_secrets, seal_fetch_header_secrets,
+};
 use crate::app::storage::error::{StorageError, StorageResult};
 use crate::app::storage::state_model::{
     AppConfig, LocaleConfig, StartupPreferences, Subscription, ThemeConfig, UpdateConfig,
     WindowConfig,
 };
+use crate::app::storage::traffic_record::{
+    TrafficBucket, TrafficDelta, TrafficDimension, TrafficGranularity, TrafficTotal,
+};
 use std::sync::Arc;
 use tauri::{AppHandle, Manager};
 use tokio::sync::OnceCell;
@@ -148,6 +162,8 @@ pub async fn get_enhanced_storage<R: tauri::Runtime>(
 #[derive(Debug, Clone)]
 pub struct EnhancedStorageService {
     database: Arc<DatabaseService>,
+    /// 加密应用配置中密钥字段所用的应用数据目录
+    app_data_dir: std::path::PathBuf,
 }
 
 impl EnhancedStorageService {
@@ -166,19 +182,33 @@ impl EnhancedStorageService {
         })?;
         let database = Arc::new(DatabaseService::new(database_path_str).await?);
 
-        Ok(Self { database })
+        Ok(Self {
+            database,
+            app_data_dir,
+        })
+    }
+
+    /// 加解密配置中密钥字段所用的应用数据目录
+    pub fn app_data_dir(&self) -> &std::path::Path {
+        &self.app_data_dir
     }
 
     // 应用配置
     pub async fn get_app_config(&self) -> StorageResult<AppConfig> {
         match self.database.load_app_config().await? {
-            Some(config) => Ok(config),
+            Some(mut config) => {
+                open_extra_inbound_secrets(&mut config.extra_inbounds, &self.app_data_dir);
+                Ok(config)
+            }
             None => Ok(AppConfig::default()),
         }
     }
 
     pub async fn save_app_config(&self, config: &AppConfig) -> StorageResult<()> {
-        self.database.save_app_config(config).await
+        let mut sealed = config.clone();
+        seal_extra_inbound_secrets(&mut sealed.extra_inbounds, &self.app_data_dir)
+            .map_err(StorageError::Invalid)?;
+        self.database.save_app_config(&sealed).await
     }
 
     // 通用 KV 配置（custom_rules 等结构化数据复用此通道，避免新表/迁移）
@@ -251,15 +281,19 @@ impl EnhancedStorageService {
             .load_config::<Vec<Subscription>>("subscriptions")
             .await?
         {
-            Some(subscriptions) => Ok(subscriptions),
+            Some(mut subscriptions) => {
+                open_fetch_header_secrets(&mut subscriptions, &self.app_data_dir);
+                Ok(subscriptions)
+            }
             None => Ok(Vec::new()),
         }
     }
 
     pub async fn save_subscriptions(&self, subscriptions: &[Subscription]) -> StorageResult<()> {
-        self.database
-            .save_config("subscriptions", &subscriptions)
-            .await
+        let mut sealed = subscriptions.to_vec();
+        seal_fetch_header_secrets(&mut sealed, &self.app_data_dir)
+            .map_err(StorageError::Invalid)?;
+        self.database.save_config("subscriptions", &sealed).await
     }
 
     // 激活订阅索引
@@ -304,6 +338,54 @@ impl EnhancedStorageService {
     pub async fn remove_config(&self, key: &str) -> StorageResult<()> {
         self.database.remove_config(key).await
     }
+
+    // 流量统计
+    pub async fn add_traffic_deltas(
+        &self,
+        hour_bucket: i64,
+        day_bucket: i64,
+        deltas: &[TrafficDelta],
+    ) -> StorageResult<()> {
+        self.database
+            .add_traffic_deltas(hour_bucket, day_bucket, deltas)
+            .await
+    }
+
+    pub async fn query_traffic_buckets(
+        &self,
+        granularity: TrafficGranularity,
+        dimension: TrafficDimension,
+        name: Option<&str>,
+        from: i64,
+        to: i64,
+    ) -> StorageResult<Vec<TrafficBucket>> {
+        self.database
+            .query_traffic_buckets(granularity, dimension, name, from, to)
+            .await
+    }
+
+    pub async fn query_traffic_top(
+        &self,
+        granularity: TrafficGranularity,
+        dimension: TrafficDimension,
+        from: i64,
+        to: i64,
+        limit: u32,
+    ) -> StorageResult<Vec<TrafficTotal>> {
+        self.database
+            .query_traffic_top(granularity, dimension, from, to, limit)
+            .await
+    }
+
+    pub async fn prune_traffic_stats(
+        &self,
+        hourly_before: i64,
+        daily_before: i64,
+    ) -> StorageResult<u64> {
+        self.database
+            .prune_traffic_stats(hourly_before, daily_before)
+            .await
+    }
 }
 
 fn resolve_app_data_dir<R: tauri::Runtime>(app_handle: &AppHandle<R>) -> std::path::PathBuf {
@@ -330,6 +412,22 @@ fn build_startup_preferences(config: &AppConfig) -> StartupPreferences {
 fn normalize_app_config_for_persistence(mut config: AppConfig) -> Result<AppConfig, String> {
     config.tun_route_exclude_address =
         normalize_tun_route_exclude_address(config.tun_route_exclude_address)?;
+    config.tun_route_address = normalize_tun_route_address(config.tun_route_address)?;
+    config.tun_route_presets = normalize_tun_route_presets(config.tun_route_presets);
+    if let Some(route_address) = config.tun_route_address.as_deref() {
+        validate_tun_route_address_conflicts(
+            route_address,
+            &resolve_tun_route_exclude_address(
+                config.tun_route_exclude_address.clone(),
+                route_address,
+                &config.tun_route_presets,
+            ),
+        )?;
+    }
+    config.tun_split_rules = normalize_tun_split_rules(config.tun_split_rules)?;
+    config.extra_inbounds =
+        normalize_extra_inbounds(config.extra_inbounds, &[config.proxy_port, config.api_port])?;
+    config.lan_allowed_cidrs = normalize_lan_allowed_cidrs(config.lan_allowed_cidrs)?;
     Ok(config)
 }
 
@@ -414,6 +512,8 @@ pub async fn db_save_app_config_internal<R: tauri::Runtime>(
         .await
         .map_err(|e| e.to_string())?;
     save_startup_preferences_sync(app, &config)?;
+    // 开启局域网访问时在这里生成凭据，后续配置生成可直接从缓存读取
+    refresh_lan_credentials(app, &config).await;
     Ok(())
 }
 
@@ -614,6 +714,7 @@ mod tests {
             last_auto_update_error: None,
             last_auto_update_error_type: None,
             last_auto_update_backoff_until: None,
+            fetch_options: Default::default(),
         }
     }
 
@@ -670,4 +771,30 @@ mod tests {
             error
         );
     }
+
+    #[test]
+    fn should_reject_route_address_covered_by_preset_on_save() {
+        use crate::app::core::tun_profile::TunRoutePreset;
+
+        let error =
+            normalize_app_config_for_persistence(crate::app::storage::state_model::AppConfig {
+                tun_route_address: Some(vec!["10.20.0.0/16".to_string()]),
+                tun_route_presets: vec![TunRoutePreset::BypassLanTailscale],
+                ..crate::app::storage::state_model::AppConfig::default()
+            })
+            .expect_err("route address covered by LAN preset should be rejected");
+        assert!(error.contains("10.0.0.0/8"), "unexpected error: {}", error);
+
+        // 未启用预设时，只路由指定网段不再附带默认私网排除
+        let normalized =
+            normalize_app_config_for_persistence(crate::app::storage::state_model::AppConfig {
+                tun_route_address: Some(vec![" 10.20.0.0/16 ".to_string()]),
+                ..crate::app::storage::state_model::AppConfig::default()
+            })
+            .expect("route address without conflicting excludes should be accepted");
+        assert_eq!(
+            normalized.tun_route_address,
+            Some(vec!["10.20.0.0/16".to_string()])
+        );
+    }
 }
@@ -2,7 +2,9 @@ pub mod custom_rule;
 pub mod database;
 pub mod enhanced_storage_service;
 pub mod error;
+pub mod secret_cipher;
 pub mod state_model;
+pub mod traffic_record;
 
 // 重新导出新模型
 pub use database::*;
@@ -0,0 +1,96 @@
+//! 本地敏感信息（sudo 密码、局域网代理凭据等）的加解密
+//!
+//! 密钥由应用数据目录与用途派生，密文为 `base64(nonce || AES-256-GCM ciphertext)`，
+//! 存入数据库的通用配置表。
+
+use aes_gcm::{
+    aead::{Aead, KeyInit},
+    Aes256Gcm, Nonce,
+};
+use base64::engine::general_purpose::STANDARD as BASE64_ENGINE;
+use base64::Engine;
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager, Runtime};
+
+const NONCE_LEN: usize = 12;
+/// 内联保存在配置结构中的密文前缀，用于区分旧版本保存的明文
+pub const SEALED_SECRET_PREFIX: &str = "enc:v1:";
+
+fn app_data_dir<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map_err(|e| format!("无法定位应用数据目录: {}", e))
+}
+
+fn derive_crypto_key(data_dir: &Path, purpose: &str) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(data_dir.to_string_lossy().as_bytes());
+    hasher.update(format!("|sing-box-windows|{}|v1", purpose).as_bytes());
+    let digest = hasher.finalize();
+
+    let mut key = [0u8; 32];
+    key.copy_from_slice(&digest);
+    key
+}
+
+/// 按用途加密明文，`purpose` 不同的密文互不通用
+pub fn encrypt_secret<R: Runtime>(
+    app: &AppHandle<R>,
+    purpose: &str,
+    plaintext: &str,
+) -> Result<String, String> {
+    encrypt_secret_in(&app_data_dir(app)?, purpose, plaintext)
+}
+
+pub fn decrypt_secret<R: Runtime>(
+    app: &AppHandle<R>,
+    purpose: &str,
+    encoded: &str,
+) -> Result<String, String> {
+    decrypt_secret_in(&app_data_dir(app)?, purpose, encoded)
+}
+
+/// 与 [`encrypt_secret`] 相同，供只持有应用数据目录的存储层使用
+pub fn encrypt_secret_in(
+    data_dir: &Path,
+    purpose: &str,
+    plaintext: &str,
+) -> Result<String, String> {
+    let key = derive_crypto_key(data_dir, purpose);
+    let cipher = Aes256Gcm::new_from_slice(&key).map_err(|e| format!("初始化加密器失败: {}", e))?;
+
+    let mut nonce_bytes = [0u8; NONCE_LEN];
+    rand::thread_rng().fill_bytes(&mut nonce_bytes);
+    let nonce = Nonce::from_slice(&nonce_bytes);
+
+    let ciphertext = cipher
+        .encrypt(nonce, plaintext.as_bytes())
+        .map_err(|e| format!("加密失败: {}", e))?;
+
+    let mut combined = Vec::with_capacity(NONCE_LEN + ciphertext.len());
+    combined.extend_from_slice(&nonce_bytes);
+    combined.extend_from_slice(&ciphertext);
+
+    Ok(BASE64_ENGINE.encode(combined))
+}
+
+pub fn decrypt_secret_in(data_dir: &Path, purpose: &str, encoded: &str) -> Result<String, String> {
+    let raw = BASE64_ENGINE
+        .decode(encoded)
+        .map_err(|e| format!("解码密文失败: {}", e))?;
+    if raw.len() <= NONCE_LEN {
+        return Err("保存的加密数据已损坏，请重新输入".to_string());
+    }
+
+    let (nonce_bytes, cipher_bytes) = raw.split_at(NONCE_LEN);
+    let key = derive_crypto_key(data_dir, purpose);
+    let cipher = Aes256Gcm::new_from_slice(&key).map_err(|e| format!("初始化解密器失败: {}", e))?;
+
+    let plaintext = cipher
+        .decrypt(Nonce::from_slice(nonce_bytes), cipher_bytes)
+        .map_err(|e| format!("解密失败: {}", e))?;
+
+    String::from_utf8(plaintext).map_err(|e| format!("解密后的内容不是有效 UTF-8: {}", e))
+}
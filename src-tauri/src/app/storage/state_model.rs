@@ -1,3 +1,5 @@
+use crate::app::core::extra_inbounds::ExtraInbound;
+use crate::app::core::tun_profile::{TunRoutePreset, TunSplitRules};
 use crate::utils::proxy_util::DEFAULT_BYPASS_LIST;
 use serde::{Deserialize, Serialize};
 use ts_rs::TS;
@@ -26,6 +28,24 @@ pub struct AppConfig {
     pub tun_stack: String,
     pub tun_enable_ipv6: bool,
     pub tun_route_exclude_address: Option<Vec<String>>,
+    /// 只让这些网段进入 TUN（例如公司内网）；为空时接管全部流量
+    #[serde(default)]
+    pub tun_route_address: Option<Vec<String>>,
+    /// 追加的 TUN 绕过预设
+    #[serde(default)]
+    pub tun_route_presets: Vec<TunRoutePreset>,
+    /// TUN 按进程 / 用户 / 网卡分流的名单
+    #[serde(default)]
+    pub tun_split_rules: TunSplitRules,
+    /// mixed-in / tun-in 之外的额外入站（SOCKS / HTTP / 透明代理 / shadowsocks / VMess）
+    #[serde(default)]
+    pub extra_inbounds: Vec<ExtraInbound>,
+    /// 允许局域网访问时 mixed 入站要求用户名 / 密码（凭据加密保存在通用配置表）
+    #[serde(default = "default_lan_auth_enabled")]
+    pub lan_auth_enabled: bool,
+    /// 允许局域网访问时可连接 mixed 入站的来源网段；为空时不限制来源
+    #[serde(default)]
+    pub lan_allowed_cidrs: Vec<String>,
     pub active_config_path: Option<String>,
     pub installed_kernel_version: Option<String>,
 
@@ -59,6 +79,10 @@ pub struct AppConfig {
     pub tun_self_heal_cooldown_secs: u16,
 }
 
+fn default_lan_auth_enabled() -> bool {
+    true
+}
+
 impl Default for AppConfig {
     fn default() -> Self {
         Self {
@@ -84,6 +108,12 @@ impl Default for AppConfig {
             // 新安装默认关闭：避免首次安装即启用 IPv6 TUN 造成意外行为
             tun_enable_ipv6: false,
             tun_route_exclude_address: None,
+            tun_route_address: None,
+            tun_route_presets: Vec::new(),
+            tun_split_rules: TunSplitRules::default(),
+            extra_inbounds: Vec::new(),
+            lan_auth_enabled: true,
+            lan_allowed_cidrs: Vec::new(),
             active_config_path: None,
             installed_kernel_version: None,
 
@@ -226,4 +256,54 @@ pub struct Subscription {
     pub last_auto_update_error: Option<String>,
     pub last_auto_update_error_type: Option<String>,
     pub last_auto_update_backoff_until: Option<u64>,
+    /// 拉取订阅时使用的出口、请求头等；旧数据没有该字段时取默认值（直连）
+    #[serde(default)]
+    pub fetch_options: SubscriptionFetchOptions,
+}
+
+/// 拉取订阅时的出口
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(
+    export,
+    export_to = "../src/types/generated/SubscriptionFetchDetour.ts"
+)]
+pub enum SubscriptionFetchDetour {
+    /// 不经过代理直接请求
+    #[default]
+    Direct,
+    /// 经 mixed-in 按当前路由规则转发
+    CurrentProxy,
+    /// 经 mixed-in 固定走 `detour_node` 指定的节点
+    Node,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../src/types/generated/SubscriptionHeader.ts")]
+pub struct SubscriptionHeader {
+    pub name: String,
+    pub value: String,
+    /// 读取时无法解密（例如数据目录变化）：`value` 保留密文，拉取订阅时不发送该请求头
+    #[serde(default)]
+    pub unreadable: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(default)]
+#[ts(
+    export,
+    export_to = "../src/types/generated/SubscriptionFetchOptions.ts"
+)]
+pub struct SubscriptionFetchOptions {
+    pub detour: SubscriptionFetchDetour,
+    /// `detour` 为 `node` 时使用的节点 tag
+    pub detour_node: Option<String>,
+    /// 自定义 User-Agent；部分机场按 UA 返回不同格式
+    pub user_agent: Option<String>,
+    /// 额外请求头，例如鉴权 token
+    pub headers: Vec<SubscriptionHeader>,
+    /// 请求超时（秒），为空时使用 30 秒
+    pub timeout_secs: Option<u64>,
+    /// 跳过 TLS 证书校验，仅用于自签证书的自建转换服务
+    pub accept_invalid_certs: bool,
 }
@@ -0,0 +1,90 @@
+//! 流量统计数据模型。
+//!
+//! 设计取舍：
+//! - 按小时、按天两张表分桶累加（`traffic_stats_hourly` / `traffic_stats_daily`），
+//!   月度统计由日表聚合得出，不单独落表；
+//! - 维度（总量/节点/出站组/订阅）作为每行 `dimension` 字段的取值存储，新增维度无需 schema 迁移；
+//! - 保留策略持久化在 `generic_config` 表（key = `RETENTION_STORAGE_KEY`）。
+
+use serde::{Deserialize, Serialize};
+
+/// generic_config 中存储流量统计保留策略所用的 key。
+pub const RETENTION_STORAGE_KEY: &str = "traffic_stats_retention";
+
+/// 统计维度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrafficDimension {
+    /// 全部流量（来自 `/traffic`）
+    Total,
+    /// 具体节点（连接 chains 的第一跳）
+    Node,
+    /// 出站组（连接 chains 的最外层）
+    Group,
+    /// 当前激活的订阅（只统计第一跳为该订阅节点的连接，不含直连与拦截）
+    Subscription,
+}
+
+impl TrafficDimension {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TrafficDimension::Total => "total",
+            TrafficDimension::Node => "node",
+            TrafficDimension::Group => "group",
+            TrafficDimension::Subscription => "subscription",
+        }
+    }
+}
+
+/// 查询粒度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrafficGranularity {
+    Hour,
+    Day,
+    Month,
+}
+
+/// 一次待写入的增量
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrafficDelta {
+    pub dimension: TrafficDimension,
+    pub name: String,
+    pub upload: u64,
+    pub download: u64,
+}
+
+/// 时间桶统计结果
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrafficBucket {
+    /// 桶起始时间（Unix 秒）
+    pub bucket_start: i64,
+    pub name: String,
+    pub upload: i64,
+    pub download: i64,
+}
+
+/// 区间汇总结果（Top-N 排行）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrafficTotal {
+    pub name: String,
+    pub upload: i64,
+    pub download: i64,
+}
+
+/// 保留策略：小时桶保留天数 / 日桶保留天数
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TrafficRetentionPolicy {
+    pub hourly_days: u32,
+    pub daily_days: u32,
+}
+
+impl Default for TrafficRetentionPolicy {
+    fn default() -> Self {
+        Self {
+            hourly_days: 31,
+            daily_days: 730,
+        }
+    }
+}
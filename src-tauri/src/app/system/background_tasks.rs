@@ -5,12 +5,17 @@ use tauri::{AppHandle, Emitter, Manager};
 use tokio::sync::OnceCell;
 use tracing::{error, info, warn};
 
+use crate::app::core::kernel_service::auto_update::start_kernel_update_loop;
 use crate::app::core::kernel_service::status::kernel_check_health;
+use crate::app::core::lan_access::start_lan_address_watch_loop;
+use crate::app::core::proxy_watchdog::start_proxy_watchdog_loop;
+use crate::app::network::mirror_registry::probe_all_mirrors;
 use crate::app::storage::enhanced_storage_service::EnhancedStorageService;
 use crate::app::system::update_service::{check_update, UpdateInfo};
 
 const UPDATE_CHECK_INTERVAL: Duration = Duration::from_secs(4 * 60 * 60); // 4h
 const KERNEL_HEALTH_INTERVAL: Duration = Duration::from_secs(10 * 60); // 10min
+const MIRROR_PROBE_DELAY_SECS: u64 = 30;
 
 pub async fn start_background_tasks(app: &AppHandle) {
     let app_handle = app.clone();
@@ -26,6 +31,24 @@ pub async fn start_background_tasks(app: &AppHandle) {
             error!("后台内核健康检查任务结束，原因: {}", e);
         }
     });
+
+    let app_handle = app.clone();
+    tauri::async_runtime::spawn(start_kernel_update_loop(app_handle));
+
+    let app_handle = app.clone();
+    tauri::async_runtime::spawn(start_proxy_watchdog_loop(app_handle));
+
+    let app_handle = app.clone();
+    tauri::async_runtime::spawn(start_lan_address_watch_loop(app_handle));
+
+    // 启动后稍作延迟再探测下载镜像，避免与首次内核启动抢占网络
+    let app_handle = app.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(Duration::from_secs(MIRROR_PROBE_DELAY_SECS)).await;
+        if let Err(e) = probe_all_mirrors(&app_handle).await {
+            warn!("下载镜像探测失败: {}", e);
+        }
+    });
 }
 
 async fn wait_for_storage(app: &AppHandle) -> Option<Arc<EnhancedStorageService>> {
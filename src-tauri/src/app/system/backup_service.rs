@@ -1,5 +1,9 @@
 use crate::app::constants::paths;
+use crate::app::core::extra_inbounds::{open_extra_inbound_secrets, seal_extra_inbound_secrets};
 use crate::app::core::kernel_auto_manage::auto_manage_with_saved_config;
+use crate::app::network::subscription_service::fetch_options::{
+    open_fetch_header_secrets, seal_fetch_header_secrets,
+};
 use crate::app::storage::enhanced_storage_service::get_enhanced_storage;
 use crate::app::storage::state_model::{
     AppConfig, LocaleConfig, Subscription, ThemeConfig, UpdateConfig, WindowConfig,
@@ -433,6 +437,26 @@ fn write_config_content(path: &Path, content: &str) -> Result<(), String> {
     std::fs::write(path, content).map_err(|e| format!("恢复活动配置文件失败: {}", e))
 }
 
+/// 备份文件中的额外入站密钥与订阅请求头保持加密，只能在同一数据目录下解密恢复
+fn seal_snapshot_secrets(
+    app_config: &mut AppConfig,
+    subscriptions: &mut [Subscription],
+    data_dir: &Path,
+) -> Result<(), String> {
+    seal_extra_inbound_secrets(&mut app_config.extra_inbounds, data_dir)?;
+    seal_fetch_header_secrets(subscriptions, data_dir)
+}
+
+/// 恢复前解密；返回无法解密（例如备份来自其他设备）的字段数
+fn open_snapshot_secrets(
+    app_config: &mut AppConfig,
+    subscriptions: &mut [Subscription],
+    data_dir: &Path,
+) -> usize {
+    open_extra_inbound_secrets(&mut app_config.extra_inbounds, data_dir)
+        + open_fetch_header_secrets(subscriptions, data_dir)
+}
+
 async fn build_snapshot(app: &tauri::AppHandle) -> Result<BackupSnapshot, String> {
     let storage = get_enhanced_storage(app).await?;
     let app_config = storage.get_app_config().await.map_err(|e| e.to_string())?;
@@ -482,6 +506,11 @@ async fn build_snapshot(app: &tauri::AppHandle) -> Result<BackupSnapshot, String
             .as_deref()
             .map(|path| encode_path_for_snapshot(path, SnapshotPathKind::SubscriptionBackup));
     }
+    seal_snapshot_secrets(
+        &mut snapshot_app_config,
+        &mut snapshot_subscriptions,
+        storage.app_data_dir(),
+    )?;
 
     Ok(BackupSnapshot {
         format_version: SNAPSHOT_FORMAT_VERSION,
@@ -531,10 +560,22 @@ async fn apply_snapshot(
         warnings.push("备份中缺少 active_config_path，已回退为默认 config.json".to_string());
     }
     let storage = get_enhanced_storage(app).await?;
-    let (mut app_config, rewritten_subscriptions, rewrite_stats) =
+    let (mut app_config, mut rewritten_subscriptions, rewrite_stats) =
         rewrite_paths_for_snapshot(snapshot);
     warnings.extend(rewrite_stats_warnings(&rewrite_stats, false));
 
+    let dropped_secrets = open_snapshot_secrets(
+        &mut app_config,
+        &mut rewritten_subscriptions,
+        storage.app_data_dir(),
+    );
+    if dropped_secrets > 0 {
+        warnings.push(format!(
+            "备份中有 {} 项密钥无法在本机解密（可能来自其他设备），相关额外入站已停用、订阅请求头拉取时不会发送，请重新填写",
+            dropped_secrets
+        ));
+    }
+
     let mut update_config = snapshot.update_config.clone();
     if update_config.update_channel.is_none() {
         update_config.update_channel = Some("stable".to_string());
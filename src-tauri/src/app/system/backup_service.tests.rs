@@ -22,6 +22,7 @@ fn build_subscription(path: &str) -> Subscription {
         last_auto_update_error: None,
         last_auto_update_error_type: None,
         last_auto_update_backoff_until: None,
+        fetch_options: Default::default(),
     }
 }
 
@@ -74,3 +75,66 @@ fn rewrite_paths_for_snapshot_should_migrate_legacy_absolute_paths() {
     assert!(stats.absolute_rewrites >= 2);
     assert!(stats.active_path_rewritten);
 }
+
+#[test]
+fn snapshot_secrets_should_stay_encrypted_in_backup_file() {
+    use crate::app::core::extra_inbounds::{ExtraInbound, ExtraInboundKind};
+    use crate::app::storage::state_model::SubscriptionHeader;
+
+    let data_dir = std::env::temp_dir().join("backup-snapshot-secret-test");
+    let mut app_config = AppConfig {
+        extra_inbounds: vec![ExtraInbound {
+            kind: ExtraInboundKind::Shadowsocks,
+            tag: "extra-ss".to_string(),
+            enabled: true,
+            listen: None,
+            listen_port: 8388,
+            username: None,
+            password: Some("inbound-secret".to_string()),
+            method: Some("aes-128-gcm".to_string()),
+            uuid: None,
+        }],
+        ..Default::default()
+    };
+    let mut subscription = build_subscription("configs/sub.json");
+    subscription.fetch_options.headers = vec![SubscriptionHeader {
+        name: "Authorization".to_string(),
+        value: "Bearer header-secret".to_string(),
+        unreadable: false,
+    }];
+    let mut subscriptions = vec![subscription];
+
+    seal_snapshot_secrets(&mut app_config, &mut subscriptions, &data_dir).expect("seal");
+    let snapshot = BackupSnapshot {
+        app_config,
+        subscriptions,
+        ..Default::default()
+    };
+    let serialized = serde_json::to_string(&snapshot).expect("serialize");
+    assert!(!serialized.contains("inbound-secret"));
+    assert!(!serialized.contains("header-secret"));
+
+    let (mut app_config, mut subscriptions, _) = rewrite_paths_for_snapshot(&snapshot);
+    assert_eq!(
+        open_snapshot_secrets(&mut app_config, &mut subscriptions, &data_dir),
+        0
+    );
+    assert_eq!(
+        app_config.extra_inbounds[0].password.as_deref(),
+        Some("inbound-secret")
+    );
+    assert_eq!(
+        subscriptions[0].fetch_options.headers[0].value,
+        "Bearer header-secret"
+    );
+
+    // 其他设备（数据目录不同）恢复时无法解密：停用入站，请求头保留密文并标记
+    let (mut app_config, mut subscriptions, _) = rewrite_paths_for_snapshot(&snapshot);
+    assert_eq!(
+        open_snapshot_secrets(&mut app_config, &mut subscriptions, &data_dir.join("other")),
+        2
+    );
+    assert!(!app_config.extra_inbounds[0].enabled);
+    let header = &subscriptions[0].fetch_options.headers[0];
+    assert!(header.unreadable && !header.value.contains("header-secret"));
+}
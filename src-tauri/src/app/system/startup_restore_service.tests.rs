@@ -22,6 +22,7 @@ fn make_subscription(name: &str, config_path: Option<&str>) -> Subscription {
         last_auto_update_error: None,
         last_auto_update_error_type: None,
         last_auto_update_backoff_until: None,
+        fetch_options: Default::default(),
     }
 }
 
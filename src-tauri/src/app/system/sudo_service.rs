@@ -1,7 +1,5 @@
 use serde::Serialize;
 use tauri::AppHandle;
-#[cfg(any(target_os = "linux", target_os = "macos"))]
-use tauri::Manager;
 
 /// 统一给前端/调用方识别的错误码前缀（避免依赖具体文案）。
 /// 约定：Rust 端返回 `SUDO_PASSWORD_REQUIRED` / `SUDO_PASSWORD_INVALID` 等，
@@ -73,78 +71,14 @@ pub async fn sudo_clear_password(_app: AppHandle) -> Result<(), String> {
 
 #[cfg(any(target_os = "linux", target_os = "macos"))]
 use {
-    aes_gcm::{
-        aead::{Aead, KeyInit},
-        Aes256Gcm, Nonce,
-    },
-    base64::engine::general_purpose::STANDARD as BASE64_ENGINE,
-    base64::Engine,
-    rand::RngCore,
-    sha2::{Digest, Sha256},
+    crate::app::storage::secret_cipher::{decrypt_secret, encrypt_secret},
     tracing::warn,
 };
 
 #[cfg(any(target_os = "linux", target_os = "macos"))]
 const SUDO_PASSWORD_KEY: &str = "sudo_password_cipher_v1";
 #[cfg(any(target_os = "linux", target_os = "macos"))]
-const NONCE_LEN: usize = 12;
-
-#[cfg(any(target_os = "linux", target_os = "macos"))]
-fn derive_crypto_key(app: &AppHandle) -> Result<[u8; 32], String> {
-    let data_dir = app
-        .path()
-        .app_data_dir()
-        .map_err(|e| format!("无法定位应用数据目录: {}", e))?;
-
-    let mut hasher = Sha256::new();
-    hasher.update(data_dir.to_string_lossy().as_bytes());
-    hasher.update(b"|sing-box-windows|sudo|v1");
-    let digest = hasher.finalize();
-
-    let mut key = [0u8; 32];
-    key.copy_from_slice(&digest);
-    Ok(key)
-}
-
-#[cfg(any(target_os = "linux", target_os = "macos"))]
-fn encrypt_password(app: &AppHandle, password: &str) -> Result<String, String> {
-    let key = derive_crypto_key(app)?;
-    let cipher = Aes256Gcm::new_from_slice(&key).map_err(|e| format!("初始化加密器失败: {}", e))?;
-
-    let mut nonce_bytes = [0u8; NONCE_LEN];
-    rand::thread_rng().fill_bytes(&mut nonce_bytes);
-    let nonce = Nonce::from_slice(&nonce_bytes);
-
-    let ciphertext = cipher
-        .encrypt(nonce, password.as_bytes())
-        .map_err(|e| format!("加密密码失败: {}", e))?;
-
-    let mut combined = Vec::with_capacity(NONCE_LEN + ciphertext.len());
-    combined.extend_from_slice(&nonce_bytes);
-    combined.extend_from_slice(&ciphertext);
-
-    Ok(BASE64_ENGINE.encode(combined))
-}
-
-#[cfg(any(target_os = "linux", target_os = "macos"))]
-fn decrypt_password(app: &AppHandle, encoded: &str) -> Result<String, String> {
-    let raw = BASE64_ENGINE
-        .decode(encoded)
-        .map_err(|e| format!("解码密码失败: {}", e))?;
-    if raw.len() <= NONCE_LEN {
-        return Err("保存的密码数据已损坏，请重新输入".to_string());
-    }
-
-    let (nonce_bytes, cipher_bytes) = raw.split_at(NONCE_LEN);
-    let key = derive_crypto_key(app)?;
-    let cipher = Aes256Gcm::new_from_slice(&key).map_err(|e| format!("初始化解密器失败: {}", e))?;
-
-    let plaintext = cipher
-        .decrypt(Nonce::from_slice(nonce_bytes), cipher_bytes)
-        .map_err(|e| format!("解密密码失败: {}", e))?;
-
-    String::from_utf8(plaintext).map_err(|e| format!("解密后的密码不是有效 UTF-8: {}", e))
-}
+const SUDO_SECRET_PURPOSE: &str = "sudo";
 
 #[cfg(any(target_os = "linux", target_os = "macos"))]
 async fn has_saved_password(app: &AppHandle) -> Result<bool, String> {
@@ -162,7 +96,7 @@ async fn load_saved_password(app: &AppHandle) -> Result<Option<String>, String>
         .map_err(|e| e.to_string())?;
 
     if let Some(cipher) = cipher {
-        match decrypt_password(app, &cipher) {
+        match decrypt_secret(app, SUDO_SECRET_PURPOSE, &cipher) {
             Ok(pwd) if !pwd.is_empty() => Ok(Some(pwd)),
             Ok(_) => Ok(None),
             Err(err) => {
@@ -201,7 +135,7 @@ async fn load_validated_saved_password(app_handle: &AppHandle) -> Result<String,
 async fn save_password(app: &AppHandle, password: &str) -> Result<(), String> {
     use crate::app::storage::enhanced_storage_service::get_enhanced_storage;
 
-    let cipher = encrypt_password(app, password)?;
+    let cipher = encrypt_secret(app, SUDO_SECRET_PURPOSE, password)?;
     let storage = get_enhanced_storage(app).await?;
     storage
         .save_config(SUDO_PASSWORD_KEY, &cipher)
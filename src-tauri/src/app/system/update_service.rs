@@ -1,4 +1,8 @@
 use crate::app::constants::{api, messages};
+use crate::app::network::release_digest::{
+    fetch_release_asset_digest, parse_asset_digest, parse_release_download_url,
+    record_download_provenance, resolve_expected_digest, DownloadProvenance, DIGEST_UNAVAILABLE,
+};
 use crate::app::network_config;
 use crate::utils::app_util::get_work_dir_sync;
 use semver::Version;
@@ -9,6 +13,8 @@ use std::path::Path;
 use tauri::{Emitter, Manager};
 
 const RELEASES_PAGE_URL: &str = "https://github.com/xinggaoya/sing-box-windows/releases";
+/// 应用内更新只接受本仓库 release 中的安装包
+const APP_RELEASE_REPO: &str = "xinggaoya/sing-box-windows";
 
 // 获取当前平台标识符 - 使用 Rust 标准库，更准确
 fn get_platform_identifier() -> &'static str {
@@ -336,19 +342,21 @@ pub struct UpdateInfo {
     pub release_notes: Option<String>,
     pub release_date: Option<String>,
     pub file_size: Option<u64>,
+    /// GitHub 公布的安装包 SHA-256，用于下载后校验
+    pub sha256: Option<String>,
     pub is_prerelease: bool,
     pub supports_in_app_update: bool,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum UpdateChannel {
+pub(crate) enum UpdateChannel {
     Stable,
     Prerelease,
     Autobuild,
 }
 
 impl UpdateChannel {
-    fn from_inputs(channel: Option<&str>, include_prerelease: bool) -> Self {
+    pub(crate) fn from_inputs(channel: Option<&str>, include_prerelease: bool) -> Self {
         match channel.map(|c| c.trim().to_ascii_lowercase()) {
             Some(ref c) if c == "stable" => Self::Stable,
             Some(ref c) if c == "prerelease" => Self::Prerelease,
@@ -368,7 +376,7 @@ impl UpdateChannel {
     }
 }
 
-fn select_release_by_channel(
+pub(crate) fn select_release_by_channel(
     releases: &[serde_json::Value],
     channel: UpdateChannel,
 ) -> Option<serde_json::Value> {
@@ -507,6 +515,7 @@ pub async fn check_update(
     // 根据当前平台查找对应的安装程序
     let mut download_url = String::new();
     let mut file_size: Option<u64> = None;
+    let mut sha256: Option<String> = None;
     let mut best_priority = 0;
     let supports_in_app_update = supports_in_app_update();
 
@@ -525,6 +534,7 @@ pub async fn check_update(
                     .unwrap_or("")
                     .to_string();
                 file_size = asset["size"].as_u64();
+                sha256 = parse_asset_digest(asset);
                 best_priority = priority;
 
                 // 最高优先级约为 27（基础 20 + 架构 5 + 特殊 2），
@@ -554,6 +564,7 @@ pub async fn check_update(
         release_notes,
         release_date,
         file_size,
+        sha256,
         is_prerelease,
         supports_in_app_update,
     })
@@ -628,11 +639,23 @@ impl PlatformDetailedInfo {
     }
 }
 
+/// 从更新包下载链接解析 `(tag, 文件名)`，只接受本仓库 release 中的资源
+pub(crate) fn resolve_update_asset(download_url: &str) -> Result<(String, String), String> {
+    match parse_release_download_url(download_url) {
+        Some((repo, tag, name)) if repo.eq_ignore_ascii_case(APP_RELEASE_REPO) => Ok((tag, name)),
+        _ => Err(format!(
+            "更新包链接不是 {} 的 release 资源",
+            APP_RELEASE_REPO
+        )),
+    }
+}
+
 // 下载并安装更新
 #[tauri::command]
 pub async fn download_and_install_update(
     app_handle: tauri::AppHandle,
     download_url: String,
+    allow_unverified: Option<bool>,
 ) -> Result<(), String> {
     let window = app_handle
         .get_webview_window("main")
@@ -669,12 +692,40 @@ pub async fn download_and_install_update(
         }),
     );
 
+    // 摘要只从 GitHub API 获取，不信任前端传入的值；拿不到摘要时需用户确认后
+    // 带 allow_unverified 重新调用，摘要不一致则始终拒绝安装
+    let verified_digest = match resolve_update_asset(&download_url) {
+        Ok((tag, name)) => {
+            let lookup = fetch_release_asset_digest(APP_RELEASE_REPO, &tag, &name).await;
+            resolve_expected_digest(lookup, allow_unverified.unwrap_or(false))
+                .map(|digest| (tag, name, digest))
+        }
+        Err(e) => Err(e),
+    };
+    let (release_tag, asset_name, expected_sha256) = match verified_digest {
+        Ok(resolved) => resolved,
+        Err(e) if e.starts_with(DIGEST_UNAVAILABLE) => return Err(e),
+        Err(e) => {
+            let error_msg = format!("{}，已取消更新", e);
+            let _ = window.emit(
+                "update-progress",
+                json!({
+                    "status": "error",
+                    "progress": 0,
+                    "message": error_msg
+                }),
+            );
+            return Err(error_msg);
+        }
+    };
+
     // 下载更新文件
     let window_clone = window.clone();
-    // 使用fallback下载函数
-    if let Err(e) = crate::utils::file_util::download_with_fallback(
+    // 使用fallback下载函数，每个来源下载后都会校验摘要
+    let download_result = crate::utils::file_util::download_verified_with_fallback(
         &download_url,
         download_path.to_str().unwrap_or("<non-utf8-path>"),
+        expected_sha256.as_deref(),
         move |progress| {
             let _ = window_clone.emit(
                 "update-progress",
@@ -686,18 +737,36 @@ pub async fn download_and_install_update(
             );
         },
     )
-    .await
-    {
-        let _ = window.emit(
-            "update-progress",
-            json!({
-                "status": "error",
-                "progress": 0,
-                "message": format!("下载失败: {}", e)
-            }),
-        );
-        return Err(format!("下载更新失败: {}", e));
-    }
+    .await;
+
+    let (mirror_url, actual_sha256) = match download_result {
+        Ok(served) => served,
+        Err(e) => {
+            let _ = window.emit(
+                "update-progress",
+                json!({
+                    "status": "error",
+                    "progress": 0,
+                    "message": format!("下载失败: {}", e)
+                }),
+            );
+            return Err(format!("下载更新失败: {}", e));
+        }
+    };
+
+    record_download_provenance(
+        &app_handle,
+        DownloadProvenance {
+            kind: "app_update".to_string(),
+            asset_name,
+            version: release_tag.trim_start_matches('v').to_string(),
+            mirror_url,
+            sha256: actual_sha256,
+            verified: expected_sha256.is_some(),
+            recorded_at: chrono::Utc::now().timestamp(),
+        },
+    )
+    .await;
 
     // 验证下载的文件
     if !download_path.exists() {
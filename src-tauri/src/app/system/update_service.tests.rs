@@ -253,3 +253,18 @@ fn resolve_release_page_url_should_prefer_html_url() {
         "https://github.com/xinggaoya/sing-box-windows/releases"
     );
 }
+
+#[test]
+fn resolve_update_asset_should_only_accept_app_release_assets() {
+    assert_eq!(
+        resolve_update_asset(
+            "https://gh-proxy.com/https://github.com/xinggaoya/sing-box-windows/releases/download/v2.0.0/app_x64-setup.exe"
+        ),
+        Ok(("v2.0.0".to_string(), "app_x64-setup.exe".to_string()))
+    );
+    assert!(resolve_update_asset(
+        "https://github.com/attacker/sing-box-windows/releases/download/v2.0.0/app_x64-setup.exe"
+    )
+    .is_err());
+    assert!(resolve_update_asset("https://example.com/app_x64-setup.exe").is_err());
+}
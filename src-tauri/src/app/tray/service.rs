@@ -627,7 +627,7 @@ async fn restart_kernel_from_tray(app: &AppHandle) -> Result<(), String> {
 }
 
 async fn apply_system_proxy_toggle_from_tray(app: &AppHandle, enabled: bool) -> Result<(), String> {
-    let result = apply_proxy_settings(app.clone(), Some(enabled), None).await?;
+    let result = apply_proxy_settings(app.clone(), Some(enabled), None, None).await?;
     if !result
         .get("success")
         .and_then(|value| value.as_bool())
@@ -651,7 +651,7 @@ async fn apply_system_proxy_toggle_from_tray(app: &AppHandle, enabled: bool) ->
 
 async fn apply_tun_toggle_from_tray(app: &AppHandle, enabled: bool) -> Result<(), String> {
     if !enabled {
-        let apply_result = apply_proxy_settings(app.clone(), None, Some(false)).await?;
+        let apply_result = apply_proxy_settings(app.clone(), None, Some(false), None).await?;
         if !apply_result
             .get("success")
             .and_then(|value| value.as_bool())
@@ -718,7 +718,7 @@ async fn apply_tun_toggle_from_tray(app: &AppHandle, enabled: bool) -> Result<()
         }
     }
 
-    let apply_result = apply_proxy_settings(app.clone(), None, Some(true)).await?;
+    let apply_result = apply_proxy_settings(app.clone(), None, Some(true), None).await?;
     if !apply_result
         .get("success")
         .and_then(|value| value.as_bool())
@@ -1,3 +1,4 @@
+use crate::app::core::lan_access::LanCredentials;
 use serde::Serialize;
 
 #[derive(Debug, serde::Deserialize, Serialize)]
@@ -26,6 +27,18 @@ pub struct Inbound {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub route_exclude_address: Option<Vec<String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub route_exclude_address_set: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub include_interface: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exclude_interface: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub include_uid: Option<Vec<u32>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exclude_uid: Option<Vec<u32>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub users: Option<Vec<LanCredentials>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub set_system_proxy: Option<bool>,
 }
 
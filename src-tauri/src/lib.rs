@@ -153,6 +153,31 @@ pub fn run() {
                     tracing::warn!("启动时清理内核进程失败: {}", e);
                 }
 
+                // 上次异常退出时未能恢复的桌面代理设置，在这里按快照还原。
+                crate::utils::proxy_util::restore_system_proxy_after_crash();
+
+                // 加载下载镜像注册表，后续配置生成与下载都依赖它解析 GitHub 链接。
+                crate::app::network::mirror_registry::init_mirror_registry(&app_handle).await;
+
+                // 探测已安装内核的版本与构建标签，配置生成据此降级不支持的功能。
+                crate::app::core::kernel_service::capabilities::refresh_kernel_capabilities().await;
+
+                // 局域网访问已开启时预先加载 mixed 入站凭据，配置生成时直接读取缓存。
+                match crate::app::storage::enhanced_storage_service::db_get_app_config(
+                    app_handle.clone(),
+                )
+                .await
+                {
+                    Ok(app_config) => {
+                        crate::app::core::lan_access::refresh_lan_credentials(
+                            &app_handle,
+                            &app_config,
+                        )
+                        .await
+                    }
+                    Err(e) => tracing::warn!("加载局域网代理凭据失败: {}", e),
+                }
+
                 // 应用升级后：尝试刷新当前活动订阅一次，尽量在首次拉起内核前完成配置迁移。
                 crate::app::system::startup_refresh_service::start_upgrade_subscription_refresh(
                     &app_handle,
@@ -180,6 +205,9 @@ pub fn run() {
                     &app_handle,
                 )
                 .await;
+                // 上次开启的局域网订阅服务
+                crate::app::network::subscription_server::restore_subscription_server(&app_handle)
+                    .await;
 
                 if should_start_lightweight {
                     if let Err(err) =
@@ -218,6 +246,14 @@ pub fn run() {
             crate::app::core::kernel_service::download::download_kernel,
             crate::app::core::kernel_service::import::pick_kernel_import_file,
             crate::app::core::kernel_service::import::import_kernel_executable,
+            crate::app::core::kernel_service::kernel_store::list_installed_kernels,
+            crate::app::core::kernel_service::kernel_store::activate_kernel_version,
+            crate::app::core::kernel_service::kernel_store::gc_kernel_versions,
+            crate::app::core::kernel_service::capabilities::get_kernel_capabilities,
+            crate::app::core::kernel_service::auto_update::get_kernel_update_policy,
+            crate::app::core::kernel_service::auto_update::save_kernel_update_policy,
+            crate::app::core::kernel_service::auto_update::get_kernel_update_status,
+            crate::app::core::kernel_service::auto_update::check_kernel_update_now,
             crate::app::core::kernel_service::versioning::get_latest_kernel_version_cmd,
             crate::app::core::kernel_service::versioning::get_kernel_releases_cmd,
             crate::app::core::kernel_service::versioning::check_kernel_version,
@@ -232,15 +268,45 @@ pub fn run() {
             crate::app::core::kernel_service::status::kernel_check_health,
             crate::app::core::kernel_auto_manage::kernel_auto_manage,
             crate::app::core::kernel_service::runtime::apply_proxy_settings,
+            // Core - 可用配置快照与回滚
+            crate::app::core::kernel_service::config_snapshot::list_good_configs,
+            crate::app::core::kernel_service::config_snapshot::restore_good_config,
+            crate::app::core::kernel_service::config_snapshot::set_config_auto_rollback,
+            // Core - 流量统计
+            crate::app::core::traffic_accounting::get_traffic_stats,
+            crate::app::core::traffic_accounting::get_traffic_top,
+            crate::app::core::traffic_accounting::get_traffic_retention,
+            crate::app::core::traffic_accounting::set_traffic_retention,
+            // Core - 进程/域名/出站链连接统计
+            crate::app::core::connection_stats::get_connection_stats,
             // Network - Subscription service commands
             crate::app::network::subscription_service::download_subscription,
+            crate::app::network::subscription_service::update_subscription_fetch_options,
             crate::app::network::subscription_service::add_manual_subscription,
             crate::app::network::subscription_service::get_current_config,
+            crate::app::network::subscription_service::export_node,
+            crate::app::network::subscription_service::export_clash_meta_config,
+            crate::app::network::subscription_server::subscription_server_get_status,
+            crate::app::network::subscription_server::subscription_server_set_settings,
+            crate::app::network::subscription_server::subscription_server_regenerate_token,
             crate::app::network::subscription_service::set_active_config_path,
             crate::app::network::subscription_service::delete_subscription_config,
             crate::app::network::subscription_service::rollback_subscription_config,
             crate::app::network::subscription_service::toggle_proxy_mode,
             crate::app::network::subscription_service::get_current_proxy_mode,
+            // Network - 多目标连通性探测
+            crate::app::network::connectivity_probe::get_connectivity_probe_config,
+            crate::app::network::connectivity_probe::save_connectivity_probe_config,
+            crate::app::network::connectivity_probe::run_connectivity_probe,
+            crate::app::network::connectivity_probe::get_connectivity_probe_history,
+            // Network - 下载校验与来源记录
+            crate::app::network::release_digest::get_download_provenance,
+            // Network - 下载镜像注册表
+            crate::app::network::mirror_registry::get_mirror_registry,
+            crate::app::network::mirror_registry::save_mirror_registry,
+            crate::app::network::mirror_registry::set_config_mirror,
+            crate::app::network::mirror_registry::reset_mirror_registry,
+            crate::app::network::mirror_registry::probe_download_mirrors,
             // System - System service commands
             crate::app::system::system_service::check_admin,
             crate::app::system::system_service::restart_as_admin,
@@ -253,6 +319,10 @@ pub fn run() {
             crate::app::system::sudo_service::sudo_password_status,
             crate::app::system::sudo_service::sudo_set_password,
             crate::app::system::sudo_service::sudo_clear_password,
+            crate::app::core::lan_access::lan_auth_get_credentials,
+            crate::app::core::lan_access::lan_auth_set_credentials,
+            crate::app::core::lan_access::lan_auth_regenerate_credentials,
+            crate::app::core::lan_share::lan_share_get_info,
             // System - Update service commands
             crate::app::system::update_service::check_update,
             crate::app::system::update_service::download_update,
@@ -263,6 +333,7 @@ pub fn run() {
             crate::app::system::config_service::update_singbox_ports,
             // Core - Proxy service commands
             crate::app::core::proxy_service::set_system_proxy,
+            crate::app::core::proxy_service::set_pac_proxy,
             crate::app::core::proxy_service::set_manual_proxy,
             crate::app::core::proxy_service::set_tun_proxy,
             crate::app::core::proxy_service::toggle_ip_version,
@@ -270,6 +341,14 @@ pub fn run() {
             crate::app::core::proxy_service::get_proxies,
             crate::app::core::proxy_service::get_proxy_providers,
             crate::app::core::proxy_service::change_proxy,
+            crate::app::core::proxy_service::close_connections_by_filter,
+            crate::app::core::proxy_service::get_connection_close_policy,
+            crate::app::core::proxy_service::save_connection_close_policy,
+            crate::app::core::proxy_watchdog::get_proxy_watchdog_policy,
+            crate::app::core::proxy_watchdog::save_proxy_watchdog_policy,
+            crate::app::core::proxy_service::get_shell_proxy_targets,
+            crate::app::core::proxy_service::save_shell_proxy_targets,
+            crate::app::core::proxy_service::get_shell_proxy_exports,
             crate::app::core::proxy_service::test_node_delay,
             crate::app::core::proxy_service::test_group_delay,
             crate::app::core::proxy_service::test_nodes_delay,
@@ -298,13 +377,22 @@ pub fn run() {
         ])
         .build(tauri::generate_context!())
         .expect("error while building tauri application")
-        .run(|_, event| {
-            if let RunEvent::ExitRequested { api, .. } = event {
+        .run(|app_handle, event| match event {
+            RunEvent::ExitRequested { api, .. } => {
                 if crate::app::tray::should_prevent_exit() {
                     tracing::info!("主窗口已销毁，保留托盘与后台任务，阻止应用退出");
                     api.prevent_exit();
                 }
             }
+            RunEvent::Exit => {
+                // 退出前把内存中尚未落库的流量增量写入
+                if let Err(e) = tauri::async_runtime::block_on(
+                    crate::app::core::traffic_accounting::flush_pending(app_handle),
+                ) {
+                    tracing::warn!("退出时写入流量统计失败: {}", e);
+                }
+            }
+            _ => {}
         });
 }
 
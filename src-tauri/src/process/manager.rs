@@ -1,6 +1,7 @@
 use super::{ProcessError, Result};
 use crate::app::constants::{messages, paths};
 use crate::app::core::kernel_service::state::KERNEL_STATE;
+use crate::app::core::proxy_watchdog::clear_applied_proxy;
 use crate::utils::proxy_util::disable_system_proxy;
 
 use std::collections::VecDeque;
@@ -853,7 +854,8 @@ impl ProcessManager {
 
     // 停止进程
     pub async fn stop(&self, app_handle: Option<&AppHandle>) -> Result<()> {
-        // 尝试关闭系统代理
+        // 尝试关闭系统代理（先撤销漂移守护的期望值，避免被重新写入）
+        clear_applied_proxy();
         if let Err(e) = disable_system_proxy() {
             warn!("关闭系统代理失败: {}", e);
         } else {
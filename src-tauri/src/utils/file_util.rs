@@ -1,13 +1,32 @@
 use crate::app::constants::{messages, network_config};
-use futures::StreamExt;
+use crate::app::network::mirror_registry::resolve_download_urls;
+use crate::app::network::release_digest::verify_file_digest;
+use crate::utils::resumable_download::{download_resumable, DownloadOptions};
 use std::fs::File;
-use std::io::Write;
 use std::path::Path;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Mutex;
 use tracing::{error, info};
 use zip::ZipArchive;
 
+/// 镜像下载时的并行连接数
+const DOWNLOAD_CONNECTIONS: usize = 4;
+
 // 根据url下载文件到指定位置
 pub async fn download_file<F>(url: String, path: &str, progress_callback: F) -> Result<(), String>
+where
+    F: Fn(u32) + Send + 'static,
+{
+    download_file_with_options(url, path, &DownloadOptions::default(), progress_callback).await
+}
+
+/// 支持断点续传 / 多连接分块的下载，进度以百分比回调
+pub async fn download_file_with_options<F>(
+    url: String,
+    path: &str,
+    options: &DownloadOptions,
+    progress_callback: F,
+) -> Result<(), String>
 where
     F: Fn(u32) + Send + 'static,
 {
@@ -19,91 +38,32 @@ where
         file_path.to_str().unwrap_or("<non-utf8-path>")
     );
 
+    // 只限制连接与读取超时，大文件在慢速网络下也不会因总耗时被中断
     let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(
+        .connect_timeout(std::time::Duration::from_secs(
+            network_config::HTTP_TIMEOUT_SECONDS,
+        ))
+        .read_timeout(std::time::Duration::from_secs(
             network_config::HTTP_TIMEOUT_SECONDS,
         ))
         .no_proxy() // 禁用代理
         .build()
         .map_err(|e| format!("{}: {}", messages::ERR_HTTP_CLIENT_FAILED, e))?;
 
-    let response = client
-        .get(&url)
-        .send()
-        .await
-        .map_err(|e| format!("{}: {}", messages::ERR_REQUEST_FAILED, e))?;
-
-    if !response.status().is_success() {
-        return Err(format!(
-            "{}: {}",
-            messages::ERR_SERVER_ERROR,
-            response.status()
-        ));
-    }
-
-    // 获取文件大小，如果不存在则为0
-    let total_size = response.content_length().unwrap_or(0);
-    let unknown_size = total_size == 0;
-
-    if unknown_size {
-        info!("无法获取文件大小，将使用流式下载");
-    }
-
-    // 创建目录
-    if let Some(parent) = file_path.parent() {
-        if let Err(e) = std::fs::create_dir_all(parent) {
-            error!("{}: {}", messages::ERR_CREATE_DIR_FAILED, e);
-            return Err(format!("{}: {}", messages::ERR_CREATE_DIR_FAILED, e));
-        }
-    }
-
-    // 创建临时文件
-    let temp_path = file_path.with_extension("tmp");
-    let mut file = File::create(&temp_path)
-        .map_err(|e| format!("{}: {}", messages::ERR_CREATE_FILE_FAILED, e))?;
-
-    let mut downloaded = 0u64;
-    let mut stream = response.bytes_stream();
-    let mut last_percent = 0u32;
-    let mut last_progress_update = std::time::Instant::now();
-
-    // 开始下载
-    while let Some(item) = stream.next().await {
-        let chunk = item.map_err(|e| format!("{}: {}", messages::ERR_REQUEST_FAILED, e))?;
-        file.write_all(&chunk)
-            .map_err(|e| format!("{}: {}", messages::ERR_WRITE_FILE_FAILED, e))?;
-
-        downloaded += chunk.len() as u64;
-
-        // 根据是否知道文件大小，使用不同的进度计算方式
-        if unknown_size {
-            // 对于未知大小的文件，每1MB更新一次进度，使用已下载的大小作为进度指示
-            // 下载的越多，进度条增长越慢，给用户一种持续下载的感觉
-            let now = std::time::Instant::now();
-            if now.duration_since(last_progress_update).as_millis() > 500 {
-                // 每500ms更新一次
-                let percent = ((downloaded as f64 / 1_000_000.0).min(100.0)) as u32;
-                if percent != last_percent {
-                    last_percent = percent;
-                    progress_callback(percent);
-                    last_progress_update = now;
-                }
-            }
-        } else {
-            // 已知文件大小，正常计算百分比
-            let percent = ((downloaded as f64 / total_size as f64) * 100.0) as u32;
-            if percent != last_percent {
-                last_percent = percent;
-                progress_callback(percent);
+    let progress_callback = Mutex::new(progress_callback);
+    let last_percent = AtomicU32::new(0);
+    download_resumable(&client, &url, file_path, options, move |progress| {
+        // 未知大小时按已下载的 MB 数推进进度条，给用户持续下载的反馈
+        let percent = progress
+            .percent()
+            .unwrap_or(((progress.downloaded as f64 / 1_000_000.0).min(100.0)) as u32);
+        if last_percent.swap(percent, Ordering::Relaxed) != percent {
+            if let Ok(callback) = progress_callback.lock() {
+                callback(percent);
             }
         }
-    }
-
-    // 完成下载，重命名临时文件
-    std::fs::rename(&temp_path, file_path)
-        .map_err(|e| format!("{}: {}", messages::ERR_WRITE_FILE_FAILED, e))?;
-
-    Ok(())
+    })
+    .await
 }
 
 pub async fn unzip_file(path: &str, to: &str) -> Result<(), String> {
@@ -183,19 +143,61 @@ pub async fn download_with_fallback<F>(
 where
     F: Fn(u32) + Send + Clone + 'static,
 {
-    // 首先尝试通过代理下载 https://gh-proxy.com/https://github.com/...
-    let proxy_url = format!("https://gh-proxy.com/{}", original_url);
-    info!("尝试通过代理下载: {}", proxy_url);
-
-    match download_file(proxy_url, path, progress_callback.clone()).await {
-        Ok(_) => {
-            info!("通过代理下载成功");
-            Ok(())
+    download_verified_with_fallback(original_url, path, None, progress_callback)
+        .await
+        .map(|_| ())
+}
+
+/// 按镜像注册表依次尝试各下载源，每次下载完成后校验 SHA-256，校验失败视为该来源不可用。
+/// 返回 (实际提供文件的 URL, 文件 SHA-256)。`expected_sha256` 为空时只计算不比对。
+pub async fn download_verified_with_fallback<F>(
+    original_url: &str,
+    path: &str,
+    expected_sha256: Option<&str>,
+    progress_callback: F,
+) -> Result<(String, String), String>
+where
+    F: Fn(u32) + Send + Clone + 'static,
+{
+    // 按镜像注册表的顺序依次尝试，默认先走 gh-proxy 加速，最后直连 GitHub
+    let candidates = resolve_download_urls(original_url);
+    // 以原始链接作为续传标识，切换镜像时接着已下载的部分继续
+    let options = DownloadOptions {
+        connections: DOWNLOAD_CONNECTIONS,
+        resume_key: Some(original_url.to_string()),
+    };
+
+    let mut last_error = String::new();
+    for (mirror, url) in candidates.iter() {
+        if !last_error.is_empty() {
+            info!("上一个下载源失败: {}", last_error);
         }
-        Err(e) => {
-            info!("代理下载失败: {}，尝试直接下载", e);
-            // 代理下载失败，尝试直接下载
-            download_file(original_url.to_string(), path, progress_callback).await
+        info!("尝试通过 {} 下载: {}", mirror.name, url);
+
+        let result = match download_file_with_options(
+            url.clone(),
+            path,
+            &options,
+            progress_callback.clone(),
+        )
+        .await
+        {
+            Ok(_) => verify_file_digest(Path::new(path), expected_sha256),
+            Err(e) => Err(e),
+        };
+
+        match result {
+            Ok(sha256) => {
+                info!("下载成功，来源: {}", url);
+                return Ok((url.clone(), sha256));
+            }
+            Err(e) => {
+                let _ = std::fs::remove_file(path);
+                last_error = e;
+            }
         }
     }
+
+    // 全部失败时保留 .part 以便下次续传；若已下载完整但校验失败则不会留下残留
+    Err(last_error)
 }
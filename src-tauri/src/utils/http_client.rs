@@ -1,6 +1,11 @@
+use crate::utils::resumable_download::{download_resumable, DownloadOptions, DownloadProgress};
 use reqwest::Client;
+use std::path::Path;
 use std::time::Duration;
 
+/// 本程序发出请求时默认使用的 User-Agent
+pub const DEFAULT_USER_AGENT: &str = "sing-box-windows/1.0 (sing-box; compatible; Windows NT 10.0)";
+
 /// 全局 HTTP 客户端管理器
 /// 提供高效的连接池和重用机制
 pub struct HttpClientManager {
@@ -23,7 +28,7 @@ impl HttpClientManager {
             .pool_idle_timeout(Duration::from_secs(90))
             .connect_timeout(Duration::from_secs(10))
             .no_proxy()
-            .user_agent("sing-box-windows/1.0 (sing-box; compatible; Windows NT 10.0)")
+            .user_agent(DEFAULT_USER_AGENT)
             .build()
             .expect("创建HTTP客户端失败");
 
@@ -34,7 +39,7 @@ impl HttpClientManager {
             .pool_idle_timeout(Duration::from_secs(60))
             .connect_timeout(Duration::from_secs(5))
             .no_proxy()
-            .user_agent("sing-box-windows/1.0 (sing-box; compatible; Windows NT 10.0)")
+            .user_agent(DEFAULT_USER_AGENT)
             .build()
             .expect("创建代理测试HTTP客户端失败");
 
@@ -54,30 +59,36 @@ impl HttpClientManager {
         &self.proxy_client
     }
 
-    /// 下载文件到指定路径
+    /// 下载文件到指定路径（支持断点续传）
     pub async fn download_file(
         &self,
         url: &str,
         file_path: &str,
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        use futures::StreamExt;
-        use tokio::fs::File;
-        use tokio::io::AsyncWriteExt;
-
-        let response = self.client.get(url).send().await?;
-        response.error_for_status_ref()?;
-
-        let mut file = File::create(file_path).await?;
-
-        let mut stream = response.bytes_stream();
-        while let Some(chunk) = stream.next().await {
-            let chunk = chunk?;
-            file.write_all(&chunk).await?;
-        }
-
-        file.flush().await?;
+        self.download_file_with_progress(url, file_path, &DownloadOptions::default(), |_| {})
+            .await
+    }
 
-        Ok(())
+    /// 下载文件到指定路径，支持多连接分块并回调进度（含速度与剩余时间）
+    pub async fn download_file_with_progress<F>(
+        &self,
+        url: &str,
+        file_path: &str,
+        options: &DownloadOptions,
+        on_progress: F,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>>
+    where
+        F: Fn(DownloadProgress) + Send + Sync,
+    {
+        download_resumable(
+            &self.client,
+            url,
+            Path::new(file_path),
+            options,
+            on_progress,
+        )
+        .await
+        .map_err(Into::into)
     }
 
     /// 获取JSON数据
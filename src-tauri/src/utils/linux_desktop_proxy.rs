@@ -0,0 +1,419 @@
+//! Linux 桌面环境（GNOME / KDE）的系统代理写入与恢复
+//!
+//! 启用代理前把桌面原有的代理设置快照到配置目录，关闭代理时按快照原样恢复；
+//! 快照文件在恢复后删除，因此启动时若发现残留快照，说明上次未正常关闭，需要先恢复一次。
+
+use crate::app::constants::paths;
+use crate::utils::proxy_util::{wildcard_ip_to_cidr, ObservedSystemProxy};
+use serde::{Deserialize, Serialize};
+use std::ffi::OsString;
+use std::io;
+use std::path::PathBuf;
+use std::process::Command;
+use tracing::{info, warn};
+
+const SNAPSHOT_FILE: &str = "desktop_proxy_snapshot.json";
+
+const GNOME_PROXY_SCHEMA: &str = "org.gnome.system.proxy";
+const GNOME_HTTP_SCHEMA: &str = "org.gnome.system.proxy.http";
+const GNOME_HTTPS_SCHEMA: &str = "org.gnome.system.proxy.https";
+/// 启用代理时会改写的 GNOME 设置项
+const GNOME_KEYS: &[(&str, &str)] = &[
+    (GNOME_PROXY_SCHEMA, "mode"),
+    (GNOME_PROXY_SCHEMA, "ignore-hosts"),
+    (GNOME_PROXY_SCHEMA, "autoconfig-url"),
+    (GNOME_HTTP_SCHEMA, "host"),
+    (GNOME_HTTP_SCHEMA, "port"),
+    (GNOME_HTTPS_SCHEMA, "host"),
+    (GNOME_HTTPS_SCHEMA, "port"),
+];
+
+const KDE_FILE: &str = "kioslaverc";
+const KDE_GROUP: &str = "Proxy Settings";
+/// 启用代理时会改写的 KDE 设置项
+const KDE_KEYS: &[&str] = &[
+    "ProxyType",
+    "httpProxy",
+    "httpsProxy",
+    "NoProxyFor",
+    "Proxy Config Script",
+];
+/// (写入工具, 读取工具)，优先 Plasma 6
+const KDE_TOOLS: &[(&str, &str)] = &[
+    ("kwriteconfig6", "kreadconfig6"),
+    ("kwriteconfig5", "kreadconfig5"),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GnomeSetting {
+    pub schema: String,
+    pub key: String,
+    /// `gsettings get` 输出的 GVariant 文本，可直接用于 `gsettings set`
+    pub value: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KdeSetting {
+    pub key: String,
+    /// 为空表示原先未设置该项，恢复时删除
+    pub value: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KdeSnapshot {
+    pub writer: String,
+    pub settings: Vec<KdeSetting>,
+}
+
+/// 启用代理前的桌面代理设置
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DesktopProxySnapshot {
+    #[serde(default)]
+    pub gnome: Vec<GnomeSetting>,
+    #[serde(default)]
+    pub kde: Option<KdeSnapshot>,
+}
+
+/// 把绕过列表转换为 GNOME `ignore-hosts` 的 GVariant 字符串数组
+pub fn gnome_ignore_hosts(entries: &[String]) -> String {
+    let items: Vec<String> = entries
+        .iter()
+        .filter(|entry| entry.as_str() != "<local>")
+        .map(|entry| wildcard_ip_to_cidr(entry).unwrap_or_else(|| entry.clone()))
+        .map(|entry| gvariant_string(&entry))
+        .collect();
+    format!("[{}]", items.join(", "))
+}
+
+/// 把绕过列表转换为 KDE `NoProxyFor`（逗号分隔，域名通配写作 `.example.com`）
+pub fn kde_no_proxy_for(entries: &[String]) -> String {
+    entries
+        .iter()
+        .filter(|entry| entry.as_str() != "<local>")
+        .map(|entry| {
+            wildcard_ip_to_cidr(entry)
+                .or_else(|| entry.strip_prefix('*').map(str::to_string))
+                .unwrap_or_else(|| entry.clone())
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn gvariant_string(value: &str) -> String {
+    format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+/// `gsettings get` 输出的 GVariant 字符串还原为原始文本
+fn gvariant_unquote(value: &str) -> String {
+    let value = value.trim();
+    let inner = value
+        .strip_prefix('\'')
+        .and_then(|rest| rest.strip_suffix('\''))
+        .unwrap_or(value);
+    inner.replace("\\'", "'").replace("\\\\", "\\")
+}
+
+/// KDE 的 `http://host port` 转换为 `host:port`
+fn kde_proxy_server(value: &str) -> Option<String> {
+    let value = value.trim();
+    let address = value.split_once("://").map_or(value, |(_, rest)| rest);
+    let (host, port) = address.rsplit_once(' ')?;
+    Some(format!("{}:{}", host.trim(), port.trim()))
+}
+
+/// 桌面代理操作的执行环境；默认使用系统 PATH 与配置目录下的快照文件
+pub struct DesktopProxyEnv {
+    search_path: Option<OsString>,
+    snapshot_file: PathBuf,
+}
+
+impl DesktopProxyEnv {
+    pub fn system() -> Self {
+        Self {
+            search_path: None,
+            snapshot_file: paths::get_config_dir().join(SNAPSHOT_FILE),
+        }
+    }
+
+    #[cfg(test)]
+    fn with_paths(search_path: OsString, snapshot_file: PathBuf) -> Self {
+        Self {
+            search_path: Some(search_path),
+            snapshot_file,
+        }
+    }
+
+    fn command(&self, program: &str) -> Command {
+        let mut cmd = Command::new(program);
+        if let Some(path) = &self.search_path {
+            cmd.env("PATH", path);
+        }
+        cmd
+    }
+
+    /// 执行命令，成功时返回去掉首尾空白的标准输出；命令不存在或失败返回 `None`
+    fn run(&self, program: &str, args: &[&str]) -> Option<String> {
+        let output = self.command(program).args(args).output().ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+
+    fn gsettings_set(&self, schema: &str, key: &str, value: &str) {
+        if self
+            .run("gsettings", &["set", schema, key, value])
+            .is_none()
+        {
+            warn!("gsettings 写入 {} {} 失败", schema, key);
+        }
+    }
+
+    fn kde_tools(&self) -> Option<(&'static str, &'static str)> {
+        KDE_TOOLS.iter().copied().find(|(_, reader)| {
+            self.run(
+                reader,
+                &[
+                    "--file",
+                    KDE_FILE,
+                    "--group",
+                    KDE_GROUP,
+                    "--key",
+                    "ProxyType",
+                ],
+            )
+            .is_some()
+        })
+    }
+
+    fn kde_write(&self, writer: &str, key: &str, value: &str) {
+        let mut args = vec!["--file", KDE_FILE, "--group", KDE_GROUP, "--key", key];
+        if value.is_empty() {
+            args.push("--delete");
+        } else {
+            args.push(value);
+        }
+        if self.run(writer, &args).is_none() {
+            warn!("{} 写入 {} 失败", writer, key);
+        }
+    }
+
+    fn notify_kde(&self) {
+        let _ = self
+            .command("dbus-send")
+            .args([
+                "--type=signal",
+                "/KIO/Scheduler",
+                "org.kde.KIO.Scheduler.reparseSlaveConfiguration",
+                "string:''",
+            ])
+            .output();
+    }
+
+    /// 读取当前 GNOME / KDE 的代理设置
+    pub fn capture(&self) -> DesktopProxySnapshot {
+        let gnome = GNOME_KEYS
+            .iter()
+            .map_while(|&(schema, key)| {
+                self.run("gsettings", &["get", schema, key])
+                    .map(|value| GnomeSetting {
+                        schema: schema.to_string(),
+                        key: key.to_string(),
+                        value,
+                    })
+            })
+            .collect::<Vec<_>>();
+        // 只要有一项读取失败就视为 GNOME 设置不可用，避免恢复出不完整的状态
+        let gnome = if gnome.len() == GNOME_KEYS.len() {
+            gnome
+        } else {
+            Vec::new()
+        };
+
+        let kde = self.kde_tools().map(|(writer, reader)| KdeSnapshot {
+            writer: writer.to_string(),
+            settings: KDE_KEYS
+                .iter()
+                .map(|&key| KdeSetting {
+                    key: key.to_string(),
+                    value: self
+                        .run(
+                            reader,
+                            &["--file", KDE_FILE, "--group", KDE_GROUP, "--key", key],
+                        )
+                        .unwrap_or_default(),
+                })
+                .collect(),
+        });
+
+        DesktopProxySnapshot { gnome, kde }
+    }
+
+    /// 读回当前桌面生效的代理；GNOME 与 KDE 都不可用时返回 `None`
+    pub fn observe(&self) -> Option<ObservedSystemProxy> {
+        if let Some(mode) = self.run("gsettings", &["get", GNOME_PROXY_SCHEMA, "mode"]) {
+            let get = |schema: &str, key: &str| {
+                self.run("gsettings", &["get", schema, key])
+                    .map(|value| gvariant_unquote(&value))
+                    .unwrap_or_default()
+            };
+            return Some(match gvariant_unquote(&mode).as_str() {
+                "manual" => ObservedSystemProxy {
+                    enabled: true,
+                    server: Some(format!(
+                        "{}:{}",
+                        get(GNOME_HTTP_SCHEMA, "host"),
+                        get(GNOME_HTTP_SCHEMA, "port")
+                    )),
+                    pac_url: None,
+                },
+                "auto" => ObservedSystemProxy {
+                    enabled: false,
+                    server: None,
+                    pac_url: Some(get(GNOME_PROXY_SCHEMA, "autoconfig-url"))
+                        .filter(|url| !url.is_empty()),
+                },
+                _ => ObservedSystemProxy::default(),
+            });
+        }
+
+        let (_, reader) = self.kde_tools()?;
+        let read = |key: &str| {
+            self.run(
+                reader,
+                &["--file", KDE_FILE, "--group", KDE_GROUP, "--key", key],
+            )
+            .unwrap_or_default()
+        };
+        Some(match read("ProxyType").as_str() {
+            "1" => ObservedSystemProxy {
+                enabled: true,
+                server: kde_proxy_server(&read("httpProxy")),
+                pac_url: None,
+            },
+            "2" => ObservedSystemProxy {
+                enabled: false,
+                server: None,
+                pac_url: Some(read("Proxy Config Script")).filter(|url| !url.is_empty()),
+            },
+            _ => ObservedSystemProxy::default(),
+        })
+    }
+
+    fn load_snapshot(&self) -> Option<DesktopProxySnapshot> {
+        let content = std::fs::read_to_string(&self.snapshot_file).ok()?;
+        match serde_json::from_str(&content) {
+            Ok(snapshot) => Some(snapshot),
+            Err(e) => {
+                warn!("桌面代理快照损坏，忽略: {}", e);
+                None
+            }
+        }
+    }
+
+    fn save_snapshot(&self, snapshot: &DesktopProxySnapshot) -> io::Result<()> {
+        if let Some(parent) = self.snapshot_file.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let content = serde_json::to_string_pretty(snapshot)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        std::fs::write(&self.snapshot_file, content)
+    }
+
+    /// 写入桌面代理设置；首次启用时先保存原有设置
+    pub fn enable(&self, host: &str, port: u16, bypass_entries: &[String]) -> io::Result<()> {
+        // 已有快照说明代理处于启用状态（或上次异常退出），此时桌面上已是本程序写入的值，不能覆盖快照
+        if self.load_snapshot().is_none() {
+            self.save_snapshot(&self.capture())?;
+        }
+
+        let port = port.to_string();
+        let host_value = gvariant_string(host);
+        self.gsettings_set(GNOME_HTTP_SCHEMA, "host", &host_value);
+        self.gsettings_set(GNOME_HTTP_SCHEMA, "port", &port);
+        self.gsettings_set(GNOME_HTTPS_SCHEMA, "host", &host_value);
+        self.gsettings_set(GNOME_HTTPS_SCHEMA, "port", &port);
+        self.gsettings_set(
+            GNOME_PROXY_SCHEMA,
+            "ignore-hosts",
+            &gnome_ignore_hosts(bypass_entries),
+        );
+        self.gsettings_set(GNOME_PROXY_SCHEMA, "mode", "'manual'");
+
+        if let Some((writer, _)) = self.kde_tools() {
+            // KDE 的手动代理地址使用空格分隔主机与端口
+            let proxy_url = format!("http://{} {}", host, port);
+            self.kde_write(writer, "httpProxy", &proxy_url);
+            self.kde_write(writer, "httpsProxy", &proxy_url);
+            self.kde_write(writer, "NoProxyFor", &kde_no_proxy_for(bypass_entries));
+            self.kde_write(writer, "ProxyType", "1");
+            self.notify_kde();
+        }
+        Ok(())
+    }
+
+    /// 写入 PAC 自动配置地址；首次启用时先保存原有设置
+    pub fn enable_pac(&self, pac_url: &str) -> io::Result<()> {
+        if self.load_snapshot().is_none() {
+            self.save_snapshot(&self.capture())?;
+        }
+
+        self.gsettings_set(
+            GNOME_PROXY_SCHEMA,
+            "autoconfig-url",
+            &gvariant_string(pac_url),
+        );
+        self.gsettings_set(GNOME_PROXY_SCHEMA, "mode", "'auto'");
+
+        if let Some((writer, _)) = self.kde_tools() {
+            self.kde_write(writer, "Proxy Config Script", pac_url);
+            // KDE 代理类型 2 表示使用自动配置脚本
+            self.kde_write(writer, "ProxyType", "2");
+            self.notify_kde();
+        }
+        Ok(())
+    }
+
+    fn restore(&self, snapshot: &DesktopProxySnapshot) {
+        for setting in &snapshot.gnome {
+            self.gsettings_set(&setting.schema, &setting.key, &setting.value);
+        }
+        if let Some(kde) = &snapshot.kde {
+            for setting in &kde.settings {
+                self.kde_write(&kde.writer, &setting.key, &setting.value);
+            }
+            self.notify_kde();
+        }
+    }
+
+    /// 恢复启用前的桌面代理设置；没有快照时退回到关闭代理
+    pub fn disable(&self) -> io::Result<()> {
+        if self.restore_pending_snapshot() {
+            return Ok(());
+        }
+
+        self.gsettings_set(GNOME_PROXY_SCHEMA, "mode", "'none'");
+        if let Some((writer, _)) = self.kde_tools() {
+            self.kde_write(writer, "ProxyType", "0");
+            self.notify_kde();
+        }
+        Ok(())
+    }
+
+    /// 存在快照时按快照恢复并删除快照，返回是否执行了恢复
+    pub fn restore_pending_snapshot(&self) -> bool {
+        let Some(snapshot) = self.load_snapshot() else {
+            return false;
+        };
+        self.restore(&snapshot);
+        if let Err(e) = std::fs::remove_file(&self.snapshot_file) {
+            warn!("删除桌面代理快照失败: {}", e);
+        }
+        info!("已恢复启用代理前的桌面代理设置");
+        true
+    }
+}
+
+#[cfg(test)]
+#[path = "linux_desktop_proxy.tests.rs"]
+mod tests;
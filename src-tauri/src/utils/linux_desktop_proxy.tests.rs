@@ -0,0 +1,302 @@
+use super::*;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const FAKE_GSETTINGS: &str = r#"#!/bin/sh
+PATH=/usr/bin:/bin
+STATE="$(dirname "$0")/state/gsettings"
+mkdir -p "$STATE"
+file="$STATE/$2.$3"
+case "$1" in
+  get) [ -f "$file" ] || exit 1; cat "$file" ;;
+  set) printf '%s' "$4" > "$file" ;;
+  *) exit 1 ;;
+esac
+"#;
+
+const FAKE_KDE_CONFIG: &str = r#"#!/bin/sh
+PATH=/usr/bin:/bin
+STATE="$(dirname "$0")/state/kde"
+mkdir -p "$STATE"
+key=""; value=""; delete=0
+while [ $# -gt 0 ]; do
+  case "$1" in
+    --file|--group) shift 2 ;;
+    --key) key="$2"; shift 2 ;;
+    --delete) delete=1; shift ;;
+    *) value="$1"; shift ;;
+  esac
+done
+case "$(basename "$0")" in
+  kread*) [ -f "$STATE/$key" ] && cat "$STATE/$key"; exit 0 ;;
+  *) if [ "$delete" = 1 ]; then rm -f "$STATE/$key"; else printf '%s' "$value" > "$STATE/$key"; fi ;;
+esac
+"#;
+
+fn create_temp_dir(label: &str) -> PathBuf {
+    let unique = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("system time should be after epoch")
+        .as_nanos();
+    let dir = std::env::temp_dir().join(format!("sing-box-windows-{label}-{unique}"));
+    std::fs::create_dir_all(&dir).expect("should create temp dir");
+    dir
+}
+
+fn install_script(dir: &Path, name: &str, content: &str) {
+    let path = dir.join(name);
+    std::fs::write(&path, content).expect("should write fake tool");
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))
+        .expect("should mark fake tool executable");
+}
+
+fn seed(dir: &Path, kind: &str, name: &str, value: &str) {
+    let state = dir.join("state").join(kind);
+    std::fs::create_dir_all(&state).expect("should create fake state dir");
+    std::fs::write(state.join(name), value).expect("should seed fake state");
+}
+
+fn read_state(dir: &Path, kind: &str, name: &str) -> Option<String> {
+    std::fs::read_to_string(dir.join("state").join(kind).join(name)).ok()
+}
+
+fn seed_gnome_defaults(dir: &Path) {
+    seed(dir, "gsettings", "org.gnome.system.proxy.mode", "'auto'");
+    seed(
+        dir,
+        "gsettings",
+        "org.gnome.system.proxy.ignore-hosts",
+        "['localhost', '127.0.0.0/8']",
+    );
+    seed(
+        dir,
+        "gsettings",
+        "org.gnome.system.proxy.http.host",
+        "'corp-proxy'",
+    );
+    seed(dir, "gsettings", "org.gnome.system.proxy.http.port", "3128");
+    seed(dir, "gsettings", "org.gnome.system.proxy.https.host", "''");
+    seed(dir, "gsettings", "org.gnome.system.proxy.https.port", "0");
+    seed(
+        dir,
+        "gsettings",
+        "org.gnome.system.proxy.autoconfig-url",
+        "''",
+    );
+}
+
+fn fake_env(dir: &Path) -> DesktopProxyEnv {
+    DesktopProxyEnv::with_paths(dir.as_os_str().to_owned(), dir.join(SNAPSHOT_FILE))
+}
+
+fn bypass() -> Vec<String> {
+    vec![
+        "localhost".to_string(),
+        "192.168.*".to_string(),
+        "*.example.com".to_string(),
+    ]
+}
+
+#[test]
+fn wildcard_ip_to_cidr_should_convert_octet_prefixes() {
+    assert_eq!(wildcard_ip_to_cidr("10.*"), Some("10.0.0.0/8".to_string()));
+    assert_eq!(
+        wildcard_ip_to_cidr("172.16.*"),
+        Some("172.16.0.0/16".to_string())
+    );
+    assert_eq!(
+        wildcard_ip_to_cidr("192.168.1.*"),
+        Some("192.168.1.0/24".to_string())
+    );
+    assert_eq!(wildcard_ip_to_cidr("*.example.com"), None);
+    assert_eq!(wildcard_ip_to_cidr("300.*"), None);
+}
+
+#[test]
+fn bypass_entries_should_use_each_desktop_format() {
+    let mut entries = bypass();
+    entries.push("<local>".to_string());
+    assert_eq!(
+        gnome_ignore_hosts(&entries),
+        "['localhost', '192.168.0.0/16', '*.example.com']"
+    );
+    assert_eq!(
+        kde_no_proxy_for(&entries),
+        "localhost,192.168.0.0/16,.example.com"
+    );
+}
+
+#[test]
+fn gnome_enable_then_disable_should_restore_previous_settings() {
+    let dir = create_temp_dir("gnome-proxy");
+    install_script(&dir, "gsettings", FAKE_GSETTINGS);
+    seed_gnome_defaults(&dir);
+    let env = fake_env(&dir);
+
+    env.enable("127.0.0.1", 7890, &bypass())
+        .expect("enable should succeed");
+    assert_eq!(
+        read_state(&dir, "gsettings", "org.gnome.system.proxy.mode").as_deref(),
+        Some("'manual'")
+    );
+    assert_eq!(
+        read_state(&dir, "gsettings", "org.gnome.system.proxy.ignore-hosts").as_deref(),
+        Some("['localhost', '192.168.0.0/16', '*.example.com']")
+    );
+    assert_eq!(
+        read_state(&dir, "gsettings", "org.gnome.system.proxy.http.port").as_deref(),
+        Some("7890")
+    );
+
+    // 再次启用不应把本程序写入的值当作原有设置
+    env.enable("127.0.0.1", 7891, &bypass())
+        .expect("second enable should succeed");
+
+    env.disable().expect("disable should succeed");
+    assert_eq!(
+        read_state(&dir, "gsettings", "org.gnome.system.proxy.mode").as_deref(),
+        Some("'auto'")
+    );
+    assert_eq!(
+        read_state(&dir, "gsettings", "org.gnome.system.proxy.http.host").as_deref(),
+        Some("'corp-proxy'")
+    );
+    assert_eq!(
+        read_state(&dir, "gsettings", "org.gnome.system.proxy.ignore-hosts").as_deref(),
+        Some("['localhost', '127.0.0.0/8']")
+    );
+    assert!(!dir.join(SNAPSHOT_FILE).exists());
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn kde_snapshot_should_survive_crash_and_restore_on_next_launch() {
+    let dir = create_temp_dir("kde-proxy");
+    install_script(&dir, "kreadconfig5", FAKE_KDE_CONFIG);
+    install_script(&dir, "kwriteconfig5", FAKE_KDE_CONFIG);
+    seed(&dir, "kde", "ProxyType", "0");
+    seed(&dir, "kde", "NoProxyFor", "intranet.local");
+
+    fake_env(&dir)
+        .enable("127.0.0.1", 7890, &bypass())
+        .expect("enable should succeed");
+    assert_eq!(read_state(&dir, "kde", "ProxyType").as_deref(), Some("1"));
+    assert_eq!(
+        read_state(&dir, "kde", "NoProxyFor").as_deref(),
+        Some("localhost,192.168.0.0/16,.example.com")
+    );
+    assert_eq!(
+        read_state(&dir, "kde", "httpProxy").as_deref(),
+        Some("http://127.0.0.1 7890")
+    );
+
+    // 模拟异常退出后重新启动：新的实例读取残留快照并恢复
+    assert!(fake_env(&dir).restore_pending_snapshot());
+    assert_eq!(read_state(&dir, "kde", "ProxyType").as_deref(), Some("0"));
+    assert_eq!(
+        read_state(&dir, "kde", "NoProxyFor").as_deref(),
+        Some("intranet.local")
+    );
+    assert_eq!(read_state(&dir, "kde", "httpProxy"), None);
+    assert!(!fake_env(&dir).restore_pending_snapshot());
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn disable_without_snapshot_should_turn_proxy_off() {
+    let dir = create_temp_dir("desktop-proxy-off");
+    install_script(&dir, "gsettings", FAKE_GSETTINGS);
+    seed_gnome_defaults(&dir);
+    seed(&dir, "gsettings", "org.gnome.system.proxy.mode", "'manual'");
+
+    fake_env(&dir).disable().expect("disable should succeed");
+    assert_eq!(
+        read_state(&dir, "gsettings", "org.gnome.system.proxy.mode").as_deref(),
+        Some("'none'")
+    );
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn pac_mode_should_point_desktop_to_autoconfig_url_and_restore() {
+    let dir = create_temp_dir("desktop-proxy-pac");
+    install_script(&dir, "gsettings", FAKE_GSETTINGS);
+    install_script(&dir, "kreadconfig5", FAKE_KDE_CONFIG);
+    install_script(&dir, "kwriteconfig5", FAKE_KDE_CONFIG);
+    seed_gnome_defaults(&dir);
+    seed(&dir, "kde", "ProxyType", "0");
+    let env = fake_env(&dir);
+
+    env.enable_pac("http://127.0.0.1:34567/proxy.pac")
+        .expect("enable pac should succeed");
+    assert_eq!(
+        read_state(&dir, "gsettings", "org.gnome.system.proxy.mode").as_deref(),
+        Some("'auto'")
+    );
+    assert_eq!(
+        read_state(&dir, "gsettings", "org.gnome.system.proxy.autoconfig-url").as_deref(),
+        Some("'http://127.0.0.1:34567/proxy.pac'")
+    );
+    assert_eq!(read_state(&dir, "kde", "ProxyType").as_deref(), Some("2"));
+    assert_eq!(
+        read_state(&dir, "kde", "Proxy Config Script").as_deref(),
+        Some("http://127.0.0.1:34567/proxy.pac")
+    );
+
+    env.disable().expect("disable should succeed");
+    assert_eq!(
+        read_state(&dir, "gsettings", "org.gnome.system.proxy.mode").as_deref(),
+        Some("'auto'")
+    );
+    assert_eq!(
+        read_state(&dir, "gsettings", "org.gnome.system.proxy.autoconfig-url").as_deref(),
+        Some("''")
+    );
+    assert_eq!(read_state(&dir, "kde", "ProxyType").as_deref(), Some("0"));
+    assert_eq!(read_state(&dir, "kde", "Proxy Config Script"), None);
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn observe_should_read_back_gnome_and_kde_proxy() {
+    let dir = create_temp_dir("desktop-proxy-observe");
+    assert_eq!(fake_env(&dir).observe(), None);
+
+    install_script(&dir, "kreadconfig5", FAKE_KDE_CONFIG);
+    install_script(&dir, "kwriteconfig5", FAKE_KDE_CONFIG);
+    seed(&dir, "kde", "ProxyType", "0");
+    let env = fake_env(&dir);
+    assert_eq!(env.observe(), Some(ObservedSystemProxy::default()));
+
+    env.enable("127.0.0.1", 7890, &bypass())
+        .expect("enable should succeed");
+    assert_eq!(
+        env.observe(),
+        Some(ObservedSystemProxy {
+            enabled: true,
+            server: Some("127.0.0.1:7890".to_string()),
+            pac_url: None,
+        })
+    );
+
+    // 同时存在 GNOME 设置时以 gsettings 为准
+    install_script(&dir, "gsettings", FAKE_GSETTINGS);
+    seed_gnome_defaults(&dir);
+    env.enable_pac("http://127.0.0.1:34567/proxy.pac")
+        .expect("enable pac should succeed");
+    assert_eq!(
+        env.observe(),
+        Some(ObservedSystemProxy {
+            enabled: false,
+            server: None,
+            pac_url: Some("http://127.0.0.1:34567/proxy.pac".to_string()),
+        })
+    );
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -2,6 +2,10 @@ pub mod app_util;
 pub mod config_util;
 pub mod file_util;
 pub mod http_client;
+#[cfg(target_os = "linux")]
+pub mod linux_desktop_proxy;
 pub mod log_util;
 pub mod process_util;
 pub mod proxy_util;
+pub mod resumable_download;
+pub mod shell_proxy;
@@ -1,7 +1,11 @@
+use serde::Serialize;
 use std::io;
 
 #[cfg(target_os = "windows")]
 use crate::app::constants::registry;
+#[cfg(target_os = "linux")]
+use crate::utils::linux_desktop_proxy::DesktopProxyEnv;
+use crate::utils::shell_proxy::remove_shell_proxy_snippets;
 #[cfg(target_os = "windows")]
 use tracing::warn;
 #[cfg(target_os = "windows")]
@@ -14,7 +18,7 @@ pub const DEFAULT_BYPASS_LIST: &str =
     "localhost;127.*;10.*;172.16.*;172.17.*;172.18.*;172.19.*;172.20.*;172.21.*;172.22.*;\
 172.23.*;172.24.*;172.25.*;172.26.*;172.27.*;172.28.*;172.29.*;172.30.*;172.31.*;192.168.*";
 
-fn parse_bypass_entries(raw: Option<&str>) -> Vec<String> {
+pub(crate) fn parse_bypass_entries(raw: Option<&str>) -> Vec<String> {
     let source = raw
         .filter(|value| !value.trim().is_empty())
         .unwrap_or(DEFAULT_BYPASS_LIST);
@@ -27,8 +31,27 @@ fn parse_bypass_entries(raw: Option<&str>) -> Vec<String> {
         .collect()
 }
 
+/// `10.*`、`192.168.*` 这类通配 IP 转换为 CIDR；其他格式返回 `None`
+pub fn wildcard_ip_to_cidr(entry: &str) -> Option<String> {
+    let prefix = entry.strip_suffix(".*")?;
+    let octets: Vec<&str> = prefix.split('.').collect();
+    if octets.is_empty()
+        || octets.len() > 3
+        || octets.iter().any(|octet| octet.parse::<u8>().is_err())
+    {
+        return None;
+    }
+    let mut address: Vec<&str> = octets.clone();
+    address.resize(4, "0");
+    Some(format!("{}/{}", address.join("."), octets.len() * 8))
+}
+
 /// 禁用系统代理 (跨平台实现)
+///
+/// 同时清理写入终端与命令行工具配置中的代理片段。
 pub fn disable_system_proxy() -> io::Result<()> {
+    remove_shell_proxy_snippets();
+
     #[cfg(target_os = "windows")]
     {
         disable_system_proxy_windows()
@@ -50,6 +73,87 @@ pub fn disable_system_proxy() -> io::Result<()> {
     }
 }
 
+/// 启用 PAC 模式：把系统的自动配置脚本地址指向 `pac_url` (跨平台实现)
+pub fn enable_system_proxy_pac(pac_url: &str) -> io::Result<()> {
+    #[cfg(target_os = "windows")]
+    {
+        enable_system_proxy_pac_windows(pac_url)
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        DesktopProxyEnv::system().enable_pac(pac_url)
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        enable_system_proxy_pac_macos(pac_url)
+    }
+
+    #[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "macos")))]
+    {
+        let _ = pac_url;
+        Ok(()) // 其他平台暂时不执行任何操作
+    }
+}
+
+/// 恢复上次异常退出时残留的系统代理设置（启动时调用）
+///
+/// 目前仅 Linux 桌面会在启用代理前保存原有设置，其他平台无需处理；
+/// 终端代理片段指向的端口已失效，所有平台都需要清理。
+pub fn restore_system_proxy_after_crash() {
+    remove_shell_proxy_snippets();
+
+    #[cfg(target_os = "linux")]
+    {
+        DesktopProxyEnv::system().restore_pending_snapshot();
+    }
+}
+
+/// 从系统读回的代理设置
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ObservedSystemProxy {
+    /// 固定代理是否启用
+    pub enabled: bool,
+    /// 固定代理地址（`host:port`）
+    pub server: Option<String>,
+    /// 生效中的 PAC 地址
+    pub pac_url: Option<String>,
+}
+
+/// 读回当前生效的系统代理 (跨平台实现)
+///
+/// 当前平台或桌面环境无法读取时返回 `Ok(None)`，调用方应视为“未知”而不是“已关闭”。
+pub fn read_system_proxy() -> io::Result<Option<ObservedSystemProxy>> {
+    #[cfg(target_os = "windows")]
+    {
+        read_system_proxy_windows().map(Some)
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        Ok(DesktopProxyEnv::system().observe())
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        let output = std::process::Command::new("scutil")
+            .arg("--proxy")
+            .output()?;
+        if !output.status.success() {
+            return Ok(None);
+        }
+        Ok(Some(parse_scutil_proxy(&String::from_utf8_lossy(
+            &output.stdout,
+        ))))
+    }
+
+    #[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "macos")))]
+    {
+        Ok(None)
+    }
+}
+
 /// 启用系统代理 (跨平台实现)
 pub fn enable_system_proxy(host: &str, port: u16, bypass: Option<&str>) -> io::Result<()> {
     #[cfg(target_os = "windows")]
@@ -84,12 +188,40 @@ fn disable_system_proxy_windows() -> io::Result<()> {
     // 清空代理服务器地址
     settings.set_value(registry::PROXY_SERVER, &"")?;
 
+    // 清除 PAC 地址（值不存在时忽略）
+    let _ = settings.delete_value(registry::AUTO_CONFIG_URL);
+
     // 通知 WinINet 重新读取设置，使基于 WinINet 的应用（Edge/Chrome/IE 等）立即生效。
     notify_wininet_change();
 
     Ok(())
 }
 
+#[cfg(target_os = "windows")]
+fn read_system_proxy_windows() -> io::Result<ObservedSystemProxy> {
+    let hkcu = RegKey::predef(HKEY_CURRENT_USER);
+    let settings = hkcu.open_subkey_with_flags(registry::INTERNET_SETTINGS, KEY_READ)?;
+
+    let enabled = settings
+        .get_value::<u32, _>(registry::PROXY_ENABLE)
+        .unwrap_or(0)
+        == 1;
+    let server = settings
+        .get_value::<String, _>(registry::PROXY_SERVER)
+        .ok()
+        .filter(|value| !value.trim().is_empty());
+    let pac_url = settings
+        .get_value::<String, _>(registry::AUTO_CONFIG_URL)
+        .ok()
+        .filter(|value| !value.trim().is_empty());
+
+    Ok(ObservedSystemProxy {
+        enabled,
+        server,
+        pac_url,
+    })
+}
+
 #[cfg(target_os = "linux")]
 fn disable_system_proxy_linux() -> io::Result<()> {
     // Linux下的系统代理设置通常通过环境变量
@@ -103,63 +235,8 @@ fn disable_system_proxy_linux() -> io::Result<()> {
     std::env::remove_var("no_proxy");
     std::env::remove_var("NO_PROXY");
 
-    // 尝试使用gsettings重置代理设置 (GNOME/Unity/XFCE等)
-    if std::process::Command::new("which")
-        .arg("gsettings")
-        .output()
-        .is_ok()
-    {
-        let _ = std::process::Command::new("gsettings")
-            .args(["set", "org.gnome.system.proxy.http", "host", "''"])
-            .output();
-        let _ = std::process::Command::new("gsettings")
-            .args(["set", "org.gnome.system.proxy.http", "port", "0"])
-            .output();
-        let _ = std::process::Command::new("gsettings")
-            .args(["set", "org.gnome.system.proxy.https", "host", "''"])
-            .output();
-        let _ = std::process::Command::new("gsettings")
-            .args(["set", "org.gnome.system.proxy.https", "port", "0"])
-            .output();
-        let _ = std::process::Command::new("gsettings")
-            .args(["set", "org.gnome.system.proxy", "mode", "'none'"])
-            .output();
-    }
-
-    // 尝试使用kwriteconfig5/6重置代理设置 (KDE Plasma)
-    for kwriteconfig in &["kwriteconfig6", "kwriteconfig5"] {
-        if std::process::Command::new("which")
-            .arg(kwriteconfig)
-            .output()
-            .is_ok()
-        {
-            // 设置代理模式为无代理 (0)
-            let _ = std::process::Command::new(kwriteconfig)
-                .args([
-                    "--file",
-                    "kioslaverc",
-                    "--group",
-                    "Proxy Settings",
-                    "--key",
-                    "ProxyType",
-                    "0",
-                ])
-                .output();
-
-            // 通知KDE配置已更改
-            let _ = std::process::Command::new("dbus-send")
-                .args([
-                    "--type=signal",
-                    "/KIO/Scheduler",
-                    "org.kde.KIO.Scheduler.reparseSlaveConfiguration",
-                    "string:''",
-                ])
-                .output();
-            break;
-        }
-    }
-
-    Ok(())
+    // 恢复启用前的 GNOME / KDE 代理设置（没有快照时关闭桌面代理）
+    DesktopProxyEnv::system().disable()
 }
 
 #[cfg(target_os = "macos")]
@@ -191,6 +268,11 @@ fn disable_system_proxy_macos() -> io::Result<()> {
                 let _ = std::process::Command::new("networksetup")
                     .args(["-setsocksfirewallproxystate", service, "off"])
                     .output();
+
+                // 禁用自动代理（PAC）
+                let _ = std::process::Command::new("networksetup")
+                    .args(["-setautoproxystate", service, "off"])
+                    .output();
             }
         }
     }
@@ -218,6 +300,9 @@ fn enable_system_proxy_windows(host: &str, port: u16, bypass: Option<&str>) -> i
     // 启用代理
     settings.set_value(registry::PROXY_ENABLE, &1u32)?;
 
+    // 全局代理与 PAC 互斥，避免系统优先使用旧的自动配置脚本
+    let _ = settings.delete_value(registry::AUTO_CONFIG_URL);
+
     // 设置绕过本地地址
     let entries = parse_bypass_entries(bypass);
     let override_value = if entries.is_empty() {
@@ -233,6 +318,20 @@ fn enable_system_proxy_windows(host: &str, port: u16, bypass: Option<&str>) -> i
     Ok(())
 }
 
+#[cfg(target_os = "windows")]
+fn enable_system_proxy_pac_windows(pac_url: &str) -> io::Result<()> {
+    let hkcu = RegKey::predef(HKEY_CURRENT_USER);
+    let settings = hkcu.open_subkey_with_flags(registry::INTERNET_SETTINGS, KEY_WRITE)?;
+
+    // PAC 模式下关闭固定代理，由自动配置脚本决定每个请求的去向
+    settings.set_value(registry::PROXY_ENABLE, &0u32)?;
+    settings.set_value(registry::AUTO_CONFIG_URL, &pac_url)?;
+
+    notify_wininet_change();
+
+    Ok(())
+}
+
 /// 通知 WinINet 配置已变更并刷新，使系统代理设置即时生效。
 ///
 /// 仅写注册表而不调用本函数时，基于 WinINet 的应用（Edge/Chrome/IE/资源管理器等）
@@ -297,97 +396,72 @@ fn enable_system_proxy_linux(host: &str, port: u16, bypass: Option<&str>) -> io:
     std::env::set_var("no_proxy", &no_proxy);
     std::env::set_var("NO_PROXY", &no_proxy);
 
-    // 尝试使用gsettings设置代理 (GNOME/Unity/XFCE等)
-    if std::process::Command::new("which")
-        .arg("gsettings")
-        .output()
-        .is_ok()
-    {
-        let _ = std::process::Command::new("gsettings")
-            .args(["set", "org.gnome.system.proxy.http", "host", host])
-            .output();
-        let _ = std::process::Command::new("gsettings")
-            .args([
-                "set",
-                "org.gnome.system.proxy.http",
-                "port",
-                &port.to_string(),
-            ])
-            .output();
-        let _ = std::process::Command::new("gsettings")
-            .args(["set", "org.gnome.system.proxy.https", "host", host])
-            .output();
-        let _ = std::process::Command::new("gsettings")
-            .args([
-                "set",
-                "org.gnome.system.proxy.https",
-                "port",
-                &port.to_string(),
-            ])
-            .output();
-        let _ = std::process::Command::new("gsettings")
-            .args(["set", "org.gnome.system.proxy", "mode", "'manual'"])
-            .output();
+    // 写入 GNOME / KDE 桌面代理（含绕过列表），首次启用时会先保存原有设置
+    let entries = if entries.is_empty() {
+        parse_bypass_entries(None)
+    } else {
+        entries
+    };
+    DesktopProxyEnv::system().enable(host, port, &entries)
+}
+
+/// 解析 `scutil --proxy` 输出（主网络服务上实际生效的代理）
+#[cfg(any(target_os = "macos", test))]
+fn parse_scutil_proxy(output: &str) -> ObservedSystemProxy {
+    let value_of = |name: &str| {
+        output.lines().find_map(|line| {
+            let (key, value) = line.split_once(" : ")?;
+            (key.trim() == name).then(|| value.trim().to_string())
+        })
+    };
+
+    let enabled = value_of("HTTPEnable").as_deref() == Some("1");
+    let server = match (value_of("HTTPProxy"), value_of("HTTPPort")) {
+        (Some(host), Some(port)) if enabled => Some(format!("{}:{}", host, port)),
+        _ => None,
+    };
+    let pac_url = if value_of("ProxyAutoConfigEnable").as_deref() == Some("1") {
+        value_of("ProxyAutoConfigURLString")
+    } else {
+        None
+    };
+
+    ObservedSystemProxy {
+        enabled,
+        server,
+        pac_url,
     }
+}
+
+#[cfg(target_os = "macos")]
+fn enable_system_proxy_pac_macos(pac_url: &str) -> io::Result<()> {
+    let output = std::process::Command::new("networksetup")
+        .args(["-listallnetworkservices"])
+        .output()?;
+
+    if output.status.success() {
+        let services = String::from_utf8_lossy(&output.stdout);
+
+        // 跳过第一行（标题行），处理每个网络服务
+        for line in services.lines().skip(1) {
+            let service = line.trim();
+            if !service.is_empty() && service != "*" {
+                // PAC 模式下关闭固定代理，由自动配置脚本决定每个请求的去向
+                let _ = std::process::Command::new("networksetup")
+                    .args(["-setwebproxystate", service, "off"])
+                    .output();
+                let _ = std::process::Command::new("networksetup")
+                    .args(["-setsecurewebproxystate", service, "off"])
+                    .output();
 
-    // 尝试使用kwriteconfig5/6设置代理 (KDE Plasma)
-    for kwriteconfig in &["kwriteconfig6", "kwriteconfig5"] {
-        if std::process::Command::new("which")
-            .arg(kwriteconfig)
-            .output()
-            .is_ok()
-        {
-            let proxy_url = format!("http://{}:{}", host, port);
-
-            // 设置HTTP代理
-            let _ = std::process::Command::new(kwriteconfig)
-                .args([
-                    "--file",
-                    "kioslaverc",
-                    "--group",
-                    "Proxy Settings",
-                    "--key",
-                    "httpProxy",
-                    &proxy_url,
-                ])
-                .output();
-
-            // 设置HTTPS代理
-            let _ = std::process::Command::new(kwriteconfig)
-                .args([
-                    "--file",
-                    "kioslaverc",
-                    "--group",
-                    "Proxy Settings",
-                    "--key",
-                    "httpsProxy",
-                    &proxy_url,
-                ])
-                .output();
-
-            // 设置代理模式为手动 (1)
-            let _ = std::process::Command::new(kwriteconfig)
-                .args([
-                    "--file",
-                    "kioslaverc",
-                    "--group",
-                    "Proxy Settings",
-                    "--key",
-                    "ProxyType",
-                    "1",
-                ])
-                .output();
-
-            // 通知KDE配置已更改
-            let _ = std::process::Command::new("dbus-send")
-                .args([
-                    "--type=signal",
-                    "/KIO/Scheduler",
-                    "org.kde.KIO.Scheduler.reparseSlaveConfiguration",
-                    "string:''",
-                ])
-                .output();
-            break;
+                // 设置并启用自动代理地址
+                let _ = std::process::Command::new("networksetup")
+                    .args(["-setautoproxyurl", service, pac_url])
+                    .output();
+                let _ = std::process::Command::new("networksetup")
+                    .args(["-setautoproxystate", service, "on"])
+                    .output();
+            }
         }
     }
 
@@ -429,6 +503,11 @@ fn enable_system_proxy_macos(host: &str, port: u16, bypass: Option<&str>) -> io:
                     .args(["-setsecurewebproxystate", service, "on"])
                     .output();
 
+                // 全局代理与 PAC 互斥
+                let _ = std::process::Command::new("networksetup")
+                    .args(["-setautoproxystate", service, "off"])
+                    .output();
+
                 // 设置代理绕过列表
                 if !entries.is_empty() {
                     let mut cmd = std::process::Command::new("networksetup");
@@ -455,3 +534,7 @@ fn enable_system_proxy_macos(host: &str, port: u16, bypass: Option<&str>) -> io:
 
     Ok(())
 }
+
+#[cfg(test)]
+#[path = "proxy_util.tests.rs"]
+mod tests;
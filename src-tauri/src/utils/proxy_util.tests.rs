@@ -0,0 +1,56 @@
+use super::*;
+
+#[test]
+fn parse_bypass_entries_should_fall_back_to_default_list() {
+    let entries = parse_bypass_entries(Some("  "));
+    assert_eq!(entries.first().map(String::as_str), Some("localhost"));
+    assert!(entries.iter().any(|entry| entry == "192.168.*"));
+
+    assert_eq!(
+        parse_bypass_entries(Some("a.com; b.com,\nc.com")),
+        vec!["a.com", "b.com", "c.com"]
+    );
+}
+
+#[test]
+fn parse_scutil_proxy_should_read_manual_proxy() {
+    let output = r#"<dictionary> {
+  ExceptionsList : <array> {
+    0 : localhost
+  }
+  HTTPEnable : 1
+  HTTPPort : 7890
+  HTTPProxy : 127.0.0.1
+  HTTPSEnable : 1
+  ProxyAutoConfigEnable : 0
+}
+"#;
+    assert_eq!(
+        parse_scutil_proxy(output),
+        ObservedSystemProxy {
+            enabled: true,
+            server: Some("127.0.0.1:7890".to_string()),
+            pac_url: None,
+        }
+    );
+}
+
+#[test]
+fn parse_scutil_proxy_should_read_pac_and_disabled_proxy() {
+    let output = r#"<dictionary> {
+  HTTPEnable : 0
+  HTTPPort : 8080
+  HTTPProxy : corp-proxy
+  ProxyAutoConfigEnable : 1
+  ProxyAutoConfigURLString : http://127.0.0.1:34567/proxy.pac
+}
+"#;
+    assert_eq!(
+        parse_scutil_proxy(output),
+        ObservedSystemProxy {
+            enabled: false,
+            server: None,
+            pac_url: Some("http://127.0.0.1:34567/proxy.pac".to_string()),
+        }
+    );
+}
@@ -0,0 +1,550 @@
+//! 可断点续传的分块下载
+//!
+//! 下载内容先写入 `<目标文件>.part`，分块进度记录在 `<目标文件>.part.json`：
+//! - 服务器支持 Range 时按块续传，切换镜像或重启应用后从已下载位置继续；
+//! - 文件较大时可开启多连接并行下载各块；
+//! - 服务器不支持 Range 或无法获知文件大小时，退化为普通单连接下载。
+//!
+//! 续传依据是调用方给出的 `resume_key`（通常为 GitHub 原始链接）与文件总大小，
+//! 因此不同镜像之间可以接力；最终完整性由调用方的摘要校验兜底。
+
+use crate::app::constants::messages;
+use futures::future::try_join_all;
+use futures::StreamExt;
+use reqwest::header::{CONTENT_RANGE, RANGE};
+use reqwest::{Client, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::io::SeekFrom;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncSeekExt, AsyncWriteExt};
+use tracing::{info, warn};
+
+/// 小于该大小的文件不拆分并行下载
+const MIN_PARALLEL_SIZE: u64 = 4 * 1024 * 1024;
+/// 每个块至少写入这么多字节才刷新一次元数据
+const META_SAVE_INTERVAL_BYTES: u64 = 512 * 1024;
+/// 进度回调的最小间隔
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(300);
+/// 速度统计的平滑系数（指数滑动平均）
+const SPEED_SMOOTHING: f64 = 0.3;
+
+/// 下载参数
+#[derive(Debug, Clone)]
+pub struct DownloadOptions {
+    /// 并行连接数（仅在服务器支持 Range 且文件足够大时生效）
+    pub connections: usize,
+    /// 续传标识；相同标识且文件大小一致时复用已下载的部分。为空时使用下载链接
+    pub resume_key: Option<String>,
+}
+
+impl Default for DownloadOptions {
+    fn default() -> Self {
+        Self {
+            connections: 1,
+            resume_key: None,
+        }
+    }
+}
+
+/// 下载进度快照
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct DownloadProgress {
+    pub downloaded: u64,
+    /// 文件总大小，未知时为 None
+    pub total: Option<u64>,
+    /// 平滑后的下载速度（字节/秒）
+    pub speed_bps: u64,
+    /// 预计剩余秒数，总大小或速度未知时为 None
+    pub eta_secs: Option<u64>,
+}
+
+impl DownloadProgress {
+    /// 百分比（0-100），总大小未知时为 None
+    pub fn percent(&self) -> Option<u32> {
+        self.total
+            .filter(|total| *total > 0)
+            .map(|total| ((self.downloaded.min(total) * 100) / total) as u32)
+    }
+
+    /// 形如 `6.0 MB / 20.0 MB，3.2 MB/s，剩余 5 秒` 的简短描述
+    pub fn describe(&self) -> String {
+        let mut text = format!(
+            "{} / {}，{}/s",
+            format_bytes(self.downloaded),
+            self.total
+                .map(format_bytes)
+                .unwrap_or_else(|| "未知大小".to_string()),
+            format_bytes(self.speed_bps)
+        );
+        if let Some(eta) = self.eta_secs {
+            text.push_str(&format!("，剩余 {} 秒", eta));
+        }
+        text
+    }
+}
+
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+/// 单个分块的状态，`end` 为闭区间
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChunkState {
+    pub start: u64,
+    pub end: u64,
+    pub downloaded: u64,
+}
+
+impl ChunkState {
+    fn len(&self) -> u64 {
+        self.end - self.start + 1
+    }
+
+    fn is_complete(&self) -> bool {
+        self.downloaded >= self.len()
+    }
+}
+
+/// `.part.json` 中保存的续传元数据
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PartMeta {
+    pub resume_key: String,
+    pub total_size: u64,
+    pub chunks: Vec<ChunkState>,
+}
+
+impl PartMeta {
+    fn downloaded(&self) -> u64 {
+        self.chunks.iter().map(|c| c.downloaded).sum()
+    }
+
+    /// 判断已有元数据能否用于本次下载
+    fn is_resumable_for(&self, resume_key: &str, total_size: u64) -> bool {
+        self.resume_key == resume_key
+            && self.total_size == total_size
+            && !self.chunks.is_empty()
+            && self
+                .chunks
+                .iter()
+                .all(|c| c.start <= c.end && c.end < total_size && c.downloaded <= c.len())
+    }
+}
+
+/// 把 `[0, total)` 均分为不超过 `connections` 个块
+pub fn plan_chunks(total_size: u64, connections: usize) -> Vec<ChunkState> {
+    if total_size == 0 {
+        return Vec::new();
+    }
+    let count = if total_size < MIN_PARALLEL_SIZE {
+        1
+    } else {
+        connections.clamp(1, 16) as u64
+    };
+    let chunk_size = total_size.div_ceil(count);
+    (0..count)
+        .map(|i| i * chunk_size)
+        .take_while(|start| *start < total_size)
+        .map(|start| ChunkState {
+            start,
+            end: (start + chunk_size).min(total_size) - 1,
+            downloaded: 0,
+        })
+        .collect()
+}
+
+/// 解析 `Content-Range: bytes 0-0/12345` 中的总大小
+pub fn parse_content_range_total(value: &str) -> Option<u64> {
+    let rest = value.trim().strip_prefix("bytes")?.trim();
+    let (_, total) = rest.split_once('/')?;
+    total.trim().parse().ok()
+}
+
+pub fn part_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".part");
+    PathBuf::from(name)
+}
+
+pub fn meta_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".part.json");
+    PathBuf::from(name)
+}
+
+/// 删除续传残留（摘要校验失败等场景需要从头下载）
+pub fn discard_partial(path: &Path) {
+    let _ = std::fs::remove_file(part_path(path));
+    let _ = std::fs::remove_file(meta_path(path));
+}
+
+fn load_meta(path: &Path) -> Option<PartMeta> {
+    let content = std::fs::read_to_string(meta_path(path)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+fn save_meta(path: &Path, meta: &PartMeta) -> Result<(), String> {
+    let content = serde_json::to_string(meta)
+        .map_err(|e| format!("{}: {}", messages::ERR_WRITE_FILE_FAILED, e))?;
+    std::fs::write(meta_path(path), content)
+        .map_err(|e| format!("{}: {}", messages::ERR_WRITE_FILE_FAILED, e))
+}
+
+/// 进度统计：累计字节数 + 平滑速度 + 节流回调
+struct ProgressTracker<F> {
+    downloaded: AtomicU64,
+    total: Option<u64>,
+    state: Mutex<SpeedState>,
+    callback: F,
+}
+
+struct SpeedState {
+    last_at: Instant,
+    last_bytes: u64,
+    speed_bps: f64,
+}
+
+impl<F> ProgressTracker<F>
+where
+    F: Fn(DownloadProgress) + Send + Sync,
+{
+    fn new(initial: u64, total: Option<u64>, callback: F) -> Self {
+        Self {
+            downloaded: AtomicU64::new(initial),
+            total,
+            state: Mutex::new(SpeedState {
+                last_at: Instant::now(),
+                last_bytes: initial,
+                speed_bps: 0.0,
+            }),
+            callback,
+        }
+    }
+
+    fn add(&self, bytes: u64) {
+        let downloaded = self.downloaded.fetch_add(bytes, Ordering::Relaxed) + bytes;
+        let snapshot = {
+            let Ok(mut state) = self.state.lock() else {
+                return;
+            };
+            let elapsed = state.last_at.elapsed();
+            if elapsed < PROGRESS_INTERVAL {
+                return;
+            }
+            let instant =
+                downloaded.saturating_sub(state.last_bytes) as f64 / elapsed.as_secs_f64();
+            state.speed_bps = if state.speed_bps == 0.0 {
+                instant
+            } else {
+                state.speed_bps * (1.0 - SPEED_SMOOTHING) + instant * SPEED_SMOOTHING
+            };
+            state.last_at = Instant::now();
+            state.last_bytes = state.last_bytes.max(downloaded);
+            self.snapshot(downloaded, state.speed_bps)
+        };
+        (self.callback)(snapshot);
+    }
+
+    fn snapshot(&self, downloaded: u64, speed_bps: f64) -> DownloadProgress {
+        let speed = speed_bps.max(0.0) as u64;
+        DownloadProgress {
+            downloaded,
+            total: self.total,
+            speed_bps: speed,
+            eta_secs: self
+                .total
+                .filter(|_| speed > 0)
+                .map(|total| total.saturating_sub(downloaded) / speed),
+        }
+    }
+
+    fn finish(&self) {
+        let downloaded = self.downloaded.load(Ordering::Relaxed);
+        let speed = self.state.lock().map(|s| s.speed_bps).unwrap_or(0.0);
+        let mut snapshot = self.snapshot(downloaded, speed);
+        snapshot.eta_secs = Some(0);
+        (self.callback)(snapshot);
+    }
+}
+
+/// 探测结果：文件总大小与是否支持 Range
+struct ProbeResult {
+    total_size: Option<u64>,
+    accepts_ranges: bool,
+}
+
+async fn probe(client: &Client, url: &str) -> Result<ProbeResult, String> {
+    let response = client
+        .get(url)
+        .header(RANGE, "bytes=0-0")
+        .send()
+        .await
+        .map_err(|e| format!("{}: {}", messages::ERR_REQUEST_FAILED, e))?;
+
+    match response.status() {
+        StatusCode::PARTIAL_CONTENT => Ok(ProbeResult {
+            total_size: response
+                .headers()
+                .get(CONTENT_RANGE)
+                .and_then(|v| v.to_str().ok())
+                .and_then(parse_content_range_total),
+            accepts_ranges: true,
+        }),
+        status if status.is_success() => Ok(ProbeResult {
+            total_size: response.content_length().filter(|len| *len > 0),
+            accepts_ranges: false,
+        }),
+        status => Err(format!("{}: {}", messages::ERR_SERVER_ERROR, status)),
+    }
+}
+
+/// 下载文件到 `path`，支持断点续传与多连接分块。
+pub async fn download_resumable<F>(
+    client: &Client,
+    url: &str,
+    path: &Path,
+    options: &DownloadOptions,
+    on_progress: F,
+) -> Result<(), String>
+where
+    F: Fn(DownloadProgress) + Send + Sync,
+{
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("{}: {}", messages::ERR_CREATE_DIR_FAILED, e))?;
+    }
+
+    let probe = probe(client, url).await?;
+    let resume_key = options.resume_key.as_deref().unwrap_or(url);
+
+    match probe.total_size {
+        Some(total_size) if probe.accepts_ranges && total_size > 0 => {
+            download_ranged(
+                client,
+                url,
+                path,
+                resume_key,
+                total_size,
+                options.connections,
+                on_progress,
+            )
+            .await
+        }
+        total_size => {
+            info!("服务器不支持断点续传，使用单连接下载: {}", url);
+            discard_partial(path);
+            download_streaming(client, url, path, total_size, on_progress).await
+        }
+    }?;
+
+    std::fs::rename(part_path(path), path)
+        .map_err(|e| format!("{}: {}", messages::ERR_WRITE_FILE_FAILED, e))?;
+    let _ = std::fs::remove_file(meta_path(path));
+    Ok(())
+}
+
+async fn download_ranged<F>(
+    client: &Client,
+    url: &str,
+    path: &Path,
+    resume_key: &str,
+    total_size: u64,
+    connections: usize,
+    on_progress: F,
+) -> Result<(), String>
+where
+    F: Fn(DownloadProgress) + Send + Sync,
+{
+    let part = part_path(path);
+    let meta = match load_meta(path) {
+        Some(meta) if part.exists() && meta.is_resumable_for(resume_key, total_size) => {
+            info!(
+                "继续未完成的下载: 已完成 {} / {}",
+                format_bytes(meta.downloaded()),
+                format_bytes(total_size)
+            );
+            meta
+        }
+        _ => {
+            discard_partial(path);
+            PartMeta {
+                resume_key: resume_key.to_string(),
+                total_size,
+                chunks: plan_chunks(total_size, connections),
+            }
+        }
+    };
+
+    let file = std::fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(false)
+        .open(&part)
+        .map_err(|e| format!("{}: {}", messages::ERR_CREATE_FILE_FAILED, e))?;
+    file.set_len(total_size)
+        .map_err(|e| format!("{}: {}", messages::ERR_WRITE_FILE_FAILED, e))?;
+    drop(file);
+    save_meta(path, &meta)?;
+
+    let tracker = ProgressTracker::new(meta.downloaded(), Some(total_size), on_progress);
+    let pending: Vec<usize> = (0..meta.chunks.len())
+        .filter(|i| !meta.chunks[*i].is_complete())
+        .collect();
+    let shared = Arc::new(Mutex::new(meta));
+
+    try_join_all(
+        pending
+            .into_iter()
+            .map(|index| download_chunk(client, url, path, &shared, index, &tracker)),
+    )
+    .await?;
+
+    tracker.finish();
+    Ok(())
+}
+
+async fn download_chunk<F>(
+    client: &Client,
+    url: &str,
+    path: &Path,
+    shared: &Arc<Mutex<PartMeta>>,
+    index: usize,
+    tracker: &ProgressTracker<F>,
+) -> Result<(), String>
+where
+    F: Fn(DownloadProgress) + Send + Sync,
+{
+    let chunk = shared
+        .lock()
+        .map(|meta| meta.chunks[index].clone())
+        .map_err(|_| "下载状态异常".to_string())?;
+    let offset = chunk.start + chunk.downloaded;
+
+    let response = client
+        .get(url)
+        .header(RANGE, format!("bytes={}-{}", offset, chunk.end))
+        .send()
+        .await
+        .map_err(|e| format!("{}: {}", messages::ERR_REQUEST_FAILED, e))?;
+    if response.status() != StatusCode::PARTIAL_CONTENT {
+        return Err(format!(
+            "{}: 分块请求返回 {}",
+            messages::ERR_SERVER_ERROR,
+            response.status()
+        ));
+    }
+
+    let mut file = tokio::fs::OpenOptions::new()
+        .write(true)
+        .open(part_path(path))
+        .await
+        .map_err(|e| format!("{}: {}", messages::ERR_OPEN_FILE_FAILED, e))?;
+    file.seek(SeekFrom::Start(offset))
+        .await
+        .map_err(|e| format!("{}: {}", messages::ERR_WRITE_FILE_FAILED, e))?;
+
+    let mut remaining = chunk.end + 1 - offset;
+    let mut unsaved = 0u64;
+    let mut stream = response.bytes_stream();
+    while let Some(item) = stream.next().await {
+        if remaining == 0 {
+            break;
+        }
+        let bytes = item.map_err(|e| format!("{}: {}", messages::ERR_REQUEST_FAILED, e))?;
+        // 防止服务器返回超出请求范围的数据覆盖相邻分块
+        let take = (bytes.len() as u64).min(remaining) as usize;
+        file.write_all(&bytes[..take])
+            .await
+            .map_err(|e| format!("{}: {}", messages::ERR_WRITE_FILE_FAILED, e))?;
+        remaining -= take as u64;
+        unsaved += take as u64;
+        tracker.add(take as u64);
+
+        if unsaved >= META_SAVE_INTERVAL_BYTES || remaining == 0 {
+            // 先落盘数据再记录进度，保证元数据中的已下载量不超过实际写入量
+            file.flush()
+                .await
+                .map_err(|e| format!("{}: {}", messages::ERR_WRITE_FILE_FAILED, e))?;
+            let snapshot = {
+                let mut meta = shared.lock().map_err(|_| "下载状态异常".to_string())?;
+                meta.chunks[index].downloaded += unsaved;
+                meta.clone()
+            };
+            if let Err(e) = save_meta(path, &snapshot) {
+                warn!("保存下载进度失败: {}", e);
+            }
+            unsaved = 0;
+        }
+    }
+
+    if remaining > 0 {
+        return Err(format!(
+            "{}: 连接提前结束，分块还差 {} 字节",
+            messages::ERR_REQUEST_FAILED,
+            remaining
+        ));
+    }
+    Ok(())
+}
+
+async fn download_streaming<F>(
+    client: &Client,
+    url: &str,
+    path: &Path,
+    total_size: Option<u64>,
+    on_progress: F,
+) -> Result<(), String>
+where
+    F: Fn(DownloadProgress) + Send + Sync,
+{
+    let response = client
+        .get(url)
+        .send()
+        .await
+        .map_err(|e| format!("{}: {}", messages::ERR_REQUEST_FAILED, e))?;
+    if !response.status().is_success() {
+        return Err(format!(
+            "{}: {}",
+            messages::ERR_SERVER_ERROR,
+            response.status()
+        ));
+    }
+
+    let total_size = total_size.or_else(|| response.content_length().filter(|len| *len > 0));
+    let mut file = tokio::fs::File::create(part_path(path))
+        .await
+        .map_err(|e| format!("{}: {}", messages::ERR_CREATE_FILE_FAILED, e))?;
+    let tracker = ProgressTracker::new(0, total_size, on_progress);
+
+    let mut stream = response.bytes_stream();
+    while let Some(item) = stream.next().await {
+        let bytes = item.map_err(|e| format!("{}: {}", messages::ERR_REQUEST_FAILED, e))?;
+        file.write_all(&bytes)
+            .await
+            .map_err(|e| format!("{}: {}", messages::ERR_WRITE_FILE_FAILED, e))?;
+        tracker.add(bytes.len() as u64);
+    }
+    file.flush()
+        .await
+        .map_err(|e| format!("{}: {}", messages::ERR_WRITE_FILE_FAILED, e))?;
+
+    tracker.finish();
+    Ok(())
+}
+
+#[cfg(test)]
+#[path = "resumable_download.tests.rs"]
+mod tests;
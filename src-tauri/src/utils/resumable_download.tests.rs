@@ -0,0 +1,91 @@
+use super::*;
+
+#[test]
+fn plan_chunks_should_cover_whole_file_without_overlap() {
+    let total = 20 * 1024 * 1024 + 7;
+    let chunks = plan_chunks(total, 4);
+    assert_eq!(chunks.len(), 4);
+    assert_eq!(chunks[0].start, 0);
+    assert_eq!(chunks.last().unwrap().end, total - 1);
+    for pair in chunks.windows(2) {
+        assert_eq!(pair[0].end + 1, pair[1].start);
+    }
+    assert_eq!(chunks.iter().map(ChunkState::len).sum::<u64>(), total);
+}
+
+#[test]
+fn plan_chunks_should_not_split_small_files() {
+    assert_eq!(
+        plan_chunks(1000, 8),
+        vec![ChunkState {
+            start: 0,
+            end: 999,
+            downloaded: 0
+        }]
+    );
+    assert!(plan_chunks(0, 4).is_empty());
+}
+
+#[test]
+fn parse_content_range_total_should_read_total_size() {
+    assert_eq!(parse_content_range_total("bytes 0-0/12345"), Some(12345));
+    assert_eq!(parse_content_range_total("bytes */999"), Some(999));
+    assert_eq!(parse_content_range_total("bytes 0-0/*"), None);
+    assert_eq!(parse_content_range_total("items 0-0/10"), None);
+}
+
+#[test]
+fn part_meta_should_only_resume_matching_downloads() {
+    let meta = PartMeta {
+        resume_key: "https://github.com/a/b/releases/download/v1/x.zip".to_string(),
+        total_size: 100,
+        chunks: vec![ChunkState {
+            start: 0,
+            end: 99,
+            downloaded: 40,
+        }],
+    };
+    assert!(meta.is_resumable_for(&meta.resume_key, 100));
+    assert!(!meta.is_resumable_for(&meta.resume_key, 101));
+    assert!(!meta.is_resumable_for("https://example.com/other.zip", 100));
+
+    let mut corrupted = meta.clone();
+    corrupted.chunks[0].downloaded = 200;
+    assert!(!corrupted.is_resumable_for(&meta.resume_key, 100));
+}
+
+#[test]
+fn download_progress_should_report_percent_and_description() {
+    let progress = DownloadProgress {
+        downloaded: 5 * 1024 * 1024,
+        total: Some(20 * 1024 * 1024),
+        speed_bps: 1024 * 1024,
+        eta_secs: Some(15),
+    };
+    assert_eq!(progress.percent(), Some(25));
+    assert_eq!(
+        progress.describe(),
+        "5.0 MB / 20.0 MB，1.0 MB/s，剩余 15 秒"
+    );
+
+    let unknown = DownloadProgress {
+        total: None,
+        eta_secs: None,
+        ..progress
+    };
+    assert_eq!(unknown.percent(), None);
+    assert_eq!(format_bytes(512), "512 B");
+}
+
+#[test]
+fn part_paths_should_append_suffix_to_full_file_name() {
+    let path = Path::new("/tmp/sing-box-1.12.0-linux-amd64.tar.gz");
+    assert_eq!(
+        part_path(path),
+        PathBuf::from("/tmp/sing-box-1.12.0-linux-amd64.tar.gz.part")
+    );
+    assert_eq!(
+        meta_path(path),
+        PathBuf::from("/tmp/sing-box-1.12.0-linux-amd64.tar.gz.part.json")
+    );
+}
@@ -0,0 +1,457 @@
+//! 终端 / 命令行工具的代理环境
+//!
+//! 进程内 `set_var` 只对本程序生效，终端里的 curl、git、npm、pip 仍然直连。
+//! 这里按用户选择把代理写入 `environment.d`、bash/zsh/fish 启动脚本以及 git/npm/pip 配置：
+//! 写入内容包在标记行之间，关闭代理时只删除标记范围内的内容，不影响用户原有配置。
+
+use crate::app::constants::paths;
+use crate::utils::proxy_util::wildcard_ip_to_cidr;
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+const BLOCK_BEGIN: &str = "# >>> sing-box-windows proxy >>>";
+const BLOCK_END: &str = "# <<< sing-box-windows proxy <<<";
+
+const ENVIRONMENT_D_FILE: &str = "90-sing-box-windows-proxy.conf";
+const FISH_CONF_FILE: &str = "sing-box-windows-proxy.fish";
+const GIT_INCLUDE_FILE: &str = "git-proxy.gitconfig";
+/// 只更新已存在的启动脚本，不替用户创建
+const SHELL_PROFILES: &[&str] = &[".bashrc", ".zshrc"];
+
+/// 需要写入代理的目标，全部默认关闭
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShellProxyTargets {
+    /// `~/.config/environment.d`（systemd 用户会话，仅 Linux）
+    pub environment_d: bool,
+    /// bash/zsh 启动脚本与 fish `conf.d`（非 Windows）
+    pub shell_profiles: bool,
+    pub git: bool,
+    pub npm: bool,
+    pub pip: bool,
+}
+
+/// 输出 export 语句的目标 shell
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShellKind {
+    Posix,
+    Fish,
+    PowerShell,
+    Cmd,
+}
+
+impl ShellKind {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "sh" | "bash" | "zsh" | "posix" => Some(Self::Posix),
+            "fish" => Some(Self::Fish),
+            "powershell" | "pwsh" => Some(Self::PowerShell),
+            "cmd" => Some(Self::Cmd),
+            _ => None,
+        }
+    }
+
+    pub fn platform_default() -> Self {
+        if cfg!(target_os = "windows") {
+            Self::PowerShell
+        } else {
+            Self::Posix
+        }
+    }
+}
+
+/// 代理相关环境变量的取值
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProxyEnvVars {
+    pub http: String,
+    pub socks: String,
+    pub no_proxy: String,
+}
+
+/// 绕过列表条目只允许主机名 / IP / CIDR / 通配符会用到的字符，
+/// 其余条目（可能含引号、`$`、反引号、换行等 shell 元字符）直接丢弃
+fn is_safe_bypass_entry(entry: &str) -> bool {
+    !entry.is_empty()
+        && entry.chars().all(|c| {
+            c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_' | ':' | '/' | '*' | '[' | ']')
+        })
+}
+
+/// POSIX shell 单引号：内部的 `'` 写成 `'\''`
+fn posix_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+/// fish 单引号：只有 `\\` 与 `\'` 两种转义
+fn fish_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\\', r"\\").replace('\'', r"\'"))
+}
+
+/// PowerShell 单引号：内部的 `'` 写成 `''`
+fn powershell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+impl ProxyEnvVars {
+    pub fn new(host: &str, port: u16, bypass_entries: &[String]) -> Self {
+        let no_proxy = bypass_entries
+            .iter()
+            .filter(|entry| entry.as_str() != "<local>")
+            .filter(|entry| {
+                let safe = is_safe_bypass_entry(entry);
+                if !safe {
+                    warn!("绕过列表条目含有不支持的字符，未写入 no_proxy: {:?}", entry);
+                }
+                safe
+            })
+            .map(|entry| {
+                wildcard_ip_to_cidr(entry)
+                    .or_else(|| entry.strip_prefix('*').map(str::to_string))
+                    .unwrap_or_else(|| entry.clone())
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+        Self {
+            http: format!("http://{}:{}", host, port),
+            socks: format!("socks5://{}:{}", host, port),
+            no_proxy,
+        }
+    }
+
+    /// (变量名, 值)；大小写两种写法都导出，兼容只认其中一种的工具
+    pub fn pairs(&self) -> Vec<(&'static str, &str)> {
+        vec![
+            ("http_proxy", self.http.as_str()),
+            ("https_proxy", self.http.as_str()),
+            ("all_proxy", self.socks.as_str()),
+            ("no_proxy", self.no_proxy.as_str()),
+            ("HTTP_PROXY", self.http.as_str()),
+            ("HTTPS_PROXY", self.http.as_str()),
+            ("ALL_PROXY", self.socks.as_str()),
+            ("NO_PROXY", self.no_proxy.as_str()),
+        ]
+    }
+
+    /// 生成可直接粘贴到终端执行的语句，取值按各 shell 的单引号规则转义
+    pub fn render(&self, shell: ShellKind) -> String {
+        let pairs = self.pairs();
+        let lines: Vec<String> = match shell {
+            ShellKind::Posix => pairs
+                .iter()
+                .map(|(name, value)| format!("export {}={}", name, posix_quote(value)))
+                .collect(),
+            ShellKind::Fish => pairs
+                .iter()
+                .map(|(name, value)| format!("set -gx {} {}", name, fish_quote(value)))
+                .collect(),
+            // Windows 环境变量不区分大小写，只输出大写形式
+            ShellKind::PowerShell => pairs
+                .iter()
+                .filter(|(name, _)| name.chars().all(|c| !c.is_ascii_lowercase()))
+                .map(|(name, value)| format!("$env:{} = {}", name, powershell_quote(value)))
+                .collect(),
+            ShellKind::Cmd => pairs
+                .iter()
+                .filter(|(name, _)| name.chars().all(|c| !c.is_ascii_lowercase()))
+                .map(|(name, value)| format!("set {}={}", name, value))
+                .collect(),
+        };
+        lines.join("\n")
+    }
+
+    fn environment_d(&self) -> String {
+        self.pairs()
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// 删除标记范围内的内容；缺少结束标记时原样返回，避免误删用户配置
+pub fn remove_managed_block(content: &str) -> String {
+    let lines: Vec<&str> = content.lines().collect();
+    let Some(begin) = lines.iter().position(|line| line.trim() == BLOCK_BEGIN) else {
+        return content.to_string();
+    };
+    let Some(end) = lines[begin..]
+        .iter()
+        .position(|line| line.trim() == BLOCK_END)
+        .map(|offset| begin + offset)
+    else {
+        return content.to_string();
+    };
+
+    let mut kept: Vec<&str> = lines[..begin].to_vec();
+    kept.extend_from_slice(&lines[end + 1..]);
+    join_lines(&kept)
+}
+
+/// 写入（或原位替换）受管片段；`insert_after` 指定的行存在时插在其后，否则追加到末尾
+pub fn upsert_managed_block(content: &str, body: &str, insert_after: Option<&str>) -> String {
+    let mut block = vec![BLOCK_BEGIN];
+    block.extend(body.lines());
+    block.push(BLOCK_END);
+
+    let lines: Vec<&str> = content.lines().collect();
+    let begin = lines.iter().position(|line| line.trim() == BLOCK_BEGIN);
+    let end = begin.and_then(|begin| {
+        lines[begin..]
+            .iter()
+            .position(|line| line.trim() == BLOCK_END)
+            .map(|offset| begin + offset)
+    });
+
+    let mut result: Vec<&str> = Vec::with_capacity(lines.len() + block.len());
+    if let (Some(begin), Some(end)) = (begin, end) {
+        result.extend_from_slice(&lines[..begin]);
+        result.extend_from_slice(&block);
+        result.extend_from_slice(&lines[end + 1..]);
+    } else if let Some(anchor) =
+        insert_after.and_then(|anchor| lines.iter().position(|line| line.trim() == anchor))
+    {
+        result.extend_from_slice(&lines[..=anchor]);
+        result.extend_from_slice(&block);
+        result.extend_from_slice(&lines[anchor + 1..]);
+    } else {
+        // 不额外插入空行，保证移除片段后文件与写入前一致
+        result.extend_from_slice(&lines);
+        result.extend_from_slice(&block);
+    }
+    join_lines(&result)
+}
+
+fn join_lines(lines: &[&str]) -> String {
+    if lines.is_empty() {
+        String::new()
+    } else {
+        format!("{}\n", lines.join("\n"))
+    }
+}
+
+fn read_or_empty(path: &Path) -> io::Result<String> {
+    match std::fs::read_to_string(path) {
+        Ok(content) => Ok(content),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(String::new()),
+        Err(e) => Err(e),
+    }
+}
+
+fn write_file(path: &Path, content: &str) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, content)
+}
+
+fn remove_file_if_exists(path: &Path) -> io::Result<()> {
+    match std::fs::remove_file(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+fn upsert_in_file(path: &Path, body: &str, insert_after: Option<&str>) -> io::Result<()> {
+    let content = read_or_empty(path)?;
+    write_file(path, &upsert_managed_block(&content, body, insert_after))
+}
+
+/// 从文件中移除受管片段；移除后文件为空（说明由本程序创建）则删除文件
+fn remove_from_file(path: &Path) -> io::Result<()> {
+    let content = read_or_empty(path)?;
+    let updated = remove_managed_block(&content);
+    if updated == content {
+        return Ok(());
+    }
+    if updated.trim().is_empty() {
+        remove_file_if_exists(path)
+    } else {
+        std::fs::write(path, updated)
+    }
+}
+
+/// 代理环境写入的目录布局；默认使用用户主目录、系统配置目录与应用配置目录
+pub struct ShellProxyEnv {
+    home: PathBuf,
+    config_dir: PathBuf,
+    app_dir: PathBuf,
+}
+
+impl ShellProxyEnv {
+    pub fn system() -> Option<Self> {
+        Some(Self {
+            home: dirs::home_dir()?,
+            config_dir: dirs::config_dir()?,
+            app_dir: paths::get_config_dir(),
+        })
+    }
+
+    #[cfg(test)]
+    fn with_dirs(home: PathBuf, config_dir: PathBuf, app_dir: PathBuf) -> Self {
+        Self {
+            home,
+            config_dir,
+            app_dir,
+        }
+    }
+
+    fn environment_d_file(&self) -> PathBuf {
+        self.config_dir
+            .join("environment.d")
+            .join(ENVIRONMENT_D_FILE)
+    }
+
+    fn fish_conf_dir(&self) -> PathBuf {
+        self.config_dir.join("fish")
+    }
+
+    fn git_include_file(&self) -> PathBuf {
+        self.app_dir.join(GIT_INCLUDE_FILE)
+    }
+
+    fn pip_config_file(&self) -> PathBuf {
+        let name = if cfg!(target_os = "windows") {
+            "pip.ini"
+        } else {
+            "pip.conf"
+        };
+        self.config_dir.join("pip").join(name)
+    }
+
+    fn write_environment_d(&self, vars: &ProxyEnvVars) -> io::Result<()> {
+        let content = upsert_managed_block("", &vars.environment_d(), None);
+        write_file(&self.environment_d_file(), &content)
+    }
+
+    fn write_shell_profiles(&self, vars: &ProxyEnvVars) -> io::Result<()> {
+        let posix = vars.render(ShellKind::Posix);
+        for profile in SHELL_PROFILES {
+            let path = self.home.join(profile);
+            if path.exists() {
+                upsert_in_file(&path, &posix, None)?;
+            }
+        }
+        // fish 会自动加载 conf.d 下的脚本，仅在用户使用 fish 时写入
+        if self.fish_conf_dir().exists() {
+            let content = upsert_managed_block("", &vars.render(ShellKind::Fish), None);
+            write_file(
+                &self.fish_conf_dir().join("conf.d").join(FISH_CONF_FILE),
+                &content,
+            )?;
+        }
+        Ok(())
+    }
+
+    fn remove_shell_profiles(&self) -> io::Result<()> {
+        for profile in SHELL_PROFILES {
+            remove_from_file(&self.home.join(profile))?;
+        }
+        remove_file_if_exists(&self.fish_conf_dir().join("conf.d").join(FISH_CONF_FILE))
+    }
+
+    /// git 的代理写在独立文件里，`~/.gitconfig` 中只保留一条 include，
+    /// 避免 `git config --global` 把用户自己的设置写进受管片段
+    fn write_git(&self, vars: &ProxyEnvVars) -> io::Result<()> {
+        // git 的 https 请求同样读取 http.proxy
+        let include = format!("[http]\n\tproxy = {}\n", vars.http);
+        write_file(&self.git_include_file(), &include)?;
+        let include_path = self.git_include_file().to_string_lossy().replace('\\', "/");
+        upsert_in_file(
+            &self.home.join(".gitconfig"),
+            &format!("[include]\n\tpath = {}", include_path),
+            None,
+        )
+    }
+
+    fn remove_git(&self) -> io::Result<()> {
+        remove_from_file(&self.home.join(".gitconfig"))?;
+        remove_file_if_exists(&self.git_include_file())
+    }
+
+    fn write_npm(&self, vars: &ProxyEnvVars) -> io::Result<()> {
+        upsert_in_file(
+            &self.home.join(".npmrc"),
+            &format!("proxy={0}\nhttps-proxy={0}", vars.http),
+            None,
+        )
+    }
+
+    /// pip 配置不允许重复的 `[global]` 段，已有时把片段插在该段之下
+    fn write_pip(&self, vars: &ProxyEnvVars) -> io::Result<()> {
+        let path = self.pip_config_file();
+        let content = read_or_empty(&path)?;
+        let has_global = remove_managed_block(&content)
+            .lines()
+            .any(|line| line.trim() == "[global]");
+        let body = if has_global {
+            format!("proxy = {}", vars.http)
+        } else {
+            format!("[global]\nproxy = {}", vars.http)
+        };
+        // 先移除旧片段，确保 `[global]` 归属变化时位置正确
+        let content = remove_managed_block(&content);
+        write_file(
+            &path,
+            &upsert_managed_block(&content, &body, Some("[global]")),
+        )
+    }
+
+    /// 按目标写入代理；未勾选的目标同时清理掉之前写入的片段
+    pub fn apply(&self, targets: &ShellProxyTargets, vars: &ProxyEnvVars) -> io::Result<()> {
+        if targets.environment_d && cfg!(target_os = "linux") {
+            self.write_environment_d(vars)?;
+        } else {
+            remove_file_if_exists(&self.environment_d_file())?;
+        }
+
+        if targets.shell_profiles && !cfg!(target_os = "windows") {
+            self.write_shell_profiles(vars)?;
+        } else {
+            self.remove_shell_profiles()?;
+        }
+
+        if targets.git {
+            self.write_git(vars)?;
+        } else {
+            self.remove_git()?;
+        }
+
+        if targets.npm {
+            self.write_npm(vars)?;
+        } else {
+            remove_from_file(&self.home.join(".npmrc"))?;
+        }
+
+        if targets.pip {
+            self.write_pip(vars)?;
+        } else {
+            remove_from_file(&self.pip_config_file())?;
+        }
+        Ok(())
+    }
+
+    /// 移除所有目标中的受管片段
+    pub fn remove_all(&self) -> io::Result<()> {
+        remove_file_if_exists(&self.environment_d_file())?;
+        self.remove_shell_profiles()?;
+        self.remove_git()?;
+        remove_from_file(&self.home.join(".npmrc"))?;
+        remove_from_file(&self.pip_config_file())
+    }
+}
+
+/// 关闭代理时清理所有终端代理片段；失败只记录日志
+pub fn remove_shell_proxy_snippets() {
+    let Some(env) = ShellProxyEnv::system() else {
+        return;
+    };
+    if let Err(e) = env.remove_all() {
+        warn!("清理终端代理环境配置失败: {}", e);
+    }
+}
+
+#[cfg(test)]
+#[path = "shell_proxy.tests.rs"]
+mod tests;
@@ -0,0 +1,189 @@
+use super::*;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+fn create_temp_dir(label: &str) -> PathBuf {
+    let unique = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("system time should be after epoch")
+        .as_nanos();
+    let dir = std::env::temp_dir().join(format!("sing-box-windows-{label}-{unique}"));
+    std::fs::create_dir_all(&dir).expect("should create temp dir");
+    dir
+}
+
+fn vars() -> ProxyEnvVars {
+    ProxyEnvVars::new(
+        "127.0.0.1",
+        7890,
+        &[
+            "localhost".to_string(),
+            "192.168.*".to_string(),
+            "*.example.com".to_string(),
+            "<local>".to_string(),
+        ],
+    )
+}
+
+fn read(path: &Path) -> String {
+    std::fs::read_to_string(path).unwrap_or_default()
+}
+
+#[test]
+fn render_should_print_exports_for_each_shell() {
+    let vars = vars();
+    assert_eq!(vars.no_proxy, "localhost,192.168.0.0/16,.example.com");
+
+    let posix = vars.render(ShellKind::Posix);
+    assert!(posix.contains("export http_proxy='http://127.0.0.1:7890'"));
+    assert!(posix.contains("export ALL_PROXY='socks5://127.0.0.1:7890'"));
+
+    let fish = vars.render(ShellKind::Fish);
+    assert!(fish.contains("set -gx https_proxy 'http://127.0.0.1:7890'"));
+
+    let powershell = vars.render(ShellKind::PowerShell);
+    assert!(powershell.contains("$env:HTTP_PROXY = 'http://127.0.0.1:7890'"));
+    assert!(!powershell.contains("http_proxy"));
+
+    assert!(vars
+        .render(ShellKind::Cmd)
+        .contains("set NO_PROXY=localhost,192.168.0.0/16,.example.com"));
+    assert_eq!(ShellKind::parse("ZSH"), Some(ShellKind::Posix));
+    assert_eq!(ShellKind::parse("nu"), None);
+}
+
+#[test]
+fn new_should_drop_bypass_entries_with_shell_metacharacters() {
+    let hostile = [
+        "localhost",
+        "$(touch /tmp/pwned)",
+        "a'b.example.com",
+        "`id`.example.com",
+        "x\"; rm -rf ~; echo \"",
+        "evil.com\nexport PATH=/tmp",
+        "10.0.0.0/8",
+        "[fd00::1]",
+    ]
+    .map(str::to_string);
+    let vars = ProxyEnvVars::new("127.0.0.1", 7890, &hostile);
+    assert_eq!(vars.no_proxy, "localhost,10.0.0.0/8,[fd00::1]");
+}
+
+#[test]
+fn render_should_quote_values_for_each_shell() {
+    let vars = ProxyEnvVars {
+        http: "http://127.0.0.1:7890".to_string(),
+        socks: "socks5://127.0.0.1:7890".to_string(),
+        no_proxy: r"it's $(id) `id` \x".to_string(),
+    };
+
+    assert!(vars
+        .render(ShellKind::Posix)
+        .contains(r"export no_proxy='it'\''s $(id) `id` \x'"));
+    assert!(vars
+        .render(ShellKind::Fish)
+        .contains(r"set -gx no_proxy 'it\'s $(id) `id` \\x'"));
+    assert!(vars
+        .render(ShellKind::PowerShell)
+        .contains(r"$env:NO_PROXY = 'it''s $(id) `id` \x'"));
+}
+
+#[test]
+fn managed_block_should_round_trip_and_replace_in_place() {
+    let original = "alias ll='ls -l'\nexport EDITOR=vim\n";
+    let with_block = upsert_managed_block(original, "export A=1", None);
+    assert_eq!(
+        with_block,
+        format!("{original}{BLOCK_BEGIN}\nexport A=1\n{BLOCK_END}\n")
+    );
+
+    let user_edit = format!("{with_block}export AFTER=1\n");
+    let replaced = upsert_managed_block(&user_edit, "export A=2", None);
+    assert!(replaced.contains("export A=2"));
+    assert!(!replaced.contains("export A=1"));
+    assert!(replaced.ends_with("export AFTER=1\n"));
+
+    assert_eq!(remove_managed_block(&with_block), original);
+    assert_eq!(remove_managed_block(original), original);
+}
+
+#[test]
+fn remove_managed_block_should_keep_content_without_end_marker() {
+    let content = format!("before\n{BLOCK_BEGIN}\nexport A=1\n");
+    assert_eq!(remove_managed_block(&content), content);
+}
+
+#[test]
+fn upsert_managed_block_should_insert_after_anchor() {
+    let content = "[global]\ntimeout = 60\n[install]\nuser = true\n";
+    let updated = upsert_managed_block(content, "proxy = x", Some("[global]"));
+    assert_eq!(
+        updated,
+        format!("[global]\n{BLOCK_BEGIN}\nproxy = x\n{BLOCK_END}\ntimeout = 60\n[install]\nuser = true\n")
+    );
+}
+
+// environment.d 与 shell 启动脚本只在 Linux 上写入
+#[cfg(target_os = "linux")]
+#[test]
+fn apply_then_remove_all_should_restore_user_files() {
+    let dir = create_temp_dir("shell-proxy");
+    let home = dir.join("home");
+    let config_dir = home.join(".config");
+    let app_dir = dir.join("app");
+    std::fs::create_dir_all(config_dir.join("fish")).expect("should create fish dir");
+    std::fs::create_dir_all(config_dir.join("pip")).expect("should create pip dir");
+
+    let bashrc = "export PATH=$HOME/bin:$PATH\n";
+    let gitconfig = "[user]\n\tname = tester\n";
+    let pip_conf = "[global]\ntimeout = 60\n";
+    std::fs::write(home.join(".bashrc"), bashrc).unwrap();
+    std::fs::write(home.join(".gitconfig"), gitconfig).unwrap();
+    std::fs::write(config_dir.join("pip").join("pip.conf"), pip_conf).unwrap();
+
+    let env = ShellProxyEnv::with_dirs(home.clone(), config_dir.clone(), app_dir.clone());
+    let targets = ShellProxyTargets {
+        environment_d: true,
+        shell_profiles: true,
+        git: true,
+        npm: true,
+        pip: true,
+    };
+    env.apply(&targets, &vars()).expect("apply should succeed");
+
+    assert!(read(&home.join(".bashrc")).contains(r#"export https_proxy="http://127.0.0.1:7890""#));
+    assert!(!home.join(".zshrc").exists());
+    assert!(read(&config_dir.join("fish/conf.d").join(FISH_CONF_FILE)).contains("set -gx"));
+    assert!(
+        read(&config_dir.join("environment.d").join(ENVIRONMENT_D_FILE))
+            .contains("http_proxy=http://127.0.0.1:7890")
+    );
+    assert!(read(&home.join(".gitconfig")).contains("[include]"));
+    assert!(read(&app_dir.join(GIT_INCLUDE_FILE)).contains("proxy = http://127.0.0.1:7890"));
+    assert!(read(&home.join(".npmrc")).contains("https-proxy=http://127.0.0.1:7890"));
+    let pip = read(&config_dir.join("pip").join("pip.conf"));
+    assert_eq!(pip.matches("[global]").count(), 1);
+    assert!(pip.contains("proxy = http://127.0.0.1:7890"));
+
+    // 关闭 npm 后只清理 npm 的片段
+    let without_npm = ShellProxyTargets {
+        npm: false,
+        ..targets
+    };
+    env.apply(&without_npm, &vars())
+        .expect("apply should succeed");
+    assert!(!home.join(".npmrc").exists());
+    assert!(read(&home.join(".gitconfig")).contains("[include]"));
+
+    env.remove_all().expect("remove should succeed");
+    assert_eq!(read(&home.join(".bashrc")), bashrc);
+    assert_eq!(read(&home.join(".gitconfig")), gitconfig);
+    assert_eq!(read(&config_dir.join("pip").join("pip.conf")), pip_conf);
+    assert!(!config_dir.join("fish/conf.d").join(FISH_CONF_FILE).exists());
+    assert!(!config_dir
+        .join("environment.d")
+        .join(ENVIRONMENT_D_FILE)
+        .exists());
+    assert!(!app_dir.join(GIT_INCLUDE_FILE).exists());
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
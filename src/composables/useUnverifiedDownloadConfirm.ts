@@ -0,0 +1,46 @@
+import { useDialog } from 'naive-ui'
+import { useI18n } from 'vue-i18n'
+
+/** 与后端 release_digest::DIGEST_UNAVAILABLE 保持一致 */
+const DIGEST_UNAVAILABLE = 'DIGEST_UNAVAILABLE'
+
+/**
+ * 判断下载错误是否为“无法获取摘要”，是则返回去掉前缀后的原因
+ */
+export const parseDigestUnavailable = (error: unknown): string | null => {
+  const msg = error instanceof Error ? error.message : String(error || '')
+  const index = msg.indexOf(DIGEST_UNAVAILABLE)
+  if (index < 0) return null
+  return msg.slice(index + DIGEST_UNAVAILABLE.length).replace(/^[:\s]+/, '')
+}
+
+/**
+ * 摘要不可用时弹窗让用户确认是否无校验继续安装，需在 n-dialog-provider 内使用
+ */
+export const useUnverifiedDownloadConfirm = () => {
+  const dialog = useDialog()
+  const { t } = useI18n()
+
+  return (reason: string) => {
+    let resolved = false
+
+    return new Promise<boolean>((resolve) => {
+      const finish = (result: boolean) => {
+        if (resolved) return
+        resolved = true
+        resolve(result)
+      }
+
+      dialog.warning({
+        title: t('setting.update.digestUnavailableTitle'),
+        content: t('setting.update.digestUnavailableConfirm', { reason }),
+        positiveText: t('common.confirm'),
+        negativeText: t('common.cancel'),
+        maskClosable: false,
+        onPositiveClick: () => finish(true),
+        onNegativeClick: () => finish(false),
+        onClose: () => finish(false),
+      })
+    })
+  }
+}
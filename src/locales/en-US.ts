@@ -339,6 +339,7 @@ export default {
     useOriginalConfig: 'Use original config (ports only)',
     useExtractedNodes: 'Extract nodes to local template',
     originalConfigJsonOnly: 'Original subscription only supports sing-box JSON config',
+    headersUnreadable: 'Headers {names} could not be decrypted on this device and were not sent; please re-enter them',
     originalConfigWarning:
       'When using the original config, advanced template options (DNS/routing/ads, etc.) will not apply',
     configPathCollision: 'Detected duplicate config path. Regenerated config before switching.',
@@ -470,6 +471,9 @@ export default {
       releaseNotes: 'Release Notes',
       updateNotice: 'Update Notice',
       skipVersion: 'Skip This Version',
+      digestUnavailableTitle: 'Unable to verify download',
+      digestUnavailableConfirm:
+        'The official SHA-256 digest could not be retrieved ({reason}). The download will be installed without verification. Continue only if you trust your network.',
       prereleaseWarningDesc:
         'Pre-release versions may contain unstable features and potential issues, recommended for testing environments only.',
       prereleaseConfirm: 'Confirm Enable Pre-release',
@@ -369,6 +369,7 @@ export default {
     useOriginalConfig: '元の設定を使用（ポートのみ置換）',
     useExtractedNodes: 'ノードをローカルテンプレートに抽出',
     originalConfigJsonOnly: '元のサブスクリプションは sing-box JSON 設定のみ対応',
+    headersUnreadable: 'リクエストヘッダー {names} をこの端末で復号できないため送信しませんでした。再入力してください',
     originalConfigWarning:
       '元の設定を使用する場合、DNS/分流/広告ブロックなどの高度な設定は反映されません',
     configPathCollision: '設定パスの重複を検出したため、再生成してから切り替えます',
@@ -513,6 +514,9 @@ export default {
       releaseNotes: 'リリースノート',
       updateNotice: '更新通知',
       skipVersion: 'このバージョンをスキップ',
+      digestUnavailableTitle: 'ダウンロードを検証できません',
+      digestUnavailableConfirm:
+        '公式の SHA-256 ダイジェストを取得できません（{reason}）。ダウンロードしたファイルは検証なしでインストールされます。ネットワークが信頼できる場合のみ続行してください。',
       prereleaseWarningDesc:
         'テストバージョンには不安定な機能や潜在的な問題が含まれる可能性があります。テスト環境でのみ使用することをお勧めします。',
       prereleaseConfirm: 'プレリリースバージョンを有効にする確認',
@@ -373,6 +373,7 @@ export default {
     useOriginalConfig: 'Использовать исходную конфигурацию (только замена портов)',
     useExtractedNodes: 'Извлечь узлы в локальный шаблон',
     originalConfigJsonOnly: 'Оригинальная подписка поддерживает только sing-box JSON конфигурацию',
+    headersUnreadable: 'Заголовки {names} не удалось расшифровать на этом устройстве, они не были отправлены; введите их заново',
     originalConfigWarning:
       'При использовании исходной конфигурации расширенные параметры (DNS/маршрутизация/реклама и т.д.) не применяются',
     configPathCollision:
@@ -522,6 +523,9 @@ export default {
       releaseNotes: 'Заметки о выпуске',
       updateNotice: 'Уведомление об обновлении',
       skipVersion: 'Пропустить эту версию',
+      digestUnavailableTitle: 'Не удалось проверить загрузку',
+      digestUnavailableConfirm:
+        'Не удалось получить официальный дайджест SHA-256 ({reason}). Файл будет установлен без проверки. Продолжайте, только если доверяете своей сети.',
       prereleaseWarningDesc:
         'Предварительные версии могут содержать нестабильные функции и потенциальные проблемы, рекомендуется использовать только в тестовых средах.',
       prereleaseConfirm: 'Подтвердить включение предварительных версий',
@@ -337,6 +337,7 @@ export default {
     useOriginalConfig: '使用原始配置（仅替换端口）',
     useExtractedNodes: '提取节点到本地模板',
     originalConfigJsonOnly: '原始订阅仅支持 sing-box JSON 配置内容',
+    headersUnreadable: '请求头 {names} 无法在本机解密，本次拉取未发送，请重新填写',
     originalConfigWarning: '使用原始配置时，订阅模板高级选项（DNS/分流组/广告拦截等）不会生效',
     configPathCollision: '检测到订阅配置路径重复，已重新生成配置后再切换',
     urlSubscription: '订阅链接',
@@ -467,6 +468,9 @@ export default {
       releaseNotes: '更新日志',
       updateNotice: '更新提醒',
       skipVersion: '跳过此版本',
+      digestUnavailableTitle: '无法校验下载文件',
+      digestUnavailableConfirm:
+        '无法获取官方发布的 SHA-256 摘要（{reason}），下载的文件将不经校验直接安装。仅在确认网络环境可信时继续。',
       prereleaseWarningDesc: '测试版本可能包含未稳定的功能和潜在的问题，建议仅在测试环境中使用。',
       prereleaseConfirm: '确认启用测试版本',
       prereleaseConfirmDesc:
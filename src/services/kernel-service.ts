@@ -48,7 +48,7 @@ export interface TunSettings {
 }
 
 export interface KernelConfig {
-  proxy_mode: 'system' | 'tun' | 'manual'
+  proxy_mode: 'system' | 'pac' | 'tun' | 'manual'
   api_port: number
   proxy_port: number
   prefer_ipv6: boolean
@@ -57,6 +57,33 @@ export interface KernelConfig {
   tun: TunSettings
 }
 
+/** 通过稳定性校验的配置快照 */
+export interface GoodConfigSnapshot {
+  id: string
+  /** 配置内容 SHA-256（hex） */
+  hash: string
+  /** 快照对应的原始配置路径 */
+  source_path: string
+  /** 快照副本路径 */
+  snapshot_path: string
+  recorded_at: number
+  /** 最近一次以该配置成功启动的时间 */
+  last_verified_at: number
+}
+
+export interface GoodConfigHistory {
+  /** 启动失败时是否自动回滚 */
+  auto_rollback: boolean
+  /** 按最近验证时间倒序排列 */
+  entries: GoodConfigSnapshot[]
+}
+
+export interface RestoreGoodConfigResult {
+  success: boolean
+  message: string
+  config_path: string
+}
+
 export interface KernelStartConfig {
   proxy_mode: string
   api_port: number
@@ -94,6 +121,14 @@ export interface KernelDownloadPayload {
   progress?: number
   message?: string
   status?: 'downloading' | 'completed' | 'error'
+  /** 已下载字节数 */
+  downloaded?: number
+  /** 文件总字节数，未知时为 null */
+  total?: number | null
+  /** 下载速度（字节/秒） */
+  speed?: number
+  /** 预计剩余秒数，未知时为 null */
+  eta?: number | null
 }
 
 class KernelService {
@@ -244,11 +279,11 @@ class KernelService {
   }
 
   async switchProxyMode(
-    mode: 'system' | 'tun' | 'manual',
+    mode: 'system' | 'pac' | 'tun' | 'manual',
   ): Promise<{ success: boolean; message: string }> {
     try {
-      const overrides: Record<string, boolean> = {}
-      if (mode === 'system') {
+      const overrides: Record<string, boolean | string> = { proxy_mode: mode }
+      if (mode === 'system' || mode === 'pac') {
         overrides.system_proxy_enabled = true
         overrides.tun_enabled = false
       } else if (mode === 'tun') {
@@ -415,6 +450,27 @@ class KernelService {
   async updateSingboxPorts(proxyPort: number, apiPort: number): Promise<void> {
     return invokeWithAppContext<void>('update_singbox_ports', { proxyPort, apiPort })
   }
+
+  /**
+   * 列出可用配置快照
+   */
+  async listGoodConfigs(): Promise<GoodConfigHistory> {
+    return invokeWithAppContext<GoodConfigHistory>('list_good_configs')
+  }
+
+  /**
+   * 将快照恢复到其原始配置路径，重启内核后生效
+   */
+  async restoreGoodConfig(id: string): Promise<RestoreGoodConfigResult> {
+    return invokeWithAppContext<RestoreGoodConfigResult>('restore_good_config', { id })
+  }
+
+  /**
+   * 设置启动失败时是否自动回滚到上一份可用配置
+   */
+  async setConfigAutoRollback(enabled: boolean): Promise<void> {
+    return invokeWithAppContext<void>('set_config_auto_rollback', { enabled })
+  }
 }
 
 export const kernelService = new KernelService()
@@ -0,0 +1,31 @@
+import type { LanCredentials } from '@/types/generated/LanCredentials'
+import type { LanShareInfo } from '@/types/generated/LanShareInfo'
+import { invokeWithAppContext } from './invoke-client'
+
+export const lanAuthService = {
+  getCredentials() {
+    return invokeWithAppContext<LanCredentials | null>('lan_auth_get_credentials', undefined, {
+      skipDataRestore: true,
+    })
+  },
+
+  setCredentials(username: string, password: string) {
+    return invokeWithAppContext<void>(
+      'lan_auth_set_credentials',
+      { username, password },
+      { skipDataRestore: true }
+    )
+  },
+
+  regenerateCredentials() {
+    return invokeWithAppContext<LanCredentials>('lan_auth_regenerate_credentials', undefined, {
+      skipDataRestore: true,
+    })
+  },
+
+  getShareInfo() {
+    return invokeWithAppContext<LanShareInfo>('lan_share_get_info', undefined, {
+      skipDataRestore: true,
+    })
+  },
+}
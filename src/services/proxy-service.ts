@@ -1,6 +1,7 @@
 import { useAppStore } from '@/stores/app/AppStore'
 import { useKernelStore } from '@/stores/kernel/KernelStore'
 import type { ProxyProvidersResponse, RuleProvidersResponse, RulesResponse } from '@/types/controller'
+import type { TunRoutePreset } from '@/types/generated/TunRoutePreset'
 import { NotificationService } from './notification-service'
 import { invokeWithAppContext } from './invoke-client'
 import i18n from '@/locales'
@@ -48,8 +49,27 @@ export interface TunOptionsPayload {
   stack: 'system' | 'gvisor' | 'mixed'
   enable_ipv6: boolean
   route_exclude_address?: string[]
+  route_address?: string[]
+  route_presets?: TunRoutePreset[]
 }
 
+export interface ProxyWatchdogPolicy {
+  enabled: boolean
+  auto_reapply: boolean
+  max_reapplies: number
+  window_secs: number
+}
+
+export interface ShellProxyTargets {
+  environment_d: boolean
+  shell_profiles: boolean
+  git: boolean
+  npm: boolean
+  pip: boolean
+}
+
+export type ShellKind = 'bash' | 'zsh' | 'fish' | 'powershell' | 'cmd'
+
 export class ProxyService {
   private static instance: ProxyService
   private notificationService = NotificationService.getInstance()
@@ -80,13 +100,14 @@ export class ProxyService {
    * @returns 是否需要关闭应用（重启管理员）
    */
   public async switchMode(
-    mode: 'system' | 'tun' | 'manual',
+    mode: 'system' | 'pac' | 'tun' | 'manual',
     messageCallback?: (type: 'success' | 'info' | 'error', content: string) => void,
   ): Promise<boolean> {
     try {
       // 根据模式同步独立开关，具体配置由后端从数据库读取
-      if (mode === 'system') {
+      if (mode === 'system' || mode === 'pac') {
         await this.appStore.toggleSystemProxy(true)
+        await this.appStore.setSystemProxyPac(mode === 'pac')
         await this.appStore.toggleTun(false)
       } else if (mode === 'manual') {
         await this.appStore.toggleSystemProxy(false)
@@ -105,7 +126,7 @@ export class ProxyService {
 
       if (messageCallback) {
         const content =
-          mode === 'system'
+          mode === 'system' || mode === 'pac'
             ? this.t('notification.systemProxyEnabled')
             : mode === 'tun'
               ? this.t('notification.tunEnabled')
@@ -177,6 +198,26 @@ export class ProxyService {
     return invokeWithAppContext<string>('get_current_proxy_mode')
   }
 
+  async getProxyWatchdogPolicy() {
+    return invokeWithAppContext<ProxyWatchdogPolicy>('get_proxy_watchdog_policy')
+  }
+
+  async saveProxyWatchdogPolicy(policy: ProxyWatchdogPolicy) {
+    return invokeWithAppContext<void>('save_proxy_watchdog_policy', { policy })
+  }
+
+  async getShellProxyTargets() {
+    return invokeWithAppContext<ShellProxyTargets>('get_shell_proxy_targets')
+  }
+
+  async saveShellProxyTargets(targets: ShellProxyTargets) {
+    return invokeWithAppContext<void>('save_shell_proxy_targets', { targets })
+  }
+
+  async getShellProxyExports(shell?: ShellKind) {
+    return invokeWithAppContext<string>('get_shell_proxy_exports', shell ? { shell } : undefined)
+  }
+
   async getProxies() {
     return invokeWithAppContext<ProxiesData>('get_proxies', undefined, {
       withApiPort: 'port'
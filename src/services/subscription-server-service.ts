@@ -0,0 +1,29 @@
+import type { SubscriptionServerSettings } from '@/types/generated/SubscriptionServerSettings'
+import type { SubscriptionServerStatus } from '@/types/generated/SubscriptionServerStatus'
+import { invokeWithAppContext } from './invoke-client'
+
+export const subscriptionServerService = {
+  getStatus() {
+    return invokeWithAppContext<SubscriptionServerStatus>(
+      'subscription_server_get_status',
+      undefined,
+      { skipDataRestore: true }
+    )
+  },
+
+  setSettings(settings: SubscriptionServerSettings) {
+    return invokeWithAppContext<SubscriptionServerStatus>(
+      'subscription_server_set_settings',
+      { settings },
+      { skipDataRestore: true }
+    )
+  },
+
+  regenerateToken() {
+    return invokeWithAppContext<SubscriptionServerStatus>(
+      'subscription_server_regenerate_token',
+      undefined,
+      { skipDataRestore: true }
+    )
+  },
+}
@@ -1,9 +1,14 @@
+import type { ClashMetaExport } from '@/types/generated/ClashMetaExport'
+import type { NodeExportFormat } from '@/types/generated/NodeExportFormat'
+import type { SubscriptionFetchOptions } from '@/types/generated/SubscriptionFetchOptions'
 import { invokeWithAppContext } from './invoke-client'
 
 export interface SubscriptionPersistOptions {
   fileName?: string
   configPath?: string
   applyRuntime?: boolean
+  /** 不传时后端使用该订阅已保存的拉取选项 */
+  fetchOptions?: SubscriptionFetchOptions
 }
 
 export interface SetActiveConfigOptions {
@@ -16,6 +21,7 @@ interface BackendSubscriptionPersistResult {
   subscription_download?: number | null
   subscription_total?: number | null
   subscription_expire?: number | null
+  skipped_headers?: string[]
 }
 
 export interface SubscriptionPersistResult {
@@ -24,6 +30,7 @@ export interface SubscriptionPersistResult {
   subscriptionDownload?: number
   subscriptionTotal?: number
   subscriptionExpire?: number
+  skippedHeaders: string[]
 }
 
 const mapPersistResult = (result: BackendSubscriptionPersistResult): SubscriptionPersistResult => ({
@@ -32,6 +39,7 @@ const mapPersistResult = (result: BackendSubscriptionPersistResult): Subscriptio
   subscriptionDownload: result.subscription_download ?? undefined,
   subscriptionTotal: result.subscription_total ?? undefined,
   subscriptionExpire: result.subscription_expire ?? undefined,
+  skippedHeaders: result.skipped_headers ?? [],
 })
 
 export const subscriptionService = {
@@ -44,11 +52,19 @@ export const subscriptionService = {
         fileName: options.fileName,
         configPath: options.configPath,
         applyRuntime: options.applyRuntime,
+        fetchOptions: options.fetchOptions,
       },
       { withProxyPort: true, withApiPort: true },
     ).then(mapPersistResult)
   },
 
+  updateFetchOptions(url: string, configPath: string | undefined, options: SubscriptionFetchOptions) {
+    return invokeWithAppContext<SubscriptionFetchOptions>(
+      'update_subscription_fetch_options',
+      { url, configPath, options },
+    )
+  },
+
   addManualSubscription(content: string, useOriginalConfig: boolean, options: SubscriptionPersistOptions = {}) {
     return invokeWithAppContext<BackendSubscriptionPersistResult>(
       'add_manual_subscription',
@@ -93,4 +109,12 @@ export const subscriptionService = {
   getCurrentConfig() {
     return invokeWithAppContext<unknown>('get_current_config', undefined, { withApiPort: true })
   },
+
+  exportNode(tag: string, format: NodeExportFormat) {
+    return invokeWithAppContext<string>('export_node', { tag, format })
+  },
+
+  exportClashMetaConfig() {
+    return invokeWithAppContext<ClashMetaExport>('export_clash_meta_config')
+  },
 }
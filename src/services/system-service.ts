@@ -28,10 +28,46 @@ export interface AppUpdateInfo {
   release_notes?: string
   release_date?: string
   file_size?: number
+  sha256?: string | null
   is_prerelease?: boolean
   supports_in_app_update: boolean
 }
 
+export interface InstalledKernel {
+  version: string
+  source: 'download' | 'import' | 'legacy'
+  sha256: string
+  installed_at: number
+  path: string
+  size_bytes: number
+  active: boolean
+}
+
+export interface KernelUpdatePolicy {
+  enabled: boolean
+  channel: 'stable' | 'prerelease'
+  check_interval_hours: number
+  auto_install_when_idle: boolean
+  min_version: string
+}
+
+export interface KernelUpdateStatus {
+  last_checked_at?: number | null
+  latest_version?: string | null
+  pending_version?: string | null
+  last_installed_version?: string | null
+  rolled_back_from?: string | null
+  last_error?: string | null
+}
+
+export interface KernelCapabilityReport {
+  probed: boolean
+  version?: string | null
+  tags: string[]
+  /** 当前设置中内核不支持的功能提示 */
+  warnings: string[]
+}
+
 export const systemService = {
   pickKernelImportFile() {
     return invokeWithAppContext<string | null>('pick_kernel_import_file', undefined, {
@@ -48,6 +84,55 @@ export const systemService = {
     }>('import_kernel_executable', { filePath }, { skipDataRestore: true })
   },
 
+  listInstalledKernels() {
+    return invokeWithAppContext<InstalledKernel[]>('list_installed_kernels', undefined, {
+      skipDataRestore: true,
+    })
+  },
+
+  activateKernelVersion(version: string) {
+    return invokeWithAppContext<{
+      imported_version: string
+      restarted: boolean
+      backup_path?: string | null
+      message: string
+    }>('activate_kernel_version', { version }, { skipDataRestore: true })
+  },
+
+  gcKernelVersions(keep?: number) {
+    return invokeWithAppContext<string[]>('gc_kernel_versions', { keep }, { skipDataRestore: true })
+  },
+
+  getKernelUpdatePolicy() {
+    return invokeWithAppContext<KernelUpdatePolicy>('get_kernel_update_policy', undefined, {
+      skipDataRestore: true,
+    })
+  },
+
+  saveKernelUpdatePolicy(policy: KernelUpdatePolicy) {
+    return invokeWithAppContext<void>('save_kernel_update_policy', { policy }, {
+      skipDataRestore: true,
+    })
+  },
+
+  getKernelUpdateStatus() {
+    return invokeWithAppContext<KernelUpdateStatus>('get_kernel_update_status', undefined, {
+      skipDataRestore: true,
+    })
+  },
+
+  checkKernelUpdateNow(install = false) {
+    return invokeWithAppContext<KernelUpdateStatus>('check_kernel_update_now', { install }, {
+      skipDataRestore: true,
+    })
+  },
+
+  getKernelCapabilities() {
+    return invokeWithAppContext<KernelCapabilityReport>('get_kernel_capabilities', undefined, {
+      skipDataRestore: true,
+    })
+  },
+
   checkAdmin() {
     return invokeWithAppContext<boolean>('check_admin', undefined, { skipDataRestore: true })
   },
@@ -84,12 +169,19 @@ export const systemService = {
     return invokeWithAppContext<void>('download_update')
   },
 
-  downloadAndInstallUpdate(downloadUrl?: string) {
-    return invokeWithAppContext<void>('download_and_install_update', { downloadUrl })
+  downloadAndInstallUpdate(downloadUrl?: string, allowUnverified?: boolean) {
+    return invokeWithAppContext<void>('download_and_install_update', {
+      downloadUrl,
+      allowUnverified,
+    })
   },
 
-  downloadKernel(version?: string) {
-    return invokeWithAppContext<void>('download_kernel', { version }, { skipDataRestore: true })
+  downloadKernel(version?: string, allowUnverified?: boolean) {
+    return invokeWithAppContext<void>(
+      'download_kernel',
+      { version, allowUnverified },
+      { skipDataRestore: true },
+    )
   },
 
   openDevtools() {
@@ -5,9 +5,12 @@ import type { MessageApi } from 'naive-ui/es/message'
 import { kernelService } from '@/services/kernel-service'
 import { useAppMessaging } from './composables/messaging'
 import { createAppPersistence } from './composables/persistence'
+import type { ExtraInbound } from '@/types/generated/ExtraInbound'
+import type { TunRoutePreset } from '@/types/generated/TunRoutePreset'
+import type { TunSplitRules } from '@/types/generated/TunSplitRules'
 
 // 代理模式类型
-export type ProxyMode = 'system' | 'tun' | 'manual'
+export type ProxyMode = 'system' | 'pac' | 'tun' | 'manual'
 export type TrayCloseBehavior = 'hide' | 'lightweight'
 
 const DEFAULT_SYSTEM_PROXY_BYPASS =
@@ -49,12 +52,14 @@ export const useAppStore = defineStore(
 
     // 代理模式 - 独立的System Proxy和TUN开关
     const systemProxyEnabled = ref(false)
+    // 系统代理使用 PAC 自动配置脚本，而非固定代理地址
+    const systemProxyPac = ref(false)
     const tunEnabled = ref(false)
 
     // 向后兼容：从独立开关派生proxyMode
     const proxyMode = computed<ProxyMode>(() => {
       if (tunEnabled.value) return 'tun'
-      if (systemProxyEnabled.value) return 'system'
+      if (systemProxyEnabled.value) return systemProxyPac.value ? 'pac' : 'system'
       return 'manual'
     })
 
@@ -83,6 +88,20 @@ export const useAppStore = defineStore(
     const tunStack = ref<'system' | 'gvisor' | 'mixed'>('mixed')
     const tunEnableIpv6 = ref(false)
     const tunRouteExcludeAddress = ref<string[] | null>(null)
+    const tunRouteAddress = ref<string[] | null>(null)
+    const tunRoutePresets = ref<TunRoutePreset[]>([])
+    const tunSplitRules = ref<TunSplitRules>({
+      include_process: [],
+      exclude_process: [],
+      include_uid: [],
+      exclude_uid: [],
+      include_interface: [],
+      exclude_interface: [],
+    })
+    const extraInbounds = ref<ExtraInbound[]>([])
+    // 局域网访问时 mixed 入站的认证开关与来源网段白名单
+    const lanAuthEnabled = ref(true)
+    const lanAllowedCidrs = ref<string[]>([])
     const activeConfigPath = ref<string | null>(null)
     const installedKernelVersion = ref<string | null>(null)
 
@@ -115,6 +134,7 @@ export const useAppStore = defineStore(
       stopAutoSave,
     } = createAppPersistence({
       systemProxyEnabled,
+      systemProxyPac,
       tunEnabled,
       autoStartKernel,
       autoStartApp,
@@ -134,6 +154,12 @@ export const useAppStore = defineStore(
       tunStack,
       tunEnableIpv6,
       tunRouteExcludeAddress,
+      tunRouteAddress,
+      tunRoutePresets,
+      tunSplitRules,
+      extraInbounds,
+      lanAuthEnabled,
+      lanAllowedCidrs,
       activeConfigPath,
       installedKernelVersion,
       singboxDnsProxy,
@@ -281,6 +307,12 @@ export const useAppStore = defineStore(
       await waitForSaveCompletion()
     }
 
+    // 切换系统代理的 PAC 方式
+    const setSystemProxyPac = async (enabled: boolean) => {
+      systemProxyPac.value = enabled
+      await waitForSaveCompletion()
+    }
+
     // 切换TUN模式
     const toggleTun = async (enabled: boolean) => {
       tunEnabled.value = enabled
@@ -292,6 +324,12 @@ export const useAppStore = defineStore(
       switch (targetMode) {
         case 'system':
           systemProxyEnabled.value = true
+          systemProxyPac.value = false
+          tunEnabled.value = false
+          break
+        case 'pac':
+          systemProxyEnabled.value = true
+          systemProxyPac.value = true
           tunEnabled.value = false
           break
         case 'tun':
@@ -307,7 +345,7 @@ export const useAppStore = defineStore(
     }
 
     // 向后兼容：设置代理模式（已弃用）
-    const setProxyMode = async (mode: 'system' | 'tun' | 'manual') => {
+    const setProxyMode = async (mode: ProxyMode) => {
       await switchProxyMode(mode)
     }
 
@@ -328,6 +366,12 @@ export const useAppStore = defineStore(
       tunStack?: 'system' | 'gvisor' | 'mixed'
       tunEnableIpv6?: boolean
       tunRouteExcludeAddress?: string[] | null
+      tunRouteAddress?: string[] | null
+      tunRoutePresets?: TunRoutePreset[]
+      tunSplitRules?: TunSplitRules
+      extraInbounds?: ExtraInbound[]
+      lanAuthEnabled?: boolean
+      lanAllowedCidrs?: string[]
       tunSelfHealEnabled?: boolean
       tunSelfHealCooldownSecs?: number
     }) => {
@@ -360,6 +404,26 @@ export const useAppStore = defineStore(
           ? [...settings.tunRouteExcludeAddress]
           : null
       }
+      if ('tunRouteAddress' in settings) {
+        tunRouteAddress.value = settings.tunRouteAddress?.length
+          ? [...settings.tunRouteAddress]
+          : null
+      }
+      if (Array.isArray(settings.tunRoutePresets)) {
+        tunRoutePresets.value = [...settings.tunRoutePresets]
+      }
+      if (settings.tunSplitRules) {
+        tunSplitRules.value = { ...settings.tunSplitRules }
+      }
+      if (Array.isArray(settings.extraInbounds)) {
+        extraInbounds.value = settings.extraInbounds.map((inbound) => ({ ...inbound }))
+      }
+      if (typeof settings.lanAuthEnabled === 'boolean') {
+        lanAuthEnabled.value = settings.lanAuthEnabled
+      }
+      if (Array.isArray(settings.lanAllowedCidrs)) {
+        lanAllowedCidrs.value = [...settings.lanAllowedCidrs]
+      }
       if (typeof settings.tunSelfHealEnabled === 'boolean') {
         tunSelfHealEnabled.value = settings.tunSelfHealEnabled
       }
@@ -410,6 +474,7 @@ export const useAppStore = defineStore(
       isDataRestored,
       trayInstanceId,
       systemProxyEnabled,
+      systemProxyPac,
       tunEnabled,
       proxyMode,
       autoStartKernel,
@@ -429,6 +494,12 @@ export const useAppStore = defineStore(
       tunStack,
       tunEnableIpv6,
       tunRouteExcludeAddress,
+      tunRouteAddress,
+      tunRoutePresets,
+      tunSplitRules,
+      extraInbounds,
+      lanAuthEnabled,
+      lanAllowedCidrs,
       activeConfigPath,
       installedKernelVersion,
       singboxDnsProxy,
@@ -453,6 +524,7 @@ export const useAppStore = defineStore(
       setAutoHideToTrayOnAutostart,
       setTrayCloseBehavior,
       toggleSystemProxy,
+      setSystemProxyPac,
       toggleTun,
       switchProxyMode,
       setProxyMode,
@@ -5,6 +5,7 @@ import { systemService, type AppUpdateInfo } from '@/services/system-service'
 import { getVersion } from '@tauri-apps/api/app'
 import { DatabaseService } from '@/services/database-service'
 import type { UpdateConfig } from '@/types/database'
+import { parseDigestUnavailable } from '@/composables/useUnverifiedDownloadConfirm'
 
 export type UpdateChannel = 'stable' | 'prerelease' | 'autobuild'
 type PlatformOs = 'windows' | 'linux' | 'macos' | 'unknown'
@@ -42,6 +43,7 @@ export const useUpdateStore = defineStore('update', () => {
   const releaseNotes = ref('')
   const releaseDate = ref('')
   const fileSize = ref(0)
+  const fileSha256 = ref('')
 
   const platformOs = ref<PlatformOs>('unknown')
   const supportsInAppUpdate = ref(false)
@@ -161,6 +163,7 @@ export const useUpdateStore = defineStore('update', () => {
     releaseNotes.value = info.release_notes || ''
     releaseDate.value = info.release_date || ''
     fileSize.value = info.file_size || 0
+    fileSha256.value = info.sha256 || ''
     isPrerelease.value = info.is_prerelease || false
     syncPlatformCapability(platformOs.value, info.supports_in_app_update)
   }
@@ -214,7 +217,13 @@ export const useUpdateStore = defineStore('update', () => {
     }
   }
 
-  const downloadAndInstallUpdate = async () => {
+  /**
+   * 下载并安装更新。拿不到官方摘要时通过 `confirmUnverified` 询问用户，
+   * 确认后以无校验模式重试；未提供回调时按失败处理。
+   */
+  const downloadAndInstallUpdate = async (
+    confirmUnverified?: (reason: string) => Promise<boolean>,
+  ): Promise<boolean | void> => {
     if (!supportsInAppUpdate.value) {
       const message = '当前平台暂不支持应用内更新，请前往版本页面下载最新版本'
       updateState.value.downloading = false
@@ -232,8 +241,20 @@ export const useUpdateStore = defineStore('update', () => {
       updateState.value.progress = 0
       updateState.value.error = null
 
-      const result = await systemService.downloadAndInstallUpdate(downloadUrl.value)
-      return result
+      try {
+        return await systemService.downloadAndInstallUpdate(downloadUrl.value)
+      } catch (error) {
+        const reason = parseDigestUnavailable(error)
+        if (reason === null || !confirmUnverified) throw error
+
+        updateState.value.downloading = false
+        updateState.value.status = 'idle'
+        if (!(await confirmUnverified(reason))) return false
+
+        updateState.value.downloading = true
+        updateState.value.status = 'downloading'
+        return await systemService.downloadAndInstallUpdate(downloadUrl.value, true)
+      }
     } catch (error) {
       console.error('下载更新失败:', error)
       const message = error instanceof Error ? error.message : String(error)
@@ -268,6 +289,7 @@ export const useUpdateStore = defineStore('update', () => {
     releaseNotes.value = ''
     releaseDate.value = ''
     fileSize.value = 0
+    fileSha256.value = ''
     updateState.value = {
       checking: false,
       downloading: false,
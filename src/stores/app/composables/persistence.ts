@@ -1,9 +1,13 @@
 import { nextTick, ref, watch, type Ref } from 'vue'
 import { DatabaseService } from '@/services/database-service'
 import type { AppConfig } from '@/types/generated/AppConfig'
+import type { ExtraInbound } from '@/types/generated/ExtraInbound'
+import type { TunRoutePreset } from '@/types/generated/TunRoutePreset'
+import type { TunSplitRules } from '@/types/generated/TunSplitRules'
 
 export interface PersistenceState {
   systemProxyEnabled: Ref<boolean>
+  systemProxyPac: Ref<boolean>
   tunEnabled: Ref<boolean>
   autoStartKernel: Ref<boolean>
   autoStartApp: Ref<boolean>
@@ -23,6 +27,12 @@ export interface PersistenceState {
   tunStack: Ref<string>
   tunEnableIpv6: Ref<boolean>
   tunRouteExcludeAddress: Ref<string[] | null>
+  tunRouteAddress: Ref<string[] | null>
+  tunRoutePresets: Ref<TunRoutePreset[]>
+  tunSplitRules: Ref<TunSplitRules>
+  extraInbounds: Ref<ExtraInbound[]>
+  lanAuthEnabled: Ref<boolean>
+  lanAllowedCidrs: Ref<string[]>
   activeConfigPath: Ref<string | null>
   installedKernelVersion: Ref<string | null>
   singboxDnsProxy: Ref<string>
@@ -105,8 +115,10 @@ export function createAppPersistence(state: PersistenceState) {
         state.systemProxyEnabled.value = appConfig.system_proxy_enabled
       } else {
         // 向后兼容：从旧的proxy_mode派生
-        state.systemProxyEnabled.value = appConfig.proxy_mode === 'system'
+        state.systemProxyEnabled.value =
+          appConfig.proxy_mode === 'system' || appConfig.proxy_mode === 'pac'
       }
+      state.systemProxyPac.value = appConfig.proxy_mode === 'pac'
 
       if (appConfig.tun_enabled !== undefined) {
         state.tunEnabled.value = appConfig.tun_enabled
@@ -138,6 +150,23 @@ export function createAppPersistence(state: PersistenceState) {
         appConfig.tun_route_exclude_address.length > 0
           ? [...appConfig.tun_route_exclude_address]
           : null
+      state.tunRouteAddress.value =
+        Array.isArray(appConfig.tun_route_address) && appConfig.tun_route_address.length > 0
+          ? [...appConfig.tun_route_address]
+          : null
+      state.tunRoutePresets.value = Array.isArray(appConfig.tun_route_presets)
+        ? [...appConfig.tun_route_presets]
+        : []
+      if (appConfig.tun_split_rules) {
+        state.tunSplitRules.value = { ...appConfig.tun_split_rules }
+      }
+      state.extraInbounds.value = Array.isArray(appConfig.extra_inbounds)
+        ? appConfig.extra_inbounds.map((inbound) => ({ ...inbound }))
+        : []
+      state.lanAuthEnabled.value = appConfig.lan_auth_enabled ?? true
+      state.lanAllowedCidrs.value = Array.isArray(appConfig.lan_allowed_cidrs)
+        ? [...appConfig.lan_allowed_cidrs]
+        : []
 
       // sing-box 配置生成高级选项（旧版本数据库可能没有这些字段）
       state.singboxDnsProxy.value = appConfig.singbox_dns_proxy || state.singboxDnsProxy.value
@@ -178,7 +207,7 @@ export function createAppPersistence(state: PersistenceState) {
     if (state.tunEnabled.value) {
       proxyMode = 'tun'
     } else if (state.systemProxyEnabled.value) {
-      proxyMode = 'system'
+      proxyMode = state.systemProxyPac.value ? 'pac' : 'system'
     }
 
     const config: AppConfig = {
@@ -205,6 +234,14 @@ export function createAppPersistence(state: PersistenceState) {
       tun_route_exclude_address: state.tunRouteExcludeAddress.value?.length
         ? [...state.tunRouteExcludeAddress.value]
         : null,
+      tun_route_address: state.tunRouteAddress.value?.length
+        ? [...state.tunRouteAddress.value]
+        : null,
+      tun_route_presets: [...state.tunRoutePresets.value],
+      tun_split_rules: state.tunSplitRules.value,
+      extra_inbounds: state.extraInbounds.value.map((inbound) => ({ ...inbound })),
+      lan_auth_enabled: state.lanAuthEnabled.value,
+      lan_allowed_cidrs: [...state.lanAllowedCidrs.value],
       active_config_path: state.activeConfigPath.value,
       installed_kernel_version: state.installedKernelVersion.value,
       singbox_dns_proxy: state.singboxDnsProxy.value,
@@ -246,6 +283,7 @@ export function createAppPersistence(state: PersistenceState) {
   const stopAutoSave = watch(
     [
       state.systemProxyEnabled,
+      state.systemProxyPac,
       state.tunEnabled,
       state.autoStartKernel,
       state.autoStartApp,
@@ -265,6 +303,12 @@ export function createAppPersistence(state: PersistenceState) {
       state.tunStack,
       state.tunEnableIpv6,
       state.tunRouteExcludeAddress,
+      state.tunRouteAddress,
+      state.tunRoutePresets,
+      state.tunSplitRules,
+      state.extraInbounds,
+      state.lanAuthEnabled,
+      state.lanAllowedCidrs,
       state.activeConfigPath,
       state.singboxDnsProxy,
       state.singboxDnsCn,
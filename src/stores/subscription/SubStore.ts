@@ -33,6 +33,7 @@ export const useSubStore = defineStore(
         lastAutoUpdateError: sub.last_auto_update_error ?? undefined,
         lastAutoUpdateErrorType: sub.last_auto_update_error_type ?? undefined,
         lastAutoUpdateBackoffUntil: sub.last_auto_update_backoff_until ?? undefined,
+        fetchOptions: sub.fetch_options,
       }))
     }
 
@@ -58,6 +59,7 @@ export const useSubStore = defineStore(
         last_auto_update_error: sub.lastAutoUpdateError ?? null,
         last_auto_update_error_type: sub.lastAutoUpdateErrorType ?? null,
         last_auto_update_backoff_until: sub.lastAutoUpdateBackoffUntil ?? null,
+        fetch_options: sub.fetchOptions,
       }))
     }
 
@@ -1,3 +1,5 @@
+import type { SubscriptionFetchOptions } from '@/types/generated/SubscriptionFetchOptions'
+
 export const DEFAULT_AUTO_UPDATE_MINUTES = 720 // 12h
 
 // 前端订阅模型（camelCase），与后端 snake_case 模型分离，避免视图层反复转换字段。
@@ -21,4 +23,5 @@ export interface FrontendSubscription {
   lastAutoUpdateError?: string
   lastAutoUpdateErrorType?: string
   lastAutoUpdateBackoffUntil?: number
+  fetchOptions?: SubscriptionFetchOptions
 }
@@ -73,6 +73,7 @@ export type StartupDiagnosisKind =
   | 'sudo_required'
   | 'sudo_invalid'
   | 'port_conflict'
+  | 'tun_address_conflict'
   | 'process_exited_early'
   | 'api_http_error'
   | 'api_timeout'
@@ -113,7 +114,7 @@ export interface KernelLifecyclePayload {
   startup_diagnosis?: StartupDiagnosis | null
   kernel_state?: 'stopped' | 'starting' | 'running' | 'stopping' | 'failed' | 'crashed'
   state_version?: number
-  proxy_mode?: 'system' | 'tun' | 'manual'
+  proxy_mode?: 'system' | 'pac' | 'tun' | 'manual'
   api_port?: number
   proxy_port?: number
   auto_restarted?: boolean
@@ -169,3 +170,16 @@ export interface TrayToggleProxyFeaturePayload {
   feature: 'systemProxy' | 'tun'
   enabled: boolean
 }
+
+export interface ObservedSystemProxy {
+  enabled: boolean
+  server: string | null
+  pac_url: string | null
+}
+
+export interface SystemProxyDriftPayload {
+  expected: { mode: 'system' | 'pac'; value: string }
+  observed: ObservedSystemProxy
+  reapplied: boolean
+  rate_limited: boolean
+}
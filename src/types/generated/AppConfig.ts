@@ -1,3 +1,7 @@
+import type { ExtraInbound } from './ExtraInbound'
+import type { TunRoutePreset } from './TunRoutePreset'
+import type { TunSplitRules } from './TunSplitRules'
+
 export interface AppConfig {
   auto_start_kernel: boolean
   auto_start_app: boolean
@@ -20,6 +24,12 @@ export interface AppConfig {
   tun_stack: string
   tun_enable_ipv6: boolean
   tun_route_exclude_address: string[] | null
+  tun_route_address: string[] | null
+  tun_route_presets: TunRoutePreset[]
+  tun_split_rules: TunSplitRules
+  extra_inbounds: ExtraInbound[]
+  lan_auth_enabled: boolean
+  lan_allowed_cidrs: string[]
   active_config_path: string | null
   installed_kernel_version: string | null
   singbox_dns_proxy: string
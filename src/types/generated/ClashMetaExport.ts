@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type ClashMetaExport = { yaml: string, 
+/**
+ * 未能转换或语义有差异的条目，逐条描述
+ */
+warnings: Array<string>, };
@@ -0,0 +1,28 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ExtraInboundKind } from "./ExtraInboundKind";
+
+export type ExtraInbound = { kind: ExtraInboundKind, 
+/**
+ * 留空时按类型与端口生成；保存时统一补上 `extra-` 前缀
+ */
+tag: string, enabled: boolean, 
+/**
+ * 监听地址；留空时跟随“允许局域网访问”开关
+ */
+listen: string | null, listen_port: number, 
+/**
+ * socks / http 认证用户名（与 password 同时设置才生效）
+ */
+username: string | null, 
+/**
+ * socks / http 认证密码，或 shadowsocks 密码
+ */
+password: string | null, 
+/**
+ * shadowsocks 加密方式
+ */
+method: string | null, 
+/**
+ * VMess 用户 UUID
+ */
+uuid: string | null, };
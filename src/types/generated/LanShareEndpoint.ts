@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type LanShareEndpoint = { interface: string, address: string, 
+/**
+ * 疑似虚拟网卡（容器 / 虚拟机 / VPN），手机通常无法直接访问
+ */
+is_virtual: boolean, http_uri: string, socks_uri: string, 
+/**
+ * Clash / Mihomo `proxies` 列表中的一项
+ */
+clash_proxy: string, 
+/**
+ * sing-box socks 出站
+ */
+singbox_outbound: string, };
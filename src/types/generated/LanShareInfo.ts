@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { LanShareEndpoint } from "./LanShareEndpoint";
+
+export type LanShareInfo = { port: number, auth_enabled: boolean, 
+/**
+ * 内核未运行时片段仍可生成，但暂时无法连通
+ */
+kernel_running: boolean, endpoints: Array<LanShareEndpoint>, };
@@ -1,3 +1,5 @@
+import type { SubscriptionFetchOptions } from './SubscriptionFetchOptions'
+
 export interface Subscription {
   name: string
   url: string
@@ -18,4 +20,5 @@ export interface Subscription {
   last_auto_update_error?: string | null
   last_auto_update_error_type?: string | null
   last_auto_update_backoff_until?: number | null
+  fetch_options?: SubscriptionFetchOptions
 }
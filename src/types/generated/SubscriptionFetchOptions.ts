@@ -0,0 +1,25 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { SubscriptionFetchDetour } from "./SubscriptionFetchDetour";
+import type { SubscriptionHeader } from "./SubscriptionHeader";
+
+export type SubscriptionFetchOptions = { detour: SubscriptionFetchDetour, 
+/**
+ * `detour` 为 `node` 时使用的节点 tag
+ */
+detour_node: string | null, 
+/**
+ * 自定义 User-Agent；部分机场按 UA 返回不同格式
+ */
+user_agent: string | null, 
+/**
+ * 额外请求头，例如鉴权 token
+ */
+headers: Array<SubscriptionHeader>, 
+/**
+ * 请求超时（秒），为空时使用 30 秒
+ */
+timeout_secs: number | null, 
+/**
+ * 跳过 TLS 证书校验，仅用于自签证书的自建转换服务
+ */
+accept_invalid_certs: boolean, };
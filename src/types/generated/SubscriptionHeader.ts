@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type SubscriptionHeader = { name: string, value: string, 
+/**
+ * 读取时无法解密（例如数据目录变化）：`value` 保留密文，拉取订阅时不发送该请求头
+ */
+unreadable: boolean, };
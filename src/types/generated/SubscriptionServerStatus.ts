@@ -0,0 +1,11 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type SubscriptionServerStatus = { enabled: boolean, running: boolean, port: number, 
+/**
+ * 订阅地址使用的局域网地址（查询时按当前网络解析）；未运行或无可用地址时为空
+ */
+address: string | null, token: string, 
+/**
+ * 各格式的完整订阅地址（含 token），未运行或无可用地址时为空
+ */
+base64_url: string | null, clash_url: string | null, singbox_url: string | null, };
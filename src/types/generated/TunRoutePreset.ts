@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * TUN 路由预设：一键追加常见的不走 TUN 的目标
+ */
+export type TunRoutePreset = "bypass_cn" | "bypass_lan_tailscale" | "bypass_container_bridges";
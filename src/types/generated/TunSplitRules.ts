@@ -0,0 +1,18 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * TUN 分流名单：按进程 / 用户 / 网卡决定哪些流量进入 TUN。
+ *
+ * - 进程条目含路径分隔符时按 `process_path` 匹配，否则按 `process_name` 匹配，
+ * 通过只作用于 tun-in 的 route 规则实现；
+ * - UID 与网卡写入 tun inbound 的 `include_*` / `exclude_*` 字段，仅 Linux 生效。
+ */
+export type TunSplitRules = { 
+/**
+ * 非空时只有这些进程走 TUN，其余进程直连
+ */
+include_process: Array<string>, 
+/**
+ * 这些进程始终直连，例如公司 VPN 客户端、备份程序
+ */
+exclude_process: Array<string>, include_uid: Array<number>, exclude_uid: Array<number>, include_interface: Array<string>, exclude_interface: Array<string>, };
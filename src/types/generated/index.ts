@@ -7,3 +7,17 @@ export type { Subscription } from './Subscription'
 export type { CustomRule } from './CustomRule'
 export type { CustomRuleAction } from './CustomRuleAction'
 export type { CustomRuleMatchType } from './CustomRuleMatchType'
+export type { TunRoutePreset } from './TunRoutePreset'
+export type { TunSplitRules } from './TunSplitRules'
+export type { ExtraInbound } from './ExtraInbound'
+export type { ExtraInboundKind } from './ExtraInboundKind'
+export type { LanCredentials } from './LanCredentials'
+export type { LanShareEndpoint } from './LanShareEndpoint'
+export type { LanShareInfo } from './LanShareInfo'
+export type { NodeExportFormat } from './NodeExportFormat'
+export type { ClashMetaExport } from './ClashMetaExport'
+export type { SubscriptionServerSettings } from './SubscriptionServerSettings'
+export type { SubscriptionServerStatus } from './SubscriptionServerStatus'
+export type { SubscriptionFetchDetour } from './SubscriptionFetchDetour'
+export type { SubscriptionFetchOptions } from './SubscriptionFetchOptions'
+export type { SubscriptionHeader } from './SubscriptionHeader'
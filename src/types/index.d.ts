@@ -4,7 +4,7 @@ export * from './store'
 export * from './models'
 
 // 全局常量类型
-export type ProxyMode = 'system' | 'tun' | 'manual'
+export type ProxyMode = 'system' | 'pac' | 'tun' | 'manual'
 
 // StatusCard类型
 export type StatusCardType = 'default' | 'primary' | 'success' | 'warning' | 'error'
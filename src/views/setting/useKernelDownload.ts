@@ -3,6 +3,7 @@ import { APP_EVENTS } from '@/constants/events'
 import { eventService } from '@/services/event-service'
 import type { KernelDownloadPayload } from '@/services/kernel-service'
 import { systemService } from '@/services/system-service'
+import { parseDigestUnavailable } from '@/composables/useUnverifiedDownloadConfirm'
 
 interface MessageApiLike {
   success: (content: string) => void
@@ -14,6 +15,8 @@ interface UseKernelDownloadOptions {
   message: MessageApiLike
   t: (key: string) => string
   checkKernelInstallation: () => Promise<unknown> | unknown
+  /** 无法获取官方摘要时询问用户是否无校验继续 */
+  confirmUnverifiedDownload?: (reason: string) => Promise<boolean>
 }
 
 export const useKernelDownload = (options: UseKernelDownloadOptions) => {
@@ -36,10 +39,11 @@ export const useKernelDownload = (options: UseKernelDownloadOptions) => {
     await Promise.resolve(options.checkKernelInstallation())
   }
 
-  const downloadTheKernel = async () => {
+  const runKernelDownload = async (allowUnverified: boolean): Promise<void> => {
     if (downloading.value) return
 
     let downloadCompleted = false
+    let unverifiedReason: string | null = null
     loading.value = true
     downloading.value = true
     downloadProgress.value = 0
@@ -76,20 +80,26 @@ export const useKernelDownload = (options: UseKernelDownloadOptions) => {
     })
 
     try {
-      await systemService.downloadKernel(options.selectedVersion.value)
+      await systemService.downloadKernel(options.selectedVersion.value, allowUnverified)
 
       // 后端若未推送 completed，也做一次兜底检查。
       if (!downloadCompleted) {
         await runKernelInstallationCheck()
       }
     } catch (error) {
-      console.error('下载内核失败:', error)
-      downloadError.value =
-        error instanceof Error ? error.message : options.t('setting.kernel.downloadFailed')
-      options.message.error(downloadError.value)
       downloading.value = false
       loading.value = false
       cleanupDownloadListener()
+
+      const reason = parseDigestUnavailable(error)
+      if (reason !== null && !allowUnverified && options.confirmUnverifiedDownload) {
+        unverifiedReason = reason
+      } else {
+        console.error('下载内核失败:', error)
+        downloadError.value =
+          error instanceof Error ? error.message : options.t('setting.kernel.downloadFailed')
+        options.message.error(downloadError.value)
+      }
     } finally {
       // 理论上应由事件驱动结束态，这里只做兜底避免按钮卡死。
       if (downloading.value) {
@@ -97,8 +107,19 @@ export const useKernelDownload = (options: UseKernelDownloadOptions) => {
         downloading.value = false
       }
     }
+
+    // 拿不到官方摘要时由用户决定是否无校验继续；摘要不一致不会走到这里
+    if (unverifiedReason !== null) {
+      if (await options.confirmUnverifiedDownload?.(unverifiedReason)) {
+        await runKernelDownload(true)
+      } else {
+        downloadMessage.value = ''
+      }
+    }
   }
 
+  const downloadTheKernel = () => runKernelDownload(false)
+
   return {
     loading,
     downloading,